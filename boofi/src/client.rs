@@ -0,0 +1,185 @@
+use boofi_core::client::{Client, ClientError};
+use boofi_core::error::{Erro, Resul};
+use boofi_core::tls::certificate_authorities;
+use clap::{Args, Subcommand, ValueEnum};
+use serde_json::Value;
+
+/// Environment variable read if `--password` is missing
+const PASSWORD_VARIABLE: &str = "BOOFI_PASSWORD";
+
+/// Client name of tokens requested for a single command
+const TOKEN_CLIENT: &str = "boofi-client";
+
+/// Talks to a running boofi server
+#[derive(Args, Debug)]
+pub struct ClientArgs {
+    #[arg(long, help = "Base url of the server", default_value = "http://localhost:3000")]
+    url: String,
+
+    #[arg(long, help = "Service to use", default_value = "localhost")]
+    service: String,
+
+    #[arg(long, help = "User requesting a token, required without --token")]
+    user: Option<String>,
+
+    #[arg(long, help = "Password of --user, read from BOOFI_PASSWORD if missing")]
+    password: Option<String>,
+
+    #[arg(long, help = "Existing bearer token used instead of --user")]
+    token: Option<String>,
+
+    #[arg(long, help = "PEM bundle of certificate authorities trusted for https, the system ones by default")]
+    ca: Option<String>,
+
+    #[arg(long, help = "Format of the response", value_enum, default_value_t = Output::Json)]
+    output: Output,
+
+    #[command(subcommand)]
+    command: ClientCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ClientCommand {
+    /// Request a bearer token for --user
+    Token {
+        #[arg(long, help = "Name of the client owning the token")]
+        client: Option<String>,
+    },
+    /// Read, write and delete files
+    #[command(subcommand)]
+    Files(FilesCommand),
+    /// List and run apps
+    #[command(subcommand)]
+    Apps(AppsCommand),
+    /// Show asynchronous apps
+    #[command(subcommand)]
+    Tasks(TasksCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum FilesCommand {
+    /// Documentation of the file modules
+    List,
+    /// Parsed content of a file or the listing of a directory
+    Get {
+        path: String,
+        #[arg(long, help = "Enforce a file module")]
+        name: Option<String>,
+        #[arg(long, help = "Hide sensitive values")]
+        redact: bool,
+        #[arg(long, help = "Read with sudo")]
+        sudo: bool,
+    },
+    /// Write a file from json as returned by get
+    Write {
+        path: String,
+        #[arg(long, help = "Content as json")]
+        input: String,
+        #[arg(long, help = "Enforce a file module")]
+        name: Option<String>,
+        #[arg(long, help = "Write with sudo")]
+        sudo: bool,
+    },
+    /// Delete a file
+    Delete {
+        path: String,
+        #[arg(long, help = "Delete with sudo")]
+        sudo: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AppsCommand {
+    /// Documentation of the apps
+    List,
+    /// Run an app and print its output
+    Run {
+        name: String,
+        #[arg(long, help = "Input as json", default_value = "null")]
+        input: String,
+        #[arg(long = "async", help = "Run as task and print it")]
+        r#async: bool,
+        #[arg(long, help = "Run with sudo")]
+        sudo: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum TasksCommand {
+    /// All tasks
+    List,
+    /// A single task
+    Get {
+        id: usize,
+    },
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum Output {
+    /// Pretty printed json
+    Json,
+    Yaml,
+    /// Single line json
+    Compact,
+}
+
+impl ClientArgs {
+    /// Runs the command and prints the response
+    pub async fn run(self) -> Resul<()> {
+        let roots = match &self.ca {
+            Some(ca) => Some(certificate_authorities(ca).await?),
+            None => None,
+        };
+        let mut client = Client::new(&self.url, &self.service, roots);
+
+        match (&self.token, &self.user) {
+            (Some(token), _) => client.bearer(token),
+            (None, Some(user)) => {
+                let password = match &self.password {
+                    Some(password) => password.clone(),
+                    None => std::env::var(PASSWORD_VARIABLE).map_err(|_| ClientError::PasswordMissing)?,
+                };
+                client.basic(user, &password);
+
+                // the password is sent once, further requests use the token
+                if !matches!(self.command, ClientCommand::Token { .. }) {
+                    client.token(Some(TOKEN_CLIENT)).await?;
+                }
+            }
+            (None, None) => return Err(ClientError::AuthMissing.into()),
+        }
+
+        let response = match self.command {
+            ClientCommand::Token { client: name } => client.token(name.as_deref()).await?,
+            ClientCommand::Files(FilesCommand::List) => client.files().await?,
+            ClientCommand::Files(FilesCommand::Get { path, name, redact, sudo }) =>
+                client.read_file(&path, name.as_deref(), redact, sudo).await?,
+            ClientCommand::Files(FilesCommand::Write { path, input, name, sudo }) =>
+                client.write_file(&path, name.as_deref(), &Self::json(&input)?, sudo).await?,
+            ClientCommand::Files(FilesCommand::Delete { path, sudo }) => client.delete_file(&path, sudo).await?,
+            ClientCommand::Apps(AppsCommand::List) => client.apps().await?,
+            ClientCommand::Apps(AppsCommand::Run { name, input, r#async, sudo }) =>
+                client.run_app(&name, &Self::json(&input)?, r#async, sudo).await?,
+            ClientCommand::Tasks(TasksCommand::List) => client.tasks(None).await?,
+            ClientCommand::Tasks(TasksCommand::Get { id }) => client.tasks(Some(id)).await?,
+        };
+
+        Self::print(&response, &self.output)
+    }
+
+    fn json(input: &str) -> Resul<Value> {
+        serde_json::from_str(input).map_err(Erro::from_deserialize)
+    }
+
+    /// Plain text responses are printed as they are
+    fn print(response: &Value, output: &Output) -> Resul<()> {
+        match (response, output) {
+            (Value::Null, _) => {}
+            (Value::String(text), _) => println!("{}", text.trim_end()),
+            (_, Output::Json) => println!("{}", serde_json::to_string_pretty(response)?),
+            (_, Output::Yaml) => print!("{}", serde_yaml::to_string(response)?),
+            (_, Output::Compact) => println!("{}", response),
+        }
+        Ok(())
+    }
+}
@@ -1,6 +0,0 @@
-pub(crate) mod hosts;
-pub(crate) mod passwd;
-pub(crate) mod hostname;
-pub(crate) mod crontab;
-pub(crate) mod fstab;
-pub(crate) mod os_release;
\ No newline at end of file
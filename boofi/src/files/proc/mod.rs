@@ -1,11 +0,0 @@
-pub(crate) mod version;
-pub(crate) mod mdstat;
-pub(crate) mod meminfo;
-pub(crate) mod cpuinfo;
-pub(crate) mod loadavg;
-pub(crate) mod crypto;
-pub(crate) mod filesystems;
-pub(crate) mod mounts;
-pub(crate) mod partitions;
-pub(crate) mod swaps;
-pub(crate) mod uptime;
@@ -1,30 +1,51 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
-use std::path::Path;
-use crate::controller::Controller;
-use crate::error::{Erro, Resul};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use boofi_core::controller::{Authentication, Authorization, Controller, ControllerSettings, Modules, MAX_LIST_DEPTH};
+use boofi_core::error::{Erro, Resul};
 use serde::{Serialize, Deserialize, Serializer, Deserializer};
 use tokio::fs::{File, read_to_string, write};
 use std::str::FromStr;
+use std::io::IsTerminal;
 use std::time::Duration;
-use crate::rest::Rest;
-use clap::Parser;
+use boofi_core::rest::{CompressionConfig, HttpConfig, Rest, ServicesConfig, SharedController};
+use boofi_core::task::TaskRetention;
+use boofi_core::system::{RunLimits, TransferLimits};
+use boofi_core::tls::ClientAuth;
+use boofi_core::backup::Backup;
+use boofi_core::hook::FileHook;
+use boofi_core::audit::ShellRecording;
+use boofi_core::apps::sh::ShellPolicy;
+use boofi_core::webhook::Webhook;
+use boofi_core::inventory::{Inventory, InventoryService};
+use boofi_core::cache::FileCacheConfig;
+use boofi_core::workspace::{Workspace, WorkspaceConfig, CLEANUP_INTERVAL};
+use boofi_core::grpc::{Grpc, GrpcConfig};
+use boofi_core::generate::{generate_client, Language};
+use boofi_core::system::os::Os;
+use boofi_core::secret::{self, Secret, SecretError, SecretKey};
+use boofi_core::users::hash_password;
+use boofi_core::runbook::Runbooks;
+use boofi_core::token::{MemoryTokenStore, TokenStore, TokenStoreConfig};
+use boofi_core::jwt::{Jwt, JwtConfig};
+use clap::{Parser, Subcommand, ValueEnum};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{Mutex, Notify};
+use tokio::time::{interval_at, Instant};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::LevelFilter;
+use boofi_core::acme::{Acme, LETS_ENCRYPT, RENEW_INTERVAL};
+use client::ClientArgs;
+
+mod client;
 
 
-mod error;
-mod rest;
-mod files;
-mod apps;
-mod task;
-mod utils;
-mod system;
-mod controller;
-mod description;
-
 /// Represents the SSL configuration
 /// None:   ssl disabled
 /// File:   certificates stored in files
 /// Text:   certificates stored in configuration yaml
+/// Acme:   certificates obtained and renewed from an ACME certificate authority, Let's Encrypt by default
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 enum SslConfig {
@@ -34,9 +55,22 @@ enum SslConfig {
         certificate_path: String,
     },
     Text {
-        private_key: String,
+        private_key: Secret,
         certificate: String,
     },
+    Acme {
+        domains: Vec<String>,
+        contact_email: String,
+        cache_dir: String,
+        #[serde(default = "SslConfig::default_acme_directory")]
+        directory: String,
+    },
+}
+
+impl SslConfig {
+    fn default_acme_directory() -> String {
+        LETS_ENCRYPT.into()
+    }
 }
 
 impl Default for SslConfig {
@@ -69,10 +103,27 @@ impl From<&ServiceTypeConfig> for Option<String> {
 /// General service configuration
 /// name:   name is unique and describes the service path e.g. http://localhost/<name>/files
 /// type:   service endpoint
+/// authentication: verifies api users, system credentials by default
+/// labels: free key value pairs shown in the inventory
+/// compression: compress responses and decompress requests, disabled by default
+/// shell:  restricts or disables the `sh` app
+/// modules: enabled or disabled file modules and apps by name, all are loaded by default
 #[derive(Debug, Serialize, Deserialize)]
 struct ServiceConfig {
     name: String,
     r#type: ServiceTypeConfig,
+    #[serde(default)]
+    authentication: Authentication,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compression: Option<CompressionConfig>,
+    #[serde(default)]
+    shell: ShellPolicy,
+    #[serde(default)]
+    modules: Modules,
+    #[serde(default)]
+    transfer: TransferLimits,
 }
 
 impl Default for ServiceConfig {
@@ -80,25 +131,96 @@ impl Default for ServiceConfig {
         Self {
             name: "localhost".to_string(),
             r#type: ServiceTypeConfig::Local,
+            authentication: Authentication::System,
+            labels: BTreeMap::new(),
+            compression: None,
+            shell: ShellPolicy::default(),
+            modules: Modules::default(),
+            transfer: TransferLimits::default(),
         }
     }
 }
 
 type Services = Vec<ServiceConfig>;
 
+/// Running controllers by service name with their endpoint address
+type Controllers = HashMap<String, (Option<String>, SharedController)>;
+
+/// Services which failed to start by name with their error, they are started again on demand
+type Degraded = HashMap<String, String>;
+
+/// Degraded services are started again with a request at most once in this interval
+const DEGRADED_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
 /// Represents the configuration file
 #[derive(Debug, Serialize, Deserialize)]
 struct Config {
     #[serde(skip)]
     path: String,
+    #[serde(skip)]
+    runbooks: Runbooks,
+    #[serde(skip)]
+    jwt_keys: Option<Jwt>,
+    #[serde(skip)]
+    master_key: Option<SecretKey>,
     listen: String,
     #[serde(serialize_with = "Config::serialize_duration", deserialize_with = "Config::deserialize_duration")]
     max_token_expiration: Duration,
+    #[serde(default)]
+    token_store: Option<TokenStoreConfig>,
+    #[serde(default)]
+    jwt: Option<JwtConfig>,
+    #[serde(default)]
+    secret_key: Option<String>,
+    #[serde(default = "Config::default_max_upload_size")]
+    max_upload_size: usize,
+    #[serde(default = "Config::default_max_list_depth")]
+    max_list_depth: usize,
+    #[serde(default)]
+    http: HttpConfig,
+    #[serde(default)]
+    task_retention: TaskRetention,
+    #[serde(default)]
+    task_webhook: Option<Webhook>,
+    #[serde(default)]
+    inventory: bool,
+    #[serde(default)]
+    authorization: Authorization,
+    #[serde(default)]
+    run_limits: RunLimits,
+    #[serde(default)]
+    backup: Option<Backup>,
+    #[serde(default)]
+    file_cache: FileCacheConfig,
+    #[serde(default)]
+    file_hooks: Vec<FileHook>,
+    #[serde(default)]
+    shell_recording: Option<ShellRecording>,
+    #[serde(default)]
+    workspace: WorkspaceConfig,
+    #[serde(default)]
+    plugins: Option<String>,
+    #[serde(default)]
+    file_plugins: Option<String>,
+    #[serde(default)]
+    grpc: Option<GrpcConfig>,
+    #[serde(default)]
+    log_commands: bool,
     ssl: SslConfig,
+    #[serde(default)]
+    client_auth: Option<ClientAuth>,
     services: Services,
 }
 
 impl Config {
+    fn default_max_upload_size() -> usize {
+        100 * 1024 * 1024
+    }
+
+    fn default_max_list_depth() -> usize {
+        MAX_LIST_DEPTH
+    }
+
     fn serialize_duration<S: Serializer>(v: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_u64(v.as_secs())
     }
@@ -111,38 +233,330 @@ impl Config {
     }
 
     async fn save(&self) -> Resul<()> {
-        log::debug!("[SAVE] saving file to {}", self.path);
+        tracing::debug!("[SAVE] saving file to {}", self.path);
         let file = File::create(&self.path).await?;
         serde_yaml::to_writer(file.into_std().await, &self).map_err(Into::into)
     }
 
-    async fn load_or_new(path: &str) -> Resul<Self> {
+    /// Runbooks are saved next to the configuration file, e.g. `config.runbooks.yml` for `config.yml`
+    fn runbooks_path(path: &str) -> PathBuf {
+        Path::new(path).with_extension("runbooks.yml")
+    }
+
+    /// Token store of a service, tokens are kept in memory if none is configured or it can't be opened
+    async fn token_store(&self, service: &str) -> Box<dyn TokenStore> {
+        match &self.token_store {
+            Some(config) => match config.store(service).await {
+                Ok(store) => Box::new(store),
+                Err(e) => {
+                    tracing::error!("tokens of service {} are kept in memory: {}", service, e);
+                    Box::new(MemoryTokenStore)
+                }
+            },
+            None => Box::new(MemoryTokenStore),
+        }
+    }
+
+    /// Settings of the controller of a service
+    async fn controller_settings(&self, service_config: &ServiceConfig) -> ControllerSettings {
+        ControllerSettings {
+            max_token_expiration: self.max_token_expiration,
+            task_retention: self.task_retention.clone(),
+            task_webhook: self.task_webhook.clone(),
+            authorization: self.authorization.clone(),
+            authentication: service_config.authentication.clone(),
+            run_limits: self.run_limits.clone(),
+            backup: self.backup.clone(),
+            file_cache: self.file_cache.clone(),
+            file_hooks: self.file_hooks.clone(),
+            shell_recording: self.shell_recording.clone(),
+            shell: service_config.shell.clone(),
+            modules: service_config.modules.clone(),
+            workspace: Workspace::new(&service_config.name, &self.workspace),
+            transfer: service_config.transfer.clone(),
+            runbooks: self.runbooks.clone(),
+            max_list_depth: self.max_list_depth,
+            token_store: self.token_store(&service_config.name).await,
+            jwt: self.jwt_keys.clone(),
+            plugins: self.plugins.clone(),
+            file_plugins: self.file_plugins.clone(),
+        }
+    }
+
+    /// Loads the configuration, the secret key is asked for on a terminal if `!vault` secrets need it
+    async fn load(path: &str) -> Resul<Self> {
+        let key = SecretKey::from_env()?;
+
+        match Self::load_or_new(path, key.clone()).await {
+            Err(Erro::Secret(SecretError::KeyMissing)) if key.is_none() && std::io::stdin().is_terminal() => {
+                let encoded = rpassword::prompt_password("secret key: ")?;
+                Self::load_or_new(path, Some(SecretKey::decode(&encoded, "prompt")?)).await
+            }
+            result => result,
+        }
+    }
+
+    /// Resolves the tagged secrets, the `secret_key` file takes precedence over the given key
+    async fn resolve_secrets(&mut self, key: Option<SecretKey>) -> Resul<()> {
+        self.master_key = match &self.secret_key {
+            Some(path) => Some(SecretKey::load(path).await?),
+            None => key,
+        };
+
+        let key = self.master_key.as_ref();
+        for service in self.services.iter_mut() {
+            service.authentication.resolve(key).await?;
+        }
+        if let Some(jwt) = self.jwt.as_mut() {
+            jwt.resolve(key).await?;
+        }
+        if let Some(client_auth) = self.client_auth.as_mut() {
+            client_auth.resolve(key).await?;
+        }
+        if let SslConfig::Text { private_key, .. } = &mut self.ssl {
+            private_key.resolve(key).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads or creates the configuration, `key` decrypts `!vault` secrets without a `secret_key` file
+    async fn load_or_new(path: &str, key: Option<SecretKey>) -> Resul<Self> {
         if tokio::fs::try_exists(path).await? {
-            log::debug!("[LOAD] loading file from {}", path);
-            tokio::fs::read(path).await.map(|bytes| {
-                serde_yaml::from_slice::<Config>(&bytes).map(|mut config| {
-                    log::info!("[LOAD] configuration file loaded from {}", path);
-                    config.path = path.into();
-                    config
-                })
-            })?.map_err(Into::into)
+            tracing::debug!("[LOAD] loading file from {}", path);
+            let mut config = serde_yaml::from_slice::<Config>(&tokio::fs::read(path).await?)?;
+            config.resolve_secrets(key).await?;
+            config.run_limits.validate()?;
+            tracing::info!("[LOAD] configuration file loaded from {}", path);
+            config.path = path.into();
+            config.runbooks = Runbooks::load(Self::runbooks_path(path)).await?;
+            config.jwt_keys = match &config.jwt {
+                Some(jwt) => Some(jwt.load().await?),
+                None => None,
+            };
+            Ok(config)
         } else {
-            log::debug!("[NEW] generate default config for {}", path);
+            tracing::debug!("[NEW] generate default config for {}", path);
             let this = Self {
                 services: vec![Default::default()],
                 path: path.into(),
+                runbooks: Runbooks::load(Self::runbooks_path(path)).await?,
+                jwt_keys: None,
+                master_key: key,
                 listen: "127.0.0.1:3000".into(),
                 max_token_expiration: Duration::from_secs(60 * 60 * 24),
+                token_store: None,
+                jwt: None,
+                secret_key: None,
+                max_upload_size: Self::default_max_upload_size(),
+                max_list_depth: Self::default_max_list_depth(),
+                http: Default::default(),
+                task_retention: Default::default(),
+                task_webhook: None,
+                inventory: false,
+                authorization: Default::default(),
+                run_limits: Default::default(),
+                backup: None,
+                file_cache: Default::default(),
+                file_hooks: vec![],
+                shell_recording: None,
+                workspace: Default::default(),
+                plugins: None,
+                file_plugins: None,
+                grpc: None,
+                log_commands: false,
                 ssl: Default::default(),
+                client_auth: None,
             };
 
             this.save().await?;
-            log::info!("[NEW] configuration file saved to {}", path);
+            tracing::info!("[NEW] configuration file saved to {}", path);
 
             Ok(this)
         }
     }
 
+    /// Controllers of all configured services.
+    /// Unchanged services keep their controller with tokens and tasks but get the new settings.
+    /// A service failing to start doesn't affect the others, it is degraded until it starts.
+    async fn controllers(&self, current: &Controllers) -> (Controllers, Degraded) {
+        let mut controllers = HashMap::new();
+        let mut degraded = HashMap::new();
+
+        for service_config in self.services.iter() {
+            let name = service_config.name.clone();
+            let address: Option<String> = (&service_config.r#type).into();
+
+            // loaded modules can't be changed, the service gets a new controller instead
+            let reusable = match current.get(&name) {
+                Some((current_address, controller)) if current_address == &address => {
+                    controller.lock().await.modules() == &service_config.modules
+                }
+                _ => false,
+            };
+
+            let settings = self.controller_settings(service_config).await;
+            let controller = match current.get(&name) {
+                Some((_, controller)) if reusable => {
+                    controller.lock().await.reconfigure(settings);
+                    tracing::debug!("service {} reconfigured", name);
+                    controller.clone()
+                }
+                _ => {
+                    tracing::debug!("preparing service {}", name);
+                    let controller = match Controller::new(settings, address.as_deref()).await {
+                        Ok(controller) => controller,
+                        Err(e) => {
+                            tracing::error!("service {} failed to start: {}", name, e);
+                            degraded.insert(name, e.to_string());
+                            continue;
+                        }
+                    };
+                    tracing::debug!("service {} configured", name);
+                    Arc::new(Mutex::new(controller))
+                }
+            };
+
+            controllers.insert(name, (address, controller));
+        }
+
+        for name in current.keys().filter(|name| !controllers.contains_key(*name) && !degraded.contains_key(*name)) {
+            tracing::info!("service {} removed", name);
+        }
+
+        (controllers, degraded)
+    }
+
+    /// Prints the first line of stdin as `!vault` secret, encrypted with `BOOFI_SECRET_KEY` or the `secret_key` of the configuration.
+    /// The `secret_key` file is generated if missing.
+    async fn encrypt_secret(path: &str) -> Resul<()> {
+        let key = match SecretKey::from_env()? {
+            Some(key) => key,
+            None => {
+                let config = serde_yaml::from_slice::<Config>(&tokio::fs::read(path).await?)?;
+                SecretKey::load_or_generate(config.secret_key.as_deref().ok_or(SecretError::KeyMissing)?).await?
+            }
+        };
+
+        print!("{}", serde_yaml::to_string(&key.encrypt(&Self::read_line()?)?)?);
+        Ok(())
+    }
+
+    /// Line of stdin without the line break, secrets are not passed as arguments to keep them out of the process list
+    fn read_line() -> Resul<String> {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    /// Writes a typed client of the built-in apps and file modules and the plugins of the configuration file, if it exists
+    async fn generate_client(path: &str, language: Language, output: &str) -> Resul<()> {
+        let (plugins, file_plugins) = if tokio::fs::try_exists(path).await? {
+            let config = serde_yaml::from_slice::<Config>(&tokio::fs::read(path).await?)?;
+            (config.plugins, config.file_plugins)
+        } else {
+            (None, None)
+        };
+
+        let controller = Controller::new(ControllerSettings {
+            plugins,
+            file_plugins,
+            ..Default::default()
+        }, None).await?;
+
+        let apps = controller.apps()
+            .map(|app| serde_json::to_value(app.help(&Os::LinuxAny).into_schema()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let files = controller.file_builders().iter()
+            .map(|file| serde_json::to_value(file.help().into_schema()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        write(output, generate_client(language, &apps, &files)?).await?;
+        tracing::info!("[GENERATE] client written to {}", output);
+        Ok(())
+    }
+
+    /// Re-read the configuration file and apply it to the running rest api.
+    /// Listen address, upload size and switching ssl on or off require a restart.
+    async fn reload(&mut self, rest: &Rest, grpc: Option<&Grpc>, controllers: &mut Controllers, degraded: &mut Degraded, retry: &Arc<Notify>) -> Resul<()> {
+        let config = Self::load_or_new(&self.path, self.master_key.clone()).await?;
+
+        if config.listen != self.listen || config.max_upload_size != self.max_upload_size || config.http != self.http {
+            tracing::warn!("[RELOAD] listen address, upload size and http settings are applied after a restart");
+        }
+
+        if config.grpc != self.grpc {
+            tracing::warn!("[RELOAD] grpc changes are applied after a restart");
+        }
+
+        if config.plugins != self.plugins || config.file_plugins != self.file_plugins {
+            tracing::warn!("[RELOAD] plugins are loaded after a restart");
+        }
+
+        (*controllers, *degraded) = config.controllers(controllers).await;
+        secret::set_log_commands(config.log_commands);
+        config.set_services(rest, grpc, controllers, degraded, retry).await;
+
+        match config.ssl().await? {
+            Some((private_key, certificate)) => match rest.set_ssl(&private_key, &certificate, config.client_auth.clone()).await {
+                Err(Erro::RestSslInactive) => tracing::warn!("[RELOAD] ssl is enabled after a restart"),
+                result => result?,
+            },
+            None if !matches!(self.ssl, SslConfig::None) => tracing::warn!("[RELOAD] ssl is disabled after a restart"),
+            None => {}
+        }
+
+        *self = config;
+        tracing::info!("[RELOAD] configuration reloaded from {}", self.path);
+
+        Ok(())
+    }
+
+    /// Starts the degraded services again, started ones replace their degraded routes
+    async fn start_degraded(&self, rest: &Rest, grpc: Option<&Grpc>, controllers: &mut Controllers, degraded: &mut Degraded, retry: &Arc<Notify>) {
+        if degraded.is_empty() {
+            return;
+        }
+
+        tracing::info!("[DEGRADED] starting {} services again", degraded.len());
+        (*controllers, *degraded) = self.controllers(controllers).await;
+        self.set_services(rest, grpc, controllers, degraded, retry).await;
+    }
+
+    async fn set_services(&self, rest: &Rest, grpc: Option<&Grpc>, controllers: &Controllers, degraded: &Degraded, retry: &Arc<Notify>) {
+        rest.set_services(self.services(rest, controllers, degraded, retry), self.inventory(controllers, degraded)).await;
+        if let Some(grpc) = grpc {
+            grpc.set_services(Self::grpc_services(controllers)).await;
+        }
+    }
+
+    /// Inventory of the configured services if enabled
+    fn inventory(&self, controllers: &Controllers, degraded: &Degraded) -> Option<Inventory> {
+        self.inventory.then(|| Inventory::new(self.services.iter()
+            .filter_map(|service| match (controllers.get(&service.name), degraded.get(&service.name)) {
+                (Some((address, controller)), _) => Some(InventoryService::new(service.name.clone(), address.clone(), service.labels.clone(), controller.clone())),
+                (None, Some(error)) => Some(InventoryService::new_degraded(service.name.clone(), (&service.r#type).into(), service.labels.clone(), error.clone())),
+                (None, None) => None,
+            })
+            .collect()))
+    }
+
+    fn services(&self, rest: &Rest, controllers: &Controllers, degraded: &Degraded, retry: &Arc<Notify>) -> ServicesConfig {
+        self.services.iter()
+            .filter_map(|service| match (controllers.get(&service.name), degraded.get(&service.name)) {
+                (Some((_, controller)), _) => Some(rest.new_service(controller.clone(), service.compression.as_ref())),
+                (None, Some(error)) => Some(rest.degraded_service(&service.name, error, retry.clone())),
+                (None, None) => None,
+            }.map(|router| (service.name.clone(), router)))
+            .collect()
+    }
+
+    fn grpc_services(controllers: &Controllers) -> HashMap<String, SharedController> {
+        controllers.iter()
+            .map(|(name, (_, controller))| (name.clone(), controller.clone()))
+            .collect()
+    }
+
     async fn ssl(&self) -> Resul<Option<(String, String)>> {
         Ok(match &self.ssl {
             SslConfig::None => None,
@@ -151,9 +565,27 @@ impl Config {
                       read_to_string(certificate_path).await?
                 ))
             }
-            SslConfig::Text { private_key, certificate } => Some((private_key.into(), certificate.into()))
+            SslConfig::Text { private_key, certificate } => Some((private_key.expose()?.into(), certificate.into())),
+            SslConfig::Acme { domains, contact_email, cache_dir, directory } => {
+                Some(Acme::new(directory, domains, contact_email, cache_dir).certificate().await?)
+            }
         })
     }
+
+    /// Renew the ACME certificate if it expires soon
+    async fn renew(&self, rest: &Rest) -> Resul<()> {
+        if let SslConfig::Acme { domains, contact_email, cache_dir, directory } = &self.ssl {
+            let acme = Acme::new(directory, domains, contact_email, cache_dir);
+
+            if acme.renewal_due().await? {
+                let (private_key, certificate) = acme.certificate().await?;
+                rest.set_ssl(&private_key, &certificate, self.client_auth.clone()).await?;
+                tracing::info!("[RENEW] certificate renewed");
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Command line options
@@ -168,38 +600,162 @@ struct Args {
 
     #[arg(long, help = "Directory location of self signed generated certificate and private key. Only usable with --self_signed_alt_names.")]
     ssl_stored_file_path: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Talk to a running boofi server instead of serving
+    Client(Box<ClientArgs>),
+    /// Generate a typed client library with one method per app and file module
+    GenerateClient {
+        #[arg(long, help = "Language of the client", value_enum)]
+        lang: ClientLanguage,
+
+        #[arg(long, help = "File to write the client to")]
+        output: String,
+    },
+    /// Hash the password read from stdin for an api user of the `users` authentication
+    HashPassword,
+    /// Encrypt the secret read from stdin as `!vault` secret with `BOOFI_SECRET_KEY` or the `secret_key` of the config file
+    EncryptSecret,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ClientLanguage {
+    Rust,
+    Ts,
+}
+
+impl From<ClientLanguage> for Language {
+    fn from(language: ClientLanguage) -> Self {
+        match language {
+            ClientLanguage::Rust => Language::Rust,
+            ClientLanguage::Ts => Language::Ts,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Resul<()> {
-    env_logger::init();
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::builder().with_default_directive(LevelFilter::INFO.into()).from_env_lossy())
+        .init();
 
     let args = Args::parse();
 
-    let mut config = Config::load_or_new(&args.config).await?;
+    match args.command {
+        Some(Command::Client(client)) => {
+            if let Err(e) = client.run().await {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::GenerateClient { lang, output }) => {
+            return Config::generate_client(&args.config, lang.into(), &output).await;
+        }
+        Some(Command::HashPassword) => {
+            println!("{}", hash_password(&Config::read_line()?)?);
+            return Ok(());
+        }
+        Some(Command::EncryptSecret) => {
+            return Config::encrypt_secret(&args.config).await;
+        }
+        None => {}
+    }
+
+    let mut config = Config::load(&args.config).await?;
 
     if args.self_signed_alt_names.is_empty() {
-        log::debug!("starting rest api on {}", config.listen);
-        let rest = Rest::new(SocketAddr::from_str(config.listen.as_str())?);
-        let mut services = HashMap::new();
-
-        for service_config in config.services.iter() {
-            let name = service_config.name.clone();
-            log::debug!("preparing service {}", name);
-            let address: Option<String> = (&service_config.r#type).into();
-            let service = rest.new_service(Controller::new(config.max_token_expiration,
-                                                           address.as_deref()).await?).await;
-            services.insert(service_config.name.clone(), service);
-            log::debug!("service {} configured", name);
-        }
+        secret::set_log_commands(config.log_commands);
+        tracing::debug!("starting rest api on {}", config.listen);
+        let rest = Rest::new(SocketAddr::from_str(config.listen.as_str())?, config.max_upload_size, config.http.clone());
+
+        let retry = Arc::new(Notify::new());
+        let (mut controllers, mut degraded) = config.controllers(&HashMap::new()).await;
+        rest.set_services(config.services(&rest, &controllers, &degraded, &retry), config.inventory(&controllers, &degraded)).await;
+
+        let grpc = match &config.grpc {
+            Some(grpc_config) => {
+                let grpc = Grpc::new(SocketAddr::from_str(grpc_config.listen.as_str())?);
+                grpc.set_services(Config::grpc_services(&controllers)).await;
+                Some(grpc)
+            }
+            None => None,
+        };
+        let ssl = config.ssl().await?;
+        let grpc_server = async {
+            match (&grpc, &ssl) {
+                (Some(grpc), Some((private_key, certificate))) => grpc.ssl(private_key, certificate).await,
+                (Some(grpc), None) => grpc.start().await,
+                (None, _) => std::future::pending().await,
+            }
+        };
+
+        let client_auth = config.client_auth.clone();
+        let server = async {
+            match &ssl {
+                Some((private_key, certificate)) => rest.ssl(private_key, certificate, client_auth).await,
+                None => {
+                    if client_auth.is_some() {
+                        tracing::warn!("client_auth is only used with ssl");
+                    }
+                    rest.start().await
+                }
+            }
+        };
+
+        // reload configuration on SIGHUP, renew acme certificates, remove stale workspace items and expired tokens and start degraded services on demand
+        let mut hangup = signal(SignalKind::hangup())?;
+        let mut last_retry: Option<Instant> = None;
+        let mut renewal = interval_at(Instant::now() + RENEW_INTERVAL, RENEW_INTERVAL);
+        let mut cleanup = interval_at(Instant::now() + CLEANUP_INTERVAL, CLEANUP_INTERVAL);
+        let reload = async {
+            loop {
+                tokio::select! {
+                    _ = hangup.recv() => {
+                        tracing::info!("[RELOAD] reloading configuration from {}", config.path);
+
+                        if let Err(e) = config.reload(&rest, grpc.as_ref(), &mut controllers, &mut degraded, &retry).await {
+                            tracing::error!("[RELOAD] keeping current configuration: {}", e);
+                        }
+                    }
+                    _ = renewal.tick() => {
+                        if let Err(e) = config.renew(&rest).await {
+                            tracing::error!("[RENEW] {}", e);
+                        }
+                    }
+                    _ = retry.notified() => {
+                        if last_retry.map(|last| last.elapsed() >= DEGRADED_RETRY_INTERVAL).unwrap_or(true) {
+                            last_retry = Some(Instant::now());
+                            config.start_degraded(&rest, grpc.as_ref(), &mut controllers, &mut degraded, &retry).await;
+                        }
+                    }
+                    _ = cleanup.tick() => {
+                        for (_, controller) in controllers.values() {
+                            let (workspace, system_manager) = {
+                                let mut controller = controller.lock().await;
+                                controller.auth_mut().purge();
+                                (controller.workspace().clone(), controller.system_manager())
+                            };
+                            workspace.cleanup_all(&system_manager).await;
+                        }
+                    }
+                }
+            }
+        };
 
-        match config.ssl().await? {
-            Some((private_key, certificate)) => rest.ssl(services, &private_key, &certificate).await?,
-            None => rest.start(services).await.map_err(Into::<Erro>::into)?,
+        tokio::select! {
+            result = server => result?,
+            result = grpc_server => result?,
+            _ = reload => {}
         }
     } else {
         let certs = rcgen::generate_simple_self_signed(args.self_signed_alt_names)?;
-        log::info!("self signed certificate generated");
+        tracing::info!("self signed certificate generated");
 
         let private_key = certs.serialize_private_key_pem();
         let certificate = certs.serialize_pem()?;
@@ -214,7 +770,7 @@ async fn main() -> Resul<()> {
             write(priv_key_path, private_key).await?;
             write(cert_path, certificate).await?;
 
-            log::info!("key and certificate written to {}", path);
+            tracing::info!("key and certificate written to {}", path);
 
             config.ssl = SslConfig::File {
                 private_key_path,
@@ -222,12 +778,12 @@ async fn main() -> Resul<()> {
             }
         } else {
             config.ssl = SslConfig::Text {
-                private_key,
+                private_key: private_key.into(),
                 certificate,
             }
         }
         config.save().await?;
-        log::info!("configuration updated");
+        tracing::info!("configuration updated");
     }
     Ok(())
 }
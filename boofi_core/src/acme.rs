@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use axum::extract::{Path, State};
+use axum::http::{header, Method, Request, Response, StatusCode, Uri};
+use axum::routing::get;
+use axum::Router;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hyper::Body;
+use hyper::client::conn;
+use rcgen::{Certificate, CertificateParams};
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use rustls::{ClientConfig, ServerName};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::fs::{create_dir_all, read, read_to_string, try_exists};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio_rustls::TlsConnector;
+use crate::error::Resul;
+use crate::secret::write_private;
+use crate::tls::{certificate_expiration, certificate_names, root_certificates};
+
+/// Let's Encrypt production directory
+pub const LETS_ENCRYPT: &str = "https://acme-v02.api.letsencrypt.org/directory";
+
+/// How often the certificate is checked for renewal
+pub const RENEW_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Certificates expiring within this duration are renewed
+const RENEW_BEFORE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// http-01 challenges are answered on this address while an order is validated
+const CHALLENGE_LISTEN: &str = "0.0.0.0:80";
+
+const ACCOUNT_KEY: &str = "account.der";
+const PRIVATE_KEY: &str = "cert.key";
+const CERTIFICATE: &str = "cert.pem";
+
+/// Pending http-01 challenges with token and key authorization
+type Challenges = Arc<Mutex<HashMap<String, String>>>;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Order {
+    authorizations: Vec<String>,
+    finalize: String,
+    certificate: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Authorization {
+    status: String,
+    identifier: Identifier,
+    challenges: Vec<Challenge>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Identifier {
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Challenge {
+    r#type: String,
+    url: String,
+    token: String,
+}
+
+/// Minimal https client for the ACME api, one connection per request
+struct Https {
+    connector: TlsConnector,
+}
+
+impl Https {
+    async fn new() -> Resul<Self> {
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(root_certificates().await?)
+            .with_no_client_auth();
+
+        Ok(Self {
+            connector: TlsConnector::from(Arc::new(config)),
+        })
+    }
+
+    async fn request(&self, method: Method, url: &str, body: Option<String>) -> Resul<Response<Vec<u8>>> {
+        let uri: Uri = url.parse().map_err(|_| AcmeError::InvalidUrl(url.into()))?;
+        let host = uri.host().ok_or(AcmeError::InvalidUrl(url.into()))?;
+        let server_name = ServerName::try_from(host).map_err(|_| AcmeError::InvalidUrl(url.into()))?;
+
+        let stream = TcpStream::connect((host, uri.port_u16().unwrap_or(443))).await?;
+        let stream = self.connector.connect(server_name, stream).await?;
+
+        let (mut sender, connection) = conn::handshake(stream).await?;
+        tokio::spawn(connection);
+
+        let request = Request::builder()
+            .method(method)
+            .uri(uri.path_and_query().map(|p| p.as_str()).unwrap_or("/"))
+            .header(header::HOST, host)
+            .header(header::USER_AGENT, "boofi")
+            .header(header::CONTENT_TYPE, "application/jose+json")
+            .body(body.map(Body::from).unwrap_or_else(Body::empty))?;
+
+        tracing::trace!("[ACME] {} {}", request.method(), url);
+
+        let (parts, body) = sender.send_request(request).await?.into_parts();
+        Ok(Response::from_parts(parts, hyper::body::to_bytes(body).await?.to_vec()))
+    }
+}
+
+/// ACME account session signing each request with the account key
+struct AcmeClient {
+    https: Https,
+    directory: Directory,
+    key: EcdsaKeyPair,
+    rng: SystemRandom,
+    nonce: Option<String>,
+    account: Option<String>,
+}
+
+impl AcmeClient {
+    const BAD_NONCE: &'static str = "urn:ietf:params:acme:error:badNonce";
+
+    async fn new(directory: &str, account_key: &[u8]) -> Resul<Self> {
+        let https = Https::new().await?;
+        let response = https.request(Method::GET, directory, None).await?;
+        let rng = SystemRandom::new();
+
+        Ok(Self {
+            directory: serde_json::from_slice(response.body())?,
+            key: EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, account_key, &rng).map_err(|_| AcmeError::AccountKeyInvalid)?,
+            https,
+            rng,
+            nonce: None,
+            account: None,
+        })
+    }
+
+    fn header(response: &Response<Vec<u8>>, name: header::HeaderName) -> Option<String> {
+        response.headers().get(name).and_then(|v| v.to_str().ok()).map(ToString::to_string)
+    }
+
+    /// public account key, members are sorted as required by the thumbprint
+    fn jwk(&self) -> Value {
+        let public_key = self.key.public_key().as_ref();
+
+        json!({
+            "crv": "P-256",
+            "kty": "EC",
+            "x": URL_SAFE_NO_PAD.encode(&public_key[1..33]),
+            "y": URL_SAFE_NO_PAD.encode(&public_key[33..65]),
+        })
+    }
+
+    fn key_authorization(&self, token: &str) -> String {
+        let thumbprint = digest(&SHA256, self.jwk().to_string().as_bytes());
+        format!("{}.{}", token, URL_SAFE_NO_PAD.encode(thumbprint.as_ref()))
+    }
+
+    async fn nonce(&mut self) -> Resul<String> {
+        match self.nonce.take() {
+            Some(nonce) => Ok(nonce),
+            None => {
+                let response = self.https.request(Method::HEAD, &self.directory.new_nonce, None).await?;
+                Ok(Self::header(&response, header::HeaderName::from_static("replay-nonce")).ok_or(AcmeError::NonceMissing)?)
+            }
+        }
+    }
+
+    /// signed request, without payload it is a `POST-as-GET`
+    /// a rejected nonce is retried once
+    async fn post(&mut self, url: &str, payload: Option<Value>) -> Resul<Response<Vec<u8>>> {
+        let payload = payload.map(|p| URL_SAFE_NO_PAD.encode(p.to_string())).unwrap_or_default();
+        let mut retry = true;
+
+        loop {
+            let mut protected = json!({
+                "alg": "ES256",
+                "nonce": self.nonce().await?,
+                "url": url,
+            });
+
+            match &self.account {
+                Some(account) => protected["kid"] = json!(account),
+                None => protected["jwk"] = self.jwk(),
+            }
+
+            let protected = URL_SAFE_NO_PAD.encode(protected.to_string());
+            let signature = self.key.sign(&self.rng, format!("{}.{}", protected, payload).as_bytes())
+                .map_err(|_| AcmeError::AccountKeyInvalid)?;
+
+            let body = json!({
+                "protected": protected,
+                "payload": payload,
+                "signature": URL_SAFE_NO_PAD.encode(signature.as_ref()),
+            });
+
+            let response = self.https.request(Method::POST, url, Some(body.to_string())).await?;
+            self.nonce = Self::header(&response, header::HeaderName::from_static("replay-nonce"));
+
+            if response.status().is_success() {
+                return Ok(response);
+            }
+
+            let problem = String::from_utf8_lossy(response.body()).to_string();
+
+            if retry && problem.contains(Self::BAD_NONCE) {
+                tracing::debug!("[ACME] nonce rejected, retrying");
+                retry = false;
+            } else {
+                return Err(AcmeError::Request(response.status().as_u16(), problem).into());
+            }
+        }
+    }
+
+    /// existing accounts of the key are returned as well
+    async fn account(&mut self, contact_email: &str) -> Resul<()> {
+        let url = self.directory.new_account.clone();
+        let response = self.post(&url, Some(json!({
+            "termsOfServiceAgreed": true,
+            "contact": [format!("mailto:{}", contact_email)],
+        }))).await?;
+
+        self.account = Some(Self::header(&response, header::LOCATION).ok_or(AcmeError::LocationMissing)?);
+        tracing::debug!("[ACME] account {:?}", self.account);
+        Ok(())
+    }
+
+    /// request the object until its status is valid
+    async fn poll(&mut self, url: &str) -> Resul<Vec<u8>> {
+        for _ in 0..30 {
+            let response = self.post(url, None).await?;
+            let status: Value = serde_json::from_slice(response.body())?;
+
+            match status["status"].as_str() {
+                Some("valid") => return Ok(response.into_body()),
+                Some("invalid") => return Err(AcmeError::Invalid(url.into(), status.to_string()).into()),
+                _ => tokio::time::sleep(Duration::from_secs(2)).await,
+            }
+        }
+
+        Err(AcmeError::Timeout(url.into()).into())
+    }
+}
+
+/// Obtains and renews certificates from an ACME certificate authority like Let's Encrypt.
+/// Domains are validated with http-01 challenges on port 80.
+/// Account key, private key and certificate are stored in the cache directory.
+pub struct Acme {
+    directory: String,
+    domains: Vec<String>,
+    contact_email: String,
+    cache_dir: PathBuf,
+}
+
+impl Acme {
+    pub fn new(directory: &str, domains: &[String], contact_email: &str, cache_dir: &str) -> Self {
+        Self {
+            directory: directory.into(),
+            domains: domains.to_vec(),
+            contact_email: contact_email.into(),
+            cache_dir: cache_dir.into(),
+        }
+    }
+
+    /// private key and certificate, a new certificate is ordered if there is no valid one for all domains
+    pub async fn certificate(&self) -> Resul<(String, String)> {
+        if let Some(cached) = self.cached().await? {
+            return Ok(cached);
+        }
+
+        create_dir_all(&self.cache_dir).await?;
+
+        let (private_key, certificate) = self.order().await?;
+
+        write_private(&self.cache_dir.join(PRIVATE_KEY), private_key.as_bytes()).await?;
+        tokio::fs::write(self.cache_dir.join(CERTIFICATE), certificate.as_bytes()).await?;
+        tracing::info!("[ACME] certificate for {} stored in {:?}", self.domains.join(", "), self.cache_dir);
+
+        Ok((private_key, certificate))
+    }
+
+    /// true if the stored certificate is missing, expires soon or lacks a domain
+    pub async fn renewal_due(&self) -> Resul<bool> {
+        self.cached().await.map(|cached| cached.is_none())
+    }
+
+    async fn cached(&self) -> Resul<Option<(String, String)>> {
+        let private_key = self.cache_dir.join(PRIVATE_KEY);
+        let certificate = self.cache_dir.join(CERTIFICATE);
+
+        if !try_exists(&private_key).await? || !try_exists(&certificate).await? {
+            return Ok(None);
+        }
+
+        let certificate = read_to_string(&certificate).await?;
+        let der = rustls_pemfile::certs(&mut certificate.as_bytes())?.into_iter().next().ok_or(AcmeError::CertificateMissing)?;
+
+        if certificate_expiration(&der)? < SystemTime::now() + RENEW_BEFORE {
+            tracing::info!("[ACME] certificate expires soon");
+            return Ok(None);
+        }
+
+        let names = certificate_names(&der)?;
+
+        if let Some(domain) = self.domains.iter().find(|d| !names.contains(d)) {
+            tracing::info!("[ACME] certificate does not contain {}", domain);
+            return Ok(None);
+        }
+
+        Ok(Some((read_to_string(&private_key).await?, certificate)))
+    }
+
+    /// stored account key or a new one
+    async fn account_key(&self) -> Resul<Vec<u8>> {
+        let path = self.cache_dir.join(ACCOUNT_KEY);
+
+        if try_exists(&path).await? {
+            Ok(read(&path).await?)
+        } else {
+            let key = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+                .map_err(|_| AcmeError::AccountKeyInvalid)?;
+            write_private(&path, key.as_ref()).await?;
+            tracing::info!("[ACME] account key generated");
+            Ok(key.as_ref().to_vec())
+        }
+    }
+
+    async fn challenge(State(challenges): State<Challenges>, Path(token): Path<String>) -> Result<String, StatusCode> {
+        tracing::debug!("[ACME] challenge {} requested", token);
+        challenges.lock().await.get(&token).cloned().ok_or(StatusCode::NOT_FOUND)
+    }
+
+    /// solve the http-01 challenges of all pending authorizations
+    async fn authorize(client: &mut AcmeClient, authorizations: &[String], challenges: &Challenges) -> Resul<()> {
+        for url in authorizations {
+            let authorization: Authorization = serde_json::from_slice(client.post(url, None).await?.body())?;
+
+            if authorization.status == "valid" {
+                continue;
+            }
+
+            let challenge = authorization.challenges.iter()
+                .find(|c| c.r#type == "http-01")
+                .ok_or(AcmeError::ChallengeMissing(authorization.identifier.value.clone()))?;
+
+            challenges.lock().await.insert(challenge.token.clone(), client.key_authorization(&challenge.token));
+
+            tracing::info!("[ACME] validating {}", authorization.identifier.value);
+            client.post(&challenge.url, Some(json!({}))).await?;
+            client.poll(url).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn order(&self) -> Resul<(String, String)> {
+        tracing::info!("[ACME] ordering certificate for {}", self.domains.join(", "));
+
+        let mut client = AcmeClient::new(&self.directory, &self.account_key().await?).await?;
+        client.account(&self.contact_email).await?;
+
+        let url = client.directory.new_order.clone();
+        let response = client.post(&url, Some(json!({
+            "identifiers": self.domains.iter().map(|d| json!({"type": "dns", "value": d})).collect::<Vec<_>>(),
+        }))).await?;
+
+        let order_url = AcmeClient::header(&response, header::LOCATION).ok_or(AcmeError::LocationMissing)?;
+        let order: Order = serde_json::from_slice(response.body())?;
+
+        let challenges = Challenges::default();
+        let router = Router::new()
+            .route("/.well-known/acme-challenge/:token", get(Self::challenge))
+            .with_state(challenges.clone());
+
+        let address: SocketAddr = CHALLENGE_LISTEN.parse()?;
+        let server = tokio::spawn(axum::Server::try_bind(&address)?.serve(router.into_make_service()));
+
+        let authorized = Self::authorize(&mut client, &order.authorizations, &challenges).await;
+        server.abort();
+        authorized?;
+
+        let certificate = Certificate::from_params(CertificateParams::new(self.domains.clone()))?;
+        client.post(&order.finalize, Some(json!({
+            "csr": URL_SAFE_NO_PAD.encode(certificate.serialize_request_der()?),
+        }))).await?;
+
+        let order: Order = serde_json::from_slice(&client.poll(&order_url).await?)?;
+        let chain = client.post(&order.certificate.ok_or(AcmeError::CertificateMissing)?, None).await?;
+
+        Ok((certificate.serialize_private_key_pem(), String::from_utf8(chain.into_body())?))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AcmeError {
+    #[error("invalid url {0}")]
+    InvalidUrl(String),
+    #[error("invalid account key")]
+    AccountKeyInvalid,
+    #[error("replay nonce missing")]
+    NonceMissing,
+    #[error("location header missing")]
+    LocationMissing,
+    #[error("request failed with status {0}: {1}")]
+    Request(u16, String),
+    #[error("no http-01 challenge for {0}")]
+    ChallengeMissing(String),
+    #[error("{0} is invalid: {1}")]
+    Invalid(String, String),
+    #[error("{0} was not valid in time")]
+    Timeout(String),
+    #[error("certificate missing")]
+    CertificateMissing,
+}
+
+#[cfg(test)]
+mod test {
+    use rcgen::{date_time_ymd, Certificate, CertificateParams};
+    use tempfile::tempdir;
+    use crate::acme::{Acme, CERTIFICATE, LETS_ENCRYPT, PRIVATE_KEY};
+
+    #[tokio::test]
+    async fn test_cached() {
+        let dir = tempdir().unwrap();
+        let domains = vec!["example.com".to_string(), "www.example.com".to_string()];
+        let acme = Acme::new(LETS_ENCRYPT, &domains, "admin@example.com", dir.path().to_str().unwrap());
+
+        assert!(acme.renewal_due().await.unwrap());
+
+        let mut params = CertificateParams::new(domains.clone());
+        params.not_after = date_time_ymd(3000, 1, 1);
+        let certificate = Certificate::from_params(params).unwrap();
+
+        std::fs::write(dir.path().join(PRIVATE_KEY), certificate.serialize_private_key_pem()).unwrap();
+        std::fs::write(dir.path().join(CERTIFICATE), certificate.serialize_pem().unwrap()).unwrap();
+
+        assert!(!acme.renewal_due().await.unwrap());
+        assert_eq!(acme.certificate().await.unwrap().0, certificate.serialize_private_key_pem());
+
+        // additional domain
+        let acme = Acme::new(LETS_ENCRYPT, &["mail.example.com".to_string()], "admin@example.com", dir.path().to_str().unwrap());
+        assert!(acme.renewal_due().await.unwrap());
+
+        // expires soon
+        let mut params = CertificateParams::new(domains.clone());
+        params.not_after = date_time_ymd(2000, 1, 1);
+        std::fs::write(dir.path().join(CERTIFICATE), Certificate::from_params(params).unwrap().serialize_pem().unwrap()).unwrap();
+
+        let acme = Acme::new(LETS_ENCRYPT, &domains, "admin@example.com", dir.path().to_str().unwrap());
+        assert!(acme.renewal_due().await.unwrap());
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use thiserror::Error;
+use crate::apps::prelude::*;
+use crate::apps::uname::Uname;
+use crate::files::cpuinfo::CpuInfo;
+use crate::files::loadavg::LoadAvg;
+use crate::files::mounts::Mounts;
+use crate::files::os_release::OsRelease;
+use crate::files::uptime::Uptime;
+use crate::system::System;
+
+/// starts every section of the script output followed by the file path or `uname`
+const SECTION: &str = "==> boofi ";
+
+/// Files read in one run, a missing file leaves its section empty
+const FILES: [&str; 6] = ["/etc/os-release", "/proc/cpuinfo", "/proc/meminfo", "/proc/mounts", "/proc/loadavg", "/proc/uptime"];
+
+/// processors: logical cpus
+/// sockets:    physical packages, 1 if unknown
+/// cores:      physical cores of all sockets, the processors if unknown
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct CpuSummary {
+    processors: usize,
+    sockets: usize,
+    cores: usize,
+    model_name: Option<String>,
+}
+
+impl CpuSummary {
+    fn parse(content: &str) -> Resul<Self> {
+        let details = CpuInfo::parse(content)?;
+        let sockets: HashSet<usize> = details.iter().filter_map(|d| d.physical_id()).collect();
+        let cores: HashSet<(Option<usize>, usize)> = details.iter()
+            .filter_map(|d| d.core_id().map(|c| (d.physical_id(), c)))
+            .collect();
+
+        Ok(Self {
+            processors: details.len(),
+            sockets: sockets.len().max(1),
+            cores: if cores.is_empty() { details.len() } else { cores.len() },
+            model_name: details.iter().find_map(|d| d.model_name()).map(Into::into),
+        })
+    }
+}
+
+/// Totals of `/proc/meminfo` in kB
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct MemorySummary {
+    mem_total: usize,
+    mem_available: Option<usize>,
+    swap_total: usize,
+    swap_free: usize,
+}
+
+impl MemorySummary {
+    fn parse(content: &str) -> Resul<Self> {
+        let values: HashMap<&str, &str> = content.lines()
+            .filter_map(|l| l.split_once(':'))
+            .map(|(name, value)| (name.trim(), value.trim().trim_end_matches("kB").trim()))
+            .collect();
+        let value = |name: &str| values.get(name).map(|v| v.parse::<usize>()).transpose();
+
+        Ok(Self {
+            mem_total: value("MemTotal")?.ok_or(FactsError::FieldMissing("MemTotal"))?,
+            mem_available: value("MemAvailable")?,
+            swap_total: value("SwapTotal")?.unwrap_or_default(),
+            swap_free: value("SwapFree")?.unwrap_or_default(),
+        })
+    }
+}
+
+/// Snapshot of the system, sections which could not be read or parsed are missing
+#[derive(Debug, Serialize, Description)]
+pub struct Facts {
+    os_release: Option<OsRelease>,
+    uname: Option<Uname>,
+    cpu: Option<CpuSummary>,
+    memory: Option<MemorySummary>,
+    mounts: Vec<Mounts>,
+    load_average: Option<LoadAvg>,
+    uptime: Option<Uptime>,
+}
+
+impl Facts {
+    /// Shell script printing all files and `uname -a`, each after its section line
+    fn script() -> String {
+        format!("for f in {}; do echo \"{}$f\"; cat \"$f\" 2>/dev/null; done; echo \"{}uname\"; {} -a",
+                FILES.join(" "), SECTION, SECTION, Uname::executable())
+    }
+
+    fn parse(output: &str) -> Self {
+        let mut sections: HashMap<&str, String> = HashMap::new();
+        let mut current = None;
+
+        for line in output.lines() {
+            if let Some(name) = line.strip_prefix(SECTION) {
+                current = Some(name);
+                sections.entry(name).or_default();
+            } else if let Some(name) = current {
+                let section = sections.entry(name).or_default();
+                section.push_str(line);
+                section.push('\n');
+            }
+        }
+
+        let content = |name: &str| sections.get(name).filter(|c| !c.trim().is_empty()).cloned();
+
+        Self {
+            os_release: content("/etc/os-release").and_then(|c| Self::section("os-release", OsRelease::try_from(c))),
+            uname: content("uname").and_then(|c| Self::section("uname", Uname::parse(&c))),
+            cpu: content("/proc/cpuinfo").and_then(|c| Self::section("cpuinfo", CpuSummary::parse(&c))),
+            memory: content("/proc/meminfo").and_then(|c| Self::section("meminfo", MemorySummary::parse(&c))),
+            mounts: content("/proc/mounts").and_then(|c| Self::section("mounts", Mounts::parse(&c))).unwrap_or_default(),
+            load_average: content("/proc/loadavg").and_then(|c| Self::section("loadavg", LoadAvg::parse(&c))),
+            uptime: content("/proc/uptime").and_then(|c| Self::section("uptime", Uptime::parse(&c))),
+        }
+    }
+
+    fn section<T, E: Display>(name: &str, result: Result<T, E>) -> Option<T> {
+        result.map_err(|e| tracing::warn!("[FACTS] skipping {}: {}", name, e)).ok()
+    }
+}
+
+pub struct FactsApp {}
+
+#[async_trait]
+impl App for FactsApp {
+    type Output = Facts;
+    type Input = ();
+
+    fn new() -> Self {
+        Self {}
+    }
+
+    async fn run<'de, I: Deserializer<'de> + Send>(&mut self, _input: I, system: &System) -> Resul<Self::Output> {
+        let output = system.run_args("/bin/sh", &["-c", Facts::script().as_str()]).await?;
+        Ok(Facts::parse(&String::from_utf8_lossy(&output)))
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FactsBuilder;
+
+impl AppBuilder for FactsBuilder {
+    app_metadata!(
+        FactsApp,
+        "facts",
+        "Snapshot of os-release, uname, cpu, memory totals, mounts, load average and uptime in one run",
+        &[Os::LinuxAny],
+        AppExample::new("get system facts", Box::new(""), Box::new(Facts::parse(&[
+            "==> boofi /etc/os-release",
+            "NAME=\"Ubuntu\"",
+            "ID=ubuntu",
+            "VERSION_ID=\"22.04\"",
+            "==> boofi /proc/cpuinfo",
+            "processor\t: 0",
+            "model name\t: Intel(R) Core(TM) i7-8565U CPU @ 1.80GHz",
+            "==> boofi /proc/meminfo",
+            "MemTotal:        8148404 kB",
+            "MemAvailable:    5123456 kB",
+            "SwapTotal:       2097148 kB",
+            "SwapFree:        2097148 kB",
+            "==> boofi /proc/mounts",
+            "/dev/sda1 / ext4 rw,relatime 0 0",
+            "==> boofi /proc/loadavg",
+            "0.07 0.42 0.55 1/820 19277",
+            "==> boofi /proc/uptime",
+            "12345.67 45678.90",
+            "==> boofi uname",
+            "Linux web1 5.15.0-78-generic #85-Ubuntu SMP Mon Jul 17 09:42:39 UTC 2023 x86_64 x86_64 x86_64 GNU/Linux",
+        ].join("\n"))))
+    );
+}
+
+#[derive(Debug, Error)]
+pub enum FactsError {
+    #[error("{0} missing")]
+    FieldMissing(&'static str),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::apps::facts::{CpuSummary, Facts, MemorySummary, SECTION};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let output = [
+            ("/etc/os-release", "os-release_ubuntu"),
+            ("/proc/cpuinfo", "cpuinfo"),
+            ("/proc/meminfo", "meminfo"),
+            ("/proc/mounts", "mounts"),
+            ("/proc/loadavg", "loadavg"),
+            ("uname", "uname_all"),
+        ].iter().map(|(section, resource)| format!("{}{}\n{}\n", SECTION, section, read_test_resources(resource))).collect::<String>()
+            + SECTION + "/proc/uptime\n";
+
+        let facts = Facts::parse(&output);
+
+        assert_eq!(facts.os_release.unwrap().id(), "ubuntu");
+        assert!(facts.uname.is_some());
+        assert!(facts.cpu.unwrap().processors > 0);
+        assert!(facts.memory.unwrap().mem_total > 0);
+        assert!(!facts.mounts.is_empty());
+        assert!(facts.load_average.is_some());
+        assert!(facts.uptime.is_none());
+    }
+
+    #[test]
+    fn test_summaries() {
+        let cpu = CpuSummary::parse("processor : 0\nphysical id : 0\ncore id : 0\n\nprocessor : 1\nphysical id : 0\ncore id : 0\n\n\
+                                     processor : 2\nphysical id : 0\ncore id : 1\n").unwrap();
+        assert_eq!((cpu.processors, cpu.sockets, cpu.cores), (3, 1, 2));
+
+        let memory = MemorySummary::parse("MemTotal: 100 kB\nSwapTotal: 0 kB\n").unwrap();
+        assert_eq!(memory, MemorySummary { mem_total: 100, mem_available: None, swap_total: 0, swap_free: 0 });
+        assert!(MemorySummary::parse("SwapTotal: 0 kB\n").is_err());
+    }
+}
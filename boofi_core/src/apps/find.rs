@@ -0,0 +1,331 @@
+use crate::apps::prelude::*;
+use thiserror::Error;
+use crate::system::{RunOutput, System};
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct FindInput {
+    path: String,
+    name: Option<String>,
+    min_size: Option<usize>,
+    max_size: Option<usize>,
+    modified_within: Option<usize>,
+    max_depth: Option<usize>,
+    content: Option<String>,
+}
+
+impl FindInput {
+    /// a path starting with `-` would be read as an expression of `find`
+    fn validate(&self) -> Result<(), FindError> {
+        if self.path.is_empty() || self.path.starts_with('-') {
+            return Err(FindError::PathInvalid(self.path.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// `find` arguments, sizes in bytes and modification time in minutes
+    fn arguments(&self) -> Vec<String> {
+        let mut arguments = vec![self.path.clone()];
+
+        if let Some(depth) = self.max_depth {
+            arguments.extend(["-maxdepth".into(), depth.to_string()]);
+        }
+
+        if let Some(name) = &self.name {
+            arguments.extend(["-name".into(), name.clone()]);
+        }
+
+        if let Some(size) = self.min_size.filter(|s| *s > 0) {
+            arguments.extend(["-size".into(), format!("+{}c", size - 1)]);
+        }
+
+        if let Some(size) = self.max_size {
+            arguments.extend(["-size".into(), format!("-{}c", size + 1)]);
+        }
+
+        if let Some(minutes) = self.modified_within {
+            arguments.extend(["-mmin".into(), format!("-{}", minutes)]);
+        }
+
+        if self.content.is_some() {
+            arguments.extend(["-type".into(), "f".into()]);
+        }
+
+        arguments
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct FindLine {
+    line: usize,
+    content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct FindMatch {
+    path: String,
+    lines: Vec<FindLine>,
+}
+
+pub struct Find;
+
+impl Find {
+    /// bytes of the paths passed to one `grep`, far below the argument limit of the system
+    const GREP_PATHS_SIZE: usize = 64 * 1024;
+
+    fn find() -> &'static str { "/usr/bin/find" }
+
+    fn grep() -> &'static str { "/bin/grep" }
+
+    /// `find` exits with 1 if a directory is unreadable and `grep` with 2 if a file is, the output found so far is kept.
+    /// Without any output `find` failed entirely, e.g. for a missing path, while `grep` just matched nothing.
+    fn partial(output: RunOutput, program: &str) -> Result<String, FindError> {
+        match output.exit_code() {
+            0 => Ok(output.stdout().into()),
+            1 | 2 if program == Self::grep() => Ok(output.stdout().into()),
+            1 if !output.stdout().is_empty() => {
+                tracing::warn!("[FIND] partial result: {}", output.stderr().trim());
+                Ok(output.stdout().into())
+            }
+            code => Err(FindError::Failed(code, output.stderr().trim().into())),
+        }
+    }
+
+    /// paths split into chunks which fit on one command line
+    fn chunks(found: &[FindMatch]) -> Vec<&[FindMatch]> {
+        let mut chunks = vec![];
+        let mut start = 0;
+        let mut size = 0;
+
+        for (index, m) in found.iter().enumerate() {
+            if size + m.path.len() > Self::GREP_PATHS_SIZE && index > start {
+                chunks.push(&found[start..index]);
+                start = index;
+                size = 0;
+            }
+            size += m.path.len() + 1;
+        }
+
+        if start < found.len() {
+            chunks.push(&found[start..]);
+        }
+
+        chunks
+    }
+
+    /// one path per line
+    pub fn parse(content: &str) -> Vec<FindMatch> {
+        content.lines()
+            .filter(|s| !s.is_empty())
+            .map(|path| FindMatch {
+                path: path.into(),
+                lines: vec![],
+            })
+            .collect()
+    }
+
+    /// `grep -n -H -Z` output, file name is terminated by a null byte followed by `line:content`
+    pub fn parse_grep(content: &str) -> Resul<Vec<FindMatch>> {
+        let mut matches: Vec<FindMatch> = vec![];
+
+        for l in content.lines().filter(|s| !s.is_empty()) {
+            let (path, rest) = l.split_once('\0').ok_or(FindError::InvalidLine(l.into()))?;
+            let (line, content) = rest.split_once(':').ok_or(FindError::InvalidLine(l.into()))?;
+
+            let line = FindLine {
+                line: line.parse()?,
+                content: content.into(),
+            };
+
+            match matches.last_mut() {
+                Some(m) if m.path == path => m.lines.push(line),
+                _ => matches.push(FindMatch {
+                    path: path.into(),
+                    lines: vec![line],
+                }),
+            }
+        }
+
+        Ok(matches)
+    }
+
+    pub async fn run_parse(input: FindInput, system: &System) -> Resul<Vec<FindMatch>> {
+        input.validate()?;
+
+        let output = system.run_output(Self::find(), &input.arguments()).await?;
+        let found = Self::parse(&Self::partial(output, Self::find())?);
+
+        let Some(pattern) = &input.content else {
+            return Ok(found);
+        };
+
+        let mut matches = vec![];
+
+        for chunk in Self::chunks(&found) {
+            let mut arguments = vec!["-n", "-H", "-Z", "-I", "-e", pattern.as_str(), "--"];
+            arguments.extend(chunk.iter().map(|m| m.path.as_str()));
+
+            let output = system.run_output(Self::grep(), &arguments).await?;
+            matches.extend(Self::parse_grep(&Self::partial(output, Self::grep())?)?);
+        }
+
+        Ok(matches)
+    }
+}
+
+#[async_trait]
+impl App for Find {
+    type Output = Vec<FindMatch>;
+    type Input = FindInput;
+
+    fn new() -> Self {
+        Self {}
+    }
+
+    async fn run<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System) -> Resul<Self::Output> {
+        let i = FindInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        Find::run_parse(i, system).await
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FindBuilder {}
+
+impl AppBuilder for FindBuilder {
+    app_metadata!(
+        Find,
+        "find",
+        "Search files by name glob, size in bytes, modification time in minutes and optionally by content. Matching lines are returned if content is given.",
+        &[Os::LinuxAny],
+        AppExample::new("Find log files modified in the last hour",
+            Box::new(FindInput {
+                path: "/var/log".into(),
+                name: Some("*.log".into()),
+                min_size: None,
+                max_size: None,
+                modified_within: Some(60),
+                max_depth: Some(2),
+                content: None,
+            }),
+            Box::new(vec![FindMatch {
+                path: "/var/log/syslog.log".into(),
+                lines: vec![],
+            }])
+        ),
+        AppExample::new("Find configuration files containing a value",
+            Box::new(FindInput {
+                path: "/etc/ssh".into(),
+                name: Some("*_config".into()),
+                min_size: None,
+                max_size: Some(65536),
+                modified_within: None,
+                max_depth: None,
+                content: Some("PermitRootLogin".into()),
+            }),
+            Box::new(vec![FindMatch {
+                path: "/etc/ssh/sshd_config".into(),
+                lines: vec![FindLine {
+                    line: 33,
+                    content: "PermitRootLogin no".into(),
+                }],
+            }])
+        )
+    );
+}
+
+#[derive(Debug, Error)]
+pub enum FindError {
+    #[error("invalid grep line {0}")]
+    InvalidLine(String),
+    #[error("invalid path {0}, it must not start with -")]
+    PathInvalid(String),
+    #[error("find failed with exit code {0}: {1}")]
+    Failed(usize, String),
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use crate::apps::find::{Find, FindError, FindInput, FindLine, FindMatch};
+    use crate::system::RunOutput;
+
+    #[test]
+    fn test_arguments() {
+        assert_eq!(FindInput {
+            path: "/var/log".into(),
+            name: Some("*.log".into()),
+            min_size: Some(1024),
+            max_size: Some(2048),
+            modified_within: Some(60),
+            max_depth: Some(2),
+            content: Some("error".into()),
+        }.arguments(), [
+            "/var/log", "-maxdepth", "2", "-name", "*.log", "-size", "+1023c", "-size", "-2049c", "-mmin", "-60", "-type", "f"
+        ]);
+    }
+
+    #[test]
+    fn test_validate() {
+        let input = |path: &str| FindInput {
+            path: path.into(),
+            name: None,
+            min_size: None,
+            max_size: None,
+            modified_within: None,
+            max_depth: None,
+            content: None,
+        };
+
+        assert!(input("/var/log").validate().is_ok());
+        assert!(input("./-delete").validate().is_ok());
+        assert!(matches!(input("-delete").validate(), Err(FindError::PathInvalid(_))));
+        assert!(matches!(input("-exec").validate(), Err(FindError::PathInvalid(_))));
+        assert!(matches!(input("").validate(), Err(FindError::PathInvalid(_))));
+    }
+
+    #[test]
+    fn test_partial() {
+        let output = |stdout: &str, stderr: &str, code: u32| RunOutput::new(stdout.into(), stderr.into(), code, Duration::ZERO);
+
+        assert_eq!(Find::partial(output("/var/log/syslog\n", "", 0), Find::find()).unwrap(), "/var/log/syslog\n");
+        assert_eq!(Find::partial(output("/var/log/syslog\n", "find: '/var/log/private': Permission denied", 1), Find::find()).unwrap(), "/var/log/syslog\n");
+        assert!(matches!(Find::partial(output("", "find: '/missing': No such file or directory", 1), Find::find()), Err(FindError::Failed(1, _))));
+        assert_eq!(Find::partial(output("", "", 1), Find::grep()).unwrap(), "");
+        assert_eq!(Find::partial(output("/etc/hosts\u{0}1:localhost\n", "grep: /etc/shadow: Permission denied", 2), Find::grep()).unwrap(), "/etc/hosts\u{0}1:localhost\n");
+    }
+
+    #[test]
+    fn test_chunks() {
+        let found: Vec<FindMatch> = (0..5000).map(|i| FindMatch {
+            path: format!("/var/lib/data/some/deeply/nested/directory/file-{:05}", i),
+            lines: vec![],
+        }).collect();
+
+        let chunks = Find::chunks(&found);
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.iter().map(|c| c.len()).sum::<usize>(), found.len());
+        assert!(chunks.iter().all(|c| c.iter().map(|m| m.path.len() + 1).sum::<usize>() <= Find::GREP_PATHS_SIZE));
+        assert!(Find::chunks(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_grep() {
+        let content = "/etc/hosts\u{0}1:127.0.0.1 localhost\n/etc/hosts\u{0}2:::1 localhost\n/etc/hostname\u{0}1:dev\n";
+
+        assert_eq!(Find::parse_grep(content).unwrap(), vec![
+            FindMatch {
+                path: "/etc/hosts".into(),
+                lines: vec![
+                    FindLine { line: 1, content: "127.0.0.1 localhost".into() },
+                    FindLine { line: 2, content: "::1 localhost".into() },
+                ],
+            },
+            FindMatch {
+                path: "/etc/hostname".into(),
+                lines: vec![FindLine { line: 1, content: "dev".into() }],
+            },
+        ]);
+
+        assert!(Find::parse_grep("/etc/hosts:1:localhost").is_err());
+    }
+}
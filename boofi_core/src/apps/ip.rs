@@ -0,0 +1,339 @@
+use serde::de::DeserializeOwned;
+use crate::apps::prelude::*;
+use thiserror::Error;
+use crate::system::System;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Description)]
+#[serde(rename_all = "lowercase")]
+pub enum IpObject {
+    Link,
+    Address,
+    Route,
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct IpInput {
+    #[desc(description = "all objects are returned if missing")]
+    objects: Option<Vec<IpObject>>,
+}
+
+/// Field names of `ip -j` are accepted as alias
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct IpLink {
+    #[serde(alias = "ifindex")]
+    index: usize,
+    #[serde(alias = "ifname")]
+    name: String,
+    #[serde(default)]
+    flags: Vec<String>,
+    mtu: Option<usize>,
+    #[serde(alias = "operstate")]
+    state: Option<String>,
+    link_type: Option<String>,
+    #[serde(alias = "address")]
+    mac: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct IpAddress {
+    #[desc(description = "inet or inet6")]
+    family: String,
+    local: String,
+    #[serde(alias = "prefixlen")]
+    prefix_length: usize,
+    scope: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct IpInterface {
+    #[serde(alias = "ifname")]
+    name: String,
+    #[serde(default, alias = "addr_info")]
+    addresses: Vec<IpAddress>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct IpRoute {
+    #[serde(alias = "dst")]
+    #[desc(description = "default or network in CIDR notation")]
+    destination: String,
+    gateway: Option<String>,
+    #[serde(alias = "dev")]
+    device: Option<String>,
+    protocol: Option<String>,
+    scope: Option<String>,
+    #[serde(alias = "prefsrc")]
+    source: Option<String>,
+    metric: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Description)]
+pub struct IpOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    links: Option<Vec<IpLink>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interfaces: Option<Vec<IpInterface>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    routes: Option<Vec<IpRoute>>,
+}
+
+/// Value following a keyword like `mtu 1500`
+fn value_of<'a>(tokens: &[&'a str], key: &str) -> Option<&'a str> {
+    tokens.iter().position(|t| *t == key).and_then(|i| tokens.get(i + 1)).copied()
+}
+
+/// Parser of the human readable output of old `ip` versions without json support
+pub struct IpText;
+
+impl IpText {
+    /// `ip -o link`, one link per line like `2: eth0: <UP> mtu 1500 ... state UP ...\    link/ether 52:54:00:12:34:56 brd ...`
+    pub fn links(content: &str) -> Resul<Vec<IpLink>> {
+        content.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| {
+                let invalid = || IpError::InvalidLine(l.into());
+                let mut parts = l.splitn(3, ": ");
+                let index = parts.next().ok_or_else(invalid)?.trim().parse()?;
+                // virtual links are named like `eth0.20@eth0`
+                let name = parts.next().ok_or_else(invalid)?.split('@').next().unwrap_or_default();
+                let rest = parts.next().ok_or_else(invalid)?;
+                let tokens: Vec<&str> = rest.split_whitespace().collect();
+                let link = tokens.iter().position(|t| t.starts_with("link/"));
+
+                Ok(IpLink {
+                    index,
+                    name: name.into(),
+                    flags: rest.split_once('<')
+                        .and_then(|(_, r)| r.split_once('>'))
+                        .map(|(flags, _)| flags.split(',').filter(|f| !f.is_empty()).map(Into::into).collect())
+                        .unwrap_or_default(),
+                    mtu: value_of(&tokens, "mtu").map(str::parse).transpose()?,
+                    state: value_of(&tokens, "state").map(Into::into),
+                    link_type: link.map(|i| tokens[i].trim_start_matches("link/").into()),
+                    mac: link.and_then(|i| tokens.get(i + 1)).filter(|m| m.contains(':')).map(|m| m.to_string()),
+                })
+            })
+            .collect()
+    }
+
+    /// `ip -o addr`, one address per line like `2: eth0    inet 192.168.1.10/24 brd ... scope global eth0\ ...`
+    pub fn interfaces(content: &str) -> Resul<Vec<IpInterface>> {
+        let mut interfaces: Vec<IpInterface> = vec![];
+
+        for l in content.lines().filter(|l| !l.trim().is_empty()) {
+            let invalid = || IpError::InvalidLine(l.into());
+            let tokens: Vec<&str> = l.split_whitespace().collect();
+            let (name, family, cidr) = match tokens.as_slice() {
+                [_, name, family, cidr, ..] => (name.split('@').next().unwrap_or_default(), *family, *cidr),
+                _ => return Err(invalid().into()),
+            };
+            let (address, prefix_length) = cidr.split_once('/').ok_or_else(invalid)?;
+
+            let address = IpAddress {
+                family: family.into(),
+                local: address.into(),
+                prefix_length: prefix_length.parse()?,
+                scope: value_of(&tokens, "scope").map(Into::into),
+            };
+
+            match interfaces.iter_mut().find(|i| i.name == name) {
+                Some(interface) => interface.addresses.push(address),
+                None => interfaces.push(IpInterface {
+                    name: name.into(),
+                    addresses: vec![address],
+                }),
+            }
+        }
+
+        Ok(interfaces)
+    }
+
+    /// `ip route`, one route per line like `default via 192.168.1.1 dev eth0 proto dhcp src 192.168.1.10 metric 100`
+    pub fn routes(content: &str) -> Resul<Vec<IpRoute>> {
+        content.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| {
+                let tokens: Vec<&str> = l.split_whitespace().collect();
+
+                Ok(IpRoute {
+                    destination: tokens.first().ok_or(IpError::InvalidLine(l.into()))?.to_string(),
+                    gateway: value_of(&tokens, "via").map(Into::into),
+                    device: value_of(&tokens, "dev").map(Into::into),
+                    protocol: value_of(&tokens, "proto").map(Into::into),
+                    scope: value_of(&tokens, "scope").map(Into::into),
+                    source: value_of(&tokens, "src").map(Into::into),
+                    metric: value_of(&tokens, "metric").map(str::parse).transpose()?,
+                })
+            })
+            .collect()
+    }
+}
+
+pub struct Ip;
+
+impl Ip {
+    fn ip() -> &'static str { "/sbin/ip" }
+
+    /// json output if supported, otherwise the human readable one
+    async fn run<T: DeserializeOwned>(system: &System, arguments: &[&str], text: fn(&str) -> Resul<T>) -> Resul<T> {
+        let mut json = vec!["-j"];
+        json.extend_from_slice(arguments);
+
+        match system.run_args(Self::ip(), &json).await {
+            Ok(o) => serde_json::from_slice(&o).map_err(Into::into),
+            Err(e) => {
+                tracing::debug!("[IP] json output not supported, parsing text: {}", e);
+
+                let mut plain = vec!["-o"];
+                plain.extend_from_slice(arguments);
+                text(&String::from_utf8(system.run_args(Self::ip(), &plain).await?)?)
+            }
+        }
+    }
+
+    pub async fn run_parse(input: IpInput, system: &System) -> Resul<IpOutput> {
+        let objects = input.objects.unwrap_or_else(|| vec![IpObject::Link, IpObject::Address, IpObject::Route]);
+        let mut output = IpOutput::default();
+
+        if objects.contains(&IpObject::Link) {
+            output.links = Some(Self::run(system, &["link"], IpText::links).await?);
+        }
+
+        if objects.contains(&IpObject::Address) {
+            output.interfaces = Some(Self::run(system, &["addr"], IpText::interfaces).await?);
+        }
+
+        if objects.contains(&IpObject::Route) {
+            let mut routes = Self::run(system, &["route"], IpText::routes).await?;
+            routes.extend(Self::run(system, &["-6", "route"], IpText::routes).await?);
+            output.routes = Some(routes);
+        }
+
+        Ok(output)
+    }
+}
+
+#[async_trait]
+impl App for Ip {
+    type Output = IpOutput;
+    type Input = IpInput;
+
+    fn new() -> Self {
+        Self {}
+    }
+
+    async fn run<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System) -> Resul<Self::Output> {
+        let i = IpInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        Ip::run_parse(i, system).await
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct IpBuilder {}
+
+impl AppBuilder for IpBuilder {
+    app_metadata!(
+        Ip,
+        "ip",
+        "Network interfaces with their link state and addresses and the IPv4 and IPv6 routing table.",
+        &[Os::LinuxAny],
+        AppExample::new("Addresses and routes",
+            Box::new(IpInput {
+                objects: Some(vec![IpObject::Address, IpObject::Route]),
+            }),
+            Box::new(IpOutput {
+                links: None,
+                interfaces: Some(vec![IpInterface {
+                    name: "eth0".into(),
+                    addresses: vec![IpAddress {
+                        family: "inet".into(),
+                        local: "192.168.1.10".into(),
+                        prefix_length: 24,
+                        scope: Some("global".into()),
+                    }],
+                }]),
+                routes: Some(vec![IpRoute {
+                    destination: "default".into(),
+                    gateway: Some("192.168.1.1".into()),
+                    device: Some("eth0".into()),
+                    protocol: Some("dhcp".into()),
+                    scope: None,
+                    source: Some("192.168.1.10".into()),
+                    metric: Some(100),
+                }]),
+            })
+        ),
+        AppExample::new("Link states",
+            Box::new(IpInput {
+                objects: Some(vec![IpObject::Link]),
+            }),
+            Box::new(IpOutput {
+                links: Some(vec![IpLink {
+                    index: 2,
+                    name: "eth0".into(),
+                    flags: vec!["BROADCAST".into(), "MULTICAST".into(), "UP".into(), "LOWER_UP".into()],
+                    mtu: Some(1500),
+                    state: Some("UP".into()),
+                    link_type: Some("ether".into()),
+                    mac: Some("52:54:00:12:34:56".into()),
+                }]),
+                interfaces: None,
+                routes: None,
+            })
+        )
+    );
+}
+
+#[derive(Debug, Error)]
+pub enum IpError {
+    #[error("invalid ip line {0}")]
+    InvalidLine(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::apps::ip::{IpInterface, IpLink, IpRoute, IpText};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_links() {
+        let links: Vec<IpLink> = serde_json::from_str(&read_test_resources("ip_link_json")).unwrap();
+
+        assert_eq!(links[1], IpLink {
+            index: 2,
+            name: "eth0".into(),
+            flags: vec!["BROADCAST".into(), "MULTICAST".into(), "UP".into(), "LOWER_UP".into()],
+            mtu: Some(1500),
+            state: Some("UP".into()),
+            link_type: Some("ether".into()),
+            mac: Some("52:54:00:12:34:56".into()),
+        });
+        assert_eq!(IpText::links(&read_test_resources("ip_link")).unwrap(), links);
+    }
+
+    #[test]
+    fn test_interfaces() {
+        let interfaces: Vec<IpInterface> = serde_json::from_str(&read_test_resources("ip_addr_json")).unwrap();
+
+        assert_eq!(interfaces.iter().map(|i| (i.name.as_str(), i.addresses.len())).collect::<Vec<_>>(), vec![("lo", 2), ("eth0", 2)]);
+        assert_eq!(interfaces[1].addresses[0].local, "192.168.1.10");
+        assert_eq!(interfaces[1].addresses[0].prefix_length, 24);
+        assert_eq!(IpText::interfaces(&read_test_resources("ip_addr")).unwrap(), interfaces);
+    }
+
+    #[test]
+    fn test_routes() {
+        let routes: Vec<IpRoute> = serde_json::from_str(&read_test_resources("ip_route_json")).unwrap();
+
+        assert_eq!(routes[0], IpRoute {
+            destination: "default".into(),
+            gateway: Some("192.168.1.1".into()),
+            device: Some("eth0".into()),
+            protocol: Some("dhcp".into()),
+            scope: None,
+            source: Some("192.168.1.10".into()),
+            metric: Some(100),
+        });
+        assert_eq!(IpText::routes(&read_test_resources("ip_route")).unwrap(), routes);
+    }
+}
@@ -0,0 +1,259 @@
+use serde_json::{Map, Value};
+use crate::apps::prelude::*;
+use thiserror::Error;
+use crate::system::System;
+
+/// newest entries returned if no number of lines is given
+const JOURNAL_LINES: usize = 100;
+
+/// Syslog priorities from most to least important, a filter includes all more important ones
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Description)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalPriority {
+    Emerg,
+    Alert,
+    Crit,
+    Err,
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+impl JournalPriority {
+    fn name(&self) -> &'static str {
+        match self {
+            JournalPriority::Emerg => "emerg",
+            JournalPriority::Alert => "alert",
+            JournalPriority::Crit => "crit",
+            JournalPriority::Err => "err",
+            JournalPriority::Warning => "warning",
+            JournalPriority::Notice => "notice",
+            JournalPriority::Info => "info",
+            JournalPriority::Debug => "debug",
+        }
+    }
+}
+
+/// since/until:    timestamps like `2024-01-01 12:00:00`, `yesterday` or `-1h`
+/// grep:           pattern matched against the message
+/// lines:          newest entries, 100 by default
+#[derive(Serialize, Deserialize, Description)]
+pub struct JournalctlInput {
+    unit: Option<String>,
+    priority: Option<JournalPriority>,
+    since: Option<String>,
+    until: Option<String>,
+    grep: Option<String>,
+    lines: Option<usize>,
+}
+
+impl JournalctlInput {
+    fn arguments(&self) -> Vec<String> {
+        let mut arguments = vec![
+            "--output=json".into(),
+            "--no-pager".into(),
+            format!("--lines={}", self.lines.unwrap_or(JOURNAL_LINES)),
+        ];
+
+        if let Some(unit) = &self.unit {
+            arguments.push(format!("--unit={}", unit));
+        }
+
+        if let Some(priority) = &self.priority {
+            arguments.push(format!("--priority={}", priority.name()));
+        }
+
+        if let Some(since) = &self.since {
+            arguments.push(format!("--since={}", since));
+        }
+
+        if let Some(until) = &self.until {
+            arguments.push(format!("--until={}", until));
+        }
+
+        if let Some(grep) = &self.grep {
+            arguments.push(format!("--grep={}", grep));
+        }
+
+        arguments
+    }
+}
+
+/// timestamp:  microseconds since unix epoch
+/// priority:   0 (emerg) to 7 (debug)
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct JournalEntry {
+    timestamp: usize,
+    priority: Option<usize>,
+    unit: Option<String>,
+    identifier: Option<String>,
+    pid: Option<usize>,
+    hostname: Option<String>,
+    message: String,
+}
+
+impl JournalEntry {
+    /// Fields are strings, binary values are arrays of bytes
+    fn field(fields: &Map<String, Value>, key: &str) -> Option<String> {
+        match fields.get(key)? {
+            Value::String(s) => Some(s.clone()),
+            Value::Array(bytes) => Some(String::from_utf8_lossy(&bytes.iter()
+                .filter_map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                .collect::<Vec<u8>>()).to_string()),
+            _ => None,
+        }
+    }
+
+    /// one json object per line like written by `journalctl --output=json`
+    pub fn parse(content: &str) -> Resul<Vec<Self>> {
+        content.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| {
+                let fields: Map<String, Value> = serde_json::from_str(l)?;
+                let number = |key: &str| Self::field(&fields, key).and_then(|v| v.parse().ok());
+
+                Ok(Self {
+                    timestamp: number("__REALTIME_TIMESTAMP").ok_or(JournalctlError::InvalidEntry(l.into()))?,
+                    priority: number("PRIORITY"),
+                    unit: Self::field(&fields, "_SYSTEMD_UNIT"),
+                    identifier: Self::field(&fields, "SYSLOG_IDENTIFIER"),
+                    pid: number("_PID"),
+                    hostname: Self::field(&fields, "_HOSTNAME"),
+                    message: Self::field(&fields, "MESSAGE").unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+}
+
+pub struct Journalctl;
+
+impl Journalctl {
+    fn journalctl() -> &'static str { "/bin/journalctl" }
+
+    pub async fn run_parse(input: JournalctlInput, system: &System) -> Resul<Vec<JournalEntry>> {
+        let o = system.run_args(Self::journalctl(), &input.arguments()).await?;
+        JournalEntry::parse(&String::from_utf8(o)?)
+    }
+}
+
+#[async_trait]
+impl App for Journalctl {
+    type Output = Vec<JournalEntry>;
+    type Input = JournalctlInput;
+
+    fn new() -> Self {
+        Self {}
+    }
+
+    async fn run<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System) -> Resul<Self::Output> {
+        let i = JournalctlInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        Journalctl::run_parse(i, system).await
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct JournalctlBuilder {}
+
+impl AppBuilder for JournalctlBuilder {
+    app_metadata!(
+        Journalctl,
+        "journalctl",
+        "Query the systemd journal filtered by unit, priority, time range and message pattern. Entries are returned oldest first.",
+        &[Os::LinuxAny],
+        AppExample::new("Errors of a service in the last hour",
+            Box::new(JournalctlInput {
+                unit: Some("ssh.service".into()),
+                priority: Some(JournalPriority::Err),
+                since: Some("-1h".into()),
+                until: None,
+                grep: None,
+                lines: Some(50),
+            }),
+            Box::new(vec![JournalEntry {
+                timestamp: 1700000000000000,
+                priority: Some(3),
+                unit: Some("ssh.service".into()),
+                identifier: Some("sshd".into()),
+                pid: Some(812),
+                hostname: Some("dev".into()),
+                message: "error: kex_exchange_identification: Connection closed by remote host".into(),
+            }])
+        ),
+        AppExample::new("Search failed logins since yesterday",
+            Box::new(JournalctlInput {
+                unit: None,
+                priority: None,
+                since: Some("yesterday".into()),
+                until: None,
+                grep: Some("Failed password".into()),
+                lines: None,
+            }),
+            Box::new(vec![JournalEntry {
+                timestamp: 1700000000000000,
+                priority: Some(6),
+                unit: Some("ssh.service".into()),
+                identifier: Some("sshd".into()),
+                pid: Some(1024),
+                hostname: Some("dev".into()),
+                message: "Failed password for invalid user admin from 192.168.1.20 port 50122 ssh2".into(),
+            }])
+        )
+    );
+}
+
+#[derive(Debug, Error)]
+pub enum JournalctlError {
+    #[error("invalid journal entry {0}")]
+    InvalidEntry(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::apps::journalctl::{JournalctlInput, JournalEntry, JournalPriority};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_arguments() {
+        assert_eq!(JournalctlInput {
+            unit: Some("ssh.service".into()),
+            priority: Some(JournalPriority::Warning),
+            since: Some("2024-01-01 12:00:00".into()),
+            until: Some("-1h".into()),
+            grep: Some("Failed password".into()),
+            lines: None,
+        }.arguments(), [
+            "--output=json", "--no-pager", "--lines=100", "--unit=ssh.service", "--priority=warning",
+            "--since=2024-01-01 12:00:00", "--until=-1h", "--grep=Failed password"
+        ]);
+    }
+
+    #[test]
+    fn test_parse() {
+        let entries = JournalEntry::parse(&read_test_resources("journalctl_json")).unwrap();
+
+        assert_eq!(entries, vec![
+            JournalEntry {
+                timestamp: 1700000000123456,
+                priority: Some(6),
+                unit: Some("ssh.service".into()),
+                identifier: Some("sshd".into()),
+                pid: Some(812),
+                hostname: Some("dev".into()),
+                message: "Server listening on 0.0.0.0 port 22.".into(),
+            },
+            JournalEntry {
+                timestamp: 1700000001000000,
+                priority: Some(5),
+                unit: None,
+                identifier: Some("kernel".into()),
+                pid: None,
+                hostname: Some("dev".into()),
+                message: "binary\u{fffd}".into(),
+            },
+        ]);
+
+        assert!(JournalEntry::parse("{\"MESSAGE\": \"no timestamp\"}").is_err());
+    }
+}
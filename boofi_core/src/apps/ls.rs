@@ -6,7 +6,7 @@ use crate::system::os::Os;
 use crate::system::System;
 
 #[derive(Debug, Deserialize, PartialEq)]
-pub(crate) enum LsArguments {
+pub enum LsArguments {
     All,
     List,
     HumanReadable,
@@ -14,17 +14,17 @@ pub(crate) enum LsArguments {
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
-pub(crate) struct LsEntry {
+pub struct LsEntry {
     filename: String,
     size: Option::<String>,
     permissions: Option::<String>,
 }
 
 impl LsEntry {
-    pub(crate) fn filename(&self) -> &str { self.filename.as_str() }
-    pub(crate) fn size(&self) -> Option<&str> { self.size.as_deref() }
+    pub fn filename(&self) -> &str { self.filename.as_str() }
+    pub fn size(&self) -> Option<&str> { self.size.as_deref() }
 
-    pub(crate) fn parse_from_line(arguments: &LsInput, line: &str) -> Resul<Self> {
+    pub fn parse_from_line(arguments: &LsInput, line: &str) -> Resul<Self> {
         let (permissions,
             size,
             filename,
@@ -50,16 +50,20 @@ impl LsEntry {
 
 
 #[derive(Serialize, Deserialize, Debug, Description)]
-pub(crate) struct LsInput {
+pub struct LsInput {
+    #[desc(default = "false")]
     list: Option::<bool>,
+    #[desc(default = "false")]
     all: Option::<bool>,
+    #[desc(default = "false")]
     human_readable: Option::<bool>,
+    #[desc(default = "false")]
     classify: Option::<bool>,
     path: String,
 }
 
 impl LsInput {
-    pub(crate) fn new<T, P>(list: T,
+    pub fn new<T, P>(list: T,
                             all: T,
                             human_readable: T,
                             classify: T,
@@ -78,10 +82,10 @@ impl LsInput {
     }
 }
 
-pub(crate) struct Ls;
+pub struct Ls;
 
 impl Ls {
-    pub(crate) fn parse(input: &LsInput, content: &str) -> Resul<Vec<LsEntry>> {
+    pub fn parse(input: &LsInput, content: &str) -> Resul<Vec<LsEntry>> {
         content.split('\n')
             .skip(1)// skip "total .."
             .filter(|s| !s.is_empty())
@@ -91,10 +95,10 @@ impl Ls {
     }
 }
 
-pub(crate) struct LsApp {}
+pub struct LsApp {}
 
 impl LsApp {
-    pub(crate) async fn run_parse(input: LsInput, system: &System) -> Resul<Vec<LsEntry>> {
+    pub async fn run_parse(input: LsInput, system: &System) -> Resul<Vec<LsEntry>> {
         let mut arguments = vec![];
 
         if input.all == Some(true) { arguments.push("-a") }
@@ -114,7 +118,7 @@ impl LsApp {
 
 #[derive(Clone)]
 #[derive(Default)]
-pub(crate) struct LsBuilder {}
+pub struct LsBuilder {}
 
 impl LsBuilder {
     fn path() -> &'static str { "/bin/ls" }
@@ -140,7 +144,7 @@ impl AppBuilder for LsBuilder {
 
     const NAME: &'static str = "ls";
     const DESCRIPTION: &'static str = "Use ls to list directory and files.";
-    const SUPPORTED_OS: &'static [Os] = &[Os::LinuxAny];
+    const SUPPORTED_OS: &'static [Os] = &[Os::PosixAny];
 
     fn examples(&self) -> &[AppExample] {
         lazy_static! {
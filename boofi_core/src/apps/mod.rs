@@ -0,0 +1,312 @@
+pub mod ls;
+pub mod wget;
+pub mod sh;
+pub mod touch;
+pub mod uname;
+pub mod facts;
+pub mod systemctl;
+pub mod find;
+pub mod journalctl;
+pub mod ip;
+pub mod sockets;
+pub mod tar;
+pub mod rsync;
+pub mod plugin;
+
+pub use crate::apps::facts::FactsBuilder;
+pub use crate::apps::find::FindBuilder;
+pub use crate::apps::ip::IpBuilder;
+pub use crate::apps::journalctl::JournalctlBuilder;
+pub use crate::apps::ls::LsBuilder;
+pub use crate::apps::plugin::PluginBuilder;
+pub use crate::apps::rsync::RsyncBuilder;
+pub use crate::apps::sh::ShBuilder;
+pub use crate::apps::sockets::SocketsBuilder;
+pub use crate::apps::systemctl::SystemctlBuilder;
+pub use crate::apps::tar::TarBuilder;
+pub use crate::apps::touch::TouchBuilder;
+pub use crate::apps::uname::UnameBuilder;
+pub use crate::apps::wget::WgetBuilder;
+
+use crate::error::{Erro, Resul};
+use crate::system::os::Os;
+use crate::system::System;
+use async_trait::async_trait;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{to_value, Value};
+use tokio::sync::watch;
+use crate::description::{Description, DescriptionField, Documentation};
+use crate::batch::{BatchError, Undo};
+
+/// Add `crate::apps::prelude::*` to your app. It provides all basic dependencies to make a new app.
+pub mod prelude {
+    pub(crate) use crate::utils::{app_metadata, count};
+    pub use super::{AppExample, AppBuilder, App};
+    pub use lazy_static::lazy_static;
+    pub use serde::{Deserialize, Serialize, Deserializer};
+    pub use async_trait::async_trait;
+    pub use crate::error::*;
+    pub use crate::system::os::*;
+    pub use crate::description::*;
+    pub use super::{Progress, ProgressSender};
+}
+
+pub type Serializable = Box<dyn erased_serde::Serialize + Send + Sync>;
+
+/// Latest progress of a running app, the receiver only sees the newest value
+pub type ProgressSender = watch::Sender<Progress>;
+
+/// Progress reported by long running apps
+/// percent:    0 to 100 if the total amount of work is known
+/// message:    current step like downloaded bytes
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Progress {
+    pub percent: Option<usize>,
+    pub message: Option<String>,
+}
+
+impl Progress {
+    pub fn message(message: String) -> Self {
+        Self {
+            percent: None,
+            message: Some(message),
+        }
+    }
+}
+
+/// All related app information in one struct.
+/// Used for end user documentation
+#[derive(Serialize)]
+pub struct AppHelp<'a> {
+    name: &'a str,
+    description: &'a str,
+    compatible: bool,
+    input: Documentation,
+    output: Documentation,
+    supported_os: &'a [Os],
+    examples: &'a [AppExample],
+}
+
+impl AppHelp<'_> {
+    /// Renders in and output as json schema
+    pub fn into_schema(self) -> Self {
+        Self {
+            input: self.input.into_schema(),
+            output: self.output.into_schema(),
+            ..self
+        }
+    }
+}
+
+/// An app example usage
+/// Helpful for end user
+#[derive(Serialize)]
+pub struct AppExample {
+    description: &'static str,
+    input: Serializable,
+    output: Serializable,
+}
+
+impl AppExample {
+    pub fn new(description: &'static str, input: Serializable, output: Serializable) -> Self {
+        Self {
+            description,
+            input,
+            output,
+        }
+    }
+}
+
+#[async_trait]
+pub trait App: Send + Sync {
+    type Output: Serialize + Description;
+    type Input: Description;
+
+    fn new() -> Self;
+
+    /// The actual `run` call. It will be called mostly once per instance.
+    async fn run<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System) -> Resul<Self::Output>;
+
+    /// Like `run` but reports progress while running.
+    /// Apps which can measure their work override it, all others just run.
+    async fn run_with_progress<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System, _progress: &ProgressSender) -> Resul<Self::Output> {
+        self.run(input, system).await
+    }
+
+    /// Apps with an inverse operation set it and implement `rollback`
+    const REVERSIBLE: bool = false;
+
+    /// Undoes the finished `run` of this instance, e.g. `touch` removes the file it created.
+    async fn rollback(&mut self, _system: &System) -> Resul<()> {
+        Err(BatchError::RollbackUnsupported.into())
+    }
+
+    fn input_meta() -> &'static DescriptionField {
+        Self::Input::field()
+    }
+
+    fn output_meta() -> &'static DescriptionField {
+        Self::Output::field()
+    }
+}
+
+#[async_trait]
+impl<A: App + 'static> Undo for A {
+    async fn undo(&mut self, system: &System) -> Resul<()> {
+        self.rollback(system).await
+    }
+}
+
+pub trait AppBuilder {
+    type App: App;
+
+    const NAME: &'static str;
+    const DESCRIPTION: &'static str;
+    const SUPPORTED_OS: &'static [Os];
+
+    /// Describes all input parameters with name, type, optional and default value.
+    /// Use `doc_` macros to produce common structure.
+    fn input(&self) -> &'static DescriptionField {
+        Self::App::input_meta()
+    }
+
+    /// Expected output definition with name and type.
+    /// Use `doc_` macros to produce common structure.
+    fn output(&self) -> &'static DescriptionField {
+        Self::App::output_meta()
+    }
+
+    /// One ore more examples with `description`, `input` and `output`.
+    fn examples(&self) -> &[AppExample] {
+        &[]
+    }
+
+    /// Summary of all related information
+    fn help(&self, os: &Os) -> AppHelp {
+        AppHelp {
+            name: Self::NAME,
+            description: Self::DESCRIPTION,
+            supported_os: Self::SUPPORTED_OS,
+            input: Documentation::Description(self.input()),
+            output: Documentation::Description(self.output()),
+            examples: self.examples(),
+            compatible: self.compatible(os),
+        }
+    }
+
+    /// Returns compatibility with the target `os`.
+    fn compatible(&self, os: &Os) -> bool {
+        Self::SUPPORTED_OS
+            .iter()
+            .any(|o| o.compatible(os))
+    }
+
+    fn new_app(&self) -> Self::App {
+        Self::App::new()
+    }
+}
+
+macro_rules! app_builders {
+    ($(
+        $typ:tt
+    ),*
+    ) => {
+        /// Built-in apps and plugins loaded from manifests
+        #[derive(Clone)]
+        pub enum AppBuilders {
+            $(
+                $typ($typ),
+            )*
+            Plugin(PluginBuilder),
+        }
+
+        impl AppBuilders {
+            pub fn name(&self) -> &str {
+                match self {
+                    $( Self::$typ(_)  => $typ::NAME, )*
+                    Self::Plugin(i) => i.name(),
+                }
+            }
+
+            pub fn help(&self, os: &Os) -> AppHelp {
+                match self {
+                    $( Self::$typ(i)  => i.help(os), )*
+                    Self::Plugin(i) => i.help(os),
+                }
+            }
+
+            pub fn compatible(&self, os: &Os) -> bool {
+                match self {
+                    $( Self::$typ(i)  => i.compatible(os), )*
+                    Self::Plugin(i) => i.compatible(os),
+                }
+            }
+
+            /// Checks the input against the description, plugins only document theirs as schema
+            pub fn validate(&self, input: &Value) -> Resul<()> {
+                let invalid = match self {
+                    $( Self::$typ(i)  => i.input().validate(input), )*
+                    Self::Plugin(_) => vec![],
+                };
+
+                if invalid.is_empty() { Ok(()) } else { Err(Erro::InputInvalid(invalid)) }
+            }
+
+            pub async fn run<'de, I: Deserializer<'de> + Send + Sync>(&mut self, input: I, system: &System) -> Resul<Box<dyn erased_serde::Serialize + Send>> {
+                match self {
+                    $(
+                    Self::$typ(i)  => {
+                        Ok(i.new_app().run(input, system).await.map(Box::new)?)
+                    },
+                    )*
+                    Self::Plugin(i) => Ok(Box::new(i.run(input, system).await?)),
+                }
+            }
+
+            /// Like `run` but keeps reversible apps to undo the run, plugins can't be undone
+            pub async fn run_undoable(&mut self, input: Value, system: &System) -> Resul<(Value, Option<Box<dyn Undo>>)> {
+                match self {
+                    $(
+                    Self::$typ(i)  => {
+                        let mut app = i.new_app();
+                        let output = to_value(app.run(input, system).await?)?;
+                        let undo: Option<Box<dyn Undo>> = if <$typ as AppBuilder>::App::REVERSIBLE { Some(Box::new(app)) } else { None };
+                        Ok((output, undo))
+                    },
+                    )*
+                    Self::Plugin(i) => Ok((to_value(i.run(input, system).await?)?, None)),
+                }
+            }
+
+            pub async fn run_with_progress<'de, I: Deserializer<'de> + Send + Sync>(&mut self, input: I, system: &System, progress: &ProgressSender) -> Resul<Box<dyn erased_serde::Serialize + Send>> {
+                match self {
+                    $(
+                    Self::$typ(i)  => {
+                        Ok(i.new_app().run_with_progress(input, system, progress).await.map(Box::new)?)
+                    },
+                    )*
+                    Self::Plugin(i) => Ok(Box::new(i.run(input, system).await?)),
+                }
+            }
+        }
+    }
+}
+
+app_builders!(
+    LsBuilder,
+    ShBuilder,
+    TouchBuilder,
+    UnameBuilder,
+    FactsBuilder,
+    WgetBuilder,
+    SystemctlBuilder,
+    FindBuilder,
+    JournalctlBuilder,
+    IpBuilder,
+    SocketsBuilder,
+    TarBuilder,
+    RsyncBuilder
+);
+
+
+
@@ -0,0 +1,269 @@
+use std::path::Path;
+use std::sync::Arc;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+use crate::apps::AppHelp;
+use crate::description::Documentation;
+use crate::error::{Erro, Resul};
+use crate::secret;
+use crate::system::os::Os;
+use crate::system::System;
+use crate::utils::{manifest_paths, regex_captures};
+
+/// Plugins run on every posix system, the executable has to exist on the target
+const PLUGIN_OS: &[Os] = &[Os::PosixAny];
+
+lazy_static! {
+    static ref PLACEHOLDER: Regex = Regex::new(r"\{([A-Za-z_][A-Za-z0-9_]*)}").unwrap();
+}
+
+/// How the standard output of a plugin is turned into json
+/// text:   whole output as string
+/// lines:  array of non-empty lines
+/// json:   output is parsed as json
+/// regex:  array of objects with the named groups of every matching line
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "parser", rename_all = "snake_case")]
+pub enum PluginOutput {
+    #[default]
+    Text,
+    Lines,
+    Json,
+    Regex {
+        #[serde(with = "serde_regex")]
+        pattern: Regex,
+    },
+}
+
+impl PluginOutput {
+    fn parse(&self, stdout: &[u8]) -> Resul<Value> {
+        let text = String::from_utf8_lossy(stdout);
+
+        Ok(match self {
+            PluginOutput::Text => Value::String(text.into_owned()),
+            PluginOutput::Lines => text.lines().filter(|l| !l.is_empty()).map(|l| Value::String(l.into())).collect(),
+            PluginOutput::Json => serde_json::from_slice(stdout)?,
+            PluginOutput::Regex { pattern } => Value::Array(regex_captures(pattern, &text)),
+        })
+    }
+
+    /// Schema of the output if the manifest doesn't provide one
+    fn schema(&self) -> Value {
+        match self {
+            PluginOutput::Text => json!({"type": "string"}),
+            PluginOutput::Lines => json!({"type": "array", "items": {"type": "string"}}),
+            PluginOutput::Json => json!({}),
+            PluginOutput::Regex { pattern } => json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": pattern.capture_names().flatten()
+                        .map(|name| (name.to_string(), json!({"type": ["string", "null"]})))
+                        .collect::<Map<String, Value>>(),
+                },
+            }),
+        }
+    }
+}
+
+/// App manifest loaded from the plugin directory
+/// name:           unique app name, must not collide with a built-in app
+/// bin:            absolute path of the executable on the target system
+/// arguments:      `{field}` is replaced by the input field, an argument only containing
+///                 a missing field is left out and an array expands to multiple arguments
+/// input:          json schema of the input shown in the help
+/// output:         parser of the standard output with an optional json schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    name: String,
+    description: String,
+    bin: String,
+    #[serde(default)]
+    arguments: Vec<String>,
+    #[serde(default)]
+    input: Option<Value>,
+    #[serde(default)]
+    output: PluginOutputManifest,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginOutputManifest {
+    #[serde(flatten)]
+    parser: PluginOutput,
+    #[serde(default)]
+    schema: Option<Value>,
+}
+
+/// Runs an external executable described by a manifest
+#[derive(Clone)]
+pub struct PluginBuilder {
+    manifest: Arc<PluginManifest>,
+}
+
+impl PluginBuilder {
+    pub fn new(manifest: PluginManifest) -> Self {
+        Self {
+            manifest: Arc::new(manifest),
+        }
+    }
+
+    /// Loads all `.yaml`, `.yml` and `.json` manifests of a directory
+    pub async fn load_dir(path: &str) -> Resul<Vec<Self>> {
+        let mut plugins = vec![];
+
+        for path in manifest_paths(path).await? {
+            plugins.push(Self::load(&path).await?);
+        }
+
+        plugins.sort_by(|a: &Self, b| a.manifest.name.cmp(&b.manifest.name));
+        Ok(plugins)
+    }
+
+    async fn load(path: &Path) -> Resul<Self> {
+        tracing::debug!("[PLUGIN] loading manifest {}", path.display());
+        let manifest = serde_yaml::from_slice::<PluginManifest>(&tokio::fs::read(path).await?)
+            .map_err(|e| PluginError::InvalidManifest(path.display().to_string(), e.to_string()))?;
+
+        if !manifest.bin.starts_with('/') {
+            return Err(PluginError::RelativeBin(manifest.name).into());
+        }
+
+        Ok(Self::new(manifest))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    pub fn help(&self, os: &Os) -> AppHelp<'_> {
+        AppHelp {
+            name: &self.manifest.name,
+            description: &self.manifest.description,
+            compatible: self.compatible(os),
+            input: Documentation::Schema(self.manifest.input.clone().unwrap_or_else(|| json!({"type": "object"}))),
+            output: Documentation::Schema(self.manifest.output.schema.clone().unwrap_or_else(|| self.manifest.output.parser.schema())),
+            supported_os: PLUGIN_OS,
+            examples: &[],
+        }
+    }
+
+    pub fn compatible(&self, os: &Os) -> bool {
+        PLUGIN_OS.iter().any(|o| o.compatible(os))
+    }
+
+    /// Replaces the placeholders of the manifest arguments with the input fields
+    fn arguments(&self, input: &Map<String, Value>) -> Resul<Vec<String>> {
+        let mut arguments = vec![];
+
+        for argument in self.manifest.arguments.iter() {
+            if let Some(captures) = PLACEHOLDER.captures(argument).filter(|c| c[0].len() == argument.len()) {
+                match input.get(&captures[1]) {
+                    None | Some(Value::Null) => {}
+                    Some(Value::Array(values)) => for value in values {
+                        arguments.push(Self::scalar(&captures[1], value)?);
+                    },
+                    Some(value) => arguments.push(Self::scalar(&captures[1], value)?),
+                }
+                continue;
+            }
+
+            let mut replaced = String::new();
+            let mut last = 0;
+            for captures in PLACEHOLDER.captures_iter(argument) {
+                let whole = captures.get(0).unwrap();
+                let value = input.get(&captures[1])
+                    .filter(|v| !v.is_null())
+                    .ok_or_else(|| PluginError::InputMissing(captures[1].to_string()))?;
+
+                replaced.push_str(&argument[last..whole.start()]);
+                replaced.push_str(&Self::scalar(&captures[1], value)?);
+                last = whole.end();
+            }
+            replaced.push_str(&argument[last..]);
+            arguments.push(replaced);
+        }
+
+        Ok(arguments)
+    }
+
+    fn scalar(name: &str, value: &Value) -> Resul<String> {
+        match value {
+            Value::String(s) => Ok(s.clone()),
+            Value::Number(n) => Ok(n.to_string()),
+            Value::Bool(b) => Ok(b.to_string()),
+            _ => Err(PluginError::InputInvalid(name.to_string()).into()),
+        }
+    }
+
+    pub async fn run<'de, I: Deserializer<'de> + Send>(&self, input: I, system: &System) -> Resul<Value> {
+        let input = match Value::deserialize(input).map_err(Erro::from_deserialize)? {
+            Value::Object(fields) => fields,
+            Value::Null => Map::new(),
+            _ => return Err(PluginError::InputInvalid(self.manifest.name.clone()).into()),
+        };
+
+        let arguments = self.arguments(&input)?;
+        tracing::debug!("[PLUGIN] running {}", secret::command_line(&self.manifest.bin, &arguments));
+        self.manifest.output.parser.parse(&system.run_args(&self.manifest.bin, &arguments).await?)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("plugin manifest {0} is invalid: {1}")]
+    InvalidManifest(String, String),
+    #[error("plugin {0} needs an absolute executable path")]
+    RelativeBin(String),
+    #[error("plugin {0} has the same name as another app")]
+    DuplicateName(String),
+    #[error("input field {0} is missing")]
+    InputMissing(String),
+    #[error("input field {0} has to be a string, number or boolean")]
+    InputInvalid(String),
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Map, Value};
+    use crate::apps::plugin::{PluginBuilder, PluginManifest};
+
+    fn plugin() -> PluginBuilder {
+        PluginBuilder::new(serde_yaml::from_str::<PluginManifest>(r#"
+name: df
+description: report file system disk space usage
+bin: /bin/df
+arguments: ["--output=source,target", "--type={type}", "{paths}"]
+output:
+  parser: regex
+  pattern: '^(?P<source>/\S+)\s+(?P<target>\S+)$'
+"#).unwrap())
+    }
+
+    fn input(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn arguments() {
+        let plugin = plugin();
+        assert_eq!(plugin.arguments(&input(json!({"type": "ext4", "paths": ["/", "/home"]}))).unwrap(),
+                   vec!["--output=source,target", "--type=ext4", "/", "/home"]);
+        assert_eq!(plugin.arguments(&input(json!({"type": "ext4"}))).unwrap(),
+                   vec!["--output=source,target", "--type=ext4"]);
+        assert!(plugin.arguments(&input(json!({"paths": "/"}))).is_err());
+        assert!(plugin.arguments(&input(json!({"type": {}}))).is_err());
+    }
+
+    #[test]
+    fn parse() {
+        let plugin = plugin();
+        assert_eq!(plugin.manifest.output.parser.parse(b"Filesystem Mounted on\n/dev/sda1 /\n/dev/sda2 /home\n").unwrap(), json!([
+            {"source": "/dev/sda1", "target": "/"},
+            {"source": "/dev/sda2", "target": "/home"},
+        ]));
+        assert!(plugin.help(&crate::system::os::Os::LinuxAny).compatible);
+    }
+}
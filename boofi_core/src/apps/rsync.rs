@@ -0,0 +1,216 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::apps::prelude::*;
+use thiserror::Error;
+use crate::system::System;
+
+/// source/destination:    local paths or remote ones like `user@host:/path`
+/// delete:     remove files from the destination which are missing in the source
+/// dry_run:    only report what would be transferred
+/// excludes:   patterns passed to `--exclude`
+#[derive(Serialize, Deserialize, Description)]
+pub struct RsyncInput {
+    source: String,
+    destination: String,
+    delete: Option<bool>,
+    dry_run: Option<bool>,
+    excludes: Option<Vec<String>>,
+}
+
+impl RsyncInput {
+    fn arguments(&self) -> Vec<String> {
+        let mut arguments = vec![
+            "--archive".into(),
+            "--stats".into(),
+            "--itemize-changes".into(),
+        ];
+
+        if let Some(true) = self.delete { arguments.push("--delete".into()) }
+        if let Some(true) = self.dry_run { arguments.push("--dry-run".into()) }
+
+        for exclude in self.excludes.iter().flatten() {
+            arguments.push(format!("--exclude={}", exclude));
+        }
+
+        arguments.push("--".into());
+        arguments.push(self.source.clone());
+        arguments.push(self.destination.clone());
+
+        arguments
+    }
+}
+
+/// sizes in bytes
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Description)]
+pub struct RsyncStats {
+    files: usize,
+    created_files: usize,
+    deleted_files: usize,
+    transferred_files: usize,
+    total_size: usize,
+    transferred_size: usize,
+    sent: usize,
+    received: usize,
+}
+
+/// changes:    itemized changes like `>f+++++++++ file.txt` or `*deleting   old.txt`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct RsyncOutput {
+    changes: Vec<String>,
+    stats: RsyncStats,
+}
+
+impl RsyncOutput {
+    /// `rsync --stats --itemize-changes` output
+    pub fn parse(content: &str) -> Resul<Self> {
+        lazy_static! {
+            static ref CHANGE: Regex = Regex::new(r"^(?:[<>ch.*][fdLDS][^ ]{9}|\*deleting) +.+$").unwrap();
+            static ref STAT: Regex = Regex::new(r"^([A-Za-z ]+): ([\d,]+)").unwrap();
+        }
+
+        let mut stats = RsyncStats::default();
+        let mut found = false;
+
+        for c in content.lines().filter_map(|l| STAT.captures(l)) {
+            let value = c[2].replace(',', "").parse()?;
+            let field = match &c[1] {
+                "Number of files" => &mut stats.files,
+                "Number of created files" => &mut stats.created_files,
+                "Number of deleted files" => &mut stats.deleted_files,
+                "Number of regular files transferred" => &mut stats.transferred_files,
+                "Total file size" => &mut stats.total_size,
+                "Total transferred file size" => &mut stats.transferred_size,
+                "Total bytes sent" => &mut stats.sent,
+                "Total bytes received" => &mut stats.received,
+                _ => continue,
+            };
+
+            *field = value;
+            found = true;
+        }
+
+        if !found {
+            return Err(RsyncError::StatsMissing.into());
+        }
+
+        Ok(Self {
+            changes: content.lines()
+                .filter(|l| CHANGE.is_match(l))
+                .map(|l| l.into())
+                .collect(),
+            stats,
+        })
+    }
+}
+
+pub struct Rsync;
+
+impl Rsync {
+    fn rsync() -> &'static str { "/usr/bin/rsync" }
+
+    pub async fn run_parse(input: RsyncInput, system: &System) -> Resul<RsyncOutput> {
+        let o = system.run_args(Self::rsync(), &input.arguments()).await?;
+        RsyncOutput::parse(&String::from_utf8(o)?)
+    }
+}
+
+#[async_trait]
+impl App for Rsync {
+    type Output = RsyncOutput;
+    type Input = RsyncInput;
+
+    fn new() -> Self {
+        Self {}
+    }
+
+    async fn run<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System) -> Resul<Self::Output> {
+        let i = RsyncInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        Rsync::run_parse(i, system).await
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RsyncBuilder {}
+
+impl AppBuilder for RsyncBuilder {
+    app_metadata!(
+        Rsync,
+        "rsync",
+        "Synchronize files in archive mode between local and remote paths with transfer statistics.",
+        &[Os::LinuxAny],
+        AppExample::new("Preview mirroring a web root",
+            Box::new(RsyncInput {
+                source: "/srv/www/".into(),
+                destination: "backup@192.168.1.20:/backup/www/".into(),
+                delete: Some(true),
+                dry_run: Some(true),
+                excludes: Some(vec!["*.log".into()]),
+            }),
+            Box::new(RsyncOutput {
+                changes: vec!["<f.st...... index.html".into(), "*deleting   old.html".into()],
+                stats: RsyncStats {
+                    files: 12,
+                    created_files: 0,
+                    deleted_files: 1,
+                    transferred_files: 1,
+                    total_size: 40960,
+                    transferred_size: 2048,
+                    sent: 512,
+                    received: 30,
+                },
+            })
+        )
+    );
+}
+
+#[derive(Debug, Error)]
+pub enum RsyncError {
+    #[error("rsync statistics missing in output")]
+    StatsMissing,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::apps::rsync::{RsyncInput, RsyncOutput, RsyncStats};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_arguments() {
+        assert_eq!(RsyncInput {
+            source: "/srv/www/".into(),
+            destination: "/backup/www/".into(),
+            delete: Some(true),
+            dry_run: None,
+            excludes: Some(vec!["*.log".into(), "cache/".into()]),
+        }.arguments(), [
+            "--archive", "--stats", "--itemize-changes", "--delete", "--exclude=*.log", "--exclude=cache/",
+            "--", "/srv/www/", "/backup/www/"
+        ]);
+    }
+
+    #[test]
+    fn test_parse() {
+        let output = RsyncOutput::parse(&read_test_resources("rsync")).unwrap();
+
+        assert_eq!(output, RsyncOutput {
+            changes: vec![
+                "*deleting   old.html".into(),
+                "cd+++++++++ assets/".into(),
+                ">f+++++++++ assets/app.js".into(),
+                ">f.st...... index.html".into(),
+            ],
+            stats: RsyncStats {
+                files: 12,
+                created_files: 2,
+                deleted_files: 1,
+                transferred_files: 2,
+                total_size: 1234567,
+                transferred_size: 5120,
+                sent: 5837,
+                received: 96,
+            },
+        });
+
+        assert!(RsyncOutput::parse("sending incremental file list").is_err());
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use regex::Regex;
+use thiserror::Error;
+use crate::apps::prelude::*;
+use crate::system::{RunOutput, System};
+
+/// Restricts the commands of the `sh` app, configured per service
+/// enabled:    the app is hidden and not found if false
+/// allow:      every command of a list like `a && b | c` must match one of these, anything if empty
+/// deny:       the whole command and none of its commands may match one of these
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellPolicy {
+    #[serde(default = "ShellPolicy::default_enabled")]
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<CommandPattern>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<CommandPattern>,
+}
+
+impl ShellPolicy {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    /// Checked before the command runs.
+    /// Commands are split on `;`, `&`, `|` and new lines without respecting quotes, which only splits more than the shell.
+    /// Substitutions like `$(...)` can hide any command and redirections like `> file` write any file,
+    /// both are rejected if an allowlist is set.
+    pub fn check(&self, command: &str) -> Result<(), ShError> {
+        let commands: Vec<&str> = command.split([';', '&', '|', '\n'])
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .collect();
+
+        for pattern in self.deny.iter() {
+            if pattern.r#match(command.trim()) || commands.iter().any(|c| pattern.r#match(c)) {
+                return Err(ShError::Denied(pattern.to_string()));
+            }
+        }
+
+        if self.allow.is_empty() {
+            return Ok(());
+        }
+
+        if ["$(", "`", "<(", ">("].iter().any(|s| command.contains(s)) {
+            return Err(ShError::Substitution);
+        }
+
+        if command.contains(['>', '<']) {
+            return Err(ShError::Redirection);
+        }
+
+        match commands.into_iter().find(|c| !self.allow.iter().any(|p| p.r#match(c))) {
+            Some(command) => Err(ShError::NotAllowed(command.into())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for ShellPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            allow: vec![],
+            deny: vec![],
+        }
+    }
+}
+
+/// prefix: leading words of the command like `systemctl status`
+/// regex:  matched anywhere in the command, use `^` and `$` to match all of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandPattern {
+    Prefix(String),
+    Regex(#[serde(with = "serde_regex")] Regex),
+}
+
+impl CommandPattern {
+    fn r#match(&self, command: &str) -> bool {
+        match self {
+            CommandPattern::Prefix(prefix) => {
+                let mut words = command.split_whitespace();
+                prefix.split_whitespace().all(|p| words.next() == Some(p))
+            }
+            CommandPattern::Regex(regex) => regex.is_match(command),
+        }
+    }
+}
+
+impl Display for CommandPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandPattern::Prefix(prefix) => write!(f, "prefix '{}'", prefix),
+            CommandPattern::Regex(regex) => write!(f, "regex '{}'", regex.as_str()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct ShInput {
+    command: String,
+}
+
+impl From<ShInput> for Vec<String> {
+    fn from(value: ShInput) -> Self {
+        vec!["-c".into(), value.command]
+    }
+}
+
+pub struct Sh {
+    policy: Arc<ShellPolicy>,
+}
+
+#[async_trait]
+impl App for Sh {
+    type Output = RunOutput;
+    type Input = ShInput;
+
+    fn new() -> Self {
+        Self {
+            policy: Default::default(),
+        }
+    }
+
+    async fn run<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System) -> Resul<Self::Output> {
+        let input = ShInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        let command = input.command.clone();
+        self.policy.check(&command)?;
+        let args: Vec<String> = input.into();
+
+        let result = system.run_output("/bin/sh", args.as_slice()).await;
+        if let Some(session) = system.shell_session() {
+            session.record(&command, &result);
+        }
+        result
+    }
+}
+
+#[derive(Clone)]
+#[derive(Default)]
+pub struct ShBuilder {
+    policy: Arc<ShellPolicy>,
+}
+
+impl ShBuilder {
+    pub fn new(policy: ShellPolicy) -> Self {
+        Self {
+            policy: Arc::new(policy),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.policy.enabled
+    }
+}
+
+impl AppBuilder for ShBuilder {
+    app_metadata!(
+        Sh,
+        "sh",
+        "Shell, returns stdout, stderr, exit code and duration in milliseconds. A non-zero exit code is no error.",
+        &[Os::PosixAny],
+        AppExample::new("Run command",
+            Box::new(ShInput {
+                command: "whoami".into()
+            }),
+            Box::new(RunOutput::new("root\n".into(), "".into(), 0, std::time::Duration::from_millis(3)))
+        )
+    );
+
+    fn new_app(&self) -> Self::App {
+        Sh {
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ShError {
+    #[error("command denied by {0}")]
+    Denied(String),
+    #[error("command '{0}' is not allowed")]
+    NotAllowed(String),
+    #[error("command substitution is not allowed with an allowlist")]
+    Substitution,
+    #[error("redirection is not allowed with an allowlist")]
+    Redirection,
+}
+
+
+#[cfg(test)]
+mod test {
+    use serde_json::to_value;
+    use regex::Regex;
+    use crate::apps::{App, AppBuilder};
+    use crate::apps::sh::{CommandPattern, Sh, ShBuilder, ShError, ShInput, ShellPolicy};
+    use crate::error::Erro;
+    use crate::utils::test::system_user;
+
+    #[tokio::test]
+    async fn test_run() {
+        let mut sh = Sh::new();
+
+        let result = sh.run(to_value(ShInput {
+            command: "echo test".into(),
+        }).unwrap(), &system_user().await).await.unwrap();
+
+        assert_eq!(result.stdout(), "test\n");
+        assert_eq!(result.exit_code(), 0);
+    }
+
+    #[test]
+    fn policy() {
+        let policy = ShellPolicy {
+            enabled: true,
+            allow: vec![
+                CommandPattern::Prefix("systemctl status".into()),
+                CommandPattern::Prefix("grep".into()),
+                CommandPattern::Regex(Regex::new("^ls( -[al]+)?$").unwrap()),
+            ],
+            deny: vec![CommandPattern::Regex(Regex::new(r"rm\s+-rf\s+/").unwrap())],
+        };
+
+        assert!(policy.check("systemctl status nginx | grep active").is_ok());
+        assert!(policy.check("ls -la").is_ok());
+        assert!(matches!(policy.check("systemctl stop nginx"), Err(ShError::NotAllowed(c)) if c == "systemctl stop nginx"));
+        assert!(matches!(policy.check("ls; whoami"), Err(ShError::NotAllowed(c)) if c == "whoami"));
+        assert!(matches!(policy.check("ls && rm -rf /"), Err(ShError::Denied(_))));
+        assert!(matches!(policy.check("grep $(cat /etc/shadow)"), Err(ShError::Substitution)));
+        assert!(matches!(policy.check("lsblk"), Err(ShError::NotAllowed(_))));
+        assert!(matches!(policy.check("grep x `whoami`"), Err(ShError::Substitution)));
+        assert!(matches!(policy.check("grep x <(cat /etc/shadow)"), Err(ShError::Substitution)));
+        assert!(matches!(policy.check("systemctl status > /etc/sudoers.d/x"), Err(ShError::Redirection)));
+        assert!(matches!(policy.check("systemctl status >> /etc/sudoers.d/x"), Err(ShError::Redirection)));
+        assert!(matches!(policy.check("grep root < /etc/shadow"), Err(ShError::Redirection)));
+        assert!(matches!(policy.check("ls -la 2>/dev/null"), Err(ShError::Redirection)));
+
+        let policy = ShellPolicy {
+            deny: vec![CommandPattern::Prefix("rm".into())],
+            ..Default::default()
+        };
+        assert!(policy.check("echo $(whoami)").is_ok());
+        assert!(policy.check("echo a\n rm -f file").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_denied() {
+        let builder = ShBuilder::new(serde_yaml::from_str("deny: [!prefix echo]").unwrap());
+
+        let result = builder.new_app().run(to_value(ShInput {
+            command: "echo test".into(),
+        }).unwrap(), &system_user().await).await;
+
+        assert!(matches!(result, Err(Erro::Sh(ShError::Denied(_)))));
+    }
+}
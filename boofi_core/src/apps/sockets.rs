@@ -0,0 +1,210 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::apps::prelude::*;
+use thiserror::Error;
+use crate::system::System;
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct SocketsInput {
+    #[desc(description = "include established and other non listening sockets")]
+    all: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct SocketProcess {
+    name: String,
+    pid: usize,
+    fd: usize,
+}
+
+/// port:   `None` for any port like `*`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct SocketAddress {
+    address: String,
+    port: Option<usize>,
+}
+
+impl SocketAddress {
+    /// `0.0.0.0:22`, `[::]:22`, `127.0.0.53%lo:53` or `*:*`
+    fn parse(value: &str) -> Result<Self, SocketsError> {
+        let (address, port) = value.rsplit_once(':').ok_or(SocketsError::InvalidAddress(value.into()))?;
+
+        Ok(Self {
+            address: address.trim_start_matches('[').trim_end_matches(']').into(),
+            port: match port {
+                "*" => None,
+                p => Some(p.parse().map_err(|_| SocketsError::InvalidAddress(value.into()))?),
+            },
+        })
+    }
+}
+
+/// processes:  owning processes, only visible for sockets of the same user or with sudo
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct Socket {
+    #[desc(description = "tcp or udp")]
+    protocol: String,
+    state: String,
+    receive_queue: usize,
+    send_queue: usize,
+    local: SocketAddress,
+    peer: SocketAddress,
+    processes: Vec<SocketProcess>,
+    uid: Option<usize>,
+}
+
+impl Socket {
+    /// `ss -tupen` output, the header line is skipped
+    pub fn parse(content: &str) -> Resul<Vec<Self>> {
+        lazy_static! {
+            static ref PROCESS: Regex = Regex::new(r#"\("([^"]*)",pid=(\d+),fd=(\d+)\)"#).unwrap();
+            static ref UID: Regex = Regex::new(r"(?:^|\s)uid:(\d+)").unwrap();
+        }
+
+        content.lines()
+            .filter(|l| !l.trim().is_empty() && !l.starts_with("Netid"))
+            .map(|l| {
+                // process names may contain spaces, so only the first six columns are split
+                let mut rest = l;
+                let mut column = || {
+                    let trimmed = rest.trim_start();
+                    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+                    rest = &trimmed[end..];
+                    (!trimmed.is_empty()).then_some(&trimmed[..end]).ok_or(SocketsError::InvalidLine(l.into()))
+                };
+
+                let protocol = column()?;
+                let state = column()?;
+                let receive_queue = column()?.parse()?;
+                let send_queue = column()?.parse()?;
+                let local = SocketAddress::parse(column()?)?;
+                let peer = SocketAddress::parse(column()?)?;
+
+                Ok(Self {
+                    protocol: protocol.into(),
+                    state: state.into(),
+                    receive_queue,
+                    send_queue,
+                    local,
+                    peer,
+                    processes: PROCESS.captures_iter(rest)
+                        .map(|c| Ok(SocketProcess {
+                            name: c[1].into(),
+                            pid: c[2].parse()?,
+                            fd: c[3].parse()?,
+                        }))
+                        .collect::<Resul<_>>()?,
+                    uid: UID.captures(rest).map(|c| c[1].parse()).transpose()?,
+                })
+            })
+            .collect()
+    }
+}
+
+pub struct Sockets;
+
+impl Sockets {
+    fn ss() -> &'static str { "/bin/ss" }
+
+    pub async fn run_parse(input: SocketsInput, system: &System) -> Resul<Vec<Socket>> {
+        let options = if input.all == Some(true) { "-tuapen" } else { "-tulpen" };
+        let o = system.run_args(Self::ss(), &[options]).await?;
+        Socket::parse(&String::from_utf8(o)?)
+    }
+}
+
+#[async_trait]
+impl App for Sockets {
+    type Output = Vec<Socket>;
+    type Input = SocketsInput;
+
+    fn new() -> Self {
+        Self {}
+    }
+
+    async fn run<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System) -> Resul<Self::Output> {
+        let i = SocketsInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        Sockets::run_parse(i, system).await
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SocketsBuilder {}
+
+impl AppBuilder for SocketsBuilder {
+    app_metadata!(
+        Sockets,
+        "sockets",
+        "Listening or all tcp and udp sockets with their addresses, state and owning processes.",
+        &[Os::LinuxAny],
+        AppExample::new("Audit open ports",
+            Box::new(SocketsInput {
+                all: None,
+            }),
+            Box::new(vec![Socket {
+                protocol: "tcp".into(),
+                state: "LISTEN".into(),
+                receive_queue: 0,
+                send_queue: 128,
+                local: SocketAddress { address: "0.0.0.0".into(), port: Some(22) },
+                peer: SocketAddress { address: "0.0.0.0".into(), port: None },
+                processes: vec![SocketProcess { name: "sshd".into(), pid: 812, fd: 3 }],
+                uid: None,
+            }])
+        ),
+        AppExample::new("Established connections",
+            Box::new(SocketsInput {
+                all: Some(true),
+            }),
+            Box::new(vec![Socket {
+                protocol: "tcp".into(),
+                state: "ESTAB".into(),
+                receive_queue: 0,
+                send_queue: 0,
+                local: SocketAddress { address: "192.168.1.10".into(), port: Some(22) },
+                peer: SocketAddress { address: "192.168.1.20".into(), port: Some(50122) },
+                processes: vec![SocketProcess { name: "sshd".into(), pid: 1024, fd: 4 }],
+                uid: None,
+            }])
+        )
+    );
+}
+
+#[derive(Debug, Error)]
+pub enum SocketsError {
+    #[error("invalid socket line {0}")]
+    InvalidLine(String),
+    #[error("invalid socket address {0}")]
+    InvalidAddress(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::apps::sockets::{Socket, SocketAddress, SocketProcess};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let sockets = Socket::parse(&read_test_resources("ss")).unwrap();
+
+        assert_eq!(sockets.len(), 4);
+        assert_eq!(sockets[0], Socket {
+            protocol: "udp".into(),
+            state: "UNCONN".into(),
+            receive_queue: 0,
+            send_queue: 0,
+            local: SocketAddress { address: "127.0.0.53%lo".into(), port: Some(53) },
+            peer: SocketAddress { address: "0.0.0.0".into(), port: None },
+            processes: vec![SocketProcess { name: "systemd-resolve".into(), pid: 612, fd: 13 }],
+            uid: Some(101),
+        });
+        assert_eq!(sockets[2].local, SocketAddress { address: "::".into(), port: Some(22) });
+        assert_eq!(sockets[2].processes, vec![
+            SocketProcess { name: "sshd".into(), pid: 812, fd: 4 },
+            SocketProcess { name: "sshd worker".into(), pid: 900, fd: 4 },
+        ]);
+        assert_eq!(sockets[3].state, "ESTAB");
+        assert_eq!(sockets[3].processes, vec![]);
+        assert_eq!(sockets[3].uid, Some(1000));
+    }
+}
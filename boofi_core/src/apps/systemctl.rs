@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use crate::apps::prelude::*;
+use thiserror::Error;
+use crate::system::System;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemctlAction {
+    Start,
+    Stop,
+    Restart,
+    Enable,
+    Disable,
+    Status,
+    IsActive,
+}
+
+impl SystemctlAction {
+    /// systemctl command, `None` if only the unit state is requested
+    fn command(&self) -> Option<&'static str> {
+        match self {
+            SystemctlAction::Start => Some("start"),
+            SystemctlAction::Stop => Some("stop"),
+            SystemctlAction::Restart => Some("restart"),
+            SystemctlAction::Enable => Some("enable"),
+            SystemctlAction::Disable => Some("disable"),
+            SystemctlAction::Status |
+            SystemctlAction::IsActive => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct SystemctlInput {
+    action: SystemctlAction,
+    unit: String,
+}
+
+impl SystemctlInput {
+    /// arguments of the action, `None` if only the unit state is requested
+    fn command_arguments(&self) -> Option<[&str; 3]> {
+        self.action.command().map(|command| [command, "--", self.unit.as_str()])
+    }
+
+    fn show_arguments(&self) -> [&str; 4] {
+        ["show", SystemctlUnit::PROPERTIES, "--", self.unit.as_str()]
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct SystemctlUnit {
+    unit: String,
+    load_state: String,
+    active_state: String,
+    sub_state: String,
+    unit_file_state: Option<String>,
+    description: String,
+}
+
+impl SystemctlUnit {
+    const PROPERTIES: &'static str = "--property=Id,LoadState,ActiveState,SubState,UnitFileState,Description";
+
+    /// parse `systemctl show` key=value output
+    pub fn parse(content: &str) -> Resul<Self> {
+        let mut properties: HashMap<&str, String> = content.lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(k, v)| (k, v.to_string()))
+            .collect();
+
+        let mut property = |key: &'static str| {
+            properties.remove(key).ok_or(SystemctlError::PropertyMissing(key))
+        };
+
+        Ok(Self {
+            unit: property("Id")?,
+            load_state: property("LoadState")?,
+            active_state: property("ActiveState")?,
+            sub_state: property("SubState")?,
+            unit_file_state: property("UnitFileState").ok().filter(|s| !s.is_empty()),
+            description: property("Description")?,
+        })
+    }
+}
+
+pub struct Systemctl;
+
+impl Systemctl {
+    pub fn executable() -> &'static str { "/bin/systemctl" }
+
+    pub async fn run_parse(input: SystemctlInput, system: &System) -> Resul<SystemctlUnit> {
+        if let Some(arguments) = input.command_arguments() {
+            system.run_args(Self::executable(), &arguments).await?;
+        }
+
+        let o = system.run_args(Self::executable(), &input.show_arguments()).await?;
+        SystemctlUnit::parse(&String::from_utf8(o)?)
+    }
+}
+
+#[async_trait]
+impl App for Systemctl {
+    type Output = SystemctlUnit;
+    type Input = SystemctlInput;
+
+    fn new() -> Self {
+        Self {}
+    }
+
+    async fn run<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System) -> Resul<Self::Output> {
+        let i = SystemctlInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        Systemctl::run_parse(i, system).await
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SystemctlBuilder;
+
+impl AppBuilder for SystemctlBuilder {
+    app_metadata!(
+        Systemctl,
+        "systemctl",
+        "Manage systemd units. Start, stop, restart, enable or disable a unit and get its state.",
+        &[Os::LinuxAny],
+        AppExample::new("Restart a service",
+            Box::new(SystemctlInput {
+                action: SystemctlAction::Restart,
+                unit: "ssh.service".into(),
+            }),
+            Box::new(SystemctlUnit {
+                unit: "ssh.service".into(),
+                load_state: "loaded".into(),
+                active_state: "active".into(),
+                sub_state: "running".into(),
+                unit_file_state: Some("enabled".into()),
+                description: "OpenBSD Secure Shell server".into(),
+            })
+        ),
+        AppExample::new("Check if a service is active",
+            Box::new(SystemctlInput {
+                action: SystemctlAction::IsActive,
+                unit: "cron.service".into(),
+            }),
+            Box::new(SystemctlUnit {
+                unit: "cron.service".into(),
+                load_state: "loaded".into(),
+                active_state: "inactive".into(),
+                sub_state: "dead".into(),
+                unit_file_state: Some("disabled".into()),
+                description: "Regular background program processing daemon".into(),
+            })
+        )
+    );
+}
+
+#[derive(Debug, Error)]
+pub enum SystemctlError {
+    #[error("property {0} missing")]
+    PropertyMissing(&'static str),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::apps::systemctl::{SystemctlAction, SystemctlInput, SystemctlUnit};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_arguments() {
+        let input = SystemctlInput { action: SystemctlAction::Restart, unit: "-H.service".into() };
+        assert_eq!(input.command_arguments(), Some(["restart", "--", "-H.service"]));
+        assert_eq!(input.show_arguments(), ["show", SystemctlUnit::PROPERTIES, "--", "-H.service"]);
+
+        let input = SystemctlInput { action: SystemctlAction::Status, unit: "ssh.service".into() };
+        assert_eq!(input.command_arguments(), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(SystemctlUnit::parse(&read_test_resources("systemctl_show")).unwrap(), SystemctlUnit {
+            unit: "ssh.service".into(),
+            load_state: "loaded".into(),
+            active_state: "active".into(),
+            sub_state: "running".into(),
+            unit_file_state: Some("enabled".into()),
+            description: "OpenBSD Secure Shell server".into(),
+        });
+    }
+}
@@ -0,0 +1,206 @@
+use crate::apps::prelude::*;
+use thiserror::Error;
+use crate::system::System;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Description)]
+#[serde(rename_all = "lowercase")]
+pub enum TarMode {
+    Create,
+    Extract,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Description)]
+#[serde(rename_all = "lowercase")]
+pub enum TarCompression {
+    Gzip,
+    Bzip2,
+    Xz,
+}
+
+impl TarCompression {
+    fn argument(&self) -> &'static str {
+        match self {
+            TarCompression::Gzip => "--gzip",
+            TarCompression::Bzip2 => "--bzip2",
+            TarCompression::Xz => "--xz",
+        }
+    }
+}
+
+/// create:     sources are packed into the destination archive
+/// extract:    the single source archive is unpacked into the destination directory
+/// compression:    none by default, detected from the archive on extract if not given
+#[derive(Serialize, Deserialize, Description)]
+pub struct TarInput {
+    mode: TarMode,
+    sources: Vec<String>,
+    destination: String,
+    compression: Option<TarCompression>,
+}
+
+impl TarInput {
+    /// `--force-local` keeps archive names with a `:` local, GNU tar would run `rsh` for `host:/x.tar`
+    fn arguments(&self) -> Result<Vec<String>, TarError> {
+        let mut arguments = vec!["--verbose".to_string(), "--force-local".to_string()];
+
+        match self.mode {
+            TarMode::Create => {
+                if self.sources.is_empty() {
+                    return Err(TarError::SourcesMissing);
+                }
+
+                arguments.push("--create".into());
+                arguments.push(format!("--file={}", self.destination));
+            }
+            TarMode::Extract => {
+                if self.sources.len() != 1 {
+                    return Err(TarError::SingleArchive(self.sources.len()));
+                }
+
+                arguments.push("--extract".into());
+                arguments.push(format!("--file={}", self.sources[0]));
+                arguments.push(format!("--directory={}", self.destination));
+            }
+        }
+
+        if let Some(compression) = &self.compression {
+            arguments.push(compression.argument().into());
+        }
+
+        if self.mode == TarMode::Create {
+            arguments.push("--".into());
+            arguments.extend(self.sources.iter().cloned());
+        }
+
+        Ok(arguments)
+    }
+}
+
+/// entries:    paths packed or unpacked, relative to the archive root
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct TarOutput {
+    entries: Vec<String>,
+}
+
+pub struct Tar;
+
+impl Tar {
+    fn tar() -> &'static str { "/bin/tar" }
+
+    pub async fn run_parse(input: TarInput, system: &System) -> Resul<TarOutput> {
+        let o = system.run_args(Self::tar(), &input.arguments()?).await?;
+
+        Ok(TarOutput {
+            entries: String::from_utf8(o)?.lines()
+                .filter(|l| !l.is_empty())
+                .map(|l| l.into())
+                .collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl App for Tar {
+    type Output = TarOutput;
+    type Input = TarInput;
+
+    fn new() -> Self {
+        Self {}
+    }
+
+    async fn run<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System) -> Resul<Self::Output> {
+        let i = TarInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        Tar::run_parse(i, system).await
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TarBuilder {}
+
+impl AppBuilder for TarBuilder {
+    app_metadata!(
+        Tar,
+        "tar",
+        "Create or extract tar archives with optional gzip, bzip2 or xz compression.",
+        &[Os::LinuxAny],
+        AppExample::new("Backup a configuration directory",
+            Box::new(TarInput {
+                mode: TarMode::Create,
+                sources: vec!["/etc/nginx".into()],
+                destination: "/var/backups/nginx.tar.gz".into(),
+                compression: Some(TarCompression::Gzip),
+            }),
+            Box::new(TarOutput {
+                entries: vec!["etc/nginx/".into(), "etc/nginx/nginx.conf".into()],
+            })
+        ),
+        AppExample::new("Deploy a release",
+            Box::new(TarInput {
+                mode: TarMode::Extract,
+                sources: vec!["/tmp/release.tar.gz".into()],
+                destination: "/opt/app".into(),
+                compression: None,
+            }),
+            Box::new(TarOutput {
+                entries: vec!["bin/".into(), "bin/app".into()],
+            })
+        )
+    );
+}
+
+#[derive(Debug, Error)]
+pub enum TarError {
+    #[error("no sources to create an archive from")]
+    SourcesMissing,
+    #[error("exactly one archive can be extracted, got {0}")]
+    SingleArchive(usize),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::apps::tar::{TarCompression, TarInput, TarMode};
+
+    #[test]
+    fn test_arguments() {
+        assert_eq!(TarInput {
+            mode: TarMode::Create,
+            sources: vec!["/etc/nginx".into(), "/etc/hosts".into()],
+            destination: "/tmp/backup.tar.gz".into(),
+            compression: Some(TarCompression::Gzip),
+        }.arguments().unwrap(), [
+            "--verbose", "--force-local", "--create", "--file=/tmp/backup.tar.gz", "--gzip", "--", "/etc/nginx", "/etc/hosts"
+        ]);
+
+        assert_eq!(TarInput {
+            mode: TarMode::Extract,
+            sources: vec!["/tmp/backup.tar.gz".into()],
+            destination: "/opt/app".into(),
+            compression: None,
+        }.arguments().unwrap(), [
+            "--verbose", "--force-local", "--extract", "--file=/tmp/backup.tar.gz", "--directory=/opt/app"
+        ]);
+
+        assert_eq!(TarInput {
+            mode: TarMode::Extract,
+            sources: vec!["host:/x.tar".into()],
+            destination: "/opt/app".into(),
+            compression: None,
+        }.arguments().unwrap(), [
+            "--verbose", "--force-local", "--extract", "--file=host:/x.tar", "--directory=/opt/app"
+        ]);
+
+        assert!(TarInput {
+            mode: TarMode::Create,
+            sources: vec![],
+            destination: "/tmp/backup.tar".into(),
+            compression: None,
+        }.arguments().is_err());
+
+        assert!(TarInput {
+            mode: TarMode::Extract,
+            sources: vec!["/tmp/a.tar".into(), "/tmp/b.tar".into()],
+            destination: "/opt/app".into(),
+            compression: None,
+        }.arguments().is_err());
+    }
+}
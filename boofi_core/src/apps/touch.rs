@@ -2,11 +2,14 @@ use crate::apps::prelude::*;
 use crate::system::System;
 
 #[derive(Serialize, Deserialize, Description)]
-pub(crate) struct TouchInput {
+pub struct TouchInput {
     path: String,
 }
 
-pub(crate) struct Touch;
+/// created:    path of the file if the run created it, a rollback only removes created files
+pub struct Touch {
+    created: Option<String>,
+}
 
 #[async_trait]
 impl App for Touch {
@@ -14,17 +17,35 @@ impl App for Touch {
     type Input = TouchInput;
 
     fn new() -> Self {
-        Self {}
+        Self {
+            created: None,
+        }
     }
 
     async fn run<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System) -> Resul<Self::Output> {
         let i = TouchInput::deserialize(input).map_err(Erro::from_deserialize)?;
-        system.run_args("/bin/touch", &[i.path]).await.map(|_| ())
+        let existed = system.path_exist(&i.path).await?;
+        system.run_args("/bin/touch", &[i.path.as_str()]).await?;
+
+        if !existed {
+            self.created = Some(i.path);
+        }
+        Ok(())
+    }
+
+    const REVERSIBLE: bool = true;
+
+    /// Removes the file if it was created, an existing file keeps its new timestamp
+    async fn rollback(&mut self, system: &System) -> Resul<()> {
+        match self.created.take() {
+            Some(path) => system.delete(&path).await,
+            None => Ok(()),
+        }
     }
 }
 
 #[derive(Clone, Default)]
-pub(crate) struct TouchBuilder;
+pub struct TouchBuilder;
 
 impl AppBuilder for TouchBuilder {
     app_metadata!(
@@ -52,10 +73,14 @@ mod test {
     async fn test_touch() {
         let path = "/tmp/test123";
         let system = system_user().await;
-        Touch {}.run(to_value(TouchInput { path: path.into() }).unwrap(),
-                     &system,
+        let mut touch = Touch::new();
+        touch.run(to_value(TouchInput { path: path.into() }).unwrap(),
+                  &system,
         ).await.unwrap();
 
         assert_eq!(system.read_to_string(path).await.unwrap(), "");
+
+        touch.rollback(&system).await.unwrap();
+        assert!(!system.path_exist(path).await.unwrap());
     }
 }
@@ -2,7 +2,7 @@ use crate::apps::prelude::*;
 use thiserror::Error;
 use crate::system::System;
 
-pub(crate) enum UnameOptions {
+pub enum UnameOptions {
     All,
     /*KernelName,
     Nodename,
@@ -15,7 +15,7 @@ pub(crate) enum UnameOptions {
 }
 
 impl UnameOptions {
-    pub(crate) fn value(&self) -> &str {
+    pub fn value(&self) -> &str {
         match self {
             UnameOptions::All => "-a",
             /*UnameOptions::KernelName => "-s",
@@ -32,7 +32,7 @@ impl UnameOptions {
 
 
 #[derive(Debug, Serialize, Deserialize, Description)]
-pub(crate) struct Uname {
+pub struct Uname {
     kernel_name: String,
     nodename: String,
     kernel_release: String,
@@ -44,11 +44,11 @@ pub(crate) struct Uname {
 }
 
 impl Uname {
-    pub(crate) fn executable() -> &'static str { "/bin/uname" }
+    pub fn executable() -> &'static str { "/bin/uname" }
 }
 
 impl Uname {
-    pub(crate) fn parse(content: &str) -> Resul<Uname> {
+    pub fn parse(content: &str) -> Resul<Uname> {
         let mut left: Vec<&str> = content.splitn(4, ' ').collect();
         let mut right: Vec<&str> = left.last().ok_or(UnameError::ParseRight)?.trim_end().rsplitn(5, ' ').collect();
 
@@ -65,10 +65,10 @@ impl Uname {
     }
 }
 
-pub(crate) struct UnameApp {}
+pub struct UnameApp {}
 
 impl UnameApp {
-    pub(crate) async fn run_parse(system: &System) -> Resul<Uname> {
+    pub async fn run_parse(system: &System) -> Resul<Uname> {
         let o = system.run_args(Uname::executable(), &[UnameOptions::All.value()]).await?;
         Uname::parse(&String::from_utf8(o)?)
     }
@@ -89,7 +89,7 @@ impl App for UnameApp {
 }
 
 #[derive(Clone, Default)]
-pub(crate) struct UnameBuilder;
+pub struct UnameBuilder;
 
 impl AppBuilder for UnameBuilder {
     app_metadata!(
@@ -111,7 +111,7 @@ impl AppBuilder for UnameBuilder {
 }
 
 #[derive(Debug, Error)]
-pub(crate) enum UnameError {
+pub enum UnameError {
     #[error("failed to parse from right")]
     ParseRight
 }
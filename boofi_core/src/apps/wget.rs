@@ -1,8 +1,12 @@
 use crate::apps::prelude::*;
+use std::time::Duration;
 use crate::system::System;
 
+/// how often the downloaded size is checked while a task runs
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Serialize, Deserialize, Description)]
-pub(crate) struct WgetInput {
+pub struct WgetInput {
     output: Option::<String>,
     user: Option::<String>,
     password: Option::<String>,
@@ -33,7 +37,7 @@ impl From<WgetInput> for Vec<String> {
     }
 }
 
-pub(crate) struct Wget;
+pub struct Wget;
 
 #[async_trait]
 impl App for Wget {
@@ -53,11 +57,40 @@ impl App for Wget {
 
         Ok(())
     }
+
+    /// Reports the downloaded bytes of the output file, the total size is unknown upfront
+    async fn run_with_progress<'de, I: Deserializer<'de> + Send>(&mut self, input: I, system: &System, progress: &ProgressSender) -> Resul<Self::Output> {
+        let i = WgetInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        let output = i.output.clone();
+        let arguments: Vec<String> = i.into();
+
+        let download = system.run_args("/usr/bin/wget", arguments.as_slice());
+        tokio::pin!(download);
+
+        let mut interval = tokio::time::interval(PROGRESS_INTERVAL);
+
+        loop {
+            tokio::select! {
+                result = &mut download => {
+                    result?;
+                    return Ok(());
+                }
+                _ = interval.tick() => {
+                    if let Some(size) = match &output {
+                        Some(output) => system.metadata(output).await.ok().map(|m| m.size()),
+                        None => None,
+                    } {
+                        progress.send_replace(Progress::message(format!("{} bytes downloaded", size)));
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
 #[derive(Default)]
-pub(crate) struct WgetBuilder {}
+pub struct WgetBuilder {}
 
 
 impl AppBuilder for WgetBuilder {
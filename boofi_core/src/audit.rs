@@ -0,0 +1,242 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::error::Resul;
+use crate::system::RunOutput;
+
+/// Records every command run by the `sh` app, disabled by default
+/// max_records:    oldest records are dropped beyond this number
+/// max_output:     bytes kept of stdout and stderr each
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShellRecording {
+    #[serde(default = "ShellRecording::default_max_records")]
+    pub max_records: usize,
+    #[serde(default = "ShellRecording::default_max_output")]
+    pub max_output: usize,
+}
+
+impl ShellRecording {
+    fn default_max_records() -> usize {
+        1000
+    }
+
+    fn default_max_output() -> usize {
+        4096
+    }
+}
+
+impl Default for ShellRecording {
+    fn default() -> Self {
+        Self {
+            max_records: Self::default_max_records(),
+            max_output: Self::default_max_output(),
+        }
+    }
+}
+
+/// A command of the `sh` app
+/// user:       authenticated api user
+/// run_as:     local user the command ran as, `root` with sudo
+/// exit_code:  missing if the command could not run, see `error`
+/// truncated:  stdout or stderr were longer than `max_output`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShellRecord {
+    pub id: usize,
+    pub timestamp: u64,
+    pub user: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_as: Option<String>,
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<usize>,
+    pub stdout: String,
+    pub stderr: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub truncated: bool,
+}
+
+/// Query of recorded commands, all of them without filter
+/// since:  unix timestamp in seconds
+/// failed: only commands with a non-zero exit code or error
+/// limit:  newest records only
+#[derive(Debug, Default, Deserialize)]
+pub struct ShellRecordFilter {
+    pub user: Option<String>,
+    pub since: Option<u64>,
+    pub failed: Option<bool>,
+    pub limit: Option<usize>,
+}
+
+impl ShellRecordFilter {
+    fn r#match(&self, record: &ShellRecord) -> bool {
+        self.user.as_ref().map(|u| u == &record.user).unwrap_or(true) &&
+            self.since.map(|s| record.timestamp >= s).unwrap_or(true) &&
+            self.failed.map(|f| f == (record.exit_code != Some(0))).unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Default)]
+struct ShellRecords {
+    config: Option<ShellRecording>,
+    records: VecDeque<ShellRecord>,
+    next_id: usize,
+}
+
+/// In-memory store of recorded commands, shared by all requests of a controller
+#[derive(Debug, Clone, Default)]
+pub struct ShellRecorder {
+    records: Arc<Mutex<ShellRecords>>,
+}
+
+impl ShellRecorder {
+    pub fn new(config: Option<ShellRecording>) -> Self {
+        let recorder = Self::default();
+        recorder.set_config(config);
+        recorder
+    }
+
+    /// Records are kept up to the new maximum and dropped if recording is disabled
+    pub fn set_config(&self, config: Option<ShellRecording>) {
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        let max_records = config.as_ref().map(|c| c.max_records).unwrap_or_default();
+
+        while records.records.len() > max_records {
+            records.records.pop_front();
+        }
+        records.config = config;
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.records.lock().map(|r| r.config.is_some()).unwrap_or(false)
+    }
+
+    /// Handle recording the commands of one request, none if recording is disabled
+    pub fn session(&self, user: &str, run_as: Option<&str>) -> Option<ShellSession> {
+        self.enabled().then(|| ShellSession {
+            recorder: self.clone(),
+            user: user.into(),
+            run_as: run_as.map(Into::into),
+        })
+    }
+
+    /// Matching records, oldest first
+    pub fn records(&self, filter: &ShellRecordFilter) -> Vec<ShellRecord> {
+        let records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        let mut matching = records.records.iter()
+            .filter(|r| filter.r#match(r))
+            .cloned()
+            .collect::<Vec<ShellRecord>>();
+
+        if let Some(limit) = filter.limit {
+            matching.drain(..matching.len().saturating_sub(limit));
+        }
+        matching
+    }
+
+    fn push(&self, mut record: ShellRecord) {
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        let max_output = match &records.config {
+            Some(config) => config.max_output,
+            None => return,
+        };
+
+        record.truncated = truncate(&mut record.stdout, max_output) | truncate(&mut record.stderr, max_output);
+        record.id = records.next_id;
+        records.next_id += 1;
+        records.records.push_back(record);
+
+        let max_records = records.config.as_ref().map(|c| c.max_records).unwrap_or_default();
+        while records.records.len() > max_records {
+            records.records.pop_front();
+        }
+    }
+}
+
+/// Records the commands of one request with its user
+#[derive(Debug, Clone)]
+pub struct ShellSession {
+    recorder: ShellRecorder,
+    user: String,
+    run_as: Option<String>,
+}
+
+impl ShellSession {
+    pub fn record(&self, command: &str, result: &Resul<RunOutput>) {
+        tracing::debug!("[SHELL RECORDING] recording command of {}", self.user);
+        let (exit_code, stdout, stderr, error) = match result {
+            Ok(output) => (Some(output.exit_code()), output.stdout().to_string(), output.stderr().to_string(), None),
+            Err(e) => (None, String::new(), String::new(), Some(e.to_string())),
+        };
+
+        self.recorder.push(ShellRecord {
+            id: 0,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+            user: self.user.clone(),
+            run_as: self.run_as.clone(),
+            command: command.into(),
+            exit_code,
+            stdout,
+            stderr,
+            error,
+            truncated: false,
+        });
+    }
+}
+
+/// Cuts the text to at most `max` bytes on a character boundary
+fn truncate(text: &mut String, max: usize) -> bool {
+    if text.len() <= max {
+        return false;
+    }
+
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use crate::audit::{ShellRecorder, ShellRecordFilter, ShellRecording};
+    use crate::error::Erro;
+    use crate::system::RunOutput;
+
+    #[test]
+    fn record() {
+        assert!(ShellRecorder::new(None).session("dev", None).is_none());
+
+        let recorder = ShellRecorder::new(Some(ShellRecording { max_records: 2, max_output: 4 }));
+        let dev = recorder.session("dev", None).unwrap();
+        let ops = recorder.session("ops", Some("root")).unwrap();
+
+        dev.record("echo first", &Ok(RunOutput::new("first\n".into(), "".into(), 0, Duration::from_millis(1))));
+        ops.record("cat /etc/shadow", &Ok(RunOutput::new("".into(), "denied".into(), 1, Duration::from_millis(1))));
+        dev.record("whoami", &Err(Erro::RunTimeout(1)));
+
+        let records = recorder.records(&ShellRecordFilter::default());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id, 1);
+        assert_eq!(records[0].run_as.as_deref(), Some("root"));
+        assert_eq!(records[0].stderr, "deni");
+        assert!(records[0].truncated);
+        assert_eq!(records[1].exit_code, None);
+        assert!(records[1].error.is_some());
+
+        let filter = ShellRecordFilter { user: Some("ops".into()), ..Default::default() };
+        assert_eq!(recorder.records(&filter).len(), 1);
+        let filter = ShellRecordFilter { failed: Some(false), ..Default::default() };
+        assert!(recorder.records(&filter).is_empty());
+        let filter = ShellRecordFilter { limit: Some(1), ..Default::default() };
+        assert_eq!(recorder.records(&filter)[0].command, "whoami");
+
+        recorder.set_config(None);
+        assert!(recorder.records(&ShellRecordFilter::default()).is_empty());
+        dev.record("echo dropped", &Ok(RunOutput::new("".into(), "".into(), 0, Duration::from_millis(1))));
+        assert!(recorder.records(&ShellRecordFilter::default()).is_empty());
+    }
+}
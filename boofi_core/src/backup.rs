@@ -0,0 +1,153 @@
+use std::cmp::Reverse;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::apps::ls::{LsApp, LsInput};
+use crate::error::{Erro, Resul};
+use crate::system::{FileType, System};
+
+/// Copies files before they are changed or deleted, disabled by default.
+/// Backups are named `<file name>.bak.<unix timestamp in milliseconds>`.
+/// dir:            central directory mirroring the original paths, next to the file by default
+/// max_versions:   oldest backups of a file exceeding this count are removed
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Backup {
+    dir: Option<String>,
+    max_versions: Option<usize>,
+}
+
+/// One backup of a file
+/// version:    unix timestamp in milliseconds
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Version {
+    version: u64,
+    path: String,
+}
+
+impl Version {
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+impl Backup {
+    /// Directory containing the backups of a file
+    fn directory(&self, path: &str) -> Resul<String> {
+        let parent = Path::new(path).parent().and_then(Path::to_str).ok_or(Erro::PathInvalid)?;
+
+        Ok(match &self.dir {
+            Some(dir) => format!("{}/{}", dir.trim_end_matches('/'), parent.trim_start_matches('/')).trim_end_matches('/').to_string(),
+            None => parent.to_string(),
+        })
+    }
+
+    fn prefix(path: &str) -> Resul<String> {
+        let name = Path::new(path).file_name().and_then(|n| n.to_str()).ok_or(Erro::PathInvalid)?;
+        Ok(format!("{}.bak.", name))
+    }
+
+    fn path(&self, path: &str, version: u64) -> Resul<String> {
+        Ok(Path::new(&self.directory(path)?).join(format!("{}{}", Self::prefix(path)?, version)).to_string_lossy().to_string())
+    }
+
+    /// Versions of the given file names, newest first
+    fn parse_versions<'a>(&self, path: &str, names: impl Iterator<Item=&'a str>) -> Resul<Vec<Version>> {
+        let prefix = Self::prefix(path)?;
+        let mut versions = vec![];
+
+        for version in names.filter_map(|name| name.strip_prefix(&prefix)?.parse().ok()) {
+            versions.push(Version {
+                version,
+                path: self.path(path, version)?,
+            });
+        }
+
+        versions.sort_by_key(|v| Reverse(v.version));
+        Ok(versions)
+    }
+
+    /// Backups of a file, newest first
+    pub async fn versions(&self, path: &str, system: &System) -> Resul<Vec<Version>> {
+        let directory = self.directory(path)?;
+
+        if !system.path_exist(&directory).await? {
+            return Ok(vec![]);
+        }
+
+        let entries = LsApp::run_parse(LsInput::new(false, true, false, false, directory.as_str()), system).await?;
+        self.parse_versions(path, entries.iter().map(|e| e.filename()))
+    }
+
+    /// Copies an existing regular file, the oldest versions are removed if `max_versions` is exceeded
+    pub async fn create(&self, path: &str, system: &System) -> Resul<Option<Version>> {
+        if !system.path_exist(path).await? || system.file_type(path).await? != FileType::File {
+            return Ok(None);
+        }
+
+        let version = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        let backup = self.path(path, version)?;
+
+        if self.dir.is_some() {
+            system.create_dir_all(&self.directory(path)?).await?;
+        }
+
+        tracing::debug!("[BACKUP] {} to {}", path, backup);
+        system.copy(path, &backup).await?;
+
+        if let Some(max_versions) = self.max_versions {
+            for old in self.versions(path, system).await?.iter().skip(max_versions) {
+                tracing::debug!("[BACKUP] removing {}", old.path);
+                system.delete(&old.path).await?;
+            }
+        }
+
+        Ok(Some(Version {
+            version,
+            path: backup,
+        }))
+    }
+
+    /// Replaces a file with one of its backups, the current content is backed up before
+    pub async fn restore(&self, path: &str, version: u64, system: &System) -> Resul<()> {
+        let backup = self.path(path, version)?;
+
+        if !system.path_exist(&backup).await? {
+            return Err(Erro::BackupVersionNotFound(version));
+        }
+
+        self.create(path, system).await?;
+
+        tracing::debug!("[BACKUP] restoring {} from {}", path, backup);
+        system.copy(&backup, path).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::backup::{Backup, Version};
+
+    #[test]
+    fn test_path() {
+        let backup = Backup::default();
+        assert_eq!(backup.path("/etc/fstab", 1700000000000).unwrap(), "/etc/fstab.bak.1700000000000");
+        assert_eq!(backup.path("/fstab", 1).unwrap(), "/fstab.bak.1");
+
+        let backup = Backup {
+            dir: Some("/var/backups/boofi/".into()),
+            max_versions: None,
+        };
+        assert_eq!(backup.path("/etc/fstab", 1700000000000).unwrap(), "/var/backups/boofi/etc/fstab.bak.1700000000000");
+        assert_eq!(backup.path("/fstab", 1).unwrap(), "/var/backups/boofi/fstab.bak.1");
+    }
+
+    #[test]
+    fn test_parse_versions() {
+        let names = ["fstab", "fstab.bak.1", "fstab.bak.3", "fstab.bak.invalid", "hosts.bak.2", "fstab.bak.2"];
+
+        assert_eq!(Backup::default().parse_versions("/etc/fstab", names.into_iter()).unwrap(), vec![
+            Version { version: 3, path: "/etc/fstab.bak.3".into() },
+            Version { version: 2, path: "/etc/fstab.bak.2".into() },
+            Version { version: 1, path: "/etc/fstab.bak.1".into() },
+        ]);
+    }
+}
@@ -0,0 +1,161 @@
+use std::future::Future;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+use crate::error::Resul;
+use crate::system::System;
+
+/// A finished app run which can be undone, e.g. the file created by `touch` is removed
+#[async_trait]
+pub trait Undo: Send + Sync {
+    async fn undo(&mut self, system: &System) -> Resul<()>;
+}
+
+/// rolled_back:    finished before a later app failed and undone
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Finished,
+    Failed,
+    Skipped,
+    RolledBack,
+}
+
+/// Result of one app in the order of the request
+/// error:  why the app failed, or why a finished app couldn't be rolled back
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BatchResult {
+    status: BatchStatus,
+    output: Option<Value>,
+    error: Option<String>,
+}
+
+/// Apps running one after another with a result for each.
+/// stop_on_error:  apps after a failed one are skipped
+/// rollback:       apps finished before a failed one are undone in reverse order, implies `stop_on_error`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Batch {
+    stop_on_error: bool,
+    rollback: bool,
+}
+
+impl Batch {
+    pub fn new(stop_on_error: bool, rollback: bool) -> Self {
+        Self {
+            stop_on_error: stop_on_error || rollback,
+            rollback,
+        }
+    }
+
+    /// Runs all apps, `run` is called with the index of the app and returns the undo of reversible apps
+    pub async fn execute<F, Fut>(&self, count: usize, system: &System, run: F) -> Vec<BatchResult>
+        where F: Fn(usize) -> Fut,
+              Fut: Future<Output=Resul<(Value, Option<Box<dyn Undo>>)>> {
+        let mut results = vec![];
+        let mut undos: Vec<(usize, Option<Box<dyn Undo>>)> = vec![];
+        let mut failed = false;
+
+        for i in 0..count {
+            if failed && self.stop_on_error {
+                tracing::debug!("[BATCH] app {} skipped", i);
+                results.push(BatchResult { status: BatchStatus::Skipped, output: None, error: None });
+                continue;
+            }
+
+            match run(i).await {
+                Ok((output, undo)) => {
+                    undos.push((i, undo));
+                    results.push(BatchResult { status: BatchStatus::Finished, output: Some(output), error: None });
+                }
+                Err(error) => {
+                    tracing::error!("[BATCH] app {} failed", i);
+                    failed = true;
+                    results.push(BatchResult { status: BatchStatus::Failed, output: None, error: Some(format!("{:?}", error)) });
+                }
+            }
+        }
+
+        if failed && self.rollback {
+            for (i, undo) in undos.into_iter().rev() {
+                let result = match undo {
+                    Some(mut undo) => undo.undo(system).await,
+                    None => Err(BatchError::RollbackUnsupported.into()),
+                };
+
+                match result {
+                    Ok(()) => {
+                        tracing::debug!("[BATCH] app {} rolled back", i);
+                        results[i].status = BatchStatus::RolledBack;
+                    }
+                    Err(error) => {
+                        tracing::error!("[BATCH] app {} not rolled back", i);
+                        results[i].error = Some(format!("{:?}", error));
+                    }
+                }
+            }
+        }
+
+        results
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error("app has no inverse operation and can't be rolled back")]
+    RollbackUnsupported,
+    #[error("batches with stop_on_error or rollback can't run asynchronous")]
+    AsyncUnsupported,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use tokio::sync::Mutex;
+    use crate::batch::{Batch, BatchStatus, Undo};
+    use crate::error::{Erro, Resul};
+    use crate::system::{Credential, Platform, System};
+    use crate::system::os::Os;
+    use crate::system::posix::Posix;
+
+    struct Recorded(usize, Arc<Mutex<Vec<usize>>>);
+
+    #[async_trait]
+    impl Undo for Recorded {
+        async fn undo(&mut self, _system: &System) -> Resul<()> {
+            self.1.lock().await.push(self.0);
+            Ok(())
+        }
+    }
+
+    async fn execute(batch: Batch) -> (Vec<BatchStatus>, Vec<usize>) {
+        let system = System::new(Platform::Posix(Posix::new(Credential::new("dev", "secret"), None)), Some(Os::LinuxUnknown));
+        let undone = Arc::new(Mutex::new(vec![]));
+
+        let results = batch.execute(5, &system, |i| {
+            let undone = undone.clone();
+            async move {
+                match i {
+                    // reversible, irreversible, failing
+                    0 | 1 => Ok((json!(i), Some(Box::new(Recorded(i, undone)) as Box<dyn Undo>))),
+                    2 => Ok((json!(i), None)),
+                    _ => Err(Erro::AppNotFound),
+                }
+            }
+        }).await;
+
+        let undone = undone.lock().await.clone();
+        (results.iter().map(|r| r.status).collect(), undone)
+    }
+
+    #[tokio::test]
+    async fn execute_policies() {
+        use BatchStatus::*;
+
+        assert_eq!(execute(Batch::default()).await, (vec![Finished, Finished, Finished, Failed, Failed], vec![]));
+        assert_eq!(execute(Batch::new(true, false)).await, (vec![Finished, Finished, Finished, Failed, Skipped], vec![]));
+        assert_eq!(execute(Batch::new(false, true)).await, (vec![RolledBack, RolledBack, Finished, Failed, Skipped], vec![1, 0]));
+    }
+}
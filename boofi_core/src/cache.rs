@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Caches parsed read-only files like `/proc/cpuinfo`, disabled by default
+/// ttl:    seconds a parsed file is served from the cache
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileCacheConfig {
+    pub ttl: Option<u64>,
+}
+
+/// Path, file builder name and redaction of a cached read
+type FileCacheKey = (String, Option<String>, bool);
+
+/// A cached read with its etag
+#[derive(Debug, Clone)]
+pub struct FileCacheEntry {
+    created: Instant,
+    pub content: Value,
+    pub etag: Option<String>,
+}
+
+impl FileCacheEntry {
+    /// Seconds since the file was read
+    pub fn age(&self) -> u64 {
+        self.created.elapsed().as_secs()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FileCache {
+    config: FileCacheConfig,
+    entries: HashMap<FileCacheKey, FileCacheEntry>,
+}
+
+impl FileCache {
+    pub fn new(config: FileCacheConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// All entries are dropped if the configuration changed
+    pub fn set_config(&mut self, config: FileCacheConfig) {
+        if self.config != config {
+            self.config = config;
+            self.entries.clear();
+        }
+    }
+
+    fn ttl(&self) -> Option<Duration> {
+        self.config.ttl.map(Duration::from_secs)
+    }
+
+    pub fn get(&mut self, path: &str, name: Option<&str>, redacted: bool) -> Option<FileCacheEntry> {
+        let ttl = self.ttl()?;
+        self.entries.retain(|_, entry| entry.created.elapsed() < ttl);
+
+        let entry = self.entries.get(&(path.to_string(), name.map(ToString::to_string), redacted)).cloned();
+        if entry.is_some() {
+            tracing::trace!("[FILE CACHE] hit for {}", path);
+        }
+        entry
+    }
+
+    /// Ignored if the cache is disabled
+    pub fn insert(&mut self, path: &str, name: Option<&str>, redacted: bool, content: Value, etag: Option<String>) {
+        if self.ttl().is_some() {
+            tracing::trace!("[FILE CACHE] storing {}", path);
+            self.entries.insert((path.to_string(), name.map(ToString::to_string), redacted), FileCacheEntry {
+                created: Instant::now(),
+                content,
+                etag,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::cache::{FileCache, FileCacheConfig};
+
+    #[test]
+    fn get_insert() {
+        let mut cache = FileCache::default();
+        cache.insert("/proc/cpuinfo", None, false, json!([]), None);
+        assert!(cache.get("/proc/cpuinfo", None, false).is_none());
+
+        cache.set_config(FileCacheConfig { ttl: Some(60) });
+        cache.insert("/proc/cpuinfo", None, false, json!([1]), Some("\"abc\"".into()));
+        let entry = cache.get("/proc/cpuinfo", None, false).unwrap();
+        assert_eq!(entry.content, json!([1]));
+        assert_eq!(entry.etag.as_deref(), Some("\"abc\""));
+        assert!(cache.get("/proc/cpuinfo", None, true).is_none());
+        assert!(cache.get("/proc/cpuinfo", Some("text"), false).is_none());
+
+        cache.set_config(FileCacheConfig { ttl: Some(0) });
+        cache.insert("/proc/cpuinfo", None, false, json!([1]), None);
+        assert!(cache.get("/proc/cpuinfo", None, false).is_none());
+    }
+}
@@ -0,0 +1,194 @@
+use axum::http::{header, Method};
+use base64::Engine;
+use percent_encoding::{AsciiSet, CONTROLS, NON_ALPHANUMERIC, utf8_percent_encode};
+use rustls::RootCertStore;
+use serde_json::Value;
+use thiserror::Error;
+use crate::error::Resul;
+use crate::http;
+
+/// Characters escaped in file paths, `/` separates the segments
+const PATH: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'%').add(b'<').add(b'>').add(b'?').add(b'`').add(b'{').add(b'}');
+
+/// Client of a running boofi rest api, used by `boofi client`
+/// url:        base url of the server e.g. `https://localhost:3000`
+/// service:    name of the service, every request is sent below `/<service>`
+/// roots:      certificate authorities trusted for https, the system ones by default
+pub struct Client {
+    url: String,
+    service: String,
+    authorization: Option<String>,
+    roots: Option<RootCertStore>,
+}
+
+impl Client {
+    pub fn new(url: &str, service: &str, roots: Option<RootCertStore>) -> Self {
+        Self {
+            url: url.trim_end_matches('/').into(),
+            service: service.trim_matches('/').into(),
+            authorization: None,
+            roots,
+        }
+    }
+
+    /// Basic authentication sent with every request
+    pub fn basic(&mut self, username: &str, password: &str) {
+        let credential = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+        self.authorization = Some(format!("Basic {}", credential));
+    }
+
+    /// Bearer authentication with an existing token
+    pub fn bearer(&mut self, token: &str) {
+        self.authorization = Some(format!("Bearer {}", token));
+    }
+
+    /// Requests a new token with the current authentication and uses it for further requests
+    pub async fn token(&mut self, client: Option<&str>) -> Resul<Value> {
+        let query = client.map(|c| vec![("client", c.to_string())]).unwrap_or_default();
+        let result = self.request(Method::GET, "/token", &query, None).await?;
+
+        let token = result.get("token").and_then(Value::as_str).ok_or(ClientError::TokenMissing)?;
+        self.bearer(token);
+
+        Ok(result)
+    }
+
+    /// Parsed content of a file or the listing of a directory
+    pub async fn read_file(&self, path: &str, name: Option<&str>, redact: bool, sudo: bool) -> Resul<Value> {
+        let mut query = Self::flags(&[("redact", redact), ("sudo", sudo)]);
+        query.extend(name.map(|n| ("name", n.to_string())));
+        self.request(Method::GET, &Self::file_path(path), &query, None).await
+    }
+
+    /// Writes the content as the file module of the path or `name` expects it
+    pub async fn write_file(&self, path: &str, name: Option<&str>, content: &Value, sudo: bool) -> Resul<Value> {
+        let mut query = Self::flags(&[("sudo", sudo)]);
+        query.extend(name.map(|n| ("name", n.to_string())));
+        self.request(Method::POST, &Self::file_path(path), &query, Some(content)).await
+    }
+
+    pub async fn delete_file(&self, path: &str, sudo: bool) -> Resul<Value> {
+        self.request(Method::DELETE, &Self::file_path(path), &Self::flags(&[("sudo", sudo)]), None).await
+    }
+
+    /// Documentation of the available file modules
+    pub async fn files(&self) -> Resul<Value> {
+        self.request(Method::GET, "/files", &[], None).await
+    }
+
+    /// Documentation of the available apps
+    pub async fn apps(&self) -> Resul<Value> {
+        self.request(Method::GET, "/apps", &[], None).await
+    }
+
+    /// Output of the app or the created task if `async` is set
+    pub async fn run_app(&self, name: &str, input: &Value, r#async: bool, sudo: bool) -> Resul<Value> {
+        let path = format!("/apps/{}", utf8_percent_encode(name, NON_ALPHANUMERIC));
+        self.request(Method::POST, &path, &Self::flags(&[("async", r#async), ("sudo", sudo)]), Some(input)).await
+    }
+
+    /// All tasks or the one with `id`
+    pub async fn tasks(&self, id: Option<usize>) -> Resul<Value> {
+        let path = match id {
+            Some(id) => format!("/tasks/{}", id),
+            None => "/tasks".into(),
+        };
+        self.request(Method::GET, &path, &[], None).await
+    }
+
+    /// Sends a request to the service, json responses are parsed and others returned as string.
+    /// Unsuccessful responses are errors with their body.
+    pub async fn request(&self, method: Method, path: &str, query: &[(&str, String)], body: Option<&Value>) -> Resul<Value> {
+        let mut url = format!("{}/{}{}", self.url, self.service, path);
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&query.iter()
+                .map(|(key, value)| format!("{}={}", key, utf8_percent_encode(value, NON_ALPHANUMERIC)))
+                .collect::<Vec<String>>()
+                .join("&"));
+        }
+
+        let headers = self.authorization.iter()
+            .map(|a| (header::AUTHORIZATION, a.clone()))
+            .collect::<Vec<_>>();
+
+        tracing::debug!("[CLIENT] {} {}", method, url);
+        let response = http::send(&url, method, &headers, body.map(Value::to_string), self.roots.clone()).await?;
+        let text = String::from_utf8_lossy(&response.body).to_string();
+
+        if !response.status.is_success() {
+            return Err(ClientError::Status(response.status.as_u16(), text).into());
+        }
+
+        if text.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        Ok(serde_json::from_str(&text).unwrap_or(Value::String(text)))
+    }
+
+    fn file_path(path: &str) -> String {
+        format!("/files/{}", utf8_percent_encode(path.trim_start_matches('/'), PATH))
+    }
+
+    /// Query of the flags which are set
+    fn flags(flags: &[(&'static str, bool)]) -> Vec<(&'static str, String)> {
+        flags.iter().filter(|(_, set)| *set).map(|(key, _)| (*key, "true".to_string())).collect()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("server responded with status {0}: {1}")]
+    Status(u16, String),
+    #[error("token missing in response")]
+    TokenMissing,
+    #[error("--user or --token is required")]
+    AuthMissing,
+    #[error("password missing, use --password or BOOFI_PASSWORD")]
+    PasswordMissing,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use axum::extract::RawQuery;
+    use axum::http::{HeaderMap, StatusCode, Uri};
+    use axum::Router;
+    use axum::routing::{any, get};
+    use serde_json::{json, Value};
+    use crate::client::{Client, ClientError};
+    use crate::error::Erro;
+
+    #[tokio::test]
+    async fn request() {
+        let router = Router::new()
+            .route("/localhost/token", get(|| async { axum::Json(json!({"token": "abc", "expires_at": 1})) }))
+            .route("/localhost/files/*path", any(|uri: Uri, RawQuery(query): RawQuery, headers: HeaderMap| async move {
+                axum::Json(json!({
+                    "path": uri.path(),
+                    "query": query,
+                    "authorization": headers.get("authorization").and_then(|a| a.to_str().ok()),
+                }))
+            }))
+            .route("/localhost/apps/fail", any(|| async { (StatusCode::BAD_REQUEST, "invalid input") }));
+
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(router.into_make_service());
+        let address = server.local_addr();
+        tokio::spawn(server);
+
+        let mut client = Client::new(&format!("http://{}/", address), "localhost", None);
+        client.basic("dev", "dev");
+        client.token(None).await.unwrap();
+
+        let file = client.read_file("/etc/my hosts", Some("hosts"), true, false).await.unwrap();
+        assert_eq!(file, json!({
+            "path": "/localhost/files/etc/my%20hosts",
+            "query": "redact=true&name=hosts",
+            "authorization": "Bearer abc",
+        }));
+
+        let error = client.run_app("fail", &Value::Null, false, false).await.unwrap_err();
+        assert!(matches!(error, Erro::Client(ClientError::Status(400, ref body)) if body == "invalid input"));
+    }
+}
@@ -0,0 +1,1149 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::fmt::{Debug, Display, Formatter};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{watch, OwnedMutexGuard};
+use tokio::task::JoinHandle;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::apps::*;
+use crate::apps::plugin::PluginError;
+use crate::apps::sh::ShellPolicy;
+use crate::files::plugin::FilePluginError;
+use crate::files::matcher::FileMatcher;
+use crate::files::*;
+use crate::backup::Backup;
+use crate::hook::FileHook;
+use crate::audit::{ShellRecorder, ShellRecording};
+use crate::secret::{Secret, SecretKey};
+use crate::users::ApiUsers;
+use crate::error::{Erro, Resul};
+use crate::ldap::Ldap;
+use crate::system::{Credential, RunLimits, System, SystemManager, TransferLimits};
+use crate::system::os::Os;
+use crate::task::{TaskController, TaskRetention};
+use crate::webhook::Webhook;
+use crate::cache::{FileCache, FileCacheConfig};
+use crate::workspace::Workspace;
+use crate::runbook::Runbooks;
+use crate::token::{MemoryTokenStore, TokenStore};
+use crate::jwt::Jwt;
+
+/// levels of a recursive directory listing allowed if none are configured
+pub const MAX_LIST_DEPTH: usize = 10;
+
+/// Stores authentication data
+/// client: optional name to distinguish multiple tokens of one user
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Auth {
+    token: String,
+    username: String,
+    password: String,
+    client: Option<String>,
+    date: SystemTime,
+}
+
+impl Auth {
+    fn expired(&self, duration: Duration) -> bool {
+        SystemTime::now() >= self.expires_at(duration)
+    }
+
+    pub fn expires_at(&self, duration: Duration) -> SystemTime {
+        self.date + duration
+    }
+
+    pub fn client(&self) -> Option<&str> {
+        self.client.as_deref()
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+/// Manages all credentials and checks expiration.
+/// A user has one token per client, every change is saved in the token store.
+/// With jwt keys tokens are issued as JWTs instead, they are only checked and not kept.
+pub struct AuthController {
+    auths: Vec<Auth>,
+    duration: Duration,
+    saved: watch::Sender<Vec<Auth>>,
+    writer: Option<JoinHandle<()>>,
+    jwt: Option<Jwt>,
+}
+
+impl AuthController {
+    /// Tokens of the store which are not expired, tokens which can't be loaded are dropped
+    pub fn new(duration: Duration, store: Box<dyn TokenStore>) -> Self {
+        let auths = match store.load() {
+            Ok(auths) => auths,
+            Err(e) => {
+                tracing::error!("[TOKENS] stored tokens dropped: {}", e);
+                vec![]
+            }
+        };
+
+        let (saved, writer) = Self::writer(store, None);
+        let mut auth = Self { auths, duration, saved, writer: Some(writer), jwt: None };
+        auth.purge();
+        auth
+    }
+
+    fn token() -> String {
+        rand::thread_rng().sample_iter(rand::distributions::Alphanumeric).take(16).map(char::from).collect()
+    }
+
+    /// Writes the latest tokens to the store in the background, the controller lock is not held while writing.
+    /// Writes of a previous store are finished first, an older state never overwrites a newer one.
+    fn writer(store: Box<dyn TokenStore>, previous: Option<JoinHandle<()>>) -> (watch::Sender<Vec<Auth>>, JoinHandle<()>) {
+        let (saved, mut receiver) = watch::channel(vec![]);
+
+        let writer = tokio::spawn(async move {
+            if let Some(previous) = previous {
+                let _ = previous.await;
+            }
+
+            // ends once the controller or the store is replaced and the last tokens are written
+            while receiver.changed().await.is_ok() {
+                let auths = receiver.borrow_and_update().clone();
+
+                if let Err(e) = store.save(&auths).await {
+                    tracing::error!("[TOKENS] tokens not saved: {}", e);
+                }
+            }
+        });
+
+        (saved, writer)
+    }
+
+    /// Saves all tokens, a failure is only logged and the tokens are kept in memory
+    fn save(&self) {
+        self.saved.send_replace(self.auths.clone());
+    }
+
+    /// Replaces the store, the current tokens are saved in the new one
+    fn set_store(&mut self, store: Box<dyn TokenStore>) {
+        let (saved, writer) = Self::writer(store, self.writer.take());
+        self.saved = saved;
+        self.writer = Some(writer);
+        self.save();
+    }
+
+    /// Removes the expired tokens, returns how many were removed
+    pub fn purge(&mut self) -> usize {
+        let count = self.auths.len();
+        self.auths.retain(|auth| !auth.expired(self.duration));
+        let purged = count - self.auths.len();
+
+        if purged > 0 {
+            tracing::debug!("[TOKENS] {} expired tokens removed", purged);
+            self.save();
+        }
+        purged
+    }
+
+    fn clear(&mut self) {
+        self.auths.clear();
+        self.save();
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn jwt(&self) -> Option<&Jwt> {
+        self.jwt.as_ref()
+    }
+
+    pub fn set_jwt(&mut self, jwt: Option<Jwt>) {
+        self.jwt = jwt;
+    }
+
+    /// Add a new token or replace the token of the same user and client, the expiration starts again
+    pub fn insert_or_replace(&mut self, username: String, password: String, client: Option<String>) -> &Auth {
+        let i = match self.auths.iter().position(|auth| auth.username == username && auth.client == client) {
+            Some(i) => {
+                let auth = &mut self.auths[i];
+                auth.password = password;
+                auth.token = Self::token();
+                auth.date = SystemTime::now();
+                i
+            }
+            None => {
+                self.auths.push(Auth {
+                    token: Self::token(),
+                    username,
+                    password,
+                    client,
+                    date: SystemTime::now(),
+                });
+                self.auths.len() - 1
+            }
+        };
+
+        self.save();
+        &self.auths[i]
+    }
+
+    /// Restart the expiration of a valid token
+    pub fn refresh(&mut self, token: &str) -> Resul<&Auth> {
+        self.get(token)?;
+
+        let i = self.auths.iter()
+            .position(|auth| auth.token == token)
+            .ok_or(Erro::AuthNotFound)?;
+        self.auths[i].date = SystemTime::now();
+        self.save();
+
+        Ok(&self.auths[i])
+    }
+
+    pub fn get(&self, token: &str) -> Resul<&Auth> {
+        self.auths.iter().find(|auth| {
+            auth.token == token
+        }).map(|auth| {
+            if auth.expired(self.duration) {
+                Err(Erro::AuthTokenExpired)
+            } else {
+                Ok(auth)
+            }
+        }).ok_or(Erro::AuthNotFound)?
+    }
+
+    pub fn delete(&mut self, token: &str) -> bool {
+        let i = self.auths.len();
+        self.auths.retain(|auth| auth.token != token);
+        self.deleted(i)
+    }
+
+    /// Revoke the token of another client of the same user
+    pub fn delete_client(&mut self, username: &str, client: &str) -> bool {
+        let i = self.auths.len();
+        self.auths.retain(|auth| auth.username != username || auth.client.as_deref() != Some(client));
+        self.deleted(i)
+    }
+
+    /// Saves the tokens if any of the previous `count` was deleted
+    fn deleted(&self, count: usize) -> bool {
+        let deleted = count > self.auths.len();
+        if deleted {
+            self.save();
+        }
+        deleted
+    }
+}
+
+/// Actions a role may be allowed to perform
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    ReadFiles,
+    WriteFiles,
+    RunApps,
+    ManageTasks,
+    Sudo,
+    RunAs,
+    ReadAudit,
+    ManageRunbooks,
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Permission::ReadFiles => "read files",
+            Permission::WriteFiles => "write files",
+            Permission::RunApps => "run apps",
+            Permission::ManageTasks => "manage tasks",
+            Permission::Sudo => "use sudo",
+            Permission::RunAs => "run as other users",
+            Permission::ReadAudit => "read audit records",
+            Permission::ManageRunbooks => "manage runbooks",
+        })
+    }
+}
+
+/// Maps users to roles and roles to permissions
+/// roles:          role name with its permissions
+/// users:          username with its role name
+/// default_role:   role of users without mapping
+/// run_as:         local users the `run_as` permission may switch to, nobody if empty
+/// Everything is allowed if no roles are configured, except switching to users missing in `run_as`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Authorization {
+    #[serde(default)]
+    roles: HashMap<String, Vec<Permission>>,
+    #[serde(default)]
+    users: HashMap<String, String>,
+    default_role: Option<String>,
+    #[serde(default)]
+    run_as: Vec<String>,
+}
+
+impl Authorization {
+    /// Checks whether the user has the permission through its role
+    pub fn check(&self, username: &str, permission: Permission) -> Resul<()> {
+        if self.roles.is_empty() {
+            return Ok(());
+        }
+
+        let allowed = self.users.get(username)
+            .or(self.default_role.as_ref())
+            .and_then(|role| self.roles.get(role))
+            .map(|permissions| permissions.contains(&permission))
+            .unwrap_or(false);
+
+        if allowed {
+            Ok(())
+        } else {
+            tracing::debug!("[AUTHORIZATION] {} is not allowed to {}", username, permission);
+            Err(Erro::Forbidden(permission))
+        }
+    }
+
+    /// Checks whether the user may run programs as the target user
+    pub fn check_run_as(&self, username: &str, target: &str) -> Resul<()> {
+        self.check(username, Permission::RunAs)?;
+
+        if self.run_as.iter().any(|user| user == target) {
+            Ok(())
+        } else {
+            tracing::debug!("[AUTHORIZATION] {} is not allowed to run as {}", username, target);
+            Err(Erro::RunAsNotAllowed(target.into()))
+        }
+    }
+}
+
+/// Credential used to run programs for users verified by a directory or boofi,
+/// the password may be a tagged secret, e.g. `!vault <base64>`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceAccount {
+    username: String,
+    password: Secret,
+}
+
+/// Verifies the credentials of api users
+/// system: credentials are verified by the endpoint and used to run programs
+/// ldap:   credentials are verified by a directory, programs run with the service account
+/// users:  credentials are verified by the password hashes of the configuration, programs run with the service account
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Authentication {
+    #[default]
+    System,
+    Ldap {
+        #[serde(flatten)]
+        ldap: Ldap,
+        service_account: ServiceAccount,
+    },
+    Users {
+        users: ApiUsers,
+        service_account: ServiceAccount,
+    },
+}
+
+impl Authentication {
+    /// Checks credentials which are not verified by the endpoint
+    pub async fn verify(&self, username: &str, password: &str) -> Resul<()> {
+        match self {
+            Self::System => Ok(()),
+            Self::Ldap { ldap, .. } => ldap.verify(username, password).await,
+            Self::Users { users, .. } => users.verify(username, password).await,
+        }
+    }
+
+    /// Resolves the password of the service account
+    pub async fn resolve(&mut self, key: Option<&SecretKey>) -> Resul<()> {
+        match self {
+            Self::System => Ok(()),
+            Self::Ldap { service_account, .. } |
+            Self::Users { service_account, .. } => service_account.password.resolve(key).await,
+        }
+    }
+
+    /// Credential used to run programs instead of the one of the api user
+    pub fn run_as(&self) -> Option<Credential> {
+        match self {
+            Self::System => None,
+            Self::Ldap { service_account, .. } |
+            Self::Users { service_account, .. } => {
+                let password = service_account.password.expose().unwrap_or_default();
+                Some(Credential::new(&service_account.username, password))
+            }
+        }
+    }
+}
+
+/// Names of the modules of one kind which are loaded
+/// enabled:    only these are loaded, all if missing
+/// disabled:   these are never loaded
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enabled: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    disabled: Vec<String>,
+}
+
+impl ModuleFilter {
+    pub fn new(enabled: Option<Vec<String>>, disabled: Vec<String>) -> Self {
+        Self {
+            enabled,
+            disabled,
+        }
+    }
+
+    pub fn allows(&self, name: &str) -> bool {
+        self.enabled.as_ref().map(|enabled| enabled.iter().any(|n| n == name)).unwrap_or(true)
+            && !self.disabled.iter().any(|n| n == name)
+    }
+
+    /// Configured names which are not a loadable module, probably typos
+    fn unknown<'a>(&'a self, names: &'a [&str]) -> impl Iterator<Item = &'a String> {
+        self.enabled.iter().flatten().chain(self.disabled.iter()).filter(|n| !names.contains(&n.as_str()))
+    }
+}
+
+/// File modules and apps including plugins which are loaded for a service, everything by default.
+/// Paths of a disabled file module fall back to the next matching one e.g. `text`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Modules {
+    #[serde(default)]
+    files: ModuleFilter,
+    #[serde(default)]
+    apps: ModuleFilter,
+}
+
+impl Modules {
+    pub fn new(files: ModuleFilter, apps: ModuleFilter) -> Self {
+        Self {
+            files,
+            apps,
+        }
+    }
+}
+
+/// An app of the service, disabled apps are excluded by the modules or the shell policy
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AppCapabilities {
+    name: String,
+    enabled: bool,
+    compatible: bool,
+}
+
+/// A file module of the service with the operations it supports
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FileCapabilities {
+    name: String,
+    enabled: bool,
+    compatible: bool,
+    capabilities: Vec<Capability>,
+}
+
+/// Apps and file modules of a service for the detected os of the target
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Capabilities {
+    os: Os,
+    apps: Vec<AppCapabilities>,
+    files: Vec<FileCapabilities>,
+}
+
+/// Immutable file builders of a service, cloning only shares them and their resolved paths
+#[derive(Clone)]
+pub struct FileRegistry {
+    files: Arc<Vec<FileBuilders>>,
+    matcher: Arc<FileMatcher>,
+}
+
+impl FileRegistry {
+    pub fn new(files: Vec<FileBuilders>) -> Self {
+        Self {
+            matcher: Arc::new(FileMatcher::new(&files)),
+            files: Arc::new(files),
+        }
+    }
+
+    pub fn by_name(&self, name: &str) -> Resul<&FileBuilders> {
+        tracing::debug!("[FILE] trying to get by name {}",name);
+
+        for f in self.files.iter() {
+            tracing::trace!("[FILE] trying name {}",name);
+
+            if f.name() == name {
+                tracing::debug!("[FILE] {} found",name);
+                return Ok(f);
+            }
+        }
+        tracing::debug!("[FILE] nothing found by name {}",name);
+        Err(Erro::FilesNotMatchedByName(name.into()))
+    }
+
+    pub fn by_match(&self, pattern: &str, system: &System) -> Resul<&FileBuilders> {
+        tracing::debug!("[FILE MATCH] trying to match file by pattern {}", pattern);
+        self.matching(pattern, system.os()?).next()
+            .ok_or(Erro::FilesNotMatchedByPattern(pattern.into()))
+    }
+
+    /// File builders managing the path, the first one is used if no name is given
+    pub fn matching(&self, path: &str, os: &Os) -> impl Iterator<Item = &FileBuilders> {
+        self.matcher.matches(path, os).into_iter().map(|index| &self.files[index])
+    }
+
+    pub fn file_builders(&self) -> &[FileBuilders] {
+        self.files.as_slice()
+    }
+}
+
+/// Serializes changes of one path, e.g. read-modify-write of passwd, within a service
+/// timeout: how long a change waits for another change of the same path
+#[derive(Clone)]
+pub struct FileLocks {
+    locks: Arc<std::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+    timeout: Duration,
+}
+
+impl Default for FileLocks {
+    fn default() -> Self {
+        Self {
+            locks: Default::default(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl FileLocks {
+    /// Waits until no other change of the path is running, fails with a conflict after the timeout
+    pub async fn lock(&self, path: &str) -> Resul<OwnedMutexGuard<()>> {
+        let lock = {
+            let mut locks = self.locks.lock().map_err(|_| Erro::FileLocked(path.into()))?;
+            // locks nobody holds or waits for are removed
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(path.into()).or_default().clone()
+        };
+
+        tokio::time::timeout(self.timeout, lock.lock_owned()).await
+            .map_err(|_| Erro::FileLocked(path.into()))
+    }
+}
+
+/// Settings of one service, built from the configuration.
+/// `reconfigure` applies all of them to a running controller but the modules and plugins, those need a new one.
+pub struct ControllerSettings {
+    pub max_token_expiration: Duration,
+    pub task_retention: TaskRetention,
+    pub task_webhook: Option<Webhook>,
+    pub authorization: Authorization,
+    pub authentication: Authentication,
+    pub run_limits: RunLimits,
+    pub backup: Option<Backup>,
+    pub file_cache: FileCacheConfig,
+    pub file_hooks: Vec<FileHook>,
+    pub shell_recording: Option<ShellRecording>,
+    pub shell: ShellPolicy,
+    pub modules: Modules,
+    pub workspace: Workspace,
+    pub transfer: TransferLimits,
+    pub runbooks: Runbooks,
+    pub max_list_depth: usize,
+    pub token_store: Box<dyn TokenStore>,
+    pub jwt: Option<Jwt>,
+    pub plugins: Option<String>,
+    pub file_plugins: Option<String>,
+}
+
+impl Default for ControllerSettings {
+    fn default() -> Self {
+        Self {
+            max_token_expiration: Duration::from_secs(60 * 60 * 24),
+            task_retention: Default::default(),
+            task_webhook: None,
+            authorization: Default::default(),
+            authentication: Default::default(),
+            run_limits: Default::default(),
+            backup: None,
+            file_cache: Default::default(),
+            file_hooks: vec![],
+            shell_recording: None,
+            shell: Default::default(),
+            modules: Default::default(),
+            workspace: Default::default(),
+            transfer: Default::default(),
+            runbooks: Default::default(),
+            max_list_depth: MAX_LIST_DEPTH,
+            token_store: Box::new(MemoryTokenStore),
+            jwt: None,
+            plugins: None,
+            file_plugins: None,
+        }
+    }
+}
+
+/// Manages all apps/files/tasks + authentication
+/// Used for one target/endpoint
+pub struct Controller {
+    files: FileRegistry,
+    apps: Vec<AppBuilders>,
+    task_controller: TaskController,
+    auth: AuthController,
+    authorization: Authorization,
+    authentication: Authentication,
+    backup: Option<Backup>,
+    file_cache: FileCache,
+    file_hooks: Vec<FileHook>,
+    file_locks: FileLocks,
+    shell_recorder: ShellRecorder,
+    system_manager: SystemManager,
+    modules: Modules,
+    disabled_files: Vec<FileBuilders>,
+    disabled_apps: Vec<AppBuilders>,
+    workspace: Workspace,
+    runbooks: Runbooks,
+    max_list_depth: usize,
+}
+
+impl Controller {
+    /// Instantiate a new controller for local or ssh endpoint
+    pub async fn new(settings: ControllerSettings, address: Option<&str>) -> Resul<Self> {
+        let ControllerSettings {
+            max_token_expiration,
+            task_retention,
+            task_webhook,
+            authorization,
+            authentication,
+            run_limits,
+            backup,
+            file_cache,
+            file_hooks,
+            shell_recording,
+            shell,
+            modules,
+            workspace,
+            transfer,
+            runbooks,
+            max_list_depth,
+            token_store,
+            jwt,
+            plugins,
+            file_plugins,
+        } = settings;
+
+        let system_manager = SystemManager::new(address, run_limits);
+        system_manager.set_workspace(Some(workspace.dir().to_string()));
+        system_manager.set_transfer_limits(transfer);
+
+        tracing::debug!("loading file builders");
+        let mut files = vec![];
+
+        // plugins are matched before built-in files to manage site specific formats
+        if let Some(path) = file_plugins.as_deref() {
+            tracing::debug!("loading file plugins from {}", path);
+            for plugin in FilePluginBuilder::load_dir(path).await? {
+                files.push(FileBuilders::Plugin(plugin));
+            }
+        }
+
+        for file in [
+            FileBuilders::VersionBuilder(VersionBuilder {}),
+            FileBuilders::UptimeBuilder(UptimeBuilder {}),
+            FileBuilders::SwapsBuilder(SwapsBuilder {}),
+            FileBuilders::PartitionsBuilder(PartitionsBuilder {}),
+            FileBuilders::MountsBuilder(MountsBuilder {}),
+            FileBuilders::MeminfoBuilder(MeminfoBuilder {}),
+            FileBuilders::MdstatBuilder(MdstatBuilder {}),
+            FileBuilders::LoadAvgBuilder(LoadAvgBuilder {}),
+            FileBuilders::FilesystemBuilder(FilesystemBuilder {}),
+            FileBuilders::CryptoBuilder(CryptoBuilder {}),
+            FileBuilders::CpuinfoBuilder(CpuinfoBuilder {}),
+            FileBuilders::NetDevBuilder(NetDevBuilder {}),
+            FileBuilders::StatBuilder(StatBuilder {}),
+            FileBuilders::ProcessStatusBuilder(ProcessStatusBuilder {}),
+            FileBuilders::ProcessCmdlineBuilder(ProcessCmdlineBuilder {}),
+            FileBuilders::ProcessIoBuilder(ProcessIoBuilder {}),
+            FileBuilders::NetSocketsBuilder(NetSocketsBuilder {}),
+            FileBuilders::PasswdBuilder(PasswdBuilder {}),
+            FileBuilders::GroupBuilder(GroupBuilder {}),
+            FileBuilders::ShadowBuilder(ShadowBuilder {}),
+            FileBuilders::OsReleaseBuilder(OsReleaseBuilder {}),
+            FileBuilders::HostsBuilder(HostsBuilder {}),
+            FileBuilders::HostnameBuilder(HostnameBuilder {}),
+            FileBuilders::FstabBuilder(FstabBuilder {}),
+            FileBuilders::CrontabBuilder(CrontabBuilder {}),
+            FileBuilders::SystemdUnitBuilder(SystemdUnitBuilder {}),
+            FileBuilders::NetplanBuilder(NetplanBuilder {}),
+            FileBuilders::InterfacesBuilder(InterfacesBuilder {}),
+            FileBuilders::SshdConfigBuilder(SshdConfigBuilder {}),
+            FileBuilders::SudoersBuilder(SudoersBuilder {}),
+            FileBuilders::ExportsBuilder(ExportsBuilder {}),
+            FileBuilders::SmbConfBuilder(SmbConfBuilder {}),
+            FileBuilders::EnvironmentBuilder(EnvironmentBuilder {}),
+            FileBuilders::AuthorizedKeysBuilder(AuthorizedKeysBuilder {}),
+            FileBuilders::X509Builder(X509Builder {}),
+            FileBuilders::YamlBuilder(YamlBuilder {}),
+            FileBuilders::JsonBuilder(JsonBuilder {}),
+            FileBuilders::CsvBuilder(CsvBuilder {}),
+            FileBuilders::CsvHeaderBuilder(CsvHeaderBuilder {}),
+            FileBuilders::TextBuilder(TextBuilder {}),
+            FileBuilders::KeyValueBuilder(KeyValueBuilder {}),
+            FileBuilders::BinaryBuilder(BinaryBuilder {}),
+        ].into_iter() {
+            if files.iter().any(|f| f.name() == file.name()) {
+                return Err(FilePluginError::DuplicateName(file.name().to_string()).into());
+            }
+
+            files.push(file);
+        }
+
+        let names: Vec<&str> = files.iter().map(|f| f.name()).collect();
+        for name in modules.files.unknown(&names) {
+            tracing::warn!("file builder '{}' of the modules is unknown", name);
+        }
+
+        let (files, disabled_files): (Vec<_>, Vec<_>) = files.into_iter().partition(|file| modules.files.allows(file.name()));
+        for file in files.iter() {
+            tracing::info!("file builder '{}' loaded", file.name());
+        }
+
+        tracing::debug!("loading app builders");
+        let mut apps = vec![];
+        for app in [
+            AppBuilders::LsBuilder(LsBuilder::default()),
+            AppBuilders::UnameBuilder(UnameBuilder::default()),
+            AppBuilders::FactsBuilder(FactsBuilder::default()),
+            AppBuilders::WgetBuilder(WgetBuilder::default()),
+            AppBuilders::TouchBuilder(TouchBuilder::default()),
+            AppBuilders::ShBuilder(ShBuilder::new(shell)),
+            AppBuilders::SystemctlBuilder(SystemctlBuilder::default()),
+            AppBuilders::FindBuilder(FindBuilder::default()),
+            AppBuilders::JournalctlBuilder(JournalctlBuilder::default()),
+            AppBuilders::IpBuilder(IpBuilder::default()),
+            AppBuilders::SocketsBuilder(SocketsBuilder::default()),
+            AppBuilders::TarBuilder(TarBuilder::default()),
+            AppBuilders::RsyncBuilder(RsyncBuilder::default()),
+        ].into_iter() {
+            apps.push(app);
+        }
+
+        if let Some(path) = plugins.as_deref() {
+            tracing::debug!("loading plugins from {}", path);
+            for plugin in PluginBuilder::load_dir(path).await? {
+                if apps.iter().any(|app| app.name() == plugin.name()) {
+                    return Err(PluginError::DuplicateName(plugin.name().to_string()).into());
+                }
+
+                apps.push(AppBuilders::Plugin(plugin));
+            }
+        }
+
+        let names: Vec<&str> = apps.iter().map(|app| app.name()).collect();
+        for name in modules.apps.unknown(&names) {
+            tracing::warn!("app '{}' of the modules is unknown", name);
+        }
+
+        let (apps, disabled_apps): (Vec<_>, Vec<_>) = apps.into_iter().partition(|app| modules.apps.allows(app.name()));
+        for app in apps.iter() {
+            tracing::info!("app builder '{}' loaded", app.name());
+        }
+
+        let mut auth = AuthController::new(max_token_expiration, token_store);
+        auth.set_jwt(jwt);
+
+        Ok(Self {
+            files: FileRegistry::new(files),
+            apps,
+            task_controller: TaskController::new(task_retention, task_webhook),
+            auth,
+            authorization,
+            authentication,
+            backup,
+            file_cache: FileCache::new(file_cache),
+            file_hooks,
+            file_locks: FileLocks::default(),
+            shell_recorder: ShellRecorder::new(shell_recording),
+            system_manager,
+            modules,
+            disabled_files,
+            disabled_apps,
+            workspace,
+            runbooks,
+            max_list_depth,
+        })
+    }
+
+    /// Apply changed settings, tokens and tasks are kept.
+    /// Tokens are revoked and the system is detected again if the authentication changed.
+    pub fn reconfigure(&mut self, settings: ControllerSettings) {
+        let ControllerSettings {
+            max_token_expiration,
+            task_retention,
+            task_webhook,
+            authorization,
+            authentication,
+            run_limits,
+            backup,
+            file_cache,
+            file_hooks,
+            shell_recording,
+            shell,
+            workspace,
+            transfer,
+            runbooks,
+            max_list_depth,
+            token_store,
+            jwt,
+            ..
+        } = settings;
+
+        self.auth.set_store(token_store);
+        self.auth.set_jwt(jwt);
+
+        if self.authentication != authentication {
+            self.auth.clear();
+            self.system_manager.reset();
+            self.authentication = authentication;
+        }
+
+        self.auth.duration = max_token_expiration;
+        self.authorization = authorization;
+        self.task_controller.set_retention(task_retention);
+        self.task_controller.set_webhook(task_webhook);
+        self.system_manager.set_limits(run_limits);
+        self.backup = backup;
+        self.file_cache.set_config(file_cache);
+        self.file_hooks = file_hooks;
+        self.shell_recorder.set_config(shell_recording);
+        self.system_manager.set_workspace(Some(workspace.dir().to_string()));
+        self.workspace = workspace;
+        self.system_manager.set_transfer_limits(transfer);
+        self.runbooks = runbooks;
+        self.max_list_depth = max_list_depth;
+
+        for app in self.apps.iter_mut() {
+            if let AppBuilders::ShBuilder(sh) = app {
+                *sh = ShBuilder::new(shell.clone());
+            }
+        }
+    }
+
+    /// Shared system manager to detect systems without holding the controller lock
+    pub fn system_manager(&self) -> SystemManager {
+        self.system_manager.clone()
+    }
+
+    pub fn auth(&self) -> &AuthController {
+        &self.auth
+    }
+
+    pub fn auth_mut(&mut self) -> &mut AuthController {
+        &mut self.auth
+    }
+
+    pub fn authorization(&self) -> &Authorization {
+        &self.authorization
+    }
+
+    pub fn authentication(&self) -> &Authentication {
+        &self.authentication
+    }
+
+    /// Modules the service was loaded with, changing them needs a new controller
+    pub fn modules(&self) -> &Modules {
+        &self.modules
+    }
+
+    /// Loaded and disabled apps and file modules with their compatibility to the `os`
+    pub fn capabilities(&self, os: &Os) -> Capabilities {
+        Capabilities {
+            os: os.clone(),
+            apps: self.apps.iter().map(|app| (app, Self::app_enabled(app)))
+                .chain(self.disabled_apps.iter().map(|app| (app, false)))
+                .map(|(app, enabled)| AppCapabilities {
+                    name: app.name().to_string(),
+                    enabled,
+                    compatible: app.compatible(os),
+                }).collect(),
+            files: self.file_builders().iter().map(|file| (file, true))
+                .chain(self.disabled_files.iter().map(|file| (file, false)))
+                .map(|(file, enabled)| FileCapabilities {
+                    name: file.name().to_string(),
+                    enabled,
+                    compatible: file.compatible(os),
+                    capabilities: file.capabilities().to_vec(),
+                }).collect(),
+        }
+    }
+
+    /// Scratch directory of the service on the target
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+
+    /// Shared runbooks of all services
+    pub fn runbooks(&self) -> Runbooks {
+        self.runbooks.clone()
+    }
+
+    pub fn max_list_depth(&self) -> usize {
+        self.max_list_depth
+    }
+
+    pub fn backup(&self) -> Option<&Backup> {
+        self.backup.as_ref()
+    }
+
+    pub fn file_hooks(&self) -> &[FileHook] {
+        self.file_hooks.as_slice()
+    }
+
+    pub fn file_cache_mut(&mut self) -> &mut FileCache {
+        &mut self.file_cache
+    }
+
+    /// Shared path locks to wait for without holding the controller lock
+    pub fn file_locks(&self) -> FileLocks {
+        self.file_locks.clone()
+    }
+
+    /// Shared store of the recorded `sh` commands
+    pub fn shell_recorder(&self) -> ShellRecorder {
+        self.shell_recorder.clone()
+    }
+
+    /// Shared file builders to use without holding the controller lock
+    pub fn file_registry(&self) -> FileRegistry {
+        self.files.clone()
+    }
+
+    pub fn file_builders(&self) -> &[FileBuilders] {
+        self.files.file_builders()
+    }
+
+    /// The `sh` app is disabled by the shell policy
+    fn app_enabled(app: &AppBuilders) -> bool {
+        !matches!(app, AppBuilders::ShBuilder(sh) if !sh.enabled())
+    }
+
+    /// Apps of the service, without the `sh` app if the shell policy disables it
+    pub fn apps(&self) -> impl Iterator<Item = &AppBuilders> {
+        self.apps.iter().filter(|app| Self::app_enabled(app))
+    }
+
+    pub fn app(&self, name: &str) -> Option<&AppBuilders> {
+        self.apps().find(|app| app.name() == name)
+    }
+
+    pub fn task_controller(&self) -> &TaskController {
+        &self.task_controller
+    }
+
+    pub fn task_controller_mut(&mut self) -> &mut TaskController {
+        &mut self.task_controller
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use serde_json::{from_value, json};
+    use std::sync::Arc;
+    use crate::controller::{AuthController, Authentication, Authorization, Controller, ControllerSettings, FileLocks, FileRegistry, ModuleFilter, Modules, Permission};
+    use crate::error::Erro;
+    use crate::files::{FileBuilders, VersionBuilder};
+    use crate::system::os::Os;
+    use crate::token::MemoryTokenStore;
+    use crate::secret::SecretKey;
+    use crate::users::hash_password;
+
+    #[tokio::test]
+    async fn file_locks() {
+        let locks = FileLocks { timeout: Duration::from_millis(50), ..Default::default() };
+
+        let passwd = locks.lock("/etc/passwd").await.unwrap();
+        let _hosts = locks.lock("/etc/hosts").await.unwrap();
+        assert!(matches!(locks.lock("/etc/passwd").await, Err(Erro::FileLocked(_))));
+
+        // released locks are removed once the next change is locked
+        drop(passwd);
+        let _passwd = locks.lock("/etc/passwd").await.unwrap();
+        assert_eq!(locks.locks.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn examples_valid() {
+        let controller = Controller::new(Default::default(), None).await.unwrap();
+
+        for app in controller.apps.iter() {
+            let help = serde_json::to_value(app.help(&Os::LinuxAny)).unwrap();
+            for example in help["examples"].as_array().unwrap() {
+                assert!(app.validate(&example["input"]).is_ok(), "{} {}", app.name(), example["input"]);
+            }
+        }
+
+        for file in controller.file_registry().file_builders() {
+            let help = serde_json::to_value(file.help()).unwrap();
+            for example in help["examples"].as_array().unwrap().iter().filter_map(|e| e.get("Write")) {
+                assert!(file.validate(&example["input"]).is_ok(), "{} {}", file.name(), example["input"]);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn modules() {
+        let modules = Modules::new(ModuleFilter::new(None, vec!["passwd".into(), "binary".into()]),
+                                   ModuleFilter::new(Some(vec!["ls".into(), "wget".into(), "unknown".into()]), vec!["wget".into()]));
+        let controller = Controller::new(ControllerSettings {
+            modules: modules.clone(),
+            ..Default::default()
+        }, None).await.unwrap();
+
+        assert!(controller.file_registry().by_name("passwd").is_err());
+        assert!(controller.file_registry().by_name("binary").is_err());
+        assert!(controller.file_registry().by_name("shadow").is_ok());
+        assert_eq!(controller.apps().map(|app| app.name()).collect::<Vec<_>>(), vec!["ls"]);
+        assert_eq!(controller.modules(), &modules);
+
+        let capabilities = serde_json::to_value(controller.capabilities(&Os::LinuxAny)).unwrap();
+        let passwd = capabilities["files"].as_array().unwrap().iter().find(|file| file["name"] == "passwd").unwrap();
+        assert_eq!(passwd["enabled"], false);
+        assert_eq!(passwd["compatible"], true);
+        assert!(passwd["capabilities"].as_array().unwrap().contains(&json!("Read")));
+        assert!(capabilities["apps"].as_array().unwrap().iter().any(|app| app["name"] == "wget" && app["enabled"] == false));
+
+        let modules: Modules = serde_yaml::from_str("files:\n  disabled: [passwd]\n").unwrap();
+        assert!(!modules.files.allows("passwd"));
+        assert!(modules.apps.allows("wget"));
+    }
+
+    #[tokio::test]
+    async fn token_expired() {
+        let mut auth = AuthController::new(Default::default(), Box::new(MemoryTokenStore));
+
+        let token = auth.insert_or_replace("user".into(), "pass".into(), None).token().to_string();
+        assert!(auth.get(&token).is_err());
+    }
+
+    #[tokio::test]
+    async fn token_remove() {
+        let mut auth = AuthController::new(Default::default(), Box::new(MemoryTokenStore));
+
+        let token = auth.insert_or_replace("user".into(), "pass".into(), None).token().to_string();
+
+        assert!(auth.delete(&token));
+        assert!(!auth.delete(&token));
+    }
+
+    #[tokio::test]
+    async fn authentication_users() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = SecretKey::load_or_generate(&dir.path().join("secret.key").to_string_lossy()).await.unwrap();
+
+        let mut authentication: Authentication = serde_yaml::from_str(&format!(r#"
+!users
+users:
+  alice: {}
+service_account:
+  username: boofi
+  password: {}
+"#, hash_password("s3cret").unwrap(), serde_yaml::to_string(&key.encrypt("service secret").unwrap()).unwrap())).unwrap();
+
+        authentication.verify("alice", "s3cret").await.unwrap();
+        assert!(matches!(authentication.verify("alice", "wrong").await, Err(Erro::AuthUserInvalid)));
+        assert!(matches!(authentication.verify("boofi", "service secret").await, Err(Erro::AuthUserInvalid)));
+
+        assert!(authentication.clone().resolve(None).await.is_err());
+        authentication.resolve(Some(&key)).await.unwrap();
+        let credential = authentication.run_as().unwrap();
+        assert_eq!(credential.username(), "boofi");
+        assert_eq!(credential.password(), "service secret");
+    }
+
+    #[tokio::test]
+    async fn token_clients() {
+        let mut auth = AuthController::new(Duration::from_secs(100), Box::new(MemoryTokenStore));
+
+        let laptop = auth.insert_or_replace("user".into(), "pass".into(), Some("laptop".into())).token().to_string();
+        let phone = auth.insert_or_replace("user".into(), "pass".into(), Some("phone".into())).token().to_string();
+
+        assert!(auth.get(&laptop).is_ok());
+        assert_eq!(auth.get(&phone).unwrap().client(), Some("phone"));
+
+        // same client replaces its token only
+        let renewed = auth.insert_or_replace("user".into(), "pass".into(), Some("phone".into())).token().to_string();
+        assert!(auth.get(&phone).is_err());
+        assert!(auth.get(&renewed).is_ok());
+        assert!(auth.get(&laptop).is_ok());
+
+        let expires_at = auth.get(&laptop).unwrap().expires_at(Duration::from_secs(100));
+        assert!(auth.refresh(&laptop).unwrap().expires_at(Duration::from_secs(100)) >= expires_at);
+
+        assert!(!auth.delete_client("other", "laptop"));
+        assert!(auth.delete_client("user", "laptop"));
+        assert!(auth.get(&laptop).is_err());
+        assert!(auth.refresh(&laptop).is_err());
+    }
+
+    #[test]
+    fn authorization() {
+        assert!(Authorization::default().check("user", Permission::WriteFiles).is_ok());
+
+        let authorization: Authorization = from_value(json!({
+            "roles": {
+                "admin": ["read_files", "write_files", "run_apps", "manage_tasks"],
+                "viewer": ["read_files"]
+            },
+            "users": {
+                "root": "admin"
+            },
+            "default_role": "viewer"
+        })).unwrap();
+
+        assert!(authorization.check("root", Permission::WriteFiles).is_ok());
+        assert!(authorization.check("user", Permission::ReadFiles).is_ok());
+        assert!(authorization.check("user", Permission::RunApps).is_err());
+
+        let authorization: Authorization = from_value(json!({
+            "roles": {
+                "viewer": ["read_files"]
+            }
+        })).unwrap();
+
+        assert!(authorization.check("user", Permission::ReadFiles).is_err());
+    }
+
+    #[test]
+    fn authorization_run_as() {
+        assert!(matches!(Authorization::default().check_run_as("user", "deploy"), Err(Erro::RunAsNotAllowed(_))));
+
+        let authorization: Authorization = from_value(json!({
+            "roles": {
+                "admin": ["run_apps", "run_as"],
+                "viewer": ["read_files"]
+            },
+            "users": {
+                "root": "admin"
+            },
+            "default_role": "viewer",
+            "run_as": ["deploy"]
+        })).unwrap();
+
+        assert!(authorization.check_run_as("root", "deploy").is_ok());
+        assert!(matches!(authorization.check_run_as("root", "postgres"), Err(Erro::RunAsNotAllowed(_))));
+        assert!(matches!(authorization.check_run_as("user", "deploy"), Err(Erro::Forbidden(Permission::RunAs))));
+    }
+
+    #[test]
+    fn file_registry_by_name() {
+        let registry = FileRegistry::new(vec![FileBuilders::VersionBuilder(VersionBuilder {})]);
+        let shared = registry.clone();
+
+        assert_eq!(shared.by_name("version").unwrap().name(), "version");
+        assert!(shared.by_name("cpuinfo").is_err());
+        assert!(Arc::ptr_eq(&registry.files, &shared.files));
+    }
+}
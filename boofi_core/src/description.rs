@@ -0,0 +1,625 @@
+use std::fmt::{Display, Formatter};
+pub use boofi_macros::Description;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+const JSON_SCHEMA: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Description about in and output with their types, fields and name
+/// Use derive(Description) if possible
+pub trait Description {
+    const KIND: &'static str = "unknown";
+    const NAME: &'static str = Self::KIND;
+    const DESCRIPTION: &'static str = "";
+    const TYPE: DescriptionType = DescriptionType::Unknown;
+    const FIELDS: &'static [DescriptionField] = &[];
+
+    fn field() -> &'static DescriptionField {
+        &DescriptionField {
+            kind: Self::KIND,
+            name: Self::NAME,
+            description: Self::DESCRIPTION,
+            r#type: Self::TYPE,
+            required: true,
+            default: None,
+            flatten: false,
+            fields: Self::FIELDS,
+        }
+    }
+}
+
+/// Value type of a description, used to render json schema
+/// `Array` and `Optional` have exactly one field, `Tuple` one per element and `Enum` one per variant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DescriptionType {
+    Unknown,
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array,
+    Tuple,
+    Optional,
+    Object,
+    Enum,
+}
+
+/// The actual field description
+/// required:   false for `Option<T>` and serde `default` fields which may be left out
+/// default:    value used if the field is left out, json or plain text
+/// flatten:    fields of a serde `flatten` field are part of the surrounding object
+#[derive(Debug, Serialize)]
+pub struct DescriptionField {
+    pub kind: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    #[serde(skip)]
+    pub r#type: DescriptionType,
+    pub required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<&'static str>,
+    #[serde(skip)]
+    pub flatten: bool,
+    pub fields: &'static [Self],
+}
+
+/// Why an input field does not match its description
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidReason {
+    Unknown,
+    Missing,
+    Type,
+    Value,
+}
+
+/// Input field not matching its description
+/// field:      path of the field e.g. `routes[0].to`, empty for the whole input
+/// expected:   kind of the described field, none for unknown fields, or the accepted values
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InvalidField {
+    pub field: String,
+    pub reason: InvalidReason,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<&'static str>,
+}
+
+impl Display for InvalidField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let field = if self.field.is_empty() { "input" } else { self.field.as_str() };
+
+        match (self.reason, self.expected) {
+            (InvalidReason::Unknown, _) => write!(f, "{} is unknown", field),
+            (InvalidReason::Missing, Some(expected)) => write!(f, "{} is missing, expected {}", field, expected),
+            (InvalidReason::Value, Some(expected)) => write!(f, "{} has an invalid value, expected {}", field, expected),
+            (_, Some(expected)) => write!(f, "{} has the wrong type, expected {}", field, expected),
+            (_, None) => write!(f, "{} is invalid", field),
+        }
+    }
+}
+
+impl InvalidField {
+    /// Well typed field whose value would break the written file
+    pub fn value(field: String, expected: &'static str) -> Self {
+        Self {
+            field,
+            reason: InvalidReason::Value,
+            expected: Some(expected),
+        }
+    }
+}
+
+impl DescriptionField {
+    /// Renders the description as standalone json schema
+    pub fn json_schema(&self) -> Value {
+        let mut schema = self.schema();
+
+        if let Value::Object(o) = &mut schema {
+            o.insert("$schema".into(), JSON_SCHEMA.into());
+        }
+
+        schema
+    }
+
+    fn schema(&self) -> Value {
+        let mut schema = match self.r#type {
+            DescriptionType::Unknown => json!({}),
+            DescriptionType::Null => json!({"type": "null"}),
+            DescriptionType::Boolean => json!({"type": "boolean"}),
+            DescriptionType::Integer => json!({"type": "integer"}),
+            DescriptionType::Number => json!({"type": "number"}),
+            DescriptionType::String => json!({"type": "string"}),
+            DescriptionType::Array => json!({
+                "type": "array",
+                "items": self.fields.first().map(Self::schema).unwrap_or_else(|| json!({})),
+            }),
+            DescriptionType::Tuple => Self::tuple(self.fields),
+            DescriptionType::Optional => json!({
+                "anyOf": [
+                    self.fields.first().map(Self::schema).unwrap_or_else(|| json!({})),
+                    {"type": "null"},
+                ],
+            }),
+            DescriptionType::Object => {
+                let mut schema = json!({
+                    "title": self.kind,
+                    "type": "object",
+                    "properties": Self::properties(self.fields),
+                    "required": Self::required(self.fields),
+                });
+                if let (Value::Object(o), Some(additional)) = (&mut schema, Self::additional(self.fields)) {
+                    o.insert("additionalProperties".into(), additional);
+                }
+                schema
+            }
+            DescriptionType::Enum => json!({
+                "title": self.kind,
+                "oneOf": self.fields.iter().map(Self::variant).collect::<Vec<Value>>(),
+            }),
+        };
+
+        if let Value::Object(o) = &mut schema {
+            if !self.description.is_empty() {
+                o.insert("description".into(), self.description.into());
+            }
+
+            if let Some(default) = self.default {
+                o.insert("default".into(), serde_json::from_str(default).unwrap_or_else(|_| default.into()));
+            }
+        }
+
+        schema
+    }
+
+    /// Checks a json input against the description, every offending field is returned.
+    /// An empty input `()` is never read, so any value is accepted.
+    pub fn validate(&self, value: &Value) -> Vec<InvalidField> {
+        let mut invalid = vec![];
+        if self.r#type != DescriptionType::Null {
+            self.check("", value, &mut invalid);
+        }
+        invalid
+    }
+
+    fn check(&self, path: &str, value: &Value, invalid: &mut Vec<InvalidField>) {
+        let matches = match self.r#type {
+            DescriptionType::Unknown => true,
+            DescriptionType::Null => value.is_null(),
+            DescriptionType::Boolean => value.is_boolean(),
+            DescriptionType::Integer => value.is_i64() || value.is_u64(),
+            DescriptionType::Number => value.is_number(),
+            DescriptionType::String => value.is_string(),
+            DescriptionType::Array => match (value.as_array(), self.fields.first()) {
+                (Some(items), Some(field)) => {
+                    for (i, item) in items.iter().enumerate() {
+                        field.check(&format!("{}[{}]", path, i), item, invalid);
+                    }
+                    true
+                }
+                (items, _) => items.is_some(),
+            },
+            DescriptionType::Tuple => match value.as_array() {
+                Some(items) if items.len() == self.fields.len() => {
+                    for (i, (field, item)) in self.fields.iter().zip(items).enumerate() {
+                        field.check(&format!("{}[{}]", path, i), item, invalid);
+                    }
+                    true
+                }
+                _ => false,
+            },
+            DescriptionType::Optional => {
+                if let (false, Some(field)) = (value.is_null(), self.fields.first()) {
+                    field.check(path, value, invalid);
+                }
+                true
+            }
+            DescriptionType::Object => match value.as_object() {
+                Some(object) => {
+                    Self::check_fields(self.fields, path, object, invalid);
+                    true
+                }
+                None => false,
+            },
+            DescriptionType::Enum => self.check_variant(path, value, invalid),
+        };
+
+        if !matches {
+            invalid.push(InvalidField {
+                field: path.into(),
+                reason: InvalidReason::Type,
+                expected: Some(self.kind),
+            });
+        }
+    }
+
+    /// Missing required and unknown fields, unknown ones are allowed next to a flattened field
+    fn check_fields(fields: &[Self], path: &str, object: &Map<String, Value>, invalid: &mut Vec<InvalidField>) {
+        for field in fields.iter().filter(|f| !f.flatten) {
+            let field_path = Self::join(path, field.name);
+
+            match object.get(field.name) {
+                Some(value) => field.check(&field_path, value, invalid),
+                None if field.required && field.r#type != DescriptionType::Optional => invalid.push(InvalidField {
+                    field: field_path,
+                    reason: InvalidReason::Missing,
+                    expected: Some(field.kind),
+                }),
+                None => {}
+            }
+        }
+
+        if !fields.iter().any(|f| f.flatten) {
+            for key in object.keys().filter(|key| !fields.iter().any(|f| f.name == key.as_str())) {
+                invalid.push(InvalidField {
+                    field: Self::join(path, key),
+                    reason: InvalidReason::Unknown,
+                    expected: None,
+                });
+            }
+        }
+    }
+
+    /// Externally tagged like serde: unit variants are plain strings, others objects with the variant as key
+    fn check_variant(&self, path: &str, value: &Value, invalid: &mut Vec<InvalidField>) -> bool {
+        match value {
+            Value::String(name) => self.fields.iter().any(|v| v.r#type == DescriptionType::Null && v.name == name),
+            Value::Object(object) if object.len() == 1 => {
+                let (name, content) = object.iter().next().unwrap();
+                let variant_path = Self::join(path, name);
+
+                match self.fields.iter().find(|v| v.r#type != DescriptionType::Null && v.name == name) {
+                    Some(variant) if variant.r#type == DescriptionType::Tuple && variant.fields.len() == 1 => {
+                        variant.fields[0].check(&variant_path, content, invalid);
+                        true
+                    }
+                    Some(variant) => {
+                        variant.check(&variant_path, content, invalid);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn join(path: &str, name: &str) -> String {
+        if path.is_empty() { name.into() } else { format!("{}.{}", path, name) }
+    }
+
+    fn tuple(fields: &[Self]) -> Value {
+        json!({
+            "type": "array",
+            "prefixItems": fields.iter().map(Self::schema).collect::<Vec<Value>>(),
+            "minItems": fields.len(),
+            "maxItems": fields.len(),
+        })
+    }
+
+    /// Fields of a flattened struct are inlined, a flattened map only allows additional properties
+    fn properties(fields: &[Self]) -> Map<String, Value> {
+        let mut properties = Map::new();
+
+        for field in fields.iter() {
+            match (field.flatten, field.r#type) {
+                (true, DescriptionType::Object) => properties.extend(Self::properties(field.fields)),
+                (true, _) => {}
+                (false, _) => {
+                    properties.insert(field.name.to_string(), field.schema());
+                }
+            }
+        }
+
+        properties
+    }
+
+    fn required(fields: &[Self]) -> Vec<&'static str> {
+        fields.iter()
+            .flat_map(|f| match (f.flatten, f.r#type) {
+                (true, DescriptionType::Object) => Self::required(f.fields),
+                (true, _) => vec![],
+                (false, _) if f.required && f.r#type != DescriptionType::Optional => vec![f.name],
+                (false, _) => vec![],
+            })
+            .collect()
+    }
+
+    /// Schema of the properties a flattened map collects next to the named ones
+    fn additional(fields: &[Self]) -> Option<Value> {
+        fields.iter()
+            .find(|f| f.flatten && f.r#type != DescriptionType::Object)
+            .map(Self::schema)
+    }
+
+    /// Externally tagged like serde: unit variants are plain strings, others objects with the variant as key
+    fn variant(&self) -> Value {
+        let content = match self.r#type {
+            DescriptionType::Tuple if self.fields.len() == 1 => self.fields[0].schema(),
+            DescriptionType::Tuple => Self::tuple(self.fields),
+            DescriptionType::Object => json!({
+                "type": "object",
+                "properties": Self::properties(self.fields),
+                "required": Self::required(self.fields),
+            }),
+            _ => return json!({"const": self.name}),
+        };
+
+        json!({
+            "type": "object",
+            "properties": {
+                self.name: content,
+            },
+            "required": [self.name],
+            "additionalProperties": false,
+        })
+    }
+}
+
+/// In- or output documentation for end users
+/// Either the plain description or rendered as json schema
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Documentation {
+    Description(&'static DescriptionField),
+    Schema(Value),
+}
+
+impl Documentation {
+    pub fn into_schema(self) -> Self {
+        match self {
+            Documentation::Description(d) => Documentation::Schema(d.json_schema()),
+            schema => schema,
+        }
+    }
+}
+
+macro_rules! description {
+    (
+        $typ:ty,
+        $r#type:ident
+    ) => {
+        impl crate::description::Description for $typ {
+            const KIND: &'static str = stringify!($typ);
+            const TYPE: DescriptionType = DescriptionType::$r#type;
+        }
+    };
+    (
+        $typ:ty,
+        $kind:literal,
+        $r#type:ident
+    ) => {
+        impl crate::description::Description for $typ {
+            const KIND: &'static str = $kind;
+            const TYPE: DescriptionType = DescriptionType::$r#type;
+        }
+    };
+}
+
+macro_rules! description_field_generic {
+    () => {
+        const FIELDS: &'static [DescriptionField] = &[DescriptionField {
+            kind: T::KIND,
+            name: T::NAME,
+            description: T::DESCRIPTION,
+            r#type: T::TYPE,
+            required: true,
+            default: None,
+            flatten: false,
+            fields: T::FIELDS,
+        }];
+    }
+}
+
+description!(bool, Boolean);
+description!(usize, Integer);
+description!(isize, Integer);
+description!(f32, Number);
+description!(f64, Number);
+description!(String, String);
+description!((), "empty", Null);
+
+impl Description for (bool, String) {
+    const KIND: &'static str = "(bool, String)";
+    const TYPE: DescriptionType = DescriptionType::Tuple;
+    const FIELDS: &'static [DescriptionField] = &[
+        DescriptionField {
+            kind: bool::KIND,
+            name: bool::NAME,
+            description: bool::DESCRIPTION,
+            r#type: bool::TYPE,
+            required: true,
+            default: None,
+            flatten: false,
+            fields: bool::FIELDS,
+        },
+        DescriptionField {
+            kind: String::KIND,
+            name: String::NAME,
+            description: String::DESCRIPTION,
+            r#type: String::TYPE,
+            required: true,
+            default: None,
+            flatten: false,
+            fields: String::FIELDS,
+        },
+    ];
+}
+
+impl<T: Description> Description for Option<T> {
+    const KIND: &'static str = "optional";
+    const NAME: &'static str = "optional (see fields)";
+    const DESCRIPTION: &'static str = T::DESCRIPTION;
+    const TYPE: DescriptionType = DescriptionType::Optional;
+    description_field_generic!();
+}
+
+impl<T: Description> Description for Vec<T> {
+    const KIND: &'static str = "array";
+    const TYPE: DescriptionType = DescriptionType::Array;
+    description_field_generic!();
+}
+
+#[cfg(test)]
+mod test {
+    use boofi_macros::Description;
+    use serde_json::json;
+    use crate::description::*;
+
+    #[allow(dead_code)]
+    #[derive(Description)]
+    enum Third {
+        A(bool)
+    }
+
+    #[allow(dead_code)]
+    #[derive(Description)]
+    struct Second<T> {
+        aa: T,
+        #[desc(kind = "text", name = "b²", description = "double b")]
+        bb: String,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Description)]
+    #[desc(kind = "1st", name = "1", description = "start")]
+    struct First {
+        a: bool,
+        #[desc(kind = "II", name = "2nd", description = "second")]
+        b: Second::<bool>,
+        c: Option<bool>,
+        d: (bool, String),
+        e: Third,
+
+    }
+
+    #[allow(dead_code)]
+    #[derive(Serialize, Description)]
+    #[serde(rename_all = "snake_case")]
+    enum Action {
+        IsActive,
+        Run(String),
+        Pair(bool, String),
+        Limit { value: usize },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Serialize, Description)]
+    struct Input {
+        r#type: Action,
+        #[serde(rename = "ids")]
+        numbers: Vec<isize>,
+        #[desc(description = "free text")]
+        comment: Option<String>,
+        #[serde(default)]
+        #[desc(default = "false")]
+        force: bool,
+        #[desc(default = "-")]
+        separator: Option<String>,
+        #[serde(skip)]
+        internal: bool,
+    }
+
+    #[test]
+    fn test() {
+        First::field();
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Input::field().validate(&json!({"type": {"limit": {"value": 3}}, "ids": [1, -2]})).is_empty());
+        assert!(Input::field().validate(&json!({"type": "is_active", "ids": [], "comment": null, "force": true})).is_empty());
+
+        let invalid = Input::field().validate(&json!({"type": {"run": 1}, "ids": [1, "2"], "other": true}));
+        assert_eq!(invalid, vec![
+            InvalidField { field: "type.run".into(), reason: InvalidReason::Type, expected: Some("String") },
+            InvalidField { field: "ids[1]".into(), reason: InvalidReason::Type, expected: Some("isize") },
+            InvalidField { field: "other".into(), reason: InvalidReason::Unknown, expected: None },
+        ]);
+
+        let invalid = Input::field().validate(&json!({"type": "stop", "comment": 1}));
+        assert_eq!(invalid, vec![
+            InvalidField { field: "type".into(), reason: InvalidReason::Type, expected: Some("Action") },
+            InvalidField { field: "ids".into(), reason: InvalidReason::Missing, expected: Some("array") },
+            InvalidField { field: "comment".into(), reason: InvalidReason::Type, expected: Some("String") },
+        ]);
+        assert_eq!(invalid[1].to_string(), "ids is missing, expected array");
+
+        assert_eq!(Input::field().validate(&json!([])).len(), 1);
+    }
+
+    #[test]
+    fn test_required() {
+        let required = Input::FIELDS.iter()
+            .map(|f| (f.name, f.required, f.default))
+            .collect::<Vec<_>>();
+
+        assert_eq!(required, vec![
+            ("type", true, None),
+            ("ids", true, None),
+            ("comment", false, None),
+            ("force", false, Some("false")),
+            ("separator", false, Some("-")),
+        ]);
+
+        assert_eq!(serde_json::to_value(&Input::FIELDS[2]).unwrap()["required"], json!(false));
+    }
+
+    #[test]
+    fn test_json_schema() {
+        assert_eq!(Input::field().json_schema(), json!({
+            "title": "Input",
+            "type": "object",
+            "properties": {
+                "type": {
+                    "title": "Action",
+                    "oneOf": [
+                        {"const": "is_active"},
+                        {
+                            "type": "object",
+                            "properties": {"run": {"type": "string"}},
+                            "required": ["run"],
+                            "additionalProperties": false
+                        },
+                        {
+                            "type": "object",
+                            "properties": {"pair": {
+                                "type": "array",
+                                "prefixItems": [{"type": "boolean"}, {"type": "string"}],
+                                "minItems": 2,
+                                "maxItems": 2
+                            }},
+                            "required": ["pair"],
+                            "additionalProperties": false
+                        },
+                        {
+                            "type": "object",
+                            "properties": {"limit": {
+                                "type": "object",
+                                "properties": {"value": {"type": "integer"}},
+                                "required": ["value"]
+                            }},
+                            "required": ["limit"],
+                            "additionalProperties": false
+                        }
+                    ]
+                },
+                "ids": {
+                    "type": "array",
+                    "items": {"type": "integer"}
+                },
+                "comment": {
+                    "anyOf": [{"type": "string"}, {"type": "null"}],
+                    "description": "free text"
+                },
+                "force": {"type": "boolean", "default": false},
+                "separator": {
+                    "anyOf": [{"type": "string"}, {"type": "null"}],
+                    "default": "-"
+                }
+            },
+            "required": ["type", "ids"],
+            "$schema": "https://json-schema.org/draft/2020-12/schema"
+        }));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,377 @@
+use std::convert::Infallible;
+use semver::Error as SemverError;
+use std::io::Error as IoError;
+use std::net::AddrParseError;
+use regex::Error as RegexError;
+use std::num::{ParseFloatError, ParseIntError};
+use std::string::FromUtf8Error;
+use axum::extract::rejection::{BytesRejection, JsonRejection};
+use axum::extract::multipart::{MultipartError, MultipartRejection};
+use axum::http::header::{InvalidHeaderValue, ToStrError};
+use base64::DecodeError;
+use serde_json::Error as SerdeJsonError;
+use ssh_rs::error::SshError;
+use axum::http::{Error as AxumError, Method};
+use hyper::Error as HyperError;
+use async_ssh2_tokio::Error as AsyncSshError;
+use rcgen::RcgenError;
+use yasna::ASN1Error;
+use thiserror::Error;
+use tokio::task::JoinError;
+use crate::files::hosts::HostsError;
+use crate::files::passwd::PasswdError;
+use crate::files::group::GroupError;
+use crate::files::shadow::ShadowError;
+use crate::files::net_dev::NetDevError;
+use crate::files::stat::StatError;
+use crate::files::process_status::ProcessStatusError;
+use crate::files::process_io::ProcessIoError;
+use crate::files::net_sockets::NetSocketsError;
+use crate::apps::uname::UnameError;
+use crate::apps::sh::ShError;
+use crate::apps::facts::FactsError;
+use crate::apps::systemctl::SystemctlError;
+use crate::apps::find::FindError;
+use crate::apps::journalctl::JournalctlError;
+use crate::apps::ip::IpError;
+use crate::apps::sockets::SocketsError;
+use crate::apps::tar::TarError;
+use crate::apps::rsync::RsyncError;
+use crate::files::crontab::CrontabError;
+use crate::files::crypto::CryptoError;
+use crate::files::FileError;
+use crate::files::loadavg::LoadAvgError;
+use crate::files::mdstat::MdstatError;
+use crate::files::version::VersionError;
+use crate::files::os_release::OsReleaseError;
+use crate::files::systemd_unit::SystemdUnitError;
+use crate::files::netplan::NetplanError;
+use crate::files::interfaces::InterfacesError;
+use crate::files::sshd_config::SshdConfigError;
+use crate::files::sudoers::SudoersError;
+use crate::files::exports::ExportsError;
+use crate::files::smb_conf::SmbConfError;
+use crate::files::environment::EnvironmentError;
+use crate::files::authorized_keys::AuthorizedKeysError;
+use crate::acme::AcmeError;
+use crate::webhook::WebhookError;
+use crate::http::HttpError;
+use crate::client::ClientError;
+use crate::plan::PlanError;
+use crate::batch::BatchError;
+use crate::template::TemplateError;
+use crate::runbook::RunbookError;
+use crate::token::TokenStoreError;
+use crate::jwt::JwtError;
+use crate::secret::SecretError;
+use crate::users::UsersError;
+use crate::apps::plugin::PluginError;
+use crate::files::plugin::FilePluginError;
+use crate::files::key_value::KeyValueError;
+use crate::files::csv::CsvError;
+use crate::ldap::LdapError;
+use crate::patch::PatchError;
+use crate::controller::Permission;
+use crate::description::InvalidField;
+
+/// Manages and converts all errors
+/// File/app implementations have their own error type which needs conversion
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub enum Erro {
+    #[error("os detection failed")]
+    OsDetection,
+    #[error("no compatible platform found")]
+    EndpointIncompatible,
+    #[error("run user not supported for {0}")]
+    RunUserUnsupported(&'static str),
+    #[error("read user not supported for {0}")]
+    ReadUserUnsupported(&'static str),
+    #[error("read ssh not supported for {0}")]
+    ReadSshUnsupported(&'static str),
+    #[error("write user not supported for {0}")]
+    WriteUserUnsupported(&'static str),
+    #[error("write ssh not supported for {0}")]
+    WriteSshUnsupported(&'static str),
+    #[error("delete user not supported for {0}")]
+    DeleteUserUnsupported(&'static str),
+    #[error("delete ssh not supported for {0}")]
+    DeleteSshUnsupported(&'static str),
+    #[error("read range not supported for {0}")]
+    ReadRangeUnsupported(&'static str),
+    #[error("tail not supported for {0}")]
+    TailUnsupported(&'static str),
+    #[error("metadata not supported")]
+    MetadataUnsupported,
+    #[error("invalid metadata {0}")]
+    MetadataInvalid(String),
+    #[error("directory listing not supported")]
+    DirListUnsupported,
+    #[error("chmod not supported for {0}")]
+    ChmodUnsupported(&'static str),
+    #[error("chown not supported for {0}")]
+    ChownUnsupported(&'static str),
+    #[error("copy not supported for {0}")]
+    CopyUnsupported(&'static str),
+    #[error("rename not supported for {0}")]
+    RenameUnsupported(&'static str),
+    #[error("hash not supported for {0}")]
+    HashUnsupported(&'static str),
+    #[error("invalid hash output {0}")]
+    HashInvalid(String),
+    #[error("create directory not supported for {0}")]
+    CreateDirUnsupported(&'static str),
+    #[error("remove directory not supported for {0}")]
+    RemoveDirUnsupported(&'static str),
+    #[error("workspace not supported for {0}")]
+    WorkspaceUnsupported(&'static str),
+    #[error("invalid workspace item {0}")]
+    WorkspaceItemInvalid(String),
+    #[error("chunked writes not supported for {0}")]
+    WriteChunkUnsupported(&'static str),
+    #[error("replacing files not supported for {0}")]
+    ReplaceUnsupported(&'static str),
+    #[error("invalid content range {0}, expected bytes <start>-<end>/<total or *>")]
+    ContentRangeInvalid(String),
+    #[error("content range of {0} bytes but {1} bytes received")]
+    ContentRangeLength(usize, usize),
+    #[error("upload continues at byte {0}")]
+    ContentRangeUnsatisfiable(u64),
+    #[error("checksum {1} differs from the expected {0}")]
+    ChecksumMismatch(String, String),
+    #[error("expected checksum missing")]
+    DigestMissing,
+    #[error("run user but user is invalid")]
+    RunUserUserInvalid,
+    #[error("run user but password is invalid")]
+    RunUserPasswordInvalid,
+    #[error("run user but issues with password stdin")]
+    RunUserStdin,
+    #[error("run user but issues with stdout")]
+    RunUserStdout,
+    #[error("run user but issues with stderr")]
+    RunUserStderr,
+    #[error("run exceeded timeout of {0} seconds")]
+    RunTimeout(u64),
+    #[error("timeout must be at least 1 second")]
+    RunTimeoutInvalid,
+    #[error("depth {0} exceeds the maximum of {1} levels")]
+    ListDepthTooLarge(usize, usize),
+    #[error("output exceeded limit of {0} bytes")]
+    OutputTooLarge(usize),
+    #[error("file of {0} bytes exceeds the read limit of {1} bytes, read a range with offset and length")]
+    ReadTooLarge(u64, u64),
+    #[error("file of {0} bytes exceeds the write limit of {1} bytes")]
+    WriteTooLarge(u64, u64),
+    #[error("run with output not supported for {0}")]
+    RunOutputUnsupported(&'static str),
+    #[error("run limits not supported for {0}")]
+    RunLimitsUnsupported(&'static str),
+    #[error("sudo not supported for {0}")]
+    SudoUnsupported(&'static str),
+    #[error("run user with exit code {0} and message: {1}")]
+    RunUser(u32, String),
+    #[error("run ssh with exit code {0} and message: {1}")]
+    RunSsh(u32, String),
+    #[error("endpoint missing")]
+    EndpointMissing,
+    #[error("write user but temporary file path is invalid")]
+    WriteUserTempPath,
+    #[error("write of {0} failed at {1}: {2}")]
+    WriteAtomic(String, &'static str, String),
+    #[error("operating system detection failed")]
+    OsDetectionFailed,
+    #[error("authentication missing")]
+    RestAuthMissing,
+    #[error("unsupported authentication method")]
+    RestAuthInvalid,
+    #[error("app is incompatible")]
+    AppIncompatible,
+    #[error("app not found")]
+    AppNotFound,
+    #[error("body missing")]
+    AppBodyMissing,
+    #[error("method {0} not allowed")]
+    HttpMethodNotAllowed(Method),
+    #[error("task not found")]
+    TaskNotFound,
+    #[error("task index invalid")]
+    TaskInvalidIndex,
+    #[error("path invalid")]
+    PathInvalid,
+    #[error("path {0} not found")]
+    PathNotFound(String),
+    #[error("File type unsupported")]
+    FileTypeUnsupported,
+    #[error("path exist unsupported")]
+    PathExistUnsupported,
+    #[error("File type {0} unknown")]
+    FileTypeUnknown(String),
+    #[error("nothing matched")]
+    FilesNotMatched,
+    #[error("nothing matched by name {0}")]
+    FilesNotMatchedByName(String),
+    #[error("nothing matched by pattern {0}")]
+    FilesNotMatchedByPattern(String),
+    #[error("failed to execute child process")]
+    AuthTokenExpired,
+    #[error("no authentication found")]
+    AuthNotFound,
+    #[error("private key path")]
+    PrivateKeyPath,
+    #[error("certificate path")]
+    CertificatePath,
+    #[error("ssl is not active")]
+    RestSslInactive,
+    #[error("service {0} is degraded: {1}")]
+    ServiceDegraded(String, String),
+    #[error("fan-out target {0} not found")]
+    FanOutTargetNotFound(String),
+    #[error("grpc service {0} not found")]
+    GrpcServiceNotFound(String),
+    #[error("grpc without ssl only listens on loopback addresses, not on {0}")]
+    GrpcPlainText(String),
+    #[error("no valid certificate authority in {0}")]
+    ClientCaInvalid(String),
+    #[error("invalid certificate")]
+    CertificateInvalid,
+    #[error("no system certificate authorities found")]
+    RootCertificatesMissing,
+    #[error("invalid directory credentials")]
+    AuthLdapInvalid,
+    #[error("invalid api user credentials")]
+    AuthUserInvalid,
+    #[error("upload form field missing")]
+    UploadFieldMissing,
+    #[error("invalid file mode {0}")]
+    FileModeInvalid(String),
+    #[error("invalid owner or group {0}")]
+    FileOwnerInvalid(String),
+    #[error("no mode, owner or group given")]
+    FileAttributesMissing,
+    #[error("target of {0} missing")]
+    FileTargetMissing(&'static str),
+    #[error("conflict: {0}")]
+    FileConflict(String),
+    #[error("conflict: {0} is changed by another request")]
+    FileLocked(String),
+    #[error("content changed since it was read")]
+    PreconditionFailed,
+    #[error("backups are not configured")]
+    BackupDisabled,
+    #[error("backup version missing")]
+    BackupVersionMissing,
+    #[error("backup version {0} not found")]
+    BackupVersionNotFound(u64),
+    #[error("not allowed to {0}")]
+    Forbidden(Permission),
+    #[error("not allowed to run as {0}")]
+    RunAsNotAllowed(String),
+    #[error("run_as can not be combined with sudo")]
+    RunAsSudo,
+    #[error("shell recording is disabled")]
+    ShellRecordingDisabled,
+    #[error("unknown response format {0}, use json, pretty, yaml or toml")]
+    ResponseFormatUnknown(String),
+    #[error("compression failed: {0}")]
+    Compression(String),
+    Deserialize(String),
+    #[error("invalid input: {}", .0.iter().map(ToString::to_string).collect::<Vec<String>>().join(", "))]
+    InputInvalid(Vec<InvalidField>),
+
+    // file/app errors
+    File(#[from] FileError),
+    Hosts(#[from] HostsError),
+    Mdstat(#[from] MdstatError),
+    Crypto(#[from] CryptoError),
+    LoadAvg(#[from] LoadAvgError),
+    Version(#[from] VersionError),
+    Cron(#[from] CrontabError),
+    Uname(#[from] UnameError),
+    Sh(#[from] ShError),
+    Facts(#[from] FactsError),
+    Systemctl(#[from] SystemctlError),
+    Find(#[from] FindError),
+    Journalctl(#[from] JournalctlError),
+    Ip(#[from] IpError),
+    Sockets(#[from] SocketsError),
+    Tar(#[from] TarError),
+    Rsync(#[from] RsyncError),
+    Passwd(#[from] PasswdError),
+    Group(#[from] GroupError),
+    Shadow(#[from] ShadowError),
+    NetDev(#[from] NetDevError),
+    Stat(#[from] StatError),
+    ProcessStatus(#[from] ProcessStatusError),
+    ProcessIo(#[from] ProcessIoError),
+    NetSockets(#[from] NetSocketsError),
+    OsRelease(#[from] OsReleaseError),
+    SystemdUnit(#[from] SystemdUnitError),
+    Netplan(#[from] NetplanError),
+    Interfaces(#[from] InterfacesError),
+    SshdConfig(#[from] SshdConfigError),
+    Sudoers(#[from] SudoersError),
+    Exports(#[from] ExportsError),
+    SmbConf(#[from] SmbConfError),
+    Environment(#[from] EnvironmentError),
+    AuthorizedKeys(#[from] AuthorizedKeysError),
+    Acme(#[from] AcmeError),
+    Ldap(#[from] LdapError),
+    Patch(#[from] PatchError),
+    Webhook(#[from] WebhookError),
+    HttpClient(#[from] HttpError),
+    Client(#[from] ClientError),
+    Plan(#[from] PlanError),
+    Batch(#[from] BatchError),
+    Template(#[from] TemplateError),
+    Runbook(#[from] RunbookError),
+    TokenStore(#[from] TokenStoreError),
+    Jwt(#[from] JwtError),
+    Secret(#[from] SecretError),
+    Users(#[from] UsersError),
+    Plugin(#[from] PluginError),
+    FilePlugin(#[from] FilePluginError),
+    KeyValue(#[from] KeyValueError),
+    Csv(#[from] CsvError),
+
+    // extern crate errors
+    Semver(#[from] SemverError),
+    Grpc(#[from] tonic::transport::Error),
+    Io(#[from] IoError),
+    Regex(#[from] RegexError),
+    ParseInt(#[from] ParseIntError),
+    SerdeJson(#[from] SerdeJsonError),
+    FromUtf8(#[from] FromUtf8Error),
+    Ssh(#[from] SshError),
+    ParseFloat(#[from] ParseFloatError),
+    JsonRejection(#[from] JsonRejection),
+    BytesRejection(#[from] BytesRejection),
+    MultipartRejection(#[from] MultipartRejection),
+    Multipart(#[from] MultipartError),
+    ToStrError(#[from] ToStrError),
+    Base64Decode(#[from] DecodeError),
+    Http(#[from] AxumError),
+    Body(#[from] axum::Error),
+    InvalidHeaderValue(#[from] InvalidHeaderValue),
+    HyperError(#[from] HyperError),
+    AsyncSsh(#[from] AsyncSshError),
+    Yaml(#[from] serde_yaml::Error),
+    Toml(#[from] toml::ser::Error),
+    AddrParse(#[from] AddrParseError),
+    Join(#[from] JoinError),
+    Rcgen(#[from] RcgenError),
+    Rustls(#[from] rustls::Error),
+    JsonWebToken(#[from] jsonwebtoken::errors::Error),
+    Asn1(#[from] ASN1Error),
+    Infallible(#[from] Infallible),
+}
+
+/// Common result type
+pub type Resul<T, E = Erro> = Result<T, E>;
+
+impl Erro {
+    // conversion workaround
+    pub fn from_deserialize<T: serde::de::Error>(error: T) -> Self {
+        Self::Deserialize(error.to_string())
+    }
+}
\ No newline at end of file
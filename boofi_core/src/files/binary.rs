@@ -0,0 +1,120 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use crate::files::prelude::*;
+use serde_json::{json, Value};
+use crate::files::Regex;
+
+#[derive(Debug)]
+pub struct Binary {
+    path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Description)]
+pub struct BinaryInput {
+    #[desc(description = "base64 encoded bytes")]
+    content: String,
+}
+
+impl BinaryInput {
+    fn decode(&self) -> Resul<Vec<u8>> {
+        Ok(STANDARD.decode(self.content.trim())?)
+    }
+}
+
+#[async_trait]
+impl File for Binary {
+    type Output = String;
+    type Input = BinaryInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        Ok(STANDARD.encode(system.read(self.path()).await?))
+    }
+
+    /// Only used for diffs of a dry run, bytes which aren't utf-8 are replaced
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, _system: &System) -> Resul<String> {
+        let i = BinaryInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        Ok(String::from_utf8_lossy(&i.decode()?).into_owned())
+    }
+
+    async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
+        let i = BinaryInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        system.write(self.path(), &i.decode()?).await
+    }
+
+    async fn check<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, _system: &System) -> Resul<()> {
+        BinaryInput::deserialize(input).map_err(Erro::from_deserialize)?.decode().map(|_| ())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"content": output})
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BinaryBuilder;
+
+impl FileBuilder for BinaryBuilder {
+    type File = Binary;
+
+    const NAME: &'static str = "binary";
+    const DESCRIPTION: &'static str = "Get and write any file as base64, choose it with `?encoding=base64` or `?name=binary`. Meant for small files, use the upload and raw content for large ones.";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read, Capability::Write, Capability::Delete];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_regex(Regex::new(".*").unwrap(), &[Os::PosixAny])];
+        }
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EXAMPLE: [FileExample;2] = [
+                FileExample::new_get("gzip header", "H4sIAAAAAAAAAw=="),
+                FileExample::new_write("write bytes", BinaryInput {
+                    content: "H4sIAAAAAAAAAw==".to_string()
+                }),
+            ];
+        }
+
+        EXAMPLE.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::files::binary::{Binary, BinaryInput};
+    use crate::files::File;
+    use crate::utils::test::system_user;
+
+    #[test]
+    fn test_decode() {
+        let input = BinaryInput { content: "AAH/\n".into() };
+        assert_eq!(input.decode().unwrap(), vec![0, 1, 255]);
+        assert!(BinaryInput { content: "not base64!".into() }.decode().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_and_read() {
+        let path = "/tmp/_binary_test_file";
+        let binary = Binary::new(path);
+        let system = system_user().await;
+
+        binary.write(json!({"content": "AAH/"}), &system).await.unwrap();
+        assert_eq!(tokio::fs::read(path).await.unwrap(), vec![0, 1, 255]);
+        assert_eq!(binary.read(&system).await.unwrap(), "AAH/");
+
+        binary.delete(&system).await.unwrap();
+    }
+}
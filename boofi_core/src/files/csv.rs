@@ -0,0 +1,358 @@
+use serde_json::{json, Map, Value};
+use crate::files::prelude::*;
+use crate::files::Regex;
+use thiserror::Error;
+
+const DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+/// Fields of all records, quoted fields may contain delimiters, line breaks and `""` for a quote
+fn parse(content: &str, delimiter: char) -> Result<Vec<Vec<String>>, CsvError> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            _ if quoted => field.push(c),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ if c == delimiter => row.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(CsvError::UnterminatedQuote);
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// The most frequent delimiter outside quotes of the first line, `,` if there is none
+fn detect_delimiter(content: &str) -> char {
+    let mut counts = [0; DELIMITERS.len()];
+    let mut quoted = false;
+
+    for c in content.chars() {
+        if c == '\n' && !quoted {
+            break;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if let Some(i) = DELIMITERS.iter().position(|d| *d == c).filter(|_| !quoted) {
+            counts[i] += 1;
+        }
+    }
+
+    counts.iter().enumerate()
+        .filter(|(_, count)| **count > 0)
+        .max_by_key(|(i, count)| (**count, usize::MAX - i))
+        .map(|(i, _)| DELIMITERS[i])
+        .unwrap_or(',')
+}
+
+fn field(value: &str, delimiter: char) -> String {
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.into()
+    }
+}
+
+fn scalar(value: &Value) -> Result<String, CsvError> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        Value::Null => Ok(String::new()),
+        _ => Err(CsvError::InvalidValue(value.to_string())),
+    }
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct CsvInput {
+    #[desc(description = "single character like `,`, `;` or a tab, detected from the current file or `,` if missing")]
+    delimiter: Option<String>,
+    #[desc(description = "first line, also the column order of object rows. The keys of the object rows if missing")]
+    header: Option<Vec<String>>,
+    #[desc(description = "arrays of values or objects keyed by the header, replace all records")]
+    rows: Vec<Value>,
+}
+
+impl CsvInput {
+    fn render(self, current: &str) -> Resul<String> {
+        let delimiter = match self.delimiter {
+            Some(d) => {
+                let mut chars = d.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if !matches!(c, '"' | '\n' | '\r') => c,
+                    _ => return Err(CsvError::InvalidDelimiter(d).into()),
+                }
+            }
+            None => detect_delimiter(current),
+        };
+
+        let mut header = self.header.unwrap_or_default();
+        if header.is_empty() {
+            for key in self.rows.iter().filter_map(Value::as_object).flat_map(Map::keys) {
+                if !header.contains(key) {
+                    header.push(key.clone());
+                }
+            }
+        }
+
+        let mut lines = vec![];
+        if !header.is_empty() {
+            lines.push(header.iter().map(|h| field(h, delimiter)).collect::<Vec<String>>());
+        }
+
+        for row in self.rows.iter() {
+            let values = match row {
+                Value::Array(values) => values.iter().map(scalar).collect::<Result<Vec<String>, CsvError>>()?,
+                Value::Object(values) => {
+                    if let Some(key) = values.keys().find(|k| !header.contains(k)) {
+                        return Err(CsvError::ColumnUnknown(key.clone()).into());
+                    }
+                    header.iter().map(|h| values.get(h).map(scalar).unwrap_or(Ok(String::new()))).collect::<Result<_, _>>()?
+                }
+                _ => return Err(CsvError::InvalidValue(row.to_string()).into()),
+            };
+            lines.push(values.iter().map(|v| field(v, delimiter)).collect());
+        }
+
+        Ok(lines.iter().map(|l| l.join(&delimiter.to_string()) + "\n").collect())
+    }
+}
+
+pub struct CsvFile {
+    path: String,
+}
+
+impl CsvFile {
+    async fn current(&self, system: &System) -> Resul<String> {
+        if system.path_exist(self.path()).await? { system.read_to_string(self.path()).await } else { Ok(String::new()) }
+    }
+}
+
+#[async_trait]
+impl File for CsvFile {
+    type Output = Vec<Vec<String>>;
+    type Input = CsvInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        let content = system.read_to_string(self.path()).await?;
+        Ok(parse(&content, detect_delimiter(&content))?)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        CsvInput::deserialize(input).map_err(Erro::from_deserialize)?.render(&self.current(system).await?)
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"rows": output})
+    }
+}
+
+/// Records as objects keyed by the first line, missing fields are empty
+pub struct CsvHeaderFile {
+    csv: CsvFile,
+}
+
+#[async_trait]
+impl File for CsvHeaderFile {
+    type Output = Vec<Value>;
+    type Input = CsvInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            csv: CsvFile::new(path),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        let mut rows = self.csv.read(system).await?.into_iter();
+        let header = rows.next().unwrap_or_default();
+
+        rows.enumerate().map(|(i, row)| {
+            if row.len() > header.len() {
+                return Err(CsvError::TooManyFields(i + 1).into());
+            }
+
+            Ok(Value::Object(header.iter().enumerate()
+                .map(|(j, h)| (h.clone(), Value::String(row.get(j).cloned().unwrap_or_default())))
+                .collect()))
+        }).collect()
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        self.csv.render(input, system).await
+    }
+
+    fn path(&self) -> &str {
+        self.csv.path()
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"rows": output})
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CsvBuilder;
+
+impl FileBuilder for CsvBuilder {
+    type File = CsvFile;
+
+    const NAME: &'static str = "csv";
+    const DESCRIPTION: &'static str = "Read or write csv file as rows of fields, a write replaces all rows";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read, Capability::Write, Capability::Delete];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_regex(Regex::new("^.*\\.(csv|CSV)$").unwrap(), &[Os::PosixAny])];
+        }
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EXAMPLE: [FileExample;2] = [
+                FileExample::new_get("inventory", vec![
+                    vec!["host".to_string(), "ip".into(), "role".into()],
+                    vec!["web1".to_string(), "10.0.0.11".into(), "frontend".into()],
+                ]),
+                FileExample::new_write("inventory with header", CsvInput {
+                    delimiter: Some(";".into()),
+                    header: Some(vec!["host".into(), "ip".into(), "role".into()]),
+                    rows: vec![
+                        json!(["web1", "10.0.0.11", "frontend"]),
+                        json!({"host": "db1", "ip": "10.0.0.21", "role": "database"}),
+                    ],
+                }),
+            ];
+        }
+
+        EXAMPLE.as_slice()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CsvHeaderBuilder;
+
+impl FileBuilder for CsvHeaderBuilder {
+    type File = CsvHeaderFile;
+
+    const NAME: &'static str = "csv_header";
+    const DESCRIPTION: &'static str = "Read or write csv file as objects keyed by the first line, choose it with `?name=csv_header`";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read, Capability::Write, Capability::Delete];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_regex(Regex::new("^.*\\.(csv|CSV)$").unwrap(), &[Os::PosixAny])];
+        }
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EXAMPLE: [FileExample;2] = [
+                FileExample::new_get("inventory", vec![
+                    json!({"host": "web1", "ip": "10.0.0.11", "role": "frontend"}),
+                ]),
+                FileExample::new_write("inventory", CsvInput {
+                    delimiter: None,
+                    header: None,
+                    rows: vec![json!({"host": "web1", "ip": "10.0.0.11", "role": "frontend"})],
+                }),
+            ];
+        }
+
+        EXAMPLE.as_slice()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CsvError {
+    #[error("quoted field not terminated")]
+    UnterminatedQuote,
+    #[error("invalid delimiter {0}, expected a single character other than quotes and line breaks")]
+    InvalidDelimiter(String),
+    #[error("invalid value {0}, expected arrays or objects of strings, numbers, booleans or null")]
+    InvalidValue(String),
+    #[error("column {0} is not part of the header")]
+    ColumnUnknown(String),
+    #[error("record {0} has more fields than the header")]
+    TooManyFields(usize),
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_value, json};
+    use crate::files::csv::{detect_delimiter, parse, CsvInput};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let content = read_test_resources("csv");
+        assert_eq!(detect_delimiter(&content), ',');
+
+        let rows = parse(&content, ',').unwrap();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], vec!["host", "ip", "role", "note"]);
+        assert_eq!(rows[1], vec!["web1", "10.0.0.11", "frontend", ""]);
+        assert_eq!(rows[2], vec!["web2", "10.0.0.12", "frontend", "behind \"lb1\", eu"]);
+        assert_eq!(rows[3], vec!["db1", "10.0.0.21", "database", "primary\nreplicated"]);
+
+        assert_eq!(detect_delimiter("\"a;b\",c,d;e\r\n"), ',');
+        assert_eq!(detect_delimiter("a\tb\tc\n"), '\t');
+        assert_eq!(parse("a;b\r\nc;d", ';').unwrap(), vec![vec!["a", "b"], vec!["c", "d"]]);
+        assert!(parse("a,\"b\n", ',').is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let input: CsvInput = from_value(json!({
+            "rows": [
+                {"host": "web1", "ip": "10.0.0.11"},
+                {"host": "db1", "role": "database;primary"},
+                ["lb1", 1, true, null]
+            ]
+        })).unwrap();
+        assert_eq!(input.render("a;b\n").unwrap(), "host;ip;role\nweb1;10.0.0.11;\ndb1;;\"database;primary\"\nlb1;1;true;\n");
+
+        let content = read_test_resources("csv");
+        let rows = parse(&content, ',').unwrap();
+        let input: CsvInput = from_value(json!({"rows": rows})).unwrap();
+        assert_eq!(input.render(&content).unwrap(), content);
+
+        let input: CsvInput = from_value(json!({"header": ["host"], "rows": [{"ip": "10.0.0.1"}]})).unwrap();
+        assert!(input.render("").is_err());
+        let input: CsvInput = from_value(json!({"delimiter": "\"", "rows": []})).unwrap();
+        assert!(input.render("").is_err());
+    }
+}
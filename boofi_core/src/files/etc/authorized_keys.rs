@@ -0,0 +1,390 @@
+use std::fmt::{Display, Formatter};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use regex::Regex;
+use crate::files::prelude::*;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+const KEY_TYPES: [&str; 8] = [
+    "ssh-ed25519",
+    "ssh-rsa",
+    "ecdsa-sha2-nistp256",
+    "ecdsa-sha2-nistp384",
+    "ecdsa-sha2-nistp521",
+    "sk-ssh-ed25519@openssh.com",
+    "sk-ecdsa-sha2-nistp256@openssh.com",
+    "ssh-dss",
+];
+
+/// options:    like `from="192.168.1.0/24"` or `no-pty`
+/// key:        base64 encoded public key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct AuthorizedKey {
+    options: Vec<String>,
+    key_type: String,
+    key: String,
+    comment: Option<String>,
+}
+
+impl Display for AuthorizedKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.options.is_empty() {
+            write!(f, "{} ", self.options.join(","))?;
+        }
+
+        write!(f, "{} {}", self.key_type, self.key)?;
+
+        if let Some(comment) = &self.comment {
+            write!(f, " {}", comment)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl AuthorizedKey {
+    /// Comma separated options until the first whitespace outside of quotes
+    fn parse_options(line: &str) -> Result<(Vec<String>, &str), AuthorizedKeysError> {
+        let mut options = vec![];
+        let mut start = 0;
+        let mut quoted = false;
+        let mut escaped = false;
+
+        for (i, c) in line.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if quoted => escaped = true,
+                '"' => quoted = !quoted,
+                ',' if !quoted => {
+                    options.push(line[start..i].to_string());
+                    start = i + 1;
+                }
+                c if c.is_whitespace() && !quoted => {
+                    options.push(line[start..i].to_string());
+                    return Ok((options, line[i..].trim_start()));
+                }
+                _ => {}
+            }
+        }
+
+        Err(AuthorizedKeysError::InvalidLine(line.into()))
+    }
+
+    fn parse(line: &str) -> Result<Self, AuthorizedKeysError> {
+        let l = line.trim();
+        let first = l.split_whitespace().next().unwrap_or_default();
+
+        let (options, rest) = if KEY_TYPES.contains(&first) {
+            (vec![], l)
+        } else {
+            Self::parse_options(l)?
+        };
+
+        let mut parts = rest.splitn(3, char::is_whitespace);
+
+        match (parts.next(), parts.next()) {
+            (Some(key_type), Some(key)) => Ok(Self {
+                options,
+                key_type: key_type.into(),
+                key: key.into(),
+                comment: parts.next().map(str::trim).filter(|c| !c.is_empty()).map(Into::into),
+            }),
+            _ => Err(AuthorizedKeysError::InvalidLine(line.into())),
+        }
+    }
+
+    /// Known key type matching the type encoded in the key
+    fn validate(&self) -> Result<(), AuthorizedKeysError> {
+        let invalid = || AuthorizedKeysError::InvalidKey(self.to_string());
+
+        if !KEY_TYPES.contains(&self.key_type.as_str()) {
+            return Err(invalid());
+        }
+
+        let decoded = STANDARD.decode(&self.key).map_err(|_| invalid())?;
+        let length = decoded.get(..4)
+            .map(|l| u32::from_be_bytes([l[0], l[1], l[2], l[3]]) as usize)
+            .ok_or_else(invalid)?;
+
+        if decoded.get(4..4 + length) != Some(self.key_type.as_bytes()) {
+            return Err(invalid());
+        }
+
+        Ok(())
+    }
+
+    fn has_comment(&self, comment: &str) -> bool {
+        self.comment.as_deref() == Some(comment)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub enum AuthorizedKeysLine {
+    Comment(String),
+    Empty,
+    Key(AuthorizedKey),
+}
+
+impl Display for AuthorizedKeysLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthorizedKeysLine::Comment(c) => f.write_str(c),
+            AuthorizedKeysLine::Empty => Ok(()),
+            AuthorizedKeysLine::Key(k) => k.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Description)]
+pub struct AuthorizedKeys {
+    content: Vec<AuthorizedKeysLine>,
+}
+
+impl Display for AuthorizedKeys {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.content.iter() {
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl AuthorizedKeys {
+    fn parse(content: &str) -> Resul<Self> {
+        let mut lines = vec![];
+
+        for line in content.lines() {
+            let l = line.trim();
+
+            lines.push(if l.is_empty() {
+                AuthorizedKeysLine::Empty
+            } else if l.starts_with('#') {
+                AuthorizedKeysLine::Comment(line.into())
+            } else {
+                AuthorizedKeysLine::Key(AuthorizedKey::parse(line)?)
+            });
+        }
+
+        Ok(Self {
+            content: lines,
+        })
+    }
+
+    fn keys(&self) -> impl Iterator<Item=&AuthorizedKey> {
+        self.content.iter().filter_map(|l| match l {
+            AuthorizedKeysLine::Key(k) => Some(k),
+            _ => None,
+        })
+    }
+
+    fn add_key(&mut self, key: AuthorizedKey) -> Result<(), AuthorizedKeysError> {
+        key.validate()?;
+
+        if self.keys().any(|k| k.key_type == key.key_type && k.key == key.key) {
+            Err(AuthorizedKeysError::KeyAlreadyExist(key.comment.unwrap_or(key.key)))
+        } else {
+            self.content.push(AuthorizedKeysLine::Key(key));
+            Ok(())
+        }
+    }
+
+    /// Removes all keys with the comment
+    fn remove_key(&mut self, comment: &str) -> Result<(), AuthorizedKeysError> {
+        let len = self.content.len();
+        self.content.retain(|l| !matches!(l, AuthorizedKeysLine::Key(k) if k.has_comment(comment)));
+
+        if len == self.content.len() {
+            Err(AuthorizedKeysError::KeyNotFound(comment.into()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct AuthorizedKeysInput {
+    new_entries: Option<Vec<AuthorizedKey>>,
+    remove_by_comment: Option<Vec<String>>,
+    #[desc(description = "replaces all keys and comments with the new entries")]
+    overwrite: Option<bool>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AuthorizedKeysBuilder;
+
+impl FileBuilder for AuthorizedKeysBuilder {
+    file_metadata!(
+        AuthorizedKeysFile,
+        "authorized_keys",
+        "Managed ssh authorized keys of a user. Keys are validated before writing, comments are preserved.",
+        &[Capability::Read, Capability::Write, Capability::Delete],
+        FileExample::new_get("read keys",
+            AuthorizedKeys {
+                content: vec![
+                    AuthorizedKeysLine::Comment("# workstation".into()),
+                    AuthorizedKeysLine::Key(AuthorizedKey {
+                        options: vec![],
+                        key_type: "ssh-ed25519".into(),
+                        key: "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl".into(),
+                        comment: Some("alice@workstation".into()),
+                    }),
+                ],
+            }
+        ),
+        FileExample::new_write("add a restricted backup key and remove another one",
+            AuthorizedKeysInput {
+                new_entries: Some(vec![AuthorizedKey {
+                    options: vec!["from=\"192.168.1.0/24\"".into(), "no-pty".into(), "command=\"/usr/bin/rrsync /srv/backup\"".into()],
+                    key_type: "ssh-ed25519".into(),
+                    key: "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl".into(),
+                    comment: Some("backup@nas".into()),
+                }]),
+                remove_by_comment: Some(vec!["alice@laptop".into()]),
+                overwrite: Some(false),
+            }
+        ),
+        FileExample::new_delete()
+        ;
+        FileMatchPattern::new_regex(Regex::new("^/home/[^/]+/\\.ssh/authorized_keys$").unwrap(), &[Os::LinuxAny]),
+        FileMatchPattern::new_regex(Regex::new("^/root/\\.ssh/authorized_keys$").unwrap(), &[Os::LinuxAny])
+    );
+}
+
+pub struct AuthorizedKeysFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for AuthorizedKeysFile {
+    type Output = AuthorizedKeys;
+    type Input = AuthorizedKeysInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        AuthorizedKeys::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = AuthorizedKeysInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        let mut keys = if i.overwrite == Some(true) {
+            if i.new_entries.is_none() {
+                return Err(AuthorizedKeysError::NoNewEntries.into());
+            }
+
+            AuthorizedKeys { content: vec![] }
+        } else {
+            AuthorizedKeys::parse(&system.read_to_string(self.path()).await?)?
+        };
+
+        for key in i.new_entries.unwrap_or_default() {
+            keys.add_key(key)?;
+        }
+
+        for comment in i.remove_by_comment.unwrap_or_default().iter() {
+            keys.remove_key(comment)?;
+        }
+
+        Ok(keys.to_string())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        let keys: Vec<&Value> = output["content"].as_array()
+            .map(|lines| lines.iter().filter_map(|l| l.get("Key")).collect())
+            .unwrap_or_default();
+
+        json!({"new_entries": keys, "overwrite": true})
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AuthorizedKeysError {
+    #[error("invalid authorized_keys line {0}")]
+    InvalidLine(String),
+    #[error("invalid key {0}")]
+    InvalidKey(String),
+    #[error("key {0} already exist")]
+    KeyAlreadyExist(String),
+    #[error("key with comment {0} not found")]
+    KeyNotFound(String),
+    #[error("no new entries was given")]
+    NoNewEntries,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::authorized_keys::{AuthorizedKey, AuthorizedKeys, AuthorizedKeysLine};
+    use crate::utils::test::read_test_resources;
+
+    const ED25519: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+
+    #[test]
+    fn test_parse() {
+        let content = read_test_resources("authorized_keys");
+        let keys = AuthorizedKeys::parse(&content).unwrap();
+
+        assert_eq!(keys.content, vec![
+            AuthorizedKeysLine::Comment("# workstation".into()),
+            AuthorizedKeysLine::Key(AuthorizedKey {
+                options: vec![],
+                key_type: "ssh-ed25519".into(),
+                key: ED25519.into(),
+                comment: Some("alice@workstation".into()),
+            }),
+            AuthorizedKeysLine::Empty,
+            AuthorizedKeysLine::Key(AuthorizedKey {
+                options: vec!["from=\"192.168.1.0/24,10.0.0.1\"".into(), "no-pty".into(), "command=\"rrsync -ro /srv/backup\"".into()],
+                key_type: "ssh-ed25519".into(),
+                key: ED25519.into(),
+                comment: Some("backup key".into()),
+            }),
+            AuthorizedKeysLine::Key(AuthorizedKey {
+                options: vec!["restrict".into()],
+                key_type: "ssh-ed25519".into(),
+                key: ED25519.into(),
+                comment: None,
+            }),
+        ]);
+
+        assert_eq!(keys.to_string(), content);
+        assert!(AuthorizedKeys::parse("no-pty\n").is_err());
+    }
+
+    #[test]
+    fn test_add_remove() {
+        let mut keys = AuthorizedKeys::parse(&read_test_resources("authorized_keys")).unwrap();
+
+        let key = AuthorizedKey {
+            options: vec![],
+            key_type: "ssh-ed25519".into(),
+            key: ED25519.into(),
+            comment: Some("dev".into()),
+        };
+
+        assert_eq!(&format!("{:?}", keys.add_key(key.clone())), "Err(KeyAlreadyExist(\"dev\"))");
+        assert!(keys.add_key(AuthorizedKey { key: "AAAAB3NzaC1yc2E=".into(), comment: None, ..key.clone() }).is_err());
+
+        keys.remove_key("backup key").unwrap();
+        assert_eq!(&format!("{:?}", keys.remove_key("backup key")), "Err(KeyNotFound(\"backup key\"))");
+
+        keys.remove_key("alice@workstation").unwrap();
+        keys.add_key(AuthorizedKey {
+            key: "AAAAC3NzaC1lZDI1NTE5AAAAIBoT6g5gqP9i+zhRbm+kP5AB9L3TthNeS6YJbcgxAgkf".into(),
+            comment: Some("alice@laptop".into()),
+            ..key
+        }).unwrap();
+
+        assert_eq!(keys.to_string(), format!("# workstation\n\nrestrict ssh-ed25519 {}\nssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBoT6g5gqP9i+zhRbm+kP5AB9L3TthNeS6YJbcgxAgkf alice@laptop\n", ED25519));
+    }
+}
@@ -1,11 +1,11 @@
 use crate::files::prelude::*;
 use std::mem::take;
-use log::error;
+use tracing::error;
 use regex::Regex;
 use thiserror::Error;
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Description)]
-pub(crate) enum CrontabConfig {
+pub enum CrontabConfig {
     Shell(String),
     Path(String),
 }
@@ -31,8 +31,8 @@ impl CrontabConfig {
     }
 }
 
-#[derive(Debug, PartialEq, Default, Serialize, Deserialize)]
-pub(crate) struct CrontabJobValue {
+#[derive(Debug, PartialEq, Default, Serialize, Deserialize, Description)]
+pub struct CrontabJobValue {
     value: String,
     whitespaces: String,
 }
@@ -50,8 +50,8 @@ impl CrontabJobValue {
 }
 
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub(crate) struct CrontabJob {
+#[derive(Debug, PartialEq, Serialize, Deserialize, Description)]
+pub struct CrontabJob {
     minute: CrontabJobValue,
     hour: CrontabJobValue,
     day_of_month: CrontabJobValue,
@@ -76,7 +76,7 @@ impl ToString for CrontabJob {
 }
 
 impl CrontabJob {
-    pub(crate) fn parse(line: &str) -> Resul<Self> {
+    pub fn parse(line: &str) -> Resul<Self> {
         let mut l = vec![];
         let mut v = CrontabJobValue::default();
 
@@ -121,7 +121,7 @@ impl CrontabJob {
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, PartialEq, Serialize, Deserialize, Description)]
-pub(crate) enum CrontabLine {
+pub enum CrontabLine {
     Comment(String),
     Linebreak,
     Config(CrontabConfig),
@@ -159,7 +159,7 @@ impl CrontabLine {
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Description)]
-pub(crate) struct Crontab {
+pub struct Crontab {
     content: Vec<CrontabLine>,
 }
 
@@ -183,7 +183,7 @@ impl ToString for Crontab {
 }
 
 impl Crontab {
-    pub(crate) fn parse(content: &str) -> Resul<Self> {
+    pub fn parse(content: &str) -> Resul<Self> {
         content.split('\n')
             .map(CrontabLine::parse)
             .collect::<Resul<Vec<CrontabLine>>>()
@@ -196,7 +196,7 @@ impl Crontab {
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct CrontabBuilder;
+pub struct CrontabBuilder;
 
 impl FileBuilder for CrontabBuilder {
     file_metadata!(
@@ -228,7 +228,7 @@ impl FileBuilder for CrontabBuilder {
     );
 }
 
-pub(crate) struct CrontabFile {
+pub struct CrontabFile {
     path: String,
 }
 
@@ -247,9 +247,9 @@ impl File for CrontabFile {
         Crontab::parse(&system.read_to_string(self.path()).await?)
     }
 
-    async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, _system: &System) -> Resul<String> {
         let i = Crontab::deserialize(input).map_err(Erro::from_deserialize)?;
-        system.write(self.path(), i.to_string().as_bytes()).await
+        Ok(i.to_string())
     }
     fn path(&self) -> &str {
         &self.path
@@ -257,7 +257,7 @@ impl File for CrontabFile {
 }
 
 #[derive(Debug, Error)]
-pub(crate) enum CrontabError {
+pub enum CrontabError {
     #[error("unknown crontab config variable")]
     UnknownConfig,
     #[error("failed to parse task")]
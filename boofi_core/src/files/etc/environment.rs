@@ -0,0 +1,390 @@
+use std::fmt::{Display, Formatter};
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::description::InvalidField;
+use crate::files::prelude::*;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+lazy_static! {
+    static ref KEY: Regex = Regex::new("^[A-Za-z_][A-Za-z0-9_]*$").unwrap();
+}
+
+/// Values with whitespace or shell characters are written in double quotes
+fn needs_quote(value: &str) -> bool {
+    value.is_empty() || value.contains(|c: char| c.is_whitespace() || "'\"`$\\;&|<>()#*?[]~".contains(c))
+}
+
+/// value:  without the surrounding quotes, `$VAR` is expanded by the shell in `/etc/profile.d` but not by pam_env
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct EnvironmentVariable {
+    key: String,
+    value: String,
+    #[serde(skip)]
+    export: bool,
+    #[serde(skip)]
+    quote: Option<char>,
+}
+
+impl Display for EnvironmentVariable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.export {
+            f.write_str("export ")?;
+        }
+
+        match self.quote {
+            Some(q) => write!(f, "{}={}{}{}", self.key, q, self.value, q),
+            None => write!(f, "{}={}", self.key, self.value),
+        }
+    }
+}
+
+impl EnvironmentVariable {
+    /// `KEY=value`, `KEY="value"` or `export KEY='value'`, the export is required in `/etc/profile.d`
+    fn parse(line: &str, profile: bool) -> Option<Self> {
+        let l = line.trim();
+        let (export, l) = match l.strip_prefix("export ") {
+            Some(l) => (true, l.trim_start()),
+            None => (false, l),
+        };
+
+        let (key, value) = l.split_once('=')?;
+        if (profile && !export) || !KEY.is_match(key) {
+            return None;
+        }
+
+        let quote = value.chars().next().filter(|q| (*q == '"' || *q == '\'') && value.len() > 1 && value.ends_with(*q));
+        let value = match quote {
+            Some(_) => &value[1..value.len() - 1],
+            None => value,
+        };
+
+        let simple = match quote {
+            Some(q) => !value.contains(q),
+            None => value.is_empty() || !needs_quote(value),
+        };
+        if !simple {
+            return None;
+        }
+
+        Some(Self {
+            key: key.into(),
+            value: value.into(),
+            export,
+            quote,
+        })
+    }
+
+    fn validate(&self, path: &str, invalid: &mut Vec<InvalidField>) {
+        if !KEY.is_match(&self.key) {
+            invalid.push(InvalidField::value(format!("{}.key", path), "letters, digits and underscores not starting with a digit"));
+        }
+        if self.value.contains(['\n', self.quote.unwrap_or('"')]) {
+            invalid.push(InvalidField::value(format!("{}.value", path), "single line without the quote character"));
+        }
+    }
+}
+
+/// Line continuations and other shell code are kept as `Other`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub enum EnvironmentLine {
+    Comment(String),
+    Empty,
+    Variable(EnvironmentVariable),
+    Other(String),
+}
+
+impl Display for EnvironmentLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnvironmentLine::Comment(c) | EnvironmentLine::Other(c) => f.write_str(c),
+            EnvironmentLine::Empty => Ok(()),
+            EnvironmentLine::Variable(v) => v.fmt(f),
+        }
+    }
+}
+
+impl EnvironmentLine {
+    fn parse(line: &str, profile: bool) -> Self {
+        let l = line.trim();
+
+        if l.is_empty() {
+            Self::Empty
+        } else if l.starts_with('#') {
+            Self::Comment(line.into())
+        } else {
+            EnvironmentVariable::parse(line, profile).map(Self::Variable).unwrap_or_else(|| Self::Other(line.into()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct Environment {
+    lines: Vec<EnvironmentLine>,
+}
+
+impl Display for Environment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Environment {
+    /// `profile` files in `/etc/profile.d` are shell scripts, only exported variables are managed there
+    pub fn parse(content: &str, profile: bool) -> Self {
+        Self {
+            lines: content.lines().map(|l| EnvironmentLine::parse(l, profile)).collect(),
+        }
+    }
+
+    fn validate(&self) -> Resul<()> {
+        let mut invalid = vec![];
+
+        for (i, line) in self.lines.iter().enumerate() {
+            match line {
+                EnvironmentLine::Comment(comment) if !comment.trim_start().starts_with('#') || comment.contains('\n') =>
+                    invalid.push(InvalidField::value(format!("lines[{}].Comment", i), "single line starting with #")),
+                EnvironmentLine::Other(other) if other.contains('\n') =>
+                    invalid.push(InvalidField::value(format!("lines[{}].Other", i), "single line")),
+                EnvironmentLine::Variable(v) => v.validate(&format!("lines[{}].Variable", i), &mut invalid),
+                _ => {}
+            }
+        }
+
+        if invalid.is_empty() { Ok(()) } else { Err(Erro::InputInvalid(invalid)) }
+    }
+
+    /// Changes the value of every line of the key and keeps its formatting, a missing key is appended
+    fn set(&mut self, variable: &EnvironmentVariable, profile: bool) {
+        let quote = |current: Option<char>| current.or_else(|| needs_quote(&variable.value).then_some('"'));
+        let mut found = false;
+
+        for line in self.lines.iter_mut() {
+            if let EnvironmentLine::Variable(v) = line {
+                if v.key == variable.key {
+                    v.value = variable.value.clone();
+                    v.quote = quote(v.quote);
+                    found = true;
+                }
+            }
+        }
+
+        if !found {
+            self.lines.push(EnvironmentLine::Variable(EnvironmentVariable {
+                key: variable.key.clone(),
+                value: variable.value.clone(),
+                export: profile,
+                quote: quote(None),
+            }));
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), EnvironmentError> {
+        let len = self.lines.len();
+        self.lines.retain(|l| !matches!(l, EnvironmentLine::Variable(v) if v.key == key));
+
+        if len == self.lines.len() {
+            Err(EnvironmentError::KeyNotFound(key.into()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct EnvironmentInput {
+    #[desc(description = "replaces the whole file")]
+    content: Option<Environment>,
+    #[desc(description = "variables changed in place, appended if missing")]
+    set: Option<Vec<EnvironmentVariable>>,
+    #[desc(description = "keys of variables which are removed")]
+    remove: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnvironmentBuilder;
+
+impl FileBuilder for EnvironmentBuilder {
+    file_metadata!(
+        EnvironmentFile,
+        "environment",
+        "Read and write environment variables of `/etc/environment` for all sessions and `export KEY=value` lines of `/etc/profile.d/*.sh` for login shells. Unknown lines are preserved.",
+        &[Capability::Read, Capability::Write, Capability::Delete],
+        FileExample::new_get("read environment",
+            Environment {
+                lines: vec![
+                    EnvironmentLine::Variable(EnvironmentVariable {
+                        key: "PATH".into(),
+                        value: "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin".into(),
+                        export: false,
+                        quote: Some('"'),
+                    }),
+                    EnvironmentLine::Variable(EnvironmentVariable {
+                        key: "LANG".into(),
+                        value: "en_US.UTF-8".into(),
+                        export: false,
+                        quote: None,
+                    }),
+                ],
+            }
+        ),
+        FileExample::new_write("configure a proxy",
+            EnvironmentInput {
+                content: None,
+                set: Some(vec![
+                    EnvironmentVariable { key: "http_proxy".into(), value: "http://proxy.example.com:3128".into(), export: false, quote: None },
+                    EnvironmentVariable { key: "no_proxy".into(), value: "localhost,127.0.0.1".into(), export: false, quote: None },
+                ]),
+                remove: Some(vec!["https_proxy".into()]),
+            }
+        ),
+        FileExample::new_delete()
+        ;
+        FileMatchPattern::new_regex(Regex::new("^/etc/environment$").unwrap(), &[Os::LinuxAny]),
+        FileMatchPattern::new_regex(Regex::new("^/etc/profile\\.d/[^/]+\\.sh$").unwrap(), &[Os::LinuxAny])
+    );
+}
+
+pub struct EnvironmentFile {
+    path: String,
+}
+
+impl EnvironmentFile {
+    fn profile(&self) -> bool {
+        self.path.starts_with("/etc/profile.d/")
+    }
+}
+
+#[async_trait]
+impl File for EnvironmentFile {
+    type Output = Environment;
+    type Input = EnvironmentInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        Ok(Environment::parse(&system.read_to_string(self.path()).await?, self.profile()))
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = EnvironmentInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        let mut environment = match i.content {
+            Some(mut content) => {
+                // formatting isn't part of the input, new lines get the one of the file
+                for line in content.lines.iter_mut() {
+                    if let EnvironmentLine::Variable(v) = line {
+                        v.export = self.profile();
+                        v.quote = needs_quote(&v.value).then_some('"');
+                    }
+                }
+                content
+            }
+            None => Environment::parse(&system.read_to_string(self.path()).await?, self.profile()),
+        };
+
+        for variable in i.set.unwrap_or_default().iter() {
+            environment.set(variable, self.profile());
+        }
+
+        for key in i.remove.unwrap_or_default().iter() {
+            environment.remove(key)?;
+        }
+
+        environment.validate()?;
+        Ok(environment.to_string())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"content": output})
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum EnvironmentError {
+    #[error("variable {0} not found")]
+    KeyNotFound(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::Erro;
+    use crate::files::environment::{Environment, EnvironmentLine, EnvironmentVariable};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let content = read_test_resources("environment");
+        let environment = Environment::parse(&content, false);
+
+        assert_eq!(environment.lines.len(), 7);
+        assert_eq!(environment.lines[0], EnvironmentLine::Variable(EnvironmentVariable {
+            key: "PATH".into(),
+            value: "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin:/usr/games:/usr/local/games:/snap/bin".into(),
+            export: false,
+            quote: Some('"'),
+        }));
+        assert_eq!(environment.lines[1], EnvironmentLine::Variable(EnvironmentVariable { key: "LANG".into(), value: "en_US.UTF-8".into(), export: false, quote: None }));
+        assert_eq!(environment.lines[3], EnvironmentLine::Variable(EnvironmentVariable {
+            key: "EDITOR".into(),
+            value: "vim".into(),
+            export: true,
+            quote: Some('\''),
+        }));
+        assert_eq!(environment.lines[5], EnvironmentLine::Other("JAVA_OPTS=-Xmx1g -Xms256m".into()));
+        assert_eq!(environment.lines[6], EnvironmentLine::Other("1INVALID=x".into()));
+        assert_eq!(environment.to_string(), content);
+
+        let profile = Environment::parse("# proxy\nexport http_proxy=http://proxy:3128\nno_proxy=localhost\nif [ -n \"$PS1\" ]; then umask 022; fi\n", true);
+        assert_eq!(profile.lines[1], EnvironmentLine::Variable(EnvironmentVariable {
+            key: "http_proxy".into(),
+            value: "http://proxy:3128".into(),
+            export: true,
+            quote: None,
+        }));
+        assert_eq!(profile.lines[2], EnvironmentLine::Other("no_proxy=localhost".into()));
+        assert!(matches!(profile.lines[3], EnvironmentLine::Other(_)));
+    }
+
+    #[test]
+    fn test_set_remove() {
+        let mut environment = Environment::parse(&read_test_resources("environment"), false);
+
+        environment.set(&EnvironmentVariable { key: "LANG".into(), value: "de_DE.UTF-8".into(), export: false, quote: None }, false);
+        assert_eq!(environment.lines[1].to_string(), "LANG=de_DE.UTF-8");
+
+        environment.set(&EnvironmentVariable { key: "EDITOR".into(), value: "nano".into(), export: false, quote: None }, false);
+        assert_eq!(environment.lines[3].to_string(), "export EDITOR='nano'");
+
+        environment.set(&EnvironmentVariable { key: "GREETING".into(), value: "hello world".into(), export: false, quote: None }, false);
+        assert_eq!(environment.lines[7].to_string(), "GREETING=\"hello world\"");
+
+        let mut profile = Environment::parse("", true);
+        profile.set(&EnvironmentVariable { key: "PATH".into(), value: "$PATH:/opt/bin".into(), export: false, quote: None }, true);
+        assert_eq!(profile.to_string(), "export PATH=\"$PATH:/opt/bin\"\n");
+
+        environment.remove("LANG").unwrap();
+        assert_eq!(environment.lines.len(), 7);
+        assert!(environment.remove("LANG").is_err());
+        environment.validate().unwrap();
+
+        environment.set(&EnvironmentVariable { key: "BAD KEY".into(), value: "a\"b".into(), export: false, quote: None }, false);
+        let Err(Erro::InputInvalid(invalid)) = environment.validate() else { panic!("environment has to be invalid") };
+        assert_eq!(invalid.iter().map(|i| i.field.as_str()).collect::<Vec<&str>>(), vec![
+            "lines[7].Variable.key",
+            "lines[7].Variable.value",
+        ]);
+    }
+}
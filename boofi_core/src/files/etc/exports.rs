@@ -0,0 +1,405 @@
+use std::fmt::{Display, Formatter};
+use regex::Regex;
+use crate::description::InvalidField;
+use crate::files::prelude::*;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// host:       like `192.168.1.0/24`, `*.example.com`, `@netgroup` or `*` for everybody
+/// options:    like `rw`, `sync` or `no_subtree_check`, the defaults of exportfs if empty
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct ExportsClient {
+    host: String,
+    options: Vec<String>,
+}
+
+impl Display for ExportsClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.host)?;
+        if !self.options.is_empty() {
+            write!(f, "({})", self.options.join(","))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// path:               exported directory, quoted when it contains whitespace
+/// default_options:    written as `-options` after the path, used by all following clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct ExportsEntry {
+    path: String,
+    default_options: Vec<String>,
+    clients: Vec<ExportsClient>,
+}
+
+impl Display for ExportsEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.contains(char::is_whitespace) {
+            write!(f, "\"{}\"", self.path)?;
+        } else {
+            f.write_str(&self.path)?;
+        }
+
+        if !self.default_options.is_empty() {
+            write!(f, "\t-{}", self.default_options.join(","))?;
+        }
+
+        for client in self.clients.iter() {
+            write!(f, "\t{}", client)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ExportsEntry {
+    /// Values which can't be parsed back or which exportfs would reject
+    fn validate(&self, path: &str, invalid: &mut Vec<InvalidField>) {
+        let mut check = |valid: bool, field: &str, expected: &'static str| if !valid {
+            invalid.push(InvalidField::value(format!("{}.{}", path, field), expected));
+        };
+        let option = |o: &String| Self::word(o) && !o.contains([',', '(', ')']);
+
+        check(self.path.starts_with('/') && !self.path.contains(['"', '\n']), "path", "absolute path without quotes or line breaks");
+        check(self.default_options.iter().all(option), "default_options", "options without whitespace, commas or parentheses");
+        check(!self.clients.is_empty(), "clients", "at least one client, `*` for everybody");
+
+        for (i, client) in self.clients.iter().enumerate() {
+            check(Self::word(&client.host) && !client.host.contains(['(', ')']), &format!("clients[{}].host", i),
+                  "host, network or wildcard without whitespace or parentheses");
+            check(client.options.iter().all(option), &format!("clients[{}].options", i),
+                  "options without whitespace, commas or parentheses");
+        }
+    }
+
+    /// Non-empty value without whitespace, which would split the columns
+    fn word(value: &str) -> bool {
+        !value.is_empty() && !value.chars().any(char::is_whitespace)
+    }
+
+    /// `/srv/nfs -ro client(rw,sync) other`
+    fn parse(line: &str) -> Option<Self> {
+        let (path, rest) = match line.strip_prefix('"') {
+            Some(l) => l.split_once('"')?,
+            None => line.split_once(char::is_whitespace).unwrap_or((line, "")),
+        };
+
+        let mut default_options = vec![];
+        let mut clients = vec![];
+
+        for item in rest.split_whitespace() {
+            if let Some(options) = item.strip_prefix('-') {
+                default_options.extend(options.split(',').map(String::from));
+            } else if let Some((host, options)) = item.split_once('(') {
+                clients.push(ExportsClient {
+                    host: host.into(),
+                    options: options.strip_suffix(')')?.split(',').map(String::from).collect(),
+                });
+            } else {
+                clients.push(ExportsClient {
+                    host: item.into(),
+                    options: vec![],
+                });
+            }
+        }
+
+        Some(Self {
+            path: path.into(),
+            default_options,
+            clients,
+        })
+    }
+}
+
+/// Line continuations with a backslash are joined
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub enum ExportsLine {
+    Comment(String),
+    Empty,
+    Export(ExportsEntry),
+}
+
+impl Display for ExportsLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportsLine::Comment(c) => f.write_str(c),
+            ExportsLine::Empty => Ok(()),
+            ExportsLine::Export(e) => e.fmt(f),
+        }
+    }
+}
+
+impl ExportsLine {
+    fn parse(line: &str) -> Result<Self, ExportsError> {
+        let l = line.trim();
+
+        if l.is_empty() {
+            Ok(Self::Empty)
+        } else if l.starts_with('#') {
+            Ok(Self::Comment(line.into()))
+        } else {
+            ExportsEntry::parse(l).map(Self::Export).ok_or(ExportsError::InvalidLine(line.into()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct Exports {
+    lines: Vec<ExportsLine>,
+}
+
+impl Display for Exports {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Exports {
+    pub fn parse(content: &str) -> Resul<Self> {
+        let mut lines = vec![];
+        let mut continued = String::new();
+
+        for line in content.lines() {
+            if let Some(l) = line.trim_end().strip_suffix('\\') {
+                continued.push_str(l);
+                continued.push(' ');
+                continue;
+            }
+
+            continued.push_str(line);
+            lines.push(ExportsLine::parse(&continued)?);
+            continued.clear();
+        }
+
+        Ok(Self {
+            lines,
+        })
+    }
+
+    fn validate(&self) -> Resul<()> {
+        let mut invalid = vec![];
+
+        for (i, line) in self.lines.iter().enumerate() {
+            match line {
+                ExportsLine::Comment(comment) if !comment.trim_start().starts_with('#') || comment.contains('\n') =>
+                    invalid.push(InvalidField::value(format!("lines[{}].Comment", i), "single line starting with #")),
+                ExportsLine::Export(entry) => entry.validate(&format!("lines[{}].Export", i), &mut invalid),
+                _ => {}
+            }
+        }
+
+        if invalid.is_empty() { Ok(()) } else { Err(Erro::InputInvalid(invalid)) }
+    }
+
+    /// Replaces the export of the same path or appends it
+    fn set(&mut self, entry: &ExportsEntry) {
+        let line = ExportsLine::Export(entry.clone());
+
+        match self.lines.iter_mut().find(|l| matches!(l, ExportsLine::Export(e) if e.path == entry.path)) {
+            Some(l) => *l = line,
+            None => self.lines.push(line),
+        }
+    }
+
+    fn remove(&mut self, path: &str) -> Result<(), ExportsError> {
+        let len = self.lines.len();
+        self.lines.retain(|l| !matches!(l, ExportsLine::Export(e) if e.path == path));
+
+        if len == self.lines.len() {
+            Err(ExportsError::ExportNotFound(path.into()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct ExportsInput {
+    #[desc(description = "replaces the whole file")]
+    content: Option<Exports>,
+    #[desc(description = "exports replacing the one of the same path, appended if missing")]
+    set: Option<Vec<ExportsEntry>>,
+    #[desc(description = "paths of exports which are removed")]
+    remove: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ExportsBuilder;
+
+impl FileBuilder for ExportsBuilder {
+    file_metadata!(
+        ExportsFile,
+        "exports",
+        "Read and write the directories exported by the nfs server. Apply changes with `exportfs -ra`, e.g. by a file hook.",
+        &[Capability::Read, Capability::Write, Capability::Delete],
+        FileExample::new_get("read exports",
+            Exports {
+                lines: vec![
+                    ExportsLine::Comment("# /etc/exports: the access control list for filesystems which may be exported".into()),
+                    ExportsLine::Export(ExportsEntry {
+                        path: "/srv/nfs/share".into(),
+                        default_options: vec![],
+                        clients: vec![ExportsClient {
+                            host: "192.168.1.0/24".into(),
+                            options: vec!["rw".into(), "sync".into(), "no_subtree_check".into()],
+                        }],
+                    }),
+                ],
+            }
+        ),
+        FileExample::new_write("export backups read only to everybody and writable to one host",
+            ExportsInput {
+                content: None,
+                set: Some(vec![ExportsEntry {
+                    path: "/srv/nfs/backups".into(),
+                    default_options: vec![],
+                    clients: vec![
+                        ExportsClient { host: "backup.example.com".into(), options: vec!["rw".into(), "sync".into(), "no_root_squash".into()] },
+                        ExportsClient { host: "*".into(), options: vec!["ro".into()] },
+                    ],
+                }]),
+                remove: Some(vec!["/srv/nfs/old".into()]),
+            }
+        ),
+        FileExample::new_delete()
+        ;
+        FileMatchPattern::new_regex(Regex::new("^/etc/exports$").unwrap(), &[Os::LinuxAny]),
+        FileMatchPattern::new_regex(Regex::new("^/etc/exports\\.d/[^/]+\\.exports$").unwrap(), &[Os::LinuxAny])
+    );
+}
+
+pub struct ExportsFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for ExportsFile {
+    type Output = Exports;
+    type Input = ExportsInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        Exports::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = ExportsInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        let mut exports = match i.content {
+            Some(content) => content,
+            None => Exports::parse(&system.read_to_string(self.path()).await?)?,
+        };
+
+        for entry in i.set.unwrap_or_default().iter() {
+            exports.set(entry);
+        }
+
+        for path in i.remove.unwrap_or_default().iter() {
+            exports.remove(path)?;
+        }
+
+        exports.validate()?;
+        Ok(exports.to_string())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"content": output})
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExportsError {
+    #[error("invalid exports line {0}")]
+    InvalidLine(String),
+    #[error("export {0} not found")]
+    ExportNotFound(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::Erro;
+    use crate::files::exports::{Exports, ExportsClient, ExportsEntry, ExportsLine};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let exports = Exports::parse(&read_test_resources("exports")).unwrap();
+
+        assert_eq!(exports.lines.len(), 9);
+        assert_eq!(exports.lines[3], ExportsLine::Export(ExportsEntry {
+            path: "/srv/nfs/share".into(),
+            default_options: vec![],
+            clients: vec![ExportsClient { host: "192.168.1.0/24".into(), options: vec!["rw".into(), "sync".into(), "no_subtree_check".into()] }],
+        }));
+        assert_eq!(exports.lines[4], ExportsLine::Export(ExportsEntry {
+            path: "/srv/nfs/backups".into(),
+            default_options: vec![],
+            clients: vec![ExportsClient { host: "backup.example.com".into(), options: vec!["rw".into(), "sync".into(), "no_root_squash".into()] }, ExportsClient { host: "*".into(), options: vec!["ro".into()] }],
+        }));
+        assert_eq!(exports.lines[5], ExportsLine::Export(ExportsEntry {
+            path: "/srv/nfs/public files".into(),
+            default_options: vec!["ro".into(), "all_squash".into()],
+            clients: vec![ExportsClient { host: "*.example.com".into(), options: vec![] }, ExportsClient { host: "@trusted".into(), options: vec!["rw".into()] }],
+        }));
+        assert_eq!(exports.lines[7], ExportsLine::Export(ExportsEntry {
+            path: "/srv/nfs/home".into(),
+            default_options: vec![],
+            clients: vec![ExportsClient { host: "10.0.0.1".into(), options: vec!["rw".into()] }, ExportsClient { host: "10.0.0.2".into(), options: vec!["rw".into()] }],
+        }));
+        assert!(matches!(&exports.lines[0], ExportsLine::Comment(c) if c.starts_with("# /etc/exports")));
+
+        assert_eq!(Exports::parse(&exports.to_string()).unwrap(), exports);
+        assert_eq!(exports.lines[5].to_string(), "\"/srv/nfs/public files\"\t-ro,all_squash\t*.example.com\t@trusted(rw)");
+
+        assert!(Exports::parse("/srv host(rw\n").is_err());
+        assert!(Exports::parse("\"/srv host(rw)\n").is_err());
+    }
+
+    #[test]
+    fn test_set_remove() {
+        let mut exports = Exports::parse(&read_test_resources("exports")).unwrap();
+
+        let share = ExportsEntry {
+            path: "/srv/nfs/share".into(),
+            default_options: vec![],
+            clients: vec![ExportsClient { host: "*".into(), options: vec!["ro".into()] }],
+        };
+        exports.set(&share);
+        assert_eq!(exports.lines[3], ExportsLine::Export(share));
+
+        exports.set(&ExportsEntry { path: "/srv/nfs/new".into(), default_options: vec![], clients: vec![ExportsClient { host: "*".into(), options: vec![] }] });
+        assert_eq!(exports.lines.len(), 10);
+        assert_eq!(exports.lines[9].to_string(), "/srv/nfs/new\t*");
+
+        exports.remove("/srv/nfs/backups").unwrap();
+        assert_eq!(exports.lines.len(), 9);
+        assert!(exports.remove("/srv/nfs/backups").is_err());
+        exports.validate().unwrap();
+
+        exports.set(&ExportsEntry { path: "srv".into(), default_options: vec![], clients: vec![ExportsClient { host: "a b".into(), options: vec!["rw,sync".into()] }] });
+        exports.set(&ExportsEntry { path: "/srv/empty".into(), default_options: vec!["r o".into()], clients: vec![] });
+
+        let Err(Erro::InputInvalid(invalid)) = exports.validate() else { panic!("exports have to be invalid") };
+        assert_eq!(invalid.iter().map(|i| i.field.as_str()).collect::<Vec<&str>>(), vec![
+            "lines[9].Export.path",
+            "lines[9].Export.clients[0].host",
+            "lines[9].Export.clients[0].options",
+            "lines[10].Export.default_options",
+            "lines[10].Export.clients",
+        ]);
+    }
+}
@@ -1,9 +1,26 @@
 use std::fmt::Display;
 use std::mem::take;
+use crate::description::InvalidField;
 use crate::files::prelude::*;
 
+/// Filesystems on a block device, which is referenced by a path or a tag like `UUID=`
+const BLOCK_FILESYSTEMS: &[&str] = &[
+    "ext2", "ext3", "ext4", "xfs", "btrfs", "vfat", "exfat", "ntfs", "ntfs3", "f2fs", "jfs", "reiserfs",
+    "iso9660", "udf", "swap",
+];
+
+/// Virtual, network and stacked filesystems with an arbitrary device name, `fuse.*` types are known as well
+const OTHER_FILESYSTEMS: &[&str] = &[
+    "auto", "none", "bind", "tmpfs", "ramfs", "proc", "sysfs", "devpts", "devtmpfs", "cgroup", "cgroup2",
+    "securityfs", "debugfs", "tracefs", "hugetlbfs", "mqueue", "efivarfs", "overlay", "squashfs", "zfs",
+    "nfs", "nfs4", "cifs", "smb3", "9p", "virtiofs", "fuse",
+];
+
+/// Tags of block devices resolved by mount
+const DEVICE_TAGS: &[&str] = &["UUID=", "LABEL=", "PARTUUID=", "PARTLABEL="];
+
 #[derive(PartialEq, Debug, Serialize, Deserialize, Default, Description)]
-pub(crate) struct FstabItem<T> {
+pub struct FstabItem<T> {
     value: T,
     delimiter: String,
 }
@@ -15,7 +32,7 @@ impl<T: Display> ToString for FstabItem<T> {
 }
 
 #[derive(PartialEq, Debug, Serialize, Deserialize, Description)]
-pub(crate) struct FstabEntry {
+pub struct FstabEntry {
     device: FstabItem<String>,
     target: FstabItem<String>,
     filesystem: FstabItem<String>,
@@ -61,6 +78,48 @@ impl TryFrom<FstabItem<String>> for FstabItem<Vec<String>> {
 
 
 impl FstabEntry {
+    /// Values which can't be parsed back or which mount would reject at boot
+    fn validate(&self, path: &str, invalid: &mut Vec<InvalidField>) {
+        let mut check = |valid: bool, field: &str, expected: &'static str| if !valid {
+            invalid.push(InvalidField::value(format!("{}.{}", path, field), expected));
+        };
+
+        let filesystem = self.filesystem.value.as_str();
+        let block = BLOCK_FILESYSTEMS.contains(&filesystem);
+
+        check(Self::device_valid(&self.device.value, block), "device.value",
+              "UUID=, LABEL=, PARTUUID=, PARTLABEL= or an absolute path for block filesystems");
+        check(Self::word(&self.target.value) && (self.target.value.starts_with('/') || self.target.value == "none"), "target.value",
+              "absolute path or none");
+        check(block || OTHER_FILESYSTEMS.contains(&filesystem) || filesystem.starts_with("fuse."), "filesystem.value",
+              "known filesystem e.g. ext4, xfs, vfat, swap, tmpfs or nfs");
+        check(!self.options.value.is_empty() && self.options.value.iter().all(|o| Self::word(o) && !o.contains(',')), "options.value",
+              "non-empty options without whitespace or commas");
+        check(self.dump.value <= 1, "dump.value", "0 or 1");
+        check(self.fsck.value <= 2, "fsck.value", "0, 1 or 2");
+
+        for (field, delimiter) in [("device", &self.device.delimiter), ("target", &self.target.delimiter),
+            ("filesystem", &self.filesystem.delimiter), ("options", &self.options.delimiter), ("dump", &self.dump.delimiter)] {
+            check(!delimiter.is_empty() && delimiter.chars().all(|c| c == ' ' || c == '\t'), &format!("{}.delimiter", field),
+                  "spaces or tabs");
+        }
+        check(self.fsck.delimiter.chars().all(|c| c == ' ' || c == '\t'), "fsck.delimiter", "spaces, tabs or nothing");
+    }
+
+    fn device_valid(device: &str, block: bool) -> bool {
+        if let Some(tag) = DEVICE_TAGS.iter().find(|t| device.starts_with(*t)) {
+            let value = &device[tag.len()..];
+            return Self::word(value) && (*tag != "UUID=" || value.chars().all(|c| c.is_ascii_hexdigit() || c == '-'));
+        }
+
+        Self::word(device) && (!block || device.starts_with('/'))
+    }
+
+    /// Non-empty value without whitespace, which would split the fstab columns
+    fn word(value: &str) -> bool {
+        !value.is_empty() && !value.chars().any(char::is_whitespace)
+    }
+
     fn parse(line: &str) -> Resul<Self> {
         let mut items: Vec<FstabItem<String>> = vec![];
         let mut is_new = false;
@@ -97,7 +156,7 @@ impl FstabEntry {
 
 #[allow(clippy::large_enum_variant)]
 #[derive(PartialEq, Debug, Serialize, Deserialize, Description)]
-pub(crate) enum FstabLine {
+pub enum FstabLine {
     Comment(String),
     Empty,
     Entry(FstabEntry),
@@ -126,11 +185,26 @@ impl FstabLine {
 }
 
 #[derive(PartialEq, Debug, Serialize, Deserialize, Description)]
-pub(crate) struct Fstab {
+pub struct Fstab {
     content: Vec<FstabLine>,
 }
 
 impl Fstab {
+    fn validate(&self) -> Resul<()> {
+        let mut invalid = vec![];
+
+        for (i, line) in self.content.iter().enumerate() {
+            match line {
+                FstabLine::Comment(comment) if !comment.starts_with('#') || comment.contains('\n') =>
+                    invalid.push(InvalidField::value(format!("content[{}].Comment", i), "single line starting with #")),
+                FstabLine::Entry(entry) => entry.validate(&format!("content[{}].Entry", i), &mut invalid),
+                _ => {}
+            }
+        }
+
+        if invalid.is_empty() { Ok(()) } else { Err(Erro::InputInvalid(invalid)) }
+    }
+
     fn parse(content: &str) -> Resul<Self> {
         Ok(Self {
             content: content.split('\n')
@@ -146,7 +220,7 @@ impl ToString for Fstab {
     }
 }
 
-pub(crate) struct FstabFile {
+pub struct FstabFile {
     path: String,
 }
 
@@ -165,17 +239,20 @@ impl File for FstabFile {
         Fstab::parse(&system.read_to_string(self.path()).await?)
     }
 
-    async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, _system: &System) -> Resul<String> {
         let fstab = Fstab::deserialize(input).map_err(Erro::from_deserialize)?;
-        system.write(self.path(), fstab.to_string().as_bytes()).await
+        fstab.validate()?;
+        Ok(fstab.to_string())
     }
+
+
     fn path(&self) -> &str {
         &self.path
     }
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct FstabBuilder;
+pub struct FstabBuilder;
 
 impl FileBuilder for FstabBuilder {
     file_metadata!(
@@ -205,6 +282,8 @@ impl FileBuilder for FstabBuilder {
 
 #[cfg(test)]
 mod test {
+    use crate::description::InvalidField;
+    use crate::error::Erro;
     use crate::files::fstab::{Fstab, FstabEntry, FstabItem};
     use crate::files::fstab::FstabLine::{Comment, Empty, Entry};
 
@@ -250,4 +329,51 @@ mod test {
         assert_eq!(Fstab::parse(&content).unwrap(), fstab);
         assert_eq!(fstab.to_string(), content);
     }
+
+    #[test]
+    fn test_validate() {
+        let mut fstab = Fstab::parse(&read_test_resources("fstab")).unwrap();
+        fstab.validate().unwrap();
+
+        fstab.content.push(Entry(FstabEntry {
+            device: FstabItem { value: "tmpfs".into(), delimiter: " ".into() },
+            target: FstabItem { value: "/tmp".into(), delimiter: " ".into() },
+            filesystem: FstabItem { value: "tmpfs".into(), delimiter: " ".into() },
+            options: FstabItem { value: vec!["defaults".into()], delimiter: " ".into() },
+            dump: FstabItem { value: 0, delimiter: " ".into() },
+            fsck: FstabItem { value: 0, delimiter: "".into() },
+        }));
+        fstab.validate().unwrap();
+
+        fstab.content.push(Entry(FstabEntry {
+            device: FstabItem { value: "UUID=not-a-uuid".into(), delimiter: " ".into() },
+            target: FstabItem { value: "data".into(), delimiter: "".into() },
+            filesystem: FstabItem { value: "ext5".into(), delimiter: " ".into() },
+            options: FstabItem { value: vec!["no auto".into()], delimiter: " ".into() },
+            dump: FstabItem { value: 2, delimiter: " ".into() },
+            fsck: FstabItem { value: 3, delimiter: "".into() },
+        }));
+        fstab.content.push(Comment("no comment".into()));
+
+        let Err(Erro::InputInvalid(invalid)) = fstab.validate() else { panic!("fstab has to be invalid") };
+        assert_eq!(invalid.iter().map(|i| i.field.as_str()).collect::<Vec<&str>>(), vec![
+            "content[9].Entry.device.value",
+            "content[9].Entry.target.value",
+            "content[9].Entry.filesystem.value",
+            "content[9].Entry.options.value",
+            "content[9].Entry.dump.value",
+            "content[9].Entry.fsck.value",
+            "content[9].Entry.target.delimiter",
+            "content[10].Comment",
+        ]);
+        assert_eq!(invalid[4], InvalidField::value("content[9].Entry.dump.value".into(), "0 or 1"));
+        assert_eq!(invalid[4].to_string(), "content[9].Entry.dump.value has an invalid value, expected 0 or 1");
+
+        // block filesystems need a path or tag
+        fstab.content.truncate(7);
+        let Some(Entry(entry)) = fstab.content.last_mut() else { panic!("last line has to be an entry") };
+        entry.filesystem.value = "ext4".into();
+        entry.device.value = "tmpfs".into();
+        assert!(fstab.validate().is_err());
+    }
 }
@@ -0,0 +1,354 @@
+use std::fmt::{Display, Formatter};
+use std::vec;
+use crate::files::prelude::*;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Description)]
+pub struct GroupEntry {
+    name: String,
+    password: String,
+    group_id: usize,
+    members: Vec<String>,
+}
+
+impl Display for GroupEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}:{}",
+               self.name,
+               self.password,
+               self.group_id,
+               self.members.join(","),
+        )
+    }
+}
+
+impl TryFrom<String> for GroupEntry {
+    type Error = Erro;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut parts: Vec<String> = value.split(':').map(ToString::to_string).collect();
+
+        if parts.len() != 4 {
+            return Err(GroupError::InvalidLine(value).into());
+        }
+
+        Ok(Self {
+            name: parts.remove(0),
+            password: parts.remove(0),
+            group_id: parts.remove(0).parse()?,
+            members: parts.remove(0)
+                .split(',')
+                .filter(|s| !s.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+        })
+    }
+}
+
+/// Membership of a single user in a group
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Description)]
+pub struct GroupMember {
+    group: String,
+    user: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Description)]
+pub struct Group {
+    content: Vec<GroupEntry>,
+}
+
+impl Group {
+    fn parse(content: &str) -> Resul<Self> {
+        content.split('\n')
+            .filter_map(|s| {
+                if s.is_empty() {
+                    None
+                } else {
+                    Some(GroupEntry::try_from(s.to_string()))
+                }
+            })
+            .collect::<Resul<Vec<GroupEntry>>>()
+            .map(|entries| {
+                Self {
+                    content: entries
+                }
+            })
+    }
+
+    fn content_string(&self) -> String {
+        let s: Vec<String> = self.content
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let mut r = s.join("\n");
+        r.push('\n');
+        r
+    }
+
+    fn entry_mut(&mut self, name: &str) -> Result<&mut GroupEntry, GroupError> {
+        self.content
+            .iter_mut()
+            .find(|e| e.name == name)
+            .ok_or(GroupError::GroupNotFound(name.into()))
+    }
+
+    fn add_group(&mut self, entry: GroupEntry) -> Result<(), GroupError> {
+        if !self.content
+            .iter().any(|e| e.name == entry.name) {
+            self.content.push(entry);
+            Ok(())
+        } else {
+            Err(GroupError::GroupAlreadyExist(entry.name))
+        }
+    }
+
+    fn remove_group(&mut self, name: &str) -> Result<(), GroupError> {
+        let len = self.content.len();
+        self.content.retain(|entry| entry.name != name);
+
+        if len == self.content.len() {
+            Err(GroupError::GroupNotFound(name.into()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn add_member(&mut self, member: GroupMember) -> Result<(), GroupError> {
+        let entry = self.entry_mut(&member.group)?;
+
+        if entry.members.contains(&member.user) {
+            Err(GroupError::MemberAlreadyExist(member.user, member.group))
+        } else {
+            entry.members.push(member.user);
+            Ok(())
+        }
+    }
+
+    fn remove_member(&mut self, member: GroupMember) -> Result<(), GroupError> {
+        let entry = self.entry_mut(&member.group)?;
+        let len = entry.members.len();
+        entry.members.retain(|user| user != &member.user);
+
+        if len == entry.members.len() {
+            Err(GroupError::MemberNotFound(member.user, member.group))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GroupFile {
+    path: String,
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct GroupInput {
+    new_entries: Option<Vec<GroupEntry>>,
+    remove_by_name: Option<Vec<String>>,
+    add_members: Option<Vec<GroupMember>>,
+    remove_members: Option<Vec<GroupMember>>,
+    overwrite: Option<bool>,
+}
+
+#[async_trait]
+impl File for GroupFile {
+    type Output = Group;
+    type Input = GroupInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        Group::parse(&system.read_to_string(&self.path).await?)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = GroupInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        if i.overwrite == Some(true) {
+            if let Some(new_entries) = i.new_entries {
+                Ok(Group {
+                    content: new_entries
+                }.content_string())
+            } else {
+                Err(GroupError::NoNewEntries.into())
+            }
+        } else {
+            let mut group = Group::parse(&system.read_to_string(self.path()).await?)?;
+
+            if let Some(new) = i.new_entries {
+                for e in new.into_iter() {
+                    group.add_group(e)?;
+                }
+            }
+
+            if let Some(names) = i.remove_by_name {
+                for name in names.into_iter() {
+                    group.remove_group(&name)?;
+                }
+            }
+
+            if let Some(members) = i.add_members {
+                for member in members.into_iter() {
+                    group.add_member(member)?;
+                }
+            }
+
+            if let Some(members) = i.remove_members {
+                for member in members.into_iter() {
+                    group.remove_member(member)?;
+                }
+            }
+
+            Ok(group.content_string())
+        }
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"new_entries": output["content"], "overwrite": true})
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GroupBuilder;
+
+impl FileBuilder for GroupBuilder {
+    type File = GroupFile;
+
+    const NAME: &'static str = "group";
+    const DESCRIPTION: &'static str = "Managed group file.";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read, Capability::Write, Capability::Delete];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_path("/etc/group", &[Os::LinuxAny])];
+        }
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EXAMPLES: Vec<FileExample> = vec![
+                FileExample::new_get("Example content", vec![GroupEntry {
+                    name: "sudo".to_string(),
+                    password: "x".to_string(),
+                    group_id: 27,
+                    members: vec!["homer".to_string()],
+                }]),
+                FileExample::new_write("Add a group, remove another one and add a member.", GroupInput {
+                    new_entries: Some(vec![GroupEntry {
+                        name: "simpsons".to_string(),
+                        password: "x".to_string(),
+                        group_id: 1000,
+                        members: vec!["homer".to_string(), "marge".to_string()],
+                    }]),
+                    remove_by_name: Some(vec!["flanders".to_string()]),
+                    add_members: Some(vec![GroupMember {
+                        group: "sudo".to_string(),
+                        user: "marge".to_string(),
+                    }]),
+                    remove_members: None,
+                    overwrite: Some(false)
+                }),
+                FileExample::new_delete(),
+            ];
+        }
+
+        EXAMPLES.as_slice()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum GroupError {
+    #[error("invalid group line {0}")]
+    InvalidLine(String),
+    #[error("group {0} already exist")]
+    GroupAlreadyExist(String),
+    #[error("group {0} not found")]
+    GroupNotFound(String),
+    #[error("user {0} already member of group {1}")]
+    MemberAlreadyExist(String, String),
+    #[error("user {0} is no member of group {1}")]
+    MemberNotFound(String, String),
+    #[error("no new entries was given")]
+    NoNewEntries,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::group::{Group, GroupEntry, GroupMember};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let content = read_test_resources("group");
+        let group = Group::parse(&content).unwrap();
+
+        assert_eq!(group.content, vec![
+            GroupEntry { name: "root".into(), password: "x".into(), group_id: 0, members: vec![] },
+            GroupEntry { name: "adm".into(), password: "x".into(), group_id: 4, members: vec!["syslog".into(), "dev".into()] },
+            GroupEntry { name: "sudo".into(), password: "x".into(), group_id: 27, members: vec!["dev".into()] },
+            GroupEntry { name: "dev".into(), password: "x".into(), group_id: 1001, members: vec![] },
+        ]);
+
+        assert_eq!(group.content_string(), content);
+    }
+
+    #[test]
+    fn test_add_remove_group() {
+        let root = GroupEntry { name: "root".into(), password: "x".into(), group_id: 0, members: vec![] };
+        let test = GroupEntry { name: "test".into(), password: "x".into(), group_id: 1, members: vec![] };
+
+        let mut group = Group {
+            content: vec![root.clone()],
+        };
+
+        group.add_group(test.clone()).unwrap();
+        assert_eq!(group.content, vec![root.clone(), test.clone()]);
+
+        // duplicate
+        let duplicate = GroupEntry { group_id: 2, ..test };
+        assert_eq!(&format!("{:?}", group.add_group(duplicate)), "Err(GroupAlreadyExist(\"test\"))");
+
+        group.remove_group("test").unwrap();
+        assert_eq!(group.content, vec![root]);
+
+        // already gone
+        assert_eq!(&format!("{:?}", group.remove_group("test")), "Err(GroupNotFound(\"test\"))");
+    }
+
+    #[test]
+    fn test_add_remove_member() {
+        let sudo = GroupEntry { name: "sudo".into(), password: "x".into(), group_id: 27, members: vec!["dev".into()] };
+
+        let mut group = Group {
+            content: vec![sudo.clone()],
+        };
+
+        let member = GroupMember {
+            group: "sudo".into(),
+            user: "test".into(),
+        };
+
+        group.add_member(member.clone()).unwrap();
+        assert_eq!(group.content, vec![GroupEntry { members: vec!["dev".into(), "test".into()], ..sudo.clone() }]);
+
+        // duplicate
+        assert_eq!(&format!("{:?}", group.add_member(member.clone())), "Err(MemberAlreadyExist(\"test\", \"sudo\"))");
+
+        group.remove_member(member.clone()).unwrap();
+        assert_eq!(group.content, vec![sudo]);
+
+        // already gone
+        assert_eq!(&format!("{:?}", group.remove_member(member)), "Err(MemberNotFound(\"test\", \"sudo\"))");
+    }
+}
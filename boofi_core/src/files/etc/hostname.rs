@@ -1,15 +1,34 @@
-use crate::files::hosts::HostsManaged;
 use crate::files::prelude::*;
+use serde_json::{json, Value};
 
-pub(crate) struct Hostname {
+/// Hostname file of the running system, other paths e.g. of a mounted root are only written
+const HOSTNAME_PATH: &str = "/etc/hostname";
+
+pub struct Hostname {
     path: String,
 }
 
 #[derive(Deserialize, Description)]
-pub(crate) struct HostnameInput {
+pub struct HostnameInput {
     hostname: String,
 }
 
+impl Hostname {
+    fn hostnamectl() -> &'static str { "/usr/bin/hostnamectl" }
+
+    fn hostname() -> &'static str { "/bin/hostname" }
+
+    /// applies the hostname without a reboot, `hostname` is used on systems without systemd
+    async fn apply(hostname: &str, system: &System) -> Resul<()> {
+        if let Err(e) = system.run_args(Self::hostnamectl(), &["set-hostname", hostname]).await {
+            tracing::debug!("[HOSTNAME] hostnamectl failed, falling back to hostname: {}", e);
+            system.run_args(Self::hostname(), &[hostname]).await?;
+        }
+
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl File for Hostname {
     type Output = String;
@@ -23,28 +42,43 @@ impl File for Hostname {
         system.read_to_string(self.path()).await
     }
 
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, _system: &System) -> Resul<String> {
+        Ok(HostnameInput::deserialize(input).map_err(Erro::from_deserialize)?.hostname)
+    }
+
     async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
-        let i = HostnameInput::deserialize(input).map_err(Erro::from_deserialize)?;
-        system.write(self.path(), i.hostname.as_bytes()).await
+        let hostname = self.render(input, system).await?;
+        system.write(self.path(), hostname.as_bytes()).await?;
+
+        if self.path() == HOSTNAME_PATH {
+            Self::apply(hostname.trim(), system).await?;
+        }
+
+        Ok(())
     }
+
     fn path(&self) -> &str {
         &self.path
     }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"hostname": output})
+    }
 }
 
 #[derive(Clone)]
-pub(crate) struct HostnameBuilder;
+pub struct HostnameBuilder;
 
 impl FileBuilder for HostnameBuilder {
-    type File = HostsManaged;
+    type File = Hostname;
 
     const NAME: &'static str = "hostname";
-    const DESCRIPTION: &'static str = "Get or set hostname";
+    const DESCRIPTION: &'static str = "Get or set hostname, writing /etc/hostname also applies it to the running system";
     const CAPABILITIES: &'static [Capability] = &[Capability::Read, Capability::Write, Capability::Delete];
 
     fn patterns(&self) -> &[FileMatchPattern] {
         lazy_static! {
-            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_path("/etc/hostname", &[Os::LinuxAny])];
+            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_path(HOSTNAME_PATH, &[Os::LinuxAny])];
         }
         PATTERN.as_slice()
     }
@@ -1,9 +1,12 @@
+use std::net::IpAddr;
 use std::str::FromStr;
+use crate::description::InvalidField;
 use crate::files::prelude::*;
+use serde_json::{json, Value};
 use thiserror::Error;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
-pub(crate) struct Item {
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct Item {
     identifier: String,
     whitespaces: Option<String>,
 }
@@ -23,8 +26,8 @@ impl Default for Item {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
-pub(crate) struct Entry {
+#[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
+pub struct Entry {
     address: Item,
     hosts: Vec<Item>,
 }
@@ -36,7 +39,7 @@ impl ToString for Entry {
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Description)]
-pub(crate) enum HostsLine {
+pub enum HostsLine {
     Comment(String),
     Entries(Entry),
     Empty,
@@ -92,7 +95,7 @@ impl FromStr for HostsLine {
 }
 
 #[derive(Debug)]
-pub(crate) struct Hosts;
+pub struct Hosts;
 
 impl Hosts {
     fn parse(content: &str) -> Resul<Vec<HostsLine>> {
@@ -102,6 +105,49 @@ impl Hosts {
             .map_err(Into::into)
     }
 
+    /// Added lines which are not parsed back as the same entries
+    fn validate(lines: &[HostsLine]) -> Resul<()> {
+        let mut invalid = vec![];
+
+        for (i, line) in lines.iter().enumerate() {
+            match line {
+                HostsLine::Comment(comment) if !comment.starts_with('#') || comment.contains('\n') =>
+                    invalid.push(InvalidField::value(format!("add[{}].Comment", i), "single line starting with #")),
+                HostsLine::Entries(entry) => {
+                    // link local IPv6 addresses may carry a zone index e.g. fe80::1%eth0
+                    let address = entry.address.identifier.split('%').next().unwrap_or_default();
+                    if address.parse::<IpAddr>().is_err() {
+                        invalid.push(InvalidField::value(format!("add[{}].Entries.address.identifier", i), "IPv4 or IPv6 address"));
+                    }
+                    if !Self::whitespaces(&entry.address, false) {
+                        invalid.push(InvalidField::value(format!("add[{}].Entries.address.whitespaces", i), "spaces or tabs"));
+                    }
+                    if entry.hosts.is_empty() {
+                        invalid.push(InvalidField::value(format!("add[{}].Entries.hosts", i), "at least one host name"));
+                    }
+                    for (j, host) in entry.hosts.iter().enumerate() {
+                        if host.identifier.is_empty() || host.identifier.contains(char::is_whitespace) || host.identifier.starts_with('#') {
+                            invalid.push(InvalidField::value(format!("add[{}].Entries.hosts[{}].identifier", i, j), "host name without whitespace"));
+                        }
+                        if !Self::whitespaces(host, j + 1 == entry.hosts.len()) {
+                            invalid.push(InvalidField::value(format!("add[{}].Entries.hosts[{}].whitespaces", i, j), "spaces or tabs"));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if invalid.is_empty() { Ok(()) } else { Err(Erro::InputInvalid(invalid)) }
+    }
+
+    /// Only the last item of an entry may be followed by nothing
+    fn whitespaces(item: &Item, last: bool) -> bool {
+        item.whitespaces.as_ref()
+            .map(|w| (last || !w.is_empty()) && w.chars().all(|c| c == ' ' || c == '\t'))
+            .unwrap_or(true)
+    }
+
     fn lines_to_string(lines: Vec<HostsLine>) -> String {
         lines.iter()
             .map(|host_line| {
@@ -117,7 +163,7 @@ impl Hosts {
 
 
 #[derive(Debug)]
-pub(crate) struct HostsManaged {
+pub struct HostsManaged {
     path: String,
 }
 
@@ -125,16 +171,10 @@ impl HostsManaged {
     async fn parse(&self, system: &System) -> Resul<Vec<HostsLine>> {
         Hosts::parse(&system.read_to_string(&self.path).await?)
     }
-
-    async fn write(&self, lines: Vec<HostsLine>, system: &System) -> Resul<()> {
-        system.write(&self.path,
-                     Hosts::lines_to_string(lines).as_bytes(),
-        ).await.map_err(Into::into)
-    }
 }
 
 #[derive(Serialize, Deserialize)]
-pub(crate) struct HostsInput {
+pub struct HostsInput {
     add: Option<Vec<HostsLine>>,
     remove: Option<Vec<String>>,
     overwrite: Option<bool>,
@@ -155,8 +195,9 @@ impl File for HostsManaged {
         self.parse(system).await
     }
 
-    async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
         let i = HostsInput::deserialize(input).map_err(Erro::from_deserialize)?;
+        Hosts::validate(i.add.as_deref().unwrap_or_default())?;
 
         let mut c = if i.overwrite == Some(true) {
             vec![]
@@ -177,16 +218,21 @@ impl File for HostsManaged {
             c.append(&mut add);
         }
 
-        self.write(c, system).await
+        Ok(Hosts::lines_to_string(c))
     }
 
+
     fn path(&self) -> &str {
         &self.path
     }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"add": output, "overwrite": true})
+    }
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct HostsBuilder {}
+pub struct HostsBuilder {}
 
 impl FileBuilder for HostsBuilder {
     type File = HostsManaged;
@@ -227,10 +273,11 @@ impl FileBuilder for HostsBuilder {
 }
 
 #[derive(Debug, Error)]
-pub(crate) enum HostsError {}
+pub enum HostsError {}
 
 #[cfg(test)]
 mod test {
+    use crate::error::Erro;
     use crate::files::hosts::{Entry, Hosts, Item};
     use crate::files::hosts::HostsLine::{Comment, Entries, Empty};
     use crate::utils::test::read_test_resources;
@@ -264,4 +311,35 @@ mod test {
         assert_eq!(Hosts::parse(&content).unwrap(), entries);
         assert_eq!(Hosts::lines_to_string(entries), content);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn validate() {
+        let entries = Hosts::parse(&read_test_resources("hosts")).unwrap();
+        Hosts::validate(&entries).unwrap();
+
+        let invalid = vec![
+            Entries(Entry {
+                address: Item { identifier: "fe80::1%eth0".into(), whitespaces: None },
+                hosts: vec![Item { identifier: "router".into(), whitespaces: None }],
+            }),
+            Entries(Entry {
+                address: Item { identifier: "127.0.0.256".into(), whitespaces: Some("".into()) },
+                hosts: vec![Item { identifier: "my host".into(), whitespaces: Some("".into()) }],
+            }),
+            Entries(Entry {
+                address: Item { identifier: "10.0.0.1".into(), whitespaces: None },
+                hosts: vec![],
+            }),
+            Comment("no comment".into()),
+        ];
+
+        let Err(Erro::InputInvalid(invalid)) = Hosts::validate(&invalid) else { panic!("hosts have to be invalid") };
+        assert_eq!(invalid.iter().map(|i| i.field.as_str()).collect::<Vec<&str>>(), vec![
+            "add[1].Entries.address.identifier",
+            "add[1].Entries.address.whitespaces",
+            "add[1].Entries.hosts[0].identifier",
+            "add[2].Entries.hosts",
+            "add[3].Comment",
+        ]);
+    }
+}
@@ -0,0 +1,416 @@
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use regex::Regex;
+use crate::files::prelude::*;
+use crate::utils::parse_cidr;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// Line below an `iface` stanza, comments are kept as they are
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub enum InterfacesOption {
+    Comment(String),
+    Empty,
+    Entry {
+        key: String,
+        value: String,
+    },
+}
+
+impl Display for InterfacesOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterfacesOption::Comment(c) => f.write_str(c),
+            InterfacesOption::Empty => Ok(()),
+            InterfacesOption::Entry { key, value } => write!(f, "    {} {}", key, value),
+        }
+    }
+}
+
+/// family: inet, inet6, ...
+/// method: static, dhcp, loopback, manual, ...
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct InterfacesIface {
+    name: String,
+    family: String,
+    method: String,
+    options: Vec<InterfacesOption>,
+}
+
+impl InterfacesIface {
+    fn values<'a>(&'a self, key: &'a str) -> impl Iterator<Item=&'a str> + 'a {
+        self.options.iter().filter_map(move |o| match o {
+            InterfacesOption::Entry { key: k, value } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    fn validate(&self) -> Result<(), InterfacesError> {
+        let invalid = |reason: String| InterfacesError::InvalidIface(self.name.clone(), reason);
+
+        for address in self.values("address") {
+            if address.parse::<IpAddr>().is_err() && parse_cidr(address).is_none() {
+                return Err(invalid(format!("address {} is invalid", address)));
+            }
+        }
+
+        for ip in self.values("gateway").chain(self.values("dns-nameservers").flat_map(str::split_whitespace)) {
+            ip.parse::<IpAddr>().map_err(|_| invalid(format!("{} is no ip address", ip)))?;
+        }
+
+        if self.method == "static" && self.values("address").next().is_none() {
+            return Err(invalid("static method requires an address".into()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Top level line, stanzas other than `auto`, `allow-` and `iface` like `source` or `mapping` are kept as they are
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub enum InterfacesLine {
+    Comment(String),
+    Empty,
+    Auto(Vec<String>),
+    Allow {
+        #[desc(description = "hotplug, auto, ...")]
+        class: String,
+        interfaces: Vec<String>,
+    },
+    Iface(InterfacesIface),
+    Other(String),
+}
+
+impl Display for InterfacesLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterfacesLine::Comment(c) | InterfacesLine::Other(c) => f.write_str(c),
+            InterfacesLine::Empty => Ok(()),
+            InterfacesLine::Auto(interfaces) => write!(f, "auto {}", interfaces.join(" ")),
+            InterfacesLine::Allow { class, interfaces } => write!(f, "allow-{} {}", class, interfaces.join(" ")),
+            InterfacesLine::Iface(iface) => {
+                write!(f, "iface {} {} {}", iface.name, iface.family, iface.method)?;
+                for option in iface.options.iter() {
+                    write!(f, "\n{}", option)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct Interfaces {
+    lines: Vec<InterfacesLine>,
+}
+
+impl Display for Interfaces {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Interfaces {
+    pub fn parse(content: &str) -> Resul<Self> {
+        let mut lines: Vec<InterfacesLine> = vec![];
+        // options of a mapping stanza are kept as they are
+        let mut mapping = false;
+
+        for line in content.lines() {
+            let l = line.trim();
+            let mut words = l.split_whitespace();
+            let first = words.next().unwrap_or_default();
+            let stanza = matches!(first, "auto" | "iface" | "mapping" | "source" | "source-directory" | "rename" | "no-auto-down" | "no-scripts")
+                || first.starts_with("allow-");
+
+            if !stanza {
+                match lines.last_mut() {
+                    Some(InterfacesLine::Iface(iface)) => {
+                        iface.options.push(if l.is_empty() {
+                            InterfacesOption::Empty
+                        } else if l.starts_with('#') {
+                            InterfacesOption::Comment(line.into())
+                        } else {
+                            let (key, value) = l.split_once(char::is_whitespace).ok_or(InterfacesError::InvalidLine(line.into()))?;
+                            InterfacesOption::Entry { key: key.into(), value: value.trim_start().into() }
+                        });
+                    }
+                    _ if l.is_empty() => lines.push(InterfacesLine::Empty),
+                    _ if l.starts_with('#') => lines.push(InterfacesLine::Comment(line.into())),
+                    _ if mapping => lines.push(InterfacesLine::Other(line.into())),
+                    _ => return Err(InterfacesError::InvalidLine(line.into()).into()),
+                }
+                continue;
+            }
+
+            mapping = first == "mapping";
+
+            lines.push(match (first, first.strip_prefix("allow-")) {
+                ("auto", _) => InterfacesLine::Auto(words.map(Into::into).collect()),
+                (_, Some(class)) => InterfacesLine::Allow { class: class.into(), interfaces: words.map(Into::into).collect() },
+                ("iface", _) => match (words.next(), words.next(), words.next(), words.next()) {
+                    (Some(name), Some(family), Some(method), None) => InterfacesLine::Iface(InterfacesIface {
+                        name: name.into(),
+                        family: family.into(),
+                        method: method.into(),
+                        options: vec![],
+                    }),
+                    _ => return Err(InterfacesError::InvalidLine(line.into()).into()),
+                },
+                _ => InterfacesLine::Other(line.into()),
+            });
+        }
+
+        Ok(Self {
+            lines,
+        })
+    }
+
+    fn validate(&self) -> Result<(), InterfacesError> {
+        for line in self.lines.iter() {
+            if let InterfacesLine::Iface(iface) = line {
+                iface.validate()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the iface with the same name and family or appends it after an empty line
+    fn set(&mut self, iface: InterfacesIface) {
+        let existing = self.lines.iter_mut().find(|l| matches!(l, InterfacesLine::Iface(i) if i.name == iface.name && i.family == iface.family));
+
+        match existing {
+            Some(line) => *line = InterfacesLine::Iface(iface),
+            None => {
+                if !self.lines.is_empty() && !self.to_string().ends_with("\n\n") {
+                    self.lines.push(InterfacesLine::Empty);
+                }
+                self.lines.push(InterfacesLine::Iface(iface));
+            }
+        }
+    }
+
+    /// Brings the interface up at boot with an `auto` line before its first iface stanza
+    fn auto(&mut self, name: &str) {
+        if self.lines.iter().any(|l| matches!(l, InterfacesLine::Auto(i) if i.iter().any(|i| i == name))) {
+            return;
+        }
+
+        let position = self.lines.iter()
+            .position(|l| matches!(l, InterfacesLine::Iface(i) if i.name == name))
+            .unwrap_or(self.lines.len());
+        self.lines.insert(position, InterfacesLine::Auto(vec![name.into()]));
+    }
+
+    /// Removes all iface stanzas of the interface and its name from `auto` and `allow-` lines
+    fn remove(&mut self, name: &str) -> Result<(), InterfacesError> {
+        let len = self.lines.len();
+        self.lines.retain(|l| !matches!(l, InterfacesLine::Iface(i) if i.name == name));
+        let removed = len != self.lines.len();
+
+        for line in self.lines.iter_mut() {
+            if let InterfacesLine::Auto(interfaces) | InterfacesLine::Allow { interfaces, .. } = line {
+                interfaces.retain(|i| i != name);
+            }
+        }
+
+        self.lines.retain(|l| !matches!(l, InterfacesLine::Auto(i) | InterfacesLine::Allow { interfaces: i, .. } if i.is_empty()));
+
+        if removed {
+            Ok(())
+        } else {
+            Err(InterfacesError::IfaceNotFound(name.into()))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct InterfacesInput {
+    #[desc(description = "replaces the whole interfaces file")]
+    content: Option<Interfaces>,
+    #[desc(description = "replaces iface stanzas with the same name and family or adds them")]
+    set: Option<Vec<InterfacesIface>>,
+    #[desc(description = "interface names brought up at boot")]
+    auto: Option<Vec<String>>,
+    #[desc(description = "interface names")]
+    remove: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct InterfacesBuilder;
+
+impl FileBuilder for InterfacesBuilder {
+    file_metadata!(
+        InterfacesFile,
+        "interfaces",
+        "Read and write debian network interfaces. Comments and order are preserved, addresses are validated before writing.",
+        &[Capability::Read, Capability::Write, Capability::Delete],
+        FileExample::new_get("read interfaces",
+            Interfaces {
+                lines: vec![
+                    InterfacesLine::Auto(vec!["lo".into()]),
+                    InterfacesLine::Iface(InterfacesIface {
+                        name: "lo".into(),
+                        family: "inet".into(),
+                        method: "loopback".into(),
+                        options: vec![],
+                    }),
+                ],
+            }
+        ),
+        FileExample::new_write("configure a static address at boot",
+            InterfacesInput {
+                content: None,
+                set: Some(vec![InterfacesIface {
+                    name: "eth0".into(),
+                    family: "inet".into(),
+                    method: "static".into(),
+                    options: vec![
+                        InterfacesOption::Entry { key: "address".into(), value: "192.168.1.10/24".into() },
+                        InterfacesOption::Entry { key: "gateway".into(), value: "192.168.1.1".into() },
+                    ],
+                }]),
+                auto: Some(vec!["eth0".into()]),
+                remove: None,
+            }
+        ),
+        FileExample::new_write("remove an interface",
+            InterfacesInput {
+                content: None,
+                set: None,
+                auto: None,
+                remove: Some(vec!["eth1".into()]),
+            }
+        ),
+        FileExample::new_delete()
+        ;
+        FileMatchPattern::new_regex(Regex::new("^/etc/network/interfaces$").unwrap(), &[Os::LinuxAny]),
+        FileMatchPattern::new_regex(Regex::new("^/etc/network/interfaces\\.d/[^/]+$").unwrap(), &[Os::LinuxAny])
+    );
+}
+
+pub struct InterfacesFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for InterfacesFile {
+    type Output = Interfaces;
+    type Input = InterfacesInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        Interfaces::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = InterfacesInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        let mut interfaces = match i.content {
+            Some(content) => content,
+            None => Interfaces::parse(&system.read_to_string(self.path()).await?)?,
+        };
+
+        for name in i.remove.unwrap_or_default().iter() {
+            interfaces.remove(name)?;
+        }
+
+        for iface in i.set.unwrap_or_default() {
+            interfaces.set(iface);
+        }
+
+        for name in i.auto.unwrap_or_default().iter() {
+            interfaces.auto(name);
+        }
+
+        interfaces.validate()?;
+        Ok(interfaces.to_string())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"content": output})
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum InterfacesError {
+    #[error("invalid interfaces line {0}")]
+    InvalidLine(String),
+    #[error("iface {0} invalid: {1}")]
+    InvalidIface(String, String),
+    #[error("iface {0} not found")]
+    IfaceNotFound(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::interfaces::{Interfaces, InterfacesIface, InterfacesLine, InterfacesOption};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let content = read_test_resources("interfaces");
+        let interfaces = Interfaces::parse(&content).unwrap();
+
+        assert_eq!(interfaces.lines[1], InterfacesLine::Other("source /etc/network/interfaces.d/*".into()));
+        assert_eq!(interfaces.lines[5], InterfacesLine::Allow { class: "hotplug".into(), interfaces: vec!["eth0".into()] });
+        assert_eq!(interfaces.lines[6], InterfacesLine::Iface(InterfacesIface {
+            name: "eth0".into(),
+            family: "inet".into(),
+            method: "static".into(),
+            options: vec![
+                InterfacesOption::Entry { key: "address".into(), value: "192.168.1.10/24".into() },
+                InterfacesOption::Entry { key: "gateway".into(), value: "192.168.1.1".into() },
+                InterfacesOption::Comment("    # resolvconf".into()),
+                InterfacesOption::Entry { key: "dns-nameservers".into(), value: "1.1.1.1 9.9.9.9".into() },
+                InterfacesOption::Empty,
+            ],
+        }));
+        assert!(interfaces.validate().is_ok());
+
+        assert_eq!(interfaces.to_string(), content);
+        assert!(Interfaces::parse("    address 192.168.1.10\n").is_err());
+    }
+
+    #[test]
+    fn test_set_remove_validate() {
+        let mut interfaces = Interfaces::parse(&read_test_resources("interfaces")).unwrap();
+
+        interfaces.remove("eth0").unwrap();
+        assert!(interfaces.remove("eth0").is_err());
+        assert!(!interfaces.to_string().contains("eth0"));
+
+        interfaces.set(InterfacesIface {
+            name: "eth1".into(),
+            family: "inet".into(),
+            method: "static".into(),
+            options: vec![InterfacesOption::Entry { key: "address".into(), value: "10.0.0.5".into() }, InterfacesOption::Entry { key: "netmask".into(), value: "255.255.255.0".into() }],
+        });
+        interfaces.auto("eth1");
+        interfaces.auto("eth1");
+        assert!(interfaces.validate().is_ok());
+        assert!(interfaces.to_string().ends_with("iface eth2 inet6 auto\n\nauto eth1\niface eth1 inet static\n    address 10.0.0.5\n    netmask 255.255.255.0\n"));
+
+        interfaces.set(InterfacesIface {
+            name: "eth1".into(),
+            family: "inet".into(),
+            method: "static".into(),
+            options: vec![InterfacesOption::Entry { key: "gateway".into(), value: "10.0.0.1".into() }],
+        });
+        assert_eq!(&format!("{:?}", interfaces.validate()), "Err(InvalidIface(\"eth1\", \"static method requires an address\"))");
+    }
+}
@@ -0,0 +1,17 @@
+pub mod hosts;
+pub mod passwd;
+pub mod group;
+pub mod shadow;
+pub mod hostname;
+pub mod crontab;
+pub mod fstab;
+pub mod os_release;
+pub mod systemd_unit;
+pub mod netplan;
+pub mod interfaces;
+pub mod sshd_config;
+pub mod authorized_keys;
+pub mod sudoers;
+pub mod exports;
+pub mod smb_conf;
+pub mod environment;
\ No newline at end of file
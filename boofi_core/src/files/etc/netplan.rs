@@ -0,0 +1,423 @@
+use std::net::IpAddr;
+use regex::Regex;
+use serde_yaml::{from_str, from_value, to_string, to_value, Mapping};
+use crate::files::prelude::*;
+use crate::utils::parse_cidr;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+impl Description for Mapping {
+    const DESCRIPTION: &'static str = "yaml mapping";
+}
+
+/// Device groups below `network`, each mapping device names to their settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Description)]
+#[serde(rename_all = "lowercase")]
+pub enum NetplanKind {
+    Ethernets,
+    Bonds,
+    Bridges,
+    Vlans,
+    Wifis,
+}
+
+impl NetplanKind {
+    const ALL: [NetplanKind; 5] = [Self::Ethernets, Self::Bonds, Self::Bridges, Self::Vlans, Self::Wifis];
+
+    fn key(&self) -> &'static str {
+        match self {
+            Self::Ethernets => "ethernets",
+            Self::Bonds => "bonds",
+            Self::Bridges => "bridges",
+            Self::Vlans => "vlans",
+            Self::Wifis => "wifis",
+        }
+    }
+}
+
+/// to:     `default` or a network in CIDR notation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct NetplanRoute {
+    to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    via: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metric: Option<usize>,
+    #[serde(flatten)]
+    #[desc(description = "other route settings like table or on-link")]
+    other: Mapping,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct NetplanNameservers {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    addresses: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    search: Vec<String>,
+}
+
+/// Common device settings, everything else like `match` or `parameters` is kept in `other`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Description)]
+pub struct NetplanSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dhcp4: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dhcp6: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[desc(description = "vlan id")]
+    id: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[desc(description = "underlying device of a vlan")]
+    link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[desc(description = "members of a bond or bridge")]
+    interfaces: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[desc(description = "addresses in CIDR notation")]
+    addresses: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    routes: Option<Vec<NetplanRoute>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nameservers: Option<NetplanNameservers>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mtu: Option<usize>,
+    #[serde(flatten)]
+    other: Mapping,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct NetplanDevice {
+    name: String,
+    kind: NetplanKind,
+    settings: NetplanSettings,
+}
+
+impl NetplanDevice {
+    fn validate(&self) -> Result<(), NetplanError> {
+        let invalid = |reason: &str| NetplanError::InvalidDevice(self.name.clone(), reason.into());
+        let settings = &self.settings;
+
+        for address in settings.addresses.iter().flatten() {
+            parse_cidr(address).ok_or_else(|| invalid(&format!("address {} is not in CIDR notation", address)))?;
+        }
+
+        for route in settings.routes.iter().flatten() {
+            if route.to != "default" && parse_cidr(&route.to).is_none() {
+                return Err(invalid(&format!("route destination {} is neither default nor in CIDR notation", route.to)));
+            }
+
+            if let Some(via) = &route.via {
+                via.parse::<IpAddr>().map_err(|_| invalid(&format!("gateway {} is no ip address", via)))?;
+            }
+        }
+
+        for nameserver in settings.nameservers.iter().flat_map(|n| n.addresses.iter()) {
+            nameserver.parse::<IpAddr>().map_err(|_| invalid(&format!("nameserver {} is no ip address", nameserver)))?;
+        }
+
+        if self.kind == NetplanKind::Vlans && (settings.id.is_none() || settings.link.is_none()) {
+            return Err(invalid("vlan requires id and link"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Content of a netplan file. Comments are not preserved, unknown keys are.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct Netplan {
+    version: usize,
+    renderer: Option<String>,
+    devices: Vec<NetplanDevice>,
+    #[desc(description = "other keys below network")]
+    other: Mapping,
+}
+
+impl Netplan {
+    pub fn parse(content: &str) -> Resul<Self> {
+        let mut root: Mapping = from_str(content)?;
+        let mut network: Mapping = from_value(root.remove("network").ok_or(NetplanError::NetworkMissing)?)?;
+
+        let version = network.remove("version").map(from_value).transpose()?.unwrap_or(2);
+        let renderer = network.remove("renderer").map(from_value).transpose()?;
+        let mut devices = vec![];
+
+        for kind in NetplanKind::ALL {
+            if let Some(group) = network.remove(kind.key()) {
+                for (name, settings) in from_value::<Mapping>(group)? {
+                    devices.push(NetplanDevice {
+                        name: from_value(name)?,
+                        kind,
+                        settings: from_value(settings)?,
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            version,
+            renderer,
+            devices,
+            other: network,
+        })
+    }
+
+    /// Devices grouped by their kind below `network`
+    pub fn to_yaml(&self) -> Resul<String> {
+        let mut network = Mapping::new();
+        network.insert("version".into(), self.version.into());
+
+        if let Some(renderer) = &self.renderer {
+            network.insert("renderer".into(), renderer.as_str().into());
+        }
+
+        for kind in NetplanKind::ALL {
+            let mut group = Mapping::new();
+
+            for device in self.devices.iter().filter(|d| d.kind == kind) {
+                group.insert(device.name.as_str().into(), to_value(&device.settings)?);
+            }
+
+            if !group.is_empty() {
+                network.insert(kind.key().into(), group.into());
+            }
+        }
+
+        network.extend(self.other.clone());
+
+        let mut root = Mapping::new();
+        root.insert("network".into(), network.into());
+        Ok(to_string(&root)?)
+    }
+
+    fn validate(&self) -> Result<(), NetplanError> {
+        if self.version != 2 {
+            return Err(NetplanError::VersionUnsupported(self.version));
+        }
+
+        for (i, device) in self.devices.iter().enumerate() {
+            if self.devices[..i].iter().any(|d| d.name == device.name) {
+                return Err(NetplanError::InvalidDevice(device.name.clone(), "defined more than once".into()));
+            }
+
+            device.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the device with the same name or appends it
+    fn set(&mut self, device: NetplanDevice) {
+        match self.devices.iter_mut().find(|d| d.name == device.name) {
+            Some(d) => *d = device,
+            None => self.devices.push(device),
+        }
+    }
+
+    fn remove(&mut self, name: &str) -> Result<(), NetplanError> {
+        let len = self.devices.len();
+        self.devices.retain(|d| d.name != name);
+
+        if len == self.devices.len() {
+            Err(NetplanError::DeviceNotFound(name.into()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct NetplanInput {
+    #[desc(description = "replaces the whole netplan file")]
+    content: Option<Netplan>,
+    #[desc(description = "replaces devices with the same name or adds them")]
+    set: Option<Vec<NetplanDevice>>,
+    #[desc(description = "device names")]
+    remove: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NetplanBuilder;
+
+impl FileBuilder for NetplanBuilder {
+    file_metadata!(
+        NetplanFile,
+        "netplan",
+        "Read and write netplan network configuration. Addresses, routes and nameservers are validated before writing, comments are not preserved.",
+        &[Capability::Read, Capability::Write, Capability::Delete],
+        FileExample::new_get("read configuration",
+            Netplan {
+                version: 2,
+                renderer: Some("networkd".into()),
+                devices: vec![NetplanDevice {
+                    name: "eth0".into(),
+                    kind: NetplanKind::Ethernets,
+                    settings: NetplanSettings {
+                        dhcp4: Some(true),
+                        ..NetplanSettings::default()
+                    },
+                }],
+                other: Mapping::new(),
+            }
+        ),
+        FileExample::new_write("configure a static address",
+            NetplanInput {
+                content: None,
+                set: Some(vec![NetplanDevice {
+                    name: "eth0".into(),
+                    kind: NetplanKind::Ethernets,
+                    settings: NetplanSettings {
+                        addresses: Some(vec!["192.168.1.10/24".into()]),
+                        routes: Some(vec![NetplanRoute { to: "default".into(), via: Some("192.168.1.1".into()), metric: None, other: Mapping::new() }]),
+                        nameservers: Some(NetplanNameservers { addresses: vec!["192.168.1.1".into()], search: vec![] }),
+                        ..NetplanSettings::default()
+                    },
+                }]),
+                remove: None,
+            }
+        ),
+        FileExample::new_write("add a vlan and remove a device",
+            NetplanInput {
+                content: None,
+                set: Some(vec![NetplanDevice {
+                    name: "vlan20".into(),
+                    kind: NetplanKind::Vlans,
+                    settings: NetplanSettings {
+                        id: Some(20),
+                        link: Some("eth0".into()),
+                        addresses: Some(vec!["10.0.20.5/24".into()]),
+                        ..NetplanSettings::default()
+                    },
+                }]),
+                remove: Some(vec!["eth1".into()]),
+            }
+        ),
+        FileExample::new_delete()
+        ;
+        FileMatchPattern::new_regex(Regex::new("^/etc/netplan/[^/]+\\.yaml$").unwrap(), &[Os::LinuxAny])
+    );
+}
+
+pub struct NetplanFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for NetplanFile {
+    type Output = Netplan;
+    type Input = NetplanInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        Netplan::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = NetplanInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        let mut netplan = match i.content {
+            Some(content) => content,
+            None => Netplan::parse(&system.read_to_string(self.path()).await?)?,
+        };
+
+        for device in i.set.unwrap_or_default() {
+            netplan.set(device);
+        }
+
+        for name in i.remove.unwrap_or_default().iter() {
+            netplan.remove(name)?;
+        }
+
+        netplan.validate()?;
+        netplan.to_yaml()
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"content": output})
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum NetplanError {
+    #[error("network key missing")]
+    NetworkMissing,
+    #[error("version {0} not supported")]
+    VersionUnsupported(usize),
+    #[error("device {0} invalid: {1}")]
+    InvalidDevice(String, String),
+    #[error("device {0} not found")]
+    DeviceNotFound(String),
+}
+
+#[cfg(test)]
+mod test {
+    use serde_yaml::Mapping;
+    use crate::files::netplan::{Netplan, NetplanDevice, NetplanKind, NetplanRoute, NetplanSettings};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let content = read_test_resources("netplan");
+        let netplan = Netplan::parse(&content).unwrap();
+
+        assert_eq!(netplan.renderer.as_deref(), Some("networkd"));
+        assert_eq!(netplan.devices.iter().map(|d| (d.name.as_str(), d.kind)).collect::<Vec<_>>(), vec![
+            ("eth0", NetplanKind::Ethernets),
+            ("eth1", NetplanKind::Ethernets),
+            ("vlan20", NetplanKind::Vlans),
+        ]);
+        assert_eq!(netplan.devices[0].settings.routes, Some(vec![NetplanRoute {
+            to: "default".into(),
+            via: Some("192.168.1.1".into()),
+            metric: None,
+            other: Mapping::new(),
+        }]));
+        assert!(netplan.devices[0].settings.other.contains_key("match"));
+        assert!(netplan.validate().is_ok());
+
+        assert_eq!(netplan.to_yaml().unwrap(), content);
+    }
+
+    #[test]
+    fn test_set_remove_validate() {
+        let mut netplan = Netplan::parse(&read_test_resources("netplan")).unwrap();
+
+        netplan.remove("eth1").unwrap();
+        assert!(netplan.remove("eth1").is_err());
+
+        netplan.set(NetplanDevice {
+            name: "vlan30".into(),
+            kind: NetplanKind::Vlans,
+            settings: NetplanSettings {
+                link: Some("eth0".into()),
+                ..NetplanSettings::default()
+            },
+        });
+        assert_eq!(&format!("{:?}", netplan.validate()), "Err(InvalidDevice(\"vlan30\", \"vlan requires id and link\"))");
+
+        netplan.set(NetplanDevice {
+            name: "vlan30".into(),
+            kind: NetplanKind::Vlans,
+            settings: NetplanSettings {
+                id: Some(30),
+                link: Some("eth0".into()),
+                addresses: Some(vec!["10.0.30.5".into()]),
+                ..NetplanSettings::default()
+            },
+        });
+        assert!(netplan.validate().is_err());
+
+        netplan.devices.last_mut().unwrap().settings.addresses = Some(vec!["10.0.30.5/24".into()]);
+        assert!(netplan.validate().is_ok());
+        assert!(netplan.to_yaml().unwrap().contains("  vlans:\n    vlan20:\n      id: 20\n      link: eth0\n      addresses:\n      - 10.0.20.5/24\n    vlan30:\n"));
+    }
+}
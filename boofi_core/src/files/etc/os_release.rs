@@ -4,7 +4,7 @@ use thiserror::Error;
 
 
 #[derive(Serialize, Debug, PartialEq, Description)]
-pub(crate) struct OsRelease {
+pub struct OsRelease {
     name: String,
     version: Option<String>,
     id: String,
@@ -24,9 +24,9 @@ pub(crate) struct OsRelease {
 }
 
 impl OsRelease {
-    pub(crate) fn id(&self) -> &str { self.id.as_str() }
+    pub fn id(&self) -> &str { self.id.as_str() }
 
-    pub(crate) fn version_codename(&self) -> Option<&str> { self.version_codename.as_deref() }
+    pub fn version_codename(&self) -> Option<&str> { self.version_codename.as_deref() }
 }
 
 impl TryFrom<String> for OsRelease {
@@ -65,12 +65,12 @@ impl TryFrom<String> for OsRelease {
     }
 }
 
-pub(crate) struct OsReleaseFile {
+pub struct OsReleaseFile {
     path: String,
 }
 
 impl OsReleaseFile {
-    pub(crate) async fn release(&self, system: &System) -> Resul<OsRelease> {
+    pub async fn release(&self, system: &System) -> Resul<OsRelease> {
         system.read_to_string(self.path.as_str())
             .await?
             .try_into()
@@ -99,7 +99,7 @@ impl File for OsReleaseFile {
 }
 
 #[derive(Clone)]
-pub(crate) struct OsReleaseBuilder;
+pub struct OsReleaseBuilder;
 
 impl FileBuilder for OsReleaseBuilder {
     type File = OsReleaseFile;
@@ -107,6 +107,7 @@ impl FileBuilder for OsReleaseBuilder {
     const NAME: &'static str = "os-release";
     const DESCRIPTION: &'static str = "read os-release file";
     const CAPABILITIES: &'static [Capability] = &[Capability::Read];
+    const CACHEABLE: bool = true;
 
     fn patterns(&self) -> &[FileMatchPattern] {
         lazy_static! {
@@ -125,7 +126,7 @@ impl FileBuilder for OsReleaseBuilder {
 }
 
 #[derive(Debug, Error)]
-pub(crate) enum OsReleaseError {
+pub enum OsReleaseError {
     #[error("NAME missing")]
     Name,
     #[error("ID missing")]
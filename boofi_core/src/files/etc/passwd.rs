@@ -1,9 +1,14 @@
 use std::vec;
+use crate::description::InvalidField;
 use crate::files::prelude::*;
+use serde_json::{json, Value};
 use thiserror::Error;
 
+/// Highest user or group id, `(uid_t) -1` is reserved as error value
+const MAX_ID: usize = u32::MAX as usize - 1;
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Description)]
-pub(crate) struct PasswdEntry {
+pub struct PasswdEntry {
     user: String,
     password: String,
     user_id: usize,
@@ -28,6 +33,29 @@ impl ToString for PasswdEntry {
 }
 
 
+impl PasswdEntry {
+    /// Values which would shift the colon separated columns or are out of the id range
+    fn validate(&self, path: &str, invalid: &mut Vec<InvalidField>) {
+        let user_valid = !self.user.is_empty() && !self.user.starts_with('-')
+            && !self.user.contains(|c: char| c == ':' || c.is_whitespace());
+        if !user_valid {
+            invalid.push(InvalidField::value(format!("{}.user", path), "non-empty name without colons or whitespace"));
+        }
+
+        for (field, id) in [("user_id", self.user_id), ("group_id", self.group_id)] {
+            if id > MAX_ID {
+                invalid.push(InvalidField::value(format!("{}.{}", path, field), "id from 0 to 4294967294"));
+            }
+        }
+
+        for (field, value) in [("password", &self.password), ("comment", &self.comment), ("home", &self.home), ("program", &self.program)] {
+            if value.contains([':', '\n']) {
+                invalid.push(InvalidField::value(format!("{}.{}", path, field), "value without colons or line breaks"));
+            }
+        }
+    }
+}
+
 impl TryFrom<String> for PasswdEntry {
     type Error = Erro;
 
@@ -46,7 +74,7 @@ impl TryFrom<String> for PasswdEntry {
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Description)]
-pub(crate) struct Passwd {
+pub struct Passwd {
     content: Vec<PasswdEntry>,
 }
 
@@ -106,7 +134,7 @@ impl Passwd {
 }
 
 #[derive(Clone, Debug)]
-pub(crate) struct PasswdBuilder;
+pub struct PasswdBuilder;
 
 #[async_trait]
 impl File for PasswdFile {
@@ -123,38 +151,19 @@ impl File for PasswdFile {
         Passwd::parse(&system.read_to_string(&self.path).await?)
     }
 
-    async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
-        let i = PasswdInput::deserialize(input).map_err(Erro::from_deserialize)?;
-
-        if i.overwrite == Some(true) {
-            if let Some(new_entries) = i.new_entries {
-                system.write(&self.path, Passwd {
-                    content: new_entries
-                }.content_string().as_bytes()).await
-            } else {
-                Err(PasswdError::NoNewEntries.into())
-            }
-        } else {
-            let mut passwd = Passwd::parse(&system.read_to_string(self.path()).await?)?;
-
-            if let Some(new) = i.new_entries {
-                for e in new.into_iter() {
-                    passwd.add_user(e)?;
-                }
-            }
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let passwd = self.merge(PasswdInput::deserialize(input).map_err(Erro::from_deserialize)?, system).await?;
+        Ok(passwd.content_string())
+    }
 
-            if let Some(usernames) = i.remove_by_username {
-                for username in usernames.into_iter() {
-                    passwd.remove_user(&username)?;
-                }
-            }
 
-            system.write(self.path(), passwd.content_string().as_bytes()).await
-        }
-    }
     fn path(&self) -> &str {
         &self.path
     }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"new_entries": output["content"], "overwrite": true})
+    }
 }
 
 impl FileBuilder for PasswdBuilder {
@@ -205,20 +214,66 @@ impl FileBuilder for PasswdBuilder {
 }
 
 #[derive(Debug)]
-pub(crate) struct PasswdFile {
+pub struct PasswdFile {
     path: String,
 }
 
+impl PasswdFile {
+    /// Content after the input is applied, the current file is only read without overwrite
+    async fn merge(&self, i: PasswdInput, system: &System) -> Resul<Passwd> {
+        i.validate()?;
+
+        if i.overwrite == Some(true) {
+            i.new_entries
+                .map(|content| Passwd { content })
+                .ok_or_else(|| PasswdError::NoNewEntries.into())
+        } else {
+            let mut passwd = Passwd::parse(&system.read_to_string(self.path()).await?)?;
+
+            if let Some(new) = i.new_entries {
+                for e in new.into_iter() {
+                    passwd.add_user(e)?;
+                }
+            }
+
+            if let Some(usernames) = i.remove_by_username {
+                for username in usernames.into_iter() {
+                    passwd.remove_user(&username)?;
+                }
+            }
+
+            Ok(passwd)
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Description)]
-pub(crate) struct PasswdInput {
+pub struct PasswdInput {
     new_entries: Option<Vec<PasswdEntry>>,
     remove_by_username: Option<Vec<String>>,
     overwrite: Option<bool>,
 }
 
+impl PasswdInput {
+    fn validate(&self) -> Resul<()> {
+        let mut invalid = vec![];
+        let entries = self.new_entries.as_deref().unwrap_or_default();
+
+        for (i, entry) in entries.iter().enumerate() {
+            entry.validate(&format!("new_entries[{}]", i), &mut invalid);
+
+            if entries[..i].iter().any(|e| e.user == entry.user) {
+                invalid.push(InvalidField::value(format!("new_entries[{}].user", i), "unique user name"));
+            }
+        }
+
+        if invalid.is_empty() { Ok(()) } else { Err(Erro::InputInvalid(invalid)) }
+    }
+}
+
 
 #[derive(Debug, Error)]
-pub(crate) enum PasswdError {
+pub enum PasswdError {
     #[error("user {0} already exist")]
     UserAlreadyExist(String),
     #[error("user {0} not found")]
@@ -229,7 +284,9 @@ pub(crate) enum PasswdError {
 
 #[cfg(test)]
 mod test {
-    use crate::files::passwd::{Passwd, PasswdEntry};
+    use serde_json::{from_value, json};
+    use crate::error::Erro;
+    use crate::files::passwd::{Passwd, PasswdEntry, PasswdInput};
     use crate::utils::test::read_test_resources;
 
     #[test]
@@ -315,4 +372,28 @@ mod test {
         // already gone
         assert_eq!(&format!("{:?}", passwd.remove_user("test")), "Err(UserNotFound(\"test\"))");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_validate() {
+        let input: PasswdInput = from_value(json!({"new_entries": [
+            {"user": "homer", "password": "x", "user_id": 1000, "group_id": 1000, "comment": "", "home": "/home/homer", "program": "/bin/sh"},
+            {"user": "marge", "password": "x", "user_id": 1001, "group_id": 1001, "comment": "", "home": "/home/marge", "program": "/bin/sh"},
+        ]})).unwrap();
+        input.validate().unwrap();
+
+        let input: PasswdInput = from_value(json!({"new_entries": [
+            {"user": "homer", "password": "x", "user_id": 1000, "group_id": 1000, "comment": "", "home": "/home/homer", "program": "/bin/sh"},
+            {"user": "homer", "password": "x", "user_id": 4294967295u64, "group_id": 1001, "comment": "a:b", "home": "/home/homer", "program": "/bin/sh"},
+            {"user": "bart simpson", "password": "x", "user_id": 1002, "group_id": 1002, "comment": "", "home": "/home/bart", "program": "/bin/sh"},
+        ]})).unwrap();
+
+        let Err(Erro::InputInvalid(invalid)) = input.validate() else { panic!("input has to be invalid") };
+        assert_eq!(invalid.iter().map(|i| i.field.as_str()).collect::<Vec<&str>>(), vec![
+            "new_entries[1].user_id",
+            "new_entries[1].comment",
+            "new_entries[1].user",
+            "new_entries[2].user",
+        ]);
+        assert_eq!(invalid[2].to_string(), "new_entries[1].user has an invalid value, expected unique user name");
+    }
+}
@@ -0,0 +1,400 @@
+use std::fmt::{Display, Formatter};
+use std::vec;
+use crate::files::prelude::*;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Description)]
+pub struct ShadowEntry {
+    user: String,
+    password: String,
+    last_change: Option<usize>,
+    min_days: Option<usize>,
+    max_days: Option<usize>,
+    warn_days: Option<usize>,
+    inactive_days: Option<usize>,
+    expire: Option<usize>,
+    reserved: String,
+}
+
+impl ShadowEntry {
+    const LOCK: char = '!';
+    const REDACTED: &'static str = "*redacted*";
+
+    fn locked(&self) -> bool {
+        self.password.starts_with(Self::LOCK)
+    }
+
+    /// Hide the password hash but keep the lock state visible
+    fn redact(&mut self) {
+        if self.password.trim_start_matches(Self::LOCK).len() > 1 {
+            let lock = if self.locked() { "!" } else { "" };
+            self.password = format!("{}{}", lock, Self::REDACTED);
+        }
+    }
+}
+
+impl Display for ShadowEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let days = |value: Option<usize>| value.map(|d| d.to_string()).unwrap_or_default();
+
+        write!(f, "{}:{}:{}:{}:{}:{}:{}:{}:{}",
+               self.user,
+               self.password,
+               days(self.last_change),
+               days(self.min_days),
+               days(self.max_days),
+               days(self.warn_days),
+               days(self.inactive_days),
+               days(self.expire),
+               self.reserved,
+        )
+    }
+}
+
+impl ShadowEntry {
+    /// Errors only name the line number, the line contains the password hash
+    fn parse(line: &str, number: usize) -> Result<Self, ShadowError> {
+        let invalid = || ShadowError::InvalidLine(number);
+        let days = |value: &str| if value.is_empty() {
+            Ok(None)
+        } else {
+            value.parse().map(Some).map_err(|_| invalid())
+        };
+
+        let parts: Vec<&str> = line.split(':').collect();
+        let [user, password, last_change, min_days, max_days, warn_days, inactive_days, expire, reserved] = parts.as_slice() else {
+            return Err(invalid());
+        };
+
+        Ok(Self {
+            user: user.to_string(),
+            password: password.to_string(),
+            last_change: days(last_change)?,
+            min_days: days(min_days)?,
+            max_days: days(max_days)?,
+            warn_days: days(warn_days)?,
+            inactive_days: days(inactive_days)?,
+            expire: days(expire)?,
+            reserved: reserved.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Description)]
+pub struct Shadow {
+    content: Vec<ShadowEntry>,
+}
+
+impl Shadow {
+    fn parse(content: &str) -> Resul<Self> {
+        content.split('\n')
+            .enumerate()
+            .filter_map(|(index, s)| {
+                if s.is_empty() {
+                    None
+                } else {
+                    Some(ShadowEntry::parse(s, index + 1))
+                }
+            })
+            .collect::<Result<Vec<ShadowEntry>, ShadowError>>()
+            .map_err(Erro::from)
+            .map(|entries| {
+                Self {
+                    content: entries
+                }
+            })
+    }
+
+    fn content_string(&self) -> String {
+        let s: Vec<String> = self.content
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let mut r = s.join("\n");
+        r.push('\n');
+        r
+    }
+
+    fn redact(&mut self) {
+        self.content.iter_mut().for_each(ShadowEntry::redact);
+    }
+
+    fn entry_mut(&mut self, user: &str) -> Result<&mut ShadowEntry, ShadowError> {
+        self.content
+            .iter_mut()
+            .find(|e| e.user == user)
+            .ok_or(ShadowError::UserNotFound(user.into()))
+    }
+
+    fn add_user(&mut self, entry: ShadowEntry) -> Result<(), ShadowError> {
+        if !self.content
+            .iter().any(|e| e.user == entry.user) {
+            self.content.push(entry);
+            Ok(())
+        } else {
+            Err(ShadowError::UserAlreadyExist(entry.user))
+        }
+    }
+
+    fn remove_user(&mut self, user: &str) -> Result<(), ShadowError> {
+        let len = self.content.len();
+        self.content.retain(|entry| entry.user != user);
+
+        if len == self.content.len() {
+            Err(ShadowError::UserNotFound(user.into()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Same as `passwd -l`, prefix the hash with `!`
+    fn lock(&mut self, user: &str) -> Result<(), ShadowError> {
+        let entry = self.entry_mut(user)?;
+
+        if entry.locked() {
+            Err(ShadowError::AlreadyLocked(user.into()))
+        } else {
+            entry.password.insert(0, ShadowEntry::LOCK);
+            Ok(())
+        }
+    }
+
+    /// Same as `passwd -u`, refuses to leave an account without password
+    fn unlock(&mut self, user: &str) -> Result<(), ShadowError> {
+        let entry = self.entry_mut(user)?;
+
+        if !entry.locked() {
+            Err(ShadowError::NotLocked(user.into()))
+        } else if entry.password.len() == 1 {
+            Err(ShadowError::UnlockPasswordless(user.into()))
+        } else {
+            entry.password.remove(0);
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ShadowFile {
+    path: String,
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct ShadowInput {
+    new_entries: Option<Vec<ShadowEntry>>,
+    remove_by_user: Option<Vec<String>>,
+    lock: Option<Vec<String>>,
+    unlock: Option<Vec<String>>,
+    overwrite: Option<bool>,
+}
+
+#[async_trait]
+impl File for ShadowFile {
+    type Output = Shadow;
+    type Input = ShadowInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        Shadow::parse(&system.read_to_string(&self.path).await?)
+    }
+
+    async fn read_redacted(&self, system: &System) -> Resul<Self::Output> {
+        let mut shadow = self.read(system).await?;
+        shadow.redact();
+        Ok(shadow)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = ShadowInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        if i.overwrite == Some(true) {
+            if let Some(new_entries) = i.new_entries {
+                Ok(Shadow {
+                    content: new_entries
+                }.content_string())
+            } else {
+                Err(ShadowError::NoNewEntries.into())
+            }
+        } else {
+            let mut shadow = Shadow::parse(&system.read_to_string(self.path()).await?)?;
+
+            if let Some(new) = i.new_entries {
+                for e in new.into_iter() {
+                    shadow.add_user(e)?;
+                }
+            }
+
+            if let Some(users) = i.remove_by_user {
+                for user in users.iter() {
+                    shadow.remove_user(user)?;
+                }
+            }
+
+            if let Some(users) = i.lock {
+                for user in users.iter() {
+                    shadow.lock(user)?;
+                }
+            }
+
+            if let Some(users) = i.unlock {
+                for user in users.iter() {
+                    shadow.unlock(user)?;
+                }
+            }
+
+            Ok(shadow.content_string())
+        }
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"new_entries": output["content"], "overwrite": true})
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ShadowBuilder;
+
+impl FileBuilder for ShadowBuilder {
+    type File = ShadowFile;
+
+    const NAME: &'static str = "shadow";
+    const DESCRIPTION: &'static str = "Managed shadow file. Use `?redact=true` to hide password hashes.";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read, Capability::Write, Capability::Delete];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_path("/etc/shadow", &[Os::LinuxAny])];
+        }
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EXAMPLES: Vec<FileExample> = vec![
+                FileExample::new_get("Example content with redacted hashes", vec![ShadowEntry {
+                    user: "homer".to_string(),
+                    password: ShadowEntry::REDACTED.to_string(),
+                    last_change: Some(19091),
+                    min_days: Some(0),
+                    max_days: Some(99999),
+                    warn_days: Some(7),
+                    inactive_days: None,
+                    expire: None,
+                    reserved: "".to_string(),
+                }]),
+                FileExample::new_write("Lock an account and unlock another one.", ShadowInput {
+                    new_entries: None,
+                    remove_by_user: None,
+                    lock: Some(vec!["bart".to_string()]),
+                    unlock: Some(vec!["lisa".to_string()]),
+                    overwrite: Some(false)
+                }),
+                FileExample::new_delete(),
+            ];
+        }
+
+        EXAMPLES.as_slice()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ShadowError {
+    #[error("invalid shadow line {0}")]
+    InvalidLine(usize),
+    #[error("user {0} already exist")]
+    UserAlreadyExist(String),
+    #[error("user {0} not found")]
+    UserNotFound(String),
+    #[error("user {0} already locked")]
+    AlreadyLocked(String),
+    #[error("user {0} not locked")]
+    NotLocked(String),
+    #[error("unlocking user {0} would result in a passwordless account")]
+    UnlockPasswordless(String),
+    #[error("no new entries was given")]
+    NoNewEntries,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::shadow::{Shadow, ShadowEntry};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let content = read_test_resources("shadow");
+        let shadow = Shadow::parse(&content).unwrap();
+
+        assert_eq!(shadow.content.len(), 51);
+        assert_eq!(shadow.content[0], ShadowEntry {
+            user: "root".into(),
+            password: "!".into(),
+            last_change: Some(19091),
+            min_days: Some(0),
+            max_days: Some(99999),
+            warn_days: Some(7),
+            inactive_days: None,
+            expire: None,
+            reserved: "".into(),
+        });
+        assert_eq!(shadow.content[46], ShadowEntry {
+            user: "systemd-coredump".into(),
+            password: "!!".into(),
+            last_change: Some(19091),
+            min_days: None,
+            max_days: None,
+            warn_days: None,
+            inactive_days: None,
+            expire: None,
+            reserved: "".into(),
+        });
+
+        assert_eq!(shadow.content_string(), content);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let error = Shadow::parse("root:!:19091:0:99999:7:::\ndev:$6$salt$hash:19091:0\n").unwrap_err().to_string();
+        assert_eq!(error, "invalid shadow line 2");
+
+        let error = Shadow::parse("dev:$6$salt$hash:x:0:99999:7:::\n").unwrap_err().to_string();
+        assert_eq!(error, "invalid shadow line 1");
+    }
+
+    #[test]
+    fn test_redact() {
+        let mut shadow = Shadow::parse("root:!:19091:0:99999:7:::\ndaemon:*:19091:0:99999:7:::\ndev:$6$salt$hash:19091:0:99999:7:::\nlocked:!$6$salt$hash:19091:0:99999:7:::\n").unwrap();
+
+        shadow.redact();
+
+        assert_eq!(shadow.content_string(), "root:!:19091:0:99999:7:::\ndaemon:*:19091:0:99999:7:::\ndev:*redacted*:19091:0:99999:7:::\nlocked:!*redacted*:19091:0:99999:7:::\n");
+    }
+
+    #[test]
+    fn test_lock_unlock() {
+        let mut shadow = Shadow::parse("dev:$6$salt$hash:19091:0:99999:7:::\nroot:!:19091:0:99999:7:::\n").unwrap();
+
+        shadow.lock("dev").unwrap();
+        assert_eq!(shadow.content[0].password, "!$6$salt$hash");
+
+        // already locked
+        assert_eq!(&format!("{:?}", shadow.lock("dev")), "Err(AlreadyLocked(\"dev\"))");
+
+        shadow.unlock("dev").unwrap();
+        assert_eq!(shadow.content[0].password, "$6$salt$hash");
+
+        assert_eq!(&format!("{:?}", shadow.unlock("dev")), "Err(NotLocked(\"dev\"))");
+        assert_eq!(&format!("{:?}", shadow.unlock("root")), "Err(UnlockPasswordless(\"root\"))");
+        assert_eq!(&format!("{:?}", shadow.lock("test")), "Err(UserNotFound(\"test\"))");
+    }
+}
@@ -0,0 +1,438 @@
+use std::fmt::{Display, Formatter};
+use regex::Regex;
+use crate::description::InvalidField;
+use crate::files::prelude::*;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// Samba compares parameter names ignoring case and whitespace, `read only` equals `readonly`
+fn same_key(a: &str, b: &str) -> bool {
+    let normalize = |k: &str| k.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect::<String>();
+    normalize(a) == normalize(b)
+}
+
+/// key:    like `workgroup`, `path` or `read only`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SmbConfParameter {
+    key: String,
+    value: String,
+}
+
+/// Comments start with `#` or `;`, line continuations with a backslash are joined
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub enum SmbConfLine {
+    Comment(String),
+    Empty,
+    Parameter(SmbConfParameter),
+}
+
+impl Display for SmbConfLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmbConfLine::Comment(c) => f.write_str(c),
+            SmbConfLine::Empty => Ok(()),
+            SmbConfLine::Parameter(p) => write!(f, "{} = {}", p.key, p.value),
+        }
+    }
+}
+
+impl SmbConfLine {
+    fn parse(line: &str) -> Result<Self, SmbConfError> {
+        let l = line.trim();
+
+        if l.is_empty() {
+            Ok(Self::Empty)
+        } else if l.starts_with(['#', ';']) {
+            Ok(Self::Comment(line.into()))
+        } else {
+            let (key, value) = l.split_once('=').ok_or(SmbConfError::InvalidLine(line.into()))?;
+
+            Ok(Self::Parameter(SmbConfParameter {
+                key: key.trim().into(),
+                value: value.trim().into(),
+            }))
+        }
+    }
+
+    fn is(&self, key: &str) -> bool {
+        matches!(self, SmbConfLine::Parameter(p) if same_key(&p.key, key))
+    }
+}
+
+/// name:   `global`, `homes`, `printers` or the name of a share
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SmbConfSection {
+    name: String,
+    lines: Vec<SmbConfLine>,
+}
+
+impl SmbConfSection {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            lines: vec![],
+        }
+    }
+
+    /// Replaces the first parameter with the same key, a missing one is appended after the last parameter
+    fn set(&mut self, parameter: &SmbConfParameter) {
+        let line = SmbConfLine::Parameter(parameter.clone());
+
+        if let Some(l) = self.lines.iter_mut().find(|l| l.is(&parameter.key)) {
+            *l = line;
+        } else {
+            let position = self.lines.iter()
+                .rposition(|l| matches!(l, SmbConfLine::Parameter(_)))
+                .map(|i| i + 1)
+                .unwrap_or(self.lines.len());
+            self.lines.insert(position, line);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SmbConf {
+    #[desc(description = "lines before the first section")]
+    lines: Vec<SmbConfLine>,
+    sections: Vec<SmbConfSection>,
+}
+
+impl Display for SmbConf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+
+        for section in self.sections.iter() {
+            writeln!(f, "[{}]", section.name)?;
+            for line in section.lines.iter() {
+                match line {
+                    SmbConfLine::Parameter(_) => writeln!(f, "   {}", line)?,
+                    _ => writeln!(f, "{}", line)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SmbConf {
+    pub fn parse(content: &str) -> Resul<Self> {
+        let mut lines = vec![];
+        let mut sections: Vec<SmbConfSection> = vec![];
+        let mut continued = String::new();
+
+        for line in content.lines() {
+            let line = if continued.is_empty() { line } else { line.trim_start() };
+            if let Some(l) = line.trim_end().strip_suffix('\\') {
+                continued.push_str(l.trim_end());
+                continued.push(' ');
+                continue;
+            }
+
+            continued.push_str(line);
+            let l = continued.trim();
+
+            if let Some(name) = l.strip_prefix('[') {
+                let name = name.strip_suffix(']').ok_or(SmbConfError::InvalidLine(continued.clone()))?;
+                sections.push(SmbConfSection::new(name.trim()));
+            } else {
+                let parsed = SmbConfLine::parse(&continued)?;
+                match sections.last_mut() {
+                    Some(s) => s.lines.push(parsed),
+                    None => lines.push(parsed),
+                }
+            }
+
+            continued.clear();
+        }
+
+        Ok(Self {
+            lines,
+            sections,
+        })
+    }
+
+    fn validate(&self) -> Resul<()> {
+        let mut invalid = vec![];
+
+        let mut check_lines = |lines: &[SmbConfLine], path: &str| {
+            for (i, line) in lines.iter().enumerate() {
+                match line {
+                    SmbConfLine::Comment(comment) if !comment.trim_start().starts_with(['#', ';']) || comment.contains('\n') =>
+                        invalid.push(InvalidField::value(format!("{}[{}].Comment", path, i), "single line starting with # or ;")),
+                    SmbConfLine::Parameter(p) => {
+                        let key = p.key.trim();
+                        if key.is_empty() || key.starts_with(['#', ';', '[']) || key.contains(['=', '\n']) {
+                            invalid.push(InvalidField::value(format!("{}[{}].Parameter.key", path, i), "name without = or line breaks"));
+                        }
+                        if p.value.contains('\n') || p.value.trim_end().ends_with('\\') {
+                            invalid.push(InvalidField::value(format!("{}[{}].Parameter.value", path, i), "single line not ending with a backslash"));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        };
+
+        check_lines(&self.lines, "lines");
+        for (i, section) in self.sections.iter().enumerate() {
+            check_lines(&section.lines, &format!("sections[{}].lines", i));
+        }
+
+        for (i, section) in self.sections.iter().enumerate() {
+            if section.name.trim().is_empty() || section.name.contains([']', '\n']) {
+                invalid.push(InvalidField::value(format!("sections[{}].name", i), "non-empty name without ] or line breaks"));
+            }
+        }
+
+        if invalid.is_empty() { Ok(()) } else { Err(Erro::InputInvalid(invalid)) }
+    }
+
+    /// Section names are compared ignoring case like samba does
+    fn section(&mut self, name: &str) -> Option<&mut SmbConfSection> {
+        self.sections.iter_mut().find(|s| s.name.eq_ignore_ascii_case(name))
+    }
+
+    fn set(&mut self, value: &SmbConfValue) {
+        if let Some(section) = self.section(&value.section) {
+            section.set(&value.parameter);
+            return;
+        }
+
+        if !matches!(self.sections.last().map(|s| &s.lines).unwrap_or(&self.lines).last(), None | Some(SmbConfLine::Empty)) {
+            match self.sections.last_mut() {
+                Some(s) => s.lines.push(SmbConfLine::Empty),
+                None => self.lines.push(SmbConfLine::Empty),
+            }
+        }
+
+        let mut section = SmbConfSection::new(&value.section);
+        section.set(&value.parameter);
+        self.sections.push(section);
+    }
+
+    fn remove(&mut self, key: &SmbConfKey) -> Result<(), SmbConfError> {
+        let Some(k) = &key.key else {
+            let len = self.sections.len();
+            self.sections.retain(|s| !s.name.eq_ignore_ascii_case(&key.section));
+            return if len == self.sections.len() { Err(SmbConfError::SectionNotFound(key.section.clone())) } else { Ok(()) };
+        };
+
+        let section = self.section(&key.section).ok_or(SmbConfError::SectionNotFound(key.section.clone()))?;
+        let len = section.lines.len();
+        section.lines.retain(|l| !l.is(k));
+
+        if len == section.lines.len() {
+            Err(SmbConfError::KeyNotFound(k.clone()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SmbConfValue {
+    #[desc(description = "created at the end of the file if missing")]
+    section: String,
+    parameter: SmbConfParameter,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SmbConfKey {
+    section: String,
+    #[desc(description = "removes the whole section if missing")]
+    key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct SmbConfInput {
+    #[desc(description = "replaces the whole configuration")]
+    content: Option<SmbConf>,
+    set: Option<Vec<SmbConfValue>>,
+    remove: Option<Vec<SmbConfKey>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SmbConfBuilder;
+
+impl FileBuilder for SmbConfBuilder {
+    file_metadata!(
+        SmbConfFile,
+        "smb_conf",
+        "Read and write the samba configuration by sections. Comments, order and unknown parameters are preserved. Apply changes with `smbcontrol all reload-config`, e.g. by a file hook.",
+        &[Capability::Read, Capability::Write, Capability::Delete],
+        FileExample::new_get("read configuration",
+            SmbConf {
+                lines: vec![SmbConfLine::Comment("# See smb.conf.example for a more detailed config file".into())],
+                sections: vec![
+                    SmbConfSection {
+                        name: "global".into(),
+                        lines: vec![
+                            SmbConfLine::Parameter(SmbConfParameter { key: "workgroup".into(), value: "WORKGROUP".into() }),
+                            SmbConfLine::Parameter(SmbConfParameter { key: "server role".into(), value: "standalone server".into() }),
+                            SmbConfLine::Empty,
+                        ],
+                    },
+                    SmbConfSection {
+                        name: "public".into(),
+                        lines: vec![
+                            SmbConfLine::Parameter(SmbConfParameter { key: "path".into(), value: "/srv/samba/public".into() }),
+                            SmbConfLine::Parameter(SmbConfParameter { key: "read only".into(), value: "yes".into() }),
+                            SmbConfLine::Parameter(SmbConfParameter { key: "guest ok".into(), value: "yes".into() }),
+                        ],
+                    },
+                ],
+            }
+        ),
+        FileExample::new_write("add a share writable by one group",
+            SmbConfInput {
+                content: None,
+                set: Some(vec![
+                    SmbConfValue { section: "projects".into(), parameter: SmbConfParameter { key: "path".into(), value: "/srv/samba/projects".into() } },
+                    SmbConfValue { section: "projects".into(), parameter: SmbConfParameter { key: "valid users".into(), value: "@developers".into() } },
+                    SmbConfValue { section: "projects".into(), parameter: SmbConfParameter { key: "read only".into(), value: "no".into() } },
+                ]),
+                remove: None,
+            }
+        ),
+        FileExample::new_write("remove a share and disable guest access",
+            SmbConfInput {
+                content: None,
+                set: Some(vec![
+                    SmbConfValue { section: "global".into(), parameter: SmbConfParameter { key: "map to guest".into(), value: "never".into() } },
+                ]),
+                remove: Some(vec![SmbConfKey { section: "public".into(), key: None }]),
+            }
+        ),
+        FileExample::new_delete()
+        ;
+        FileMatchPattern::new_regex(Regex::new("^/etc/samba/smb\\.conf$").unwrap(), &[Os::LinuxAny])
+    );
+}
+
+pub struct SmbConfFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for SmbConfFile {
+    type Output = SmbConf;
+    type Input = SmbConfInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        SmbConf::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = SmbConfInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        let mut config = match i.content {
+            Some(content) => content,
+            None => SmbConf::parse(&system.read_to_string(self.path()).await?)?,
+        };
+
+        for value in i.set.unwrap_or_default().iter() {
+            config.set(value);
+        }
+
+        for key in i.remove.unwrap_or_default().iter() {
+            config.remove(key)?;
+        }
+
+        config.validate()?;
+        Ok(config.to_string())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"content": output})
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SmbConfError {
+    #[error("invalid smb.conf line {0}")]
+    InvalidLine(String),
+    #[error("section {0} not found")]
+    SectionNotFound(String),
+    #[error("parameter {0} not found")]
+    KeyNotFound(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::Erro;
+    use crate::files::smb_conf::{SmbConf, SmbConfKey, SmbConfLine, SmbConfParameter, SmbConfValue};
+    use crate::utils::test::read_test_resources;
+
+    fn parameter(key: &str, value: &str) -> SmbConfLine {
+        SmbConfLine::Parameter(SmbConfParameter { key: key.into(), value: value.into() })
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = SmbConf::parse(&read_test_resources("smb_conf")).unwrap();
+
+        assert_eq!(config.lines.len(), 3);
+        assert_eq!(config.sections.iter().map(|s| s.name.as_str()).collect::<Vec<&str>>(), vec!["global", "homes", "public", "projects"]);
+        assert_eq!(config.sections[0].lines[1], parameter("workgroup", "WORKGROUP"));
+        assert_eq!(config.sections[0].lines[3], parameter("interfaces", "lo eth0 192.168.1.0/24"));
+        assert!(matches!(&config.sections[0].lines[2], SmbConfLine::Comment(c) if c.trim() == ";   wins server = w.x.y.z"));
+        assert_eq!(config.sections[2].lines, vec![
+            parameter("comment", "Public files"),
+            parameter("path", "/srv/samba/public"),
+            parameter("read only", "yes"),
+            parameter("guest ok", "yes"),
+            SmbConfLine::Empty,
+        ]);
+
+        assert_eq!(SmbConf::parse(&config.to_string()).unwrap(), config);
+        assert!(SmbConf::parse("[global\n").is_err());
+        assert!(SmbConf::parse("[global]\nworkgroup\n").is_err());
+    }
+
+    #[test]
+    fn test_set_remove() {
+        let mut config = SmbConf::parse(&read_test_resources("smb_conf")).unwrap();
+
+        config.set(&SmbConfValue { section: "Public".into(), parameter: SmbConfParameter { key: "readonly".into(), value: "no".into() } });
+        assert_eq!(config.sections[2].lines[2], parameter("readonly", "no"));
+
+        config.set(&SmbConfValue { section: "public".into(), parameter: SmbConfParameter { key: "browseable".into(), value: "yes".into() } });
+        assert_eq!(config.sections[2].lines[4], parameter("browseable", "yes"));
+        assert_eq!(config.sections[2].lines[5], SmbConfLine::Empty);
+
+        config.set(&SmbConfValue { section: "media".into(), parameter: SmbConfParameter { key: "path".into(), value: "/srv/media".into() } });
+        assert_eq!(config.sections[3].lines.last(), Some(&SmbConfLine::Empty));
+        assert_eq!(config.sections[4].lines, vec![parameter("path", "/srv/media")]);
+        assert!(config.to_string().ends_with("\n[media]\n   path = /srv/media\n"));
+
+        config.remove(&SmbConfKey { section: "global".into(), key: Some("Work Group".into()) }).unwrap();
+        assert!(!config.sections[0].lines.contains(&parameter("workgroup", "WORKGROUP")));
+        assert!(config.remove(&SmbConfKey { section: "global".into(), key: Some("workgroup".into()) }).is_err());
+
+        config.remove(&SmbConfKey { section: "homes".into(), key: None }).unwrap();
+        assert_eq!(config.sections.len(), 4);
+        assert!(config.remove(&SmbConfKey { section: "homes".into(), key: None }).is_err());
+        config.validate().unwrap();
+
+        config.set(&SmbConfValue { section: "bad]".into(), parameter: SmbConfParameter { key: "a = b".into(), value: "c\nd".into() } });
+        let Err(Erro::InputInvalid(invalid)) = config.validate() else { panic!("configuration has to be invalid") };
+        assert_eq!(invalid.iter().map(|i| i.field.as_str()).collect::<Vec<&str>>(), vec![
+            "sections[4].lines[0].Parameter.key",
+            "sections[4].lines[0].Parameter.value",
+            "sections[4].name",
+        ]);
+    }
+}
@@ -0,0 +1,447 @@
+use std::fmt::{Display, Formatter};
+use regex::Regex;
+use crate::files::prelude::*;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Description)]
+#[serde(rename_all = "kebab-case")]
+pub enum PermitRootLogin {
+    Yes,
+    No,
+    ProhibitPassword,
+    ForcedCommandsOnly,
+}
+
+impl Display for PermitRootLogin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PermitRootLogin::Yes => "yes",
+            PermitRootLogin::No => "no",
+            PermitRootLogin::ProhibitPassword => "prohibit-password",
+            PermitRootLogin::ForcedCommandsOnly => "forced-commands-only",
+        })
+    }
+}
+
+/// Directive with a typed value, keywords are case insensitive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub enum SshdConfigDirective {
+    PermitRootLogin(PermitRootLogin),
+    PasswordAuthentication(bool),
+    Port(usize),
+    #[desc(description = "all other options like they are written")]
+    Other {
+        keyword: String,
+        value: String,
+    },
+}
+
+impl Display for SshdConfigDirective {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SshdConfigDirective::PermitRootLogin(p) => write!(f, "PermitRootLogin {}", p),
+            SshdConfigDirective::PasswordAuthentication(p) => write!(f, "PasswordAuthentication {}", if *p { "yes" } else { "no" }),
+            SshdConfigDirective::Port(p) => write!(f, "Port {}", p),
+            SshdConfigDirective::Other { keyword, value } => write!(f, "{} {}", keyword, value),
+        }
+    }
+}
+
+impl SshdConfigDirective {
+    fn parse(keyword: &str, value: &str) -> Result<Self, SshdConfigError> {
+        let invalid = || SshdConfigError::InvalidValue(keyword.into(), value.into());
+
+        Ok(match keyword.to_ascii_lowercase().as_str() {
+            "permitrootlogin" => Self::PermitRootLogin(match value.to_ascii_lowercase().as_str() {
+                "yes" => PermitRootLogin::Yes,
+                "no" => PermitRootLogin::No,
+                "prohibit-password" | "without-password" => PermitRootLogin::ProhibitPassword,
+                "forced-commands-only" => PermitRootLogin::ForcedCommandsOnly,
+                _ => return Err(invalid()),
+            }),
+            "passwordauthentication" => Self::PasswordAuthentication(match value.to_ascii_lowercase().as_str() {
+                "yes" => true,
+                "no" => false,
+                _ => return Err(invalid()),
+            }),
+            "port" => Self::Port(value.parse::<u16>().map_err(|_| invalid())?.into()),
+            _ => Self::Other {
+                keyword: keyword.into(),
+                value: value.into(),
+            },
+        })
+    }
+
+    fn keyword(&self) -> &str {
+        match self {
+            SshdConfigDirective::PermitRootLogin(_) => "PermitRootLogin",
+            SshdConfigDirective::PasswordAuthentication(_) => "PasswordAuthentication",
+            SshdConfigDirective::Port(_) => "Port",
+            SshdConfigDirective::Other { keyword, .. } => keyword,
+        }
+    }
+
+    fn is(&self, keyword: &str) -> bool {
+        self.keyword().eq_ignore_ascii_case(keyword)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub enum SshdConfigLine {
+    Comment(String),
+    Empty,
+    Directive(SshdConfigDirective),
+}
+
+impl Display for SshdConfigLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SshdConfigLine::Comment(c) => f.write_str(c),
+            SshdConfigLine::Empty => Ok(()),
+            SshdConfigLine::Directive(d) => d.fmt(f),
+        }
+    }
+}
+
+impl SshdConfigLine {
+    /// `Keyword value` or `Keyword=value`
+    fn parse(line: &str) -> Resul<Self> {
+        let l = line.trim();
+
+        if l.is_empty() {
+            Ok(Self::Empty)
+        } else if l.starts_with('#') {
+            Ok(Self::Comment(line.into()))
+        } else {
+            let (keyword, value) = l.split_once(|c: char| c.is_whitespace() || c == '=').ok_or(SshdConfigError::InvalidLine(line.into()))?;
+            let value = value.trim_start();
+            let value = value.strip_prefix('=').unwrap_or(value).trim_start();
+
+            Ok(Self::Directive(SshdConfigDirective::parse(keyword, value)?))
+        }
+    }
+}
+
+/// Lines between `Match` and the next `Match` or the end of the file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SshdConfigMatch {
+    #[desc(description = "like `User backup` or `Address 192.168.1.0/24`")]
+    criteria: String,
+    lines: Vec<SshdConfigLine>,
+}
+
+impl SshdConfigMatch {
+    fn new(criteria: &str) -> Self {
+        Self {
+            criteria: criteria.into(),
+            lines: vec![],
+        }
+    }
+}
+
+/// Replaces the first directive with the same keyword, the first one is used by sshd.
+/// A missing directive is appended after the last one.
+fn set(lines: &mut Vec<SshdConfigLine>, directive: &SshdConfigDirective) {
+    let line = SshdConfigLine::Directive(directive.clone());
+
+    if let Some(l) = lines.iter_mut().find(|l| matches!(l, SshdConfigLine::Directive(d) if d.is(directive.keyword()))) {
+        *l = line;
+    } else {
+        let position = lines.iter()
+            .rposition(|l| matches!(l, SshdConfigLine::Directive(_)))
+            .map(|i| i + 1)
+            .unwrap_or(lines.len());
+        lines.insert(position, line);
+    }
+}
+
+/// Removes all directives with the keyword and returns whether one was found
+fn remove(lines: &mut Vec<SshdConfigLine>, keyword: &str) -> bool {
+    let len = lines.len();
+    lines.retain(|l| !matches!(l, SshdConfigLine::Directive(d) if d.is(keyword)));
+    len != lines.len()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SshdConfig {
+    #[desc(description = "lines before the first Match block")]
+    lines: Vec<SshdConfigLine>,
+    matches: Vec<SshdConfigMatch>,
+}
+
+impl Display for SshdConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+
+        for m in self.matches.iter() {
+            writeln!(f, "Match {}", m.criteria)?;
+            for line in m.lines.iter() {
+                match line {
+                    SshdConfigLine::Directive(d) => writeln!(f, "    {}", d)?,
+                    _ => writeln!(f, "{}", line)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SshdConfig {
+    pub fn parse(content: &str) -> Resul<Self> {
+        let mut lines = vec![];
+        let mut matches: Vec<SshdConfigMatch> = vec![];
+
+        for line in content.lines() {
+            let parsed = SshdConfigLine::parse(line)?;
+
+            if let SshdConfigLine::Directive(SshdConfigDirective::Other { keyword, value }) = &parsed {
+                if keyword.eq_ignore_ascii_case("match") {
+                    matches.push(SshdConfigMatch::new(value));
+                    continue;
+                }
+            }
+
+            match matches.last_mut() {
+                Some(m) => m.lines.push(parsed),
+                None => lines.push(parsed),
+            }
+        }
+
+        Ok(Self {
+            lines,
+            matches,
+        })
+    }
+
+    fn set(&mut self, value: &SshdConfigValue) {
+        match &value.criteria {
+            None => set(&mut self.lines, &value.directive),
+            Some(criteria) => match self.matches.iter_mut().find(|m| &m.criteria == criteria) {
+                Some(m) => set(&mut m.lines, &value.directive),
+                None => {
+                    if !matches!(self.matches.last().map(|m| &m.lines).unwrap_or(&self.lines).last(), None | Some(SshdConfigLine::Empty)) {
+                        match self.matches.last_mut() {
+                            Some(m) => m.lines.push(SshdConfigLine::Empty),
+                            None => self.lines.push(SshdConfigLine::Empty),
+                        }
+                    }
+
+                    let mut m = SshdConfigMatch::new(criteria);
+                    set(&mut m.lines, &value.directive);
+                    self.matches.push(m);
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &SshdConfigKey) -> Result<(), SshdConfigError> {
+        let lines = match &key.criteria {
+            None => &mut self.lines,
+            Some(criteria) => &mut self.matches.iter_mut()
+                .find(|m| &m.criteria == criteria)
+                .ok_or(SshdConfigError::MatchNotFound(criteria.clone()))?
+                .lines,
+        };
+
+        if remove(lines, &key.keyword) {
+            Ok(())
+        } else {
+            Err(SshdConfigError::KeywordNotFound(key.keyword.clone()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SshdConfigValue {
+    #[desc(description = "criteria of the Match block, outside of Match blocks if missing")]
+    criteria: Option<String>,
+    directive: SshdConfigDirective,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SshdConfigKey {
+    #[desc(description = "criteria of the Match block, outside of Match blocks if missing")]
+    criteria: Option<String>,
+    keyword: String,
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct SshdConfigInput {
+    #[desc(description = "replaces the whole configuration")]
+    content: Option<SshdConfig>,
+    set: Option<Vec<SshdConfigValue>>,
+    remove: Option<Vec<SshdConfigKey>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SshdConfigBuilder;
+
+impl FileBuilder for SshdConfigBuilder {
+    file_metadata!(
+        SshdConfigFile,
+        "sshd_config",
+        "Read and write the ssh server configuration. Comments, order and unknown options are preserved.",
+        &[Capability::Read, Capability::Write, Capability::Delete],
+        FileExample::new_get("read configuration",
+            SshdConfig {
+                lines: vec![
+                    SshdConfigLine::Directive(SshdConfigDirective::Other { keyword: "Include".into(), value: "/etc/ssh/sshd_config.d/*.conf".into() }),
+                    SshdConfigLine::Empty,
+                    SshdConfigLine::Comment("#Port 22".into()),
+                    SshdConfigLine::Directive(SshdConfigDirective::PermitRootLogin(PermitRootLogin::ProhibitPassword)),
+                    SshdConfigLine::Empty,
+                ],
+                matches: vec![SshdConfigMatch {
+                    criteria: "User backup".into(),
+                    lines: vec![
+                        SshdConfigLine::Directive(SshdConfigDirective::Other { keyword: "ForceCommand".into(), value: "internal-sftp".into() }),
+                    ],
+                }],
+            }
+        ),
+        FileExample::new_write("disable root and password login",
+            SshdConfigInput {
+                content: None,
+                set: Some(vec![
+                    SshdConfigValue { criteria: None, directive: SshdConfigDirective::PermitRootLogin(PermitRootLogin::No) },
+                    SshdConfigValue { criteria: None, directive: SshdConfigDirective::PasswordAuthentication(false) },
+                    SshdConfigValue { criteria: None, directive: SshdConfigDirective::Other { keyword: "MaxAuthTries".into(), value: "3".into() } },
+                ]),
+                remove: None,
+            }
+        ),
+        FileExample::new_write("move to another port and allow password login from the local network only",
+            SshdConfigInput {
+                content: None,
+                set: Some(vec![
+                    SshdConfigValue { criteria: None, directive: SshdConfigDirective::Port(2222) },
+                    SshdConfigValue { criteria: None, directive: SshdConfigDirective::PasswordAuthentication(false) },
+                    SshdConfigValue { criteria: Some("Address 192.168.1.0/24".into()), directive: SshdConfigDirective::PasswordAuthentication(true) },
+                ]),
+                remove: Some(vec![SshdConfigKey { criteria: None, keyword: "X11Forwarding".into() }]),
+            }
+        ),
+        FileExample::new_delete()
+        ;
+        FileMatchPattern::new_regex(Regex::new("^/etc/ssh/sshd_config$").unwrap(), &[Os::LinuxAny]),
+        FileMatchPattern::new_regex(Regex::new("^/etc/ssh/sshd_config\\.d/[^/]+\\.conf$").unwrap(), &[Os::LinuxAny])
+    );
+}
+
+pub struct SshdConfigFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for SshdConfigFile {
+    type Output = SshdConfig;
+    type Input = SshdConfigInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        SshdConfig::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = SshdConfigInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        let mut config = match i.content {
+            Some(content) => content,
+            None => SshdConfig::parse(&system.read_to_string(self.path()).await?)?,
+        };
+
+        for value in i.set.unwrap_or_default().iter() {
+            config.set(value);
+        }
+
+        for key in i.remove.unwrap_or_default().iter() {
+            config.remove(key)?;
+        }
+
+        Ok(config.to_string())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"content": output})
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SshdConfigError {
+    #[error("invalid sshd_config line {0}")]
+    InvalidLine(String),
+    #[error("invalid value {1} of {0}")]
+    InvalidValue(String, String),
+    #[error("match block {0} not found")]
+    MatchNotFound(String),
+    #[error("keyword {0} not found")]
+    KeywordNotFound(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::sshd_config::{PermitRootLogin, SshdConfig, SshdConfigDirective, SshdConfigKey, SshdConfigLine, SshdConfigValue};
+    use crate::utils::test::read_test_resources;
+
+    fn other(keyword: &str, value: &str) -> SshdConfigLine {
+        SshdConfigLine::Directive(SshdConfigDirective::Other { keyword: keyword.into(), value: value.into() })
+    }
+
+    #[test]
+    fn test_parse() {
+        let content = read_test_resources("sshd_config");
+        let config = SshdConfig::parse(&content).unwrap();
+
+        assert_eq!(config.lines[4], SshdConfigLine::Directive(SshdConfigDirective::Port(22)));
+        assert_eq!(config.lines[6], SshdConfigLine::Directive(SshdConfigDirective::PermitRootLogin(PermitRootLogin::ProhibitPassword)));
+        assert_eq!(config.lines[7], SshdConfigLine::Directive(SshdConfigDirective::PasswordAuthentication(true)));
+        assert_eq!(config.lines[8], other("X11Forwarding", "yes"));
+        assert_eq!(config.matches.iter().map(|m| m.criteria.as_str()).collect::<Vec<&str>>(), vec!["User backup", "Address 192.168.1.0/24"]);
+        assert_eq!(config.matches[0].lines, vec![
+            other("ForceCommand", "internal-sftp"),
+            other("ChrootDirectory", "/srv/backup"),
+            SshdConfigLine::Empty,
+        ]);
+
+        assert_eq!(config.to_string(), content);
+
+        assert!(SshdConfig::parse("PermitRootLogin maybe\n").is_err());
+        assert!(SshdConfig::parse("Port=70000\n").is_err());
+        assert_eq!(SshdConfig::parse("port=2222\n").unwrap().lines, vec![SshdConfigLine::Directive(SshdConfigDirective::Port(2222))]);
+    }
+
+    #[test]
+    fn test_set_remove() {
+        let mut config = SshdConfig::parse(&read_test_resources("sshd_config")).unwrap();
+
+        config.set(&SshdConfigValue { criteria: None, directive: SshdConfigDirective::PermitRootLogin(PermitRootLogin::No) });
+        config.set(&SshdConfigValue { criteria: None, directive: SshdConfigDirective::Other { keyword: "MaxAuthTries".into(), value: "3".into() } });
+        config.set(&SshdConfigValue { criteria: Some("User backup".into()), directive: SshdConfigDirective::PasswordAuthentication(false) });
+        config.set(&SshdConfigValue { criteria: Some("Group admin".into()), directive: SshdConfigDirective::Other { keyword: "AllowTcpForwarding".into(), value: "yes".into() } });
+        config.remove(&SshdConfigKey { criteria: None, keyword: "x11forwarding".into() }).unwrap();
+
+        assert_eq!(config.lines[6], SshdConfigLine::Directive(SshdConfigDirective::PermitRootLogin(PermitRootLogin::No)));
+        assert_eq!(config.lines[8], other("Subsystem", "sftp /usr/lib/openssh/sftp-server"));
+        assert_eq!(config.lines[9], other("MaxAuthTries", "3"));
+        assert_eq!(config.matches[0].lines[2], SshdConfigLine::Directive(SshdConfigDirective::PasswordAuthentication(false)));
+        assert!(config.to_string().ends_with("    PasswordAuthentication yes\n\nMatch Group admin\n    AllowTcpForwarding yes\n"));
+
+        assert_eq!(&format!("{:?}", config.remove(&SshdConfigKey { criteria: None, keyword: "X11Forwarding".into() })),
+                   "Err(KeywordNotFound(\"X11Forwarding\"))");
+        assert_eq!(&format!("{:?}", config.remove(&SshdConfigKey { criteria: Some("User root".into()), keyword: "Port".into() })),
+                   "Err(MatchNotFound(\"User root\"))");
+    }
+}
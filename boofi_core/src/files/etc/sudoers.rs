@@ -0,0 +1,429 @@
+use std::fmt::{Display, Formatter};
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::files::prelude::*;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+const ALIASES: [&str; 5] = ["User_Alias", "Runas_Alias", "Host_Alias", "Cmnd_Alias", "Cmd_Alias"];
+
+const INCLUDES: [&str; 4] = ["@include", "@includedir", "#include", "#includedir"];
+
+/// Comma separated values, commas in double quotes or escaped with a backslash are kept
+fn split_list(value: &str) -> Vec<String> {
+    let mut values = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for c in value.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                values.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+
+    values.push(current.trim().to_string());
+    values.into_iter().filter(|v| !v.is_empty()).collect()
+}
+
+/// binding:    like `:alice`, `@host`, `>root` or `!/usr/bin/ls`, all users if missing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SudoersDefaults {
+    binding: Option<String>,
+    #[desc(description = "like `env_reset`, `!requiretty` or `secure_path=\"/usr/bin:/bin\"`")]
+    parameters: Vec<String>,
+}
+
+/// kind:   `User_Alias`, `Runas_Alias`, `Host_Alias` or `Cmnd_Alias`
+/// name:   upper case name used in other lines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SudoersAlias {
+    kind: String,
+    name: String,
+    members: Vec<String>,
+}
+
+/// users:  names, `%group`, `#uid` or aliases
+/// run_as: like `root` or `ALL:ALL`, root if missing
+/// tags:   like `NOPASSWD` or `SETENV`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SudoersSpec {
+    users: Vec<String>,
+    hosts: Vec<String>,
+    run_as: Option<String>,
+    tags: Vec<String>,
+    commands: Vec<String>,
+}
+
+/// keyword:    `@include`, `@includedir` or the older `#include` and `#includedir`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SudoersInclude {
+    keyword: String,
+    path: String,
+}
+
+/// Line continuations with a backslash are joined
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub enum SudoersLine {
+    Comment(String),
+    Empty,
+    Defaults(SudoersDefaults),
+    Alias(SudoersAlias),
+    Spec(SudoersSpec),
+    Include(SudoersInclude),
+}
+
+impl Display for SudoersLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SudoersLine::Comment(c) => f.write_str(c),
+            SudoersLine::Empty => Ok(()),
+            SudoersLine::Defaults(d) => write!(f, "Defaults{}\t{}", d.binding.as_deref().unwrap_or_default(), d.parameters.join(", ")),
+            SudoersLine::Alias(a) => write!(f, "{}\t{} = {}", a.kind, a.name, a.members.join(", ")),
+            SudoersLine::Spec(s) => {
+                write!(f, "{}\t{} = ", s.users.join(", "), s.hosts.join(", "))?;
+                if let Some(run_as) = &s.run_as {
+                    write!(f, "({}) ", run_as)?;
+                }
+                for tag in s.tags.iter() {
+                    write!(f, "{}: ", tag)?;
+                }
+                f.write_str(&s.commands.join(", "))
+            }
+            SudoersLine::Include(i) => write!(f, "{} {}", i.keyword, i.path),
+        }
+    }
+}
+
+impl SudoersLine {
+    fn parse(line: &str) -> Result<Self, SudoersError> {
+        let l = line.trim();
+        let invalid = || SudoersError::InvalidLine(line.into());
+        let (keyword, rest) = l.split_once(char::is_whitespace).map(|(k, r)| (k, r.trim())).unwrap_or((l, ""));
+
+        if l.is_empty() {
+            Ok(Self::Empty)
+        } else if INCLUDES.contains(&keyword) {
+            Ok(Self::Include(SudoersInclude {
+                keyword: keyword.into(),
+                path: rest.into(),
+            }))
+        } else if l.starts_with('#') {
+            Ok(Self::Comment(line.into()))
+        } else if let Some(binding) = keyword.strip_prefix("Defaults") {
+            if !matches!(binding.chars().next(), None | Some(':' | '@' | '!' | '>')) {
+                return Err(invalid());
+            }
+
+            Ok(Self::Defaults(SudoersDefaults {
+                binding: (!binding.is_empty()).then(|| binding.into()),
+                parameters: split_list(rest),
+            }))
+        } else if ALIASES.contains(&keyword) {
+            let (name, members) = rest.split_once('=').ok_or_else(invalid)?;
+
+            Ok(Self::Alias(SudoersAlias {
+                kind: keyword.into(),
+                name: name.trim().into(),
+                members: split_list(members),
+            }))
+        } else {
+            Self::parse_spec(l).map(Self::Spec).ok_or_else(invalid)
+        }
+    }
+
+    /// `users hosts = (run_as) TAG: commands`
+    fn parse_spec(line: &str) -> Option<SudoersSpec> {
+        lazy_static! {
+            static ref COMMA: Regex = Regex::new(r"\s*,\s*").unwrap();
+            static ref TAG: Regex = Regex::new(r"^([A-Z_]+):\s*").unwrap();
+        }
+
+        let (left, right) = line.split_once('=')?;
+        let left = COMMA.replace_all(left.trim(), ",");
+        let (users, hosts) = match left.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            [users, hosts] => (split_list(users), split_list(hosts)),
+            _ => return None,
+        };
+
+        let mut rest = right.trim();
+        let run_as = match rest.strip_prefix('(') {
+            Some(r) => {
+                let (run_as, r) = r.split_once(')')?;
+                rest = r.trim_start();
+                Some(run_as.trim().to_string())
+            }
+            None => None,
+        };
+
+        let mut tags = vec![];
+        while let Some(tag) = TAG.captures(rest) {
+            tags.push(tag[1].to_string());
+            rest = &rest[tag[0].len()..];
+        }
+
+        Some(SudoersSpec {
+            users,
+            hosts,
+            run_as,
+            tags,
+            commands: split_list(rest),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct Sudoers {
+    lines: Vec<SudoersLine>,
+}
+
+impl Display for Sudoers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Sudoers {
+    pub fn parse(content: &str) -> Resul<Self> {
+        let mut lines = vec![];
+        let mut continued = String::new();
+
+        for line in content.lines() {
+            if let Some(l) = line.trim_end().strip_suffix('\\') {
+                continued.push_str(l);
+                continued.push(' ');
+                continue;
+            }
+
+            continued.push_str(line);
+            lines.push(SudoersLine::parse(&continued)?);
+            continued.clear();
+        }
+
+        Ok(Self {
+            lines,
+        })
+    }
+
+    fn visudo() -> &'static str {
+        "/usr/sbin/visudo"
+    }
+
+    /// Checks the content with `visudo` in a hidden file next to the target, sudo skips files with a dot in `@includedir`
+    async fn validate(path: &str, content: &str, system: &System) -> Resul<()> {
+        let (directory, name) = path.rsplit_once('/').unwrap_or((".", path));
+        let temp = format!("{}/.{}.boofi-check-{:x}", directory, name, rand::random::<u64>());
+
+        system.write(&temp, content.as_bytes()).await?;
+        let result = system.run_output(Self::visudo(), &["-c", "-q", "-f", temp.as_str()]).await;
+        if let Err(e) = system.delete(&temp).await {
+            tracing::warn!("[SUDOERS] temporary file {} not removed: {}", temp, e);
+        }
+
+        let output = result?;
+        if output.exit_code() != 0 {
+            let message = format!("{}{}", output.stdout(), output.stderr()).replace(temp.as_str(), path);
+            return Err(SudoersError::Rejected(message.trim().into()).into());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct SudoersInput {
+    #[desc(description = "replaces the whole file")]
+    content: Option<Sudoers>,
+    #[desc(description = "lines appended to the file")]
+    add: Option<Vec<SudoersLine>>,
+    #[desc(description = "lines removed from the file, they have to be equal")]
+    remove: Option<Vec<SudoersLine>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SudoersBuilder;
+
+impl FileBuilder for SudoersBuilder {
+    file_metadata!(
+        SudoersFile,
+        "sudoers",
+        "Read and write sudo rules, needs sudo. Every write is checked with `visudo` first and invalid content is rejected, written files get mode 0440.",
+        &[Capability::Read, Capability::Write, Capability::Delete],
+        FileExample::new_get("read rules",
+            Sudoers {
+                lines: vec![
+                    SudoersLine::Defaults(SudoersDefaults { binding: None, parameters: vec!["env_reset".into()] }),
+                    SudoersLine::Spec(SudoersSpec {
+                        users: vec!["root".into()],
+                        hosts: vec!["ALL".into()],
+                        run_as: Some("ALL:ALL".into()),
+                        tags: vec![],
+                        commands: vec!["ALL".into()],
+                    }),
+                    SudoersLine::Include(SudoersInclude { keyword: "@includedir".into(), path: "/etc/sudoers.d".into() }),
+                ],
+            }
+        ),
+        FileExample::new_write("allow deploy to restart nginx without password",
+            SudoersInput {
+                content: None,
+                add: Some(vec![SudoersLine::Spec(SudoersSpec {
+                    users: vec!["deploy".into()],
+                    hosts: vec!["ALL".into()],
+                    run_as: Some("root".into()),
+                    tags: vec!["NOPASSWD".into()],
+                    commands: vec!["/usr/bin/systemctl restart nginx".into()],
+                })]),
+                remove: None,
+            }
+        ),
+        FileExample::new_delete()
+        ;
+        FileMatchPattern::new_regex(Regex::new("^/etc/sudoers$").unwrap(), &[Os::LinuxAny]),
+        FileMatchPattern::new_regex(Regex::new("^/etc/sudoers\\.d/[^/]+$").unwrap(), &[Os::LinuxAny])
+    );
+}
+
+pub struct SudoersFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for SudoersFile {
+    type Output = Sudoers;
+    type Input = SudoersInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        Sudoers::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = SudoersInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        let mut sudoers = match i.content {
+            Some(content) => content,
+            None => Sudoers::parse(&system.read_to_string(self.path()).await?)?,
+        };
+
+        for line in i.remove.unwrap_or_default().iter() {
+            let len = sudoers.lines.len();
+            sudoers.lines.retain(|l| l != line);
+            if len == sudoers.lines.len() {
+                return Err(SudoersError::LineNotFound(line.to_string()).into());
+            }
+        }
+
+        sudoers.lines.extend(i.add.unwrap_or_default());
+        Ok(sudoers.to_string())
+    }
+
+    async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
+        let content = self.render(input, system).await?;
+        Sudoers::validate(self.path(), &content, system).await?;
+
+        system.write(self.path(), content.as_bytes()).await?;
+        system.chmod(self.path(), "440").await
+    }
+
+    async fn check<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
+        let content = self.render(input, system).await?;
+        Sudoers::validate(self.path(), &content, system).await
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"content": output})
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SudoersError {
+    #[error("invalid sudoers line {0}")]
+    InvalidLine(String),
+    #[error("line {0} not found")]
+    LineNotFound(String),
+    #[error("rejected by visudo: {0}")]
+    Rejected(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::sudoers::{split_list, Sudoers, SudoersAlias, SudoersDefaults, SudoersInclude, SudoersLine, SudoersSpec};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let sudoers = Sudoers::parse(&read_test_resources("sudoers")).unwrap();
+
+        assert_eq!(sudoers.lines.len(), 19);
+        assert_eq!(sudoers.lines[5], SudoersLine::Defaults(SudoersDefaults {
+            binding: None,
+            parameters: vec!["secure_path=\"/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin\"".into()],
+        }));
+        assert_eq!(sudoers.lines[6], SudoersLine::Defaults(SudoersDefaults {
+            binding: Some(":deploy".into()),
+            parameters: vec!["!requiretty".into(), "env_keep += \"GIT_SSH_COMMAND\"".into()],
+        }));
+        assert_eq!(sudoers.lines[9], SudoersLine::Alias(SudoersAlias {
+            kind: "Cmnd_Alias".into(),
+            name: "SERVICES".into(),
+            members: vec!["/usr/bin/systemctl restart nginx".into(), "/usr/bin/systemctl reload nginx".into()],
+        }));
+        assert_eq!(sudoers.lines[12], SudoersLine::Spec(SudoersSpec {
+            users: vec!["root".into()],
+            hosts: vec!["ALL".into()],
+            run_as: Some("ALL:ALL".into()),
+            tags: vec![],
+            commands: vec!["ALL".into()],
+        }));
+        assert_eq!(sudoers.lines[15], SudoersLine::Spec(SudoersSpec {
+            users: vec!["%admin".into()],
+            hosts: vec!["ALL".into()],
+            run_as: Some("ALL".into()),
+            tags: vec![],
+            commands: vec!["ALL".into()],
+        }));
+        assert_eq!(sudoers.lines[16], SudoersLine::Spec(SudoersSpec {
+            users: vec!["deploy".into(), "ops".into()],
+            hosts: vec!["web1".into(), "web2".into()],
+            run_as: Some("root".into()),
+            tags: vec!["NOPASSWD".into()],
+            commands: vec!["SERVICES".into(), "/usr/bin/journalctl -u nginx".into()],
+        }));
+        assert_eq!(sudoers.lines[18], SudoersLine::Include(SudoersInclude { keyword: "@includedir".into(), path: "/etc/sudoers.d".into() }));
+        assert!(matches!(&sudoers.lines[0], SudoersLine::Comment(c) if c == "#"));
+
+        assert_eq!(Sudoers::parse(&sudoers.to_string()).unwrap(), sudoers);
+        assert_eq!(sudoers.lines[16].to_string(), "deploy, ops\tweb1, web2 = (root) NOPASSWD: SERVICES, /usr/bin/journalctl -u nginx");
+
+        assert!(Sudoers::parse("deploy ALL\n").is_err());
+        assert!(Sudoers::parse("Defaults_x env_reset\n").is_err());
+    }
+
+    #[test]
+    fn test_split_list() {
+        assert_eq!(split_list(r#"a, "b, c",d\, e ,"#), vec!["a", "\"b, c\"", "d\\, e"]);
+    }
+}
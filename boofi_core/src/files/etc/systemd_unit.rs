@@ -0,0 +1,385 @@
+use std::fmt::{Display, Formatter};
+use regex::Regex;
+use crate::files::prelude::*;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub enum SystemdUnitLine {
+    Comment(String),
+    Empty,
+    Entry {
+        key: String,
+        value: String,
+    },
+}
+
+impl Display for SystemdUnitLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SystemdUnitLine::Comment(c) => f.write_str(c),
+            SystemdUnitLine::Empty => Ok(()),
+            SystemdUnitLine::Entry { key, value } => write!(f, "{}={}", key, value),
+        }
+    }
+}
+
+impl SystemdUnitLine {
+    fn parse(line: &str) -> Resul<Self> {
+        let l = line.trim();
+
+        if l.is_empty() {
+            Ok(Self::Empty)
+        } else if l.starts_with('#') || l.starts_with(';') {
+            Ok(Self::Comment(line.into()))
+        } else {
+            let (key, value) = l.split_once('=').ok_or(SystemdUnitError::InvalidLine(line.into()))?;
+            Ok(Self::Entry {
+                key: key.trim().into(),
+                value: value.trim_start().into(),
+            })
+        }
+    }
+}
+
+/// A section like `[Service]` with all its lines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SystemdUnitSection {
+    name: String,
+    lines: Vec<SystemdUnitLine>,
+}
+
+impl SystemdUnitSection {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            lines: vec![],
+        }
+    }
+
+    /// Replaces the value of the first matching key or appends it after the last entry
+    fn set(&mut self, key: &str, value: &str) {
+        let entry = SystemdUnitLine::Entry { key: key.into(), value: value.into() };
+
+        if let Some(line) = self.lines.iter_mut().find(|l| matches!(l, SystemdUnitLine::Entry { key: k, .. } if k == key)) {
+            *line = entry;
+        } else {
+            let position = self.lines.iter()
+                .rposition(|l| matches!(l, SystemdUnitLine::Entry { .. }))
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            self.lines.insert(position, entry);
+        }
+    }
+
+    /// Removes all entries with the given key
+    fn remove(&mut self, key: &str) -> Result<(), SystemdUnitError> {
+        let len = self.lines.len();
+        self.lines.retain(|l| !matches!(l, SystemdUnitLine::Entry { key: k, .. } if k == key));
+
+        if len == self.lines.len() {
+            Err(SystemdUnitError::KeyNotFound(self.name.clone(), key.into()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SystemdUnit {
+    #[desc(description = "comments before the first section")]
+    header: Vec<SystemdUnitLine>,
+    sections: Vec<SystemdUnitSection>,
+}
+
+impl Display for SystemdUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.header.iter() {
+            writeln!(f, "{}", line)?;
+        }
+
+        for section in self.sections.iter() {
+            writeln!(f, "[{}]", section.name)?;
+            for line in section.lines.iter() {
+                writeln!(f, "{}", line)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SystemdUnit {
+    pub fn parse(content: &str) -> Resul<Self> {
+        let mut header = vec![];
+        let mut sections: Vec<SystemdUnitSection> = vec![];
+        let mut continuation: Option<String> = None;
+
+        for line in content.lines() {
+            // multiline values end with a backslash
+            let l = if let Some(mut previous) = continuation.take() {
+                previous.push('\n');
+                previous.push_str(line);
+                previous
+            } else {
+                line.to_string()
+            };
+
+            if l.ends_with('\\') && !l.trim_start().starts_with(['#', ';']) {
+                continuation = Some(l);
+                continue;
+            }
+
+            let trimmed = l.trim();
+            if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                sections.push(SystemdUnitSection::new(&trimmed[1..trimmed.len() - 1]));
+            } else {
+                let parsed = SystemdUnitLine::parse(&l)?;
+                match sections.last_mut() {
+                    Some(section) => section.lines.push(parsed),
+                    None => header.push(parsed),
+                }
+            }
+        }
+
+        if let Some(l) = continuation {
+            return Err(SystemdUnitError::InvalidLine(l).into());
+        }
+
+        Ok(Self {
+            header,
+            sections,
+        })
+    }
+
+    fn section_mut(&mut self, name: &str) -> Option<&mut SystemdUnitSection> {
+        self.sections.iter_mut().find(|s| s.name == name)
+    }
+
+    fn set(&mut self, value: &SystemdUnitValue) {
+        match self.section_mut(&value.section) {
+            Some(section) => section.set(&value.key, &value.value),
+            None => {
+                let mut section = SystemdUnitSection::new(&value.section);
+                section.set(&value.key, &value.value);
+
+                // keep sections separated by an empty line
+                if let Some(last) = self.sections.last_mut() {
+                    if last.lines.last() != Some(&SystemdUnitLine::Empty) {
+                        last.lines.push(SystemdUnitLine::Empty);
+                    }
+                }
+
+                self.sections.push(section);
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &SystemdUnitKey) -> Result<(), SystemdUnitError> {
+        self.section_mut(&key.section)
+            .ok_or(SystemdUnitError::SectionNotFound(key.section.clone()))?
+            .remove(&key.key)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SystemdUnitValue {
+    section: String,
+    key: String,
+    value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct SystemdUnitKey {
+    section: String,
+    key: String,
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct SystemdUnitInput {
+    #[desc(description = "replaces the whole unit file")]
+    content: Option<SystemdUnit>,
+    set: Option<Vec<SystemdUnitValue>>,
+    remove: Option<Vec<SystemdUnitKey>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SystemdUnitBuilder;
+
+impl FileBuilder for SystemdUnitBuilder {
+    file_metadata!(
+        SystemdUnitFile,
+        "systemd_unit",
+        "Read and write systemd unit files. Comments and order are preserved.",
+        &[Capability::Read, Capability::Write, Capability::Delete],
+        FileExample::new_get("read unit",
+            SystemdUnit {
+                header: vec![SystemdUnitLine::Comment("# Simple web service".into())],
+                sections: vec![
+                    SystemdUnitSection {
+                        name: "Unit".into(),
+                        lines: vec![
+                            SystemdUnitLine::Entry { key: "Description".into(), value: "Simple web service".into() },
+                            SystemdUnitLine::Empty,
+                        ],
+                    },
+                    SystemdUnitSection {
+                        name: "Service".into(),
+                        lines: vec![
+                            SystemdUnitLine::Entry { key: "ExecStart".into(), value: "/usr/bin/python3 -m http.server 8080".into() },
+                        ],
+                    },
+                ],
+            }
+        ),
+        FileExample::new_write("create a simple service",
+            SystemdUnitInput {
+                content: Some(SystemdUnit {
+                    header: vec![],
+                    sections: vec![
+                        SystemdUnitSection {
+                            name: "Unit".into(),
+                            lines: vec![
+                                SystemdUnitLine::Entry { key: "Description".into(), value: "Simple web service".into() },
+                                SystemdUnitLine::Empty,
+                            ],
+                        },
+                        SystemdUnitSection {
+                            name: "Service".into(),
+                            lines: vec![
+                                SystemdUnitLine::Entry { key: "ExecStart".into(), value: "/usr/bin/python3 -m http.server 8080".into() },
+                                SystemdUnitLine::Entry { key: "Restart".into(), value: "on-failure".into() },
+                                SystemdUnitLine::Empty,
+                            ],
+                        },
+                        SystemdUnitSection {
+                            name: "Install".into(),
+                            lines: vec![
+                                SystemdUnitLine::Entry { key: "WantedBy".into(), value: "multi-user.target".into() },
+                            ],
+                        },
+                    ],
+                }),
+                set: None,
+                remove: None,
+            }
+        ),
+        FileExample::new_write("change the restart policy and remove a dependency",
+            SystemdUnitInput {
+                content: None,
+                set: Some(vec![SystemdUnitValue { section: "Service".into(), key: "Restart".into(), value: "always".into() }]),
+                remove: Some(vec![SystemdUnitKey { section: "Unit".into(), key: "After".into() }]),
+            }
+        ),
+        FileExample::new_delete()
+        ;
+        FileMatchPattern::new_regex(Regex::new("^/etc/systemd/system/[^/]+\\.service$").unwrap(), &[Os::LinuxAny]),
+        FileMatchPattern::new_regex(Regex::new("^/(usr/)?lib/systemd/system/[^/]+$").unwrap(), &[Os::LinuxAny])
+    );
+}
+
+pub struct SystemdUnitFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for SystemdUnitFile {
+    type Output = SystemdUnit;
+    type Input = SystemdUnitInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        SystemdUnit::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = SystemdUnitInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        let mut unit = match i.content {
+            Some(content) => content,
+            None => SystemdUnit::parse(&system.read_to_string(self.path()).await?)?,
+        };
+
+        for value in i.set.unwrap_or_default().iter() {
+            unit.set(value);
+        }
+
+        for key in i.remove.unwrap_or_default().iter() {
+            unit.remove(key)?;
+        }
+
+        Ok(unit.to_string())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"content": output})
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SystemdUnitError {
+    #[error("invalid unit line {0}")]
+    InvalidLine(String),
+    #[error("section {0} not found")]
+    SectionNotFound(String),
+    #[error("key {1} not found in section {0}")]
+    KeyNotFound(String, String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::systemd_unit::{SystemdUnit, SystemdUnitKey, SystemdUnitLine, SystemdUnitValue};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let content = read_test_resources("systemd_unit");
+        let unit = SystemdUnit::parse(&content).unwrap();
+
+        assert_eq!(unit.header.len(), 3);
+        assert_eq!(unit.sections.iter().map(|s| s.name.as_str()).collect::<Vec<&str>>(), vec!["Unit", "Service", "Install"]);
+        assert_eq!(unit.sections[1].lines, vec![
+            SystemdUnitLine::Entry { key: "Type".into(), value: "simple".into() },
+            SystemdUnitLine::Comment("; run as unprivileged user".into()),
+            SystemdUnitLine::Entry { key: "User".into(), value: "www-data".into() },
+            SystemdUnitLine::Entry { key: "ExecStart".into(), value: "/usr/bin/python3 -m http.server 8080 \\\n    --bind 127.0.0.1".into() },
+            SystemdUnitLine::Entry { key: "Restart".into(), value: "on-failure".into() },
+            SystemdUnitLine::Empty,
+        ]);
+
+        assert_eq!(unit.to_string(), content);
+    }
+
+    #[test]
+    fn test_set_remove() {
+        let mut unit = SystemdUnit::parse(&read_test_resources("systemd_unit")).unwrap();
+
+        unit.set(&SystemdUnitValue { section: "Service".into(), key: "Restart".into(), value: "always".into() });
+        unit.set(&SystemdUnitValue { section: "Unit".into(), key: "Documentation".into(), value: "man:python3(1)".into() });
+        unit.set(&SystemdUnitValue { section: "Path".into(), key: "PathChanged".into(), value: "/tmp".into() });
+        unit.remove(&SystemdUnitKey { section: "Unit".into(), key: "After".into() }).unwrap();
+
+        assert_eq!(unit.sections[0].lines, vec![
+            SystemdUnitLine::Entry { key: "Description".into(), value: "Simple web service".into() },
+            SystemdUnitLine::Entry { key: "Wants".into(), value: "network-online.target".into() },
+            SystemdUnitLine::Entry { key: "Documentation".into(), value: "man:python3(1)".into() },
+            SystemdUnitLine::Empty,
+        ]);
+        assert_eq!(unit.sections[1].lines[4], SystemdUnitLine::Entry { key: "Restart".into(), value: "always".into() });
+        assert!(unit.to_string().ends_with("WantedBy=multi-user.target\n\n[Path]\nPathChanged=/tmp\n"));
+
+        assert_eq!(&format!("{:?}", unit.remove(&SystemdUnitKey { section: "Unit".into(), key: "After".into() })),
+                   "Err(KeyNotFound(\"Unit\", \"After\"))");
+        assert_eq!(&format!("{:?}", unit.remove(&SystemdUnitKey { section: "Socket".into(), key: "After".into() })),
+                   "Err(SectionNotFound(\"Socket\"))");
+    }
+}
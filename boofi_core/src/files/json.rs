@@ -3,7 +3,7 @@ use crate::files::prelude::*;
 use crate::files::Regex;
 
 #[derive(Debug)]
-pub(crate) struct Json {
+pub struct Json {
     path: String,
 }
 
@@ -26,9 +26,9 @@ impl File for Json {
         from_slice(&system.read(self.path()).await?).map_err(Into::into)
     }
 
-    async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, _system: &System) -> Resul<String> {
         let value = Value::deserialize(input).map_err(Erro::from_deserialize)?;
-        system.write(self.path(), to_string(&value)?.as_bytes()).await
+        to_string(&value).map_err(Into::into)
     }
     fn path(&self) -> &str {
         &self.path
@@ -36,7 +36,7 @@ impl File for Json {
 }
 
 #[derive(Clone, Debug)]
-pub(crate) struct JsonBuilder;
+pub struct JsonBuilder;
 
 impl FileBuilder for JsonBuilder {
     type File = Json;
@@ -47,7 +47,7 @@ impl FileBuilder for JsonBuilder {
 
     fn patterns(&self) -> &[FileMatchPattern] {
         lazy_static! {
-            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_regex(Regex::new("^.*.(json|JSON)$").unwrap(), &[Os::LinuxAny])];
+            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_regex(Regex::new("^.*.(json|JSON)$").unwrap(), &[Os::PosixAny])];
         }
         PATTERN.as_slice()
     }
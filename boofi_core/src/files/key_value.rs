@@ -0,0 +1,246 @@
+use std::borrow::Cow;
+use serde_json::{json, Map, Value};
+use crate::files::prelude::*;
+use crate::files::Regex;
+use thiserror::Error;
+
+/// A key value line, `prefix` is everything up to the value e.g. `net.ipv4.ip_forward = ` and kept on updates
+struct KeyValueLine<'a> {
+    key: &'a str,
+    prefix: Cow<'a, str>,
+    value: &'a str,
+}
+
+impl<'a> KeyValueLine<'a> {
+    /// `None` for comments and empty lines, a whitespace `delimiter` splits at the first whitespace
+    fn parse(line: &'a str, delimiter: &str, comment: &str) -> Result<Option<Self>, KeyValueError> {
+        let l = line.trim_start();
+        if l.is_empty() || (!comment.is_empty() && l.starts_with(comment)) {
+            return Ok(None);
+        }
+
+        let (key, rest) = match delimiter.trim() {
+            "" => match l.split_once(char::is_whitespace) {
+                Some(split) => split,
+                None => return Ok(Some(Self { key: l.trim_end(), prefix: format!("{} ", l.trim_end()).into(), value: "" })),
+            },
+            d => l.split_once(d).ok_or_else(|| KeyValueError::InvalidLine(line.into()))?,
+        };
+
+        Ok(Some(Self {
+            key: key.trim(),
+            prefix: line[..line.len() - rest.trim_start().len()].into(),
+            value: rest.trim(),
+        }))
+    }
+}
+
+/// `=` or `:` of the first key value line, whitespace otherwise
+fn detect_delimiter(content: &str, comment: &str) -> String {
+    content.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && (comment.is_empty() || !l.starts_with(comment)))
+        .and_then(|l| ['=', ':'].into_iter().find(|d| l.contains(*d)))
+        .map(String::from)
+        .unwrap_or_else(|| " ".into())
+}
+
+fn parse(content: &str, delimiter: &str, comment: &str) -> Resul<Map<String, Value>> {
+    let mut values = Map::new();
+
+    for line in content.lines() {
+        if let Some(l) = KeyValueLine::parse(line, delimiter, comment)? {
+            values.insert(l.key.into(), Value::String(l.value.into()));
+        }
+    }
+
+    Ok(values)
+}
+
+fn scalar(value: &Value) -> Result<String, KeyValueError> {
+    match value {
+        Value::String(s) if !s.contains('\n') => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(KeyValueError::InvalidValue(value.to_string())),
+    }
+}
+
+/// Updates every line of a key in place and appends missing keys, comments and order are kept
+fn render(content: &str, delimiter: &str, comment: &str, set: &Map<String, Value>, remove: &[String]) -> Resul<String> {
+    let mut lines = vec![];
+    let mut missing: Vec<&String> = set.keys().collect();
+    let mut removed: Vec<&String> = vec![];
+
+    for line in content.lines() {
+        let Some(l) = KeyValueLine::parse(line, delimiter, comment)? else {
+            lines.push(line.to_string());
+            continue;
+        };
+
+        if let Some(key) = remove.iter().find(|k| k.as_str() == l.key) {
+            removed.push(key);
+        } else if let Some(value) = set.get(l.key) {
+            lines.push(format!("{}{}", l.prefix, scalar(value)?));
+            missing.retain(|k| k.as_str() != l.key);
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+
+    if let Some(key) = remove.iter().find(|k| !removed.contains(k)) {
+        return Err(KeyValueError::KeyNotFound(key.clone()).into());
+    }
+
+    for key in missing {
+        let separator = if delimiter.trim().is_empty() { " ".to_string() } else { delimiter.to_string() };
+        lines.push(format!("{}{}{}", key, separator, scalar(&set[key.as_str()])?));
+    }
+
+    Ok(lines.into_iter().map(|l| l + "\n").collect())
+}
+
+#[derive(Serialize, Deserialize, Description)]
+pub struct KeyValueInput {
+    #[desc(description = "between key and value like `=`, `: ` or ` ` for whitespace, detected from the file if missing")]
+    delimiter: Option<String>,
+    #[desc(description = "prefix of comment lines, `#` if missing")]
+    comment: Option<String>,
+    #[desc(description = "object of keys and values, existing keys are updated in place and others appended")]
+    set: Option<Value>,
+    #[desc(description = "keys which are removed")]
+    remove: Option<Vec<String>>,
+}
+
+pub struct KeyValueFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for KeyValueFile {
+    type Output = Value;
+    type Input = KeyValueInput;
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        let content = system.read_to_string(self.path()).await?;
+        parse(&content, &detect_delimiter(&content, "#"), "#").map(Value::Object)
+    }
+
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<String> {
+        let i = KeyValueInput::deserialize(input).map_err(Erro::from_deserialize)?;
+
+        let content = if system.path_exist(self.path()).await? { system.read_to_string(self.path()).await? } else { String::new() };
+        let comment = i.comment.unwrap_or_else(|| "#".into());
+        let delimiter = i.delimiter.unwrap_or_else(|| detect_delimiter(&content, &comment));
+        let set = match i.set {
+            Some(Value::Object(set)) => set,
+            Some(value) => return Err(KeyValueError::InvalidValue(value.to_string()).into()),
+            None => Map::new(),
+        };
+
+        render(&content, &delimiter, &comment, &set, &i.remove.unwrap_or_default())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"set": output})
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyValueBuilder;
+
+impl FileBuilder for KeyValueBuilder {
+    type File = KeyValueFile;
+
+    const NAME: &'static str = "key_value";
+    const DESCRIPTION: &'static str = "Read or write simple `KEY=VALUE`, `key: value` or `key value` files, choose it with `?name=key_value`. Comments and order are preserved.";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read, Capability::Write, Capability::Delete];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_regex(Regex::new(".*").unwrap(), &[Os::PosixAny])];
+        }
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EXAMPLE: [FileExample;2] = [
+                FileExample::new_get("sysctl settings", json!({"net.ipv4.ip_forward": "1", "vm.swappiness": "10"})),
+                FileExample::new_write("enable ip forwarding", KeyValueInput {
+                    delimiter: Some(" = ".into()),
+                    comment: None,
+                    set: Some(json!({"net.ipv4.ip_forward": 1})),
+                    remove: Some(vec!["vm.swappiness".into()]),
+                }),
+            ];
+        }
+
+        EXAMPLE.as_slice()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum KeyValueError {
+    #[error("line {0} has no delimiter")]
+    InvalidLine(String),
+    #[error("invalid value {0}, expected an object of strings, numbers or booleans")]
+    InvalidValue(String),
+    #[error("key {0} not found")]
+    KeyNotFound(String),
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Map, Value};
+    use crate::files::key_value::{detect_delimiter, parse, render};
+    use crate::utils::test::read_test_resources;
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_parse() {
+        let content = read_test_resources("key_value");
+        assert_eq!(detect_delimiter(&content, "#"), "=");
+        assert_eq!(Value::Object(parse(&content, "=", "#").unwrap()), json!({
+            "net.ipv4.ip_forward": "0",
+            "vm.swappiness": "60",
+            "kernel.printk": "3 4 1 3",
+            "fs.inotify.max_user_watches": "524288",
+        }));
+
+        assert_eq!(detect_delimiter("; comment\nPort 22\n", ";"), " ");
+        assert_eq!(Value::Object(parse("Port  22\nPermitRootLogin no\nUsePAM\n", " ", "#").unwrap()),
+                   json!({"Port": "22", "PermitRootLogin": "no", "UsePAM": ""}));
+        assert!(parse("a=1\nb\n", "=", "#").is_err());
+        assert_eq!(render("UsePAM\n", " ", "#", &object(json!({"UsePAM": "yes"})), &[]).unwrap(), "UsePAM yes\n");
+    }
+
+    #[test]
+    fn test_render() {
+        let content = read_test_resources("key_value");
+
+        let rendered = render(&content, "=", "#", &object(json!({"vm.swappiness": 10, "net.core.somaxconn": "1024"})),
+                              &["kernel.printk".into()]).unwrap();
+        assert_eq!(rendered, content
+            .replace("vm.swappiness = 60", "vm.swappiness = 10")
+            .replace("kernel.printk=3 4 1 3\n", "")
+            + "net.core.somaxconn=1024\n");
+
+        assert_eq!(render("", " ", "#", &object(json!({"Port": 2222})), &[]).unwrap(), "Port 2222\n");
+        assert!(render(&content, "=", "#", &Map::new(), &["missing".into()]).is_err());
+        assert!(render(&content, "=", "#", &object(json!({"a": ["b"]})), &[]).is_err());
+    }
+}
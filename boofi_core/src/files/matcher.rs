@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, PoisonError};
+use regex::RegexSet;
+use crate::files::{FileBuilders, FileMatchPatternType};
+use crate::system::os::Os;
+
+/// Resolved paths of a service which are kept, the least recently used one is dropped first
+pub const MATCH_CACHE_SIZE: usize = 4096;
+
+/// Index of the file builder and operating systems of one pattern
+type PatternTarget = (usize, Vec<Os>);
+
+/// Least recently used paths with the indexes of their file builders, paths without any are cached as well.
+/// Resolutions are only valid for one os, the cache is cleared if the os changes.
+#[derive(Debug, Default)]
+struct MatchCache {
+    os: Option<Os>,
+    entries: HashMap<String, (Vec<usize>, u64)>,
+    used: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl MatchCache {
+    fn get(&mut self, path: &str, os: &Os) -> Option<Vec<usize>> {
+        if self.os.as_ref() != Some(os) {
+            self.os = Some(os.clone());
+            self.entries.clear();
+            self.used.clear();
+            return None;
+        }
+
+        self.tick += 1;
+        let (indexes, tick) = self.entries.get_mut(path)?;
+        self.used.remove(tick);
+        self.used.insert(self.tick, path.to_string());
+        *tick = self.tick;
+        Some(indexes.clone())
+    }
+
+    fn insert(&mut self, path: &str, indexes: Vec<usize>) {
+        if self.entries.len() >= MATCH_CACHE_SIZE {
+            if let Some((_, oldest)) = self.used.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.tick += 1;
+        if let Some((_, tick)) = self.entries.insert(path.to_string(), (indexes, self.tick)) {
+            self.used.remove(&tick);
+        }
+        self.used.insert(self.tick, path.to_string());
+    }
+}
+
+/// Matches paths against the patterns of all file builders at once.
+/// Regexes are compiled into one set and exact paths are looked up, a directory listing checks each entry only once.
+#[derive(Debug)]
+pub struct FileMatcher {
+    regexes: RegexSet,
+    regex_targets: Vec<PatternTarget>,
+    paths: HashMap<String, Vec<PatternTarget>>,
+    cache: Mutex<MatchCache>,
+}
+
+impl FileMatcher {
+    pub fn new(files: &[FileBuilders]) -> Self {
+        let mut regexes = vec![];
+        let mut regex_targets = vec![];
+        let mut paths: HashMap<String, Vec<PatternTarget>> = HashMap::new();
+
+        for (index, file) in files.iter().enumerate() {
+            for pattern in file.patterns() {
+                let target = (index, pattern.compatibility.clone());
+                match &pattern.pattern {
+                    FileMatchPatternType::Path(path) => paths.entry(path.clone()).or_default().push(target),
+                    FileMatchPatternType::Regex(regex) => {
+                        regexes.push(regex.as_str().to_string());
+                        regex_targets.push(target);
+                    }
+                }
+            }
+        }
+
+        Self {
+            regexes: RegexSet::new(regexes).expect("file builder patterns are valid regexes"),
+            regex_targets,
+            paths,
+            cache: Default::default(),
+        }
+    }
+
+    /// Indexes of the file builders managing the path in their order
+    pub fn matches(&self, path: &str, os: &Os) -> Vec<usize> {
+        if let Some(indexes) = self.cache().get(path, os) {
+            tracing::trace!("[FILE MATCH] cache hit for {}", path);
+            return indexes;
+        }
+
+        let compatible = |(_, compatibility): &&PatternTarget| compatibility.iter().any(|o| o.compatible(os));
+        let mut indexes: Vec<usize> = self.regexes.matches(path).into_iter()
+            .map(|i| &self.regex_targets[i])
+            .chain(self.paths.get(path).into_iter().flatten())
+            .filter(compatible)
+            .map(|(index, _)| *index)
+            .collect();
+        indexes.sort_unstable();
+        indexes.dedup();
+
+        self.cache().insert(path, indexes.clone());
+        indexes
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, MatchCache> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::{FileBuilders, HostsBuilder, JsonBuilder, TextBuilder, VersionBuilder, YamlBuilder};
+    use crate::files::matcher::{FileMatcher, MatchCache, MATCH_CACHE_SIZE};
+    use crate::system::os::Os;
+
+    #[test]
+    fn test_matches() {
+        let files = vec![
+            FileBuilders::VersionBuilder(VersionBuilder {}),
+            FileBuilders::HostsBuilder(HostsBuilder {}),
+            FileBuilders::TextBuilder(TextBuilder {}),
+        ];
+        let matcher = FileMatcher::new(&files);
+
+        for (path, os) in [("/etc/hosts", Os::LinuxDebian), ("/proc/version", Os::LinuxDebian), ("/etc/hosts", Os::Macos), ("/nothing", Os::LinuxDebian)] {
+            let expected: Vec<usize> = files.iter().enumerate().filter(|(_, f)| f.r#match(path, &os)).map(|(i, _)| i).collect();
+            assert_eq!(matcher.matches(path, &os), expected, "{} {:?}", path, os);
+            assert_eq!(matcher.matches(path, &os), expected, "cached {} {:?}", path, os);
+        }
+    }
+
+    #[test]
+    fn test_generic_posix() {
+        for os in [Os::Macos, Os::FreeBsd, Os::LinuxDebian] {
+            assert!(FileBuilders::TextBuilder(TextBuilder {}).r#match("/usr/local/etc/app.conf", &os), "text {:?}", os);
+            assert!(FileBuilders::JsonBuilder(JsonBuilder {}).r#match("/usr/local/etc/app.json", &os), "json {:?}", os);
+            assert!(FileBuilders::YamlBuilder(YamlBuilder {}).r#match("/usr/local/etc/app.yaml", &os), "yaml {:?}", os);
+        }
+
+        assert!(!FileBuilders::HostsBuilder(HostsBuilder {}).r#match("/etc/hosts", &Os::Macos));
+    }
+
+    #[test]
+    fn test_cache_lru() {
+        let mut cache = MatchCache::default();
+        assert!(cache.get("/etc/hosts", &Os::LinuxDebian).is_none());
+
+        for i in 0..MATCH_CACHE_SIZE {
+            cache.insert(&format!("/tmp/{}", i), vec![i]);
+        }
+        assert_eq!(cache.get("/tmp/0", &Os::LinuxDebian), Some(vec![0]));
+
+        cache.insert("/etc/hosts", vec![1]);
+        assert_eq!(cache.entries.len(), MATCH_CACHE_SIZE);
+        assert!(cache.get("/tmp/1", &Os::LinuxDebian).is_none());
+        assert_eq!(cache.get("/tmp/0", &Os::LinuxDebian), Some(vec![0]));
+
+        assert!(cache.get("/tmp/0", &Os::Macos).is_none());
+        assert!(cache.entries.is_empty());
+    }
+}
@@ -0,0 +1,536 @@
+pub mod text;
+mod proc;
+mod etc;
+mod yaml;
+mod json;
+pub mod key_value;
+pub mod csv;
+mod binary;
+pub mod x509;
+pub mod plugin;
+pub mod matcher;
+
+pub use proc::*;
+pub use etc::*;
+
+pub use crate::files::text::TextBuilder;
+pub use crate::files::plugin::FilePluginBuilder;
+pub use crate::files::json::JsonBuilder;
+pub use crate::files::yaml::YamlBuilder;
+pub use crate::files::key_value::KeyValueBuilder;
+pub use crate::files::csv::{CsvBuilder, CsvHeaderBuilder};
+pub use crate::files::binary::BinaryBuilder;
+pub use crate::files::x509::X509Builder;
+pub use crate::files::crontab::CrontabBuilder;
+pub use crate::files::systemd_unit::SystemdUnitBuilder;
+pub use crate::files::netplan::NetplanBuilder;
+pub use crate::files::interfaces::InterfacesBuilder;
+pub use crate::files::sshd_config::SshdConfigBuilder;
+pub use crate::files::sudoers::SudoersBuilder;
+pub use crate::files::exports::ExportsBuilder;
+pub use crate::files::smb_conf::SmbConfBuilder;
+pub use crate::files::environment::EnvironmentBuilder;
+pub use crate::files::authorized_keys::AuthorizedKeysBuilder;
+pub use crate::files::fstab::FstabBuilder;
+pub use crate::files::hostname::HostnameBuilder;
+pub use crate::files::hosts::HostsBuilder;
+pub use crate::files::os_release::OsReleaseBuilder;
+pub use crate::files::passwd::PasswdBuilder;
+pub use crate::files::group::GroupBuilder;
+pub use crate::files::shadow::ShadowBuilder;
+pub use crate::files::cpuinfo::CpuinfoBuilder;
+pub use crate::files::crypto::CryptoBuilder;
+pub use crate::files::filesystems::FilesystemBuilder;
+pub use crate::files::loadavg::LoadAvgBuilder;
+pub use crate::files::mdstat::MdstatBuilder;
+pub use crate::files::meminfo::MeminfoBuilder;
+pub use crate::files::mounts::MountsBuilder;
+pub use crate::files::partitions::PartitionsBuilder;
+pub use crate::files::swaps::SwapsBuilder;
+pub use crate::files::uptime::UptimeBuilder;
+pub use crate::files::version::VersionBuilder;
+pub use crate::files::net_dev::NetDevBuilder;
+pub use crate::files::stat::StatBuilder;
+pub use crate::files::process_status::ProcessStatusBuilder;
+pub use crate::files::process_cmdline::ProcessCmdlineBuilder;
+pub use crate::files::process_io::ProcessIoBuilder;
+pub use crate::files::net_sockets::NetSocketsBuilder;
+
+use std::fmt::{Display, Formatter};
+use regex::Regex;
+use serde::{Deserializer, Serialize};
+use serde_json::{to_value, Value};
+use async_trait::async_trait;
+use thiserror::Error;
+use crate::system::os::Os;
+use crate::system::System;
+use crate::error::{Resul, Erro};
+use crate::patch::{apply_json_patch, JsonPatch};
+use crate::apps::Serializable;
+use crate::description::{Description, DescriptionField, Documentation};
+
+/// Import all necessary dependencies for a file implementation with `use crate::file::prelude::*`
+pub mod prelude {
+    pub(crate) use crate::utils::{file_metadata, count};
+    pub use super::{Capability, FileExample, FileMatchPattern, File, FileBuilder};
+    pub use lazy_static::lazy_static;
+    pub use serde::{Deserialize, Serialize, Deserializer};
+    pub use async_trait::async_trait;
+    pub use crate::error::*;
+    pub use crate::system::System;
+    pub use crate::system::os::*;
+    pub use crate::description::*;
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub enum Capability {
+    Read,
+    Write,
+    Delete,
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Capability::Read => "read",
+            Capability::Write => "write",
+            Capability::Delete => "delete"
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct FileHelp<'a> {
+    name: &'a str,
+    description: &'a str,
+    capabilities: &'a [Capability],
+    patterns: &'a [FileMatchPattern],
+    input: Documentation,
+    output: Documentation,
+    examples: &'a [FileExample],
+}
+
+impl FileHelp<'_> {
+    /// Renders in and output as json schema
+    pub fn into_schema(self) -> Self {
+        Self {
+            input: self.input.into_schema(),
+            output: self.output.into_schema(),
+            ..self
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReadExample {
+    description: &'static str,
+    output: Serializable,
+}
+
+#[derive(Serialize)]
+pub struct WriteExample {
+    description: &'static str,
+    input: Serializable,
+}
+
+/// Used for deletion but not common.
+#[derive(Debug, Serialize, Clone)]
+pub struct DeleteExample {
+    description: &'static str,
+}
+
+/// An example struct for each case
+#[derive(Serialize)]
+pub enum FileExample {
+    Get(ReadExample),
+    Write(WriteExample),
+    Delete(DeleteExample),
+}
+
+impl FileExample {
+    /// Shorthand for get
+    pub fn new_get<O: Serialize + Send + Sync + 'static>(description: &'static str, output: O) -> Self {
+        FileExample::Get(ReadExample { output: Box::new(output), description })
+    }
+
+    /// Shorthand for write
+    pub fn new_write<I: Serialize + Send + Sync + 'static>(description: &'static str, input: I) -> Self {
+        FileExample::Write(WriteExample { input: Box::new(input), description })
+    }
+
+    /// Shorthand for delete
+    pub fn new_delete() -> Self {
+        FileExample::Delete(DeleteExample { description: "Delete the file" })
+    }
+}
+
+/// `Path` for exact match and `Regex` for rest.
+#[derive(Debug, Clone, Serialize)]
+pub enum FileMatchPatternType {
+    Path(String),
+    #[serde(with = "serde_regex")]
+    Regex(Regex),
+}
+
+/// To identify if a file implementation is applicable it must be identified in some way.
+/// It works by matching the target operating systemd and the provided path.
+#[derive(Clone, Serialize)]
+pub struct FileMatchPattern {
+    pattern: FileMatchPatternType,
+    compatibility: Vec<Os>,
+}
+
+impl FileMatchPattern {
+    /// Use regex only if necessary.
+    pub fn new(pattern: FileMatchPatternType, compatibility: &[Os]) -> Self {
+        Self {
+            pattern,
+            compatibility: compatibility.to_vec(),
+        }
+    }
+
+    /// Shorthand for path
+    pub fn new_path(path: &str, compatibility: &[Os]) -> Self {
+        Self::new(FileMatchPatternType::Path(path.into()), compatibility)
+    }
+
+    /// Shorthand for regex
+    pub fn new_regex(regex: Regex, compatibility: &[Os]) -> Self {
+        Self::new(FileMatchPatternType::Regex(regex), compatibility)
+    }
+
+    pub fn compatible(&self, os: &Os) -> bool {
+        self.compatibility.iter().any(|i| i.compatible(os))
+    }
+
+    /// This is called very often due to directory listing.
+    pub fn r#match(&self, value: &str,
+                          os: &Os) -> bool {
+        if self.compatible(os) {
+            match &self.pattern {
+                FileMatchPatternType::Path(s) => s.as_str() == value,
+                FileMatchPatternType::Regex(regex) => regex.is_match(value)
+            }
+        } else {
+            false
+        }
+    }
+}
+
+#[async_trait]
+pub trait File: Sync + Send {
+    type Output: Serialize + Description;
+    type Input: Description;
+
+    fn new(path: &str) -> Self;
+
+    async fn read(&self, _system: &System) -> Resul<Self::Output> {
+        Err(FileError::NotCapable(Capability::Read)).map_err(Into::into)
+    }
+
+    /// Read without sensitive values e.g. password hashes. Same as `read` by default.
+    async fn read_redacted(&self, system: &System) -> Resul<Self::Output> {
+        self.read(system).await
+    }
+
+    /// Content a write would produce from the input, the current content is read if the input is merged into it
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, _input: I, _system: &System) -> Resul<String> {
+        Err(FileError::NotCapable(Capability::Write)).map_err(Into::into)
+    }
+
+    async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
+        let content = self.render(input, system).await?;
+        system.write(self.path(), content.as_bytes()).await
+    }
+
+    /// Semantic check of a write input without changing the file
+    async fn check<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
+        self.render(input, system).await.map(|_| ())
+    }
+
+    async fn delete(&self, system: &System) -> Resul<()> {
+        system.delete(self.path()).await
+    }
+
+    fn path(&self) -> &str;
+
+    /// Input replacing the whole content with a patched output, the output itself by default
+    fn replacement(&self, output: Value) -> Value {
+        output
+    }
+
+    fn input_description() -> &'static DescriptionField {
+        Self::Input::field()
+    }
+
+    fn output_description() -> &'static DescriptionField {
+        Self::Output::field()
+    }
+}
+
+pub trait FileBuilder {
+    type File: File;
+
+    const NAME: &'static str;
+    const DESCRIPTION: &'static str;
+    const CAPABILITIES: &'static [Capability];
+
+    /// Content only changes with a reboot or upgrade, reads may be served from the file cache
+    const CACHEABLE: bool = false;
+
+    /// List of patterns which matches on the target machine.
+    /// The combination of operating system and path maybe different.
+    fn patterns(&self) -> &[FileMatchPattern];
+
+    /// Try to identify if this implementation can manage the file.
+    fn r#match(&self, value: &str,
+               os: &Os)
+               -> Option<Self::File> {
+        tracing::trace!("start matching {} with value {} and os {:?}", Self::NAME, value, os);
+
+        for pattern in self.patterns() {
+            if pattern.r#match(value, os) {
+                return Some(Self::File::new(value));
+            }
+        }
+
+        None
+    }
+
+    /// Any pattern applies to the target `os`.
+    fn compatible(&self, os: &Os) -> bool {
+        self.patterns().iter().any(|pattern| pattern.compatible(os))
+    }
+
+    /// Useful examples for end user.
+    fn examples(&self) -> &[FileExample] {
+        &[]
+    }
+
+    /// Returns a documentation about all variables with their description.
+    fn input(&self) -> &'static DescriptionField {
+        Self::File::input_description()
+    }
+
+    /// Returns a documentation about all variables with their description.
+    fn output(&self) -> &'static DescriptionField {
+        Self::File::output_description()
+    }
+
+    /// Overview about all end user relevant information to interact with this implementation.
+    fn help(&self) -> FileHelp {
+        FileHelp {
+            name: Self::NAME,
+            description: Self::DESCRIPTION,
+            capabilities: Self::CAPABILITIES,
+            patterns: self.patterns(),
+            input: Documentation::Description(self.input()),
+            output: Documentation::Description(self.output()),
+            examples: self.examples(),
+        }
+    }
+}
+
+macro_rules! file_builders {
+    ($(
+        $typ:tt
+    ),*
+    ) => {
+        /// Built-in files and plugins loaded from manifests
+        pub enum FileBuilders {
+            $(
+                $typ($typ),
+            )*
+            Plugin(FilePluginBuilder),
+        }
+
+        impl FileBuilders {
+           pub fn name(&self) -> &str {
+                match self {
+                    $( Self::$typ(_)  => $typ::NAME, )*
+                    Self::Plugin(i) => i.name(),
+                }
+            }
+
+            pub fn cacheable(&self) -> bool {
+                match self {
+                    $( Self::$typ(_)  => $typ::CACHEABLE, )*
+                    Self::Plugin(_) => false,
+                }
+            }
+
+            pub fn capabilities(&self) -> &[Capability] {
+                match self {
+                    $( Self::$typ(_)  => $typ::CAPABILITIES, )*
+                    Self::Plugin(i) => i.capabilities(),
+                }
+            }
+
+            pub fn compatible(&self, os: &Os) -> bool {
+                match self {
+                    $( Self::$typ(i)  => i.compatible(os), )*
+                    Self::Plugin(i) => i.compatible(os),
+                }
+            }
+
+            pub fn patterns(&self) -> &[FileMatchPattern] {
+                match self {
+                    $( Self::$typ(i)  => i.patterns(), )*
+                    Self::Plugin(i) => i.patterns(),
+                }
+            }
+
+            pub fn r#match(&self, path: &str, os: &Os) -> bool {
+                match self {
+                    $( Self::$typ(i)  => i.r#match(path, os).is_some(), )*
+                    Self::Plugin(i) => i.r#match(path, os),
+                }
+            }
+
+           pub async fn read(&self, path: &str, system: &System) -> Resul<Box<dyn erased_serde::Serialize + Send>> {
+                match self {
+                    $( Self::$typ(i) => Ok(i.r#match(path, system.os()?).ok_or(Erro::FilesNotMatched)?.read(system).await.map(Box::new)?), )*
+                    Self::Plugin(i) => Ok(Box::new(i.read(path, system).await?)),
+                }
+            }
+
+           pub async fn read_redacted(&self, path: &str, system: &System) -> Resul<Box<dyn erased_serde::Serialize + Send>> {
+                match self {
+                    $( Self::$typ(i) => Ok(i.r#match(path, system.os()?).ok_or(Erro::FilesNotMatched)?.read_redacted(system).await.map(Box::new)?), )*
+                    Self::Plugin(i) => Ok(Box::new(i.read(path, system).await?)),
+                }
+            }
+
+           #[allow(dead_code)]
+            pub async fn read_bytes(&self, path: &str, system: &System) -> Resul<Vec<u8>> {
+                match self {
+                    $( Self::$typ(_i)  => system.read(path).await, )*
+                    Self::Plugin(_i) => system.read(path).await,
+                }
+            }
+
+            /// Applies a JSON patch to the output and writes it as replacement
+            pub async fn patch(&self, path: &str, operations: &[JsonPatch], system: &System) -> Resul<()> {
+                match self {
+                    $( Self::$typ(i) => {
+                        let file = i.r#match(path, system.os()?).ok_or(Erro::FilesNotMatched)?;
+                        let patched = apply_json_patch(to_value(file.read(system).await?)?, operations)?;
+                        file.write(file.replacement(patched), system).await
+                    } )*
+                    Self::Plugin(i) => {
+                        let patched = apply_json_patch(i.read(path, system).await?, operations)?;
+                        i.write(path, patched, system).await
+                    }
+                }
+            }
+
+            /// Checks the input of a write against the description, plugins only document theirs as schema
+            pub fn validate(&self, input: &Value) -> Resul<()> {
+                let invalid = match self {
+                    $( Self::$typ(i)  => i.input().validate(input), )*
+                    Self::Plugin(_) => vec![],
+                };
+
+                if invalid.is_empty() { Ok(()) } else { Err(Erro::InputInvalid(invalid)) }
+            }
+
+            pub async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, path: &str, input: I, system: &System) -> Resul<()> {
+                match self {
+                    $( Self::$typ(i)  => i.r#match(path, system.os()?).ok_or(Erro::FilesNotMatched)?.write(input, system).await, )*
+                    Self::Plugin(i) => i.write(path, input, system).await,
+                }
+            }
+
+            /// Content a write would produce without changing the file
+            pub async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, path: &str, input: I, system: &System) -> Resul<String> {
+                match self {
+                    $( Self::$typ(i)  => {
+                        let file = i.r#match(path, system.os()?).ok_or(Erro::FilesNotMatched)?;
+                        if !<$typ as FileBuilder>::CAPABILITIES.contains(&Capability::Write) {
+                            return Err(FileError::NotCapable(Capability::Write).into());
+                        }
+                        file.render(input, system).await
+                    } )*
+                    Self::Plugin(i) => i.render(path, input, system).await,
+                }
+            }
+
+            /// Dry run of a write, fails like the write would without changing the file
+            pub async fn check<'de, I: Deserializer<'de> + Send + Sync>(&self, path: &str, input: I, system: &System) -> Resul<()> {
+                self.render(path, input, system).await.map(|_| ())
+            }
+
+           #[allow(dead_code)]
+            pub async fn write_bytes(&self, path: &str, input: Vec<u8>, system: &System) -> Resul<()> {
+                match self {
+                    $( Self::$typ(_i)  => system.write(path, &input).await, )*
+                    Self::Plugin(_i) => system.write(path, &input).await,
+                }
+            }
+
+            pub async fn delete(&self, path: &str, system: &System) -> Resul<()> {
+                match self {
+                    $( Self::$typ(_i)  => system.delete(path).await, )*
+                    Self::Plugin(_i) => system.delete(path).await,
+                }
+            }
+            pub fn help(&self) -> FileHelp {
+                match self {
+                    $( Self::$typ(i)  => i.help(), )*
+                    Self::Plugin(i) => i.help(),
+                }
+            }
+        }
+    }
+}
+
+file_builders!(
+    VersionBuilder,
+    UptimeBuilder,
+    SwapsBuilder,
+    PartitionsBuilder,
+    MountsBuilder,
+    MeminfoBuilder,
+    MdstatBuilder,
+    LoadAvgBuilder,
+    FilesystemBuilder,
+    CryptoBuilder,
+    CpuinfoBuilder,
+    NetDevBuilder,
+    StatBuilder,
+    ProcessStatusBuilder,
+    ProcessCmdlineBuilder,
+    ProcessIoBuilder,
+    NetSocketsBuilder,
+    PasswdBuilder,
+    GroupBuilder,
+    ShadowBuilder,
+    OsReleaseBuilder,
+    HostsBuilder,
+    HostnameBuilder,
+    FstabBuilder,
+    CrontabBuilder,
+    SystemdUnitBuilder,
+    NetplanBuilder,
+    InterfacesBuilder,
+    SshdConfigBuilder,
+    SudoersBuilder,
+    ExportsBuilder,
+    SmbConfBuilder,
+    EnvironmentBuilder,
+    AuthorizedKeysBuilder,
+    X509Builder,
+    YamlBuilder,
+    JsonBuilder,
+    CsvBuilder,
+    CsvHeaderBuilder,
+    TextBuilder,
+    KeyValueBuilder,
+    BinaryBuilder
+);
+
+#[derive(Debug, Error)]
+pub enum FileError {
+    #[error("{0} not capable")]
+    NotCapable(Capability)
+}
\ No newline at end of file
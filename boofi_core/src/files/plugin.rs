@@ -0,0 +1,385 @@
+use std::path::Path;
+use std::sync::Arc;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+use crate::description::Documentation;
+use crate::error::{Erro, Resul};
+use crate::files::{Capability, FileError, FileHelp, FileMatchPattern};
+use crate::system::os::Os;
+use crate::system::System;
+use crate::utils::{manifest_paths, regex_captures};
+
+const CAPABILITIES_READ: &[Capability] = &[Capability::Read, Capability::Delete];
+const CAPABILITIES_READ_WRITE: &[Capability] = &[Capability::Read, Capability::Write, Capability::Delete];
+
+fn default_separator() -> String {
+    "=".into()
+}
+
+fn default_comment() -> String {
+    "#".into()
+}
+
+/// How the file content is turned into json and back
+/// key_value:  object of `key<separator>value` lines
+/// columns:    array of objects named by `names`, split by `separator` or whitespace, the last column takes the rest
+/// ini:        object of sections with their key value pairs, keys before the first section are in `""`
+/// regex:      array of objects with the named groups of every matching line, read only
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "parser", rename_all = "snake_case")]
+pub enum FilePluginParser {
+    KeyValue {
+        #[serde(default = "default_separator")]
+        separator: String,
+        #[serde(default = "default_comment")]
+        comment: String,
+    },
+    Columns {
+        #[serde(default)]
+        separator: Option<String>,
+        names: Vec<String>,
+        #[serde(default = "default_comment")]
+        comment: String,
+    },
+    Ini,
+    Regex {
+        #[serde(with = "serde_regex")]
+        pattern: Regex,
+    },
+}
+
+impl FilePluginParser {
+    fn lines<'a>(content: &'a str, comment: &'a str) -> impl Iterator<Item=&'a str> {
+        content.lines()
+            .map(str::trim)
+            .filter(move |l| !l.is_empty() && (comment.is_empty() || !l.starts_with(comment)))
+    }
+
+    fn parse(&self, content: &str) -> Resul<Value> {
+        Ok(match self {
+            FilePluginParser::KeyValue { separator, comment } => Value::Object(Self::lines(content, comment)
+                .map(|line| line.split_once(separator.as_str())
+                    .map(|(k, v)| (k.trim().to_string(), Value::String(v.trim().into())))
+                    .ok_or_else(|| FilePluginError::InvalidLine(line.into())))
+                .collect::<Result<Map<String, Value>, FilePluginError>>()?),
+            FilePluginParser::Columns { separator, names, comment } => Value::Array(Self::lines(content, comment).map(|line| {
+                let columns: Vec<&str> = match separator {
+                    Some(separator) => line.splitn(names.len(), separator.as_str()).map(str::trim).collect(),
+                    None => {
+                        let mut columns: Vec<&str> = vec![];
+                        let mut rest = line;
+                        while columns.len() + 1 < names.len() {
+                            match rest.split_once(char::is_whitespace) {
+                                Some((column, r)) => {
+                                    columns.push(column);
+                                    rest = r.trim_start();
+                                }
+                                None => break,
+                            }
+                        }
+                        columns.push(rest);
+                        columns
+                    }
+                };
+
+                Value::Object(names.iter().enumerate()
+                    .map(|(i, name)| (name.clone(), columns.get(i).map(|c| Value::String(c.to_string())).unwrap_or_default()))
+                    .collect())
+            }).collect()),
+            FilePluginParser::Ini => {
+                let mut sections = Map::new();
+                let mut section = String::new();
+
+                for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with(['#', ';'])) {
+                    if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                        section = name.trim().into();
+                        sections.entry(section.clone()).or_insert_with(|| Value::Object(Map::new()));
+                        continue;
+                    }
+
+                    let (key, value) = line.split_once('=').ok_or_else(|| FilePluginError::InvalidLine(line.into()))?;
+                    if let Value::Object(values) = sections.entry(section.clone()).or_insert_with(|| Value::Object(Map::new())) {
+                        values.insert(key.trim().into(), Value::String(value.trim().into()));
+                    }
+                }
+
+                Value::Object(sections)
+            }
+            FilePluginParser::Regex { pattern } => Value::Array(regex_captures(pattern, content)),
+        })
+    }
+
+    fn scalar(value: &Value) -> Resul<String> {
+        match value {
+            Value::String(s) => Ok(s.clone()),
+            Value::Number(n) => Ok(n.to_string()),
+            Value::Bool(b) => Ok(b.to_string()),
+            Value::Null => Ok(String::new()),
+            _ => Err(FilePluginError::InvalidValue(value.to_string()).into()),
+        }
+    }
+
+    fn object(value: &Value) -> Resul<&Map<String, Value>> {
+        value.as_object().ok_or_else(|| FilePluginError::InvalidValue(value.to_string()).into())
+    }
+
+    fn to_string(&self, value: &Value) -> Resul<String> {
+        let mut content = String::new();
+
+        match self {
+            FilePluginParser::KeyValue { separator, .. } => for (key, value) in Self::object(value)? {
+                content.push_str(&format!("{}{}{}\n", key, separator, Self::scalar(value)?));
+            },
+            FilePluginParser::Columns { separator, names, .. } => {
+                let rows = value.as_array().ok_or_else(|| FilePluginError::InvalidValue(value.to_string()))?;
+                for row in rows {
+                    let row = Self::object(row)?;
+                    let columns = names.iter()
+                        .map(|name| row.get(name).map(Self::scalar).unwrap_or_else(|| Ok(String::new())))
+                        .collect::<Resul<Vec<String>>>()?;
+                    content.push_str(&columns.join(separator.as_deref().unwrap_or("\t")));
+                    content.push('\n');
+                }
+            }
+            FilePluginParser::Ini => {
+                let sections = Self::object(value)?;
+                // keys without section have to be written first
+                for (name, values) in sections.iter().filter(|(n, _)| n.is_empty()).chain(sections.iter().filter(|(n, _)| !n.is_empty())) {
+                    if !name.is_empty() {
+                        content.push_str(&format!("[{}]\n", name));
+                    }
+                    for (key, value) in Self::object(values)? {
+                        content.push_str(&format!("{} = {}\n", key, Self::scalar(value)?));
+                    }
+                }
+            }
+            FilePluginParser::Regex { .. } => return Err(FileError::NotCapable(Capability::Write).into()),
+        }
+
+        Ok(content)
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        match self {
+            FilePluginParser::Regex { .. } => CAPABILITIES_READ,
+            _ => CAPABILITIES_READ_WRITE,
+        }
+    }
+
+    fn schema(&self) -> Value {
+        let string = json!({"type": ["string", "null"]});
+        match self {
+            FilePluginParser::KeyValue { .. } => json!({"type": "object", "additionalProperties": string}),
+            FilePluginParser::Columns { names, .. } => json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": names.iter().map(|n| (n.clone(), string.clone())).collect::<Map<String, Value>>(),
+                },
+            }),
+            FilePluginParser::Ini => json!({"type": "object", "additionalProperties": {"type": "object", "additionalProperties": string}}),
+            FilePluginParser::Regex { pattern } => json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": pattern.capture_names().flatten().map(|n| (n.to_string(), string.clone())).collect::<Map<String, Value>>(),
+                },
+            }),
+        }
+    }
+}
+
+/// Exact path or regex of a managed file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FilePluginPattern {
+    Path {
+        path: String,
+    },
+    Regex {
+        #[serde(with = "serde_regex")]
+        regex: Regex,
+    },
+}
+
+/// File manifest loaded from the file plugin directory
+/// name:           unique file name, must not collide with a built-in file
+/// patterns:       paths or regexes of the managed files
+/// compatibility:  operating systems the patterns apply to, every posix system by default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePluginManifest {
+    name: String,
+    description: String,
+    patterns: Vec<FilePluginPattern>,
+    #[serde(default = "FilePluginManifest::default_compatibility")]
+    compatibility: Vec<Os>,
+    #[serde(flatten)]
+    parser: FilePluginParser,
+}
+
+impl FilePluginManifest {
+    fn default_compatibility() -> Vec<Os> {
+        vec![Os::PosixAny]
+    }
+}
+
+/// Manages site specific file formats described by a manifest
+#[derive(Clone)]
+pub struct FilePluginBuilder {
+    manifest: Arc<FilePluginManifest>,
+    patterns: Arc<Vec<FileMatchPattern>>,
+}
+
+impl FilePluginBuilder {
+    pub fn new(manifest: FilePluginManifest) -> Self {
+        let patterns = manifest.patterns.iter().map(|pattern| match pattern {
+            FilePluginPattern::Path { path } => FileMatchPattern::new_path(path, &manifest.compatibility),
+            FilePluginPattern::Regex { regex } => FileMatchPattern::new_regex(regex.clone(), &manifest.compatibility),
+        }).collect();
+
+        Self {
+            manifest: Arc::new(manifest),
+            patterns: Arc::new(patterns),
+        }
+    }
+
+    /// Loads all `.yaml`, `.yml` and `.json` manifests of a directory
+    pub async fn load_dir(path: &str) -> Resul<Vec<Self>> {
+        let mut plugins = vec![];
+
+        for path in manifest_paths(path).await? {
+            plugins.push(Self::load(&path).await?);
+        }
+
+        plugins.sort_by(|a: &Self, b| a.manifest.name.cmp(&b.manifest.name));
+        Ok(plugins)
+    }
+
+    async fn load(path: &Path) -> Resul<Self> {
+        tracing::debug!("[FILE PLUGIN] loading manifest {}", path.display());
+        serde_yaml::from_slice::<FilePluginManifest>(&tokio::fs::read(path).await?)
+            .map(Self::new)
+            .map_err(|e| FilePluginError::InvalidManifest(path.display().to_string(), e.to_string()).into())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    pub fn capabilities(&self) -> &'static [Capability] {
+        self.manifest.parser.capabilities()
+    }
+
+    pub fn patterns(&self) -> &[FileMatchPattern] {
+        &self.patterns
+    }
+
+    pub fn compatible(&self, os: &Os) -> bool {
+        self.patterns.iter().any(|pattern| pattern.compatible(os))
+    }
+
+    pub fn r#match(&self, path: &str, os: &Os) -> bool {
+        self.patterns.iter().any(|pattern| pattern.r#match(path, os))
+    }
+
+    fn matched(&self, path: &str, system: &System) -> Resul<()> {
+        if self.r#match(path, system.os()?) { Ok(()) } else { Err(Erro::FilesNotMatched) }
+    }
+
+    pub async fn read(&self, path: &str, system: &System) -> Resul<Value> {
+        self.matched(path, system)?;
+        self.manifest.parser.parse(&system.read_to_string(path).await?)
+    }
+
+    pub async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, path: &str, input: I, system: &System) -> Resul<String> {
+        self.matched(path, system)?;
+        let value = Value::deserialize(input).map_err(Erro::from_deserialize)?;
+        self.manifest.parser.to_string(&value)
+    }
+
+    pub async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, path: &str, input: I, system: &System) -> Resul<()> {
+        let content = self.render(path, input, system).await?;
+        system.write(path, content.as_bytes()).await
+    }
+
+    pub fn help(&self) -> FileHelp<'_> {
+        let schema = self.manifest.parser.schema();
+        FileHelp {
+            name: &self.manifest.name,
+            description: &self.manifest.description,
+            capabilities: self.manifest.parser.capabilities(),
+            patterns: &self.patterns,
+            input: Documentation::Schema(schema.clone()),
+            output: Documentation::Schema(schema),
+            examples: &[],
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FilePluginError {
+    #[error("file plugin manifest {0} is invalid: {1}")]
+    InvalidManifest(String, String),
+    #[error("file plugin {0} has the same name as another file")]
+    DuplicateName(String),
+    #[error("line {0} can't be parsed")]
+    InvalidLine(String),
+    #[error("value {0} can't be written")]
+    InvalidValue(String),
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::files::plugin::{FilePluginBuilder, FilePluginManifest};
+    use crate::system::os::Os;
+
+    fn plugin(manifest: &str) -> FilePluginBuilder {
+        FilePluginBuilder::new(serde_yaml::from_str::<FilePluginManifest>(manifest).unwrap())
+    }
+
+    #[test]
+    fn parse_and_string() {
+        let key_value = plugin(r#"
+name: app-env
+description: environment of the app
+patterns: [{path: /etc/app/env}]
+parser: key_value
+"#);
+        let value = key_value.manifest.parser.parse("# comment\nHOME = /var/lib/app\nDEBUG=1\n").unwrap();
+        assert_eq!(value, json!({"HOME": "/var/lib/app", "DEBUG": "1"}));
+        assert_eq!(key_value.manifest.parser.to_string(&value).unwrap(), "HOME=/var/lib/app\nDEBUG=1\n");
+        assert!(key_value.r#match("/etc/app/env", &Os::LinuxDebian));
+        assert!(!key_value.r#match("/etc/app/env2", &Os::LinuxDebian));
+
+        let columns = plugin(r#"
+name: app-users
+description: users of the app
+patterns: [{regex: '^/etc/app/users\.d/.*$'}]
+compatibility: [LinuxAny]
+parser: columns
+names: [name, uid, comment]
+"#);
+        let value = columns.manifest.parser.parse("alice  1000 first user\nbob 1001\n").unwrap();
+        assert_eq!(value, json!([
+            {"name": "alice", "uid": "1000", "comment": "first user"},
+            {"name": "bob", "uid": "1001", "comment": null},
+        ]));
+        assert_eq!(columns.manifest.parser.to_string(&value).unwrap(), "alice\t1000\tfirst user\nbob\t1001\t\n");
+        assert!(columns.r#match("/etc/app/users.d/admins", &Os::LinuxDebian));
+        assert!(!columns.r#match("/etc/app/users.d/admins", &Os::Macos));
+
+        let ini = plugin(r#"
+name: app-ini
+description: settings of the app
+patterns: [{path: /etc/app/app.ini}]
+parser: ini
+"#);
+        assert!(ini.r#match("/etc/app/app.ini", &Os::Macos));
+        let value = ini.manifest.parser.parse("debug = true\n[server]\nport=80\n; comment\n").unwrap();
+        assert_eq!(value, json!({"": {"debug": "true"}, "server": {"port": "80"}}));
+        assert_eq!(ini.manifest.parser.to_string(&value).unwrap(), "debug = true\n[server]\nport = 80\n");
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use crate::files::prelude::*;
+
+fn string2bool(s: &str) -> bool {
+    s.contains("yes")
+}
+
+/// One processor, fields missing on the architecture or core are `null`
+/// x86 and ARM fields are described by their `/proc/cpuinfo` name, ARM `Features` are listed as `flags`
+#[derive(Serialize, Debug, PartialEq, Description)]
+pub struct CpuInfoDetail {
+    processor: usize,
+    vendor_id: Option<String>,
+    cpu_family: Option<usize>,
+    model: Option<usize>,
+    model_name: Option<String>,
+    stepping: Option<usize>,
+    microcode: Option<String>,
+    cpu_mhz: Option<f64>,
+    cache_size: Option<String>,
+    physical_id: Option<usize>,
+    siblings: Option<usize>,
+    core_id: Option<usize>,
+    cpu_cores: Option<usize>,
+    apicid: Option<usize>,
+    initial_apicid: Option<usize>,
+    fpu: Option<bool>,
+    fpu_exception: Option<bool>,
+    cpuid_level: Option<usize>,
+    wp: Option<bool>,
+    flags: Vec<String>,
+    bugs: Vec<String>,
+    bogomips: Option<f64>,
+    tlb_size: Option<String>,
+    clflush_size: Option<usize>,
+    cache_alignment: Option<usize>,
+    address_sizes: Option<String>,
+    cpu_implementer: Option<String>,
+    cpu_architecture: Option<String>,
+    cpu_variant: Option<String>,
+    cpu_part: Option<String>,
+    cpu_revision: Option<usize>,
+}
+
+impl CpuInfoDetail {
+    pub fn model_name(&self) -> Option<&str> { self.model_name.as_deref() }
+
+    pub fn physical_id(&self) -> Option<usize> { self.physical_id }
+
+    pub fn core_id(&self) -> Option<usize> { self.core_id }
+
+    /// `None` for blocks without a processor number like the board information of a Raspberry Pi
+    fn parse(content: &str) -> Resul<Option<Self>> {
+        // names like `cpu MHz` or `CPU implementer` as lower snake case
+        let fields = content.lines()
+            .filter_map(|l| l.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase().replace(' ', "_"), value.trim()))
+            .collect::<HashMap<String, &str>>();
+
+        let processor = match fields.get("processor") {
+            Some(processor) => processor.parse()?,
+            None => return Ok(None),
+        };
+
+        let text = |name: &str| fields.get(name).map(|v| v.to_string());
+        let flag = |name: &str| fields.get(name).map(|v| string2bool(v));
+        let list = |name: &str| fields.get(name).map(|v| v.split_whitespace().map(String::from).collect::<Vec<String>>());
+
+        Ok(Some(Self {
+            processor,
+            vendor_id: text("vendor_id"),
+            cpu_family: Self::number(&fields, "cpu_family")?,
+            model: Self::number(&fields, "model")?,
+            model_name: text("model_name"),
+            stepping: Self::number(&fields, "stepping")?,
+            microcode: text("microcode"),
+            cpu_mhz: Self::number(&fields, "cpu_mhz")?,
+            cache_size: text("cache_size"),
+            physical_id: Self::number(&fields, "physical_id")?,
+            siblings: Self::number(&fields, "siblings")?,
+            core_id: Self::number(&fields, "core_id")?,
+            cpu_cores: Self::number(&fields, "cpu_cores")?,
+            apicid: Self::number(&fields, "apicid")?,
+            initial_apicid: Self::number(&fields, "initial_apicid")?,
+            fpu: flag("fpu"),
+            fpu_exception: flag("fpu_exception"),
+            cpuid_level: Self::number(&fields, "cpuid_level")?,
+            wp: flag("wp"),
+            flags: list("flags").or_else(|| list("features")).unwrap_or_default(),
+            bugs: list("bugs").unwrap_or_default(),
+            bogomips: Self::number(&fields, "bogomips")?,
+            tlb_size: text("tlb_size"),
+            clflush_size: Self::number(&fields, "clflush_size")?,
+            cache_alignment: Self::number(&fields, "cache_alignment")?,
+            address_sizes: text("address_sizes"),
+            cpu_implementer: text("cpu_implementer"),
+            cpu_architecture: text("cpu_architecture"),
+            cpu_variant: text("cpu_variant"),
+            cpu_part: text("cpu_part"),
+            cpu_revision: Self::number(&fields, "cpu_revision")?,
+        }))
+    }
+
+    fn number<T: FromStr>(fields: &HashMap<String, &str>, name: &str) -> Resul<Option<T>> where Erro: From<T::Err> {
+        Ok(fields.get(name).map(|v| v.parse()).transpose()?)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CpuInfo;
+
+impl CpuInfo {
+    pub fn parse(content: &str) -> Resul<Vec<CpuInfoDetail>> {
+        content.split("\n\n")
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| CpuInfoDetail::parse(s).transpose())
+            .collect()
+    }
+}
+
+pub struct CpuinfoFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for CpuinfoFile {
+    type Output = Vec<CpuInfoDetail>;
+    type Input = ();
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        CpuInfo::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[derive(Clone)]
+pub struct CpuinfoBuilder;
+
+impl FileBuilder for CpuinfoBuilder {
+    type File = CpuinfoFile;
+
+    const NAME: &'static str = "cpuinfo";
+    const DESCRIPTION: &'static str = "Get information about processor";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read];
+    const CACHEABLE: bool = true;
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_path("/proc/cpuinfo", &[Os::LinuxAny])];
+        }
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref FILEEXAMPLE: [FileExample; 1] = [
+                FileExample::new_get("Single processor output", CpuInfoDetail {
+                        processor: 0,
+                        vendor_id: Some("AMtel".to_string()),
+                        cpu_family: Some(1),
+                        model: Some(2),
+                        model_name: Some("Core i Ryzen".to_string()),
+                        stepping: Some(0),
+                        microcode: Some("0xFFFFFF".to_string()),
+                        cpu_mhz: Some(133.7),
+                        cache_size: Some("1 Mb".to_string()),
+                        physical_id: Some(0),
+                        siblings: Some(0),
+                        core_id: Some(0),
+                        cpu_cores: Some(1),
+                        apicid: Some(0),
+                        initial_apicid: Some(0),
+                        fpu: Some(false),
+                        fpu_exception: Some(true),
+                        cpuid_level: Some(0),
+                        wp: Some(true),
+                        flags: vec!["sse".to_string(), "aes".to_string()],
+                        bugs: vec![],
+                        bogomips: Some(1234.56),
+                        tlb_size: Some("".to_string()),
+                        clflush_size: Some(0),
+                        cache_alignment: Some(0),
+                        address_sizes: Some("".to_string()),
+                        cpu_implementer: None,
+                        cpu_architecture: None,
+                        cpu_variant: None,
+                        cpu_part: None,
+                        cpu_revision: None,
+                    }
+                )
+            ];
+        }
+
+        FILEEXAMPLE.as_slice()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::files::cpuinfo::{CpuInfo, CpuInfoDetail};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(CpuInfo::parse(&read_test_resources("cpuinfo")).unwrap(), vec![
+            CpuInfoDetail {
+                processor: 0,
+                vendor_id: Some("AuthenticAMD".into()),
+                cpu_family: Some(23),
+                model: Some(8),
+                model_name: Some("AMD Ryzen 5 2600X Six-Core Processor".into()),
+                stepping: Some(2),
+                microcode: Some("0xffffffff".into()),
+                cpu_mhz: Some(3600.116),
+                cache_size: Some("512 KB".into()),
+                physical_id: Some(0),
+                siblings: Some(4),
+                core_id: Some(0),
+                cpu_cores: Some(4),
+                apicid: Some(0),
+                initial_apicid: Some(0),
+                fpu: Some(true),
+                fpu_exception: Some(true),
+                cpuid_level: Some(13),
+                wp: Some(true),
+                flags: ["fpu", "vme", "de", "pse", "tsc", "msr", "pae", "mce", "cx8", "apic", "sep", "mtrr", "pge", "mca", "cmov", "pat", "pse36", "clflush", "mmx", "fxsr", "sse", "sse2", "ht", "syscall", "nx", "mmxext", "fxsr_opt", "rdtscp", "lm", "constant_tsc", "rep_good", "nopl", "nonstop_tsc", "cpuid", "extd_apicid", "tsc_known_freq", "pni", "pclmulqdq", "ssse3", "cx16", "sse4_1", "sse4_2", "movbe", "popcnt", "aes", "rdrand", "hypervisor", "lahf_lm", "cmp_legacy", "cr8_legacy", "abm", "sse4a", "misalignsse", "3dnowprefetch", "ssbd", "vmmcall", "fsgsbase", "bmi1", "bmi2", "rdseed", "clflushopt", "arat"].iter().map(ToString::to_string).collect(),
+                bugs: ["fxsave_leak", "sysret_ss_attrs", "null_seg", "spectre_v1", "spectre_v2", "retbleed", "smt_rsb"].iter().map(ToString::to_string).collect(),
+                bogomips: Some(7200.23),
+                tlb_size: Some("2560 4K pages".into()),
+                clflush_size: Some(64),
+                cache_alignment: Some(64),
+                address_sizes: Some("48 bits physical, 48 bits virtual".into()),
+                cpu_implementer: None,
+                cpu_architecture: None,
+                cpu_variant: None,
+                cpu_part: None,
+                cpu_revision: None,
+            }, CpuInfoDetail {
+                processor: 1,
+                vendor_id: Some("AuthenticAMD".into()),
+                cpu_family: Some(23),
+                model: Some(8),
+                model_name: Some("AMD Ryzen 5 2600X Six-Core Processor".into()),
+                stepping: Some(2),
+                microcode: Some("0xffffffff".into()),
+                cpu_mhz: Some(3600.116),
+                cache_size: Some("512 KB".into()),
+                physical_id: Some(0),
+                siblings: Some(4),
+                core_id: Some(1),
+                cpu_cores: Some(4),
+                apicid: Some(1),
+                initial_apicid: Some(1),
+                fpu: Some(true),
+                fpu_exception: Some(true),
+                cpuid_level: Some(13),
+                wp: Some(true),
+                flags: ["fpu", "vme", "de", "pse", "tsc", "msr", "pae", "mce", "cx8", "apic", "sep", "mtrr", "pge", "mca", "cmov", "pat", "pse36", "clflush", "mmx", "fxsr", "sse", "sse2", "ht", "syscall", "nx", "mmxext", "fxsr_opt", "rdtscp", "lm", "constant_tsc", "rep_good", "nopl", "nonstop_tsc", "cpuid", "extd_apicid", "tsc_known_freq", "pni", "pclmulqdq", "ssse3", "cx16", "sse4_1", "sse4_2", "movbe", "popcnt", "aes", "rdrand", "hypervisor", "lahf_lm", "cmp_legacy", "cr8_legacy", "abm", "sse4a", "misalignsse", "3dnowprefetch", "ssbd", "vmmcall", "fsgsbase", "bmi1", "bmi2", "rdseed", "clflushopt", "arat"].iter().map(ToString::to_string).collect(),
+                bugs: ["fxsave_leak", "sysret_ss_attrs", "null_seg", "spectre_v1", "spectre_v2", "retbleed", "smt_rsb"].iter().map(ToString::to_string).collect(),
+                bogomips: Some(7200.23),
+                tlb_size: Some("2560 4K pages".into()),
+                clflush_size: Some(64),
+                cache_alignment: Some(64),
+                address_sizes: Some("48 bits physical, 48 bits virtual".into()),
+                cpu_implementer: None,
+                cpu_architecture: None,
+                cpu_variant: None,
+                cpu_part: None,
+                cpu_revision: None,
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_arm() {
+        let arm = |processor| CpuInfoDetail {
+            processor,
+            vendor_id: None,
+            cpu_family: None,
+            model: None,
+            model_name: None,
+            stepping: None,
+            microcode: None,
+            cpu_mhz: None,
+            cache_size: None,
+            physical_id: None,
+            siblings: None,
+            core_id: None,
+            cpu_cores: None,
+            apicid: None,
+            initial_apicid: None,
+            fpu: None,
+            fpu_exception: None,
+            cpuid_level: None,
+            wp: None,
+            flags: ["fp", "asimd", "evtstrm", "crc32", "cpuid"].iter().map(ToString::to_string).collect(),
+            bugs: vec![],
+            bogomips: Some(108.0),
+            tlb_size: None,
+            clflush_size: None,
+            cache_alignment: None,
+            address_sizes: None,
+            cpu_implementer: Some("0x41".into()),
+            cpu_architecture: Some("8".into()),
+            cpu_variant: Some("0x0".into()),
+            cpu_part: Some("0xd08".into()),
+            cpu_revision: Some(3),
+        };
+
+        // the board information of the last block is no processor
+        assert_eq!(CpuInfo::parse(&read_test_resources("cpuinfo_arm")).unwrap(), vec![arm(0), arm(1)]);
+        assert!(CpuInfo::parse("processor\t: 0\ncpu MHz\t: fast\n").is_err());
+    }
+}
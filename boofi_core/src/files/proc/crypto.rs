@@ -4,7 +4,7 @@ use crate::files::prelude::*;
 use thiserror::Error;
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct CryptoItem {
+pub struct CryptoItem {
     name: String,
     driver: String,
     module: String,
@@ -48,7 +48,7 @@ impl TryFrom<&str> for CryptoItem {
     }
 }
 
-pub(crate) struct Crypto;
+pub struct Crypto;
 
 impl Crypto {
     async fn parse(content: &str) -> Resul<Vec<CryptoItem>> {
@@ -60,7 +60,7 @@ impl Crypto {
     }
 }
 
-pub(crate) struct CryptoFile {
+pub struct CryptoFile {
     path: String,
 }
 
@@ -84,7 +84,7 @@ impl File for CryptoFile {
 }
 
 #[derive(Clone)]
-pub(crate) struct CryptoBuilder;
+pub struct CryptoBuilder;
 
 impl FileBuilder for CryptoBuilder {
     type File = CryptoFile;
@@ -127,7 +127,7 @@ impl FileBuilder for CryptoBuilder {
 }
 
 #[derive(Debug, Error)]
-pub(crate) enum CryptoError {
+pub enum CryptoError {
     #[error("failed to parse value")]
     ItemKeyValue,
     #[error("failed to parse key")]
@@ -1,7 +1,7 @@
 use crate::files::prelude::*;
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct FilesystemItem {
+pub struct FilesystemItem {
     name: String,
     nodev: bool,
 }
@@ -23,7 +23,7 @@ impl From<&str> for FilesystemItem {
     }
 }
 
-pub(crate) struct Filesystem;
+pub struct Filesystem;
 
 impl Filesystem {
     async fn parse(content: &str) -> Vec<FilesystemItem> {
@@ -34,7 +34,7 @@ impl Filesystem {
     }
 }
 
-pub(crate) struct FilesystemFile {
+pub struct FilesystemFile {
     path: String,
 }
 
@@ -58,7 +58,7 @@ impl File for FilesystemFile {
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct FilesystemBuilder;
+pub struct FilesystemBuilder;
 
 impl FileBuilder for FilesystemBuilder {
     type File = FilesystemFile;
@@ -66,6 +66,7 @@ impl FileBuilder for FilesystemBuilder {
     const NAME: &'static str = "filesystems";
     const DESCRIPTION: &'static str = "Get filesystems";
     const CAPABILITIES: &'static [Capability] = &[Capability::Read];
+    const CACHEABLE: bool = true;
 
     fn patterns(&self) -> &[FileMatchPattern] {
         lazy_static! {
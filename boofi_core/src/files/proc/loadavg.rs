@@ -3,7 +3,7 @@ use crate::files::prelude::*;
 use thiserror::Error;
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct LoadAvg {
+pub struct LoadAvg {
     avg1: f64,
     avg5: f64,
     avg15: f64,
@@ -13,7 +13,7 @@ pub(crate) struct LoadAvg {
 }
 
 impl LoadAvg {
-    fn parse(content: &str) -> Result<Self, LoadAvgError> {
+    pub fn parse(content: &str) -> Result<Self, LoadAvgError> {
         let mut split: Vec<&str> = content.split([' ', '/']).collect();
         Ok(Self {
             avg1: split.remove(0).parse()?,
@@ -26,7 +26,7 @@ impl LoadAvg {
     }
 }
 
-pub(crate) struct LoadAvgFile {
+pub struct LoadAvgFile {
     path: String,
 }
 
@@ -53,7 +53,7 @@ impl File for LoadAvgFile {
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct LoadAvgBuilder;
+pub struct LoadAvgBuilder;
 
 impl FileBuilder for LoadAvgBuilder {
     type File = LoadAvgFile;
@@ -91,7 +91,7 @@ impl FileBuilder for LoadAvgBuilder {
 }
 
 #[derive(Debug, Error)]
-pub(crate) enum LoadAvgError {
+pub enum LoadAvgError {
     #[error("failed to parse {0}")]
     ParseInt(ParseIntError),
     #[error("failed to parse {0}")]
@@ -116,7 +116,7 @@ mod test {
     use crate::utils::test::read_test_resources;
 
     #[test]
-    pub(crate) fn test_parse() {
+    pub fn test_parse() {
         assert_eq!(LoadAvg::parse(read_test_resources("loadavg").as_str()).unwrap(),
                    LoadAvg {
                        avg1: 0.07,
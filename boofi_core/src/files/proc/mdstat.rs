@@ -3,7 +3,7 @@ use crate::files::prelude::*;
 use thiserror::Error;
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct MdstatRecovery {
+pub struct MdstatRecovery {
     progress: f32,
     progress_blocks: usize,
     finish: String,
@@ -51,14 +51,14 @@ impl TryFrom<&str> for MdstatRecovery {
 }
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct MdstatDevice {
+pub struct MdstatDevice {
     name: String,
     number: usize,
     failed: bool,
 }
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct MdstatItem {
+pub struct MdstatItem {
     name: String,
     state: String,
     r#type: String,
@@ -116,12 +116,12 @@ impl TryFrom<String> for MdstatItem {
 }
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct MdstatDetails {
+pub struct MdstatDetails {
     personalities: Vec<String>,
     items: Vec<MdstatItem>,
 }
 
-pub(crate) struct Mdstat;
+pub struct Mdstat;
 
 impl Mdstat {
     fn parse(content: &str) -> Resul<MdstatDetails> {
@@ -155,7 +155,7 @@ impl Mdstat {
     }
 }
 
-pub(crate) struct MdstatFile {
+pub struct MdstatFile {
     path: String,
 }
 
@@ -179,7 +179,7 @@ impl File for MdstatFile {
 }
 
 #[derive(Clone)]
-pub(crate) struct MdstatBuilder;
+pub struct MdstatBuilder;
 
 impl FileBuilder for MdstatBuilder {
     type File = MdstatFile;
@@ -233,7 +233,7 @@ impl FileBuilder for MdstatBuilder {
 
 
 #[derive(Debug, Error)]
-pub(crate) enum MdstatError {
+pub enum MdstatError {
     #[error("failed to parse recovery progress")]
     RecoveryProgress,
     #[error("failed to parse recovery finish")]
@@ -1,7 +1,7 @@
 use crate::files::prelude::*;
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct Meminfo {
+pub struct Meminfo {
     mem_total: usize,
     mem_free: usize,
     mem_available: usize,
@@ -59,7 +59,7 @@ impl Meminfo {
         s.remove(0).remove(0).parse().map_err(Into::into)
     }
 
-    pub(crate) fn parse(content: &str) -> Resul<Self> {
+    pub fn parse(content: &str) -> Resul<Self> {
         let mut s: Vec<Vec<&str>> = content.split('\n')
             .filter(|s| !s.is_empty())
             .map(|s| {
@@ -127,7 +127,7 @@ impl Meminfo {
 }
 
 
-pub(crate) struct MeminfoFile {
+pub struct MeminfoFile {
     path: String,
 }
 
@@ -153,7 +153,7 @@ impl File for MeminfoFile {
 
 
 #[derive(Clone)]
-pub(crate) struct MeminfoBuilder;
+pub struct MeminfoBuilder;
 
 impl FileBuilder for MeminfoBuilder {
     type File = MeminfoFile;
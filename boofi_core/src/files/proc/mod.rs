@@ -0,0 +1,17 @@
+pub mod version;
+pub mod mdstat;
+pub mod meminfo;
+pub mod cpuinfo;
+pub mod loadavg;
+pub mod crypto;
+pub mod filesystems;
+pub mod mounts;
+pub mod partitions;
+pub mod swaps;
+pub mod uptime;
+pub mod net_dev;
+pub mod stat;
+pub mod process_status;
+pub mod process_cmdline;
+pub mod process_io;
+pub mod net_sockets;
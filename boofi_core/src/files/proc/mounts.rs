@@ -1,7 +1,7 @@
 use crate::files::prelude::*;
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct Mounts {
+pub struct Mounts {
     device: String,
     target: String,
     filesystem: String,
@@ -11,7 +11,7 @@ pub(crate) struct Mounts {
 }
 
 impl Mounts {
-    pub(crate) fn parse(content: &str) -> Resul<Vec<Self>> {
+    pub fn parse(content: &str) -> Resul<Vec<Self>> {
         content.trim()
             .split('\n')
             .map(|line| {
@@ -36,7 +36,7 @@ impl Mounts {
 }
 
 
-pub(crate) struct MountsFile {
+pub struct MountsFile {
     path: String,
 }
 
@@ -60,7 +60,7 @@ impl File for MountsFile {
 }
 
 #[derive(Clone)]
-pub(crate) struct MountsBuilder;
+pub struct MountsBuilder;
 
 impl FileBuilder for MountsBuilder {
     type File = MountsFile;
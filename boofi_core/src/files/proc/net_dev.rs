@@ -0,0 +1,193 @@
+use crate::files::prelude::*;
+use thiserror::Error;
+
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct NetDevReceive {
+    bytes: usize,
+    packets: usize,
+    errors: usize,
+    drops: usize,
+    fifo: usize,
+    frame: usize,
+    compressed: usize,
+    multicast: usize,
+}
+
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct NetDevTransmit {
+    bytes: usize,
+    packets: usize,
+    errors: usize,
+    drops: usize,
+    fifo: usize,
+    collisions: usize,
+    carrier: usize,
+    compressed: usize,
+}
+
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct NetDev {
+    interface: String,
+    receive: NetDevReceive,
+    transmit: NetDevTransmit,
+}
+
+impl NetDev {
+    pub fn parse(content: &str) -> Resul<Vec<NetDev>> {
+        content.split('\n')
+            .filter_map(|line| line.split_once(':'))
+            .map(|(interface, counters)| {
+                let c: Vec<usize> = counters.split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?;
+
+                if c.len() != 16 {
+                    return Err(NetDevError::InvalidLine(interface.trim().into()).into());
+                }
+
+                Ok(Self {
+                    interface: interface.trim().into(),
+                    receive: NetDevReceive {
+                        bytes: c[0],
+                        packets: c[1],
+                        errors: c[2],
+                        drops: c[3],
+                        fifo: c[4],
+                        frame: c[5],
+                        compressed: c[6],
+                        multicast: c[7],
+                    },
+                    transmit: NetDevTransmit {
+                        bytes: c[8],
+                        packets: c[9],
+                        errors: c[10],
+                        drops: c[11],
+                        fifo: c[12],
+                        collisions: c[13],
+                        carrier: c[14],
+                        compressed: c[15],
+                    },
+                })
+            }).collect()
+    }
+}
+
+pub struct NetDevFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for NetDevFile {
+    type Output = Vec<NetDev>;
+    type Input = ();
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        NetDev::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[derive(Clone)]
+pub struct NetDevBuilder;
+
+impl FileBuilder for NetDevBuilder {
+    type File = NetDevFile;
+
+    const NAME: &'static str = "net_dev";
+    const DESCRIPTION: &'static str = "Receive and transmit counters of each network interface";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern;1] = [FileMatchPattern::new_path("/proc/net/dev", &[Os::LinuxAny])];
+        }
+
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EAMPLES: [FileExample;1] = [
+                FileExample::new_get("Simple example",
+                    vec![NetDev {
+                        interface: "eth0".into(),
+                        receive: NetDevReceive {
+                            bytes: 1215645435,
+                            packets: 1085284,
+                            errors: 0,
+                            drops: 12,
+                            fifo: 0,
+                            frame: 0,
+                            compressed: 0,
+                            multicast: 1532,
+                        },
+                        transmit: NetDevTransmit {
+                            bytes: 67423553,
+                            packets: 514360,
+                            errors: 3,
+                            drops: 0,
+                            fifo: 0,
+                            collisions: 0,
+                            carrier: 0,
+                            compressed: 0,
+                        },
+                    }]
+                )
+            ];
+        }
+
+        EAMPLES.as_slice()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum NetDevError {
+    #[error("invalid counters of interface {0}")]
+    InvalidLine(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::net_dev::{NetDev, NetDevReceive, NetDevTransmit};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let devices = NetDev::parse(&read_test_resources("net_dev")).unwrap();
+
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].interface, "lo");
+        assert_eq!(devices[1], NetDev {
+            interface: "eth0".into(),
+            receive: NetDevReceive {
+                bytes: 1215645435,
+                packets: 1085284,
+                errors: 0,
+                drops: 12,
+                fifo: 0,
+                frame: 0,
+                compressed: 0,
+                multicast: 1532,
+            },
+            transmit: NetDevTransmit {
+                bytes: 67423553,
+                packets: 514360,
+                errors: 3,
+                drops: 0,
+                fifo: 0,
+                collisions: 0,
+                carrier: 0,
+                compressed: 0,
+            },
+        });
+    }
+}
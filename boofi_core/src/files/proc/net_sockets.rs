@@ -0,0 +1,216 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use crate::files::prelude::*;
+use regex::Regex;
+use thiserror::Error;
+
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct NetSocketAddress {
+    address: String,
+    port: usize,
+}
+
+impl NetSocketAddress {
+    /// `0100007F:0016` or 32 hex digits for ipv6, each 32 bit word is little endian as on x86 and arm
+    fn parse(value: &str) -> Result<Self, NetSocketsError> {
+        let invalid = || NetSocketsError::InvalidAddress(value.into());
+        let (address, port) = value.split_once(':').ok_or_else(invalid)?;
+
+        let words = (0..address.len() / 8)
+            .map(|i| address.get(i * 8..i * 8 + 8).and_then(|w| u32::from_str_radix(w, 16).ok()).map(u32::to_le_bytes))
+            .collect::<Option<Vec<[u8; 4]>>>()
+            .ok_or_else(invalid)?;
+
+        let address = match (address.len(), words.as_slice()) {
+            (8, [word]) => Ipv4Addr::from(*word).to_string(),
+            (32, words) => Ipv6Addr::from(<[u8; 16]>::try_from(words.concat()).map_err(|_| invalid())?).to_string(),
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            address,
+            port: usize::from_str_radix(port, 16).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// protocol:   tcp, tcp6, udp or udp6 by the file name
+/// state:      named like `ss`, unconnected udp sockets are `UNCONN`
+/// inode:      matches the `socket:[inode]` links in `/proc/<pid>/fd`
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct NetSocket {
+    protocol: String,
+    local: NetSocketAddress,
+    remote: NetSocketAddress,
+    state: String,
+    send_queue: usize,
+    receive_queue: usize,
+    uid: usize,
+    inode: usize,
+}
+
+impl NetSocket {
+    /// `/proc/net/tcp` like content, the header line is skipped
+    pub fn parse(protocol: &str, content: &str) -> Resul<Vec<Self>> {
+        content.lines()
+            .filter(|l| !l.trim().is_empty() && !l.trim_start().starts_with("sl"))
+            .map(|l| {
+                let c: Vec<&str> = l.split_whitespace().collect();
+                if c.len() < 10 {
+                    return Err(NetSocketsError::InvalidLine(l.trim().into()).into());
+                }
+
+                let (send_queue, receive_queue) = c[4].split_once(':').ok_or(NetSocketsError::InvalidLine(l.trim().into()))?;
+
+                Ok(Self {
+                    protocol: protocol.into(),
+                    local: NetSocketAddress::parse(c[1])?,
+                    remote: NetSocketAddress::parse(c[2])?,
+                    state: Self::state(c[3])?.into(),
+                    send_queue: usize::from_str_radix(send_queue, 16)?,
+                    receive_queue: usize::from_str_radix(receive_queue, 16)?,
+                    uid: c[7].parse()?,
+                    inode: c[9].parse()?,
+                })
+            })
+            .collect()
+    }
+
+    /// tcp states of `include/net/tcp_states.h`
+    fn state(code: &str) -> Result<&'static str, NetSocketsError> {
+        Ok(match code {
+            "01" => "ESTAB",
+            "02" => "SYN-SENT",
+            "03" => "SYN-RECV",
+            "04" => "FIN-WAIT-1",
+            "05" => "FIN-WAIT-2",
+            "06" => "TIME-WAIT",
+            "07" => "UNCONN",
+            "08" => "CLOSE-WAIT",
+            "09" => "LAST-ACK",
+            "0A" => "LISTEN",
+            "0B" => "CLOSING",
+            "0C" => "NEW-SYN-RECV",
+            _ => return Err(NetSocketsError::UnknownState(code.into())),
+        })
+    }
+}
+
+pub struct NetSocketsFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for NetSocketsFile {
+    type Output = Vec<NetSocket>;
+    type Input = ();
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        let protocol = self.path.rsplit('/').next().unwrap_or_default();
+        NetSocket::parse(protocol, &system.read_to_string(self.path()).await?)
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[derive(Clone)]
+pub struct NetSocketsBuilder;
+
+impl FileBuilder for NetSocketsBuilder {
+    type File = NetSocketsFile;
+
+    const NAME: &'static str = "net_sockets";
+    const DESCRIPTION: &'static str = "Tcp and udp sockets of the kernel with addresses, state, queues, owner uid and inode, works without `ss`";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern;1] = [FileMatchPattern::new_regex(Regex::new("^/proc/net/(tcp|udp)6?$").unwrap(), &[Os::LinuxAny])];
+        }
+
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EAMPLES: [FileExample;1] = [
+                FileExample::new_get("Listening ssh daemon",
+                    vec![NetSocket {
+                        protocol: "tcp".into(),
+                        local: NetSocketAddress {
+                            address: "0.0.0.0".into(),
+                            port: 22,
+                        },
+                        remote: NetSocketAddress {
+                            address: "0.0.0.0".into(),
+                            port: 0,
+                        },
+                        state: "LISTEN".into(),
+                        send_queue: 0,
+                        receive_queue: 0,
+                        uid: 0,
+                        inode: 21433,
+                    }]
+                )
+            ];
+        }
+
+        EAMPLES.as_slice()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum NetSocketsError {
+    #[error("invalid line {0}")]
+    InvalidLine(String),
+    #[error("invalid address {0}")]
+    InvalidAddress(String),
+    #[error("unknown state {0}")]
+    UnknownState(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::net_sockets::{NetSocket, NetSocketAddress};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let tcp = NetSocket::parse("tcp", &read_test_resources("net_tcp")).unwrap();
+        assert_eq!(tcp.len(), 3);
+        assert_eq!(tcp[2], NetSocket {
+            protocol: "tcp".into(),
+            local: NetSocketAddress {
+                address: "127.0.0.1".into(),
+                port: 40086,
+            },
+            remote: NetSocketAddress {
+                address: "127.0.0.1".into(),
+                port: 48271,
+            },
+            state: "ESTAB".into(),
+            send_queue: 0,
+            receive_queue: 0,
+            uid: 0,
+            inode: 133645,
+        });
+
+        let tcp6 = NetSocket::parse("tcp6", &read_test_resources("net_tcp6")).unwrap();
+        assert_eq!(tcp6[0].local, NetSocketAddress { address: "::".into(), port: 22 });
+        assert_eq!(tcp6[1].remote, NetSocketAddress { address: "::1".into(), port: 53924 });
+        assert_eq!(tcp6[1].uid, 1000);
+
+        let udp = NetSocket::parse("udp", &read_test_resources("net_udp")).unwrap();
+        assert_eq!(udp[0].local, NetSocketAddress { address: "127.0.0.53".into(), port: 53 });
+        assert_eq!(udp[0].state, "UNCONN");
+
+        assert!(NetSocket::parse("tcp", "   0: 0100007F:0016 00000000:0000 FF 00000000:00000000 00:00000000 00000000 0 0 1").is_err());
+    }
+}
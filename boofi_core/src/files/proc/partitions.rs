@@ -1,7 +1,7 @@
 use crate::files::prelude::*;
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct Partition {
+pub struct Partition {
     major: usize,
     minor: usize,
     blocks: usize,
@@ -9,7 +9,7 @@ pub(crate) struct Partition {
 }
 
 impl Partition {
-    pub(crate) fn parse(content: &str) -> Resul<Vec<Self>> {
+    pub fn parse(content: &str) -> Resul<Vec<Self>> {
         content.split('\n')
             .filter_map(|line| {
                 let l = line.trim();
@@ -34,7 +34,7 @@ impl Partition {
 }
 
 
-pub(crate) struct PartitionsFile {
+pub struct PartitionsFile {
     path: String,
 }
 
@@ -59,7 +59,7 @@ impl File for PartitionsFile {
 }
 
 #[derive(Clone)]
-pub(crate) struct PartitionsBuilder;
+pub struct PartitionsBuilder;
 
 impl FileBuilder for PartitionsBuilder {
     type File = PartitionsFile;
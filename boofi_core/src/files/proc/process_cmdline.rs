@@ -0,0 +1,88 @@
+use crate::files::prelude::*;
+use regex::Regex;
+
+/// Program and arguments a process was started with, empty for kernel threads and zombies
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct ProcessCmdline {
+    argv: Vec<String>,
+}
+
+impl ProcessCmdline {
+    /// arguments are terminated by a null byte
+    pub fn parse(content: &str) -> Self {
+        Self {
+            argv: content.split_terminator('\0').map(String::from).collect(),
+        }
+    }
+}
+
+pub struct ProcessCmdlineFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for ProcessCmdlineFile {
+    type Output = ProcessCmdline;
+    type Input = ();
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        Ok(ProcessCmdline::parse(&system.read_to_string(self.path()).await?))
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[derive(Clone)]
+pub struct ProcessCmdlineBuilder;
+
+impl FileBuilder for ProcessCmdlineBuilder {
+    type File = ProcessCmdlineFile;
+
+    const NAME: &'static str = "process_cmdline";
+    const DESCRIPTION: &'static str = "Program and arguments of a process";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern;1] = [FileMatchPattern::new_regex(Regex::new("^/proc/[0-9]+/cmdline$").unwrap(), &[Os::LinuxAny])];
+        }
+
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EAMPLES: [FileExample;1] = [
+                FileExample::new_get("Ssh daemon",
+                    ProcessCmdline {
+                        argv: vec!["/usr/sbin/sshd".into(), "-D".into()],
+                    }
+                )
+            ];
+        }
+
+        EAMPLES.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::process_cmdline::ProcessCmdline;
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ProcessCmdline::parse(&read_test_resources("process_cmdline")), ProcessCmdline {
+            argv: vec!["/usr/sbin/sshd".into(), "-D".into(), "-o".into(), "AuthorizedKeysFile .ssh/authorized_keys".into()],
+        });
+        assert!(ProcessCmdline::parse("").argv.is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use crate::files::prelude::*;
+use regex::Regex;
+use thiserror::Error;
+
+/// I/O counters of a process in bytes and system calls
+/// rchar/wchar:    read and written by system calls, including page cache hits
+/// read_bytes/write_bytes: fetched from and sent to the storage layer
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct ProcessIo {
+    rchar: usize,
+    wchar: usize,
+    syscr: usize,
+    syscw: usize,
+    read_bytes: usize,
+    write_bytes: usize,
+    cancelled_write_bytes: usize,
+}
+
+impl ProcessIo {
+    pub fn parse(content: &str) -> Resul<Self> {
+        let fields: HashMap<&str, &str> = content.lines()
+            .filter_map(|l| l.split_once(':'))
+            .map(|(key, value)| (key, value.trim()))
+            .collect();
+
+        let value = |key: &'static str| -> Resul<usize> {
+            Ok(fields.get(key).ok_or(ProcessIoError::KeyMissing(key))?.parse()?)
+        };
+
+        Ok(Self {
+            rchar: value("rchar")?,
+            wchar: value("wchar")?,
+            syscr: value("syscr")?,
+            syscw: value("syscw")?,
+            read_bytes: value("read_bytes")?,
+            write_bytes: value("write_bytes")?,
+            cancelled_write_bytes: value("cancelled_write_bytes")?,
+        })
+    }
+}
+
+pub struct ProcessIoFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for ProcessIoFile {
+    type Output = ProcessIo;
+    type Input = ();
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        ProcessIo::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[derive(Clone)]
+pub struct ProcessIoBuilder;
+
+impl FileBuilder for ProcessIoBuilder {
+    type File = ProcessIoFile;
+
+    const NAME: &'static str = "process_io";
+    const DESCRIPTION: &'static str = "I/O counters of a process, other users' processes need sudo";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern;1] = [FileMatchPattern::new_regex(Regex::new("^/proc/[0-9]+/io$").unwrap(), &[Os::LinuxAny])];
+        }
+
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EAMPLES: [FileExample;1] = [
+                FileExample::new_get("Simple example",
+                    ProcessIo {
+                        rchar: 323934931,
+                        wchar: 323929600,
+                        syscr: 632687,
+                        syscw: 632675,
+                        read_bytes: 0,
+                        write_bytes: 323932160,
+                        cancelled_write_bytes: 0,
+                    }
+                )
+            ];
+        }
+
+        EAMPLES.as_slice()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ProcessIoError {
+    #[error("key {0} missing")]
+    KeyMissing(&'static str),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::process_io::ProcessIo;
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ProcessIo::parse(&read_test_resources("process_io")).unwrap(), ProcessIo {
+            rchar: 3980,
+            wchar: 0,
+            syscr: 9,
+            syscw: 0,
+            read_bytes: 0,
+            write_bytes: 0,
+            cancelled_write_bytes: 0,
+        });
+        assert!(ProcessIo::parse("rchar: 1\n").is_err());
+    }
+}
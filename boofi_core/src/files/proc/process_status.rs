@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use crate::files::prelude::*;
+use regex::Regex;
+use thiserror::Error;
+
+/// Status of a process, memory sizes in kB
+/// state:  letter and description like `S (sleeping)`
+/// uid:    real, effective, saved set and filesystem user id
+/// gid:    real, effective, saved set and filesystem group id
+/// vm_*:   missing for kernel threads
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct ProcessStatus {
+    name: String,
+    state: String,
+    pid: usize,
+    ppid: usize,
+    uid: Vec<usize>,
+    gid: Vec<usize>,
+    threads: usize,
+    vm_peak: Option<usize>,
+    vm_size: Option<usize>,
+    vm_hwm: Option<usize>,
+    vm_rss: Option<usize>,
+    vm_swap: Option<usize>,
+    voluntary_ctxt_switches: Option<usize>,
+    nonvoluntary_ctxt_switches: Option<usize>,
+}
+
+impl ProcessStatus {
+    pub fn parse(content: &str) -> Resul<Self> {
+        let fields: HashMap<&str, &str> = content.lines()
+            .filter_map(|l| l.split_once(':'))
+            .map(|(key, value)| (key, value.trim()))
+            .collect();
+
+        let text = |key: &'static str| fields.get(key).map(|v| v.to_string()).ok_or(ProcessStatusError::KeyMissing(key));
+        let number = |key: &str| fields.get(key).map(|v| v.trim_end_matches("kB").trim().parse::<usize>()).transpose();
+        let required = |key: &'static str| number(key)?.ok_or(Erro::from(ProcessStatusError::KeyMissing(key)));
+        let ids = |key: &'static str| -> Resul<Vec<usize>> {
+            Ok(fields.get(key).ok_or(ProcessStatusError::KeyMissing(key))?
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()?)
+        };
+
+        Ok(Self {
+            name: text("Name")?,
+            state: text("State")?,
+            pid: required("Pid")?,
+            ppid: required("PPid")?,
+            uid: ids("Uid")?,
+            gid: ids("Gid")?,
+            threads: required("Threads")?,
+            vm_peak: number("VmPeak")?,
+            vm_size: number("VmSize")?,
+            vm_hwm: number("VmHWM")?,
+            vm_rss: number("VmRSS")?,
+            vm_swap: number("VmSwap")?,
+            voluntary_ctxt_switches: number("voluntary_ctxt_switches")?,
+            nonvoluntary_ctxt_switches: number("nonvoluntary_ctxt_switches")?,
+        })
+    }
+}
+
+pub struct ProcessStatusFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for ProcessStatusFile {
+    type Output = ProcessStatus;
+    type Input = ();
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        ProcessStatus::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[derive(Clone)]
+pub struct ProcessStatusBuilder;
+
+impl FileBuilder for ProcessStatusBuilder {
+    type File = ProcessStatusFile;
+
+    const NAME: &'static str = "process_status";
+    const DESCRIPTION: &'static str = "Name, state, parent, user and group ids, threads, memory sizes in kB and context switches of a process";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern;1] = [FileMatchPattern::new_regex(Regex::new("^/proc/[0-9]+/status$").unwrap(), &[Os::LinuxAny])];
+        }
+
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EAMPLES: [FileExample;1] = [
+                FileExample::new_get("Ssh daemon",
+                    ProcessStatus {
+                        name: "sshd".into(),
+                        state: "S (sleeping)".into(),
+                        pid: 812,
+                        ppid: 1,
+                        uid: vec![0, 0, 0, 0],
+                        gid: vec![0, 0, 0, 0],
+                        threads: 1,
+                        vm_peak: Some(15432),
+                        vm_size: Some(15432),
+                        vm_hwm: Some(9012),
+                        vm_rss: Some(9012),
+                        vm_swap: Some(0),
+                        voluntary_ctxt_switches: Some(210),
+                        nonvoluntary_ctxt_switches: Some(3),
+                    }
+                )
+            ];
+        }
+
+        EAMPLES.as_slice()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ProcessStatusError {
+    #[error("key {0} missing")]
+    KeyMissing(&'static str),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::process_status::ProcessStatus;
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ProcessStatus::parse(&read_test_resources("process_status")).unwrap(), ProcessStatus {
+            name: "sshd".into(),
+            state: "S (sleeping)".into(),
+            pid: 812,
+            ppid: 1,
+            uid: vec![0, 0, 0, 0],
+            gid: vec![0, 0, 0, 0],
+            threads: 1,
+            vm_peak: Some(2640),
+            vm_size: Some(2640),
+            vm_hwm: Some(1408),
+            vm_rss: Some(1408),
+            vm_swap: Some(0),
+            voluntary_ctxt_switches: Some(210),
+            nonvoluntary_ctxt_switches: Some(3),
+        });
+
+        let kernel_thread = ProcessStatus::parse("Name:\tkworker/0:1\nState:\tI (idle)\nPid:\t12\nPPid:\t2\nUid:\t0\t0\t0\t0\nGid:\t0\t0\t0\t0\nThreads:\t1\n").unwrap();
+        assert_eq!(kernel_thread.vm_rss, None);
+        assert!(ProcessStatus::parse("Name:\tcat\n").is_err());
+    }
+}
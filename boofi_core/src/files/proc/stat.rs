@@ -0,0 +1,210 @@
+use crate::files::prelude::*;
+use thiserror::Error;
+
+/// Time spent in each mode in jiffies
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct StatCpu {
+    cpu: String,
+    user: usize,
+    nice: usize,
+    system: usize,
+    idle: usize,
+    iowait: usize,
+    irq: usize,
+    softirq: usize,
+}
+
+impl StatCpu {
+    fn parse(cpu: &str, values: &str) -> Resul<Self> {
+        let v: Vec<usize> = values.split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+
+        if v.len() < 7 {
+            return Err(StatError::InvalidCpuLine(cpu.into()).into());
+        }
+
+        Ok(Self {
+            cpu: cpu.into(),
+            user: v[0],
+            nice: v[1],
+            system: v[2],
+            idle: v[3],
+            iowait: v[4],
+            irq: v[5],
+            softirq: v[6],
+        })
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Description)]
+pub struct Stat {
+    total: StatCpu,
+    cpus: Vec<StatCpu>,
+    context_switches: usize,
+    boot_time: usize,
+    processes: usize,
+}
+
+impl Stat {
+    pub fn parse(content: &str) -> Resul<Self> {
+        let mut total = None;
+        let mut cpus = vec![];
+        let mut context_switches = None;
+        let mut boot_time = None;
+        let mut processes = None;
+
+        for (key, values) in content.lines().filter_map(|line| line.split_once(' ')) {
+            match key {
+                "cpu" => total = Some(StatCpu::parse(key, values)?),
+                "ctxt" => context_switches = Some(values.trim().parse()?),
+                "btime" => boot_time = Some(values.trim().parse()?),
+                "processes" => processes = Some(values.trim().parse()?),
+                _ if key.starts_with("cpu") => cpus.push(StatCpu::parse(key, values)?),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            total: total.ok_or(StatError::KeyMissing("cpu"))?,
+            cpus,
+            context_switches: context_switches.ok_or(StatError::KeyMissing("ctxt"))?,
+            boot_time: boot_time.ok_or(StatError::KeyMissing("btime"))?,
+            processes: processes.ok_or(StatError::KeyMissing("processes"))?,
+        })
+    }
+}
+
+pub struct StatFile {
+    path: String,
+}
+
+#[async_trait]
+impl File for StatFile {
+    type Output = Stat;
+    type Input = ();
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        Stat::parse(&system.read_to_string(self.path()).await?)
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[derive(Clone)]
+pub struct StatBuilder;
+
+impl FileBuilder for StatBuilder {
+    type File = StatFile;
+
+    const NAME: &'static str = "stat";
+    const DESCRIPTION: &'static str = "Kernel statistics like cpu time in jiffies, context switches, boot time and forked processes";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern;1] = [FileMatchPattern::new_path("/proc/stat", &[Os::LinuxAny])];
+        }
+
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EAMPLES: [FileExample;1] = [
+                FileExample::new_get("Single cpu",
+                    Stat {
+                        total: StatCpu {
+                            cpu: "cpu".into(),
+                            user: 1393280,
+                            nice: 32966,
+                            system: 572056,
+                            idle: 13343292,
+                            iowait: 6130,
+                            irq: 0,
+                            softirq: 17875,
+                        },
+                        cpus: vec![StatCpu {
+                            cpu: "cpu0".into(),
+                            user: 1393280,
+                            nice: 32966,
+                            system: 572056,
+                            idle: 13343292,
+                            iowait: 6130,
+                            irq: 0,
+                            softirq: 17875,
+                        }],
+                        context_switches: 115315,
+                        boot_time: 1672845032,
+                        processes: 86031,
+                    }
+                )
+            ];
+        }
+
+        EAMPLES.as_slice()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum StatError {
+    #[error("invalid cpu line {0}")]
+    InvalidCpuLine(String),
+    #[error("key {0} missing")]
+    KeyMissing(&'static str),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::files::stat::{Stat, StatCpu};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Stat::parse(&read_test_resources("stat")).unwrap(), Stat {
+            total: StatCpu {
+                cpu: "cpu".into(),
+                user: 10132153,
+                nice: 290696,
+                system: 3084719,
+                idle: 46828483,
+                iowait: 16683,
+                irq: 0,
+                softirq: 25195,
+            },
+            cpus: vec![
+                StatCpu {
+                    cpu: "cpu0".into(),
+                    user: 1393280,
+                    nice: 32966,
+                    system: 572056,
+                    idle: 13343292,
+                    iowait: 6130,
+                    irq: 0,
+                    softirq: 17875,
+                },
+                StatCpu {
+                    cpu: "cpu1".into(),
+                    user: 1335455,
+                    nice: 42231,
+                    system: 484339,
+                    idle: 11167813,
+                    iowait: 6071,
+                    irq: 0,
+                    softirq: 3497,
+                },
+            ],
+            context_switches: 115315,
+            boot_time: 1672845032,
+            processes: 86031,
+        });
+    }
+}
@@ -1,7 +1,7 @@
 use crate::files::prelude::*;
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct Swap {
+pub struct Swap {
     filename: String,
     r#type: String,
     size: usize,
@@ -10,7 +10,7 @@ pub(crate) struct Swap {
 }
 
 impl Swap {
-    pub(crate) fn parse(content: &str) -> Resul<Vec<Swap>> {
+    pub fn parse(content: &str) -> Resul<Vec<Swap>> {
         content.split('\n')
             .filter_map(|line| {
                 let l = line.trim();
@@ -35,7 +35,7 @@ impl Swap {
     }
 }
 
-pub(crate) struct SwapsFile {
+pub struct SwapsFile {
     path: String,
 }
 
@@ -60,7 +60,7 @@ impl File for SwapsFile {
 }
 
 #[derive(Clone)]
-pub(crate) struct SwapsBuilder;
+pub struct SwapsBuilder;
 
 impl FileBuilder for SwapsBuilder {
     type File = SwapsFile;
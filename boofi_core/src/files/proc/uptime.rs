@@ -1,13 +1,13 @@
 use crate::files::prelude::*;
 
 #[derive(Serialize, Debug, PartialEq, Description)]
-pub(crate) struct Uptime {
+pub struct Uptime {
     uptime: f64,
     idle: f64,
 }
 
 impl Uptime {
-    pub(crate) fn parse(content: &str) -> Resul<Self> {
+    pub fn parse(content: &str) -> Resul<Self> {
         let mut s: Vec<&str> = content.trim().split(' ').collect();
 
         Ok(Self {
@@ -17,7 +17,7 @@ impl Uptime {
     }
 }
 
-pub(crate) struct UptimeFile {
+pub struct UptimeFile {
     path: String,
 }
 
@@ -41,7 +41,7 @@ impl File for UptimeFile {
 }
 
 #[derive(Clone)]
-pub(crate) struct UptimeBuilder;
+pub struct UptimeBuilder;
 
 impl FileBuilder for UptimeBuilder {
     type File = UptimeFile;
@@ -80,7 +80,7 @@ mod test {
     use crate::utils::test::read_test_resources;
 
     #[test]
-    pub(crate) fn test_parse() {
+    pub fn test_parse() {
         assert_eq!(Uptime::parse(read_test_resources("uptime").as_str()).unwrap(), Uptime {
             uptime: 874.22,
             idle: 2264.90,
@@ -2,7 +2,7 @@ use crate::files::prelude::*;
 use thiserror::Error;
 
 #[derive(Debug, Serialize, PartialEq, Description)]
-pub(crate) struct Version {
+pub struct Version {
     version: String,
     compiled_by: String,
     compiled_host: String,
@@ -10,7 +10,7 @@ pub(crate) struct Version {
 }
 
 impl Version {
-    pub(crate) fn parse(content: &str) -> Resul<Self> {
+    pub fn parse(content: &str) -> Resul<Self> {
         let (version, s) = content.split_once(" (").ok_or(VersionError::Version)?;
         let (compiled_by, s) = s.split_once('@').ok_or(VersionError::CompiledBy)?;
         let (compiled_host, s) = s.split_once(") (").ok_or(VersionError::CompilerHost)?;
@@ -24,11 +24,11 @@ impl Version {
         })
     }
 
-    pub(crate) fn version(&self) -> &str { &self.version }
+    pub fn version(&self) -> &str { &self.version }
 }
 
 #[derive(Description)]
-pub(crate) struct VersionFile {
+pub struct VersionFile {
     path: String,
 }
 
@@ -54,7 +54,7 @@ impl File for VersionFile {
 }
 
 #[derive(Clone)]
-pub(crate) struct VersionBuilder;
+pub struct VersionBuilder;
 
 impl FileBuilder for VersionBuilder {
     file_metadata!(
@@ -73,10 +73,12 @@ impl FileBuilder for VersionBuilder {
         ;
         FileMatchPattern::new_path("/proc/version", &[Os::LinuxAny])
     );
+
+    const CACHEABLE: bool = true;
 }
 
 #[derive(Debug, Error)]
-pub(crate) enum VersionError {
+pub enum VersionError {
     #[error("failed to parse version")]
     Version,
     #[error("failed to parse compiled by")]
@@ -93,7 +95,7 @@ mod test {
     use crate::utils::test::read_test_resources;
 
     #[test]
-    pub(crate) fn test_parse() {
+    pub fn test_parse() {
         assert_eq!(Version::parse(&read_test_resources("version")).unwrap(), Version {
             version: "Linux version 5.15.0-76-generic".into(),
             compiled_by: "buildd".into(),
@@ -1,18 +1,19 @@
 use crate::files::prelude::*;
+use serde_json::{json, Value};
 use crate::files::Regex;
 
 #[derive(Debug)]
-pub(crate) struct Text {
+pub struct Text {
     path: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct TextCreateInput {
+pub struct TextCreateInput {
     content: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Description)]
-pub(crate) struct TextInput {
+pub struct TextInput {
     content: String,
 }
 
@@ -31,18 +32,22 @@ impl File for Text {
         system.read_to_string(self.path.as_str()).await
     }
 
-    async fn write<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, system: &System) -> Resul<()> {
+    async fn render<'de, I: Deserializer<'de> + Send + Sync>(&self, input: I, _system: &System) -> Resul<String> {
         let i = TextInput::deserialize(input).map_err(Erro::from_deserialize)?;
-        system.write(self.path.as_str(), i.content.as_str().as_bytes()).await
+        Ok(i.content)
     }
 
     fn path(&self) -> &str {
         &self.path
     }
+
+    fn replacement(&self, output: Value) -> Value {
+        json!({"content": output})
+    }
 }
 
 #[derive(Clone, Debug)]
-pub(crate) struct TextBuilder;
+pub struct TextBuilder;
 
 impl FileBuilder for TextBuilder {
     type File = Text;
@@ -53,7 +58,7 @@ impl FileBuilder for TextBuilder {
 
     fn patterns(&self) -> &[FileMatchPattern] {
         lazy_static! {
-            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_regex(Regex::new(".*").unwrap(), &[Os::LinuxAny])];
+            static ref PATTERN: [FileMatchPattern; 1] = [FileMatchPattern::new_regex(Regex::new(".*").unwrap(), &[Os::PosixAny])];
         }
         PATTERN.as_slice()
     }
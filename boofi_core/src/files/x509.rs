@@ -0,0 +1,410 @@
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use regex::Regex;
+use ring::digest::{digest, SHA256};
+use rustls_pemfile::Item;
+use yasna::models::ObjectIdentifier;
+use yasna::{BERReader, Tag};
+use crate::files::prelude::*;
+use crate::tls::{certificate_elements, certificate_validity};
+
+/// Short names of distinguished name attributes like openssl prints them
+const ATTRIBUTES: [(&[u64], &str); 10] = [
+    (&[2, 5, 4, 3], "CN"),
+    (&[2, 5, 4, 5], "serialNumber"),
+    (&[2, 5, 4, 6], "C"),
+    (&[2, 5, 4, 7], "L"),
+    (&[2, 5, 4, 8], "ST"),
+    (&[2, 5, 4, 10], "O"),
+    (&[2, 5, 4, 11], "OU"),
+    (&[2, 5, 4, 12], "title"),
+    (&[1, 2, 840, 113549, 1, 9, 1], "emailAddress"),
+    (&[0, 9, 2342, 19200300, 100, 1, 25], "DC"),
+];
+
+/// Key algorithms with the bits of the algorithms without parameters
+const ALGORITHMS: [(&[u64], &str, Option<usize>); 6] = [
+    (&[1, 2, 840, 113549, 1, 1, 1], "rsa", None),
+    (&[1, 2, 840, 10045, 2, 1], "ec", None),
+    (&[1, 2, 840, 10040, 4, 1], "dsa", None),
+    (&[1, 3, 101, 110], "x25519", Some(256)),
+    (&[1, 3, 101, 112], "ed25519", Some(256)),
+    (&[1, 3, 101, 113], "ed448", Some(456)),
+];
+
+const CURVES: [(&[u64], &str, usize); 4] = [
+    (&[1, 2, 840, 10045, 3, 1, 7], "prime256v1", 256),
+    (&[1, 3, 132, 0, 10], "secp256k1", 256),
+    (&[1, 3, 132, 0, 34], "secp384r1", 384),
+    (&[1, 3, 132, 0, 35], "secp521r1", 521),
+];
+
+const ALTERNATIVE_NAME: [u64; 4] = [2, 5, 29, 17];
+
+/// Type and size of a key, the key material itself is never returned
+/// algorithm:  `rsa`, `ec`, `dsa`, `ed25519`, `ed448`, `x25519` or the object identifier
+/// curve:      named curve of `ec` keys like `prime256v1`
+#[derive(Debug, PartialEq, Serialize, Description)]
+pub struct X509Key {
+    algorithm: String,
+    bits: Option<usize>,
+    curve: Option<String>,
+}
+
+impl X509Key {
+    fn new(algorithm: &ObjectIdentifier, parameters: Option<&[u8]>, key: &[u8], private: bool) -> Self {
+        let (name, bits) = ALGORITHMS.iter()
+            .find(|(oid, _, _)| algorithm.components() == oid)
+            .map(|(_, name, bits)| (name.to_string(), *bits))
+            .unwrap_or_else(|| (algorithm.to_string(), None));
+
+        match name.as_str() {
+            "rsa" => Self { bits: Self::rsa_bits(key, private).ok(), ..Self::named(name, bits) },
+            "ec" => parameters.and_then(|p| yasna::parse_der(p, |r| r.read_oid()).ok()).map(|c| Self::curve(name.clone(), &c)).unwrap_or_else(|| Self::named(name, bits)),
+            _ => Self::named(name, bits),
+        }
+    }
+
+    fn named(algorithm: String, bits: Option<usize>) -> Self {
+        Self {
+            algorithm,
+            bits,
+            curve: None,
+        }
+    }
+
+    fn curve(algorithm: String, curve: &ObjectIdentifier) -> Self {
+        let (name, bits) = CURVES.iter()
+            .find(|(oid, _, _)| curve.components() == oid)
+            .map(|(_, name, bits)| (name.to_string(), Some(*bits)))
+            .unwrap_or_else(|| (curve.to_string(), None));
+
+        Self {
+            algorithm,
+            bits,
+            curve: Some(name),
+        }
+    }
+
+    /// Modulus size of a PKCS#1 public or private key, the private key starts with a version
+    fn rsa_bits(key: &[u8], private: bool) -> yasna::ASN1Result<usize> {
+        let modulus = yasna::parse_der(key, |r| r.read_sequence(|r| {
+            if private {
+                r.next().read_der()?;
+            }
+            let (modulus, _) = r.next().read_bigint_bytes()?;
+            skip_rest(r)?;
+            Ok(modulus)
+        }))?;
+
+        let modulus: Vec<u8> = modulus.into_iter().skip_while(|b| *b == 0).collect();
+        Ok(modulus.first().map(|b| (modulus.len() - 1) * 8 + (8 - b.leading_zeros() as usize)).unwrap_or_default())
+    }
+
+    /// `SEQUENCE { algorithm OID, parameters ANY OPTIONAL }`
+    fn algorithm_identifier(r: BERReader) -> yasna::ASN1Result<(ObjectIdentifier, Option<Vec<u8>>)> {
+        r.read_sequence(|r| {
+            let oid = r.next().read_oid()?;
+            let parameters = r.read_optional(|r| r.read_der())?;
+            Ok((oid, parameters))
+        })
+    }
+
+    /// PKCS#8 private key of any algorithm
+    fn pkcs8(der: &[u8]) -> yasna::ASN1Result<Self> {
+        yasna::parse_der(der, |r| r.read_sequence(|r| {
+            r.next().read_der()?; // version
+            let (algorithm, parameters) = Self::algorithm_identifier(r.next())?;
+            let key = r.next().read_bytes()?;
+            skip_rest(r)?;
+            Ok(Self::new(&algorithm, parameters.as_deref(), &key, true))
+        }))
+    }
+
+    /// SEC1 elliptic curve private key
+    fn sec1(der: &[u8]) -> yasna::ASN1Result<Self> {
+        yasna::parse_der(der, |r| r.read_sequence(|r| {
+            r.next().read_der()?; // version
+            r.next().read_bytes()?; // private key
+            let curve = r.read_optional(|r| r.read_tagged(Tag::context(0), |r| r.read_oid()))?;
+            skip_rest(r)?;
+            Ok(match curve {
+                Some(curve) => Self::curve("ec".into(), &curve),
+                None => Self::named("ec".into(), None),
+            })
+        }))
+    }
+
+    /// Subject public key info of a certificate
+    fn public(der: &[u8]) -> yasna::ASN1Result<Self> {
+        yasna::parse_der(der, |r| r.read_sequence(|r| {
+            let (algorithm, parameters) = Self::algorithm_identifier(r.next())?;
+            let (key, _) = r.next().read_bitvec_bytes()?;
+            Ok(Self::new(&algorithm, parameters.as_deref(), &key, false))
+        }))
+    }
+}
+
+/// Reads the optional elements at the end of a sequence
+fn skip_rest(r: &mut yasna::BERReaderSeq) -> yasna::ASN1Result<()> {
+    while r.read_optional(|r| r.read_der())?.is_some() {}
+    Ok(())
+}
+
+fn timestamp(time: SystemTime) -> usize {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as usize).unwrap_or_default()
+}
+
+/// not_before/not_after:   unix timestamps of the validity
+/// days_left:              whole days until `not_after`, negative if expired
+/// alternative_names:      like openssl e.g. `DNS:example.com`, `IP Address:10.0.0.1` or `email:ops@example.com`
+/// fingerprint:            sha256 of the DER encoding
+#[derive(Debug, PartialEq, Serialize, Description)]
+pub struct X509Certificate {
+    subject: String,
+    issuer: String,
+    serial: String,
+    alternative_names: Vec<String>,
+    not_before: usize,
+    not_after: usize,
+    days_left: isize,
+    fingerprint: String,
+    key: X509Key,
+}
+
+impl X509Certificate {
+    pub fn parse(der: &[u8], now: SystemTime) -> Resul<Self> {
+        let elements = certificate_elements(der)?;
+        let element = |i: usize| elements.get(i).map(|(_, e)| e.as_slice()).ok_or(Erro::CertificateInvalid);
+        let (not_before, not_after) = certificate_validity(der)?;
+        let (serial, _) = yasna::parse_der(element(0)?, |r| r.read_bigint_bytes())?;
+
+        let alternative_names = match elements.iter().find(|(tag, _)| *tag == Tag::context(3)) {
+            Some((_, extensions)) => Self::alternative_names(extensions)?,
+            None => vec![],
+        };
+
+        let days_left = match not_after.duration_since(now) {
+            Ok(d) => (d.as_secs() / 86400) as isize,
+            Err(e) => -((e.duration().as_secs() / 86400) as isize),
+        };
+
+        Ok(Self {
+            subject: Self::name(element(4)?)?,
+            issuer: Self::name(element(2)?)?,
+            serial: serial.iter().skip_while(|b| **b == 0).map(|b| format!("{:02X}", b)).collect(),
+            alternative_names,
+            not_before: timestamp(not_before),
+            not_after: timestamp(not_after),
+            days_left,
+            fingerprint: digest(&SHA256, der).as_ref().iter().map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(":"),
+            key: X509Key::public(element(5)?)?,
+        })
+    }
+
+    /// Distinguished name like `C=DE, O=Example, CN=example.com` in the encoded order
+    fn name(der: &[u8]) -> Resul<String> {
+        let attributes = yasna::parse_der(der, |r| r.collect_sequence_of(|r| r.collect_set_of(|r| r.read_sequence(|r| {
+            let oid = r.next().read_oid()?;
+            let value = r.next().read_tagged_der()?;
+            Ok((oid, value))
+        }))))?;
+
+        Ok(attributes.into_iter().flatten().map(|(oid, value)| {
+            let key = ATTRIBUTES.iter().find(|(o, _)| oid.components() == o).map(|(_, k)| k.to_string()).unwrap_or_else(|| oid.to_string());
+            format!("{}={}", key, value.as_str().unwrap_or_default())
+        }).collect::<Vec<String>>().join(", "))
+    }
+
+    fn alternative_names(extensions: &[u8]) -> Resul<Vec<String>> {
+        let extensions = yasna::parse_der(extensions, |r| r.read_tagged(Tag::context(3), |r| r.collect_sequence_of(|r| r.read_sequence(|r| {
+            let oid = r.next().read_oid()?;
+            r.read_optional(|r| r.read_bool())?; // critical
+            let value = r.next().read_bytes()?;
+            Ok((oid, value))
+        }))))?;
+
+        let mut names = vec![];
+        for (_, value) in extensions.iter().filter(|(oid, _)| oid.components() == &ALTERNATIVE_NAME) {
+            let alternative_names = yasna::parse_der(value, |r| r.collect_sequence_of(|r| r.read_tagged_der()))?;
+
+            names.extend(alternative_names.into_iter().filter_map(|name| {
+                let value = name.value();
+                match name.tag() {
+                    t if t == Tag::context(1) => Some(format!("email:{}", String::from_utf8_lossy(value))),
+                    t if t == Tag::context(2) => Some(format!("DNS:{}", String::from_utf8_lossy(value))),
+                    t if t == Tag::context(6) => Some(format!("URI:{}", String::from_utf8_lossy(value))),
+                    t if t == Tag::context(7) => match value.len() {
+                        4 => Some(format!("IP Address:{}", IpAddr::from(<[u8; 4]>::try_from(value).ok()?))),
+                        16 => Some(format!("IP Address:{}", IpAddr::from(<[u8; 16]>::try_from(value).ok()?))),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }));
+        }
+
+        Ok(names)
+    }
+}
+
+/// Certificates and private keys of a PEM file in their order, a DER file holds a single certificate
+#[derive(Debug, PartialEq, Serialize, Description)]
+pub struct X509 {
+    certificates: Vec<X509Certificate>,
+    keys: Vec<X509Key>,
+}
+
+impl X509 {
+    pub fn parse(content: &[u8], now: SystemTime) -> Resul<Self> {
+        let items = rustls_pemfile::read_all(&mut &content[..])?;
+        let mut x509 = Self {
+            certificates: vec![],
+            keys: vec![],
+        };
+
+        if items.is_empty() && content.first() == Some(&0x30) {
+            x509.certificates.push(X509Certificate::parse(content, now)?);
+        }
+
+        for item in items {
+            match item {
+                Item::X509Certificate(der) => x509.certificates.push(X509Certificate::parse(&der, now)?),
+                Item::RSAKey(der) => x509.keys.push(X509Key { bits: X509Key::rsa_bits(&der, true).ok(), ..X509Key::named("rsa".into(), None) }),
+                Item::PKCS8Key(der) => x509.keys.push(X509Key::pkcs8(&der)?),
+                Item::ECKey(der) => x509.keys.push(X509Key::sec1(&der)?),
+                _ => {}
+            }
+        }
+
+        Ok(x509)
+    }
+}
+
+pub struct X509File {
+    path: String,
+}
+
+#[async_trait]
+impl File for X509File {
+    type Output = X509;
+    type Input = ();
+
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+        }
+    }
+
+    async fn read(&self, system: &System) -> Resul<Self::Output> {
+        X509::parse(&system.read(self.path()).await?, SystemTime::now())
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[derive(Clone)]
+pub struct X509Builder;
+
+impl FileBuilder for X509Builder {
+    type File = X509File;
+
+    const NAME: &'static str = "x509";
+    const DESCRIPTION: &'static str = "Subject, issuer, alternative names, validity and fingerprint of certificates and the type and size of private keys, the key material is never returned. Write them with `?name=text`.";
+    const CAPABILITIES: &'static [Capability] = &[Capability::Read];
+
+    fn patterns(&self) -> &[FileMatchPattern] {
+        lazy_static! {
+            static ref PATTERN: [FileMatchPattern;1] = [FileMatchPattern::new_regex(Regex::new("^.*\\.(crt|cer|pem|key)$").unwrap(), &[Os::PosixAny])];
+        }
+
+        PATTERN.as_slice()
+    }
+
+    fn examples(&self) -> &[FileExample] {
+        lazy_static! {
+            static ref EAMPLES: [FileExample;1] = [
+                FileExample::new_get("Certificate and key of a web server",
+                    X509 {
+                        certificates: vec![X509Certificate {
+                            subject: "CN=web.example.com".into(),
+                            issuer: "C=US, O=Let's Encrypt, CN=R11".into(),
+                            serial: "03A1F4C2B87E1D6A5F0C9B2E4D7A8F1C3B5E".into(),
+                            alternative_names: vec!["DNS:web.example.com".into(), "DNS:www.example.com".into()],
+                            not_before: 1735689600,
+                            not_after: 1743465600,
+                            days_left: 42,
+                            fingerprint: "0D:4C:F3:C9:B3:08:5C:E9:89:65:BA:BD:88:25:98:CA:43:40:B3:43:C9:07:14:78:26:5C:3F:34:4B:FB:48:15".into(),
+                            key: X509Key { algorithm: "ec".into(), bits: Some(256), curve: Some("prime256v1".into()) },
+                        }],
+                        keys: vec![X509Key { algorithm: "ec".into(), bits: Some(256), curve: Some("prime256v1".into()) }],
+                    }
+                )
+            ];
+        }
+
+        EAMPLES.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, UNIX_EPOCH};
+    use rcgen::{date_time_ymd, Certificate, CertificateParams, DistinguishedName, DnType};
+    use crate::files::x509::{X509, X509Certificate, X509Key};
+    use crate::utils::test::read_test_resources;
+
+    #[test]
+    fn test_parse() {
+        let now = UNIX_EPOCH + Duration::from_secs(1767225600); // 2026-01-01
+        let x509 = X509::parse(read_test_resources("x509").as_bytes(), now).unwrap();
+
+        assert_eq!(x509.certificates, vec![X509Certificate {
+            subject: "C=DE, O=Example, CN=web.example.com".into(),
+            issuer: "C=DE, O=Example, CN=web.example.com".into(),
+            serial: "1A2B3C".into(),
+            alternative_names: vec![
+                "DNS:web.example.com".into(),
+                "DNS:www.example.com".into(),
+                "IP Address:10.0.0.11".into(),
+                "email:ops@example.com".into(),
+            ],
+            not_before: 1735689600,
+            not_after: 2051222400,
+            days_left: 3287,
+            fingerprint: "0D:4C:F3:C9:B3:08:5C:E9:89:65:BA:BD:88:25:98:CA:43:40:B3:43:C9:07:14:78:26:5C:3F:34:4B:FB:48:15".into(),
+            key: X509Key { algorithm: "rsa".into(), bits: Some(2048), curve: None },
+        }]);
+        assert_eq!(x509.keys, vec![
+            X509Key { algorithm: "rsa".into(), bits: Some(2048), curve: None },
+            X509Key { algorithm: "ec".into(), bits: Some(384), curve: Some("secp384r1".into()) },
+        ]);
+
+        let expired = X509::parse(read_test_resources("x509").as_bytes(), UNIX_EPOCH + Duration::from_secs(2051222400 + 86400 * 3)).unwrap();
+        assert_eq!(expired.certificates[0].days_left, -3);
+    }
+
+    #[test]
+    fn test_parse_generated() {
+        let mut params = CertificateParams::new(vec!["client.example.com".into()]);
+        params.distinguished_name = DistinguishedName::new();
+        params.distinguished_name.push(DnType::CommonName, "client");
+        params.not_after = date_time_ymd(2030, 1, 1);
+        let certificate = Certificate::from_params(params).unwrap();
+
+        let pem = format!("{}{}", certificate.serialize_pem().unwrap(), certificate.serialize_private_key_pem());
+        let x509 = X509::parse(pem.as_bytes(), UNIX_EPOCH).unwrap();
+        let p256 = X509Key { algorithm: "ec".into(), bits: Some(256), curve: Some("prime256v1".into()) };
+
+        assert_eq!(x509.certificates[0].subject, "CN=client");
+        assert_eq!(x509.certificates[0].alternative_names, vec!["DNS:client.example.com"]);
+        assert_eq!(x509.certificates[0].key, p256);
+        assert_eq!(x509.keys, vec![p256]);
+
+        let der = X509::parse(&certificate.serialize_der().unwrap(), UNIX_EPOCH).unwrap();
+        assert_eq!(der.certificates[0].not_after, 1893456000);
+        assert!(X509::parse(b"no certificate", UNIX_EPOCH).unwrap().certificates.is_empty());
+    }
+}
@@ -0,0 +1,620 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use crate::error::Resul;
+
+/// Rust keywords which can't be used as field or method names, some of them not even raw
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static", "struct",
+    "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final",
+    "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+const RUST_NOT_RAW: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Language of a generated client
+/// rust:   module using `boofi_core::client::Client`, serde and serde_json
+/// ts:     standalone typescript module using `fetch`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Rust,
+    Ts,
+}
+
+/// Help of an app or file module rendered as json schema, as served by `/apps?schema=true` and `/files?schema=true`
+#[derive(Debug, Deserialize)]
+struct Help {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    capabilities: Vec<String>,
+    #[serde(default)]
+    input: Value,
+    #[serde(default)]
+    output: Value,
+}
+
+/// Emits a typed client with one method per app and read and write methods per file module.
+/// Types are named after the schema titles, differing schemas with the same title get a number.
+pub fn generate_client(language: Language, apps: &[Value], files: &[Value]) -> Resul<String> {
+    let apps = apps.iter().cloned().map(serde_json::from_value).collect::<Result<Vec<Help>, _>>()?;
+    let files = files.iter().cloned().map(serde_json::from_value).collect::<Result<Vec<Help>, _>>()?;
+
+    let mut generator = Generator {
+        language,
+        types: vec![],
+    };
+    let mut methods = vec![];
+
+    for app in apps.iter() {
+        let name = pascal(&app.name);
+        let input = generator.input(&app.input, &format!("{}Input", name));
+        let output = generator.r#type(&app.output, &format!("{}Output", name));
+        methods.push(generator.app_method(app, input, &output));
+    }
+
+    for file in files.iter() {
+        let name = pascal(&file.name);
+        let output = generator.r#type(&file.output, &format!("{}Output", name));
+        methods.push(generator.read_method(file, &output));
+
+        if file.capabilities.iter().any(|c| c == "Write") {
+            let input = generator.input(&file.input, &format!("{}Input", name))
+                .unwrap_or_else(|| generator.unknown());
+            methods.push(generator.write_method(file, &input));
+        }
+    }
+
+    Ok(generator.module(apps.len(), files.len(), &methods))
+}
+
+/// Named types in order of their first use with the schema they were generated from
+struct Generator {
+    language: Language,
+    types: Vec<(String, Value, String)>,
+}
+
+impl Generator {
+    /// Type of an input, none for `()` which is never read
+    fn input(&mut self, schema: &Value, name: &str) -> Option<String> {
+        match schema.get("type") {
+            Some(Value::String(t)) if t == "null" => None,
+            _ => Some(self.r#type(schema, name)),
+        }
+    }
+
+    /// Type expression of a schema, objects and enums are added as named types
+    fn r#type(&mut self, schema: &Value, name: &str) -> String {
+        let object = match schema.as_object() {
+            Some(object) => object,
+            None => return self.unknown(),
+        };
+
+        if let Some(variants) = object.get("anyOf").and_then(Value::as_array) {
+            let types = variants.iter().filter(|v| !is_null(v)).collect::<Vec<&Value>>();
+            return match types[..] {
+                [inner] if types.len() < variants.len() => {
+                    let inner = self.r#type(inner, name);
+                    self.optional(&inner)
+                }
+                _ => self.unknown(),
+            };
+        }
+
+        if let Some(variants) = object.get("oneOf").and_then(Value::as_array) {
+            return match variants.iter().map(variant).collect::<Option<Vec<(&str, Option<&Value>)>>>() {
+                Some(variants) => self.named(schema, name, |generator, name| generator.r#enum(name, &variants)),
+                None => self.unknown(),
+            };
+        }
+
+        let kind = match object.get("type") {
+            Some(Value::String(kind)) => kind.as_str(),
+            Some(Value::Array(kinds)) => {
+                let types = kinds.iter().filter(|k| k.as_str() != Some("null")).collect::<Vec<&Value>>();
+                return match types[..] {
+                    [inner] if types.len() < kinds.len() => {
+                        let mut inner_schema = object.clone();
+                        inner_schema.insert("type".into(), inner.clone());
+                        let inner = self.r#type(&Value::Object(inner_schema), name);
+                        self.optional(&inner)
+                    }
+                    _ => self.unknown(),
+                };
+            }
+            _ => return self.unknown(),
+        };
+
+        match kind {
+            "null" => self.scalar("null"),
+            "boolean" | "integer" | "number" | "string" => self.scalar(kind),
+            "array" => {
+                let item = match object.get("items") {
+                    Some(items) => self.r#type(items, &format!("{}Item", name)),
+                    None => self.unknown(),
+                };
+                self.array(&item)
+            }
+            "object" => {
+                let additional = object.get("additionalProperties").filter(|a| a.is_object());
+                match (object.get("properties").and_then(Value::as_object), additional) {
+                    (Some(properties), _) => {
+                        let required = object.get("required").and_then(Value::as_array).cloned().unwrap_or_default();
+                        self.named(schema, name, |generator, name| generator.r#struct(name, properties, &required, additional))
+                    }
+                    (None, Some(additional)) => {
+                        let value = self.r#type(additional, &format!("{}Value", name));
+                        self.map(&value)
+                    }
+                    (None, None) => {
+                        let value = self.unknown();
+                        self.map(&value)
+                    }
+                }
+            }
+            _ => self.unknown(),
+        }
+    }
+
+    /// Reuses the type generated from the same schema or defines a new one with a free name
+    fn named<F: FnOnce(&mut Self, &str) -> String>(&mut self, schema: &Value, name: &str, define: F) -> String {
+        let key = type_key(schema);
+        let base = pascal(schema.get("title").and_then(Value::as_str).unwrap_or(name));
+
+        let mut name = base.clone();
+        for number in 2.. {
+            match self.types.iter().find(|(n, _, _)| n == &name) {
+                Some((_, existing, _)) if existing == &key => return name,
+                Some(_) => name = format!("{}{}", base, number),
+                None => break,
+            }
+        }
+
+        // reserved before the fields are resolved so the type precedes the ones it uses
+        let index = self.types.len();
+        self.types.push((name.clone(), key, String::new()));
+        self.types[index].2 = define(self, &name);
+        name
+    }
+
+    fn unknown(&self) -> String {
+        match self.language {
+            Language::Rust => "Value",
+            Language::Ts => "unknown",
+        }.into()
+    }
+
+    fn scalar(&self, kind: &str) -> String {
+        match (self.language, kind) {
+            (Language::Rust, "null") => "()",
+            (Language::Rust, "boolean") => "bool",
+            (Language::Rust, "integer") => "i64",
+            (Language::Rust, "number") => "f64",
+            (Language::Rust, _) => "String",
+            (Language::Ts, "null") => "null",
+            (Language::Ts, "boolean") => "boolean",
+            (Language::Ts, "integer" | "number") => "number",
+            (Language::Ts, _) => "string",
+        }.into()
+    }
+
+    fn optional(&self, inner: &str) -> String {
+        match self.language {
+            Language::Rust => format!("Option<{}>", inner),
+            Language::Ts => format!("{} | null", inner),
+        }
+    }
+
+    fn array(&self, item: &str) -> String {
+        match self.language {
+            Language::Rust => format!("Vec<{}>", item),
+            Language::Ts if item.contains(' ') => format!("({})[]", item),
+            Language::Ts => format!("{}[]", item),
+        }
+    }
+
+    fn map(&self, value: &str) -> String {
+        match self.language {
+            Language::Rust => format!("HashMap<String, {}>", value),
+            Language::Ts => format!("Record<string, {}>", value),
+        }
+    }
+
+    fn r#struct(&mut self, name: &str, properties: &Map<String, Value>, required: &[Value], additional: Option<&Value>) -> String {
+        let mut fields = vec![];
+
+        for (property, schema) in properties.iter() {
+            let r#type = self.r#type(schema, &format!("{}{}", name, pascal(property)));
+            let required = required.iter().any(|r| r.as_str() == Some(property));
+            let description = schema.get("description").and_then(Value::as_str).unwrap_or_default();
+
+            fields.push(match self.language {
+                Language::Rust => {
+                    let mut attributes = vec![];
+                    let field = rust_identifier(&snake(property));
+                    if field.trim_start_matches("r#") != property {
+                        attributes.push(format!("rename = \"{}\"", property));
+                    }
+
+                    let r#type = if required || r#type.starts_with("Option<") {
+                        r#type
+                    } else {
+                        format!("Option<{}>", r#type)
+                    };
+                    if !required {
+                        attributes.push("default, skip_serializing_if = \"Option::is_none\"".into());
+                    }
+
+                    let attributes = if attributes.is_empty() {
+                        String::new()
+                    } else {
+                        format!("    #[serde({})]\n", attributes.join(", "))
+                    };
+                    format!("{}{}    pub {}: {},\n", rust_doc(description, "    "), attributes, field, r#type)
+                }
+                Language::Ts => format!("{}  {}{}: {};\n", ts_doc(description, "  "), ts_property(property),
+                                        if required { "" } else { "?" }, r#type),
+            });
+        }
+
+        if let Some(additional) = additional {
+            let value = self.r#type(additional, &format!("{}Value", name));
+            fields.push(match self.language {
+                Language::Rust => format!("    #[serde(flatten)]\n    pub other: HashMap<String, {}>,\n", value),
+                Language::Ts => "  [key: string]: unknown;\n".into(),
+            });
+        }
+
+        match self.language {
+            Language::Rust => format!("#[derive(Debug, Clone, Serialize, Deserialize)]\npub struct {} {{\n{}}}\n", name, fields.concat()),
+            Language::Ts => format!("export interface {} {{\n{}}}\n", name, fields.concat()),
+        }
+    }
+
+    /// Externally tagged like serde: unit variants are strings, the others objects with the variant as only key
+    fn r#enum(&mut self, name: &str, variants: &[(&str, Option<&Value>)]) -> String {
+        let mut lines = vec![];
+
+        for (variant, content) in variants.iter() {
+            let content = content.map(|c| self.r#type(c, &format!("{}{}", name, pascal(variant))));
+
+            lines.push(match self.language {
+                Language::Rust => {
+                    let identifier = pascal(variant);
+                    let rename = if identifier != *variant {
+                        format!("    #[serde(rename = \"{}\")]\n", variant)
+                    } else {
+                        String::new()
+                    };
+                    match content {
+                        Some(content) => format!("{}    {}({}),\n", rename, identifier, content),
+                        None => format!("{}    {},\n", rename, identifier),
+                    }
+                }
+                Language::Ts => match content {
+                    Some(content) => format!("  | {{ {}: {} }}\n", ts_property(variant), content),
+                    None => format!("  | {}\n", Value::from(*variant)),
+                },
+            });
+        }
+
+        match self.language {
+            Language::Rust => format!("#[derive(Debug, Clone, Serialize, Deserialize)]\npub enum {} {{\n{}}}\n", name, lines.concat()),
+            Language::Ts => format!("export type {} =\n{};\n", name, lines.concat().trim_end()),
+        }
+    }
+
+    fn app_method(&self, app: &Help, input: Option<String>, output: &str) -> String {
+        match self.language {
+            Language::Rust => {
+                let (parameter, value) = match &input {
+                    Some(input) => (format!(", input: &{}", input), "&to_value(input)?"),
+                    None => (String::new(), "&Value::Null"),
+                };
+                format!("{}    pub async fn {}(&self{}) -> Resul<{}> {{\n        \
+                         Ok(from_value(self.client.run_app(\"{}\", {}, false, self.sudo).await?)?)\n    }}\n",
+                        rust_doc(&app.description, "    "), rust_identifier(&snake(&app.name)), parameter, output,
+                        app.name, value)
+            }
+            Language::Ts => {
+                let (parameter, value) = match &input {
+                    Some(input) => (format!("input: {}", input), "input"),
+                    None => (String::new(), "null"),
+                };
+                format!("{}  async {}({}): Promise<{}> {{\n    \
+                         return this.request(\"POST\", `/apps/${{encodeURIComponent({})}}`, {{}}, {});\n  }}\n",
+                        ts_doc(&app.description, "  "), camel(&app.name), parameter, output,
+                        Value::from(app.name.as_str()), value)
+            }
+        }
+    }
+
+    fn read_method(&self, file: &Help, output: &str) -> String {
+        match self.language {
+            Language::Rust => format!("{}    pub async fn read_{}(&self, path: &str) -> Resul<{}> {{\n        \
+                                       Ok(from_value(self.client.read_file(path, Some(\"{}\"), false, self.sudo).await?)?)\n    }}\n",
+                                      rust_doc(&file.description, "    "), snake(&file.name), output, file.name),
+            Language::Ts => format!("{}  async read{}(path: string): Promise<{}> {{\n    \
+                                     return this.request(\"GET\", filePath(path), {{ name: {} }});\n  }}\n",
+                                    ts_doc(&file.description, "  "), pascal(&file.name), output, Value::from(file.name.as_str())),
+        }
+    }
+
+    fn write_method(&self, file: &Help, input: &str) -> String {
+        match self.language {
+            Language::Rust => format!("{}    pub async fn write_{}(&self, path: &str, input: &{}) -> Resul<Value> {{\n        \
+                                       self.client.write_file(path, Some(\"{}\"), &to_value(input)?, self.sudo).await\n    }}\n",
+                                      rust_doc(&file.description, "    "), snake(&file.name), input, file.name),
+            Language::Ts => format!("{}  async write{}(path: string, input: {}): Promise<unknown> {{\n    \
+                                     return this.request(\"POST\", filePath(path), {{ name: {} }}, input);\n  }}\n",
+                                    ts_doc(&file.description, "  "), pascal(&file.name), input, Value::from(file.name.as_str())),
+        }
+    }
+
+    fn module(&self, apps: usize, files: usize, methods: &[String]) -> String {
+        let header = format!("Generated by `boofi generate-client` from the help of {} apps and {} file modules, do not edit.", apps, files);
+        let types = self.types.iter().map(|(_, _, code)| code.as_str()).collect::<Vec<&str>>().join("\n");
+        let methods = methods.join("\n");
+
+        match self.language {
+            Language::Rust => format!(r#"//! {header}
+
+#![allow(dead_code, unused_imports, clippy::all)]
+
+use std::collections::HashMap;
+use boofi_core::client::Client;
+use boofi_core::error::Resul;
+use serde::{{Deserialize, Serialize}};
+use serde_json::{{from_value, to_value, Value}};
+
+{types}
+/// Typed apps and file modules of a boofi service
+pub struct BoofiClient {{
+    pub client: Client,
+    pub sudo: bool,
+}}
+
+impl BoofiClient {{
+    pub fn new(client: Client) -> Self {{
+        Self {{
+            client,
+            sudo: false,
+        }}
+    }}
+
+{methods}}}
+"#),
+            Language::Ts => format!(r#"// {header}
+
+{types}
+function filePath(path: string): string {{
+  return "/files/" + path.replace(/^\/+/, "").split("/").map(encodeURIComponent).join("/");
+}}
+
+/** Typed apps and file modules of a boofi service */
+export class BoofiClient {{
+  constructor(private url: string, private service: string, private token?: string, public sudo = false) {{}}
+
+  private async request<T>(method: string, path: string, query: Record<string, string>, body?: unknown): Promise<T> {{
+    const params = new URLSearchParams(query);
+    if (this.sudo) {{
+      params.set("sudo", "true");
+    }}
+    const headers: Record<string, string> = {{ "Content-Type": "application/json" }};
+    if (this.token) {{
+      headers["Authorization"] = `Bearer ${{this.token}}`;
+    }}
+    const response = await fetch(`${{this.url}}/${{encodeURIComponent(this.service)}}${{path}}?${{params}}`, {{
+      method,
+      headers,
+      body: body === undefined ? undefined : JSON.stringify(body),
+    }});
+    const text = await response.text();
+    if (!response.ok) {{
+      throw new Error(`${{response.status}}: ${{text}}`);
+    }}
+    return (text ? JSON.parse(text) : null) as T;
+  }}
+
+{methods}}}
+"#),
+        }
+    }
+}
+
+fn is_null(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("null")
+}
+
+/// Name and content schema of an externally tagged variant, none if the schema is no variant
+fn variant(schema: &Value) -> Option<(&str, Option<&Value>)> {
+    if let Some(name) = schema.get("const") {
+        return Some((name.as_str()?, None));
+    }
+
+    match schema.get("properties").and_then(Value::as_object) {
+        Some(properties) if properties.len() == 1 => properties.iter().next().map(|(n, c)| (n.as_str(), Some(c))),
+        _ => None,
+    }
+}
+
+/// Schema without the parts describing where it is used
+fn type_key(schema: &Value) -> Value {
+    match schema {
+        Value::Object(object) => Value::Object(object.iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "description" | "default" | "$schema"))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()),
+        _ => json!({}),
+    }
+}
+
+fn words(name: &str) -> Vec<String> {
+    let mut words: Vec<String> = vec![];
+    let mut previous = None;
+
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            previous = None;
+            continue;
+        }
+
+        let boundary = match previous {
+            None => true,
+            Some(p) => c.is_ascii_uppercase() && (p as char).is_ascii_lowercase(),
+        };
+        if boundary {
+            words.push(String::new());
+        }
+        words.last_mut().unwrap().push(c);
+        previous = Some(c as u8);
+    }
+
+    words
+}
+
+fn pascal(name: &str) -> String {
+    let name = words(name).iter()
+        .map(|w| w[..1].to_ascii_uppercase() + &w[1..])
+        .collect::<String>();
+    if name.starts_with(|c: char| c.is_ascii_digit()) { format!("_{}", name) } else { name }
+}
+
+fn camel(name: &str) -> String {
+    let mut name = pascal(name);
+    if let Some(first) = name.get_mut(..1) {
+        first.make_ascii_lowercase();
+    }
+    name
+}
+
+fn snake(name: &str) -> String {
+    let name = words(name).iter()
+        .map(|w| w.to_ascii_lowercase())
+        .collect::<Vec<String>>()
+        .join("_");
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) { format!("_{}", name) } else { name }
+}
+
+fn rust_identifier(name: &str) -> String {
+    if RUST_NOT_RAW.contains(&name) {
+        format!("{}_", name)
+    } else if RUST_KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.into()
+    }
+}
+
+fn ts_property(name: &str) -> String {
+    let identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$') &&
+        name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if identifier { name.into() } else { Value::from(name).to_string() }
+}
+
+fn rust_doc(text: &str, indent: &str) -> String {
+    text.lines().map(|line| format!("{}/// {}\n", indent, line).replace("/// \n", "///\n")).collect()
+}
+
+fn ts_doc(text: &str, indent: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    format!("{}/** {} */\n", indent, text.replace("*/", "*\\/").lines().collect::<Vec<&str>>().join(" "))
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::generate::{generate_client, snake, Language, pascal};
+
+    fn help() -> (Vec<serde_json::Value>, Vec<serde_json::Value>) {
+        let item = |description: &str| json!({
+            "title": "Item",
+            "type": "object",
+            "properties": {"value": {"type": "string"}},
+            "required": ["value"],
+            "description": description,
+        });
+
+        let apps = vec![json!({
+            "name": "systemctl",
+            "description": "Control systemd units",
+            "input": {
+                "title": "SystemctlInput",
+                "type": "object",
+                "properties": {
+                    "action": {"title": "SystemctlAction", "oneOf": [{"const": "start"}, {"const": "is_active"}]},
+                    "unit": {"type": "string"},
+                    "force": {"anyOf": [{"type": "boolean"}, {"type": "null"}]},
+                },
+                "required": ["action", "unit"],
+            },
+            "output": {"type": "array", "items": {"type": "object", "properties": {"type": {"type": ["string", "null"]}}}},
+        })];
+        let files = vec![json!({
+            "name": "os-release",
+            "description": "Operating system identification",
+            "capabilities": ["Read", "Write"],
+            "input": {"type": "null"},
+            "output": {
+                "title": "OsRelease",
+                "type": "object",
+                "properties": {
+                    "first": item("first item"),
+                    "lines": {"type": "array", "items": {"title": "Line", "oneOf": [
+                        {"type": "object", "properties": {"Comment": {"type": "string"}}, "required": ["Comment"], "additionalProperties": false},
+                        {"type": "object", "properties": {"Entry": item("")}, "required": ["Entry"], "additionalProperties": false},
+                    ]}},
+                    "other": {"title": "Item", "type": "object", "properties": {"value": {"type": "integer"}}, "required": ["value"]},
+                },
+                "required": ["first", "lines", "other"],
+                "additionalProperties": {},
+            },
+        })];
+
+        (apps, files)
+    }
+
+    #[test]
+    fn rust() {
+        let (apps, files) = help();
+        let client = generate_client(Language::Rust, &apps, &files).unwrap();
+
+        assert!(client.contains("pub enum SystemctlAction {\n    #[serde(rename = \"start\")]\n    Start,\n    #[serde(rename = \"is_active\")]\n    IsActive,\n}"));
+        assert!(client.contains("    pub action: SystemctlAction,\n    pub unit: String,\n    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub force: Option<bool>,\n"));
+        assert!(client.contains("pub struct SystemctlOutputItem {\n    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub r#type: Option<String>,\n}"));
+        assert!(client.contains("pub enum Line {\n    Comment(String),\n    Entry(Item),\n}"));
+        assert!(client.contains("pub struct Item2 {\n    pub value: i64,\n}"));
+        assert!(client.contains("    #[serde(flatten)]\n    pub other: HashMap<String, Value>,\n"));
+        assert!(client.contains("pub async fn systemctl(&self, input: &SystemctlInput) -> Resul<Vec<SystemctlOutputItem>>"));
+        assert!(client.contains("pub async fn read_os_release(&self, path: &str) -> Resul<OsRelease>"));
+        assert!(client.contains("pub async fn write_os_release(&self, path: &str, input: &Value) -> Resul<Value>"));
+        assert_eq!(client.matches("pub struct Item ").count(), 1);
+    }
+
+    #[test]
+    fn ts() {
+        let (apps, files) = help();
+        let client = generate_client(Language::Ts, &apps, &files).unwrap();
+
+        assert!(client.contains("export type SystemctlAction =\n  | \"start\"\n  | \"is_active\";"));
+        assert!(client.contains("export interface SystemctlInput {\n  action: SystemctlAction;\n  unit: string;\n  force?: boolean | null;\n}"));
+        assert!(client.contains("export type Line =\n  | { Comment: string }\n  | { Entry: Item };"));
+        assert!(client.contains("  lines: Line[];\n"));
+        assert!(client.contains("async systemctl(input: SystemctlInput): Promise<SystemctlOutputItem[]>"));
+        assert!(client.contains("async readOsRelease(path: string): Promise<OsRelease>"));
+        assert!(client.contains("return this.request(\"POST\", filePath(path), { name: \"os-release\" }, input);"));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(pascal("prohibit-password"), "ProhibitPassword");
+        assert_eq!(pascal("PermitRootLogin"), "PermitRootLogin");
+        assert_eq!(snake("PermitRootLogin"), "permit_root_login");
+        assert_eq!(snake("os-release"), "os_release");
+        assert_eq!(snake("ID_LIKE"), "id_like");
+        assert_eq!(snake("3des"), "_3des");
+    }
+}
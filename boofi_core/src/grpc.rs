@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::RwLock;
+use tonic::{Code, Request, Response, Status};
+use tonic::transport::{Identity, Server, ServerTlsConfig};
+use crate::controller::Permission;
+use crate::error::{Erro, Resul};
+use crate::hook;
+use crate::rest::{authenticate, Rest, SharedController, TokenResult, UsernamePassword};
+use crate::system::{RunLimits, System};
+use crate::task::TaskFilter;
+use proto::boofi_server::{Boofi, BoofiServer};
+use proto::{AuthenticateRequest, AuthenticateResponse, ListTasksRequest, ListTasksResponse, ReadFileRequest,
+            ReadFileResponse, RunAppRequest, RunAppResponse, WriteFileRequest, WriteFileResponse};
+
+/// Generated from `proto/boofi.proto`
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("boofi");
+}
+
+/// Optional grpc api next to the rest api
+/// listen: address of the grpc server, it uses the ssl certificate of the rest api.
+///         Without ssl only loopback addresses are allowed, credentials would cross the network in plain text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    pub listen: String,
+}
+
+impl From<Erro> for Status {
+    /// Same classification as the status codes of the rest api
+    fn from(error: Erro) -> Self {
+        let message = error.to_string();
+        if matches!(error, Erro::RestAuthMissing) {
+            return Status::unauthenticated(message);
+        }
+
+        let code = match error.into_response().status() {
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Code::InvalidArgument,
+            StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+            StatusCode::FORBIDDEN => Code::PermissionDenied,
+            StatusCode::NOT_FOUND => Code::NotFound,
+            StatusCode::CONFLICT => Code::Aborted,
+            StatusCode::PRECONDITION_FAILED => Code::FailedPrecondition,
+            StatusCode::PAYLOAD_TOO_LARGE => Code::ResourceExhausted,
+            StatusCode::GATEWAY_TIMEOUT => Code::DeadlineExceeded,
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => Code::Unimplemented,
+            _ => Code::Internal,
+        };
+
+        Status::new(code, message)
+    }
+}
+
+/// Serves authentication, apps, files and tasks of all services over grpc
+#[derive(Clone)]
+pub struct Grpc {
+    address: SocketAddr,
+    services: Arc<RwLock<HashMap<String, SharedController>>>,
+}
+
+impl Grpc {
+    pub fn new(address: SocketAddr) -> Self {
+        Self {
+            address,
+            services: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Replace the served services, used on start and reload
+    pub async fn set_services(&self, services: HashMap<String, SharedController>) {
+        *self.services.write().await = services;
+    }
+
+    /// Starts the plain text server, only on a loopback address
+    pub async fn start(&self) -> Resul<()> {
+        if !self.address.ip().is_loopback() {
+            return Err(Erro::GrpcPlainText(self.address.to_string()));
+        }
+
+        tracing::info!("[GRPC] listening on {}", self.address);
+        Server::builder()
+            .add_service(BoofiServer::new(self.clone()))
+            .serve(self.address)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Starts the server with the certificate of the rest api, a renewed certificate is used after a restart
+    pub async fn ssl(&self, private_key: &str, certificate: &str) -> Resul<()> {
+        tracing::info!("[GRPC SSL] listening on {}", self.address);
+        Server::builder()
+            .tls_config(ServerTlsConfig::new().identity(Identity::from_pem(certificate, private_key)))?
+            .add_service(BoofiServer::new(self.clone()))
+            .serve(self.address)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn controller(&self, service: &str) -> Resul<SharedController> {
+        self.services.read().await.get(service).cloned().ok_or_else(|| Erro::GrpcServiceNotFound(service.into()))
+    }
+
+    /// Authenticated user from the `authorization` metadata
+    async fn user<T>(controller: &SharedController, request: &Request<T>) -> Resul<UsernamePassword> {
+        let authorization = request.metadata().get("authorization")
+            .ok_or(Erro::RestAuthMissing)?
+            .to_str()
+            .map_err(|_| Erro::RestAuthInvalid)?;
+
+        Ok(authenticate(controller, authorization).await?.0)
+    }
+
+    /// System of the user with sudo or run_as and run limits applied
+    async fn system(controller: &SharedController,
+                    user_password: &UsernamePassword,
+                    sudo: bool,
+                    run_as: Option<&str>,
+                    limits: RunLimits) -> Resul<System> {
+        let system_manager = controller.lock().await.system_manager();
+        let mut system = system_manager.system_credential(user_password.into()).await?;
+        Rest::switch_user(&*controller.lock().await, &user_password.username, &mut system, Some(sudo), run_as)?;
+        Rest::record_shell(&*controller.lock().await, &user_password.username, &mut system, Some(sudo), run_as);
+        system.restrict_limits(&limits)?;
+        Ok(system)
+    }
+
+    fn json(input: &str) -> Resul<Value> {
+        if input.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        serde_json::from_str(input).map_err(Erro::from_deserialize)
+    }
+
+    async fn run_app(&self, request: Request<RunAppRequest>) -> Resul<RunAppResponse> {
+        let controller = self.controller(&request.get_ref().service).await?;
+        let user_password = Self::user(&controller, &request).await?;
+        let request = request.into_inner();
+        controller.lock().await.authorization().check(&user_password.username, Permission::RunApps)?;
+
+        let limits = RunLimits::new(request.timeout, request.max_output.map(|m| m as usize));
+        let system = Self::system(&controller, &user_password, request.sudo, request.run_as.as_deref(), limits).await?;
+
+        let mut app = controller.lock().await.app(&request.name).cloned().ok_or(Erro::AppNotFound)?;
+        if !app.compatible(system.os()?) {
+            return Err(Erro::AppIncompatible);
+        }
+
+        let input = Self::json(&request.input_json)?;
+        app.validate(&input)?;
+
+        tracing::debug!("[GRPC] running app {}", request.name);
+        let output = app.run(input, &system).await?;
+        Ok(RunAppResponse { output_json: serde_json::to_string(&output)? })
+    }
+
+    async fn read_file(&self, request: Request<ReadFileRequest>) -> Resul<ReadFileResponse> {
+        let controller = self.controller(&request.get_ref().service).await?;
+        let user_password = Self::user(&controller, &request).await?;
+        let request = request.into_inner();
+        controller.lock().await.authorization().check(&user_password.username, Permission::ReadFiles)?;
+
+        let system = Self::system(&controller, &user_password, request.sudo, request.run_as.as_deref(), RunLimits::default()).await?;
+        let files = controller.lock().await.file_registry();
+        let file = match request.name.as_deref() {
+            Some(name) => files.by_name(name)?,
+            None => files.by_match(&request.path, &system)?,
+        };
+
+        tracing::debug!("[GRPC] reading file {}", request.path);
+        let content = if request.redact {
+            file.read_redacted(&request.path, &system).await?
+        } else {
+            file.read(&request.path, &system).await?
+        };
+        Ok(ReadFileResponse { content_json: serde_json::to_string(&content)? })
+    }
+
+    async fn write_file(&self, request: Request<WriteFileRequest>) -> Resul<WriteFileResponse> {
+        let controller = self.controller(&request.get_ref().service).await?;
+        let user_password = Self::user(&controller, &request).await?;
+        let request = request.into_inner();
+        controller.lock().await.authorization().check(&user_password.username, Permission::WriteFiles)?;
+
+        let system = Self::system(&controller, &user_password, request.sudo, request.run_as.as_deref(), RunLimits::default()).await?;
+        let (files, backup, hooks, locks) = {
+            let ctrl = controller.lock().await;
+            (ctrl.file_registry(), ctrl.backup().cloned(), ctrl.file_hooks().to_vec(), ctrl.file_locks())
+        };
+        let file = match request.name.as_deref() {
+            Some(name) => files.by_name(name)?,
+            None => files.by_match(&request.path, &system)?,
+        };
+
+        let content = Self::json(&request.content_json)?;
+        file.validate(&content)?;
+
+        tracing::debug!("[GRPC] writing file {}", request.path);
+        let _lock = locks.lock(&request.path).await?;
+        Rest::backup(backup.as_ref(), &request.path, &system).await?;
+        file.write(&request.path, content, &system).await?;
+
+        let outputs = hook::run_hooks(&hooks, file.name(), &request.path, &system).await;
+        Ok(WriteFileResponse { hooks_json: serde_json::to_string(&outputs)? })
+    }
+
+    async fn list_tasks(&self, request: Request<ListTasksRequest>) -> Resul<ListTasksResponse> {
+        let controller = self.controller(&request.get_ref().service).await?;
+        let user_password = Self::user(&controller, &request).await?;
+        controller.lock().await.authorization().check(&user_password.username, Permission::ManageTasks)?;
+
+        let system_manager = controller.lock().await.system_manager();
+        system_manager.system_credential((&user_password).into()).await?.verify_credential().await?;
+
+        let tasks = controller.lock().await.task_controller().list(&TaskFilter::default()).await?;
+        Ok(ListTasksResponse {
+            tasks_json: tasks.iter().map(serde_json::to_string).collect::<Result<Vec<String>, _>>()?,
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl Boofi for Grpc {
+    async fn authenticate(&self, request: Request<AuthenticateRequest>) -> Result<Response<AuthenticateResponse>, Status> {
+        let controller = self.controller(&request.get_ref().service).await?;
+        let user_password = Self::user(&controller, &request).await?;
+
+        let system_manager = controller.lock().await.system_manager();
+        system_manager.system_credential((&user_password).into()).await?.verify_credential().await?;
+
+        let mut ctrl = controller.lock().await;
+        let token = TokenResult::issue(ctrl.auth_mut(), &user_password.username, &user_password.password, request.into_inner().client)?;
+
+        tracing::debug!("[GRPC] token created");
+        Ok(Response::new(AuthenticateResponse { token: token.token, expires_at: token.expires_at }))
+    }
+
+    async fn run_app(&self, request: Request<RunAppRequest>) -> Result<Response<RunAppResponse>, Status> {
+        Ok(Response::new(Grpc::run_app(self, request).await?))
+    }
+
+    async fn read_file(&self, request: Request<ReadFileRequest>) -> Result<Response<ReadFileResponse>, Status> {
+        Ok(Response::new(Grpc::read_file(self, request).await?))
+    }
+
+    async fn write_file(&self, request: Request<WriteFileRequest>) -> Result<Response<WriteFileResponse>, Status> {
+        Ok(Response::new(Grpc::write_file(self, request).await?))
+    }
+
+    async fn list_tasks(&self, request: Request<ListTasksRequest>) -> Result<Response<ListTasksResponse>, Status> {
+        Ok(Response::new(Grpc::list_tasks(self, request).await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::{Code, Status};
+    use crate::error::Erro;
+
+    #[test]
+    fn status() {
+        assert_eq!(Status::from(Erro::AppNotFound).code(), Code::NotFound);
+        assert_eq!(Status::from(Erro::RestAuthMissing).code(), Code::Unauthenticated);
+    }
+}
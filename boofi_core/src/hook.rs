@@ -0,0 +1,90 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::secret;
+use crate::system::System;
+
+/// Commands run on the target after a file was changed through the api, e.g. to reload a service.
+/// file:   name of the file module managing the file, any module if missing
+/// path:   regex matching the changed path, any path if missing
+/// run:    programs with their arguments, `{path}` is replaced by the changed path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileHook {
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default, with = "serde_regex")]
+    path: Option<Regex>,
+    run: Vec<Vec<String>>,
+}
+
+/// Result of one hook command, either its standard output or the error
+#[derive(Debug, Serialize)]
+pub struct HookOutput {
+    program: String,
+    arguments: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl FileHook {
+    fn r#match(&self, file: &str, path: &str) -> bool {
+        self.file.as_deref().map(|f| f == file).unwrap_or(true) &&
+            self.path.as_ref().map(|p| p.is_match(path)).unwrap_or(true)
+    }
+
+    /// Runs the commands in order, a failed command skips the rest of this hook
+    async fn run(&self, path: &str, system: &System, outputs: &mut Vec<HookOutput>) {
+        for command in self.run.iter().filter(|c| !c.is_empty()) {
+            let program = command[0].clone();
+            let arguments: Vec<String> = command[1..].iter().map(|a| a.replace("{path}", path)).collect();
+
+            tracing::debug!("[HOOK] running {} after {} changed", secret::command_line(&program, &arguments), path);
+            let result = system.run_args(&program, &arguments).await;
+            let failed = result.is_err();
+
+            outputs.push(HookOutput {
+                program,
+                arguments,
+                output: result.as_ref().ok().map(|o| String::from_utf8_lossy(o).into_owned()),
+                error: result.err().map(|e| e.to_string()),
+            });
+
+            if failed {
+                tracing::warn!("[HOOK] command failed after {} changed, skipping the rest of the hook", path);
+                break;
+            }
+        }
+    }
+}
+
+/// Runs all hooks matching the file module and path, in configuration order
+pub async fn run_hooks(hooks: &[FileHook], file: &str, path: &str, system: &System) -> Vec<HookOutput> {
+    let mut outputs = vec![];
+
+    for hook in hooks.iter().filter(|h| h.r#match(file, path)) {
+        hook.run(path, system, &mut outputs).await;
+    }
+
+    outputs
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hook::FileHook;
+
+    #[test]
+    fn r#match() {
+        let hooks: Vec<FileHook> = serde_yaml::from_str(r#"
+- file: systemd_unit
+  run: [["/usr/bin/systemctl", "daemon-reload"]]
+- path: '^/etc/sysctl\.(conf|d/.*)$'
+  run: [["/sbin/sysctl", "-p", "{path}"]]
+"#).unwrap();
+
+        assert!(hooks[0].r#match("systemd_unit", "/etc/systemd/system/boofi.service"));
+        assert!(!hooks[0].r#match("text", "/etc/systemd/system/boofi.service"));
+        assert!(hooks[1].r#match("text", "/etc/sysctl.d/99-boofi.conf"));
+        assert!(!hooks[1].r#match("text", "/etc/sysctl.conf.bak"));
+    }
+}
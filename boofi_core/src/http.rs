@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use axum::body::Bytes;
+use axum::http::{header, HeaderName, Method, Request, StatusCode, Uri};
+use hyper::Body;
+use hyper::client::conn;
+use rustls::{ClientConfig, RootCertStore, ServerName};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use crate::error::Resul;
+use crate::tls::root_certificates;
+
+/// Status and complete body of a response
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub body: Bytes,
+}
+
+/// Minimal http client, one connection per request.
+/// https urls are verified with `roots` or the certificate authorities of the system.
+pub async fn send(url: &str,
+                  method: Method,
+                  headers: &[(HeaderName, String)],
+                  body: Option<String>,
+                  roots: Option<RootCertStore>) -> Resul<HttpResponse> {
+    let uri: Uri = url.parse().map_err(|_| HttpError::InvalidUrl(url.into()))?;
+    let host = uri.host().ok_or(HttpError::InvalidUrl(url.into()))?;
+
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri.path_and_query().map(|p| p.as_str()).unwrap_or("/"))
+        .header(header::HOST, host)
+        .header(header::USER_AGENT, "boofi");
+
+    if body.is_some() {
+        request = request.header(header::CONTENT_TYPE, "application/json");
+    }
+
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    let request = request.body(body.map(Body::from).unwrap_or_else(Body::empty))?;
+
+    match uri.scheme_str() {
+        Some("http") => {
+            let stream = TcpStream::connect((host, uri.port_u16().unwrap_or(80))).await?;
+            request_on(stream, request).await
+        }
+        Some("https") => {
+            let roots = match roots {
+                Some(roots) => roots,
+                None => root_certificates().await?,
+            };
+            let config = ClientConfig::builder()
+                .with_safe_defaults()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            let server_name = ServerName::try_from(host).map_err(|_| HttpError::InvalidUrl(url.into()))?;
+
+            let stream = TcpStream::connect((host, uri.port_u16().unwrap_or(443))).await?;
+            let stream = TlsConnector::from(Arc::new(config)).connect(server_name, stream).await?;
+            request_on(stream, request).await
+        }
+        _ => Err(HttpError::InvalidUrl(url.into()).into()),
+    }
+}
+
+async fn request_on<S: AsyncRead + AsyncWrite + Unpin + Send + 'static>(stream: S, request: Request<Body>) -> Resul<HttpResponse> {
+    let (mut sender, connection) = conn::handshake(stream).await?;
+    tokio::spawn(connection);
+
+    let response = sender.send_request(request).await?;
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await?;
+
+    Ok(HttpResponse { status, body })
+}
+
+#[derive(Debug, Error)]
+pub enum HttpError {
+    #[error("invalid url {0}")]
+    InvalidUrl(String),
+}
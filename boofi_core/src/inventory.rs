@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, UNIX_EPOCH};
+use futures_util::future::join_all;
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use crate::rest::SharedController;
+use crate::system::os::Os;
+
+/// ssh endpoints not accepting a connection within this duration are unreachable
+const REACHABLE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Configured service with its labels, a service which failed to start has its error instead of a controller
+#[derive(Clone)]
+pub struct InventoryService {
+    name: String,
+    address: Option<String>,
+    labels: BTreeMap<String, String>,
+    controller: Result<SharedController, String>,
+}
+
+impl InventoryService {
+    pub fn new(name: String, address: Option<String>, labels: BTreeMap<String, String>, controller: SharedController) -> Self {
+        Self { name, address, labels, controller: Ok(controller) }
+    }
+
+    pub fn new_degraded(name: String, address: Option<String>, labels: BTreeMap<String, String>, error: String) -> Self {
+        Self { name, address, labels, controller: Err(error) }
+    }
+
+    /// Local services are always reachable, ssh endpoints have to accept a connection
+    async fn reachable(&self) -> bool {
+        match &self.address {
+            Some(address) => matches!(timeout(REACHABLE_TIMEOUT, TcpStream::connect(address)).await, Ok(Ok(_))),
+            None => true,
+        }
+    }
+
+    async fn entry(&self) -> InventoryEntry {
+        let (os, last_seen, error) = match &self.controller {
+            Ok(controller) => {
+                let system_manager = controller.lock().await.system_manager();
+                (system_manager.os(), system_manager.last_seen(), None)
+            }
+            Err(error) => (None, None, Some(error.clone())),
+        };
+        let reachable = self.reachable().await;
+
+        InventoryEntry {
+            name: self.name.clone(),
+            address: self.address.clone(),
+            labels: self.labels.clone(),
+            os,
+            status: if reachable && error.is_none() { ServiceStatus::Ok } else { ServiceStatus::Degraded },
+            error,
+            reachable,
+            last_seen: last_seen.and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs()),
+        }
+    }
+}
+
+/// Degraded services failed to start or their endpoint is unreachable
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceStatus {
+    Ok,
+    Degraded,
+}
+
+/// One service of the inventory
+/// os:         detected with the first authenticated request, unknown before
+/// error:      why the service failed to start, it is started again with its next request
+/// last_seen:  unix timestamp in seconds of the last request reaching the system
+#[derive(Debug, Serialize)]
+pub struct InventoryEntry {
+    name: String,
+    address: Option<String>,
+    labels: BTreeMap<String, String>,
+    os: Option<Os>,
+    status: ServiceStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    reachable: bool,
+    last_seen: Option<u64>,
+}
+
+/// All configured services, checked concurrently
+#[derive(Clone)]
+pub struct Inventory {
+    services: Vec<InventoryService>,
+}
+
+impl Inventory {
+    pub fn new(mut services: Vec<InventoryService>) -> Self {
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { services }
+    }
+
+    pub async fn list(&self) -> Vec<InventoryEntry> {
+        tracing::debug!("[INVENTORY] checking {} services", self.services.len());
+        join_all(self.services.iter().map(InventoryService::entry)).await
+    }
+}
@@ -0,0 +1,462 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::errors::ErrorKind;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::digest::{Context, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+use crate::error::{Erro, Resul};
+use crate::secret::{Secret, SecretKey};
+
+/// Stateless bearer tokens signed by boofi, and tokens of external identity providers
+/// algorithm:      HS256, HS384 or HS512 with `secret`, RS*, PS*, ES* or EdDSA with the `private_key` and `public_key` pem files
+/// issuer:         `iss` of issued tokens
+/// audience:       `aud` of issued tokens, not set if missing
+/// external:       identity providers whose tokens are accepted
+/// Issued tokens are JWTs instead of random tokens if a `secret` or `private_key` is set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwtConfig {
+    #[serde(default = "JwtConfig::default_algorithm")]
+    algorithm: Algorithm,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret: Option<Secret>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    private_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    #[serde(default = "JwtConfig::default_issuer")]
+    issuer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    audience: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    external: Vec<JwtIssuer>,
+}
+
+impl JwtConfig {
+    fn default_algorithm() -> Algorithm {
+        Algorithm::HS256
+    }
+
+    fn default_issuer() -> String {
+        "boofi".into()
+    }
+
+    /// Resolves the secrets of the configuration and the identity providers
+    pub async fn resolve(&mut self, key: Option<&SecretKey>) -> Resul<()> {
+        let secrets = self.secret.iter_mut().chain(self.external.iter_mut().filter_map(|e| e.secret.as_mut()));
+        for secret in secrets {
+            secret.resolve(key).await?;
+        }
+        Ok(())
+    }
+
+    /// Reads the keys, a missing or invalid key fails
+    pub async fn load(&self) -> Resul<Jwt> {
+        let mut verifiers = vec![];
+
+        let signer = match (&self.secret, &self.private_key) {
+            (None, None) => None,
+            (secret, private_key) => {
+                let material = match (secret, private_key) {
+                    (Some(secret), _) if is_hmac(self.algorithm) => secret.expose()?.as_bytes().to_vec(),
+                    (_, Some(path)) if !is_hmac(self.algorithm) => tokio::fs::read(path).await?,
+                    _ => return Err(JwtError::KeyMissing(self.issuer.clone()).into()),
+                };
+
+                let key = match self.algorithm {
+                    a if is_hmac(a) => EncodingKey::from_secret(&material),
+                    Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 |
+                    Algorithm::PS256 | Algorithm::PS384 | Algorithm::PS512 => EncodingKey::from_rsa_pem(&material)?,
+                    Algorithm::ES256 | Algorithm::ES384 => EncodingKey::from_ec_pem(&material)?,
+                    _ => EncodingKey::from_ed_pem(&material)?,
+                };
+
+                verifiers.push(Verifier::new(&self.issuer,
+                                             self.audience.as_deref(),
+                                             self.algorithm,
+                                             self.secret.as_ref().map(Secret::expose).transpose()?,
+                                             self.public_key.as_deref(),
+                                             "sub",
+                                             true).await?);
+
+                Some(Arc::new(Signer {
+                    algorithm: self.algorithm,
+                    key,
+                    issuer: self.issuer.clone(),
+                    audience: self.audience.clone(),
+                    cipher: cipher(&material)?,
+                }))
+            }
+        };
+
+        for external in self.external.iter() {
+            verifiers.push(Verifier::new(&external.issuer,
+                                         external.audience.as_deref(),
+                                         external.algorithm,
+                                         external.secret.as_ref().map(Secret::expose).transpose()?,
+                                         external.public_key.as_deref(),
+                                         &external.username_claim,
+                                         false).await?);
+        }
+
+        Ok(Jwt {
+            signer,
+            verifiers: Arc::new(verifiers),
+        })
+    }
+}
+
+/// Identity provider whose tokens are accepted, e.g. for single sign-on.
+/// Its tokens carry no password, programs run with the service account of the authentication.
+/// issuer:         required `iss` of the tokens
+/// audience:       required `aud` of the tokens, not checked if missing
+/// algorithm:      RS256 by default, HS* with `secret`, the others with the `public_key` pem file
+/// username_claim: claim with the username, `sub` by default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwtIssuer {
+    issuer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    audience: Option<String>,
+    #[serde(default = "JwtIssuer::default_algorithm")]
+    algorithm: Algorithm,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret: Option<Secret>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    #[serde(default = "JwtIssuer::default_username_claim")]
+    username_claim: String,
+}
+
+impl JwtIssuer {
+    fn default_algorithm() -> Algorithm {
+        Algorithm::RS256
+    }
+
+    fn default_username_claim() -> String {
+        "sub".into()
+    }
+}
+
+fn is_hmac(algorithm: Algorithm) -> bool {
+    matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512)
+}
+
+/// Key encrypting the password in issued tokens, derived from the signing key
+fn cipher(material: &[u8]) -> Resul<LessSafeKey> {
+    let mut context = Context::new(&SHA256);
+    context.update(b"boofi jwt credential");
+    context.update(material);
+
+    Ok(LessSafeKey::new(UnboundKey::new(&AES_256_GCM, context.finish().as_ref()).map_err(|_| JwtError::Crypto)?))
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+struct Signer {
+    algorithm: Algorithm,
+    key: EncodingKey,
+    issuer: String,
+    audience: Option<String>,
+    cipher: LessSafeKey,
+}
+
+/// Checks the tokens of one issuer
+struct Verifier {
+    issuer: String,
+    key: DecodingKey,
+    validation: Validation,
+    username_claim: String,
+    own: bool,
+}
+
+impl Verifier {
+    async fn new(issuer: &str,
+                 audience: Option<&str>,
+                 algorithm: Algorithm,
+                 secret: Option<&str>,
+                 public_key: Option<&str>,
+                 username_claim: &str,
+                 own: bool) -> Resul<Self> {
+        let key = match (algorithm, secret, public_key) {
+            (a, Some(secret), _) if is_hmac(a) => DecodingKey::from_secret(secret.as_bytes()),
+            (a, _, Some(path)) if !is_hmac(a) => {
+                let pem = tokio::fs::read(path).await?;
+                match a {
+                    Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_pem(&pem)?,
+                    Algorithm::EdDSA => DecodingKey::from_ed_pem(&pem)?,
+                    _ => DecodingKey::from_rsa_pem(&pem)?,
+                }
+            }
+            _ => return Err(JwtError::KeyMissing(issuer.into()).into()),
+        };
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[issuer]);
+        validation.set_required_spec_claims(&["exp", "iss"]);
+        match audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        if own {
+            validation.leeway = 0;
+        }
+
+        Ok(Self {
+            issuer: issuer.into(),
+            key,
+            validation,
+            username_claim: username_claim.into(),
+            own,
+        })
+    }
+}
+
+/// Claims of a verified token
+/// password:   only in tokens issued by boofi
+/// external:   issued by an identity provider
+pub struct JwtClaims {
+    pub username: String,
+    pub password: Option<String>,
+    pub client: Option<String>,
+    pub expires_at: u64,
+    pub external: bool,
+}
+
+/// Claims of issued tokens, the password is encrypted in `cred`
+#[derive(Serialize)]
+struct IssuedClaims<'a> {
+    iss: &'a str,
+    sub: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aud: Option<&'a str>,
+    iat: u64,
+    exp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client: Option<&'a str>,
+    cred: String,
+}
+
+/// Loaded keys of a `JwtConfig`, cloning only shares them
+#[derive(Clone)]
+pub struct Jwt {
+    signer: Option<Arc<Signer>>,
+    verifiers: Arc<Vec<Verifier>>,
+}
+
+impl Debug for Jwt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Jwt")
+            .field("signs", &self.signs())
+            .field("issuers", &self.verifiers.iter().map(|v| v.issuer.as_str()).collect::<Vec<&str>>())
+            .finish()
+    }
+}
+
+impl Jwt {
+    /// Random tokens look different, they have no dots
+    pub fn is_jwt(token: &str) -> bool {
+        token.split('.').count() == 3
+    }
+
+    /// Whether tokens are issued as JWTs
+    pub fn signs(&self) -> bool {
+        self.signer.is_some()
+    }
+
+    /// Signed token valid for `duration` with its expiration as unix timestamp
+    pub fn issue(&self, username: &str, password: &str, client: Option<&str>, duration: Duration) -> Resul<(String, u64)> {
+        let signer = self.signer.as_ref().ok_or(JwtError::SigningDisabled)?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| JwtError::Crypto)?;
+        let mut sealed = password.as_bytes().to_vec();
+        signer.cipher.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(username.as_bytes()), &mut sealed)
+            .map_err(|_| JwtError::Crypto)?;
+
+        let now = SystemTime::now();
+        let expires_at = unix_time(now + duration);
+        let claims = IssuedClaims {
+            iss: &signer.issuer,
+            sub: username,
+            aud: signer.audience.as_deref(),
+            iat: unix_time(now),
+            exp: expires_at,
+            client,
+            cred: URL_SAFE_NO_PAD.encode([nonce.as_slice(), &sealed].concat()),
+        };
+
+        Ok((jsonwebtoken::encode(&Header::new(signer.algorithm), &claims, &signer.key)?, expires_at))
+    }
+
+    /// Checks signature, issuer, audience and expiration of a token
+    pub fn verify(&self, token: &str) -> Resul<JwtClaims> {
+        let issuer = Self::issuer(token)?;
+        let verifier = self.verifiers.iter()
+            .find(|v| v.issuer == issuer)
+            .ok_or(JwtError::IssuerUnknown(issuer))?;
+
+        let claims = jsonwebtoken::decode::<Map<String, Value>>(token, &verifier.key, &verifier.validation)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => Erro::AuthTokenExpired,
+                _ => JwtError::Invalid(e.to_string()).into(),
+            })?
+            .claims;
+
+        let username = claims.get(&verifier.username_claim)
+            .and_then(Value::as_str)
+            .ok_or_else(|| JwtError::Invalid(format!("claim {} is missing", verifier.username_claim)))?;
+
+        let password = match (verifier.own, claims.get("cred").and_then(Value::as_str)) {
+            (true, Some(cred)) => Some(self.decrypt(username, cred)?),
+            (true, None) => return Err(JwtError::Invalid("claim cred is missing".into()).into()),
+            (false, _) => None,
+        };
+
+        Ok(JwtClaims {
+            username: username.into(),
+            password,
+            client: claims.get("client").and_then(Value::as_str).map(ToString::to_string),
+            expires_at: claims.get("exp").and_then(Value::as_u64).unwrap_or_default(),
+            external: !verifier.own,
+        })
+    }
+
+    /// `iss` of a token before its signature is checked, to choose the key
+    fn issuer(token: &str) -> Resul<String> {
+        let invalid = || JwtError::Invalid("payload is not base64 encoded json".into());
+        let payload = token.split('.').nth(1).ok_or_else(invalid)?;
+        let claims: Map<String, Value> = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?)
+            .map_err(|_| invalid())?;
+
+        claims.get("iss")
+            .and_then(Value::as_str)
+            .map(ToString::to_string)
+            .ok_or_else(|| JwtError::Invalid("claim iss is missing".into()).into())
+    }
+
+    fn decrypt(&self, username: &str, cred: &str) -> Resul<String> {
+        let signer = self.signer.as_ref().ok_or(JwtError::SigningDisabled)?;
+        let invalid = || JwtError::Invalid("claim cred can't be decrypted".into());
+        let content = URL_SAFE_NO_PAD.decode(cred).map_err(|_| invalid())?;
+        if content.len() < NONCE_LEN {
+            return Err(invalid().into());
+        }
+
+        let (nonce, sealed) = content.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| invalid())?;
+        let mut sealed = sealed.to_vec();
+        let password = signer.cipher.open_in_place(nonce, Aad::from(username.as_bytes()), &mut sealed)
+            .map_err(|_| invalid())?;
+
+        String::from_utf8(password.to_vec()).map_err(|_| invalid().into())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JwtError {
+    #[error("jwt key of {0} is missing, HS* algorithms need a secret and the others a pem file")]
+    KeyMissing(String),
+    #[error("jwt is invalid: {0}")]
+    Invalid(String),
+    #[error("jwt issuer {0} is unknown")]
+    IssuerUnknown(String),
+    #[error("tokens are not issued as jwt, a secret or private key is missing")]
+    SigningDisabled,
+    #[error("tokens of an identity provider need a service account to run programs")]
+    ServiceAccountMissing,
+    #[error("jwt can't be revoked, it is valid until it expires")]
+    RevokeUnsupported,
+    #[error("tokens of an identity provider are refreshed by the identity provider")]
+    RefreshUnsupported,
+    #[error("jwt credential encryption failed")]
+    Crypto,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use jsonwebtoken::{Algorithm, EncodingKey, Header};
+    use serde_json::json;
+    use crate::error::Erro;
+    use crate::jwt::{Jwt, JwtConfig, JwtIssuer};
+
+    fn config() -> JwtConfig {
+        JwtConfig {
+            algorithm: Algorithm::HS256,
+            secret: Some("issuing secret".into()),
+            private_key: None,
+            public_key: None,
+            issuer: "boofi".into(),
+            audience: Some("api".into()),
+            external: vec![JwtIssuer {
+                issuer: "https://sso.example.com".into(),
+                audience: Some("boofi".into()),
+                algorithm: Algorithm::HS256,
+                secret: Some("idp secret".into()),
+                public_key: None,
+                username_claim: "preferred_username".into(),
+            }],
+        }
+    }
+
+    fn external(claims: serde_json::Value, secret: &str) -> String {
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &EncodingKey::from_secret(secret.as_bytes())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn issue_and_verify() {
+        let jwt = config().load().await.unwrap();
+        assert!(jwt.signs());
+
+        let (token, expires_at) = jwt.issue("dev", "s3cret", Some("laptop"), Duration::from_secs(60)).unwrap();
+        assert!(Jwt::is_jwt(&token));
+        assert!(!token.contains("s3cret"));
+
+        let claims = jwt.verify(&token).unwrap();
+        assert_eq!(claims.username, "dev");
+        assert_eq!(claims.password.as_deref(), Some("s3cret"));
+        assert_eq!(claims.client.as_deref(), Some("laptop"));
+        assert_eq!(claims.expires_at, expires_at);
+        assert!(!claims.external);
+
+        // signed with another secret
+        let other = JwtConfig { secret: Some("other".into()), ..config() }.load().await.unwrap();
+        assert!(other.verify(&token).is_err());
+
+        let (expired, _) = jwt.issue("dev", "s3cret", None, Duration::from_secs(0)).unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(matches!(jwt.verify(&expired), Err(Erro::AuthTokenExpired)));
+    }
+
+    #[tokio::test]
+    async fn verify_external() {
+        let jwt = config().load().await.unwrap();
+        let exp = jsonwebtoken::get_current_timestamp() + 60;
+
+        let claims = jwt.verify(&external(json!({
+            "iss": "https://sso.example.com", "aud": "boofi", "exp": exp, "preferred_username": "dev"
+        }), "idp secret")).unwrap();
+        assert_eq!(claims.username, "dev");
+        assert!(claims.password.is_none());
+        assert!(claims.external);
+
+        // wrong audience, unknown issuer, wrong key, missing username
+        assert!(jwt.verify(&external(json!({"iss": "https://sso.example.com", "aud": "other", "exp": exp, "preferred_username": "dev"}), "idp secret")).is_err());
+        assert!(jwt.verify(&external(json!({"iss": "https://evil.example.com", "aud": "boofi", "exp": exp, "preferred_username": "dev"}), "idp secret")).is_err());
+        assert!(jwt.verify(&external(json!({"iss": "https://sso.example.com", "aud": "boofi", "exp": exp, "preferred_username": "dev"}), "issuing secret")).is_err());
+        assert!(jwt.verify(&external(json!({"iss": "https://sso.example.com", "aud": "boofi", "exp": exp, "sub": "dev"}), "idp secret")).is_err());
+
+        // only accepting external tokens
+        let accepting = JwtConfig { secret: None, ..config() }.load().await.unwrap();
+        assert!(!accepting.signs());
+        assert!(accepting.issue("dev", "s3cret", None, Duration::from_secs(60)).is_err());
+    }
+}
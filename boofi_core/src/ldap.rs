@@ -0,0 +1,267 @@
+use std::sync::Arc;
+use std::time::Duration;
+use axum::http::Uri;
+use rustls::{ClientConfig, RootCertStore, ServerName};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::fs::read;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+use yasna::Tag;
+use crate::error::{Erro, Resul};
+use crate::tls::root_certificates;
+
+const LDAP_PORT: u16 = 389;
+const LDAPS_PORT: u16 = 636;
+
+/// Connecting, binding and reading the response must finish within this duration
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Responses are small, anything larger is rejected
+const MAX_MESSAGE: usize = 64 * 1024;
+
+/// Result codes of a bind response
+const SUCCESS: i64 = 0;
+const INVALID_CREDENTIALS: i64 = 49;
+
+/// Message 2 with an unbind request, sent before closing the connection
+const UNBIND: [u8; 7] = [0x30, 0x05, 0x02, 0x01, 0x02, 0x42, 0x00];
+
+/// Directory verifying credentials with a simple bind
+/// url:        ldap://host[:port] or ldaps://host[:port]
+/// bind_dn:    distinguished name of a user, {username} is replaced by the escaped username
+/// ca_path:    pem bundle trusted for ldaps instead of the system certificate authorities
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ldap {
+    url: String,
+    bind_dn: String,
+    #[serde(default)]
+    ca_path: Option<String>,
+}
+
+impl Ldap {
+    /// Bind distinguished name with special characters of the username escaped
+    fn dn(&self, username: &str) -> String {
+        let last = username.chars().count().saturating_sub(1);
+        let mut escaped = String::new();
+
+        for (i, c) in username.chars().enumerate() {
+            match c {
+                ',' | '+' | '"' | '\\' | '<' | '>' | ';' | '=' => escaped.extend(['\\', c]),
+                '#' if i == 0 => escaped.extend(['\\', c]),
+                ' ' if i == 0 || i == last => escaped.extend(['\\', c]),
+                '\0' => escaped.push_str("\\00"),
+                _ => escaped.push(c),
+            }
+        }
+
+        self.bind_dn.replace("{username}", &escaped)
+    }
+
+    /// Message 1 with a simple bind request
+    fn bind_request(dn: &str, password: &str) -> Vec<u8> {
+        yasna::construct_der(|w| w.write_sequence(|w| {
+            w.next().write_i64(1);
+            w.next().write_tagged_implicit(Tag::application(0), |w| w.write_sequence(|w| {
+                w.next().write_i64(3); // protocol version
+                w.next().write_bytes(dn.as_bytes());
+                w.next().write_tagged_implicit(Tag::context(0), |w| w.write_bytes(password.as_bytes()));
+            }));
+        }))
+    }
+
+    /// Result code and diagnostic message of a bind response
+    fn bind_response(response: &[u8]) -> Resul<(i64, String)> {
+        Ok(yasna::parse_ber(response, |r| r.read_sequence(|r| {
+            r.next().read_i64()?; // message id
+
+            let result = r.next().read_tagged_implicit(Tag::application(1), |r| r.read_sequence(|r| {
+                let code = r.next().read_enum()?;
+                r.next().read_bytes()?; // matched dn
+                let message = r.next().read_bytes()?;
+                while r.read_optional(|r| r.read_der())?.is_some() {} // referral and sasl credentials
+
+                Ok((code, String::from_utf8_lossy(&message).to_string()))
+            }))?;
+
+            while r.read_optional(|r| r.read_der())?.is_some() {} // controls
+            Ok(result)
+        }))?)
+    }
+
+    /// One BER element, only definite lengths are supported
+    async fn read_message<S: AsyncRead + Unpin>(stream: &mut S) -> Resul<Vec<u8>> {
+        let mut message = vec![0; 2];
+        stream.read_exact(&mut message).await?;
+
+        let length = if message[1] & 0x80 == 0 {
+            usize::from(message[1])
+        } else {
+            let mut length = vec![0; usize::from(message[1] & 0x7f)];
+
+            if length.is_empty() || length.len() > 4 {
+                return Err(LdapError::ResponseInvalid.into());
+            }
+
+            stream.read_exact(&mut length).await?;
+            message.extend(&length);
+            length.iter().fold(0, |l, b| l << 8 | usize::from(*b))
+        };
+
+        if length > MAX_MESSAGE {
+            return Err(LdapError::ResponseInvalid.into());
+        }
+
+        let start = message.len();
+        message.resize(start + length, 0);
+        stream.read_exact(&mut message[start..]).await?;
+
+        Ok(message)
+    }
+
+    async fn send<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, request: &[u8]) -> Resul<Vec<u8>> {
+        stream.write_all(request).await?;
+        stream.flush().await?;
+
+        let response = Self::read_message(&mut stream).await?;
+
+        if let Err(e) = stream.write_all(&UNBIND).await {
+            tracing::debug!("[LDAP] unbind failed: {}", e);
+        }
+
+        Ok(response)
+    }
+
+    async fn roots(&self) -> Resul<RootCertStore> {
+        match &self.ca_path {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                roots.add_parsable_certificates(&rustls_pemfile::certs(&mut read(path).await?.as_slice())?);
+
+                if roots.is_empty() {
+                    return Err(Erro::ClientCaInvalid(path.clone()));
+                }
+
+                Ok(roots)
+            }
+            None => root_certificates().await,
+        }
+    }
+
+    async fn exchange(&self, request: &[u8]) -> Resul<Vec<u8>> {
+        let uri: Uri = self.url.parse().map_err(|_| LdapError::InvalidUrl(self.url.clone()))?;
+        let host = uri.host().ok_or(LdapError::InvalidUrl(self.url.clone()))?;
+
+        match uri.scheme_str() {
+            Some("ldap") => {
+                let stream = TcpStream::connect((host, uri.port_u16().unwrap_or(LDAP_PORT))).await?;
+                Self::send(stream, request).await
+            }
+            Some("ldaps") => {
+                let config = ClientConfig::builder()
+                    .with_safe_defaults()
+                    .with_root_certificates(self.roots().await?)
+                    .with_no_client_auth();
+                let server_name = ServerName::try_from(host).map_err(|_| LdapError::InvalidUrl(self.url.clone()))?;
+
+                let stream = TcpStream::connect((host, uri.port_u16().unwrap_or(LDAPS_PORT))).await?;
+                let stream = TlsConnector::from(Arc::new(config)).connect(server_name, stream).await?;
+                Self::send(stream, request).await
+            }
+            _ => Err(LdapError::InvalidUrl(self.url.clone()).into()),
+        }
+    }
+
+    /// Simple bind with the credential of a user.
+    /// Empty passwords are rejected, the directory would treat them as anonymous bind.
+    pub async fn verify(&self, username: &str, password: &str) -> Resul<()> {
+        if password.is_empty() {
+            return Err(Erro::AuthLdapInvalid);
+        }
+
+        let request = Self::bind_request(&self.dn(username), password);
+        let response = timeout(TIMEOUT, self.exchange(&request)).await
+            .map_err(|_| LdapError::Timeout(self.url.clone()))??;
+
+        match Self::bind_response(&response)? {
+            (SUCCESS, _) => {
+                tracing::debug!("[LDAP] {} verified", username);
+                Ok(())
+            }
+            (INVALID_CREDENTIALS, _) => Err(Erro::AuthLdapInvalid),
+            (code, message) => Err(LdapError::Bind(code, message).into()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LdapError {
+    #[error("invalid url {0}")]
+    InvalidUrl(String),
+    #[error("invalid response")]
+    ResponseInvalid,
+    #[error("bind failed with code {0}: {1}")]
+    Bind(i64, String),
+    #[error("{0} did not respond in time")]
+    Timeout(String),
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use yasna::Tag;
+    use crate::error::Erro;
+    use crate::ldap::Ldap;
+
+    fn bind_response(code: i64) -> Vec<u8> {
+        yasna::construct_der(|w| w.write_sequence(|w| {
+            w.next().write_i64(1);
+            w.next().write_tagged_implicit(Tag::application(1), |w| w.write_sequence(|w| {
+                w.next().write_enum(code);
+                w.next().write_bytes(b"");
+                w.next().write_bytes(b"");
+            }));
+        }))
+    }
+
+    #[test]
+    fn test_dn() {
+        let ldap = Ldap {
+            url: "ldap://localhost".into(),
+            bind_dn: "uid={username},ou=people,dc=example,dc=com".into(),
+            ca_path: None,
+        };
+
+        assert_eq!(ldap.dn("alice"), "uid=alice,ou=people,dc=example,dc=com");
+        assert_eq!(ldap.dn("#a,b=c "), r"uid=\#a\,b\=c\ ,ou=people,dc=example,dc=com");
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ldap = Ldap {
+            url: format!("ldap://{}", listener.local_addr().unwrap()),
+            bind_dn: "uid={username},dc=example,dc=com".into(),
+            ca_path: None,
+        };
+        let expected = Ldap::bind_request("uid=alice,dc=example,dc=com", "secret");
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; expected.len()];
+                stream.read_exact(&mut request).await.unwrap();
+
+                let code = if request == expected { 0 } else { 49 };
+                stream.write_all(&bind_response(code)).await.unwrap();
+            }
+        });
+
+        assert!(ldap.verify("alice", "secret").await.is_ok());
+        assert!(matches!(ldap.verify("alice", "wrong!").await, Err(Erro::AuthLdapInvalid)));
+        assert!(matches!(ldap.verify("alice", "").await, Err(Erro::AuthLdapInvalid)));
+    }
+}
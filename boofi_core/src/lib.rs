@@ -0,0 +1,39 @@
+//! Core of boofi: manage files and run apps on local or ssh systems.
+//!
+//! A [`controller::Controller`] bundles the file and app builders, tasks and authentication of one endpoint.
+//! The [`rest::Rest`] api exposes one or more controllers, the `boofi` binary only loads the configuration file.
+//! New files and apps implement [`files::FileBuilder`] and [`apps::AppBuilder`] and act on a [`system::System`].
+
+pub mod error;
+pub mod rest;
+pub mod files;
+pub mod apps;
+pub mod task;
+mod utils;
+pub mod system;
+pub mod controller;
+pub mod description;
+pub mod tls;
+pub mod acme;
+pub mod ldap;
+pub mod patch;
+pub mod backup;
+pub mod hook;
+pub mod generate;
+pub mod audit;
+pub mod webhook;
+pub mod http;
+pub mod client;
+pub mod secret;
+pub mod plan;
+pub mod batch;
+pub mod template;
+pub mod runbook;
+pub mod token;
+pub mod jwt;
+pub mod users;
+pub mod inventory;
+pub mod cache;
+pub mod grpc;
+pub mod workspace;
+pub mod upload;
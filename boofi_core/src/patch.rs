@@ -0,0 +1,404 @@
+use ring::digest::{digest, SHA256};
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+use crate::error::{Erro, Resul};
+
+/// Lowercase hex encoded SHA-256 of the content, used as base of patches
+pub fn sha256(content: &[u8]) -> String {
+    digest(&SHA256, content).as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Rejects a patch if the content changed since the client read it
+pub fn check_base(content: &[u8], base: Option<&str>) -> Resul<()> {
+    match base {
+        Some(base) if !base.trim_matches('"').eq_ignore_ascii_case(&sha256(content)) => Err(Erro::FileConflict("content changed since base".into())),
+        _ => Ok(()),
+    }
+}
+
+/// Lines without line feed and whether the last line was terminated
+fn split_lines(content: &str) -> (Vec<&str>, bool) {
+    if content.is_empty() {
+        return (vec![], true);
+    }
+
+    let mut lines: Vec<&str> = content.split('\n').collect();
+    let terminated = content.ends_with('\n');
+
+    if terminated {
+        lines.pop();
+    }
+
+    (lines, terminated)
+}
+
+/// One `@@ -start,length +start,length @@` section of a unified diff
+struct Hunk<'a> {
+    old_start: usize,
+    old_length: usize,
+    lines: Vec<(char, &'a str)>,
+}
+
+impl<'a> Hunk<'a> {
+    /// start and optional length of one side, length defaults to 1
+    fn range(range: &str) -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, length)) => Some((start.parse().ok()?, length.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    }
+
+    fn parse_header(header: &str) -> Resul<(usize, usize)> {
+        header.strip_prefix("@@ -")
+            .and_then(|h| h.split_once(' '))
+            .and_then(|(old, _)| Self::range(old))
+            .ok_or(PatchError::HunkInvalid(header.into()).into())
+    }
+
+    /// Hunks of a unified diff, file headers before the first hunk are ignored
+    fn parse(diff: &'a str) -> Resul<Vec<Self>> {
+        let (lines, _) = split_lines(diff);
+        let mut hunks: Vec<Self> = vec![];
+
+        for line in lines {
+            if line.starts_with("@@") {
+                let (old_start, old_length) = Self::parse_header(line)?;
+                hunks.push(Hunk { old_start, old_length, lines: vec![] });
+            } else if let Some(hunk) = hunks.last_mut() {
+                match line.chars().next() {
+                    Some(kind @ (' ' | '-' | '+' | '\\')) => hunk.lines.push((kind, &line[1..])),
+                    None => hunk.lines.push((' ', "")), // context of an empty line without the leading space
+                    _ => return Err(PatchError::HunkInvalid(line.into()).into()),
+                }
+            }
+        }
+
+        if hunks.is_empty() {
+            return Err(PatchError::HunksMissing.into());
+        }
+
+        Ok(hunks)
+    }
+}
+
+/// Applies a unified diff, every context and removed line must match the content
+pub fn apply_diff(content: &str, diff: &str) -> Resul<String> {
+    let (old, mut terminated) = split_lines(content);
+    let mut new: Vec<&str> = vec![];
+    let mut index = 0;
+
+    for hunk in Hunk::parse(diff)? {
+        // an empty old range starts after the given line
+        let start = if hunk.old_length == 0 { hunk.old_start } else { hunk.old_start.saturating_sub(1) };
+
+        if start < index || start > old.len() {
+            return Err(Erro::FileConflict(format!("hunk at line {} out of order or range", hunk.old_start)));
+        }
+
+        new.extend(&old[index..start]);
+        index = start;
+
+        let mut previous = ' ';
+        let mut unterminated = false;
+
+        for (kind, line) in hunk.lines {
+            match kind {
+                ' ' | '-' => {
+                    if old.get(index) != Some(&line) {
+                        return Err(Erro::FileConflict(format!("line {} does not match", index + 1)));
+                    }
+
+                    if kind == ' ' {
+                        new.push(line);
+                        unterminated = false;
+                    }
+
+                    index += 1;
+                }
+                '+' => {
+                    new.push(line);
+                    unterminated = false;
+                }
+                // no newline at end of file, only the new content is affected after context or added lines
+                _ => if previous != '-' {
+                    unterminated = true;
+                }
+            }
+
+            previous = kind;
+        }
+
+        if index == old.len() {
+            terminated = !unterminated;
+        }
+    }
+
+    new.extend(&old[index..]);
+
+    let mut patched = new.join("\n");
+
+    if terminated && !new.is_empty() {
+        patched.push('\n');
+    }
+
+    Ok(patched)
+}
+
+/// Line with whether it is the unterminated last line of its content
+type DiffLine<'a> = (&'a str, bool);
+
+fn diff_lines(content: &str) -> Vec<DiffLine<'_>> {
+    let (lines, terminated) = split_lines(content);
+    let last = lines.len().saturating_sub(1);
+    lines.into_iter().enumerate().map(|(i, line)| (line, !terminated && i == last)).collect()
+}
+
+/// Kept, removed and added lines of the longest common subsequence, equal prefix and suffix are skipped before
+fn edits<'a>(old: &[DiffLine<'a>], new: &[DiffLine<'a>]) -> Vec<(char, DiffLine<'a>)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    // lcs[i][j] is the length of the longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut edits: Vec<(char, DiffLine)> = old[..prefix].iter().map(|l| (' ', *l)).collect();
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            edits.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            edits.push(('-', a[i]));
+            i += 1;
+        } else {
+            edits.push(('+', b[j]));
+            j += 1;
+        }
+    }
+
+    edits.extend(old[old.len() - suffix..].iter().map(|l| (' ', *l)));
+    edits
+}
+
+/// Unified diff with three lines of context, which `apply_diff` accepts. Empty if both contents are equal.
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    const CONTEXT: usize = 3;
+
+    let edits = edits(&diff_lines(old), &diff_lines(new));
+    let changes: Vec<usize> = edits.iter().enumerate().filter(|(_, (kind, _))| *kind != ' ').map(|(i, _)| i).collect();
+
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // context around every change, overlapping ranges are merged into one hunk
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for change in changes {
+        let (start, end) = (change.saturating_sub(CONTEXT), (change + CONTEXT + 1).min(edits.len()));
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let count = |range: &[(char, DiffLine)], skip: char| range.iter().filter(|(kind, _)| *kind != skip).count();
+    let mut diff = format!("--- {}\n+++ {}\n", path, path);
+
+    for (start, end) in ranges {
+        let hunk = &edits[start..end];
+        let (old_length, new_length) = (count(hunk, '+'), count(hunk, '-'));
+        // an empty range names the line before it
+        let old_start = count(&edits[..start], '+') + usize::from(old_length > 0);
+        let new_start = count(&edits[..start], '-') + usize::from(new_length > 0);
+
+        diff.push_str(&format!("@@ -{},{} +{},{} @@\n", old_start, old_length, new_start, new_length));
+
+        for (kind, (line, unterminated)) in hunk {
+            diff.push_str(&format!("{}{}\n", kind, line));
+            if *unterminated {
+                diff.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    diff
+}
+
+/// One operation of a JSON patch (RFC 6902), move and copy are not supported
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum JsonPatch {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Test { path: String, value: Value },
+}
+
+/// Parent pointer and unescaped last reference token of a JSON pointer
+fn split_pointer(path: &str) -> Resul<(&str, String)> {
+    let (parent, token) = path.rsplit_once('/').ok_or(PatchError::PathInvalid(path.into()))?;
+    Ok((parent, token.replace("~1", "/").replace("~0", "~")))
+}
+
+fn index(token: &str, length: usize, path: &str) -> Resul<usize> {
+    token.parse().ok()
+        .filter(|i| *i < length)
+        .ok_or(PatchError::PathInvalid(path.into()).into())
+}
+
+/// Applies all operations or none, a failed test is a conflict
+pub fn apply_json_patch(mut value: Value, operations: &[JsonPatch]) -> Resul<Value> {
+    for operation in operations {
+        match operation {
+            JsonPatch::Test { path, value: expected } => {
+                if value.pointer(path) != Some(expected) {
+                    return Err(Erro::FileConflict(format!("test of {} failed", path)));
+                }
+            }
+            JsonPatch::Add { path, value: new } |
+            JsonPatch::Replace { path, value: new } if path.is_empty() => value = new.clone(),
+            JsonPatch::Add { path, value: new } |
+            JsonPatch::Replace { path, value: new } => {
+                let (parent, token) = split_pointer(path)?;
+
+                match value.pointer_mut(parent) {
+                    Some(Value::Object(map)) => {
+                        if matches!(operation, JsonPatch::Replace { .. }) && !map.contains_key(&token) {
+                            return Err(PatchError::PathInvalid(path.clone()).into());
+                        }
+
+                        map.insert(token, new.clone());
+                    }
+                    Some(Value::Array(array)) => match operation {
+                        JsonPatch::Add { .. } if token == "-" => array.push(new.clone()),
+                        JsonPatch::Add { .. } => {
+                            let i = index(&token, array.len() + 1, path)?;
+                            array.insert(i, new.clone());
+                        }
+                        _ => {
+                            let i = index(&token, array.len(), path)?;
+                            array[i] = new.clone();
+                        }
+                    },
+                    _ => return Err(PatchError::PathInvalid(path.clone()).into()),
+                }
+            }
+            JsonPatch::Remove { path } => {
+                let (parent, token) = split_pointer(path)?;
+
+                match value.pointer_mut(parent) {
+                    Some(Value::Object(map)) => {
+                        map.remove(&token).ok_or(PatchError::PathInvalid(path.clone()))?;
+                    }
+                    Some(Value::Array(array)) => {
+                        let i = index(&token, array.len(), path)?;
+                        array.remove(i);
+                    }
+                    _ => return Err(PatchError::PathInvalid(path.clone()).into()),
+                }
+            }
+        }
+    }
+
+    Ok(value)
+}
+
+#[derive(Debug, Error)]
+pub enum PatchError {
+    #[error("invalid hunk line {0}")]
+    HunkInvalid(String),
+    #[error("diff contains no hunks")]
+    HunksMissing,
+    #[error("invalid path {0}")]
+    PathInvalid(String),
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::error::Erro;
+    use crate::patch::{apply_diff, apply_json_patch, check_base, sha256, unified_diff};
+
+    #[test]
+    fn test_check_base() {
+        assert_eq!(sha256(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+
+        let hash = sha256(b"127.0.0.1 localhost\n");
+        assert!(check_base(b"127.0.0.1 localhost\n", Some(&hash)).is_ok());
+        assert!(check_base(b"127.0.0.1 localhost\n", None).is_ok());
+        assert!(matches!(check_base(b"::1 localhost\n", Some(&hash)), Err(Erro::FileConflict(_))));
+    }
+
+    #[test]
+    fn test_apply_diff() {
+        let content = "127.0.0.1 localhost\n::1 localhost\n192.168.1.10 nas\n";
+        let diff = "--- a/etc/hosts\n+++ b/etc/hosts\n@@ -1,3 +1,4 @@\n 127.0.0.1 localhost\n ::1 localhost\n-192.168.1.10 nas\n+192.168.1.11 nas\n+192.168.1.12 printer\n";
+
+        assert_eq!(apply_diff(content, diff).unwrap(), "127.0.0.1 localhost\n::1 localhost\n192.168.1.11 nas\n192.168.1.12 printer\n");
+        assert!(matches!(apply_diff("127.0.0.1 localhost\n", diff), Err(Erro::FileConflict(_))));
+        assert!(apply_diff(content, "no hunks").is_err());
+
+        // insertion into an empty file and removal of the final newline
+        assert_eq!(apply_diff("", "@@ -0,0 +1 @@\n+dev\n").unwrap(), "dev\n");
+        assert_eq!(apply_diff("dev\n", "@@ -1 +1 @@\n-dev\n+dev\n\\ No newline at end of file\n").unwrap(), "dev");
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "127.0.0.1 localhost\n::1 localhost\n192.168.1.10 nas\n";
+        let new = "127.0.0.1 localhost\n::1 localhost\n192.168.1.11 nas\n192.168.1.12 printer\n";
+
+        assert_eq!(unified_diff("/etc/hosts", old, new),
+                   "--- /etc/hosts\n+++ /etc/hosts\n@@ -1,3 +1,4 @@\n 127.0.0.1 localhost\n ::1 localhost\n-192.168.1.10 nas\n+192.168.1.11 nas\n+192.168.1.12 printer\n");
+        assert_eq!(unified_diff("/etc/hosts", old, old), "");
+
+        let long = (1..=20).map(|i| format!("line {}\n", i)).collect::<String>();
+        let cases = [
+            ("", "dev\n"),
+            ("dev\n", ""),
+            ("dev\n", "dev"),
+            ("a\nb", "a\nb\nc\n"),
+            (long.as_str(), &long.replace("line 2\n", "").replace("line 18\n", "line 18\nnew\n")),
+        ];
+
+        for (old, new) in cases {
+            assert_eq!(apply_diff(old, &unified_diff("file", old, new)).unwrap(), new, "{:?} to {:?}", old, new);
+        }
+
+        // distant changes are separate hunks
+        assert_eq!(unified_diff("file", &long, cases[4].1).matches("@@ -").count(), 2);
+    }
+
+    #[test]
+    fn test_apply_json_patch() {
+        let value = json!([{"ip": "127.0.0.1", "hostnames": ["localhost"]}]);
+
+        assert_eq!(apply_json_patch(value.clone(), &serde_json::from_value::<Vec<_>>(json!([
+            {"op": "test", "path": "/0/ip", "value": "127.0.0.1"},
+            {"op": "add", "path": "/0/hostnames/-", "value": "dev"},
+            {"op": "add", "path": "/-", "value": {"ip": "::1", "hostnames": []}},
+            {"op": "replace", "path": "/1/ip", "value": "fe80::1"},
+            {"op": "remove", "path": "/0/hostnames/0"},
+        ])).unwrap()).unwrap(), json!([
+            {"ip": "127.0.0.1", "hostnames": ["dev"]},
+            {"ip": "fe80::1", "hostnames": []},
+        ]));
+
+        assert!(matches!(apply_json_patch(value.clone(), &serde_json::from_value::<Vec<_>>(json!([
+            {"op": "test", "path": "/0/ip", "value": "::1"},
+        ])).unwrap()), Err(Erro::FileConflict(_))));
+
+        assert!(apply_json_patch(value, &serde_json::from_value::<Vec<_>>(json!([
+            {"op": "remove", "path": "/3"},
+        ])).unwrap()).is_err());
+    }
+}
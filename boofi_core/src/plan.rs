@@ -0,0 +1,226 @@
+use std::future::Future;
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use crate::error::Resul;
+
+/// Reference to another entry of the same plan, by its id, unique app name or index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PlanDependency {
+    Index(usize),
+    Name(String),
+}
+
+/// fail_fast:  entries not started yet are skipped after the first failure
+/// continue:   only entries depending on a failed one are skipped
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanPolicy {
+    #[default]
+    FailFast,
+    Continue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStatus {
+    Finished,
+    Failed,
+    Skipped,
+}
+
+/// Result of one entry in the order of the request
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PlanResult {
+    status: PlanStatus,
+    output: Option<Value>,
+    error: Option<String>,
+}
+
+impl PlanResult {
+    fn skipped() -> Self {
+        Self { status: PlanStatus::Skipped, output: None, error: None }
+    }
+}
+
+/// One entry of a plan as given in the request
+pub struct PlanEntry<'a> {
+    pub name: &'a str,
+    pub id: Option<&'a str>,
+    pub depends_on: &'a [PlanDependency],
+}
+
+/// Execution plan of apps depending on each other.
+/// Entries are grouped in waves, all entries of a wave run concurrently after the previous wave.
+#[derive(Debug, PartialEq)]
+pub struct Plan {
+    dependencies: Vec<Vec<usize>>,
+    waves: Vec<Vec<usize>>,
+}
+
+impl Plan {
+    pub fn new(entries: &[PlanEntry]) -> Resul<Self> {
+        let resolve = |dependency: &PlanDependency| -> Result<usize, PlanError> {
+            match dependency {
+                PlanDependency::Index(i) if *i < entries.len() => Ok(*i),
+                PlanDependency::Index(i) => Err(PlanError::DependencyNotFound(i.to_string())),
+                PlanDependency::Name(name) => {
+                    if let Some(i) = entries.iter().position(|e| e.id == Some(name.as_str())) {
+                        return Ok(i);
+                    }
+
+                    let mut named = entries.iter().enumerate().filter(|(_, e)| e.name == name).map(|(i, _)| i);
+                    match (named.next(), named.next()) {
+                        (Some(i), None) => Ok(i),
+                        (Some(_), Some(_)) => Err(PlanError::DependencyAmbiguous(name.clone())),
+                        (None, _) => Err(PlanError::DependencyNotFound(name.clone())),
+                    }
+                }
+            }
+        };
+
+        let dependencies = entries.iter()
+            .map(|e| e.depends_on.iter().map(resolve).collect::<Result<Vec<usize>, PlanError>>())
+            .collect::<Result<Vec<Vec<usize>>, PlanError>>()?;
+
+        // group by longest dependency chain, entries left over are part of a cycle
+        let mut wave_of: Vec<Option<usize>> = vec![None; entries.len()];
+        let mut waves: Vec<Vec<usize>> = vec![];
+
+        while wave_of.iter().any(Option::is_none) {
+            let wave: Vec<usize> = (0..entries.len())
+                .filter(|i| wave_of[*i].is_none() && dependencies[*i].iter().all(|d| wave_of[*d].is_some()))
+                .collect();
+
+            if wave.is_empty() {
+                return Err(PlanError::Cycle.into());
+            }
+
+            for i in wave.iter() {
+                wave_of[*i] = Some(waves.len());
+            }
+            waves.push(wave);
+        }
+
+        Ok(Self { dependencies, waves })
+    }
+
+    /// Runs all entries wave by wave, `run` is called with the index of the entry
+    pub async fn execute<F, Fut>(&self, policy: PlanPolicy, run: F) -> Vec<PlanResult>
+        where F: Fn(usize) -> Fut,
+              Fut: Future<Output=Resul<Value>> {
+        let mut results: Vec<Option<PlanResult>> = (0..self.dependencies.len()).map(|_| None).collect();
+        let mut failed = false;
+
+        for wave in self.waves.iter() {
+            let mut running = vec![];
+
+            for i in wave.iter().copied() {
+                let dependency_missing = self.dependencies[i].iter()
+                    .any(|d| !matches!(results[*d], Some(PlanResult { status: PlanStatus::Finished, .. })));
+
+                if dependency_missing || (failed && policy == PlanPolicy::FailFast) {
+                    tracing::debug!("[PLAN] entry {} skipped", i);
+                    results[i] = Some(PlanResult::skipped());
+                } else {
+                    running.push(i);
+                }
+            }
+
+            tracing::debug!("[PLAN] running entries {:?}", running);
+
+            for (i, result) in running.iter().zip(join_all(running.iter().map(|i| run(*i))).await) {
+                results[*i] = Some(match result {
+                    Ok(output) => PlanResult { status: PlanStatus::Finished, output: Some(output), error: None },
+                    Err(error) => {
+                        tracing::error!("[PLAN] entry {} failed", i);
+                        failed = true;
+                        PlanResult { status: PlanStatus::Failed, output: None, error: Some(format!("{:?}", error)) }
+                    }
+                });
+            }
+        }
+
+        results.into_iter().map(|r| r.unwrap_or_else(PlanResult::skipped)).collect()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PlanError {
+    #[error("dependency {0} not found")]
+    DependencyNotFound(String),
+    #[error("dependency {0} matches multiple apps, use an id or index")]
+    DependencyAmbiguous(String),
+    #[error("dependencies contain a cycle")]
+    Cycle,
+    #[error("plans can't run asynchronous")]
+    AsyncUnsupported,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use serde_json::{json, Value};
+    use tokio::sync::Mutex;
+    use crate::error::{Erro, Resul};
+    use crate::plan::{Plan, PlanDependency, PlanEntry, PlanPolicy, PlanStatus};
+
+    fn entry<'a>(name: &'a str, id: Option<&'a str>, depends_on: &'a [PlanDependency]) -> PlanEntry<'a> {
+        PlanEntry { name, id, depends_on }
+    }
+
+    #[test]
+    fn new() {
+        let uname = [PlanDependency::Name("uname".into())];
+        let first = [PlanDependency::Index(0), PlanDependency::Name("second".into())];
+        let plan = Plan::new(&[
+            entry("uname", None, &[]),
+            entry("ls", Some("second"), &uname),
+            entry("ls", None, &first),
+            entry("sh", None, &[]),
+        ]).unwrap();
+
+        assert_eq!(plan.waves, vec![vec![0, 3], vec![1], vec![2]]);
+
+        let ls = [PlanDependency::Name("ls".into())];
+        assert!(Plan::new(&[entry("ls", None, &[]), entry("ls", None, &[]), entry("sh", None, &ls)]).is_err());
+        assert!(Plan::new(&[entry("ls", None, &[PlanDependency::Index(3)])]).is_err());
+        assert!(Plan::new(&[entry("ls", None, &[PlanDependency::Index(1)]), entry("sh", None, &[PlanDependency::Index(0)])]).is_err());
+    }
+
+    #[tokio::test]
+    async fn execute() {
+        let plan = Plan::new(&[
+            entry("a", None, &[]),
+            entry("b", None, &[]),
+            entry("c", None, &[PlanDependency::Name("b".into())]),
+            entry("d", None, &[PlanDependency::Name("a".into())]),
+        ]).unwrap();
+
+        let started = Arc::new(Mutex::new(vec![]));
+        let run = |i: usize| {
+            let started = started.clone();
+            async move {
+                started.lock().await.push(i);
+                tokio::time::sleep(Duration::from_millis(10 * (2 - i as u64 % 2))).await;
+                if i == 1 { Err(Erro::AppNotFound) } else { Ok(json!(i)) }
+            }
+        };
+
+        let results = plan.execute(PlanPolicy::Continue, run).await;
+        assert_eq!(results.iter().map(|r| r.status).collect::<Vec<PlanStatus>>(),
+                   vec![PlanStatus::Finished, PlanStatus::Failed, PlanStatus::Skipped, PlanStatus::Finished]);
+        assert_eq!(results[3].output, Some(json!(3)));
+        // independent entries started before any finished
+        assert_eq!(started.lock().await[..2], [0, 1]);
+
+        let results = plan.execute(PlanPolicy::FailFast, |i| async move {
+            if i == 1 { Err(Erro::AppNotFound) } else { Resul::<Value>::Ok(json!(i)) }
+        }).await;
+        assert_eq!(results.iter().map(|r| r.status).collect::<Vec<PlanStatus>>(),
+                   vec![PlanStatus::Finished, PlanStatus::Failed, PlanStatus::Skipped, PlanStatus::Skipped]);
+    }
+}
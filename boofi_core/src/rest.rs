@@ -0,0 +1,3140 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use axum::error_handling::HandleErrorLayer;
+use axum::extract::{DefaultBodyLimit, Multipart, Path, Query, State};
+use axum::http::{header, HeaderValue, Method, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::{Json, middleware, RequestExt, Router};
+use axum::body::{Body, Bytes, HttpBody, StreamBody};
+use axum::middleware::Next;
+use axum::routing::{any, get, post};
+use base64::Engine;
+use hyper::server::conn::{AddrIncoming, Http};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls::server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient};
+use rustls_pemfile::{certs, pkcs8_private_keys};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, to_value, Value};
+use tokio::net::TcpListener;
+use crate::controller::{Auth, AuthController, Controller, FileRegistry, Permission};
+use crate::error::{Erro, Resul};
+use crate::apps::{AppBuilders, AppHelp};
+use crate::apps::find::FindError;
+use crate::files::{BinaryBuilder, FileBuilder, FileHelp};
+use crate::files::sudoers::SudoersError;
+use crate::description::InvalidField;
+use tokio::sync::{Mutex, Notify, RwLock, Semaphore};
+use tokio_rustls::TlsAcceptor;
+use tracing::Instrument;
+use tower::{BoxError, MakeService, ServiceBuilder, ServiceExt, service_fn};
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::decompression::{DecompressionBody, RequestDecompressionLayer};
+use futures_util::future::{BoxFuture, poll_fn};
+use futures_util::{FutureExt, Stream, StreamExt};
+use hyper::server::accept::Accept;
+use tokio::task::JoinHandle;
+use crate::system::{Credential, DirEntry, FileType, HashAlgorithm, Metadata, RunLimits, System};
+use crate::system::os::Os;
+use crate::task::TaskFilter;
+use crate::batch::{Batch, BatchError};
+use crate::template::{self, Variables};
+use crate::runbook::{Runbook, RunbookError, RunbookRun};
+use crate::jwt::{Jwt, JwtError};
+use crate::plan::{Plan, PlanDependency, PlanEntry, PlanError, PlanPolicy};
+use crate::tls::{certificate_authorities, ClientAuth, ClientUser};
+use crate::patch;
+use crate::secret::REDACTED;
+use crate::patch::JsonPatch;
+use crate::backup::Backup;
+use crate::hook::{self, FileHook};
+use crate::audit::ShellRecordFilter;
+use crate::inventory::Inventory;
+use crate::workspace::{Workspace, WorkspaceItemType};
+use crate::upload::{ChunkedUpload, ContentRange};
+
+pub type SharedController = Arc<Mutex<Controller>>;
+
+/// levels of a recursive directory listing if no depth is given
+const DIR_LIST_DEPTH: usize = 3;
+
+/// app name of directory listings running as task with `?async=true`
+const DIR_LIST_TASK: &str = "dir_list";
+
+/// app name of runbook runs, each run is one task
+const RUNBOOK_TASK: &str = "runbook";
+
+/// content type of responses with `?stream=true`, one json document per line
+const NDJSON: &str = "application/x-ndjson";
+
+/// lines sent before following a file if no tail is given, like `tail -f`
+const FOLLOW_TAIL: usize = 10;
+
+/// a followed file is checked for new lines in this interval
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// requests below this path are forwarded to all or the `targets` services
+const FAN_OUT: &str = "/_all";
+
+/// Header with the id of a request, see [`request_id`]
+const REQUEST_ID: &str = "x-request-id";
+
+/// Header with the local user to run as, used if the `run_as` query is missing
+const RUN_AS: &str = "x-run-as";
+
+/// Used for authentication
+/// run_as: credential used to run programs instead of username and password
+#[derive(Clone)]
+pub(crate) struct UsernamePassword {
+    pub(crate) username: String,
+    pub(crate) password: String,
+    run_as: Option<Credential>,
+}
+
+impl Debug for UsernamePassword {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UsernamePassword")
+            .field("username", &self.username)
+            .field("password", &REDACTED)
+            .field("run_as", &self.run_as)
+            .finish()
+    }
+}
+
+impl From<&ClientUser> for UsernamePassword {
+    fn from(value: &ClientUser) -> Self {
+        Self {
+            username: value.username.clone(),
+            password: value.password.expose().unwrap_or_default().into(),
+            run_as: None,
+        }
+    }
+}
+
+impl From<&UsernamePassword> for Credential {
+    fn from(value: &UsernamePassword) -> Self {
+        match &value.run_as {
+            Some(credential) => credential.clone(),
+            None => Self::new(value.username.as_str(), value.password.as_str()),
+        }
+    }
+}
+
+/// Used to return the bearer token
+/// expires_at: unix timestamp
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TokenResult {
+    pub(crate) token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    client: Option<String>,
+    pub(crate) expires_at: u64,
+}
+
+impl TokenResult {
+    pub(crate) fn new(auth: &Auth, duration: Duration) -> Self {
+        Self {
+            token: auth.token().into(),
+            client: auth.client().map(ToString::to_string),
+            expires_at: auth.expires_at(duration)
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// New token of a verified user, a JWT if signing keys are configured, otherwise a random token which is kept
+    pub(crate) fn issue(auths: &mut AuthController, username: &str, password: &str, client: Option<String>) -> Resul<Self> {
+        let duration = auths.duration();
+
+        match auths.jwt().filter(|jwt| jwt.signs()) {
+            Some(jwt) => {
+                let (token, expires_at) = jwt.issue(username, password, client.as_deref(), duration)?;
+                Ok(Self { token, client, expires_at })
+            }
+            None => Ok(Self::new(auths.insert_or_replace(username.into(), password.into(), client), duration)),
+        }
+    }
+}
+
+/// url query of token requests
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    client: Option<String>,
+}
+
+/// url query of lists which can be sent item by item as newline delimited json
+#[derive(Debug, Deserialize)]
+struct StreamQuery {
+    stream: Option<bool>,
+}
+
+/// url query of a new workspace item, a file by default
+#[derive(Debug, Deserialize)]
+struct WorkspaceQuery {
+    #[serde(default)]
+    r#type: WorkspaceItemType,
+}
+
+/// url query used in app context
+#[derive(Debug, Deserialize)]
+struct AppQuery {
+    r#async: Option<bool>,
+    sudo: Option<bool>,
+    run_as: Option<String>,
+    timeout: Option<u64>,
+    max_output: Option<usize>,
+    callback_url: Option<String>,
+    plan: Option<bool>,
+    on_error: Option<PlanPolicy>,
+    stop_on_error: Option<bool>,
+    rollback: Option<bool>,
+}
+
+/// The request body for each app
+/// id:         name to reference this app in `depends_on` of others, the app name by default
+/// depends_on: ids, app names or indexes of apps which must finish before, only used with `?plan=true`
+#[derive(Debug, Serialize, Deserialize)]
+struct AppsBodyApp {
+    name: String,
+    input: Value,
+    id: Option<String>,
+    depends_on: Option<Vec<PlanDependency>>,
+}
+
+/// Apps of a request, either a list or a template with variables replacing `{{name}}` in the inputs
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AppsBody {
+    Apps(Vec<AppsBodyApp>),
+    Template {
+        #[serde(default)]
+        vars: Variables,
+        apps: Vec<AppsBodyApp>,
+    },
+}
+
+impl AppsBody {
+    fn into_apps(self) -> Resul<Vec<AppsBodyApp>> {
+        match self {
+            AppsBody::Apps(apps) => Ok(apps),
+            AppsBody::Template { vars, apps } => apps.into_iter()
+                .map(|app| Ok(AppsBodyApp { input: template::render(app.input, &vars)?, ..app }))
+                .collect(),
+        }
+    }
+}
+
+/// url query of a runbook run, its steps run with the same user and limits
+#[derive(Debug, Deserialize)]
+struct RunbookQuery {
+    sudo: Option<bool>,
+    run_as: Option<String>,
+    timeout: Option<u64>,
+    max_output: Option<usize>,
+    callback_url: Option<String>,
+}
+
+/// Variables of a runbook run overriding its defaults
+#[derive(Debug, Default, Deserialize)]
+struct RunbookBody {
+    #[serde(default)]
+    vars: Variables,
+}
+
+/// url query of app and file documentation
+#[derive(Debug, Deserialize)]
+struct HelpQuery {
+    schema: Option<bool>,
+}
+
+/// Target type of a file write
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FileQueryType {
+    File,
+    Directory,
+}
+
+/// Server side operation on an existing file or directory
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FileQueryAction {
+    Copy,
+    Move,
+    Restore,
+    Finalize,
+}
+
+impl FileQueryAction {
+    fn name(&self) -> &'static str {
+        match self {
+            FileQueryAction::Copy => "copy",
+            FileQueryAction::Move => "move",
+            FileQueryAction::Restore => "restore",
+            FileQueryAction::Finalize => "finalize",
+        }
+    }
+}
+
+/// Encoding of the content of a file read or write
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FileQueryEncoding {
+    Base64,
+}
+
+/// url query in file context
+#[derive(Debug, Deserialize)]
+struct FileQuery {
+    name: Option<String>,
+    redact: Option<bool>,
+    r#type: Option<FileQueryType>,
+    recursive: Option<bool>,
+    depth: Option<usize>,
+    meta: Option<bool>,
+    checksum: Option<HashAlgorithm>,
+    encoding: Option<FileQueryEncoding>,
+    sudo: Option<bool>,
+    run_as: Option<String>,
+    timeout: Option<u64>,
+    max_output: Option<usize>,
+    offset: Option<usize>,
+    length: Option<usize>,
+    action: Option<FileQueryAction>,
+    target: Option<String>,
+    base: Option<String>,
+    versions: Option<bool>,
+    version: Option<u64>,
+    tail: Option<usize>,
+    follow: Option<bool>,
+    refresh: Option<bool>,
+    validate_only: Option<bool>,
+    dry_run: Option<bool>,
+    partial: Option<bool>,
+    digest: Option<String>,
+    r#async: Option<bool>,
+    callback_url: Option<String>,
+    stream: Option<bool>,
+}
+
+impl FileQuery {
+    /// File module forced by `name` or by the `encoding`
+    fn module(&self) -> Option<&str> {
+        match (self.name.as_deref(), &self.encoding) {
+            (None, Some(FileQueryEncoding::Base64)) => Some(BinaryBuilder::NAME),
+            (name, _) => name,
+        }
+    }
+}
+
+/// checksum of a file computed on the target
+#[derive(Debug, Serialize)]
+struct FileChecksum {
+    path: String,
+    algorithm: HashAlgorithm,
+    checksum: String,
+}
+
+/// request body to change permissions and ownership
+#[derive(Debug, Deserialize)]
+struct FileAttributes {
+    mode: Option<String>,
+    owner: Option<String>,
+    group: Option<String>,
+}
+
+/// used in directory list context
+#[derive(Debug, Serialize)]
+struct DirItemExtended {
+    info: DirItem,
+    metadata: Metadata,
+    managed_by: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    children: Option<Vec<DirItemExtended>>,
+}
+
+/// Authentication middleware
+/// all requests are pre processed within this method
+async fn auth<B>(
+    State(controller): State<SharedController>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Resul<Response> {
+    if request.extensions().get::<UsernamePassword>().is_some() {
+        tracing::debug!("[AUTH][CLIENT CERTIFICATE] processed");
+        Ok(next.run(request).await)
+    } else if let Some(auth) = request.headers().get("authorization") {
+        tracing::trace!("[AUTH] processing");
+        let (user_password, token) = authenticate(&controller, auth.to_str()?).await?;
+
+        if let Some(token) = token {
+            request.extensions_mut().insert(token);
+        }
+
+        tracing::debug!("[AUTH] processed");
+        request.extensions_mut().insert(user_password);
+
+        Ok(next.run(request).await)
+    } else {
+        tracing::debug!("[BASIC_AUTH] sending authentication request");
+
+        let response = next.run(request).await;
+
+        tracing::trace!("[BASIC_AUTH] set header");
+        Ok(Response::builder().header("WWW-Authenticate",
+                                      HeaderValue::from_str(r#"Basic realm="rest api""#)?)
+            .status(StatusCode::UNAUTHORIZED).body(response.into_body())?)
+    }
+}
+
+/// Verifies the value of an `authorization` header, bearer tokens are returned to be refreshed
+#[tracing::instrument(name = "auth", skip_all)]
+pub(crate) async fn authenticate(controller: &SharedController, authorization: &str) -> Resul<(UsernamePassword, Option<TokenResult>)> {
+    let (typ, value) = authorization.split_once(' ').ok_or(Erro::RestAuthMissing)?;
+
+    let authentication = controller.lock().await.authentication().clone();
+
+    let ((username, password), token) = match typ {
+        "Basic" | "basic" => {
+            tracing::trace!("[AUTH][BASIC]");
+            let decoded = base64::engine::general_purpose::STANDARD.decode(value).map(String::from_utf8)??;
+            let (username, password) = decoded.split_once(':').map(|(u, p)| (u.to_string(), p.to_string()))
+                .unwrap_or((decoded.to_string(), Default::default())); // no password provided, assume empty
+
+            authentication.verify(&username, &password).await?;
+            ((username, password), None)
+        }
+        "Bearer" | "bearer" if Jwt::is_jwt(value) => {
+            tracing::trace!("[AUTH][JWT]");
+            let jwt = controller.lock().await.auth().jwt().cloned().ok_or(Erro::AuthNotFound)?;
+            let claims = jwt.verify(value)?;
+
+            if claims.external && authentication.run_as().is_none() {
+                return Err(JwtError::ServiceAccountMissing.into());
+            }
+
+            let token = TokenResult { token: value.into(), client: claims.client, expires_at: claims.expires_at };
+            ((claims.username, claims.password.unwrap_or_default()), Some(token))
+        }
+        "Bearer" | "bearer" => {
+            tracing::trace!("[AUTH][BEARER]");
+            let mut ctrl = controller.lock().await;
+            let auths = ctrl.auth_mut();
+            let duration = auths.duration();
+
+            auths.get(value).map(|a| {
+                ((a.username().to_string(), a.password().to_string()), Some(TokenResult::new(a, duration)))
+            })?
+        }
+        _ => return Err(Erro::RestAuthInvalid)
+    };
+
+    Ok((UsernamePassword {
+        username,
+        password,
+        run_as: authentication.run_as(),
+    }, token))
+}
+
+/// Tracing middleware
+/// every request runs in a span with its id, which is sent back as `X-Request-Id`.
+/// A valid id of the client is kept to correlate the request with its own logs.
+async fn request_id<B>(request: Request<B>, next: Next<B>) -> Response {
+    let id = request.headers().get(REQUEST_ID)
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        .map(ToString::to_string)
+        .unwrap_or_else(|| format!("{:016x}", rand::random::<u64>()));
+
+    let span = tracing::info_span!("request", id = %id, method = %request.method(), path = request.uri().path());
+    let mut response = next.run(request).instrument(span).await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID, value);
+    }
+    response
+}
+
+/// Serialization of json responses, chosen by `?format=` or the `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ResponseFormat {
+    Json,
+    Pretty,
+    Yaml,
+    Toml,
+}
+
+impl ResponseFormat {
+    /// `?format=` takes precedence over the first known media type of `Accept`, json by default
+    fn negotiate<B>(request: &Request<B>) -> Resul<Self> {
+        let query = request.uri().query().unwrap_or_default()
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("format="));
+
+        if let Some(format) = query {
+            return match format {
+                "json" => Ok(ResponseFormat::Json),
+                "pretty" => Ok(ResponseFormat::Pretty),
+                "yaml" => Ok(ResponseFormat::Yaml),
+                "toml" => Ok(ResponseFormat::Toml),
+                _ => Err(Erro::ResponseFormatUnknown(format.into())),
+            };
+        }
+
+        let accept = request.headers().get(header::ACCEPT).and_then(|a| a.to_str().ok()).unwrap_or_default();
+        Ok(accept.split(',')
+            .map(|media| media.split(';').next().unwrap_or_default().trim())
+            .find_map(|media| match media {
+                "application/json" => Some(ResponseFormat::Json),
+                "application/yaml" | "application/x-yaml" | "text/yaml" => Some(ResponseFormat::Yaml),
+                "application/toml" => Some(ResponseFormat::Toml),
+                _ => None,
+            })
+            .unwrap_or(ResponseFormat::Json))
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            ResponseFormat::Json | ResponseFormat::Pretty => "application/json",
+            ResponseFormat::Yaml => "application/yaml",
+            ResponseFormat::Toml => "application/toml",
+        }
+    }
+
+    fn serialize(&self, value: &Value) -> Resul<String> {
+        Ok(match self {
+            ResponseFormat::Json => value.to_string(),
+            ResponseFormat::Pretty => serde_json::to_string_pretty(value)?,
+            ResponseFormat::Yaml => serde_yaml::to_string(value)?,
+            ResponseFormat::Toml => toml::to_string_pretty(value)?,
+        })
+    }
+}
+
+/// Content negotiation middleware
+/// json responses are converted into the requested format, others are sent as they are
+async fn negotiate<B>(request: Request<B>, next: Next<B>) -> Resul<Response> {
+    let format = ResponseFormat::negotiate(&request)?;
+    let response = next.run(request).await;
+
+    let json = response.headers().get(header::CONTENT_TYPE)
+        .map(|c| c.as_bytes().starts_with(b"application/json"))
+        .unwrap_or(false);
+    if format == ResponseFormat::Json || !json {
+        return Ok(response);
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let value: Value = serde_json::from_slice(&hyper::body::to_bytes(body).await?)?;
+    let body = format.serialize(&value)?;
+
+    tracing::trace!("[NEGOTIATE] response converted to {}", format.content_type());
+    parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(format.content_type()));
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Ok(Response::from_parts(parts, axum::body::boxed(Body::from(body))))
+}
+
+/// Response compression and request decompression of a service, disabled by default
+/// gzip:       offer gzip to clients accepting it and decompress gzip request bodies, enabled by default
+/// deflate:    same for deflate, enabled by default
+/// min_size:   smaller responses are sent uncompressed, 1024 bytes by default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    #[serde(default = "CompressionConfig::enabled")]
+    pub gzip: bool,
+    #[serde(default = "CompressionConfig::enabled")]
+    pub deflate: bool,
+    #[serde(default = "CompressionConfig::default_min_size")]
+    pub min_size: u16,
+}
+
+impl CompressionConfig {
+    fn enabled() -> bool {
+        true
+    }
+
+    fn default_min_size() -> u16 {
+        1024
+    }
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            gzip: true,
+            deflate: true,
+            min_size: Self::default_min_size(),
+        }
+    }
+}
+
+/// Connection handling of the rest api, applied after a restart
+/// http2:                  serve HTTP/2 besides HTTP/1.1, without ssl to clients with prior knowledge and with ssl via ALPN, enabled by default
+/// keep_alive:             HTTP/1.1 connections stay open for further requests, enabled by default
+/// keep_alive_interval:    seconds between pings on HTTP/2 connections, disabled by default
+/// keep_alive_timeout:     seconds to wait for the answer of a ping before the connection is closed, 20 by default
+/// tcp_keepalive:          seconds of idleness before tcp keepalive probes are sent, disabled by default
+/// max_connections:        open connections, further clients wait until one is closed, unlimited by default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpConfig {
+    #[serde(default = "HttpConfig::enabled")]
+    pub http2: bool,
+    #[serde(default = "HttpConfig::enabled")]
+    pub keep_alive: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_alive_interval: Option<u64>,
+    #[serde(default = "HttpConfig::default_keep_alive_timeout")]
+    pub keep_alive_timeout: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp_keepalive: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<usize>,
+}
+
+impl HttpConfig {
+    fn enabled() -> bool {
+        true
+    }
+
+    fn default_keep_alive_timeout() -> u64 {
+        20
+    }
+
+    /// Protocol settings of every connection
+    fn protocol(&self) -> Http {
+        let mut protocol = Http::new();
+        protocol.http1_keep_alive(self.keep_alive);
+
+        if self.http2 {
+            protocol.http2_keep_alive_interval(self.keep_alive_interval.map(Duration::from_secs))
+                .http2_keep_alive_timeout(Duration::from_secs(self.keep_alive_timeout));
+        } else {
+            protocol.http1_only(true);
+        }
+
+        protocol
+    }
+
+    /// Protocols offered to ssl clients in their order of preference
+    fn alpn_protocols(&self) -> Vec<Vec<u8>> {
+        if self.http2 {
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        } else {
+            vec![b"http/1.1".to_vec()]
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            http2: true,
+            keep_alive: true,
+            keep_alive_interval: None,
+            keep_alive_timeout: Self::default_keep_alive_timeout(),
+            tcp_keepalive: None,
+            max_connections: None,
+        }
+    }
+}
+
+pub type ServicesConfig = HashMap<String, Router>;
+
+/// Services by name in a stable order for fan-out requests
+type FanOutServices = Arc<Mutex<Vec<(String, Router)>>>;
+
+/// targets:    comma separated service names, all services by default
+#[derive(Debug, Deserialize)]
+struct FanOutQuery {
+    targets: Option<String>,
+}
+
+/// Response of one service to a fan-out request
+/// body:   json or text of the response
+#[derive(Debug, Serialize)]
+struct FanOutResult {
+    success: bool,
+    status: u16,
+    body: Value,
+}
+
+/// Current ssl configuration, used for each new connection
+#[derive(Clone)]
+struct Tls {
+    acceptor: TlsAcceptor,
+    client_auth: Option<Arc<ClientAuth>>,
+}
+
+impl Tls {
+    async fn new(private_key: &str, certificate: &str, client_auth: Option<ClientAuth>, http: &HttpConfig) -> Resul<Self> {
+        let key: PrivateKey = PrivateKey(pkcs8_private_keys(&mut private_key.as_bytes())?.remove(0));
+        let certs: Vec<Certificate> = certs(&mut certificate.as_bytes())?
+            .into_iter()
+            .map(Certificate)
+            .collect();
+
+        tracing::debug!("[REST SSL] prepared");
+
+        let builder = ServerConfig::builder()
+            .with_safe_defaults();
+
+        let mut config = match &client_auth {
+            Some(client_auth) => {
+                let roots = certificate_authorities(&client_auth.ca_path).await?;
+
+                builder.with_client_cert_verifier(if client_auth.required {
+                    AllowAnyAuthenticatedClient::new(roots).boxed()
+                } else {
+                    AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed()
+                })
+            }
+            None => builder.with_no_client_auth(),
+        }.with_single_cert(certs, key)?;
+        config.alpn_protocols = http.alpn_protocols();
+
+        tracing::debug!("[REST SSL] configured");
+
+        Ok(Self {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+            client_auth: client_auth.map(Arc::new),
+        })
+    }
+
+    /// User mapped to the client certificate
+    fn user(&self, certificates: Option<&[Certificate]>) -> Option<UsernamePassword> {
+        match (&self.client_auth, certificates) {
+            (Some(client_auth), Some([certificate, ..])) => match client_auth.user(&certificate.0) {
+                Ok(user) => user.map(Into::into),
+                Err(e) => {
+                    tracing::warn!("[REST SSL] client certificate: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+/// REST API
+/// services and ssl certificate can be replaced while running
+pub struct Rest {
+    address: SocketAddr,
+    max_upload_size: usize,
+    http: HttpConfig,
+    router: Arc<Mutex<Router>>,
+    tls: RwLock<Option<Tls>>,
+}
+
+impl Rest {
+    pub fn new(address: SocketAddr, max_upload_size: usize, http: HttpConfig) -> Self {
+        Self {
+            address,
+            max_upload_size,
+            http,
+            router: Default::default(),
+            tls: Default::default(),
+        }
+    }
+
+    /// Replaces all services, running requests are finished by the previous ones
+    /// The inventory of all services is only served if given.
+    pub async fn set_services(&self, services: ServicesConfig, inventory: Option<Inventory>) {
+        *self.router.lock().await = self.router(services, inventory);
+        tracing::debug!("[SERVICES] services replaced");
+    }
+
+    /// Replaces the certificate and client authentication for new connections
+    pub async fn set_ssl(&self, private_key: &str, certificate: &str, client_auth: Option<ClientAuth>) -> Resul<()> {
+        let mut tls = self.tls.write().await;
+
+        if tls.is_none() {
+            return Err(Erro::RestSslInactive);
+        }
+
+        *tls = Some(Tls::new(private_key, certificate, client_auth, &self.http).await?);
+        tracing::debug!("[REST SSL] certificate replaced");
+        Ok(())
+    }
+
+    /// Creates a new router with the given configuration
+    fn router(&self, services: ServicesConfig, inventory: Option<Inventory>) -> Router {
+        let mut router = Router::new();
+        let mut names: Vec<String> = services.keys().cloned().collect();
+        names.sort();
+        let fan_out: FanOutServices = Arc::new(Mutex::new(names.into_iter()
+            .filter_map(|name| services.get(&name).cloned().map(|service| (name, service)))
+            .collect()));
+
+        for (mut name, service) in services {
+            name.insert(0, '/');
+            router = router.nest(&name, service);
+            tracing::trace!("[START] service {} configured", name);
+        }
+
+        if let Some(inventory) = inventory {
+            router = router.route("/inventory", get(Self::inventory)
+                .layer(middleware::from_fn(negotiate))
+                .with_state(inventory));
+        }
+
+        router.route(&format!("{}/*path", FAN_OUT), any(Self::fan_out)
+            .layer(DefaultBodyLimit::max(self.max_upload_size))
+            .layer(middleware::from_fn(negotiate))
+            .with_state(fan_out))
+            .layer(middleware::from_fn(request_id))
+    }
+
+    /// Services with labels, detected os and reachability
+    async fn inventory(State(inventory): State<Inventory>) -> Response {
+        Json(inventory.list().await).into_response()
+    }
+
+    /// Forwards the request to several services concurrently, results are keyed by service name.
+    /// Authentication is done by each service, tokens are only valid for the service which issued them.
+    async fn fan_out(State(services): State<FanOutServices>,
+                     Path(path): Path<String>,
+                     Query(query): Query<FanOutQuery>,
+                     request: Request<Body>) -> Resul<Response> {
+        let services = services.lock().await.clone();
+        let targets: Option<Vec<&str>> = query.targets.as_deref().map(|t| t.split(',').map(str::trim).collect());
+
+        if let Some(target) = targets.iter().flatten().find(|t| !services.iter().any(|(name, _)| name == *t)) {
+            return Err(Erro::FanOutTargetNotFound(target.to_string()));
+        }
+
+        // targets and format are only meant for the fan-out itself
+        let forwarded_query = request.uri().query().unwrap_or_default()
+            .split('&')
+            .filter(|p| !p.is_empty() && !p.starts_with("targets=") && !p.starts_with("format="))
+            .collect::<Vec<&str>>()
+            .join("&");
+
+        let (parts, body) = request.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        let user = parts.extensions.get::<UsernamePassword>().cloned();
+
+        tracing::debug!("[FAN OUT] {} /{} to {:?}", parts.method, path, targets);
+
+        let requests = services.iter()
+            .filter(|(name, _)| targets.as_ref().map(|t| t.contains(&name.as_str())).unwrap_or(true))
+            .map(|(name, service)| {
+                let uri = if forwarded_query.is_empty() {
+                    format!("/{}", path)
+                } else {
+                    format!("/{}?{}", path, forwarded_query)
+                };
+
+                let mut request = Request::builder()
+                    .method(parts.method.clone())
+                    .uri(uri)
+                    .body(Body::from(body.clone()));
+
+                if let Ok(request) = request.as_mut() {
+                    *request.headers_mut() = parts.headers.clone();
+                    // results are collected as json without compression
+                    request.headers_mut().remove(header::ACCEPT);
+                    request.headers_mut().remove(header::ACCEPT_ENCODING);
+                    if let Some(user) = &user {
+                        request.extensions_mut().insert(user.clone());
+                    }
+                }
+
+                let service = service.clone();
+                async move {
+                    let response = match request {
+                        Ok(request) => service.oneshot(request).await.into_response(),
+                        Err(e) => Erro::from(e).into_response(),
+                    };
+
+                    (name.clone(), Self::fan_out_result(response).await)
+                }
+            });
+
+        let mut results = serde_json::Map::new();
+        for (name, result) in futures_util::future::join_all(requests).await {
+            results.insert(name, to_value(result)?);
+        }
+
+        Ok(Json(results).into_response())
+    }
+
+    async fn fan_out_result(response: Response) -> FanOutResult {
+        let status = response.status();
+
+        match hyper::body::to_bytes(response.into_body()).await {
+            Ok(body) => FanOutResult {
+                success: status.is_success(),
+                status: status.as_u16(),
+                body: serde_json::from_slice(&body).unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).to_string())),
+            },
+            Err(e) => FanOutResult {
+                success: false,
+                status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                body: Value::String(e.to_string()),
+            },
+        }
+    }
+
+    /// Every request is passed to the current services
+    fn app(&self) -> Router {
+        let router = self.router.clone();
+
+        Router::new().fallback_service(service_fn(move |request: Request<Body>| {
+            let router = router.clone();
+            async move {
+                let current = router.lock().await.clone();
+                current.oneshot(request).await
+            }
+        }))
+    }
+
+    /// Starts all services
+    pub async fn start(&self) -> Resul<()> {
+        tracing::debug!("[START] starting server");
+        self.serve(false).await
+    }
+
+    /// Starts all services but with https
+    /// users mapped to client certificates are authenticated without basic auth
+    pub async fn ssl(&self, private_key: &str, certificate: &str, client_auth: Option<ClientAuth>) -> Resul<()> {
+        *self.tls.write().await = Some(Tls::new(private_key, certificate, client_auth, &self.http).await?);
+        tracing::debug!("[REST SSL] router configured");
+        self.serve(true).await
+    }
+
+    /// Accepts connections until the listener fails, each connection is served by its own task.
+    /// A connection is only accepted while less than `max_connections` are open.
+    async fn serve(&self, ssl: bool) -> Resul<()> {
+        let mut listener = AddrIncoming::from_listener(
+            TcpListener::bind(self.address).await?)?;
+        listener.set_keepalive(self.http.tcp_keepalive.map(Duration::from_secs));
+
+        let protocol = Arc::new(self.http.protocol());
+        let connections = self.http.max_connections.map(|max| Arc::new(Semaphore::new(max)));
+
+        let mut app = self.app().into_make_service();
+
+        loop {
+            let permit = match &connections {
+                Some(connections) => connections.clone().acquire_owned().await.ok(),
+                None => None,
+            };
+
+            match poll_fn(|cx| Pin::new(&mut listener).poll_accept(cx)).await {
+                None => {}
+                Some(result) => {
+                    let stream = result?;
+
+                    tracing::trace!("[REST] connection accepted");
+
+                    let tls = if ssl {
+                        Some(self.tls.read().await.clone().ok_or(Erro::RestSslInactive)?)
+                    } else {
+                        None
+                    };
+                    let protocol = protocol.clone();
+
+                    let svc = MakeService::make_service(&mut app, &stream);
+
+                    let _a: JoinHandle<Resul<()>> = tokio::spawn(async move {
+                        let _permit = permit;
+
+                        let Some(tls) = tls else {
+                            tracing::trace!("[REST] serve connection");
+                            let _ = protocol.serve_connection(stream, svc.await?).await;
+                            return Ok(());
+                        };
+
+                        match tls.acceptor.accept(stream).await {
+                            Ok(stream) => {
+                                tracing::trace!("[REST SSL] serve connection");
+                                let user = tls.user(stream.get_ref().1.peer_certificates());
+                                let svc = svc.await?.map_request(move |mut request: Request<Body>| {
+                                    if let Some(user) = &user {
+                                        request.extensions_mut().insert(user.clone());
+                                    }
+                                    request
+                                });
+                                let _ = protocol.serve_connection(stream, svc).await;
+                            }
+                            Err(e) => {
+                                tracing::error!("[REST SSL] {:?}", e);
+                            }
+                        }
+                        Ok(())
+                    });
+                }
+            }
+        }
+    }
+
+    /// Creates all routes with their handlers
+    fn routes() -> Router<SharedController> {
+        Router::new()
+            .route("/token", any(Self::token_get_delete))
+            .route("/token/refresh", post(Self::token_refresh))
+            .route("/tasks", get(Self::tasks_get))
+            .route("/tasks/:id", get(Self::tasks_get))
+            .route("/audit/shell", get(Self::audit_shell))
+            .route("/workspace", get(Self::workspace_get).post(Self::workspace_post))
+            .route("/workspace/:name", axum::routing::delete(Self::workspace_delete))
+            .route("/capabilities", get(Self::capabilities))
+            .route("/runbooks", get(Self::runbooks_get))
+            .route("/runbooks/:name", get(Self::runbook_get).put(Self::runbook_put).delete(Self::runbook_delete))
+            .route("/runbooks/:name/run", post(Self::runbook_run))
+            .route("/apps", get(Self::apps_help))
+            .route("/apps", post(Self::apps_post))
+            .route("/apps/:name", post(Self::app_post))
+            .route("/files", get(Self::files_help))
+            .route("/files/", get(Self::files_get_post_delete))
+            .route("/files/*key", any(Self::files_get_post_delete))
+    }
+
+    /// New single service with its own controller
+    pub fn new_service(&self, shared_controller: SharedController, compression: Option<&CompressionConfig>) -> Router<()> {
+        tracing::trace!("[NEW SERVICE] configure routes");
+
+        let router = Self::routes()
+            .with_state(shared_controller.clone())
+            .layer(middleware::from_fn_with_state(shared_controller, auth))
+            .layer(DefaultBodyLimit::max(self.max_upload_size))
+            .layer(middleware::from_fn(negotiate));
+
+        match compression {
+            Some(compression) => Self::compress(router, compression),
+            None => router,
+        }
+    }
+
+    /// Service which failed to start, every request responds with `503` and asks for another start with `retry`
+    pub fn degraded_service(&self, name: &str, error: &str, retry: Arc<Notify>) -> Router<()> {
+        let (name, error) = (name.to_string(), error.to_string());
+        let degraded = move || {
+            tracing::debug!("[DEGRADED] requesting start of service {}", name);
+            retry.notify_one();
+            let error = Erro::ServiceDegraded(name.clone(), error.clone());
+            async move { error.into_response() }
+        };
+
+        Router::new()
+            .route("/", any(degraded.clone()))
+            .route("/*path", any(degraded))
+            .layer(middleware::from_fn(negotiate))
+    }
+
+    /// Compresses responses and decompresses request bodies, server sent events are not compressed
+    fn compress(router: Router<()>, compression: &CompressionConfig) -> Router<()> {
+        let predicate = SizeAbove::new(compression.min_size)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::IMAGES)
+            .and(NotForContentType::const_new("text/event-stream"));
+
+        router
+            .layer(ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(|error: BoxError| async move { Erro::Compression(error.to_string()) }))
+                .layer(RequestDecompressionLayer::new().gzip(compression.gzip).deflate(compression.deflate))
+                .map_request(|request: Request<DecompressionBody<Body>>| request.map(|body| {
+                    Body::wrap_stream(futures_util::stream::unfold(Box::pin(body), |mut body| async move {
+                        body.data().await.map(|data| (data, body))
+                    }))
+                })))
+            .layer(CompressionLayer::new()
+                .gzip(compression.gzip)
+                .deflate(compression.deflate)
+                .compress_when(predicate))
+    }
+
+    async fn token_get_delete(Query(query): Query<TokenQuery>,
+                              State(controller): State<SharedController>,
+                              request: Request<Body>) -> Resul<Response> {
+        match *request.method() {
+            Method::GET => {
+                let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+
+                tracing::debug!("[TOKEN GET] verify credential");
+                let system_manager = controller.lock().await.system_manager();
+                let system = system_manager.system_credential(user_password.into()).await?;
+                system.verify_credential().await?;
+                tracing::debug!("[TOKEN GET] credential verified");
+
+                let mut ctrl = controller.lock().await;
+                Ok(Json(TokenResult::issue(ctrl.auth_mut(),
+                                           &user_password.username,
+                                           &user_password.password,
+                                           query.client)?).into_response())
+            }
+            Method::DELETE => {
+                let mut ctrl = controller.lock().await;
+                let token: &TokenResult = request.extensions()
+                    .get()
+                    .ok_or(Erro::RestAuthMissing)?;
+
+                let deleted = match &query.client {
+                    Some(client) => {
+                        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+                        ctrl.auth_mut().delete_client(&user_password.username, client)
+                    }
+                    None if Jwt::is_jwt(&token.token) => return Err(JwtError::RevokeUnsupported.into()),
+                    None => ctrl.auth_mut().delete(&token.token),
+                };
+
+                Ok(if deleted {
+                    tracing::debug!("[TOKEN DELETE] token deleted");
+                    StatusCode::ACCEPTED
+                } else {
+                    tracing::debug!("[TOKEN DELETE] token does not exist");
+                    StatusCode::OK
+                }.into_response())
+            }
+            _ => Err(Erro::HttpMethodNotAllowed(request.method().clone()))
+        }
+    }
+
+    /// Restart the expiration of the used bearer token, a JWT is replaced by a new one
+    async fn token_refresh(State(controller): State<SharedController>, request: Request<Body>) -> Resul<Json<TokenResult>> {
+        let token: &TokenResult = request.extensions()
+            .get()
+            .ok_or(Erro::RestAuthMissing)?;
+
+        let mut ctrl = controller.lock().await;
+        let auths = ctrl.auth_mut();
+        let duration = auths.duration();
+
+        if let Some(jwt) = auths.jwt().filter(|_| Jwt::is_jwt(&token.token)) {
+            let claims = jwt.verify(&token.token)?;
+            if claims.external {
+                return Err(JwtError::RefreshUnsupported.into());
+            }
+
+            tracing::debug!("[TOKEN REFRESH] jwt issued again");
+            let (refreshed, expires_at) = jwt.issue(&claims.username, &claims.password.unwrap_or_default(), claims.client.as_deref(), duration)?;
+            return Ok(Json(TokenResult { token: refreshed, client: claims.client, expires_at }));
+        }
+
+        tracing::debug!("[TOKEN REFRESH] token refreshed");
+        Ok(Json(TokenResult::new(auths.refresh(&token.token)?, duration)))
+    }
+
+    async fn apps_help(Query(query): Query<HelpQuery>,
+                       State(controller): State<SharedController>,
+                       request: Request<Body>) -> Resul<Response> {
+        tracing::trace!("[APPS HELP] getting authentication");
+        let user_password: &UsernamePassword = request.extensions()
+            .get()
+            .ok_or(Erro::RestAuthMissing)?;
+
+        let system_manager = controller.lock().await.system_manager();
+        let os = system_manager.system_credential(user_password.into()).await?.os()?.clone();
+        tracing::debug!("[APPS HELP] sending help");
+
+        Ok(Json(controller.lock().await.apps().map(|app| {
+            let help = app.help(&os);
+            if query.schema == Some(true) { help.into_schema() } else { help }
+        }).collect::<Vec<AppHelp>>()).into_response())
+    }
+
+    async fn capabilities(State(controller): State<SharedController>, request: Request<Body>) -> Resul<Response> {
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        let system_manager = controller.lock().await.system_manager();
+        let os = system_manager.system_credential(user_password.into()).await?.os()?.clone();
+
+        tracing::debug!("[CAPABILITIES] sending capabilities for {:?}", os);
+        Ok(Json(controller.lock().await.capabilities(&os)).into_response())
+    }
+
+    async fn runbooks_get(State(controller): State<SharedController>, request: Request<Body>) -> Resul<Response> {
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        let runbooks = {
+            let controller = controller.lock().await;
+            controller.authorization().check(&user_password.username, Permission::RunApps)?;
+            controller.runbooks()
+        };
+
+        tracing::debug!("[RUNBOOKS GET] listing runbooks");
+        Ok(Json(runbooks.list().await).into_response())
+    }
+
+    async fn runbook_get(Path(name): Path<String>,
+                         State(controller): State<SharedController>,
+                         request: Request<Body>) -> Resul<Response> {
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        let runbooks = {
+            let controller = controller.lock().await;
+            controller.authorization().check(&user_password.username, Permission::RunApps)?;
+            controller.runbooks()
+        };
+
+        tracing::debug!("[RUNBOOKS GET] sending runbook {}", name);
+        Ok(Json(runbooks.get(&name).await?).into_response())
+    }
+
+    async fn runbook_put(Path(name): Path<String>,
+                         State(controller): State<SharedController>,
+                         mut request: Request<Body>) -> Resul<Response> {
+        let runbook = serde_json::from_slice::<Runbook>(&request.body_mut().data().await.ok_or(Erro::AppBodyMissing)??)?;
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        let runbooks = {
+            let controller = controller.lock().await;
+            controller.authorization().check(&user_password.username, Permission::ManageRunbooks)?;
+            controller.runbooks()
+        };
+
+        tracing::debug!("[RUNBOOKS PUT] saving runbook {}", name);
+        runbooks.insert(&name, runbook).await?;
+        Ok(StatusCode::ACCEPTED.into_response())
+    }
+
+    async fn runbook_delete(Path(name): Path<String>,
+                            State(controller): State<SharedController>,
+                            request: Request<Body>) -> Resul<Response> {
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        let runbooks = {
+            let controller = controller.lock().await;
+            controller.authorization().check(&user_password.username, Permission::ManageRunbooks)?;
+            controller.runbooks()
+        };
+
+        tracing::debug!("[RUNBOOKS DELETE] removing runbook {}", name);
+        runbooks.remove(&name).await?;
+        Ok(StatusCode::ACCEPTED.into_response())
+    }
+
+    /// Checks the steps with the variables of the body and runs them as task
+    async fn runbook_run(Path(name): Path<String>,
+                         Query(query): Query<RunbookQuery>,
+                         State(controller): State<SharedController>,
+                         mut request: Request<Body>) -> Resul<Response> {
+        let body = match request.body_mut().data().await {
+            Some(bytes) => serde_json::from_slice::<RunbookBody>(&bytes?)?,
+            None => RunbookBody::default(),
+        };
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        let runbook = controller.lock().await.runbooks().get(&name).await?;
+
+        let system_manager = controller.lock().await.system_manager();
+        let mut system = system_manager.system_credential(user_password.into()).await?;
+        let run = {
+            let controller = controller.lock().await;
+            let run = RunbookRun::new(&runbook, &body.vars, &controller, &system)?;
+            for permission in run.permissions() {
+                controller.authorization().check(&user_password.username, permission)?;
+            }
+            Self::switch_user(&controller, &user_password.username, &mut system, query.sudo, Self::run_as(&query.run_as, &request))?;
+            Self::record_shell(&controller, &user_password.username, &mut system, query.sudo, Self::run_as(&query.run_as, &request));
+            run
+        };
+        system.restrict_limits(&RunLimits::new(query.timeout, query.max_output))?;
+
+        tracing::debug!("[RUNBOOK RUN] running runbook {} as task", name);
+        let input = json!({"name": &name, "vars": &body.vars});
+        let execution = async move { Ok(to_value(run.execute(&system).await)?) };
+        Ok(Json(controller.lock().await.task_controller_mut().new_task_future(RUNBOOK_TASK, input, execution, query.callback_url).await?).into_response())
+    }
+
+    async fn tasks_get(id: Option<Path<usize>>,
+                       Query(filter): Query<TaskFilter>,
+                       Query(stream): Query<StreamQuery>,
+                       State(controller): State<SharedController>,
+                       request: Request<Body>) -> Resul<Response> {
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        let system_manager = {
+            let ctrl = controller.lock().await;
+            ctrl.authorization().check(&user_password.username, Permission::ManageTasks)?;
+            ctrl.system_manager()
+        };
+        let system = system_manager.system_credential(user_password.into()).await?;
+        system.verify_credential().await?;
+
+        let ctrl = controller.lock().await;
+        let task_ctrl = ctrl.task_controller();
+
+        if let Some(i) = id {
+            tracing::trace!("[TASKS GET] searching for task {}", *i);
+            if let Some(task) = task_ctrl.tasks().lock().await.iter().find(|j| j.id() == *i) {
+                Ok(Json(task).into_response())
+            } else {
+                Err(Erro::TaskNotFound)
+            }
+        } else if stream.stream == Some(true) {
+            tracing::debug!("[TASKS GET] streaming tasks with {:?}", filter);
+            Ok(Self::ndjson(task_ctrl.stream(&filter).await))
+        } else {
+            tracing::debug!("[TASKS GET] listing tasks with {:?}", filter);
+            Ok(Json(task_ctrl.list(&filter).await?).into_response())
+        }
+    }
+
+    async fn audit_shell(Query(filter): Query<ShellRecordFilter>,
+                         State(controller): State<SharedController>,
+                         request: Request<Body>) -> Resul<Response> {
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        let recorder = {
+            let ctrl = controller.lock().await;
+            ctrl.authorization().check(&user_password.username, Permission::ReadAudit)?;
+            ctrl.shell_recorder()
+        };
+
+        if !recorder.enabled() {
+            return Err(Erro::ShellRecordingDisabled);
+        }
+
+        tracing::debug!("[AUDIT SHELL] listing records with {:?}", filter);
+        Ok(Json(recorder.records(&filter)).into_response())
+    }
+
+    /// System of the user with the permission and the workspace of the service
+    async fn workspace_system(controller: &SharedController, request: &Request<Body>, permission: Permission) -> Resul<(Workspace, System)> {
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        let (workspace, system_manager) = {
+            let ctrl = controller.lock().await;
+            ctrl.authorization().check(&user_password.username, permission)?;
+            (ctrl.workspace().clone(), ctrl.system_manager())
+        };
+
+        Ok((workspace, system_manager.system_credential(user_password.into()).await?))
+    }
+
+    async fn workspace_get(State(controller): State<SharedController>, request: Request<Body>) -> Resul<Response> {
+        let (workspace, system) = Self::workspace_system(&controller, &request, Permission::ReadFiles).await?;
+        tracing::debug!("[WORKSPACE GET] listing {}", workspace.dir());
+        Ok(Json(workspace.items(&system).await?).into_response())
+    }
+
+    async fn workspace_post(Query(query): Query<WorkspaceQuery>,
+                            State(controller): State<SharedController>,
+                            request: Request<Body>) -> Resul<Response> {
+        let (workspace, system) = Self::workspace_system(&controller, &request, Permission::WriteFiles).await?;
+        tracing::debug!("[WORKSPACE POST] creating {:?} in {}", query.r#type, workspace.dir());
+        Ok(Json(workspace.create(query.r#type, &system).await?).into_response())
+    }
+
+    async fn workspace_delete(Path(name): Path<String>,
+                              State(controller): State<SharedController>,
+                              request: Request<Body>) -> Resul<Response> {
+        let (workspace, system) = Self::workspace_system(&controller, &request, Permission::WriteFiles).await?;
+        workspace.remove(&name, &system).await?;
+        Ok(StatusCode::ACCEPTED.into_response())
+    }
+
+    async fn apps_post(
+        Query(query): Query<AppQuery>,
+        State(controller): State<SharedController>,
+        mut request: Request<Body>) -> Resul<Response> {
+        tracing::trace!("[APPS POST] processing body request");
+        let apps = serde_json::from_slice::<AppsBody>(&request.body_mut().data().await.ok_or(Erro::AppBodyMissing)??)?.into_apps()?;
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        controller.lock().await.authorization().check(&user_password.username, Permission::RunApps)?;
+
+        // find apps
+        let mut inputs_and_builders: Vec<(AppsBodyApp, AppBuilders)> = vec![];
+
+        let system_manager = controller.lock().await.system_manager();
+        let mut system = system_manager.system_credential(user_password.into()).await?;
+        let os = system.os()?.clone();
+
+        tracing::debug!("[APPS POST] checking apps {} compatibility", apps.iter().map(|a| a.name.clone()).collect::<Vec<String>>().join(","));
+        for app_body in apps {
+            if let Some(app_builder) = controller.lock().await.app(&app_body.name) {
+                if app_builder.compatible(&os) {
+                    app_builder.validate(&app_body.input)?;
+                    inputs_and_builders.push((app_body, app_builder.clone()));
+                } else {
+                    tracing::error!("[APPS POST] app {} incompatible", app_builder.name());
+                    return Err(Erro::AppIncompatible);
+                }
+            } else {
+                tracing::error!("[APPS POST] app {} not found", app_body.name);
+                return Err(Erro::AppNotFound);
+            }
+        }
+
+        Self::switch_user(&*controller.lock().await, &user_password.username, &mut system, query.sudo, Self::run_as(&query.run_as, &request))?;
+        Self::record_shell(&*controller.lock().await, &user_password.username, &mut system, query.sudo, Self::run_as(&query.run_as, &request));
+        system.restrict_limits(&RunLimits::new(query.timeout, query.max_output))?;
+
+        if query.plan == Some(true) {
+            if query.r#async == Some(true) {
+                return Err(PlanError::AsyncUnsupported.into());
+            }
+
+            return Self::apps_plan(inputs_and_builders, query.on_error.unwrap_or_default(), &system).await;
+        }
+
+        if query.stop_on_error == Some(true) || query.rollback == Some(true) {
+            if query.r#async == Some(true) {
+                return Err(BatchError::AsyncUnsupported.into());
+            }
+
+            let batch = Batch::new(query.stop_on_error == Some(true), query.rollback == Some(true));
+            let results = batch.execute(inputs_and_builders.len(), &system, |i| {
+                let (app_body, managed_app) = &inputs_and_builders[i];
+                let mut managed_app = managed_app.clone();
+                tracing::debug!("[APPS POST] running app {} of batch", app_body.name);
+
+                let system = &system;
+                async move { managed_app.run_undoable(app_body.input.clone(), system).await }
+            }).await;
+
+            return Ok(Json(results).into_response());
+        }
+
+        // run apps (a)sync
+        let mut results = vec![];
+        for (app_body, mut managed_app) in inputs_and_builders {
+            if query.r#async == Some(true) {
+                tracing::debug!("[APPS POST] running app {} asynchronous", app_body.name);
+
+                results.push(controller.lock().await.task_controller_mut()
+                    .new_task(managed_app, app_body.input, system.clone(), query.callback_url.clone()).await?);
+            } else {
+                tracing::debug!("[APPS POST] running app {}", app_body.name);
+                results.push(to_value(managed_app.run(app_body.input, &system).await?)?);
+            }
+        }
+
+        Ok(Json(results).into_response())
+    }
+
+    /// Runs independent apps concurrently and dependent ones after their dependencies
+    async fn apps_plan(apps: Vec<(AppsBodyApp, AppBuilders)>, policy: PlanPolicy, system: &System) -> Resul<Response> {
+        let plan = Plan::new(&apps.iter().map(|(app_body, _)| PlanEntry {
+            name: &app_body.name,
+            id: app_body.id.as_deref(),
+            depends_on: app_body.depends_on.as_deref().unwrap_or_default(),
+        }).collect::<Vec<PlanEntry>>())?;
+
+        let results = plan.execute(policy, |i| {
+            let (app_body, managed_app) = &apps[i];
+            let mut managed_app = managed_app.clone();
+            tracing::debug!("[APPS POST] running planned app {}", app_body.name);
+
+            async move {
+                to_value(managed_app.run(app_body.input.clone(), system).await?).map_err(Into::into)
+            }
+        }).await;
+
+        Ok(Json(results).into_response())
+    }
+
+    async fn app_post(
+        name: Path<String>,
+        Query(query): Query<AppQuery>,
+        State(controller): State<SharedController>,
+        mut request: Request<Body>) -> Resul<Response> {
+        tracing::trace!("[APP POST] processing body request");
+        let value = serde_json::from_slice::<Value>(&request.body_mut().data().await.ok_or(Erro::AppBodyMissing)??)?;
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        controller.lock().await.authorization().check(&user_password.username, Permission::RunApps)?;
+
+        let system_manager = controller.lock().await.system_manager();
+        let mut system = system_manager.system_credential(user_password.into()).await?;
+        Self::switch_user(&*controller.lock().await, &user_password.username, &mut system, query.sudo, Self::run_as(&query.run_as, &request))?;
+        Self::record_shell(&*controller.lock().await, &user_password.username, &mut system, query.sudo, Self::run_as(&query.run_as, &request));
+        system.restrict_limits(&RunLimits::new(query.timeout, query.max_output))?;
+        let os = system.os()?.clone();
+
+        let app_builder = controller.lock().await.app(name.0.as_str()).cloned();
+        if let Some(mut app_builder) = app_builder {
+            if !app_builder.compatible(&os) {
+                tracing::error!("[APP POST] app incompatible");
+                return Err(Erro::AppIncompatible);
+            }
+
+            app_builder.validate(&value)?;
+
+            if query.r#async == Some(true) {
+                tracing::debug!("[APP POST] running app asynchronous");
+                return Ok(Json(controller.lock().await.task_controller_mut().new_task(app_builder, value, system, query.callback_url.clone()).await?).into_response());
+            } else {
+                tracing::debug!("[APP POST] running app");
+                return Ok(Json(app_builder.run(value, &system).await?).into_response());
+            }
+        }
+        tracing::error!("[APP POST] no app found");
+
+        Err(Erro::AppNotFound)
+    }
+
+    async fn files_help(Query(query): Query<HelpQuery>,
+                        State(controller): State<SharedController>) -> Resul<Response> {
+        tracing::debug!("[FILES HELP] sending help");
+        let ctrl = controller.lock().await;
+        Ok(Json(ctrl.file_builders().iter().map(|file| {
+            let help = file.help();
+            if query.schema == Some(true) { help.into_schema() } else { help }
+        }).collect::<Vec<FileHelp>>()).into_response())
+    }
+
+    async fn files_get_post_delete(key: Option<Path<String>>,
+                                   query: Query<FileQuery>,
+                                   State(controller): State<SharedController>,
+                                   request: Request<Body>) -> Resul<Response> {
+        let p = format!("/{}", key.as_deref().unwrap_or(&String::default()));
+        tracing::debug!("[FILES GET/POST/PUT/PATCH/DELETE] processing for {}", &p);
+
+        let user_password: &UsernamePassword = request.extensions().get().ok_or(Erro::RestAuthMissing)?;
+        let method = request.method().clone();
+
+        controller.lock().await.authorization().check(&user_password.username, if method == Method::GET {
+            Permission::ReadFiles
+        } else {
+            Permission::WriteFiles
+        })?;
+
+        let system_manager = controller.lock().await.system_manager();
+        let mut system = system_manager.system_credential(user_password.into()).await?;
+        Self::switch_user(&*controller.lock().await, &user_password.username, &mut system, query.sudo, Self::run_as(&query.run_as, &request))?;
+        system.restrict_limits(&RunLimits::new(query.timeout, query.max_output))?;
+        let os = system.os()?.clone();
+
+        if method == Method::GET && query.meta == Some(true) {
+            tracing::debug!("[FILES GET] sending metadata of {}", &p);
+            return Ok(Json(system.metadata(&p).await?).into_response());
+        }
+
+        if let (&Method::GET, Some(algorithm)) = (&method, query.checksum) {
+            tracing::debug!("[FILES GET] sending {:?} checksum of {}", algorithm, &p);
+            let checksum = system.checksum(&p, algorithm).await?;
+            return Ok(Json(FileChecksum { path: p, algorithm, checksum }).into_response());
+        }
+
+        if method == Method::GET && query.partial == Some(true) {
+            tracing::debug!("[FILES GET] sending chunked upload status of {}", &p);
+            let workspace = controller.lock().await.workspace().clone();
+            return Ok(Json(ChunkedUpload::new(&p, &user_password.username, &workspace).status(&system).await?).into_response());
+        }
+
+        if method == Method::GET && query.versions == Some(true) {
+            tracing::debug!("[FILES GET] sending backup versions of {}", &p);
+            let backup = controller.lock().await.backup().cloned().ok_or(Erro::BackupDisabled)?;
+            return Ok(Json(backup.versions(&p, &system).await?).into_response());
+        }
+
+        if method == Method::GET && query.follow == Some(true) {
+            tracing::debug!("[FILES GET] following {}", &p);
+            return Ok(Self::follow(p, query.tail.unwrap_or(FOLLOW_TAIL), system).await?.into_response());
+        }
+
+        if let (&Method::GET, Some(lines)) = (&method, query.tail) {
+            tracing::debug!("[FILES GET] sending last {} lines of {}", lines, &p);
+            return Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], system.tail(&p, lines).await?).into_response());
+        }
+
+        if method == Method::GET && (query.offset.is_some() || query.length.is_some()) {
+            tracing::debug!("[FILES GET] sending raw content of {}", &p);
+            let content = system.read_range(&p, query.offset.unwrap_or_default(), query.length).await?;
+
+            // the hash of the whole content is the base of patches
+            if query.offset.unwrap_or_default() == 0 && query.length.is_none() {
+                return Ok(([(header::CONTENT_TYPE, "application/octet-stream".to_string()), (header::ETAG, Self::etag(&patch::sha256(&content)))], content).into_response());
+            }
+
+            return Ok(([(header::CONTENT_TYPE, "application/octet-stream")], content).into_response());
+        }
+
+        // the type of the target system path, symbolic links are followed
+        if method == Method::GET && !system.path_exist(&p).await? {
+            return Err(Erro::PathNotFound(p));
+        }
+
+        if method == Method::GET && system.target_file_type(&p).await? == FileType::Directory {
+            let depth = if query.recursive == Some(true) {
+                let max = controller.lock().await.max_list_depth();
+                let depth = query.depth.unwrap_or(DIR_LIST_DEPTH.min(max));
+                if depth > max {
+                    return Err(Erro::ListDepthTooLarge(depth, max));
+                }
+                depth
+            } else {
+                1
+            };
+
+            tracing::debug!("[FILES GET] listing directories and files in {} with depth {}", &p, depth);
+            let files = controller.lock().await.file_registry();
+
+            if query.r#async == Some(true) {
+                tracing::debug!("[FILES GET] listing {} as task", &p);
+                let input = json!({"path": &p, "depth": depth});
+                let listing = async move {
+                    Ok(to_value(Self::dir_list_extended(&p, depth, &system, &files, &os).await?)?)
+                };
+                return Ok(Json(controller.lock().await.task_controller_mut().new_task_future(DIR_LIST_TASK, input, listing, query.callback_url.clone()).await?).into_response());
+            }
+
+            if query.stream == Some(true) {
+                tracing::debug!("[FILES GET] streaming list for {}", &p);
+                return Ok(Self::ndjson(Self::dir_list_stream(p, depth, system, files, os).await?));
+            }
+
+            let items = Self::dir_list_extended(&p, depth, &system, &files, &os).await?;
+
+            tracing::debug!("[FILES GET] sending list for {}", &p);
+            return Ok(Json(items).into_response());
+        };
+
+        // held until the change is done, a concurrent change of the same path would be lost
+        let _lock = if method != Method::GET {
+            let locks = controller.lock().await.file_locks();
+            let lock = locks.lock(&p).await?;
+            Self::check_if_match(&request, &p, &system).await?;
+            Some(lock)
+        } else {
+            None
+        };
+
+        // the controller is not locked while reading or writing
+        let (files, backup, hooks, workspace) = {
+            let ctrl = controller.lock().await;
+            (ctrl.file_registry(), ctrl.backup().cloned(), ctrl.file_hooks().to_vec(), ctrl.workspace().clone())
+        };
+        let upload = ChunkedUpload::new(&p, &user_password.username, &workspace);
+
+        macro_rules! get_file {
+            () => {
+                if let Some(name) = query.module() {
+                    files.by_name(name)?
+                } else {
+                    files.by_match(&p, &system)?
+                }
+            };
+        }
+
+        if method == Method::GET {
+            let redacted = query.redact == Some(true);
+            let refresh = query.refresh == Some(true) || Self::no_cache(&request);
+
+            let cached = controller.lock().await.file_cache_mut().get(&p, query.module(), redacted);
+            if let Some(entry) = cached.filter(|_| !refresh) {
+                tracing::debug!("[FILES GET] sending cached file {}", &p);
+                let mut response = ([(header::AGE, entry.age().to_string())], Json(entry.content)).into_response();
+                if let Some(etag) = entry.etag {
+                    response.headers_mut().insert(header::ETAG, HeaderValue::from_str(&etag)?);
+                }
+                return Ok(response);
+            }
+
+            let file = get_file!();
+            let cacheable = file.cacheable();
+            tracing::debug!("[FILES GET] getting file {}", &p);
+            let content = if redacted {
+                Json(file.read_redacted(&p, &system).await?)
+            } else {
+                Json(file.read(&p, &system).await?)
+            };
+
+            let etag = match system.hash(&p).await {
+                Ok(hash) => Some(Self::etag(&hash)),
+                Err(e) => {
+                    tracing::debug!("[FILES GET] no etag for {}: {}", &p, e);
+                    None
+                }
+            };
+
+            if cacheable {
+                controller.lock().await.file_cache_mut().insert(&p, query.module(), redacted, to_value(&content.0)?, etag.clone());
+            }
+
+            match etag {
+                Some(etag) => Ok(([(header::ETAG, etag)], content).into_response()),
+                None => Ok(content.into_response()),
+            }
+        } else if method == Method::DELETE && query.partial == Some(true) {
+            tracing::debug!("[FILES DELETE] cancel chunked upload of {}", &p);
+            upload.cancel(&system).await?;
+            Ok(StatusCode::ACCEPTED.into_response())
+        } else if method == Method::DELETE {
+            // symbolic links are not followed, a dangling one is deleted as well
+            let file_type = match system.file_type(&p).await {
+                Ok(file_type) => file_type,
+                Err(_) if !system.path_exist(&p).await? => return Err(Erro::PathNotFound(p)),
+                Err(e) => return Err(e),
+            };
+
+            if query.recursive == Some(true) {
+                tracing::debug!("[FILES DELETE] deleting directory {} recursively", &p);
+                system.remove_dir_all(&p).await?;
+            } else if file_type == FileType::Directory {
+                tracing::debug!("[FILES DELETE] deleting directory {}", &p);
+                system.remove_dir(&p).await?;
+            } else {
+                tracing::debug!("[FILES DELETE] deleting file {}", &p);
+                Self::backup(backup.as_ref(), &p, &system).await?;
+                let file = get_file!();
+                file.delete(&p, &system).await?;
+            }
+            Ok(StatusCode::ACCEPTED.into_response())
+        } else if let (&Method::POST, Some(action)) = (&method, query.action.as_ref()) {
+            let target = query.target.as_deref().ok_or(Erro::FileTargetMissing(action.name()));
+
+            match action {
+                FileQueryAction::Copy => {
+                    tracing::debug!("[FILES POST] copy {} to {:?}", &p, target);
+                    system.copy(&p, target?).await?;
+                }
+                FileQueryAction::Move => {
+                    tracing::debug!("[FILES POST] move {} to {:?}", &p, target);
+                    system.rename(&p, target?).await?;
+                }
+                FileQueryAction::Restore => {
+                    let version = query.version.ok_or(Erro::BackupVersionMissing)?;
+                    tracing::debug!("[FILES POST] restore {} from version {}", &p, version);
+                    backup.as_ref().ok_or(Erro::BackupDisabled)?.restore(&p, version, &system).await?;
+                }
+                FileQueryAction::Finalize => {
+                    let digest = query.digest.as_deref().ok_or(Erro::DigestMissing)?;
+                    tracing::debug!("[FILES POST] finalize chunked upload of {}", &p);
+                    Self::backup(backup.as_ref(), &p, &system).await?;
+                    upload.finalize(query.checksum.unwrap_or(HashAlgorithm::Sha256), digest, &system).await?;
+                    return Ok(Self::file_hooks(&hooks, &files, query.module(), &p, &system, StatusCode::ACCEPTED.into_response()).await);
+                }
+            }
+            Ok(StatusCode::ACCEPTED.into_response())
+        } else if method == Method::POST && query.r#type == Some(FileQueryType::Directory) {
+            tracing::debug!("[FILES POST] create directory {}", &p);
+            system.create_dir(&p).await?;
+            Ok(StatusCode::ACCEPTED.into_response())
+        } else if method == Method::POST {
+            tracing::debug!("[FILES POST] write file {}", &p);
+            let value: Json<Value> = request.extract().await?;
+            let file = get_file!();
+            file.validate(&value.0)?;
+            if query.validate_only == Some(true) {
+                tracing::debug!("[FILES POST] only validating input of {}", &p);
+                file.check(&p, to_value(value.0)?, &system).await?;
+                return Ok(StatusCode::OK.into_response());
+            }
+            if query.dry_run == Some(true) {
+                tracing::debug!("[FILES POST] diff of a write to {}", &p);
+                let current = if system.path_exist(&p).await? { system.read(&p).await? } else { vec![] };
+                let rendered = file.render(&p, to_value(value.0)?, &system).await?;
+                let diff = patch::unified_diff(&p, &String::from_utf8_lossy(&current), &rendered);
+                return Ok(([(header::CONTENT_TYPE, "text/x-diff")], diff).into_response());
+            }
+            Self::backup(backup.as_ref(), &p, &system).await?;
+            file.write(&p, to_value(value.0)?, &system).await?;
+            Ok(Self::file_hooks(&hooks, &files, query.module(), &p, &system, StatusCode::ACCEPTED.into_response()).await)
+        } else if method == Method::PUT && request.headers().contains_key(header::CONTENT_RANGE) {
+            let range: ContentRange = request.headers()[header::CONTENT_RANGE].to_str()?.parse()?;
+            tracing::debug!("[FILES PUT] upload chunk {:?} of {}", range, &p);
+            let content = Self::upload_content(request).await?;
+            Ok(Json(upload.write(&range, &content, &system).await?).into_response())
+        } else if method == Method::PUT {
+            tracing::debug!("[FILES PUT] upload file {}", &p);
+            let content = Self::upload_content(request).await?;
+            Self::backup(backup.as_ref(), &p, &system).await?;
+            system.write(&p, &content).await?;
+            Ok(Self::file_hooks(&hooks, &files, query.module(), &p, &system, StatusCode::ACCEPTED.into_response()).await)
+        } else if method == Method::PATCH && matches!(Self::content_type(&request), "text/x-diff" | "text/x-patch") {
+            tracing::debug!("[FILES PATCH] apply diff to {}", &p);
+            let current = system.read(&p).await?;
+            patch::check_base(&current, query.base.as_deref())?;
+
+            let diff: Bytes = request.extract().await?;
+            let patched = patch::apply_diff(&String::from_utf8(current)?, &String::from_utf8(diff.to_vec())?)?;
+            Self::backup(backup.as_ref(), &p, &system).await?;
+            system.write(&p, patched.as_bytes()).await?;
+            let response = (StatusCode::ACCEPTED, [(header::ETAG, Self::etag(&patch::sha256(patched.as_bytes())))]).into_response();
+            Ok(Self::file_hooks(&hooks, &files, query.module(), &p, &system, response).await)
+        } else if method == Method::PATCH && Self::content_type(&request) == "application/json-patch+json" {
+            tracing::debug!("[FILES PATCH] apply json patch to {}", &p);
+            let current = system.read(&p).await?;
+            patch::check_base(&current, query.base.as_deref())?;
+
+            let body: Bytes = request.extract().await?;
+            let operations: Vec<JsonPatch> = serde_json::from_slice(&body).map_err(Erro::from_deserialize)?;
+            Self::backup(backup.as_ref(), &p, &system).await?;
+            get_file!().patch(&p, &operations, &system).await?;
+            let response = (StatusCode::ACCEPTED, [(header::ETAG, Self::etag(&system.hash(&p).await?))]).into_response();
+            Ok(Self::file_hooks(&hooks, &files, query.module(), &p, &system, response).await)
+        } else if method == Method::PATCH {
+            let attributes: Json<FileAttributes> = request.extract().await?;
+
+            if attributes.mode.is_none() && attributes.owner.is_none() && attributes.group.is_none() {
+                return Err(Erro::FileAttributesMissing);
+            }
+
+            if let Some(mode) = attributes.mode.as_deref() {
+                tracing::debug!("[FILES PATCH] change mode of {} to {}", &p, mode);
+                system.chmod(&p, mode).await?;
+            }
+
+            if attributes.owner.is_some() || attributes.group.is_some() {
+                tracing::debug!("[FILES PATCH] change ownership of {} to {:?}:{:?}", &p, attributes.owner, attributes.group);
+                system.chown(&p, attributes.owner.as_deref(), attributes.group.as_deref()).await?;
+            }
+
+            Ok(StatusCode::ACCEPTED.into_response())
+        } else {
+            tracing::error!("[FILES {}] invalid request method", &method);
+            Err(Erro::HttpMethodNotAllowed(method))
+        }
+    }
+
+    /// list a directory with metadata and matching file modules, subdirectories are listed until `depth` is reached
+    fn dir_list_extended<'a>(p: &'a str,
+                             depth: usize,
+                             system: &'a System,
+                             files: &'a FileRegistry,
+                             os: &'a Os) -> BoxFuture<'a, Resul<Vec<DirItemExtended>>> {
+        async move {
+            let mut items = vec![];
+
+            tracing::debug!("[FILES GET] collecting files and directories in {}", p);
+            for entry in Dir::list(p, system).await? {
+                items.push(Self::dir_item_extended(p, entry, depth, system, files, os).await);
+            }
+
+            Ok(items)
+        }.boxed()
+    }
+
+    /// list a directory like `dir_list_extended`, each entry is built when the client reads the previous one
+    async fn dir_list_stream(p: String,
+                             depth: usize,
+                             system: System,
+                             files: FileRegistry,
+                             os: Os) -> Resul<impl Stream<Item=Resul<DirItemExtended>>> {
+        tracing::debug!("[FILES GET] streaming files and directories in {}", p);
+        let list = Dir::list(&p, &system).await?;
+
+        Ok(futures_util::stream::unfold((list.into_iter(), p, system, files, os), move |(mut list, p, system, files, os)| async move {
+            let entry = list.next()?;
+            let item = Self::dir_item_extended(&p, entry, depth, &system, &files, &os).await;
+            Some((Ok(item), (list, p, system, files, os)))
+        }))
+    }
+
+    /// metadata and matching file modules of one entry of the directory `p`
+    async fn dir_item_extended(p: &str,
+                               entry: DirEntry,
+                               depth: usize,
+                               system: &System,
+                               files: &FileRegistry,
+                               os: &Os) -> DirItemExtended {
+        let item = DirItem::from(&entry);
+        let path = std::path::Path::new(p).join(item.name()).to_string_lossy().to_string();
+        let mut managed_by = vec![];
+        let mut children = None;
+
+        if item.directory() {
+            if depth > 1 {
+                match Self::dir_list_extended(&path, depth - 1, system, files, os).await {
+                    Ok(items) => children = Some(items),
+                    Err(e) => tracing::warn!("[FILES GET] failed to list {}: {}", path, e),
+                }
+            }
+        } else {
+            tracing::trace!("[FILES GET] matching {}", path);
+
+            for managed_file_builder in files.matching(&path, os) {
+                let name = managed_file_builder.name().to_string();
+                tracing::trace!("[FILES GET] matched with {}", name);
+                managed_by.push(name);
+            }
+        }
+
+        tracing::trace!("[FILES GET] finished with item {}", item.name);
+
+        DirItemExtended {
+            info: item,
+            metadata: entry.metadata,
+            managed_by,
+            children,
+        }
+    }
+
+    /// Newline delimited json, every item is serialized once the client reads the previous ones.
+    /// An error ends the response with its message as last line.
+    fn ndjson<S, T>(items: S) -> Response
+        where S: Stream<Item=Resul<T>> + Send + 'static,
+              T: Serialize {
+        let lines = items
+            .scan(false, |failed, item| {
+                let line = match (*failed, item) {
+                    (true, _) => return futures_util::future::ready(None),
+                    (false, Ok(item)) => serde_json::to_vec(&item).map_err(Erro::from),
+                    (false, Err(e)) => Err(e),
+                };
+
+                let line = line.unwrap_or_else(|e| {
+                    tracing::error!("[STREAM] response ended early: {}", e);
+                    *failed = true;
+                    serde_json::to_vec(&RestError { message: e.to_string(), fields: vec![] }).unwrap_or_default()
+                });
+                futures_util::future::ready(Some(line))
+            })
+            .map(|mut line| {
+                line.push(b'\n');
+                Ok::<_, Infallible>(Bytes::from(line))
+            });
+
+        ([(header::CONTENT_TYPE, NDJSON)], StreamBody::new(lines)).into_response()
+    }
+
+    /// Switches the system to `sudo` or the `run_as` user if requested and the user is allowed to
+    pub(crate) fn switch_user(controller: &Controller, username: &str, system: &mut System, sudo: Option<bool>, run_as: Option<&str>) -> Resul<()> {
+        match (sudo == Some(true), run_as) {
+            (true, Some(_)) => Err(Erro::RunAsSudo),
+            (true, None) => {
+                controller.authorization().check(username, Permission::Sudo)?;
+                tracing::debug!("[SUDO] running as root for {}", username);
+                system.set_sudo(true)
+            }
+            (false, Some(target)) => {
+                controller.authorization().check_run_as(username, target)?;
+                tracing::debug!("[RUN AS] running as {} for {}", target, username);
+                system.set_run_as(Some(target.into()))
+            }
+            (false, None) => Ok(()),
+        }
+    }
+
+    /// Records `sh` commands with the api user and the user they run as, if shell recording is enabled
+    pub(crate) fn record_shell(controller: &Controller, username: &str, system: &mut System, sudo: Option<bool>, run_as: Option<&str>) {
+        let run_as = run_as.or((sudo == Some(true)).then_some("root"));
+        system.set_shell_session(controller.shell_recorder().session(username, run_as));
+    }
+
+    /// Target user of the `run_as` query or the `X-Run-As` header
+    fn run_as<'a, B>(query: &'a Option<String>, request: &'a Request<B>) -> Option<&'a str> {
+        query.as_deref().or_else(|| request.headers().get(RUN_AS).and_then(|value| value.to_str().ok()))
+    }
+
+    /// media type of the request body without parameters
+    fn content_type<B>(request: &Request<B>) -> &str {
+        request.headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .unwrap_or_default()
+            .trim()
+    }
+
+    /// runs the hooks of a changed file, their outputs become the json body of the response if any hook matched
+    pub(crate) async fn file_hooks(hooks: &[FileHook], files: &FileRegistry, name: Option<&str>, path: &str, system: &System, response: Response) -> Response {
+        if hooks.is_empty() {
+            return response;
+        }
+
+        let file = match name {
+            Some(name) => files.by_name(name),
+            None => files.by_match(path, system),
+        }.map(|f| f.name().to_string()).unwrap_or_default();
+
+        let outputs = hook::run_hooks(hooks, &file, path, system).await;
+        if outputs.is_empty() {
+            return response;
+        }
+
+        let (parts, _) = response.into_parts();
+        (parts, Json(outputs)).into_response()
+    }
+
+    /// copies the file before it is changed if backups are configured
+    pub(crate) async fn backup(backup: Option<&Backup>, path: &str, system: &System) -> Resul<()> {
+        if let Some(backup) = backup {
+            if let Some(version) = backup.create(path, system).await? {
+                tracing::info!("[BACKUP] {} saved as version {}", path, version.version());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Content up to the last line feed, a partially written line is sent once it is complete
+    fn complete_lines(content: &[u8]) -> &[u8] {
+        content.iter()
+            .rposition(|b| *b == b'\n')
+            .map(|i| &content[..=i])
+            .unwrap_or_default()
+    }
+
+    fn line_events(content: &[u8]) -> Vec<Result<Event, Infallible>> {
+        String::from_utf8_lossy(content)
+            .lines()
+            .map(|line| Ok(Event::default().data(line.replace('\r', ""))))
+            .collect()
+    }
+
+    /// Server sent events with the last lines of a file and every line appended later, one event per line.
+    /// The size is polled, a shrinking file (truncated or rotated) is read from the start again.
+    /// The stream ends on the first error.
+    async fn follow(path: String, lines: usize, system: System) -> Resul<Sse<impl Stream<Item=Result<Event, Infallible>>>> {
+        let tail = system.tail(&path, lines).await?;
+        let offset = system.metadata(&path).await?.size() as usize;
+
+        let stream = futures_util::stream::unfold((system, path, offset, Some(tail)), |(system, path, mut offset, tail)| async move {
+            if let Some(tail) = tail {
+                return Some((Self::line_events(&tail), (system, path, offset, None)));
+            }
+
+            loop {
+                tokio::time::sleep(FOLLOW_INTERVAL).await;
+
+                let size = match system.metadata(&path).await {
+                    Ok(metadata) => metadata.size() as usize,
+                    Err(e) => {
+                        tracing::debug!("[FILES FOLLOW] stopped following {}: {}", path, e);
+                        return None;
+                    }
+                };
+
+                if size < offset {
+                    tracing::debug!("[FILES FOLLOW] {} shrunk, reading from the start", path);
+                    offset = 0;
+                }
+
+                if size > offset {
+                    let content = match system.read_range(&path, offset, Some(size - offset)).await {
+                        Ok(content) => content,
+                        Err(e) => {
+                            tracing::debug!("[FILES FOLLOW] stopped following {}: {}", path, e);
+                            return None;
+                        }
+                    };
+                    let complete = Self::complete_lines(&content);
+
+                    if !complete.is_empty() {
+                        offset += complete.len();
+                        return Some((Self::line_events(complete), (system, path, offset, None)));
+                    }
+                }
+            }
+        }).flat_map(futures_util::stream::iter);
+
+        Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+    }
+
+    /// `Cache-Control: no-cache`, `no-store` or `max-age=0` bypasses the file cache
+    fn no_cache(request: &Request<Body>) -> bool {
+        request.headers().get(header::CACHE_CONTROL)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.split(',').map(str::trim).any(|d| matches!(d, "no-cache" | "no-store" | "max-age=0")))
+            .unwrap_or(false)
+    }
+
+    /// quoted content hash
+    fn etag(hash: &str) -> String {
+        format!("\"{}\"", hash)
+    }
+
+    /// Rejects changes if the `If-Match` header does not match the current content hash.
+    /// `*` only requires the path to exist.
+    async fn check_if_match<B>(request: &Request<B>, path: &str, system: &System) -> Resul<()> {
+        if let Some(if_match) = request.headers().get(header::IF_MATCH) {
+            let if_match = if_match.to_str()?;
+
+            if !system.path_exist(path).await? {
+                return Err(Erro::PreconditionFailed);
+            }
+
+            if if_match.trim() == "*" {
+                return Ok(());
+            }
+
+            let hash = system.hash(path).await?;
+
+            if !if_match.split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/").trim_matches('"'))
+                .any(|tag| tag.eq_ignore_ascii_case(&hash)) {
+                tracing::debug!("[FILES {}] {} changed, current hash {}", request.method(), path, hash);
+                return Err(Erro::PreconditionFailed);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Raw request body or the first field of a multipart form
+    async fn upload_content(request: Request<Body>) -> Resul<Vec<u8>> {
+        let multipart = request.headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.starts_with("multipart/form-data"))
+            .unwrap_or(false);
+
+        if multipart {
+            tracing::trace!("[UPLOAD] reading multipart field");
+            let mut form: Multipart = request.extract().await?;
+            let field = form.next_field().await?.ok_or(Erro::UploadFieldMissing)?;
+            Ok(field.bytes().await?.to_vec())
+        } else {
+            tracing::trace!("[UPLOAD] reading raw body");
+            let bytes: Bytes = request.extract().await?;
+            Ok(bytes.to_vec())
+        }
+    }
+}
+
+/// Converts all errors into http status code and eventually a useful message
+/// fields:   every offending field of an invalid input
+#[derive(Debug, Serialize)]
+pub struct RestError {
+    message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<InvalidField>,
+}
+
+impl IntoResponse for Erro {
+    fn into_response(self) -> Response {
+        let message = self.to_string();
+        let fields = match &self {
+            Erro::InputInvalid(fields) => fields.clone(),
+            _ => vec![],
+        };
+
+        let code = match self {
+            Erro::InvalidHeaderValue(_) |
+            Erro::RestAuthMissing |
+            Erro::AppBodyMissing |
+            Erro::HttpMethodNotAllowed(_) |
+            Erro::Base64Decode(_) |
+            Erro::Jwt(JwtError::SigningDisabled) |
+            Erro::Jwt(JwtError::RevokeUnsupported) |
+            Erro::Jwt(JwtError::RefreshUnsupported) |
+            Erro::UploadFieldMissing |
+            Erro::FileModeInvalid(_) |
+            Erro::FileOwnerInvalid(_) |
+            Erro::FileAttributesMissing |
+            Erro::FileTargetMissing(_) |
+            Erro::WorkspaceItemInvalid(_) |
+            Erro::ContentRangeInvalid(_) |
+            Erro::ContentRangeLength(_, _) |
+            Erro::DigestMissing |
+            Erro::BackupDisabled |
+            Erro::ShellRecordingDisabled |
+            Erro::BackupVersionMissing |
+            Erro::Patch(_) |
+            Erro::Plan(_) |
+            Erro::Batch(_) |
+            Erro::Template(_) |
+            Erro::Runbook(RunbookError::NameInvalid(_)) |
+            Erro::Runbook(RunbookError::StepsMissing) |
+            Erro::Plugin(_) |
+            Erro::FilePlugin(_) |
+            Erro::KeyValue(_) |
+            Erro::Csv(_) |
+            Erro::RunAsSudo |
+            Erro::RunTimeoutInvalid |
+            Erro::ListDepthTooLarge(_, _) |
+            Erro::Find(FindError::PathInvalid(_)) |
+            Erro::ResponseFormatUnknown(_) |
+            Erro::Deserialize(_)
+            => StatusCode::BAD_REQUEST,
+
+            Erro::Toml(_) => StatusCode::NOT_ACCEPTABLE,
+
+            Erro::InputInvalid(_) |
+            Erro::ChecksumMismatch(_, _) |
+            Erro::Sudoers(SudoersError::Rejected(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+
+            Erro::FileConflict(_) |
+            Erro::FileLocked(_) => StatusCode::CONFLICT,
+            Erro::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            Erro::ContentRangeUnsatisfiable(_) => StatusCode::RANGE_NOT_SATISFIABLE,
+
+            Erro::Forbidden(_) |
+            Erro::RunAsNotAllowed(_) |
+            Erro::Sh(_) => StatusCode::FORBIDDEN,
+
+            Erro::RunTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            Erro::ServiceDegraded(_, _) => StatusCode::SERVICE_UNAVAILABLE,
+            Erro::OutputTooLarge(_) |
+            Erro::ReadTooLarge(_, _) => StatusCode::PAYLOAD_TOO_LARGE,
+            Erro::WriteTooLarge(_, _) => StatusCode::INSUFFICIENT_STORAGE,
+
+            Erro::BytesRejection(ref rejection) => rejection.status(),
+            Erro::MultipartRejection(ref rejection) => rejection.status(),
+            Erro::Multipart(ref error) => error.status(),
+
+            Erro::TaskNotFound |
+            Erro::AppNotFound |
+            Erro::PathInvalid |
+            Erro::FilesNotMatched |
+            Erro::FilesNotMatchedByName(_) |
+            Erro::FilesNotMatchedByPattern(_) |
+            Erro::BackupVersionNotFound(_) |
+            Erro::FanOutTargetNotFound(_) |
+            Erro::Runbook(RunbookError::NotFound(_)) |
+            Erro::PathNotFound(_) |
+            Erro::GrpcServiceNotFound(_) |
+            Erro::PathExistUnsupported
+            => StatusCode::NOT_FOUND,
+
+            Erro::OsDetectionFailed |
+            Erro::AppIncompatible |
+            Erro::TaskInvalidIndex |
+            Erro::Io(_) |
+            Erro::Regex(_) |
+            Erro::FromUtf8(_) |
+            Erro::File(_) |
+            Erro::Hosts(_) |
+            Erro::Mdstat(_) |
+            Erro::Crypto(_) |
+            Erro::TokenStore(_) |
+            Erro::LoadAvg(_) |
+            Erro::Version(_) |
+            Erro::Cron(_) |
+            Erro::Uname(_) |
+            Erro::Facts(_) |
+            Erro::Systemctl(_) |
+            Erro::Find(_) |
+            Erro::Journalctl(_) |
+            Erro::Ip(_) |
+            Erro::Grpc(_) |
+            Erro::GrpcPlainText(_) |
+            Erro::Sockets(_) |
+            Erro::Tar(_) |
+            Erro::Rsync(_) |
+            Erro::Passwd(_) |
+            Erro::Group(_) |
+            Erro::Shadow(_) |
+            Erro::NetDev(_) |
+            Erro::Stat(_) |
+            Erro::ProcessStatus(_) |
+            Erro::ProcessIo(_) |
+            Erro::NetSockets(_) |
+            Erro::SystemdUnit(_) |
+            Erro::Netplan(_) |
+            Erro::Interfaces(_) |
+            Erro::SshdConfig(_) |
+            Erro::Sudoers(_) |
+            Erro::Exports(_) |
+            Erro::SmbConf(_) |
+            Erro::Environment(_) |
+            Erro::AuthorizedKeys(_) |
+            Erro::Semver(_) |
+            Erro::ParseInt(_) |
+            Erro::SerdeJson(_) |
+            Erro::Ssh(_) |
+            Erro::ParseFloat(_) |
+            Erro::JsonRejection(_) |
+            Erro::ToStrError(_) |
+            Erro::Http(_) |
+            Erro::Body(_) |
+            Erro::Compression(_) |
+            Erro::HyperError(_) |
+            Erro::AsyncSsh(_) |
+            Erro::Yaml(_) |
+            Erro::AddrParse(_) |
+            Erro::Join(_) |
+            Erro::FileTypeUnknown(_) |
+            Erro::FileTypeUnsupported |
+            Erro::PrivateKeyPath |
+            Erro::RestSslInactive |
+            Erro::ClientCaInvalid(_) |
+            Erro::CertificateInvalid |
+            Erro::RootCertificatesMissing |
+            Erro::Acme(_) |
+            Erro::Webhook(_) |
+            Erro::HttpClient(_) |
+            Erro::Client(_) |
+            Erro::Ldap(_) |
+            Erro::Asn1(_) |
+            Erro::Rcgen(_) |
+            Erro::Rustls(_) |
+            Erro::JsonWebToken(_) |
+            Erro::Jwt(JwtError::KeyMissing(_)) |
+            Erro::Secret(_) |
+            Erro::Users(_) |
+            Erro::Jwt(JwtError::Crypto) |
+            Erro::Infallible(_) |
+            Erro::OsDetection |
+            Erro::EndpointIncompatible |
+            Erro::RunUserUnsupported(_) |
+            Erro::ReadUserUnsupported(_) |
+            Erro::ReadSshUnsupported(_) |
+            Erro::WriteUserUnsupported(_) |
+            Erro::WriteSshUnsupported(_) |
+            Erro::DeleteUserUnsupported(_) |
+            Erro::DeleteSshUnsupported(_) |
+            Erro::ReadRangeUnsupported(_) |
+            Erro::TailUnsupported(_) |
+            Erro::MetadataUnsupported |
+            Erro::MetadataInvalid(_) |
+            Erro::DirListUnsupported |
+            Erro::ChmodUnsupported(_) |
+            Erro::ChownUnsupported(_) |
+            Erro::CopyUnsupported(_) |
+            Erro::RenameUnsupported(_) |
+            Erro::HashUnsupported(_) |
+            Erro::HashInvalid(_) |
+            Erro::CreateDirUnsupported(_) |
+            Erro::RemoveDirUnsupported(_) |
+            Erro::WorkspaceUnsupported(_) |
+            Erro::WriteChunkUnsupported(_) |
+            Erro::ReplaceUnsupported(_) |
+            Erro::RunUserStdin |
+            Erro::RunUserStdout |
+            Erro::RunUserStderr |
+            Erro::RunLimitsUnsupported(_) |
+            Erro::RunOutputUnsupported(_) |
+            Erro::SudoUnsupported(_) |
+            Erro::RunUser(_, _) |
+            Erro::RunSsh(_, _) |
+            Erro::EndpointMissing |
+            Erro::WriteUserTempPath |
+            Erro::WriteAtomic(_, _, _) |
+            Erro::CertificatePath |
+            Erro::OsRelease(_)
+            => StatusCode::INTERNAL_SERVER_ERROR,
+
+            Erro::AuthNotFound |
+            Erro::AuthTokenExpired |
+            Erro::RestAuthInvalid |
+            Erro::Jwt(JwtError::Invalid(_)) |
+            Erro::Jwt(JwtError::IssuerUnknown(_)) |
+            Erro::Jwt(JwtError::ServiceAccountMissing) |
+            Erro::AuthLdapInvalid |
+            Erro::AuthUserInvalid |
+            Erro::RunUserUserInvalid |
+            Erro::RunUserPasswordInvalid
+            => StatusCode::UNAUTHORIZED,
+        };
+
+        tracing::error!("code {},  error {}", code, message);
+
+        (code, Json(RestError {
+            message,
+            fields,
+        })).into_response()
+    }
+}
+
+// tokio mutex not serializable
+#[derive(Serialize)]
+struct TaskResult {
+    id: usize,
+    app_name: String,
+    app_input: Value,
+    app_output: Option<Value>,
+    finished: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DirItem {
+    name: String,
+    r#type: FileType,
+    directory: bool,
+    size: u64,
+}
+
+impl From<&DirEntry> for DirItem {
+    fn from(value: &DirEntry) -> Self {
+        Self {
+            name: value.name.clone(),
+            r#type: value.file_type,
+            directory: value.file_type.is_directory(),
+            size: value.metadata.size(),
+        }
+    }
+}
+
+impl DirItem {
+    pub fn name(&self) -> &str { self.name.as_str() }
+    pub fn directory(&self) -> bool { self.directory }
+}
+
+/// Manages directory listing
+struct Dir;
+
+impl Dir {
+    /// entries sorted by name, symbolic links to directories are not followed
+    pub async fn list<P: Into<PathBuf>>(path: P, exec: &System) -> Resul<Vec<DirEntry>> {
+        let p = path.into();
+        let s = p.to_str().ok_or(Erro::PathInvalid)?;
+
+        tracing::debug!("[LIST] getting directory list {}", s);
+        let mut entries = exec.dir_list(s).await?;
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use axum::{middleware, Router};
+    use axum::http::{header, Request};
+    use axum::routing::get;
+    use base64::Engine;
+    use hyper::{Body, Method, StatusCode};
+    use tokio::sync::{Mutex, Notify};
+    use crate::rest::{AppsBodyApp, auth, CompressionConfig, HttpConfig, negotiate, NDJSON, Rest, SharedController, TokenResult};
+    use crate::error::Erro;
+    use crate::jwt::JwtConfig;
+    use tower::ServiceExt;
+    use tracing_subscriber::EnvFilter;
+    use crate::controller::{Controller, ControllerSettings};
+    use crate::plan::PlanDependency;
+    use crate::inventory::{Inventory, InventoryService};
+    use crate::audit::ShellRecording;
+    use axum::body::HttpBody;
+    use axum::response::Response;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use serde_json::{json, Value};
+    use tokio::fs::read_to_string;
+    use crate::apps::AppBuilders;
+    use crate::apps::sh::ShBuilder;
+    use crate::utils::test::{PASSWORD, system_user, USERNAME};
+
+    async fn get_body<T: DeserializeOwned>(result: Response) -> T {
+        serde_json::from_slice(result.into_body().data().await.unwrap().unwrap().as_ref()).unwrap()
+    }
+
+    fn to_body<T: Serialize>(value: &T) -> Body {
+        serde_json::to_vec(value).unwrap().into()
+    }
+
+    async fn request(app: Router, ctrl: SharedController, method: Method, body: Body, uri: &str) -> Response {
+        let token_string = ctrl.lock()
+            .await
+            .auth_mut()
+            .insert_or_replace(USERNAME.into(), PASSWORD.into(), None)
+            .token()
+            .to_string();
+
+        app.clone()
+            .oneshot(Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", "Bearer ".to_owned() + &token_string)
+                .header("Content-Type", "application/json")
+                .body(body)
+                .unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn app() -> (Router, SharedController) {
+        std::env::set_var("RUST_LOG", "trace");
+        let _ = tracing_subscriber::fmt().with_env_filter(EnvFilter::from_default_env()).with_test_writer().try_init();
+
+        let ctrl = SharedController::new(Mutex::new(
+            Controller::new(ControllerSettings {
+                max_token_expiration: Duration::from_secs(100),
+                shell_recording: Some(ShellRecording::default()),
+                ..Default::default()
+            }, None).await.unwrap()
+        ));
+
+        let router = Rest::routes()
+            .with_state(ctrl.clone())
+            .layer(middleware::from_fn_with_state(ctrl.clone(), auth));
+
+        (router, ctrl)
+    }
+
+    #[tokio::test]
+    async fn test_get_token() {
+        let (app, ctrl) = app().await;
+
+        let user_pass = base64
+        ::engine
+        ::general_purpose
+        ::STANDARD.encode(format!("{}:{}", USERNAME, PASSWORD));
+        let result = app
+            .oneshot(Request::builder()
+                .uri("/token")
+                .header("Authorization", "Basic ".to_owned() + &user_pass)
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        assert!(ctrl.lock().await.auth_mut().get(&get_body::<TokenResult>(result).await.token).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_auth_with_token_and_renew() {
+        let (app, ctrl) = app().await;
+
+        let token_string = ctrl.lock()
+            .await
+            .auth_mut()
+            .insert_or_replace(USERNAME.into(), PASSWORD.into(), None)
+            .token()
+            .to_string();
+
+        let result = app
+            .oneshot(Request::builder()
+                .uri("/token")
+                .header("Authorization", "Bearer ".to_owned() + &token_string)
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+
+        let token: TokenResult = get_body(result).await;
+        assert_ne!(token.token, token_string);
+        assert!(ctrl.lock().await.auth_mut().get(&token.token).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token() {
+        let (app, ctrl) = app().await;
+
+        let token_string = ctrl.lock()
+            .await
+            .auth_mut()
+            .insert_or_replace(USERNAME.into(), PASSWORD.into(), Some("laptop".into()))
+            .token()
+            .to_string();
+
+        let result = app
+            .oneshot(Request::builder()
+                .method(Method::POST)
+                .uri("/token/refresh")
+                .header("Authorization", "Bearer ".to_owned() + &token_string)
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+
+        let token: TokenResult = get_body(result).await;
+        assert_eq!(token.token, token_string);
+        assert_eq!(token.client.as_deref(), Some("laptop"));
+        assert!(token.expires_at > 0);
+    }
+
+    #[tokio::test]
+    async fn test_jwt() {
+        let (app, ctrl) = app().await;
+
+        let config: JwtConfig = serde_json::from_value(serde_json::json!({
+            "secret": "issuing secret",
+            "external": [{"issuer": "https://sso.example.com", "algorithm": "HS256", "secret": "idp secret"}]
+        })).unwrap();
+        let (token, issued) = {
+            let mut ctrl = ctrl.lock().await;
+            let auths = ctrl.auth_mut();
+            auths.set_jwt(Some(config.load().await.unwrap()));
+            let token = TokenResult::issue(auths, USERNAME, PASSWORD, Some("laptop".into())).unwrap();
+            let issued = auths.get(&token.token).is_ok();
+            (token, issued)
+        };
+        assert!(!issued);
+
+        let request = |method: Method, uri: &str, token: &str| Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", "Bearer ".to_owned() + token)
+            .body(Body::empty())
+            .unwrap();
+
+        let result = app.clone().oneshot(request(Method::POST, "/token/refresh", &token.token)).await.unwrap();
+        let refreshed: TokenResult = get_body(result).await;
+        assert_eq!(refreshed.client.as_deref(), Some("laptop"));
+        assert!(refreshed.expires_at >= token.expires_at);
+
+        let result = app.clone().oneshot(request(Method::DELETE, "/token", &token.token)).await.unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+
+        // tokens of the identity provider need a service account
+        let external = jsonwebtoken::encode(&jsonwebtoken::Header::default(),
+                                            &serde_json::json!({"iss": "https://sso.example.com", "sub": USERNAME, "exp": jsonwebtoken::get_current_timestamp() + 60}),
+                                            &jsonwebtoken::EncodingKey::from_secret(b"idp secret")).unwrap();
+        let result = app.clone().oneshot(request(Method::POST, "/token/refresh", &external)).await.unwrap();
+        assert_eq!(result.status(), StatusCode::UNAUTHORIZED);
+
+        let result = app.oneshot(request(Method::POST, "/token/refresh", &(token.token + "x"))).await.unwrap();
+        assert_eq!(result.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_get_token_failed() {
+        let (app, _ctrl) = app().await;
+
+        let user_pass = base64
+        ::engine
+        ::general_purpose
+        ::STANDARD.encode(format!("{}:invalid", USERNAME));
+        let result = app
+            .oneshot(Request::builder()
+                .uri("/token")
+                .header("Authorization", "Basic ".to_owned() + &user_pass)
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_auth_with_token_failed() {
+        let (app, _ctrl) = app().await;
+        let result = app
+            .oneshot(Request::builder()
+                .uri("/token")
+                .header("Authorization", "Bearer invalid")
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(result.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_delete_token() {
+        let (app, ctrl) = app().await;
+
+        let token_string = ctrl.lock()
+            .await
+            .auth_mut()
+            .insert_or_replace(USERNAME.into(), PASSWORD.into(), None)
+            .token()
+            .to_string();
+
+        for code in [
+            StatusCode::ACCEPTED,
+            StatusCode::UNAUTHORIZED
+        ] {
+            let result = app.clone()
+                .oneshot(Request::builder()
+                    .method(Method::DELETE)
+                    .uri("/token")
+                    .header("Authorization", "Bearer ".to_owned() + &token_string)
+                    .body(Body::empty())
+                    .unwrap())
+                .await
+                .unwrap();
+
+            assert_eq!(result.status(), code);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tasks() {
+        let (app, ctrl) = app().await;
+
+        let mut c = ctrl.lock().await;
+        let tk = c.task_controller_mut();
+        let mut task_result = tk.new_task(AppBuilders::ShBuilder(ShBuilder::default()),
+                                          json!({
+            "command": "sleep 3"
+        }), system_user().await, None).await.unwrap();
+
+        drop(c);
+
+        task_result.as_object_mut().unwrap().insert("status".into(), Value::String("running".into())); // is already running in the meantime
+
+        let result = request(app.clone(), ctrl.clone(), Method::GET, Body::empty(), "/tasks").await;
+        let body: Value = get_body(result).await;
+        assert_eq!(body, Value::Array(vec![task_result.clone()]));
+
+        let result = request(app, ctrl, Method::GET, Body::empty(), "/tasks/1").await;
+        let body: Value = get_body(result).await;
+        assert_eq!(body, task_result);
+    }
+
+    #[tokio::test]
+    async fn test_ndjson() {
+        let items = futures_util::stream::iter(vec![Ok(json!({"id": 1})), Err(Erro::TaskNotFound), Ok(json!({"id": 2}))]);
+        let response = Rest::ndjson(items);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], NDJSON);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(String::from_utf8(body.to_vec()).unwrap(), format!("{{\"id\":1}}\n{{\"message\":\"{}\"}}\n", Erro::TaskNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_runbooks() {
+        let (app, ctrl) = app().await;
+        let runbook = json!({
+            "vars": {"dir": "/nonexistent"},
+            "steps": [{"app": {"name": "ls", "input": {"path": "{{dir}}"}}}]
+        });
+
+        let result = request(app.clone(), ctrl.clone(), Method::PUT, to_body(&runbook), "/runbooks/list%20tmp").await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+        let result = request(app.clone(), ctrl.clone(), Method::PUT, to_body(&runbook), "/runbooks/list-tmp").await;
+        assert_eq!(result.status(), StatusCode::ACCEPTED);
+
+        let result = request(app.clone(), ctrl.clone(), Method::GET, Body::empty(), "/runbooks").await;
+        let body: Value = get_body(result).await;
+        assert_eq!(body["list-tmp"]["vars"]["dir"], "/nonexistent");
+
+        // undefined variables are rejected before the task is created
+        let result = request(app.clone(), ctrl.clone(), Method::PUT, to_body(&json!({"steps": [{"app": {"name": "ls", "input": {"path": "{{dir}}"}}}]})), "/runbooks/no-vars").await;
+        assert_eq!(result.status(), StatusCode::ACCEPTED);
+        let result = request(app.clone(), ctrl.clone(), Method::POST, Body::empty(), "/runbooks/no-vars/run").await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+
+        let result = request(app.clone(), ctrl.clone(), Method::POST, to_body(&json!({"vars": {"dir": "/tmp"}})), "/runbooks/list-tmp/run").await;
+        assert_eq!(result.status(), StatusCode::OK);
+        let task: Value = get_body(result).await;
+        assert_eq!(task["app_name"], "runbook");
+        assert_eq!(task["app_input"], json!({"name": "list-tmp", "vars": {"dir": "/tmp"}}));
+
+        let mut output = Value::Null;
+        for _ in 0..50 {
+            let result = request(app.clone(), ctrl.clone(), Method::GET, Body::empty(), &format!("/tasks/{}", task["id"])).await;
+            let task: Value = get_body(result).await;
+            if task["status"] == "finished" {
+                output = task["app_output"].clone();
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(output[0]["status"], "finished");
+
+        let result = request(app.clone(), ctrl.clone(), Method::DELETE, Body::empty(), "/runbooks/list-tmp").await;
+        assert_eq!(result.status(), StatusCode::ACCEPTED);
+        let result = request(app, ctrl, Method::POST, Body::empty(), "/runbooks/list-tmp/run").await;
+        assert_eq!(result.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_apps() {
+        let (app, ctrl) = app().await;
+
+        // help
+        let result = request(app.clone(), ctrl.clone(), Method::GET, Body::empty(), "/apps").await;
+        let body_result: Value = get_body(result).await;
+        assert!(body_result.is_array());
+
+        // capabilities
+        let result = request(app.clone(), ctrl.clone(), Method::GET, Body::empty(), "/capabilities").await;
+        let body_result: Value = get_body(result).await;
+        assert!(body_result["apps"].as_array().unwrap().iter().any(|app| app["name"] == "ls" && app["enabled"] == true));
+        assert!(body_result["files"].as_array().unwrap().iter().any(|file| file["name"] == "passwd" && file["capabilities"].as_array().is_some()));
+
+        // multi
+        let body = vec![
+            AppsBodyApp {
+                name: "ls".into(),
+                input: json!({
+                    "path": "/tmp"
+                }),
+                id: None,
+                depends_on: None,
+            },
+            AppsBodyApp {
+                name: "ls".into(),
+                input: json!({
+                    "path": "/tmp"
+                    }),
+                id: None,
+                depends_on: Some(vec![PlanDependency::Index(0)]),
+            },
+        ];
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::POST,
+                             to_body(&body),
+                             "/apps").await;
+        let body_result: Value = get_body(result).await;
+        assert!(body_result.is_array());
+
+        // multi plan
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::POST,
+                             to_body(&body),
+                             "/apps?plan=true&on_error=continue").await;
+        let body_result: Value = get_body(result).await;
+        assert_eq!(body_result[0]["status"], "finished");
+        assert_eq!(body_result[1]["status"], "finished");
+
+        // multi template
+        let template = json!({"vars": {"dir": "/tmp"}, "apps": [{"name": "ls", "input": {"path": "{{dir}}"}}]});
+        let result = request(app.clone(), ctrl.clone(), Method::POST, to_body(&template), "/apps").await;
+        let body_result: Value = get_body(result).await;
+        assert!(body_result[0].is_array());
+
+        let template = json!({"vars": {}, "apps": [{"name": "ls", "input": {"path": "{{dir}}"}}]});
+        let result = request(app.clone(), ctrl.clone(), Method::POST, to_body(&template), "/apps").await;
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+
+        // multi async
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::POST,
+                             to_body(&body),
+                             "/apps?async=true").await;
+
+        let body_result: Value = get_body(result).await;
+        assert_eq!(body_result.as_array().unwrap().get(0).unwrap().as_object().unwrap().get("id").unwrap(), 1);
+        assert_eq!(body_result.as_array().unwrap().get(1).unwrap().as_object().unwrap().get("id").unwrap(), 2);
+
+        // single
+        let body = json!({
+                            "path": "/tmp"
+                            });
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::POST,
+                             to_body(&body),
+                             "/apps/ls").await;
+        let body_result: Value = get_body(result).await;
+        assert!(body_result.is_array());
+
+        // single async
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::POST,
+                             to_body(&body),
+                             "/apps/ls?async=true").await;
+        let body_result: Value = get_body(result).await;
+        assert_eq!((body_result).as_object().unwrap().get("id").unwrap(), 3);
+
+        // shell recording
+        let body = json!({
+                            "command": "echo recorded"
+                            });
+        request(app.clone(), ctrl.clone(), Method::POST, to_body(&body), "/apps/sh").await;
+        let result = request(app, ctrl, Method::GET, Body::empty(), &format!("/audit/shell?user={}", USERNAME)).await;
+        let body_result: Value = get_body(result).await;
+        assert_eq!(body_result[0]["command"], "echo recorded");
+        assert_eq!(body_result[0]["stdout"], "recorded\n");
+    }
+
+    #[tokio::test]
+    async fn test_files() {
+        let (app, ctrl) = app().await;
+
+        // help
+        let result = request(app.clone(), ctrl.clone(), Method::GET, Body::empty(), "/files").await;
+        assert!(get_body::<Value>(result).await.is_array());
+
+        // file list
+        for path in [
+            "/files",
+            "/files/",
+            "/files/tmp"
+        ] {
+            let result = request(app.clone(), ctrl.clone(), Method::GET, Body::empty(), path).await;
+            assert!(get_body::<Value>(result).await.is_array());
+        }
+
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::GET,
+                             Body::empty(),
+                             "/files/proc/uptime").await;
+        assert!(get_body::<Value>(result).await.is_object());
+
+        let path = "/tmp/createtestfile";
+        let uri = "/files".to_owned() + path;
+
+        let content = "text1";
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::POST,
+                             to_body(&json!({
+                                  "content": content
+                              })),
+                             &uri).await;
+        assert_eq!(result.status(), StatusCode::ACCEPTED);
+        assert_eq!(content, &read_to_string(path).await.unwrap());
+
+        let content = "text2";
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::POST,
+                             to_body(&json!({
+                                  "content": content
+                              })),
+                             &uri).await;
+        assert_eq!(result.status(), StatusCode::ACCEPTED);
+        assert_eq!(content, &read_to_string(path).await.unwrap());
+
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::GET,
+                             Body::empty(),
+                             &(uri.clone() + "?checksum=md5")).await;
+        assert_eq!(get_body::<Value>(result).await["checksum"], "fe6123a759017e4a2af4a2d19961ed71");
+
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::DELETE,
+                             Body::empty(),
+                             &uri).await;
+        assert_eq!(result.status(), StatusCode::ACCEPTED);
+        assert!(!Path::new(path).exists());
+
+        // upload
+        let content = vec![0u8, 159, 146, 150];
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::PUT,
+                             Body::from(content.clone()),
+                             &uri).await;
+        assert_eq!(result.status(), StatusCode::ACCEPTED);
+        assert_eq!(content, tokio::fs::read(path).await.unwrap());
+
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::DELETE,
+                             Body::empty(),
+                             &uri).await;
+        assert_eq!(result.status(), StatusCode::ACCEPTED);
+
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::DELETE,
+                             Body::empty(),
+                             &uri).await;
+        assert_eq!(result.status(), StatusCode::NOT_FOUND);
+
+        // by name
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::GET,
+                             Body::empty(),
+                             "/files/etc/fstab?name=text").await;
+        assert!(get_body::<Value>(result).await.is_string());
+
+        // by invalid name
+        let result = request(app.clone(),
+                             ctrl.clone(),
+                             Method::GET,
+                             Body::empty(),
+                             "/files/etc/fstab?name=invalid").await;
+        assert_eq!(result.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_complete_lines() {
+        assert_eq!(Rest::complete_lines(b"first\nsecond\nthi"), b"first\nsecond\n");
+        assert_eq!(Rest::complete_lines(b"partial"), b"");
+        assert_eq!(Rest::line_events(b"first\r\nsecond\n").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fan_out() {
+        let service = |name: &'static str| Router::new()
+            .route("/echo", axum::routing::post(move |query: axum::extract::RawQuery, body: String| async move {
+                axum::Json(json!({"service": name, "query": query.0, "body": body}))
+            }));
+
+        let rest = Rest::new("127.0.0.1:3000".parse().unwrap(), 1024, Default::default());
+        let router = rest.router([("a".to_string(), service("a")), ("b".to_string(), service("b"))].into_iter().collect(), None);
+
+        let result = router.clone().oneshot(Request::builder()
+            .method(Method::POST)
+            .uri("/_all/echo?targets=b&x=1")
+            .body(Body::from("hello"))
+            .unwrap()).await.unwrap();
+        assert_eq!(get_body::<Value>(result).await, json!({
+            "b": {"success": true, "status": 200, "body": {"service": "b", "query": "x=1", "body": "hello"}}
+        }));
+
+        let result = router.clone().oneshot(Request::builder()
+            .method(Method::GET)
+            .uri("/_all/echo")
+            .body(Body::empty())
+            .unwrap()).await.unwrap();
+        let body = get_body::<Value>(result).await;
+        assert_eq!(body["a"]["status"], 405);
+        assert_eq!(body["b"]["success"], false);
+
+        let result = router.oneshot(Request::builder()
+            .uri("/_all/echo?targets=c")
+            .body(Body::empty())
+            .unwrap()).await.unwrap();
+        assert_eq!(result.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_inventory() {
+        let (_, ctrl) = app().await;
+        let labels = [("role".to_string(), "web".to_string())].into_iter().collect();
+        let inventory = Inventory::new(vec![InventoryService::new("localhost".into(), None, labels, ctrl)]);
+
+        let rest = Rest::new("127.0.0.1:3000".parse().unwrap(), 1024, Default::default());
+        let result = rest.router(Default::default(), Some(inventory)).oneshot(Request::builder()
+            .uri("/inventory")
+            .body(Body::empty())
+            .unwrap()).await.unwrap();
+
+        assert_eq!(get_body::<Value>(result).await, json!([{
+            "name": "localhost",
+            "address": null,
+            "labels": {"role": "web"},
+            "os": null,
+            "status": "ok",
+            "reachable": true,
+            "last_seen": null
+        }]));
+
+        let inventory = Inventory::new(vec![InventoryService::new_degraded("remote".into(), None, Default::default(), "plugin invalid".into())]);
+        let result = rest.router(Default::default(), Some(inventory)).oneshot(Request::builder()
+            .uri("/inventory")
+            .body(Body::empty())
+            .unwrap()).await.unwrap();
+        let body: Value = get_body(result).await;
+        assert_eq!(body[0]["status"], "degraded");
+        assert_eq!(body[0]["error"], "plugin invalid");
+
+        let result = rest.router(Default::default(), None).oneshot(Request::builder()
+            .uri("/inventory")
+            .body(Body::empty())
+            .unwrap()).await.unwrap();
+        assert_eq!(result.status(), StatusCode::NOT_FOUND);
+    }
+
+    async fn serve(http: HttpConfig) -> SocketAddr {
+        let address = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let rest = Rest::new(address, 1024, http);
+        rest.set_services(Default::default(), None).await;
+        tokio::spawn(async move { rest.start().await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        address
+    }
+
+    #[tokio::test]
+    async fn test_http_config() {
+        // cleartext HTTP/2 with prior knowledge
+        let address = serve(HttpConfig::default()).await;
+        let client = hyper::Client::builder().http2_only(true).build_http::<Body>();
+        let result = client.get(format!("http://{}/unknown", address).parse().unwrap()).await.unwrap();
+        assert_eq!(result.version(), hyper::Version::HTTP_2);
+        assert_eq!(result.status(), StatusCode::NOT_FOUND);
+
+        // a second connection waits until the first is closed
+        let http: HttpConfig = serde_yaml::from_str("http2: false\nmax_connections: 1").unwrap();
+        assert!(http.keep_alive);
+        let address = serve(http).await;
+        let first = tokio::net::TcpStream::connect(address).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let client = hyper::Client::builder().build_http::<Body>();
+        let waiting = tokio::spawn(client.get(format!("http://{}/unknown", address).parse().unwrap()));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!waiting.is_finished());
+
+        drop(first);
+        let result = tokio::time::timeout(Duration::from_secs(5), waiting).await.unwrap().unwrap().unwrap();
+        assert_eq!(result.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_degraded_service() {
+        let rest = Rest::new("127.0.0.1:3000".parse().unwrap(), 1024, Default::default());
+        let retry = Arc::new(Notify::new());
+        let services = [("remote".to_string(), rest.degraded_service("remote", "connection refused", retry.clone()))].into_iter().collect();
+
+        let result = rest.router(services, None).oneshot(Request::builder()
+            .uri("/remote/files/etc/hosts")
+            .body(Body::empty())
+            .unwrap()).await.unwrap();
+        assert_eq!(result.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(get_body::<Value>(result).await["message"].as_str().unwrap().contains("connection refused"));
+        tokio::time::timeout(Duration::from_secs(1), retry.notified()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_negotiate() {
+        let service = Router::new()
+            .route("/object", get(|| async { axum::Json(json!({"name": "boofi", "labels": {"role": "web"}})) }))
+            .route("/list", get(|| async { axum::Json(json!([1, 2])) }))
+            .route("/text", get(|| async { "plain" }))
+            .layer(middleware::from_fn(negotiate));
+
+        let rest = Rest::new("127.0.0.1:3000".parse().unwrap(), 1024, Default::default());
+        let router = rest.router([("a".to_string(), service)].into_iter().collect(), None);
+
+        let request = |uri: &str, accept: &str| Request::builder()
+            .uri(uri)
+            .header(header::ACCEPT, accept)
+            .body(Body::empty())
+            .unwrap();
+        let body = |response: Response| async move {
+            String::from_utf8(hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec()).unwrap()
+        };
+
+        let result = router.clone().oneshot(request("/a/object", "text/html, application/yaml;q=0.9")).await.unwrap();
+        assert_eq!(result.headers()[header::CONTENT_TYPE], "application/yaml");
+        assert_eq!(body(result).await, "name: boofi\nlabels:\n  role: web\n");
+
+        let result = router.clone().oneshot(request("/a/object?format=pretty", "application/yaml")).await.unwrap();
+        assert_eq!(body(result).await, "{\n  \"name\": \"boofi\",\n  \"labels\": {\n    \"role\": \"web\"\n  }\n}");
+
+        let result = router.clone().oneshot(request("/a/object?format=toml", "*/*")).await.unwrap();
+        assert_eq!(body(result).await, "name = \"boofi\"\n\n[labels]\nrole = \"web\"\n");
+
+        let result = router.clone().oneshot(request("/a/object", "*/*")).await.unwrap();
+        assert_eq!(body(result).await, r#"{"name":"boofi","labels":{"role":"web"}}"#);
+
+        let result = router.clone().oneshot(request("/a/list?format=toml", "*/*")).await.unwrap();
+        assert_eq!(result.status(), StatusCode::NOT_ACCEPTABLE);
+
+        let result = router.clone().oneshot(request("/a/text?format=yaml", "*/*")).await.unwrap();
+        assert_eq!(body(result).await, "plain");
+
+        let result = router.oneshot(request("/a/object?format=xml", "*/*")).await.unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_compress() {
+        let large = "boofi ".repeat(1000);
+        let service = Router::new()
+            .route("/large", get(move || async move { large }))
+            .route("/small", get(|| async { "small" }))
+            .route("/echo", axum::routing::post(|body: String| async move { body }));
+        let router = Rest::compress(service, &CompressionConfig::default());
+
+        let request = |uri: &str| Request::builder()
+            .uri(uri)
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+
+        let result = router.clone().oneshot(request("/small")).await.unwrap();
+        assert!(result.headers().get(header::CONTENT_ENCODING).is_none());
+
+        let result = router.clone().oneshot(request("/large")).await.unwrap();
+        assert_eq!(result.headers()[header::CONTENT_ENCODING], "gzip");
+        let compressed = hyper::body::to_bytes(result.into_body()).await.unwrap();
+        assert!(compressed.len() < 6000);
+
+        // the compressed response is sent back as compressed request body
+        let result = router.oneshot(Request::builder()
+            .method(Method::POST)
+            .uri("/echo")
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(Body::from(compressed))
+            .unwrap()).await.unwrap();
+        assert_eq!(hyper::body::to_bytes(result.into_body()).await.unwrap(), "boofi ".repeat(1000));
+    }
+
+    #[tokio::test]
+    async fn test_request_id() {
+        let service = Router::new().route("/ok", get(|| async { "ok" }));
+        let rest = Rest::new("127.0.0.1:3000".parse().unwrap(), 1024, Default::default());
+        let router = rest.router([("a".to_string(), service)].into_iter().collect(), None);
+
+        let request = |id: Option<&str>| {
+            let mut request = Request::builder().uri("/a/ok");
+            if let Some(id) = id {
+                request = request.header("x-request-id", id);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let result = router.clone().oneshot(request(None)).await.unwrap();
+        let id = result.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(id.len(), 16);
+
+        let result = router.clone().oneshot(request(Some("client-42"))).await.unwrap();
+        assert_eq!(result.headers()["x-request-id"], "client-42");
+
+        let result = router.oneshot(request(Some("no spaces"))).await.unwrap();
+        assert_ne!(result.headers()["x-request-id"], "no spaces");
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use serde_json::{to_value, Value};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use crate::apps::AppBuilders;
+use crate::backup::Backup;
+use crate::batch::{Batch, BatchResult, Undo};
+use crate::controller::{Controller, FileLocks, FileRegistry, Permission};
+use crate::error::{Erro, Resul};
+use crate::hook::{self, FileHook};
+use crate::rest::Rest;
+use crate::system::System;
+use crate::template::{self, Variables};
+
+/// One app invocation or file write of a runbook, `{{name}}` is replaced in all strings before the run
+/// module: file module writing the content, matched by path if missing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunbookStep {
+    App {
+        name: String,
+        input: Value,
+    },
+    File {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        module: Option<String>,
+        content: Value,
+    },
+}
+
+/// Named sequence of steps running one after another, steps after a failed one are skipped
+/// vars:       default variables of the steps, overridden by the variables of a run
+/// rollback:   finished steps are undone in reverse order if a later one fails
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Runbook {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default)]
+    vars: Variables,
+    #[serde(default)]
+    rollback: bool,
+    steps: Vec<RunbookStep>,
+}
+
+impl Runbook {
+    /// Steps with the default variables and `overrides` replaced
+    pub fn render(&self, overrides: &Variables) -> Resul<Vec<RunbookStep>> {
+        let mut variables = self.vars.clone();
+        variables.extend(overrides.clone());
+
+        serde_json::from_value(template::render(to_value(&self.steps)?, &variables)?).map_err(Into::into)
+    }
+}
+
+/// Runbooks of all services in one yaml file next to the configuration, every change is saved at once.
+/// Cloning only shares them, without a path they are kept in memory.
+#[derive(Debug, Clone, Default)]
+pub struct Runbooks {
+    path: Option<PathBuf>,
+    runbooks: Arc<Mutex<BTreeMap<String, Runbook>>>,
+}
+
+impl Runbooks {
+    /// Loads the runbooks from the file, a missing file is created with the first runbook
+    pub async fn load<P: AsRef<Path>>(path: P) -> Resul<Self> {
+        let path = path.as_ref().to_path_buf();
+        let runbooks = if tokio::fs::try_exists(&path).await? {
+            tracing::debug!("[RUNBOOKS] loading runbooks from {}", path.display());
+            serde_yaml::from_slice(&tokio::fs::read(&path).await?)?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self {
+            path: Some(path),
+            runbooks: Arc::new(Mutex::new(runbooks)),
+        })
+    }
+
+    pub async fn list(&self) -> BTreeMap<String, Runbook> {
+        self.runbooks.lock().await.clone()
+    }
+
+    pub async fn get(&self, name: &str) -> Resul<Runbook> {
+        self.runbooks.lock().await.get(name).cloned().ok_or_else(|| RunbookError::NotFound(name.into()).into())
+    }
+
+    /// Adds or replaces a runbook, names are limited to letters, digits, `-` and `_`
+    pub async fn insert(&self, name: &str, runbook: Runbook) -> Resul<()> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(RunbookError::NameInvalid(name.into()).into());
+        }
+
+        if runbook.steps.is_empty() {
+            return Err(RunbookError::StepsMissing.into());
+        }
+
+        let mut runbooks = self.runbooks.lock().await;
+        let previous = runbooks.insert(name.into(), runbook);
+
+        if let Err(e) = self.save(&runbooks).await {
+            match previous {
+                Some(previous) => runbooks.insert(name.into(), previous),
+                None => runbooks.remove(name),
+            };
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub async fn remove(&self, name: &str) -> Resul<()> {
+        let mut runbooks = self.runbooks.lock().await;
+        let runbook = runbooks.remove(name).ok_or_else(|| RunbookError::NotFound(name.into()))?;
+
+        if let Err(e) = self.save(&runbooks).await {
+            runbooks.insert(name.into(), runbook);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the file, a failed write keeps the previous one
+    async fn save(&self, runbooks: &BTreeMap<String, Runbook>) -> Resul<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        tracing::debug!("[RUNBOOKS] saving runbooks to {}", path.display());
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, serde_yaml::to_string(runbooks)?).await?;
+        tokio::fs::rename(&partial, path).await.map_err(Into::into)
+    }
+}
+
+/// A rendered step with its app or the file registry to write with
+enum ResolvedStep {
+    App(AppBuilders, Value),
+    File(String, Option<String>, Value),
+}
+
+/// Steps of a runbook checked against the apps and file modules of a service before the run starts
+pub struct RunbookRun {
+    steps: Vec<ResolvedStep>,
+    batch: Batch,
+    files: FileRegistry,
+    backup: Option<Backup>,
+    hooks: Vec<FileHook>,
+    locks: FileLocks,
+}
+
+impl RunbookRun {
+    pub fn new(runbook: &Runbook, overrides: &Variables, controller: &Controller, system: &System) -> Resul<Self> {
+        let os = system.os()?;
+        let files = controller.file_registry();
+        let mut steps = vec![];
+
+        for step in runbook.render(overrides)? {
+            steps.push(match step {
+                RunbookStep::App { name, input } => {
+                    let app = controller.app(&name).ok_or(Erro::AppNotFound)?;
+                    if !app.compatible(os) {
+                        tracing::error!("[RUNBOOK] app {} incompatible", name);
+                        return Err(Erro::AppIncompatible);
+                    }
+                    app.validate(&input)?;
+                    ResolvedStep::App(app.clone(), input)
+                }
+                RunbookStep::File { path, module, content } => {
+                    let file = match module.as_deref() {
+                        Some(name) => files.by_name(name)?,
+                        None => files.by_match(&path, system)?,
+                    };
+                    file.validate(&content)?;
+                    ResolvedStep::File(path, module, content)
+                }
+            });
+        }
+
+        Ok(Self {
+            steps,
+            batch: Batch::new(true, runbook.rollback),
+            files,
+            backup: controller.backup().cloned(),
+            hooks: controller.file_hooks().to_vec(),
+            locks: controller.file_locks(),
+        })
+    }
+
+    /// Permissions the user needs for the steps
+    pub fn permissions(&self) -> Vec<Permission> {
+        let mut permissions = vec![];
+
+        if self.steps.iter().any(|step| matches!(step, ResolvedStep::App(_, _))) {
+            permissions.push(Permission::RunApps);
+        }
+
+        if self.steps.iter().any(|step| matches!(step, ResolvedStep::File(_, _, _))) {
+            permissions.push(Permission::WriteFiles);
+        }
+
+        permissions
+    }
+
+    /// Runs all steps with a result for each in their order
+    pub async fn execute(&self, system: &System) -> Vec<BatchResult> {
+        self.batch.execute(self.steps.len(), system, |i| self.step(i, system)).await
+    }
+
+    async fn step(&self, i: usize, system: &System) -> Resul<(Value, Option<Box<dyn Undo>>)> {
+        match &self.steps[i] {
+            ResolvedStep::App(app, input) => {
+                tracing::debug!("[RUNBOOK] running app {}", app.name());
+                app.clone().run_undoable(input.clone(), system).await
+            }
+            ResolvedStep::File(path, module, content) => {
+                let file = match module.as_deref() {
+                    Some(name) => self.files.by_name(name)?,
+                    None => self.files.by_match(path, system)?,
+                };
+
+                tracing::debug!("[RUNBOOK] writing file {}", path);
+                let _lock = self.locks.lock(path).await?;
+                Rest::backup(self.backup.as_ref(), path, system).await?;
+                file.write(path, content.clone(), system).await?;
+
+                let outputs = hook::run_hooks(&self.hooks, file.name(), path, system).await;
+                Ok((to_value(outputs)?, None))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RunbookError {
+    #[error("runbook {0} not found")]
+    NotFound(String),
+    #[error("runbook name {0} is invalid, only letters, digits, '-' and '_' are allowed")]
+    NameInvalid(String),
+    #[error("runbook has no steps")]
+    StepsMissing,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::runbook::{Runbook, RunbookStep, Runbooks};
+    use crate::template::Variables;
+
+    fn runbook() -> Runbook {
+        serde_json::from_value(json!({
+            "vars": {"version": "1.2.3", "dir": "/opt/app"},
+            "steps": [
+                {"app": {"name": "wget", "input": {"url": "https://example.com/app-{{version}}.tar.gz", "output": "/tmp/app.tar.gz"}}},
+                {"file": {"path": "{{dir}}/version", "module": "text", "content": "{{version}}"}}
+            ]
+        })).unwrap()
+    }
+
+    #[test]
+    fn render_overrides() {
+        let overrides: Variables = serde_json::from_value(json!({"version": "2.0.0"})).unwrap();
+        let steps = runbook().render(&overrides).unwrap();
+
+        assert_eq!(steps[0], RunbookStep::App {
+            name: "wget".into(),
+            input: json!({"url": "https://example.com/app-2.0.0.tar.gz", "output": "/tmp/app.tar.gz"}),
+        });
+        assert_eq!(steps[1], RunbookStep::File {
+            path: "/opt/app/version".into(),
+            module: Some("text".into()),
+            content: json!("2.0.0"),
+        });
+
+        let mut missing = runbook();
+        missing.vars.remove("dir");
+        assert!(missing.render(&overrides).is_err());
+    }
+
+    #[tokio::test]
+    async fn persist() {
+        let dir = std::env::temp_dir().join(format!("boofi-runbooks-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("config.runbooks.yml");
+
+        let runbooks = Runbooks::load(&path).await.unwrap();
+        assert!(runbooks.list().await.is_empty());
+        assert!(runbooks.insert("deploy app", runbook()).await.is_err());
+        runbooks.insert("deploy", runbook()).await.unwrap();
+        runbooks.insert("install", runbook()).await.unwrap();
+        runbooks.remove("install").await.unwrap();
+        assert!(runbooks.remove("install").await.is_err());
+
+        let loaded = Runbooks::load(&path).await.unwrap();
+        assert_eq!(loaded.list().await.keys().collect::<Vec<_>>(), vec!["deploy"]);
+        assert_eq!(loaded.get("deploy").await.unwrap(), runbook());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
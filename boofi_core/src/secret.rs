@@ -0,0 +1,346 @@
+use std::fmt::{Debug, Formatter};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use lazy_static::lazy_static;
+use regex::Regex;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{EnumAccess, VariantAccess, Visitor};
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use crate::error::Resul;
+
+/// Replacement of redacted values
+pub const REDACTED: &str = "***";
+
+/// Environment variable with the base64 encoded secret key, instead of the `secret_key` file
+pub const SECRET_KEY_ENV: &str = "BOOFI_SECRET_KEY";
+
+/// Length of the secret key in bytes
+const KEY_LEN: usize = 32;
+
+/// Command lines are logged with their arguments, disabled by default
+static LOG_COMMANDS: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    /// `password=secret`, `token: secret` or `--password secret` of well known secret names
+    static ref SECRET_PATTERN: Regex = Regex::new(concat!(
+        r"(?i)(\b(?:password|passwd|passphrase|secret|token|api[_-]?key)\s*[=:]\s*",
+        r"|--(?:password|passwd|passphrase|secret|token|api[_-]?key)\s+)([^\s,;]+)"
+    )).unwrap();
+}
+
+/// Whether programs are logged with their arguments, which may contain secrets of app inputs
+pub fn set_log_commands(enabled: bool) {
+    LOG_COMMANDS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn log_commands() -> bool {
+    LOG_COMMANDS.load(Ordering::Relaxed)
+}
+
+/// Text with the given secrets and values of well known secret names replaced
+pub fn scrub(text: &str, secrets: &[&str]) -> String {
+    let mut text = text.to_string();
+    for secret in secrets.iter().filter(|s| !s.is_empty()) {
+        text = text.replace(secret, REDACTED);
+    }
+
+    SECRET_PATTERN.replace_all(&text, format!("${{1}}{}", REDACTED)).into_owned()
+}
+
+/// Program with its arguments if commands are logged, otherwise only the number of arguments
+pub fn command_line<T: AsRef<str>>(program: &str, arguments: &[T]) -> String {
+    if log_commands() {
+        let arguments = arguments.iter().map(AsRef::as_ref).collect::<Vec<&str>>().join(" ");
+        scrub(&format!("{} {}", program, arguments), &[])
+    } else {
+        format!("{} with {} arguments", program, arguments.len())
+    }
+}
+
+/// Base64 encoded 256 bit key encrypting secrets with AES-256-GCM, sealed values hold the nonce followed by the ciphertext
+#[derive(Clone)]
+pub struct SecretKey([u8; KEY_LEN]);
+
+impl Debug for SecretKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl SecretKey {
+    /// Key of the base64 text, `source` names where it came from
+    pub fn decode(encoded: &str, source: &str) -> Resul<Self> {
+        let key = STANDARD.decode(encoded.trim()).map_err(|_| SecretError::KeyInvalid(source.into()))?;
+
+        Ok(Self(key.try_into().map_err(|_| SecretError::KeyInvalid(source.into()))?))
+    }
+
+    pub async fn load(path: &str) -> Resul<Self> {
+        Self::decode(&tokio::fs::read_to_string(path).await?, path)
+    }
+
+    /// Key of `BOOFI_SECRET_KEY`, the variable is removed so that programs don't inherit it
+    pub fn from_env() -> Resul<Option<Self>> {
+        let Ok(encoded) = std::env::var(SECRET_KEY_ENV) else {
+            return Ok(None);
+        };
+
+        std::env::remove_var(SECRET_KEY_ENV);
+        Self::decode(&encoded, SECRET_KEY_ENV).map(Some)
+    }
+
+    /// Loads the key, a random key only readable by the owner is saved if the file is missing
+    pub async fn load_or_generate(path: &str) -> Resul<Self> {
+        if tokio::fs::try_exists(path).await? {
+            return Self::load(path).await;
+        }
+
+        let mut key = [0u8; KEY_LEN];
+        SystemRandom::new().fill(&mut key).map_err(|_| SecretError::Crypto)?;
+        write_private(Path::new(path), STANDARD.encode(key).as_bytes()).await?;
+        tracing::info!("[SECRET] new key saved to {}", path);
+
+        Ok(Self(key))
+    }
+
+    fn key(&self) -> Resul<LessSafeKey> {
+        Ok(LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &self.0).map_err(|_| SecretError::Crypto)?))
+    }
+
+    pub fn seal(&self, plain: &[u8]) -> Resul<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| SecretError::Crypto)?;
+
+        let mut sealed = plain.to_vec();
+        self.key()?.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed)
+            .map_err(|_| SecretError::Crypto)?;
+
+        Ok([nonce.as_slice(), &sealed].concat())
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> Resul<Vec<u8>> {
+        if sealed.len() < NONCE_LEN {
+            return Err(SecretError::Decrypt.into());
+        }
+
+        let (nonce, sealed) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| SecretError::Decrypt)?;
+        let mut sealed = sealed.to_vec();
+        let plain = self.key()?.open_in_place(nonce, Aad::empty(), &mut sealed).map_err(|_| SecretError::Decrypt)?;
+
+        Ok(plain.to_vec())
+    }
+
+    /// Configuration value `!vault <base64>` of the text
+    pub fn encrypt(&self, plain: &str) -> Resul<Secret> {
+        Ok(Secret {
+            source: SecretSource::Vault(STANDARD.encode(self.seal(plain.as_bytes())?)),
+            plain: Some(plain.into()),
+        })
+    }
+}
+
+/// Writes a file only readable by the owner
+pub async fn write_private(path: &Path, content: &[u8]) -> Resul<()> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .await?;
+
+    file.write_all(content).await.map_err(Into::into)
+}
+
+/// Where the value of a secret comes from, tagged in yaml
+/// env:    `!env NAME` environment variable
+/// file:   `!file /path` content of the file without trailing whitespace
+/// vault:  `!vault <base64>` encrypted with the secret key, see `boofi encrypt-secret`
+#[derive(Clone, PartialEq)]
+enum SecretSource {
+    Plain,
+    Env(String),
+    File(String),
+    Vault(String),
+}
+
+/// Secret of the configuration, plain text or a tagged source resolved when the configuration is loaded.
+/// It is serialized as configured, the resolved text is only kept in memory.
+#[derive(Clone, PartialEq)]
+pub struct Secret {
+    source: SecretSource,
+    plain: Option<String>,
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self { source: SecretSource::Plain, plain: Some(value) }
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        value.to_string().into()
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.source {
+            SecretSource::Plain => serializer.serialize_str(self.plain.as_deref().unwrap_or_default()),
+            SecretSource::Env(name) => serializer.serialize_newtype_variant("Secret", 0, "env", name),
+            SecretSource::File(path) => serializer.serialize_newtype_variant("Secret", 1, "file", path),
+            SecretSource::Vault(sealed) => serializer.serialize_newtype_variant("Secret", 2, "vault", sealed),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SecretVisitor)
+    }
+}
+
+struct SecretVisitor;
+
+impl<'de> Visitor<'de> for SecretVisitor {
+    type Value = Secret;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a text or a !env, !file or !vault tagged text")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(v.to_string().into())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(v.to_string().into())
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let (tag, value): (String, _) = data.variant()?;
+        let value: String = value.newtype_variant()?;
+
+        let source = match tag.as_str() {
+            "env" => SecretSource::Env(value),
+            "file" => SecretSource::File(value),
+            "vault" => SecretSource::Vault(value),
+            _ => return Err(de::Error::unknown_variant(&tag, &["env", "file", "vault"])),
+        };
+
+        Ok(Secret { source, plain: None })
+    }
+}
+
+impl Secret {
+    /// Reads the value of a tagged secret, only `!vault` needs the key
+    pub async fn resolve(&mut self, key: Option<&SecretKey>) -> Resul<()> {
+        let plain = match &self.source {
+            SecretSource::Plain => return Ok(()),
+            SecretSource::Env(name) => std::env::var(name).map_err(|_| SecretError::EnvMissing(name.clone()))?,
+            SecretSource::File(path) => tokio::fs::read_to_string(path).await?.trim_end().to_string(),
+            SecretSource::Vault(encoded) => {
+                let key = key.ok_or(SecretError::KeyMissing)?;
+                let sealed = STANDARD.decode(encoded).map_err(|_| SecretError::Decrypt)?;
+                String::from_utf8(key.open(&sealed)?)?
+            }
+        };
+
+        self.plain = Some(plain);
+        Ok(())
+    }
+
+    /// Resolved text, a tagged secret which was not resolved fails
+    pub fn expose(&self) -> Resul<&str> {
+        self.plain.as_deref().ok_or_else(|| SecretError::Unresolved.into())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("secret key in {0} is not a base64 encoded 256 bit key")]
+    KeyInvalid(String),
+    #[error("!vault secrets need the secret_key of the configuration, BOOFI_SECRET_KEY or the key at the prompt")]
+    KeyMissing,
+    #[error("environment variable {0} of a secret is not set")]
+    EnvMissing(String),
+    #[error("secret was not resolved when the configuration was loaded")]
+    Unresolved,
+    #[error("secret can't be decrypted, the key changed or the value is damaged")]
+    Decrypt,
+    #[error("secret encryption failed")]
+    Crypto,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::secret::{scrub, Secret, SecretKey};
+    use crate::system::Credential;
+
+    #[test]
+    fn test_scrub() {
+        assert_eq!(scrub("su: Authentication failure for dev with s3cret", &["s3cret"]), "su: Authentication failure for dev with ***");
+        assert_eq!(scrub("mysql -u root --password=hunter2 -e 'select 1'", &[]), "mysql -u root --password=*** -e 'select 1'");
+        assert_eq!(scrub("Token: abc123, user: dev", &[]), "Token: ***, user: dev");
+        assert_eq!(scrub("[sudo] password for dev: Sorry, try again.", &[]), "[sudo] password for dev: Sorry, try again.");
+        assert_eq!(scrub("curl --token abc -u dev", &[]), "curl --token *** -u dev");
+        assert_eq!(scrub("nothing to hide", &[""]), "nothing to hide");
+        assert!(!format!("{:?}", Credential::new("dev", "s3cret")).contains("s3cret"));
+    }
+
+    #[tokio::test]
+    async fn test_secret() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret.key").to_string_lossy().to_string();
+        let key = SecretKey::load_or_generate(&path).await.unwrap();
+
+        let encrypted = serde_yaml::to_string(&key.encrypt("s3cret").unwrap()).unwrap();
+        assert!(encrypted.starts_with("!vault ") && !encrypted.contains("s3cret"));
+
+        let mut secret: Secret = serde_yaml::from_str(&encrypted).unwrap();
+        assert!(secret.expose().is_err());
+        assert!(secret.clone().resolve(None).await.is_err());
+
+        // same key after a restart
+        secret.resolve(Some(&SecretKey::load(&path).await.unwrap())).await.unwrap();
+        assert_eq!(secret.expose().unwrap(), "s3cret");
+        assert_eq!(serde_yaml::to_string(&secret).unwrap(), encrypted);
+        assert!(!format!("{:?}", secret).contains("s3cret"));
+
+        let other = SecretKey::load_or_generate(&dir.path().join("other.key").to_string_lossy()).await.unwrap();
+        assert!(secret.clone().resolve(Some(&other)).await.is_err());
+
+        let file = dir.path().join("password");
+        std::fs::write(&file, "from file\n").unwrap();
+        let mut secret: Secret = serde_yaml::from_str(&format!("!file {}", file.display())).unwrap();
+        secret.resolve(None).await.unwrap();
+        assert_eq!(secret.expose().unwrap(), "from file");
+        assert_eq!(serde_yaml::to_string(&secret).unwrap().trim(), format!("!file {}", file.display()));
+
+        let mut secret: Secret = serde_yaml::from_str("!env BOOFI_TEST_SECRET_MISSING").unwrap();
+        assert!(secret.resolve(None).await.is_err());
+        assert!(serde_yaml::from_str::<Secret>("!unknown value").is_err());
+
+        let mut plain: Secret = serde_yaml::from_str("1234").unwrap();
+        plain.resolve(None).await.unwrap();
+        assert_eq!(plain.expose().unwrap(), "1234");
+        assert_eq!(SecretKey::decode("c2hvcnQ=", "test").unwrap_err().to_string(), "secret key in test is not a base64 encoded 256 bit key");
+    }
+}
@@ -0,0 +1,1494 @@
+pub mod os;
+pub mod posix;
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::description::*;
+use crate::secret::REDACTED;
+use crate::audit::ShellSession;
+use crate::error::{Erro, Resul};
+use crate::system::os::Os;
+use crate::system::posix::Posix;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileType {
+    File,
+    Directory,
+    CharacterDevice,
+    BlockDevice,
+    NamedPipe,
+    SymbolicLink,
+    Socket,
+}
+
+impl FileType {
+    #[allow(dead_code)]
+    pub fn is_file(&self) -> bool {
+        self == &Self::File
+    }
+
+    #[allow(dead_code)]
+    pub fn is_directory(&self) -> bool {
+        self == &Self::Directory
+    }
+
+    /// type letter of `find -printf %y`
+    fn from_find(letter: &str) -> Option<Self> {
+        Some(match letter {
+            "f" => Self::File,
+            "d" => Self::Directory,
+            "c" => Self::CharacterDevice,
+            "b" => Self::BlockDevice,
+            "p" => Self::NamedPipe,
+            "l" => Self::SymbolicLink,
+            "s" => Self::Socket,
+            _ => return None,
+        })
+    }
+}
+
+/// Checksum algorithms of file contents computed on the target
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Sha256,
+    Md5,
+}
+
+/// Ownership, permissions, size and modification time (unix timestamp) of a file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metadata {
+    owner: String,
+    group: String,
+    permissions: String,
+    size: u64,
+    modified: u64,
+}
+
+impl Metadata {
+    /// `stat` format with one value per line, see `parse`
+    pub const STAT_FORMAT: &'static str = "%U\n%G\n%a\n%s\n%Y\n";
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// parse the output of `stat --printf` with `STAT_FORMAT` for one or more files
+    pub fn parse(content: &str) -> Resul<Vec<Self>> {
+        let lines: Vec<&str> = content.lines().collect();
+
+        lines.chunks(5)
+            .map(|chunk| {
+                if let [owner, group, permissions, size, modified] = chunk {
+                    Ok(Self {
+                        owner: owner.to_string(),
+                        group: group.to_string(),
+                        permissions: permissions.to_string(),
+                        size: size.parse()?,
+                        modified: modified.parse()?,
+                    })
+                } else {
+                    Err(Erro::MetadataInvalid(chunk.join("\n")))
+                }
+            })
+            .collect()
+    }
+}
+
+/// Name, type and metadata of a directory entry, symbolic links are not followed
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirEntry {
+    pub name: String,
+    pub file_type: FileType,
+    pub metadata: Metadata,
+}
+
+impl DirEntry {
+    /// `find -printf` format with every value terminated by a null byte, names may contain newlines
+    pub const FIND_FORMAT: &'static str = "%f\\0%y\\0%u\\0%g\\0%m\\0%s\\0%T@\\0";
+
+    /// parse the output of `find -printf` with `FIND_FORMAT`
+    pub fn parse(content: &str) -> Resul<Vec<Self>> {
+        if content.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let values: Vec<&str> = content.strip_suffix('\0').unwrap_or(content).split('\0').collect();
+
+        values.chunks(7)
+            .map(|chunk| {
+                if let [name, file_type, owner, group, permissions, size, modified] = chunk {
+                    Ok(Self {
+                        name: name.to_string(),
+                        file_type: FileType::from_find(file_type).ok_or_else(|| Erro::FileTypeUnknown(name.to_string()))?,
+                        metadata: Metadata {
+                            owner: owner.to_string(),
+                            group: group.to_string(),
+                            permissions: permissions.to_string(),
+                            size: size.parse()?,
+                            // seconds with fraction
+                            modified: modified.split('.').next().unwrap_or_default().parse()?,
+                        },
+                    })
+                } else {
+                    Err(Erro::MetadataInvalid(chunk.join("\n")))
+                }
+            })
+            .collect()
+    }
+}
+
+/// Limits of a single program run, `None` is unlimited
+/// timeout:    seconds until the program is stopped
+/// max_output: bytes of stdout and stderr each
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunLimits {
+    pub timeout: Option<u64>,
+    pub max_output: Option<usize>,
+}
+
+impl RunLimits {
+    pub fn new(timeout: Option<u64>, max_output: Option<usize>) -> Self {
+        Self {
+            timeout,
+            max_output,
+        }
+    }
+
+    /// the stricter value of both limits wins
+    pub fn restrict(&self, other: &RunLimits) -> Self {
+        fn min<T: Ord + Copy>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+
+        Self {
+            timeout: min(self.timeout, other.timeout),
+            max_output: min(self.max_output, other.max_output),
+        }
+    }
+
+    /// a timeout of 0 would stop every program right away
+    pub fn validate(&self) -> Resul<()> {
+        match self.timeout {
+            Some(0) => Err(Erro::RunTimeoutInvalid),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_output(max_output: Option<usize>, len: usize) -> Resul<()> {
+        match max_output {
+            Some(max) if len > max => Err(Erro::OutputTooLarge(max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Limits of the file transfers of a service, `None` is unlimited
+/// max_read:   bytes of a file which are read at once
+/// max_write:  bytes of a file which are written
+/// max_rate:   bytes per second of all reads and writes, transfers are delayed to keep the average below
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransferLimits {
+    pub max_read: Option<u64>,
+    pub max_write: Option<u64>,
+    pub max_rate: Option<u64>,
+}
+
+/// Transfer limits with the time the rate allows the next transfer, shared by all systems of a service
+#[derive(Debug, Clone, Default)]
+pub struct Transfer {
+    limits: TransferLimits,
+    next: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Transfer {
+    fn check_read(&self, size: u64) -> Resul<()> {
+        match self.limits.max_read {
+            Some(max) if size > max => Err(Erro::ReadTooLarge(size, max)),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_write(&self, size: u64) -> Resul<()> {
+        match self.limits.max_write {
+            Some(max) if size > max => Err(Erro::WriteTooLarge(size, max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Waits until the bytes fit into the rate, transfers of the service queue up behind each other
+    async fn throttle(&self, bytes: usize) {
+        let Some(rate) = self.limits.max_rate.filter(|rate| *rate > 0) else {
+            return;
+        };
+
+        let until = {
+            let mut next = self.next.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            let until = next.filter(|n| *n > now).unwrap_or(now) + Duration::from_secs_f64(bytes as f64 / rate as f64);
+            *next = Some(until);
+            until
+        };
+
+        tracing::trace!("[TRANSFER] throttling {} bytes for {:?}", bytes, until.saturating_duration_since(Instant::now()));
+        tokio::time::sleep_until(until.into()).await;
+    }
+}
+
+/// Result of a finished program, a non-zero exit code is not an error
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Description)]
+pub struct RunOutput {
+    stdout: String,
+    stderr: String,
+    exit_code: usize,
+    duration_ms: usize,
+}
+
+impl RunOutput {
+    pub fn new(stdout: String, stderr: String, exit_code: u32, duration: Duration) -> Self {
+        Self {
+            stdout,
+            stderr,
+            exit_code: exit_code as usize,
+            duration_ms: duration.as_millis() as usize,
+        }
+    }
+
+    pub fn stdout(&self) -> &str { self.stdout.as_str() }
+
+    pub fn stderr(&self) -> &str { self.stderr.as_str() }
+
+    pub fn exit_code(&self) -> usize { self.exit_code }
+}
+
+#[derive(Clone)]
+pub struct Credential {
+    username: String,
+    password: String,
+}
+
+impl Debug for Credential {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credential")
+            .field("username", &self.username)
+            .field("password", &REDACTED)
+            .finish()
+    }
+}
+
+impl Credential {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    pub fn username(&self) -> &str { self.username.as_str() }
+
+    pub fn password(&self) -> &str { self.password.as_str() }
+}
+
+/// Defines necessary methods to perform platform specific actions.
+#[async_trait]
+pub trait PlatformActions {
+    fn name() -> &'static str;
+
+    /// Returns a new instance if it is responsible for the endpoint.
+    async fn detect(credentials: Credential, endpoint: Option<&str>) -> Resul<Option<Self>> where Self: Sized;
+
+    fn endpoint(&self) -> Option<&str>;
+
+    fn credential(&self) -> &Credential;
+
+    async fn verify_credential(&self) -> Resul<()>;
+
+    /// call a program on local machine
+    async fn run_user<T: AsRef<str> + Send + Sync>(&self, _path: &str, _arguments: &[T]) -> Resul<Vec<u8>> {
+        Err(Erro::RunUserUnsupported(Self::name()))
+    }
+
+    /// call a program on remote machine
+    async fn run_ssh<T: AsRef<str> + Send + Sync>(&self, _path: &str, _arguments: &[T]) -> Resul<Vec<u8>> {
+        Err(Erro::RunUserUnsupported(Self::name()))
+    }
+
+    /// call a program local or remote and return its output, exit code and duration even if it failed
+    async fn run_output<T: AsRef<str> + Send + Sync>(&self, _path: &str, _arguments: &[T]) -> Resul<RunOutput> {
+        Err(Erro::RunOutputUnsupported(Self::name()))
+    }
+
+    /// read a file on local machine
+    async fn read_user(&self, _path: &str) -> Resul<Vec<u8>> {
+        Err(Erro::ReadUserUnsupported(Self::name()))
+    }
+
+    /// read a file on remote machine
+    async fn read_ssh(&self, _path: &str) -> Resul<Vec<u8>> {
+        Err(Erro::ReadSshUnsupported(Self::name()))
+    }
+
+    /// write a file on local machine
+    async fn write_user(&self, _path: &str, _content: &[u8]) -> Resul<()> {
+        Err(Erro::WriteUserUnsupported(Self::name()))
+    }
+
+    /// write a file on remote machine
+    async fn write_ssh(&self, _path: &str, _content: &[u8]) -> Resul<()> {
+        Err(Erro::WriteSshUnsupported(Self::name()))
+    }
+
+    /// delete a file on local machine
+    async fn delete_user(&self, _path: &str) -> Resul<()> {
+        Err(Erro::DeleteUserUnsupported(Self::name()))
+    }
+
+    /// delete a file on remote machine
+    async fn delete_ssh(&self, _path: &str) -> Resul<()> {
+        Err(Erro::DeleteSshUnsupported(Self::name()))
+    }
+
+    /// run a program on remote or local with arguments
+    async fn run_args<T: AsRef<str> + Send + Sync>(&self, path: &str, arguments: &[T]) -> Resul<Vec<u8>> {
+        if self.endpoint().is_some() {
+            self.run_ssh(path, arguments).await
+        } else {
+            self.run_user(path, arguments).await
+        }
+    }
+
+    /// run a program on local or remote
+    async fn run(&self, path: &str) -> Resul<Vec<u8>> {
+        self.run_args::<&str>(path, &[]).await
+    }
+
+    /// read a file on local or remote
+    async fn read(&self, path: &str) -> Resul<Vec<u8>> {
+        if self.endpoint().is_some() {
+            self.read_ssh(path).await
+        } else {
+            self.read_user(path).await
+        }
+    }
+
+    /// read `length` bytes or until the end of a file starting at `offset`
+    async fn read_range(&self, _path: &str, _offset: usize, _length: Option<usize>) -> Resul<Vec<u8>> {
+        Err(Erro::ReadRangeUnsupported(Self::name()))
+    }
+
+    /// last `lines` lines of a file
+    async fn tail(&self, _path: &str, _lines: usize) -> Resul<Vec<u8>> {
+        Err(Erro::TailUnsupported(Self::name()))
+    }
+
+    /// read a file on local or remote into string
+    async fn read_to_string(&self, path: &str) -> Resul<String> {
+        String::from_utf8(self.read(path).await?).map_err(Into::into)
+    }
+
+    /// write a file on remote or local
+    async fn write(&self, path: &str, content: &[u8]) -> Resul<()> {
+        if self.endpoint().is_some() {
+            self.write_ssh(path, content).await
+        } else {
+            self.write_user(path, content).await
+        }
+    }
+
+    /// delete a file on local or remote
+    async fn delete(&self, path: &str) -> Resul<()> {
+        if self.endpoint().is_some() {
+            self.delete_ssh(path).await
+        } else {
+            self.delete_user(path).await
+        }
+    }
+
+    /// detect the specific operating system release
+    async fn detect_os(&self) -> Resul<Os>;
+
+    /// returns the file type e.g. file, directory, ..
+    async fn file_type(&self, _path: &str) -> Resul<FileType> {
+        Err(Erro::FileTypeUnsupported)
+    }
+
+    /// same as `file_type` but of the target if the path is a symbolic link
+    async fn target_file_type(&self, _path: &str) -> Resul<FileType> {
+        Err(Erro::FileTypeUnsupported)
+    }
+
+    /// returns if a file like type exist or not
+    async fn exist(&self, _path: &str) -> Resul<bool> {
+        Err(Erro::PathExistUnsupported)
+    }
+
+    /// run all following commands with `sudo`
+    fn set_sudo(&mut self, _sudo: bool) -> Resul<()> {
+        Err(Erro::SudoUnsupported(Self::name()))
+    }
+
+    /// run all following commands as another local user
+    fn set_run_as(&mut self, _user: Option<String>) -> Resul<()> {
+        Err(Erro::SudoUnsupported(Self::name()))
+    }
+
+    /// programs of the detected operating system are used from now on
+    fn set_os(&mut self, _os: &Os) {}
+
+    /// limits of each program run
+    fn limits(&self) -> RunLimits {
+        RunLimits::default()
+    }
+
+    /// set limits of each following program run
+    fn set_limits(&mut self, _limits: RunLimits) -> Resul<()> {
+        Err(Erro::RunLimitsUnsupported(Self::name()))
+    }
+
+    /// returns owner, group, permissions, size and modification time
+    async fn metadata(&self, path: &str) -> Resul<Metadata> {
+        self.metadata_list(&[path]).await?.pop().ok_or(Erro::MetadataInvalid(path.into()))
+    }
+
+    /// same as `metadata` for multiple files in the same order
+    async fn metadata_list(&self, _paths: &[&str]) -> Resul<Vec<Metadata>> {
+        Err(Erro::MetadataUnsupported)
+    }
+
+    /// entries of a directory without `.` and `..`
+    async fn dir_list(&self, _path: &str) -> Resul<Vec<DirEntry>> {
+        Err(Erro::DirListUnsupported)
+    }
+
+    /// change permissions to an octal mode like `0644`
+    async fn chmod(&self, _path: &str, _mode: &str) -> Resul<()> {
+        Err(Erro::ChmodUnsupported(Self::name()))
+    }
+
+    /// change owner and/or group, at least one of them must be given
+    async fn chown(&self, _path: &str, _owner: Option<&str>, _group: Option<&str>) -> Resul<()> {
+        Err(Erro::ChownUnsupported(Self::name()))
+    }
+
+    /// copy a file or a directory with all its content
+    async fn copy(&self, _source: &str, _target: &str) -> Resul<()> {
+        Err(Erro::CopyUnsupported(Self::name()))
+    }
+
+    /// lowercase hex encoded SHA-256 of the file content
+    async fn hash(&self, _path: &str) -> Resul<String> {
+        Err(Erro::HashUnsupported(Self::name()))
+    }
+
+    /// lowercase hex encoded checksum of the file content without transferring it
+    async fn checksum(&self, _path: &str, _algorithm: HashAlgorithm) -> Resul<String> {
+        Err(Erro::HashUnsupported(Self::name()))
+    }
+
+    /// move or rename a file or a directory
+    async fn rename(&self, _source: &str, _target: &str) -> Resul<()> {
+        Err(Erro::RenameUnsupported(Self::name()))
+    }
+
+    /// create an empty directory on local or remote
+    async fn create_dir(&self, _path: &str) -> Resul<()> {
+        Err(Erro::CreateDirUnsupported(Self::name()))
+    }
+
+    /// create a directory with all missing parents on local or remote
+    async fn create_dir_all(&self, _path: &str) -> Resul<()> {
+        Err(Erro::CreateDirUnsupported(Self::name()))
+    }
+
+    /// remove an empty directory on local or remote
+    async fn remove_dir(&self, _path: &str) -> Resul<()> {
+        Err(Erro::RemoveDirUnsupported(Self::name()))
+    }
+
+    /// remove a directory with all its content on local or remote
+    async fn remove_dir_all(&self, _path: &str) -> Resul<()> {
+        Err(Erro::RemoveDirUnsupported(Self::name()))
+    }
+
+    /// directory for scratch files e.g. uploads, `None` uses the default temporary directory
+    fn set_workspace(&mut self, _dir: Option<String>) -> Resul<()> {
+        Err(Erro::WorkspaceUnsupported(Self::name()))
+    }
+
+    /// create a directory shared by all users like `/tmp` if it is missing
+    async fn create_workspace(&self, _dir: &str) -> Resul<()> {
+        Err(Erro::WorkspaceUnsupported(Self::name()))
+    }
+
+    /// create an empty file or directory with a random name in a directory, which is created if missing
+    async fn create_temp(&self, _dir: &str, _directory: bool) -> Resul<String> {
+        Err(Erro::WorkspaceUnsupported(Self::name()))
+    }
+
+    /// write a chunk at the offset of a file, it is created if missing and everything behind the offset is dropped
+    async fn write_chunk(&self, _path: &str, _offset: usize, _content: &[u8]) -> Resul<()> {
+        Err(Erro::WriteChunkUnsupported(Self::name()))
+    }
+
+    /// replace a file atomically with a copy of the source, mode and ownership of an existing target are kept.
+    /// The target is overwritten in place if it can not be replaced without losing its ownership
+    async fn replace(&self, _source: &str, _target: &str) -> Resul<()> {
+        Err(Erro::ReplaceUnsupported(Self::name()))
+    }
+
+    /// remove the entries of a directory owned by the user which were not modified within `max_age`
+    async fn remove_stale(&self, _dir: &str, _max_age: Duration) -> Resul<()> {
+        Err(Erro::WorkspaceUnsupported(Self::name()))
+    }
+}
+
+/// Available platforms
+#[derive(Clone)]
+pub enum Platform {
+    Posix(Posix),
+}
+
+/// Interact between code and operating system
+/// shell_session:  records the commands of the `sh` app if shell recording is enabled
+#[derive(Clone)]
+pub struct System {
+    platform: Platform,
+    os: Option<Os>,
+    shell_session: Option<ShellSession>,
+    transfer: Transfer,
+}
+
+impl System {
+    #[cfg(test)]
+    pub fn new(mut platform: Platform, os: Option<Os>) -> Self {
+        if let (Platform::Posix(posix), Some(os)) = (&mut platform, &os) {
+            posix.set_os(os);
+        }
+
+        Self {
+            platform,
+            os,
+            shell_session: None,
+            transfer: Default::default(),
+        }
+    }
+
+    pub fn os(&self) -> Resul<&Os> {
+        self.os.as_ref().ok_or(Erro::OsDetection)
+    }
+
+    pub fn credential(&self) -> &Credential {
+        match &self.platform {
+            Platform::Posix(posix) => posix.credential()
+        }
+    }
+
+    pub async fn verify_credential(&self) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(posix) => posix.verify_credential().await
+        }
+    }
+
+    async fn detect(credential: Credential, endpoint: Option<&str>) -> Resul<Self> {
+        let platform = if let Some(t) = Posix::detect(credential.clone(), endpoint).await? {
+            Platform::Posix(t)
+        } else {
+            return Err(Erro::EndpointIncompatible);
+        };
+
+        Ok(Self {
+            platform,
+            os: None,
+            shell_session: None,
+            transfer: Default::default(),
+        })
+    }
+
+    async fn detect_os(&mut self) -> Resul<&Os> {
+        let os = match &self.platform {
+            Platform::Posix(posix) => posix.detect_os().await
+        }?;
+
+        match &mut self.platform {
+            Platform::Posix(posix) => posix.set_os(&os)
+        }
+
+        self.os = Some(os);
+        self.os()
+    }
+
+    pub async fn run_args<T: AsRef<str> + Send + Sync>(&self, path: &str, arguments: &[T]) -> Resul<Vec<u8>> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.run_args(path, arguments).await
+            }
+        }
+    }
+
+    pub async fn run_output<T: AsRef<str> + Send + Sync>(&self, path: &str, arguments: &[T]) -> Resul<RunOutput> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.run_output(path, arguments).await
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn run(&self, path: &str) -> Resul<Vec<u8>> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.run(path).await
+            }
+        }
+    }
+
+    /// Bytes of a file which would be read from the offset, only checked if reads are limited
+    async fn check_read(&self, path: &str, offset: usize, length: Option<usize>) -> Resul<()> {
+        if self.transfer.limits.max_read.is_some() {
+            let size = self.metadata(path).await?.size().saturating_sub(offset as u64);
+            self.transfer.check_read(length.map(|l| size.min(l as u64)).unwrap_or(size))?;
+        }
+
+        Ok(())
+    }
+
+    /// the size is checked before, files like `/proc/*` without one after the read
+    #[allow(dead_code)]
+    pub async fn read(&self, path: &str) -> Resul<Vec<u8>> {
+        self.check_read(path, 0, None).await?;
+
+        let content = match &self.platform {
+            Platform::Posix(t) => {
+                t.read(path).await
+            }
+        }?;
+
+        self.transfer.check_read(content.len() as u64)?;
+        self.transfer.throttle(content.len()).await;
+        Ok(content)
+    }
+
+    pub async fn read_range(&self, path: &str, offset: usize, length: Option<usize>) -> Resul<Vec<u8>> {
+        self.check_read(path, offset, length).await?;
+
+        let content = match &self.platform {
+            Platform::Posix(t) => {
+                t.read_range(path, offset, length).await
+            }
+        }?;
+
+        self.transfer.throttle(content.len()).await;
+        Ok(content)
+    }
+
+    /// the size of the lines is only known after the read, so it is checked then
+    pub async fn tail(&self, path: &str, lines: usize) -> Resul<Vec<u8>> {
+        let content = match &self.platform {
+            Platform::Posix(t) => {
+                t.tail(path, lines).await
+            }
+        }?;
+
+        self.transfer.check_read(content.len() as u64)?;
+        self.transfer.throttle(content.len()).await;
+        Ok(content)
+    }
+
+    pub async fn read_to_string(&self, path: &str) -> Resul<String> {
+        String::from_utf8(self.read(path).await?).map_err(Into::into)
+    }
+
+    pub async fn write(&self, path: &str, content: &[u8]) -> Resul<()> {
+        self.transfer.check_write(content.len() as u64)?;
+
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.write(path, content).await
+            }
+        }?;
+
+        self.transfer.throttle(content.len()).await;
+        Ok(())
+    }
+
+    pub async fn delete(&self, path: &str) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.delete(path).await
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn file_type(&self, path: &str) -> Resul<FileType> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.file_type(path).await
+            }
+        }
+    }
+
+    pub async fn target_file_type(&self, path: &str) -> Resul<FileType> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.target_file_type(path).await
+            }
+        }
+    }
+
+    pub async fn path_exist(&self, path: &str) -> Resul<bool> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.exist(path).await
+            }
+        }
+    }
+
+    pub fn set_sudo(&mut self, sudo: bool) -> Resul<()> {
+        match &mut self.platform {
+            Platform::Posix(t) => {
+                t.set_sudo(sudo)
+            }
+        }
+    }
+
+    pub fn set_transfer(&mut self, transfer: Transfer) {
+        self.transfer = transfer;
+    }
+
+    pub fn shell_session(&self) -> Option<&ShellSession> {
+        self.shell_session.as_ref()
+    }
+
+    pub fn set_shell_session(&mut self, session: Option<ShellSession>) {
+        self.shell_session = session;
+    }
+
+    pub fn set_run_as(&mut self, user: Option<String>) -> Resul<()> {
+        match &mut self.platform {
+            Platform::Posix(t) => {
+                t.set_run_as(user)
+            }
+        }
+    }
+
+    /// apply stricter limits than the current ones
+    pub fn restrict_limits(&mut self, limits: &RunLimits) -> Resul<()> {
+        limits.validate()?;
+
+        match &mut self.platform {
+            Platform::Posix(t) => {
+                let restricted = t.limits().restrict(limits);
+                t.set_limits(restricted)
+            }
+        }
+    }
+
+    pub async fn metadata(&self, path: &str) -> Resul<Metadata> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.metadata(path).await
+            }
+        }
+    }
+
+    pub async fn metadata_list(&self, paths: &[&str]) -> Resul<Vec<Metadata>> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.metadata_list(paths).await
+            }
+        }
+    }
+
+    pub async fn dir_list(&self, path: &str) -> Resul<Vec<DirEntry>> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.dir_list(path).await
+            }
+        }
+    }
+
+    pub async fn chmod(&self, path: &str, mode: &str) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.chmod(path, mode).await
+            }
+        }
+    }
+
+    pub async fn chown(&self, path: &str, owner: Option<&str>, group: Option<&str>) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.chown(path, owner, group).await
+            }
+        }
+    }
+
+    pub async fn copy(&self, source: &str, target: &str) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.copy(source, target).await
+            }
+        }
+    }
+
+    pub async fn hash(&self, path: &str) -> Resul<String> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.hash(path).await
+            }
+        }
+    }
+
+    pub async fn checksum(&self, path: &str, algorithm: HashAlgorithm) -> Resul<String> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.checksum(path, algorithm).await
+            }
+        }
+    }
+
+    pub async fn rename(&self, source: &str, target: &str) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.rename(source, target).await
+            }
+        }
+    }
+
+    pub async fn create_dir(&self, path: &str) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.create_dir(path).await
+            }
+        }
+    }
+
+    pub async fn create_dir_all(&self, path: &str) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.create_dir_all(path).await
+            }
+        }
+    }
+
+    pub async fn remove_dir(&self, path: &str) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.remove_dir(path).await
+            }
+        }
+    }
+
+    pub async fn remove_dir_all(&self, path: &str) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.remove_dir_all(path).await
+            }
+        }
+    }
+
+    pub fn set_workspace(&mut self, dir: Option<String>) -> Resul<()> {
+        match &mut self.platform {
+            Platform::Posix(t) => {
+                t.set_workspace(dir)
+            }
+        }
+    }
+
+    pub async fn create_workspace(&self, dir: &str) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.create_workspace(dir).await
+            }
+        }
+    }
+
+    /// the limit applies to the whole file up to the end of the chunk
+    pub async fn write_chunk(&self, path: &str, offset: usize, content: &[u8]) -> Resul<()> {
+        self.transfer.check_write((offset + content.len()) as u64)?;
+
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.write_chunk(path, offset, content).await
+            }
+        }?;
+
+        self.transfer.throttle(content.len()).await;
+        Ok(())
+    }
+
+    pub async fn replace(&self, source: &str, target: &str) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.replace(source, target).await
+            }
+        }
+    }
+
+    pub async fn create_temp(&self, dir: &str, directory: bool) -> Resul<String> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.create_temp(dir, directory).await
+            }
+        }
+    }
+
+    pub async fn remove_stale(&self, dir: &str, max_age: Duration) -> Resul<()> {
+        match &self.platform {
+            Platform::Posix(t) => {
+                t.remove_stale(dir, max_age).await
+            }
+        }
+    }
+}
+
+/// Detected systems expire after this duration and are detected again with the next request
+const SYSTEM_TTL: Duration = Duration::from_secs(300);
+
+/// A detected system of one user
+struct CachedSystem {
+    created: Instant,
+    system: System,
+}
+
+struct SystemManagerState {
+    systems: HashMap<String, CachedSystem>,
+    limits: RunLimits,
+    transfer: Transfer,
+    workspace: Option<String>,
+    last_seen: Option<SystemTime>,
+}
+
+/// Bring OS, endpoint and credentials together.
+/// Systems are cached per username, clones share the cache and detect without holding the controller lock.
+#[derive(Clone)]
+pub struct SystemManager {
+    endpoint: Option<String>,
+    state: Arc<Mutex<SystemManagerState>>,
+}
+
+impl SystemManager {
+    pub fn new(endpoint: Option<&str>, limits: RunLimits) -> Self {
+        Self {
+            endpoint: endpoint.map(ToString::to_string),
+            state: Arc::new(Mutex::new(SystemManagerState {
+                systems: HashMap::new(),
+                limits,
+                transfer: Default::default(),
+                workspace: None,
+                last_seen: None,
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, SystemManagerState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Detected os of the current system
+    pub fn os(&self) -> Option<Os> {
+        self.state().systems.values().find_map(|c| c.system.os().ok()).cloned()
+    }
+
+    /// Last time the system was used by a request
+    pub fn last_seen(&self) -> Option<SystemTime> {
+        self.state().last_seen
+    }
+
+    /// all systems are detected again if the limits changed
+    pub fn set_limits(&self, limits: RunLimits) {
+        let mut state = self.state();
+        if state.limits != limits {
+            state.limits = limits;
+            state.systems.clear();
+        }
+    }
+
+    /// all systems are detected again if the transfer limits changed, the rate keeps its pending transfers
+    pub fn set_transfer_limits(&self, limits: TransferLimits) {
+        let mut state = self.state();
+        if state.transfer.limits != limits {
+            state.transfer.limits = limits;
+            state.systems.clear();
+        }
+    }
+
+    /// all systems are detected again if the workspace directory changed
+    pub fn set_workspace(&self, dir: Option<String>) {
+        let mut state = self.state();
+        if state.workspace != dir {
+            state.workspace = dir;
+            state.systems.clear();
+        }
+    }
+
+    /// Detected systems which are not expired, e.g. for maintenance without a request
+    pub fn systems(&self) -> Vec<System> {
+        self.state().systems.values()
+            .filter(|c| c.created.elapsed() < SYSTEM_TTL)
+            .map(|c| c.system.clone())
+            .collect()
+    }
+
+    /// all systems are detected again with the next credential
+    pub fn reset(&self) {
+        self.state().systems.clear();
+    }
+
+    pub async fn system_credential(&self, credential: Credential) -> Resul<System> {
+        self.system(credential).await
+    }
+
+    #[tracing::instrument(name = "system", skip_all, fields(user = credential.username()))]
+    async fn system(&self, credential: Credential) -> Resul<System> {
+        let (cached, limits, transfer, workspace) = {
+            let mut state = self.state();
+            state.last_seen = Some(SystemTime::now());
+            state.systems.retain(|_, c| c.created.elapsed() < SYSTEM_TTL);
+
+            let cached = state.systems.get(credential.username())
+                .filter(|c| c.system.credential().password() == credential.password())
+                .map(|c| c.system.clone());
+            (cached, state.limits.clone(), state.transfer.clone(), state.workspace.clone())
+        };
+
+        if let Some(system) = cached {
+            return Ok(system);
+        }
+
+        tracing::debug!("[SYSTEM] detecting system for {}", credential.username());
+        let username = credential.username().to_string();
+        let mut system = System::detect(credential, self.endpoint.as_deref()).await?;
+        system.detect_os().await?; // initial os detection - stored to system
+        system.restrict_limits(&limits)?;
+        system.set_workspace(workspace)?;
+        system.set_transfer(transfer);
+
+        self.state().systems.insert(username, CachedSystem {
+            created: Instant::now(),
+            system: system.clone(),
+        });
+        Ok(system)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    use crate::error::Erro;
+    use std::time::{Duration, Instant};
+    use crate::system::{SystemManager, Credential, DirEntry, FileType, HashAlgorithm, Metadata, RunLimits, CachedSystem, Platform, System, Transfer, TransferLimits};
+    use crate::system::os::Os;
+    use crate::system::posix::Posix;
+    use crate::utils::test::{PASSWORD, SSH_ENDPOINT, system_ssh, system_user, USERNAME};
+
+    fn credential() -> Credential {
+        Credential::new(USERNAME, PASSWORD)
+    }
+
+    fn endpoint() -> Option<&'static str> {
+        Some(SSH_ENDPOINT)
+    }
+
+    #[tokio::test]
+    async fn test_run() {
+        let samples = [
+            ("echo", ["test"].as_slice(), "test\n"),
+            ("uname", ["-s"].as_slice(), "Linux\n"),
+            ("sleep", ["1"].as_slice(), ""),
+            ("true", [].as_slice(), ""),
+            ("sh", ["-c", "echo test"].as_slice(), "test\n"),
+            ("sh", ["-c", "ls /proc | grep uptime"].as_slice(), "uptime\n")
+        ];
+
+        for (command, args, expect) in samples {
+            let system_manager = SystemManager::new(None, Default::default());
+            assert_eq!(system_manager.system(credential()).await.unwrap().run_args(command, args).await.unwrap(), expect.as_bytes());
+
+            let system_manager = SystemManager::new(endpoint(), Default::default());
+            assert_eq!(system_manager.system(credential()).await.unwrap().run_args(command, args).await.unwrap(), expect.as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_failure() {
+        let system_manager = SystemManager::new(None, Default::default());
+        assert!(format!("{:?}", &system_manager.system(credential()).await.unwrap().run("true1").await).contains(r#"not found"#));
+
+        let system_manager = SystemManager::new(endpoint(), Default::default());
+        assert!(format!("{:?}", &system_manager.system(credential()).await.unwrap().run("true1").await).contains(r#"not found"#));
+    }
+
+    #[tokio::test]
+    async fn test_read_write_delete() {
+        let path = "/tmp/testwritefile";
+        let content = "text\nenter\n\n";
+
+        // USER
+        let system_manager = SystemManager::new(None, Default::default());
+        let system = system_manager.system(credential()).await.unwrap();
+        system.write(path, content.as_bytes()).await.unwrap();
+
+        let s = system.read_to_string(path).await.unwrap();
+        assert_eq!(content, s.as_str());
+
+        system.delete(path).await.unwrap();
+        assert!(!Path::new(path).exists());
+
+        // SSH
+        let system_manager = SystemManager::new(endpoint(), Default::default());
+        let system = system_manager.system(credential()).await.unwrap();
+        system.write(path, content.as_bytes()).await.unwrap();
+
+        let s = system.read_to_string(path).await.unwrap();
+        assert_eq!(content, s.as_str());
+
+        system.delete(path).await.unwrap();
+        assert!(!Path::new(path).exists());
+    }
+
+
+    #[tokio::test]
+    async fn test_run_file_type() {
+        for (file, expect) in [
+            /* todo failed in docker environment due to missing type
+            ("/run/initctl", FileType::NamedPipe),
+            ("/run/acpid.socket", FileType::Socket),
+            ("/dev/sr0", FileType::BlockDevice),
+            */
+            ("/etc", FileType::Directory),
+            ("/dev/null", FileType::CharacterDevice),
+            ("/etc/hosts", FileType::File),
+            ("/proc/self", FileType::SymbolicLink),
+        ] {
+            let system = system_user().await;
+            assert_eq!(system.file_type(file).await.unwrap(), expect);
+        }
+
+        let system = system_user().await;
+        assert_eq!(system.target_file_type("/proc/self").await.unwrap(), FileType::Directory);
+        assert_eq!(system.target_file_type("/").await.unwrap(), FileType::Directory);
+    }
+
+    #[tokio::test]
+    async fn test_path_exist() {
+        let exist = "/etc/fstab";
+        let not = "/e/t/c/f/s/t/a/b";
+
+        let system = system_user().await;
+        assert!(system.path_exist(exist).await.unwrap());
+        assert!(!system.path_exist(not).await.unwrap());
+
+        let system = system_ssh().await;
+        assert!(system.path_exist(exist).await.unwrap());
+        assert!(!system.path_exist(not).await.unwrap());
+    }
+
+    #[test]
+    fn test_metadata_parse() {
+        let metadata = Metadata::parse("root\nroot\n644\n220\n1690000000\ndev\nusers\n755\n4096\n1690000001\n").unwrap();
+
+        assert_eq!(metadata, vec![
+            Metadata {
+                owner: "root".into(),
+                group: "root".into(),
+                permissions: "644".into(),
+                size: 220,
+                modified: 1690000000,
+            },
+            Metadata {
+                owner: "dev".into(),
+                group: "users".into(),
+                permissions: "755".into(),
+                size: 4096,
+                modified: 1690000001,
+            },
+        ]);
+
+        assert!(Metadata::parse("root\nroot\n644\n").is_err());
+    }
+
+    #[test]
+    fn test_dir_entry_parse() {
+        let entries = DirEntry::parse("null\x00c\x00root\x00root\x00666\x000\x001690000000.1234567890\x00new\nline\x00f\x00dev\x00users\x00644\x0012\x001690000001.0\x00").unwrap();
+
+        assert_eq!(entries, vec![
+            DirEntry {
+                name: "null".into(),
+                file_type: FileType::CharacterDevice,
+                metadata: Metadata {
+                    owner: "root".into(),
+                    group: "root".into(),
+                    permissions: "666".into(),
+                    size: 0,
+                    modified: 1690000000,
+                },
+            },
+            DirEntry {
+                name: "new\nline".into(),
+                file_type: FileType::File,
+                metadata: Metadata {
+                    owner: "dev".into(),
+                    group: "users".into(),
+                    permissions: "644".into(),
+                    size: 12,
+                    modified: 1690000001,
+                },
+            },
+        ]);
+
+        assert!(DirEntry::parse("").unwrap().is_empty());
+        assert!(DirEntry::parse("door\x00D\x00root\x00root\x00644\x000\x001690000000.0\x00").is_err());
+        assert!(DirEntry::parse("null\x00c\x00root\x00").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_metadata() {
+        for system in [system_user().await, system_ssh().await] {
+            let metadata = system.metadata("/etc/passwd").await.unwrap();
+            assert_eq!(metadata.owner, "root");
+            assert_eq!(metadata.permissions, "644");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_binary_range() {
+        let path = "/tmp/testbinary";
+        let content = [0u8, 1, 2, 255, 10, 13, 34, 128];
+
+        for system in [system_user().await, system_ssh().await] {
+            system.write(path, &content).await.unwrap();
+
+            assert_eq!(system.read(path).await.unwrap(), content);
+            assert_eq!(system.read_range(path, 2, Some(3)).await.unwrap(), &content[2..5]);
+            assert_eq!(system.read_range(path, 6, None).await.unwrap(), &content[6..]);
+
+            system.delete(path).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_limits_restrict() {
+        assert_eq!(RunLimits::new(Some(10), None).restrict(&RunLimits::new(Some(20), Some(1024))), RunLimits::new(Some(10), Some(1024)));
+        assert!(matches!(RunLimits::new(Some(0), None).validate(), Err(Erro::RunTimeoutInvalid)));
+        assert!(RunLimits::new(None, Some(0)).validate().is_ok());
+        assert_eq!(RunLimits::default().restrict(&RunLimits::default()), RunLimits::default());
+    }
+
+    #[tokio::test]
+    async fn test_run_limits() {
+        for mut system in [system_user().await, system_ssh().await] {
+            system.restrict_limits(&RunLimits::new(Some(1), Some(4))).unwrap();
+
+            assert!(matches!(system.run_args("sleep", &["3"]).await, Err(Erro::RunTimeout(1))));
+            assert!(matches!(system.run_args("echo", &["too long"]).await, Err(Erro::OutputTooLarge(4))));
+            assert_eq!(system.run_args("echo", &["ok"]).await.unwrap(), b"ok\n");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_throttle() {
+        let transfer = Transfer { limits: TransferLimits { max_read: Some(4), max_write: None, max_rate: Some(1000) }, ..Default::default() };
+        assert!(matches!(transfer.check_read(5), Err(Erro::ReadTooLarge(5, 4))));
+        assert!(transfer.check_read(4).is_ok());
+        assert!(transfer.check_write(u64::MAX).is_ok());
+
+        let start = Instant::now();
+        let shared = transfer.clone();
+        tokio::join!(transfer.throttle(100), shared.throttle(100));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_limits() {
+        let path = "/tmp/_transfer_limits";
+        for mut system in [system_user().await, system_ssh().await] {
+            system.write(path, b"0123456789").await.unwrap();
+            system.set_transfer(Transfer { limits: TransferLimits { max_read: Some(4), max_write: Some(8), max_rate: None }, ..Default::default() });
+
+            assert!(matches!(system.read(path).await, Err(Erro::ReadTooLarge(10, 4))));
+            assert_eq!(system.read_range(path, 8, None).await.unwrap(), b"89");
+            assert!(matches!(system.tail(path, 10).await, Err(Erro::ReadTooLarge(10, 4))));
+            assert!(matches!(system.write(path, b"012345678").await, Err(Erro::WriteTooLarge(9, 8))));
+            assert!(matches!(system.write_chunk(path, 4, b"01234").await, Err(Erro::WriteTooLarge(9, 8))));
+
+            system.delete(path).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sudo() {
+        let mut system = system_user().await;
+
+        // not readable by user
+        assert!(system.read_to_string("/etc/shadow").await.is_err());
+
+        system.set_sudo(true).unwrap();
+        assert!(system.read_to_string("/etc/shadow").await.unwrap().starts_with("root:"));
+    }
+
+    #[tokio::test]
+    async fn test_run_as() {
+        let mut system = system_user().await;
+
+        system.set_run_as(Some("nobody".into())).unwrap();
+        assert_eq!(system.run_args("/usr/bin/id", &["-un"]).await.unwrap(), b"nobody\n");
+    }
+
+    #[tokio::test]
+    async fn test_chmod_chown() {
+        let path = "/tmp/testchmod";
+
+        for system in [system_user().await, system_ssh().await] {
+            system.write(path, b"content").await.unwrap();
+
+            system.chmod(path, "0600").await.unwrap();
+            assert_eq!(system.metadata(path).await.unwrap().permissions, "600");
+
+            // not octal
+            assert!(system.chmod(path, "u+x").await.is_err());
+
+            system.chown(path, None, Some(USERNAME)).await.unwrap();
+            assert_eq!(system.metadata(path).await.unwrap().group, USERNAME);
+
+            system.delete(path).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_atomic() {
+        let path = "/tmp/testatomic";
+        let link = "/tmp/testatomiclink";
+
+        for system in [system_user().await, system_ssh().await] {
+            system.write(path, b"first").await.unwrap();
+            system.chmod(path, "0640").await.unwrap();
+            let _ = std::fs::remove_file(link);
+            std::os::unix::fs::symlink(path, link).unwrap();
+
+            // mode is kept and the link still points to the replaced file
+            system.write(link, b"second").await.unwrap();
+            assert_eq!(system.read_to_string(path).await.unwrap(), "second");
+            assert_eq!(system.metadata(path).await.unwrap().permissions, "640");
+            assert!(std::fs::symlink_metadata(link).unwrap().file_type().is_symlink());
+
+            // a failed write leaves no temporary file behind
+            assert!(system.write("/tmp/testatomicmissing/file", b"content").await.is_err());
+            assert!(!std::fs::read_dir("/tmp").unwrap().flatten().any(|e| e.file_name().to_string_lossy().starts_with(".testatomic")));
+
+            system.delete(link).await.unwrap();
+            system.delete(path).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_group_writable() {
+        let path = "/tmp/testgroupwritable";
+
+        for system in [system_user().await, system_ssh().await] {
+            // root:<user group> 0664, the user can write the file but not take over its ownership
+            std::fs::write(path, b"first").unwrap();
+            let owner = format!("root:{}", USERNAME);
+            assert!(std::process::Command::new("chown").args([owner.as_str(), path]).output().unwrap().status.success());
+            assert!(std::process::Command::new("chmod").args(["0664", path]).output().unwrap().status.success());
+
+            system.write(path, b"second").await.unwrap();
+            assert_eq!(system.read_to_string(path).await.unwrap(), "second");
+            let metadata = system.metadata(path).await.unwrap();
+            assert_eq!((metadata.owner.as_str(), metadata.group.as_str(), metadata.permissions.as_str()), ("root", USERNAME, "664"));
+
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_rename() {
+        let source = "/tmp/testcopy";
+        let copy = "/tmp/testcopy2";
+        let moved = "/tmp/testcopy3";
+
+        for system in [system_user().await, system_ssh().await] {
+            system.write(source, b"content").await.unwrap();
+
+            system.copy(source, copy).await.unwrap();
+            assert_eq!(system.read_to_string(copy).await.unwrap(), "content");
+            assert!(system.path_exist(source).await.unwrap());
+
+            system.rename(copy, moved).await.unwrap();
+            assert_eq!(system.read_to_string(moved).await.unwrap(), "content");
+            assert!(!system.path_exist(copy).await.unwrap());
+
+            system.delete(source).await.unwrap();
+            system.delete(moved).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hash() {
+        let path = "/tmp/testhash";
+
+        for system in [system_user().await, system_ssh().await] {
+            system.write(path, b"content").await.unwrap();
+            assert_eq!(system.hash(path).await.unwrap(), "ed7002b439e9ac845f22357d822bac1444730fbdb6016d3ec9432297b9ec9f73");
+            assert_eq!(system.checksum(path, HashAlgorithm::Md5).await.unwrap(), "9a0364b9e99bb480dd25e1f0284c8555");
+            system.delete(path).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_remove_dir() {
+        let path = "/tmp/testdir";
+        let nested = "/tmp/testdir/nested";
+
+        for system in [system_user().await, system_ssh().await] {
+            system.create_dir(path).await.unwrap();
+            assert_eq!(system.file_type(path).await.unwrap(), FileType::Directory);
+
+            system.remove_dir(path).await.unwrap();
+            assert!(!system.path_exist(path).await.unwrap());
+
+            system.create_dir(path).await.unwrap();
+            system.create_dir(nested).await.unwrap();
+            system.write("/tmp/testdir/nested/file", b"content").await.unwrap();
+
+            // not empty
+            assert!(system.remove_dir(path).await.is_err());
+
+            system.remove_dir_all(path).await.unwrap();
+            assert!(!system.path_exist(path).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_per_user() {
+        let system_manager = SystemManager::new(None, Default::default());
+        for username in ["alice", "bob"] {
+            let system = System::new(Platform::Posix(Posix::new(Credential::new(username, "secret"), None)), Some(Os::LinuxUnknown));
+            system_manager.state().systems.insert(username.into(), CachedSystem { created: Instant::now(), system });
+        }
+
+        let shared = system_manager.clone();
+        assert_eq!(shared.system(Credential::new("bob", "secret")).await.unwrap().credential().username(), "bob");
+        assert_eq!(system_manager.system(Credential::new("alice", "secret")).await.unwrap().credential().username(), "alice");
+        assert!(system_manager.os().is_some());
+        assert!(system_manager.last_seen().is_some());
+
+        system_manager.set_limits(RunLimits::new(Some(1), None));
+        assert!(shared.state().systems.is_empty());
+
+        system_manager.state().systems.insert("bob".into(), CachedSystem { created: Instant::now(), system: System::new(Platform::Posix(Posix::new(Credential::new("bob", "secret"), None)), Some(Os::LinuxUnknown)) });
+        system_manager.set_transfer_limits(TransferLimits::default());
+        assert_eq!(shared.state().systems.len(), 1);
+        system_manager.set_transfer_limits(TransferLimits { max_read: Some(1), ..Default::default() });
+        assert!(shared.state().systems.is_empty());
+    }
+}
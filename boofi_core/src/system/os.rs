@@ -1,12 +1,15 @@
 use std::str::FromStr;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use crate::error::Erro;
 
 /// known (and unknown) operating systems
-#[derive(Debug, Clone, Serialize, PartialEq)]
-pub(crate) enum Os {
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Os {
     Unknown,
+    /// every supported posix system, used by generic files and apps
+    PosixAny,
+
     LinuxUnknown,
     LinuxAny,
     LinuxArchlinux,
@@ -22,6 +25,9 @@ pub(crate) enum Os {
     LinuxDebianBookworm,
     LinuxDebianBullseye,
     LinuxDebianBuster,
+
+    Macos,
+    FreeBsd,
 }
 
 impl Default for Os {
@@ -36,6 +42,8 @@ impl FromStr for Os {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
             "linux" => Self::LinuxAny,
+            "macos" => Self::Macos,
+            "freebsd" => Self::FreeBsd,
             "luna" => Self::LinuxUbuntuLuna,
             "focal" => Self::LinuxUbuntuFocal,
             "bionic" => Self::LinuxUbuntuBionic,
@@ -48,12 +56,13 @@ impl FromStr for Os {
 }
 
 impl Os {
-    pub(crate) fn compatible(&self, other: &Os) -> bool {
+    pub fn compatible(&self, other: &Os) -> bool {
         if self == other {
             return true;
         }
 
         match self {
+            Os::PosixAny => [Os::LinuxAny, Os::Macos, Os::FreeBsd].contains(other) || Os::LinuxAny.compatible(other),
             Os::LinuxAny => [Os::LinuxArchlinux, Os::LinuxFedora, Os::LinuxOpenSusLeap,
                 Os::LinuxDebian, Os::LinuxUbuntu, Os::LinuxUbuntuBionic, Os::LinuxUbuntuFocal,
                 Os::LinuxUbuntuLuna, Os::LinuxDebianBookworm, Os::LinuxDebianBuster,
@@ -81,6 +90,11 @@ mod test {
         assert!(Os::LinuxUbuntu.compatible(&Os::LinuxAny));
         assert!(Os::LinuxUbuntu.compatible(&Os::LinuxUbuntuLuna));
         assert!(!Os::LinuxUbuntuLuna.compatible(&Os::LinuxUbuntu));
+        assert!(Os::PosixAny.compatible(&Os::LinuxDebianBookworm));
+        assert!(Os::PosixAny.compatible(&Os::Macos));
+        assert!(Os::PosixAny.compatible(&Os::FreeBsd));
+        assert!(!Os::LinuxAny.compatible(&Os::Macos));
+        assert!(!Os::Macos.compatible(&Os::FreeBsd));
     }
 
     #[tokio::test]
@@ -0,0 +1,1186 @@
+use std::net::{TcpStream};
+use std::process::{Stdio};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_ssh2_tokio::{AuthMethod, Client, ServerCheckMethod};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ssh_rs::{SessionBuilder, SessionConnector};
+
+use tokio::spawn;
+use tracing::Instrument;
+use crate::apps::prelude::Os;
+use crate::error::{Erro, Resul};
+use crate::secret;
+
+use crate::files::version::Version;
+use crate::system::{PlatformActions, Credential, DirEntry, FileType, HashAlgorithm, Metadata, RunLimits, RunOutput};
+use std::io::Write;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::sync::{Mutex, oneshot};
+use crate::files::os_release::OsRelease;
+
+/// An open ssh connection which is reused until it is idle for too long
+struct SshSession {
+    client: Client,
+    last_used: Instant,
+}
+
+/// A finished program, also if it exited with a non-zero code
+struct Executed {
+    code: u32,
+    stdout: Vec<u8>,
+    stderr: String,
+}
+
+impl Executed {
+    /// program output if it succeeded, otherwise the error built from code and stderr
+    fn stdout(self, error: fn(u32, String) -> Erro) -> Resul<Vec<u8>> {
+        if self.code == 0 {
+            Ok(self.stdout)
+        } else {
+            Err(error(self.code, self.stderr))
+        }
+    }
+}
+
+/// Compatible with most linux distributions
+#[derive(Clone)]
+pub struct Posix {
+    credential: Credential,
+    endpoint: Option<String>,
+    ssh: Arc<Mutex<Option<SshSession>>>,
+    sudo: bool,
+    run_as: Option<String>,
+    limits: RunLimits,
+    workspace: Option<String>,
+    os: Option<Os>,
+}
+
+impl Posix {
+    /// close and reopen the ssh connection if it was not used for this duration
+    const SSH_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+    /// recognize the password prompt of sudo in stderr
+    const SUDO_PROMPT: &'static str = "[boofi sudo password]";
+
+    /// `timeout` kills the program this long after it was asked to stop
+    const KILL_AFTER: &'static str = "--kill-after=5";
+
+    /// local wait after the timeout before `su` or the ssh command is given up, if `timeout` did not stop the program
+    const TIMEOUT_GRACE: Duration = Duration::from_secs(10);
+
+    /// caps stdout and stderr of a remote program at `$1` bytes each and keeps its exit code,
+    /// the program gets SIGPIPE once a stream is full instead of filling the memory of boofi
+    const CAP_OUTPUT: &'static str = r#"max=$1; shift; exec 3>&1 5>&2; code=$( { { { "$@" 4>&-; echo $? >&4; } 2>&1 1>&6 6>&- | head -c "$max" >&5; } 6>&1 | head -c "$max" >&3; } 4>&1 ); exit "${code:-1}""#;
+
+    /// creates the empty file `$1` readable by its owner only, fails if it exists.
+    /// scp keeps the mode of an existing file, content staged for sudo is never readable by others
+    const PRIVATE_FILE: &'static str = r#"umask 077 && set -C && : > "$1""#;
+
+    #[cfg(test)]
+    pub fn new(credential: Credential, endpoint: Option<String>) -> Self {
+        Self {
+            credential,
+            endpoint,
+            ssh: Default::default(),
+            sudo: false,
+            run_as: None,
+            limits: Default::default(),
+            workspace: None,
+            os: None,
+        }
+    }
+
+    fn su() -> &'static str {
+        "/bin/su"
+    }
+
+    fn unlink() -> &'static str {
+        "/bin/unlink"
+    }
+
+    fn stat() -> &'static str {
+        "/usr/bin/stat"
+    }
+
+    /// resolved through `PATH`, it is `/bin/true` without merged `/usr` and `/usr/bin/true` on macOS and FreeBSD
+    fn r#true() -> &'static str {
+        "true"
+    }
+
+    fn cp() -> &'static str {
+        "/bin/cp"
+    }
+
+    fn cat() -> &'static str {
+        "/bin/cat"
+    }
+
+    fn chmod() -> &'static str {
+        "/bin/chmod"
+    }
+
+    fn chown() -> &'static str {
+        "/bin/chown"
+    }
+
+    fn mv() -> &'static str {
+        "/bin/mv"
+    }
+
+    fn sha256sum() -> &'static str {
+        "/usr/bin/sha256sum"
+    }
+
+    fn md5sum() -> &'static str {
+        "/usr/bin/md5sum"
+    }
+
+    fn shasum() -> &'static str {
+        "/usr/bin/shasum"
+    }
+
+    fn sha256() -> &'static str {
+        "/sbin/sha256"
+    }
+
+    fn md5() -> &'static str {
+        "/sbin/md5"
+    }
+
+    fn base64() -> &'static str {
+        "/usr/bin/base64"
+    }
+
+    fn tail() -> &'static str {
+        "/usr/bin/tail"
+    }
+
+    fn sw_vers() -> &'static str {
+        "/usr/bin/sw_vers"
+    }
+
+    fn uname() -> &'static str {
+        "/usr/bin/uname"
+    }
+
+    fn sudo() -> &'static str {
+        "/usr/bin/sudo"
+    }
+
+    fn timeout() -> &'static str {
+        "/usr/bin/timeout"
+    }
+
+    fn test() -> &'static str { "/bin/test" }
+
+    fn find() -> &'static str {
+        "/usr/bin/find"
+    }
+
+    fn mkdir() -> &'static str {
+        "/bin/mkdir"
+    }
+
+    fn rmdir() -> &'static str {
+        "/bin/rmdir"
+    }
+
+    fn rm() -> &'static str {
+        "/bin/rm"
+    }
+
+    fn sync() -> &'static str {
+        "/bin/sync"
+    }
+
+    fn realpath() -> &'static str {
+        "/usr/bin/realpath"
+    }
+
+    fn mktemp() -> &'static str {
+        "/usr/bin/mktemp"
+    }
+
+    fn dd() -> &'static str {
+        "/bin/dd"
+    }
+
+    fn sh() -> &'static str {
+        "/bin/sh"
+    }
+
+    /// GNU coreutils on linux, macOS and FreeBSD only have the posix options of them.
+    /// Until the system is detected GNU is assumed.
+    fn gnu(&self) -> bool {
+        !matches!(self.os, Some(Os::Macos | Os::FreeBsd))
+    }
+
+    /// Symbolic links are kept, the file they point to is replaced
+    async fn write_target(&self, path: &str) -> Resul<String> {
+        let resolved = String::from_utf8(self.run_args(Self::realpath(), &["-m", "--", path]).await?)?;
+        Ok(resolved.trim_end_matches('\n').to_string())
+    }
+
+    /// Hidden file in the directory of the target, a rename is only atomic within the same filesystem
+    fn write_temp_path(target: &str) -> String {
+        let (directory, name) = target.rsplit_once('/').unwrap_or((".", target));
+        format!("{}/.{}.boofi-{:x}", directory, name, rand::random::<u64>())
+    }
+
+    async fn write_step<T: AsRef<str> + Send + Sync>(&self, target: &str, step: &'static str, program: &str, arguments: &[T]) -> Resul<()> {
+        self.run_args(program, arguments).await
+            .map(|_| ())
+            .map_err(|e| Erro::WriteAtomic(target.into(), step, e.to_string()))
+    }
+
+    /// Takes over mode and ownership of an existing target, syncs the temporary file to disk and renames it over the target.
+    /// `false` if the ownership can not be taken over, e.g. of a group writable file owned by another user, the target is untouched then.
+    async fn write_replace(&self, temp: &str, target: &str) -> Resul<bool> {
+        if self.exist(target).await? {
+            let reference = format!("--reference={}", target);
+            self.write_step(target, "mode", Self::chmod(), &[reference.as_str(), "--", temp]).await?;
+
+            let kept = match self.metadata_list(&[temp, target]).await?.as_slice() {
+                [temp, target] => temp.owner == target.owner && temp.group == target.group,
+                _ => false,
+            };
+            if !kept {
+                if let Err(e) = self.run_args(Self::chown(), &[reference.as_str(), "--", temp]).await {
+                    tracing::debug!("[WRITE] ownership of {:?} not kept: {}", target, e);
+                    return Ok(false);
+                }
+            }
+        }
+
+        self.write_step(target, "sync", Self::sync(), &["--", temp]).await?;
+        tracing::debug!("[WRITE] rename {:?} to {:?}", temp, target);
+        self.write_step(target, "rename", Self::mv(), &["-f", "-T", "--", temp, target]).await?;
+
+        // the rename itself is durable once the directory is synced
+        let directory = target.rsplit_once('/').map(|(d, _)| if d.is_empty() { "/" } else { d }).unwrap_or(".");
+        self.write_step(target, "sync", Self::sync(), &["--", directory]).await?;
+        Ok(true)
+    }
+
+    /// Copies the source next to the target and replaces the target with it atomically.
+    /// Overwrites the target in place if the temporary file can not be created or the ownership not be kept.
+    async fn write_copy(&self, source: &str, path: &str) -> Resul<()> {
+        let target = self.write_target(path).await?;
+        let target_temp = Self::write_temp_path(&target);
+
+        tracing::debug!("[WRITE] copy from {:?} to {:?}", source, target_temp);
+        let result: Resul<bool> = async {
+            self.write_step(&target, "copy", Self::cp(), &[
+                "--no-preserve=mode,ownership", // ignore chmod workaround
+                source,
+                target_temp.as_str(),
+            ]).await?;
+            self.write_replace(&target_temp, &target).await
+        }.await;
+
+        match self.write_discard(&target_temp, result).await {
+            Ok(true) => Ok(()),
+            Ok(false) | Err(Erro::WriteAtomic(_, "copy", _)) => self.write_in_place(source, &target).await,
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Overwrites the target in place, used without GNU coreutils.
+    /// Mode, ownership and symbolic links are kept but a reader may see a partially written file.
+    async fn write_in_place(&self, source: &str, target: &str) -> Resul<()> {
+        tracing::debug!("[WRITE] copy from {:?} over {:?}", source, target);
+        self.write_step(target, "copy", Self::cp(), &["--", source, target]).await
+    }
+
+    /// Uploads next to the target with scp and replaces the target with it
+    async fn write_ssh_direct(&self, local: &Path, path: &str) -> Resul<()> {
+        let target = self.write_target(path).await?;
+        let target_temp = Self::write_temp_path(&target);
+
+        tracing::trace!("[WRITE SSH] connecting ssh scp");
+        let exec = self.ssh_connect_scp()?.run_local().open_scp()?;
+
+        tracing::debug!("[WRITE SSH] upload local {:?} to remote {:?}", local, target_temp);
+        let result: Resul<bool> = async {
+            exec.upload(local, target_temp.as_ref())
+                .map_err(|e| Erro::WriteAtomic(target.clone(), "copy", e.to_string()))?;
+            self.write_replace(&target_temp, &target).await
+        }.await;
+
+        match self.write_discard(&target_temp, result).await {
+            Ok(true) => Ok(()),
+            Ok(false) | Err(Erro::WriteAtomic(_, "copy", _)) => {
+                tracing::debug!("[WRITE SSH] upload local {:?} over remote {:?}", local, target);
+                self.ssh_connect_scp()?.run_local().open_scp()?.upload(local, target.as_ref())
+                    .map_err(|e| Erro::WriteAtomic(target.clone(), "copy", e.to_string()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Uploads to the workspace with scp and copies it to the target with the programs of the user, e.g. with sudo
+    async fn write_ssh_staged(&self, local: &Path, path: &str) -> Resul<()> {
+        let remote_temp = match &self.workspace {
+            Some(dir) => {
+                Self::run_ssh(&self.ssh_client().await?, Self::mkdir(), &["-p", "-m", "1777", "--", dir.as_str()], &self.limits).await?;
+                format!("{}/.upload-{:x}", dir, rand::random::<u64>())
+            }
+            None => format!("/tmp/.boofi-{}", rand::random::<u64>()),
+        };
+        Self::run_ssh(&self.ssh_client().await?, Self::sh(), &["-c", Self::PRIVATE_FILE, "sh", remote_temp.as_str()], &self.limits).await?;
+
+        tracing::trace!("[WRITE SSH] connecting ssh scp");
+        let exec = self.ssh_connect_scp()?.run_local().open_scp()?;
+
+        tracing::debug!("[WRITE SSH] upload local {:?} to remote {:?} and copy to {:?}", local, remote_temp, path);
+        let result = match exec.upload(local, remote_temp.as_ref()) {
+            Ok(_) if self.gnu() => self.write_copy(&remote_temp, path).await,
+            Ok(_) => self.write_in_place(&remote_temp, path).await,
+            Err(e) => Err(e.into()),
+        };
+
+        Self::run_ssh(&self.ssh_client().await?, Self::unlink(), &[remote_temp.as_str()], &self.limits).await?;
+        result
+    }
+
+    /// Removes the temporary file of a write which did not replace the target, the target is untouched
+    async fn write_discard(&self, temp: &str, result: Resul<bool>) -> Resul<bool> {
+        if !matches!(result, Ok(true)) {
+            if let Err(e) = self.run_args(Self::unlink(), &[temp]).await {
+                tracing::trace!("[WRITE] temporary file {} not removed: {}", temp, e);
+            }
+        }
+
+        result
+    }
+
+    /// quote an argument for a posix shell, arguments of safe characters only are kept as they are.
+    /// single quotes prevent any expansion, a single quote itself is closed, escaped and reopened
+    fn quote(argument: &str) -> String {
+        let safe = |c: char| c.is_ascii_alphanumeric() || "_-.,:/@%+=".contains(c);
+
+        if !argument.is_empty() && argument.chars().all(safe) {
+            argument.into()
+        } else {
+            format!("'{}'", argument.replace('\'', r"'\''"))
+        }
+    }
+
+    /// user or group name like `[a-z_][a-z0-9_-]*` or a numeric id, anything else could be read as option or `owner:group` by `chown`
+    fn valid_owner(name: &str) -> bool {
+        let mut chars = name.chars();
+
+        match chars.next() {
+            Some(c) if c.is_ascii_digit() => chars.all(|c| c.is_ascii_digit()),
+            Some(c) if c.is_ascii_lowercase() || c == '_' => chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-'),
+            _ => false,
+        }
+    }
+
+    /// file type of `stat --printf %F`
+    async fn stat_file_type(&self, args: &[&str], path: &str) -> Resul<FileType> {
+        Ok(match String::from_utf8(self.run_args(Self::stat(), args).await?)?.as_str() {
+            "socket" => FileType::Socket,
+            "directory" => FileType::Directory,
+            "regular file" | "regular empty file" => FileType::File,
+            "block special file" => FileType::BlockDevice,
+            "symbolic link" => FileType::SymbolicLink,
+            "character special file" => FileType::CharacterDevice,
+            "fifo" => FileType::NamedPipe,
+            _ => return Err(Erro::FileTypeUnknown(path.to_string()))
+        })
+    }
+
+    /// user programs are switched to with `sudo`, root or the one of `run_as`
+    fn switch_user(&self) -> Option<&str> {
+        self.run_as.as_deref().or(self.sudo.then_some("root"))
+    }
+
+    /// call a program as user and fail if it fails
+    async fn run_user<T: AsRef<str>>(username: &str, password: &str, path: &str, arguments: &[T], switch: Option<&str>, limits: &RunLimits) -> Resul<Vec<u8>> {
+        Self::execute_user(username, password, path, arguments, switch, limits).await?.stdout(Erro::RunUser)
+    }
+
+    /// call a program as user with provided password using `su`.
+    /// with `switch` the program runs via `sudo -S -u` which gets the password as soon as it asks for it.
+    /// The timeout is enforced by `timeout` as the target user, boofi can not signal programs of other users.
+    #[tracing::instrument(name = "run", skip_all, fields(user = username, program = path, switch))]
+    async fn execute_user<T: AsRef<str>>(username: &str, password: &str, path: &str, arguments: &[T], switch: Option<&str>, limits: &RunLimits) -> Resul<Executed> {
+        let sudo = switch.is_some();
+        let mut args = match switch {
+            Some(user) => vec![Self::sudo(), "-S", "-p", Self::SUDO_PROMPT, "-u", user, "--"],
+            None => vec![],
+        };
+
+        let timeout = limits.timeout.map(|t| t.to_string());
+        if let Some(timeout) = timeout.as_deref() {
+            args.extend([Self::timeout(), Self::KILL_AFTER, timeout]);
+        }
+
+        args.push(path);
+
+        for arg in arguments {
+            args.push(arg.as_ref())
+        }
+
+        let mut command = Command::new(Self::su());
+        command.args([
+            username,
+            "-c",
+            &args.iter().map(|s| Self::quote(s)).collect::<Vec<String>>().join(" ")
+        ]);
+
+        tracing::debug!("[RUN USER] execute {} {} -c {}", Self::su(), username, secret::command_line(path, arguments));
+
+        let mut child = command.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let mut stdin = child.stdin.take().ok_or(Erro::RunUserStdin)?;
+        let mut stdout = child.stdout.take().ok_or(Erro::RunUserStdout)?;
+        let mut stderr = child.stderr.take().ok_or(Erro::RunUserStderr)?;
+        let (prompt_sender, prompt_receiver) = oneshot::channel::<()>();
+
+        let pw = password.to_string();
+
+        spawn(async move {
+            tracing::trace!("[RUN USER] pass password to stdin");
+            let result = if sudo {
+                // su reads a single line, the second one is passed after sudo prompted
+                let mut result = stdin.write_all(format!("{}\n", pw).as_bytes()).await;
+
+                if result.is_ok() && prompt_receiver.await.is_ok() {
+                    tracing::trace!("[RUN USER] pass password to sudo");
+                    result = stdin.write_all(format!("{}\n", pw).as_bytes()).await;
+                }
+
+                result
+            } else {
+                stdin.write_all(pw.as_bytes()).await
+            };
+
+            if let Err(e) = result {
+                tracing::error!("[RUN USER] {}", e);
+            }
+        }.in_current_span());
+
+        let max_output = limits.max_output;
+
+        let stdout_reader = spawn(async move {
+            let mut content = vec![];
+            let mut buffer = [0u8; 1024];
+
+            loop {
+                let read = stdout.read(&mut buffer).await?;
+
+                if read == 0 {
+                    break;
+                }
+
+                content.extend_from_slice(&buffer[..read]);
+                RunLimits::check_output(max_output, content.len())?;
+            }
+
+            Ok::<Vec<u8>, Erro>(content)
+        });
+
+        let stderr_reader = spawn(async move {
+            let mut content = vec![];
+            let mut buffer = [0u8; 1024];
+            let mut prompt_sender = Some(prompt_sender);
+
+            loop {
+                let read = stderr.read(&mut buffer).await?;
+
+                if read == 0 {
+                    break;
+                }
+
+                content.extend_from_slice(&buffer[..read]);
+                RunLimits::check_output(max_output, content.len())?;
+
+                if prompt_sender.is_some() && String::from_utf8_lossy(&content).contains(Self::SUDO_PROMPT) {
+                    prompt_sender.take().map(|s| s.send(()));
+                }
+            }
+
+            Ok::<Vec<u8>, Erro>(content)
+        });
+
+        let run = async {
+            // stop reading and kill the program as soon as the output is too large
+            let stdout = stdout_reader.await??;
+            let stderr = stderr_reader.await??;
+            let status = child.wait().await?;
+
+            Ok::<_, Erro>((status, stdout, stderr))
+        };
+
+        // dropping the child kills `su` if `timeout` did not stop the program, or on too large output
+        let started = Instant::now();
+        let (status, stdout, stderr) = if let Some(timeout) = limits.timeout {
+            let wait = Duration::from_secs(timeout) + Self::TIMEOUT_GRACE;
+            tokio::time::timeout(wait, run).await.map_err(|_| Erro::RunTimeout(timeout))??
+        } else {
+            run.await?
+        };
+
+        let err = String::from_utf8(stderr)?.replace(Self::SUDO_PROMPT, "");
+        let code = status.code().unwrap_or(1) as u32;
+
+        // `timeout` exits with 124, or 137 if the program had to be killed
+        if let Some(timeout) = limits.timeout {
+            if matches!(code, 124 | 137) && started.elapsed() >= Duration::from_secs(timeout) {
+                tracing::error!("[RUN USER] stopped after {} seconds", timeout);
+                return Err(Erro::RunTimeout(timeout));
+            }
+        }
+
+        // without su prefix and secrets, the raw output is only used to catch credential errors
+        let output = secret::scrub(if err.to_lowercase().starts_with("password: ") {
+            &err[10..]
+        } else {
+            &err
+        }, &[password]);
+
+        if !status.success() {
+            tracing::error!("[RUN USER] execution failed with code {} and output {}", code, output);
+
+            // catch credential errors
+            if err.trim().to_lowercase().contains("password: su: authentication failure") {
+                return Err(Erro::RunUserPasswordInvalid).map_err(Into::into);
+            }
+
+            if err.starts_with("su: user") && err.contains("does not exist") {
+                return Err(Erro::RunUserUserInvalid).map_err(Into::into);
+            }
+        }
+
+        tracing::debug!("[RUN USER] finished");
+
+        Ok(Executed {
+            code,
+            stdout,
+            stderr: output,
+        })
+    }
+
+    /// execute a command on an already connected ssh client and fail if it fails
+    async fn run_ssh<T: AsRef<str>>(client: &Client, path: &str, arguments: &[T], limits: &RunLimits) -> Resul<Vec<u8>> {
+        Self::execute_ssh(client, path, arguments, limits).await?.stdout(Erro::RunSsh)
+    }
+
+    /// execute a command on an already connected ssh client.
+    /// current implementation does not allow raw byte stream (u8 is just dirty string conversion)
+    /// The timeout is enforced by `timeout` on the remote side, the local one only gives up on an unresponsive connection.
+    /// The client buffers the whole output, with `max_output` the remote side stops the program one byte after the limit.
+    #[tracing::instrument(name = "run", skip_all, fields(program = path))]
+    async fn execute_ssh<T: AsRef<str>>(client: &Client, path: &str, arguments: &[T], limits: &RunLimits) -> Resul<Executed> {
+        let mut args = match limits.max_output {
+            Some(max) => vec![Self::sh().into(), "-c".into(), Self::quote(Self::CAP_OUTPUT), "sh".into(), (max + 1).to_string()],
+            None => vec![],
+        };
+
+        if let Some(timeout) = limits.timeout {
+            args.extend([Self::timeout().into(), Self::KILL_AFTER.into(), timeout.to_string()]);
+        }
+
+        args.push(Self::quote(path));
+
+        for arg in arguments {
+            args.push(Self::quote(arg.as_ref()));
+        }
+
+        let command = args.join(" ");
+
+        tracing::debug!("[RUN SSH] execute {}", secret::command_line(path, arguments));
+
+        let started = Instant::now();
+        let result = if let Some(timeout) = limits.timeout {
+            let wait = Duration::from_secs(timeout) + Self::TIMEOUT_GRACE;
+            tokio::time::timeout(wait, client.execute(&command)).await.map_err(|_| Erro::RunTimeout(timeout))??
+        } else {
+            client.execute(&command).await?
+        };
+
+        // `timeout` exits with 124, or 137 if the program had to be killed
+        if let Some(timeout) = limits.timeout {
+            if matches!(result.exit_status, 124 | 137) && started.elapsed() >= Duration::from_secs(timeout) {
+                tracing::error!("[RUN SSH] stopped after {} seconds", timeout);
+                return Err(Erro::RunTimeout(timeout));
+            }
+        }
+
+        RunLimits::check_output(limits.max_output, result.stdout.len())?;
+        RunLimits::check_output(limits.max_output, result.stderr.len())?;
+
+        let stderr = secret::scrub(&result.stderr, &[]);
+        if result.exit_status > 0 {
+            tracing::error!("[RUN SSH] exit code {} and output: {}", result.exit_status, stderr);
+        } else {
+            tracing::trace!("[RUN SSH] finished with {} bytes output", result.stdout.len());
+        }
+
+        // todo: use byte stream somehow ? (russh)
+        Ok(Executed {
+            code: result.exit_status,
+            stdout: result.stdout.into_bytes(),
+            stderr,
+        })
+    }
+
+    async fn ssh_connect(endpoint: &str, username: &str, password: &str) -> Resul<Client> {
+        tracing::debug!("[SSH CONNECT] connecting to {:?}", endpoint);
+        Client::connect(
+            endpoint,
+            username,
+            AuthMethod::with_password(password),
+            ServerCheckMethod::NoCheck,
+        ).await.map_err(Into::into)
+    }
+
+    /// reuse the open ssh connection or connect if there is none or it was idle for too long
+    async fn ssh_client(&self) -> Resul<Client> {
+        let mut session = self.ssh.lock().await;
+
+        match session.as_mut() {
+            Some(s) if s.last_used.elapsed() < Self::SSH_IDLE_TIMEOUT => {
+                tracing::trace!("[SSH] reusing connection");
+                s.last_used = Instant::now();
+                Ok(s.client.clone())
+            }
+            _ => {
+                let client = Self::ssh_connect(self.endpoint_ok()?, self.credential().username(), self.credential().password()).await?;
+
+                *session = Some(SshSession {
+                    client: client.clone(),
+                    last_used: Instant::now(),
+                });
+
+                Ok(client)
+            }
+        }
+    }
+
+    /// forget the open ssh connection, the next call connects again
+    async fn ssh_reset(&self) {
+        tracing::debug!("[SSH] dropping connection");
+        self.ssh.lock().await.take();
+    }
+
+    /// execute on the reused ssh connection.
+    /// `sudo` can not receive the password without stdin, it has to be allowed without password
+    async fn ssh_execute<T: AsRef<str>>(&self, path: &str, arguments: &[T]) -> Resul<Executed> {
+        let (path, arguments) = match self.switch_user() {
+            Some(user) => {
+                let mut args = vec!["-n", "-u", user, "--", path];
+                args.extend(arguments.iter().map(AsRef::as_ref));
+                (Self::sudo(), args)
+            }
+            None => (path, arguments.iter().map(AsRef::as_ref).collect()),
+        };
+
+        match Self::execute_ssh(&self.ssh_client().await?, path, &arguments, &self.limits).await {
+            // connection is broken e.g. closed by the remote, reconnect once
+            Err(Erro::AsyncSsh(e)) => {
+                tracing::warn!("[RUN SSH] reconnecting after {}", e);
+                self.ssh_reset().await;
+                Self::execute_ssh(&self.ssh_client().await?, path, &arguments, &self.limits).await
+            }
+            result => result,
+        }
+    }
+
+    fn ssh_connect_scp(&self) -> Resul<SessionConnector<TcpStream>> {
+        tracing::debug!("[SSH SCP] connecting to {:?}", self.endpoint);
+
+        let credential = self.credential();
+
+        SessionBuilder::new()
+            .username(credential.username())
+            .password(credential.password())
+            .connect(self.endpoint_ok()?)
+            .map_err(Into::into)
+    }
+
+    /// decode the base64 output of a read, line breaks differ between implementations
+    fn decode_base64(encoded: Vec<u8>) -> Resul<Vec<u8>> {
+        let encoded = String::from_utf8(encoded)?;
+        STANDARD.decode(encoded.split_whitespace().collect::<String>()).map_err(Into::into)
+    }
+
+    /// option to result
+    fn endpoint_ok(&self) -> Resul<&str> {
+        self.endpoint.as_deref().ok_or(Erro::EndpointMissing)
+    }
+}
+
+#[async_trait]
+impl PlatformActions for Posix {
+    fn name() -> &'static str {
+        "posix"
+    }
+
+    async fn detect(credential: Credential, endpoint: Option<&str>) -> Resul<Option<Self>> {
+        // locations are shared by linux, macos and freebsd, `chown` differs and is left out
+        let executables = &[
+            Self::unlink(),
+            Self::cp(),
+            Self::cat(),
+            Self::chmod(),
+            Self::mv(),
+            Self::base64(),
+            Self::test(),
+            Self::mkdir(),
+            Self::rmdir(),
+            Self::rm(),
+        ];
+
+        let session = if let Some(e) = endpoint {
+            let client = Self::ssh_connect(e, credential.username(), credential.password()).await?;
+            Self::run_ssh(&client, Self::stat(), executables, &RunLimits::default()).await?;
+
+            Some(SshSession {
+                client,
+                last_used: Instant::now(),
+            })
+        } else {
+            let mut local = vec![Self::su()];
+            local.extend(executables);
+
+            Self::run_user(credential.username(), credential.password(), Self::stat(), &local, None, &RunLimits::default()).await?;
+            None
+        };
+
+        tracing::info!("{} compatibility check successful", Self::name());
+        Ok(Some(Self {
+            credential,
+            endpoint: endpoint.map(ToString::to_string),
+            ssh: Arc::new(Mutex::new(session)),
+            sudo: false,
+            run_as: None,
+            limits: Default::default(),
+            workspace: None,
+            os: None,
+        }))
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    fn credential(&self) -> &Credential {
+        &self.credential
+    }
+
+    async fn verify_credential(&self) -> Resul<()> {
+        self.run(Self::r#true()).await.map(|_| ())
+    }
+
+    async fn run_user<T: AsRef<str> + Send + Sync>(&self, path: &str, arguments: &[T]) -> Resul<Vec<u8>> {
+        Self::run_user(self.credential().username(), self.credential().password(), path, arguments, self.switch_user(), &self.limits).await
+    }
+
+    async fn run_ssh<T: AsRef<str> + Send + Sync>(&self, path: &str, arguments: &[T]) -> Resul<Vec<u8>> {
+        self.ssh_execute(path, arguments).await?.stdout(Erro::RunSsh)
+    }
+
+    async fn run_output<T: AsRef<str> + Send + Sync>(&self, path: &str, arguments: &[T]) -> Resul<RunOutput> {
+        let start = Instant::now();
+
+        let executed = if self.endpoint.is_some() {
+            self.ssh_execute(path, arguments).await?
+        } else {
+            Self::execute_user(self.credential().username(), self.credential().password(), path, arguments, self.switch_user(), &self.limits).await?
+        };
+
+        Ok(RunOutput::new(String::from_utf8(executed.stdout)?, executed.stderr, executed.code, start.elapsed()))
+    }
+
+    fn set_sudo(&mut self, sudo: bool) -> Resul<()> {
+        self.sudo = sudo;
+        Ok(())
+    }
+
+    fn set_run_as(&mut self, user: Option<String>) -> Resul<()> {
+        self.run_as = user;
+        Ok(())
+    }
+
+    fn set_os(&mut self, os: &Os) {
+        self.os = Some(os.clone());
+    }
+
+    fn limits(&self) -> RunLimits {
+        self.limits.clone()
+    }
+
+    fn set_limits(&mut self, limits: RunLimits) -> Resul<()> {
+        self.limits = limits;
+        Ok(())
+    }
+
+    async fn read_user(&self, path: &str) -> Resul<Vec<u8>> {
+        self.run_user(Self::cat(), &[path]).await
+    }
+
+    /// ssh output is transferred as string, encode it to stay binary safe
+    async fn read_ssh(&self, path: &str) -> Resul<Vec<u8>> {
+        tracing::debug!("[READ SSH] reading {}", path);
+        // `base64` of macOS and FreeBSD takes the file with different options, stdin works everywhere
+        Self::decode_base64(self.run_args(Self::sh(), &["-c", r#"exec < "$1" || exit 1; base64"#, "sh", path]).await?)
+    }
+
+    /// `tail` skips and `head` limits, the part is base64 encoded like `read_ssh`.
+    /// The file is opened by the shell first, a missing file fails instead of returning nothing.
+    async fn read_range(&self, path: &str, offset: usize, length: Option<usize>) -> Resul<Vec<u8>> {
+        tracing::debug!("[READ RANGE] reading {} from {} with length {:?}", path, offset, length);
+
+        let script = match length {
+            Some(_) => r#"exec < "$2" || exit 1; tail -c "+$1" | head -c "$3" | base64"#,
+            None => r#"exec < "$2" || exit 1; tail -c "+$1" | base64"#,
+        };
+
+        let start = (offset + 1).to_string();
+        let mut args = vec!["-c", script, "sh", start.as_str(), path];
+
+        let length = length.map(|l| l.to_string());
+        if let Some(length) = length.as_deref() {
+            args.push(length);
+        }
+
+        Self::decode_base64(self.run_args(Self::sh(), &args).await?)
+    }
+
+    async fn tail(&self, path: &str, lines: usize) -> Resul<Vec<u8>> {
+        tracing::debug!("[TAIL] reading last {} lines of {}", lines, path);
+        self.run_args(Self::tail(), &["-n", lines.to_string().as_str(), "--", path]).await
+    }
+
+    /// use temporary file, `cp` and `chmod` to create a temporary file next to the target which replaces it atomically
+    async fn write_user(&self, path: &str, content: &[u8]) -> Resul<()> {
+        let mut temp = tempfile::NamedTempFile::new()?;
+
+        tracing::debug!("[WRITE USER] writing bytes to {:?}", temp.path());
+        temp.write_all(content)?;
+
+        let tmp_path_str = temp.path().to_str().ok_or(Erro::WriteUserTempPath)?;
+
+        Command::new(Self::chmod()).args(["444", tmp_path_str]).output().await?;
+
+        let result = if self.gnu() {
+            self.write_copy(tmp_path_str, path).await
+        } else {
+            self.write_in_place(tmp_path_str, path).await
+        };
+
+        temp.close()?;
+        result
+    }
+
+    /// use temporary file and scp to upload a temporary file next to the target which replaces it atomically
+    async fn write_ssh(&self, path: &str, content: &[u8]) -> Resul<()> {
+        let mut temp = tempfile::NamedTempFile::new()?;
+        tracing::debug!("[WRITE SSH] writing bytes to {:?}", temp.path());
+        temp.write_all(content)?;
+
+        let result = if self.switch_user().is_none() && self.gnu() {
+            self.write_ssh_direct(temp.path(), path).await
+        } else {
+            // scp can not switch users and without GNU coreutils the target is written in place
+            self.write_ssh_staged(temp.path(), path).await
+        };
+
+        temp.close()?;
+        result
+    }
+
+    async fn delete_user(&self, path: &str) -> Resul<()> {
+        self.run_user(Self::unlink(), &[path]).await.map(|_| {})
+    }
+
+    async fn delete_ssh(&self, path: &str) -> Resul<()> {
+        self.run_ssh(Self::unlink(), &[path]).await.map(|_| {})
+    }
+
+    async fn detect_os(&self) -> Resul<Os> {
+        let linux = match self.read_to_string("/proc/version").await {
+            Ok(s) => Version::parse(&s)?.version().contains("Linux"),
+            Err(_) => false,
+        };
+
+        if linux {
+            tracing::debug!("[DETECT] Linux detected");
+
+            let os: Os = if let Ok(s) = self.read_to_string("/etc/os-release").await {
+                let release = OsRelease::try_from(s)?;
+
+                match release.id() {
+                    "ubuntu" | "debian" => release.version_codename().unwrap_or(release.id()).parse()?,
+                    _ => release.id().parse()?
+                }
+            } else {
+                Os::LinuxUnknown
+            };
+
+            tracing::debug!("[DETECT] {:?} detected", os);
+
+            Ok(os)
+        } else if self.run_args(Self::sw_vers(), &["-productName"]).await.is_ok() {
+            tracing::debug!("[DETECT] macOS detected");
+            Ok(Os::Macos)
+        } else if String::from_utf8(self.run_args(Self::uname(), &["-s"]).await?)?.trim() == "FreeBSD" {
+            tracing::debug!("[DETECT] FreeBSD detected");
+            Ok(Os::FreeBsd)
+        } else {
+            Err(Erro::OsDetectionFailed)
+        }
+    }
+
+    async fn file_type(&self, path: &str) -> Resul<FileType> {
+        self.stat_file_type(&["--printf", "%F", "--", path], path).await
+    }
+
+    async fn target_file_type(&self, path: &str) -> Resul<FileType> {
+        self.stat_file_type(&["--dereference", "--printf", "%F", "--", path], path).await
+    }
+
+    async fn exist(&self, path: &str) -> Resul<bool> {
+        let result = self.run_args(Self::test(), &["-e", path]).await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(Erro::RunUser(code, _)) |
+            Err(Erro::RunSsh(code, _)) if code == 1 => Ok(false),
+            Err(e) => Err(e)
+        }
+    }
+
+    async fn metadata_list(&self, paths: &[&str]) -> Resul<Vec<Metadata>> {
+        let mut args = vec!["--printf", Metadata::STAT_FORMAT, "--"];
+        args.extend_from_slice(paths);
+        Metadata::parse(&String::from_utf8(self.run_args(Self::stat(), &args).await?)?)
+    }
+
+    async fn dir_list(&self, path: &str) -> Resul<Vec<DirEntry>> {
+        // -H follows the path itself if it is a symbolic link
+        let args = ["-H", path, "-mindepth", "1", "-maxdepth", "1", "-printf", DirEntry::FIND_FORMAT];
+        DirEntry::parse(&String::from_utf8(self.run_args(Self::find(), &args).await?)?)
+    }
+
+    async fn chmod(&self, path: &str, mode: &str) -> Resul<()> {
+        if !(3..=4).contains(&mode.len()) || !mode.chars().all(|c| c.is_digit(8)) {
+            return Err(Erro::FileModeInvalid(mode.into()));
+        }
+
+        tracing::debug!("[CHMOD] {} {}", mode, path);
+        self.run_args(Self::chmod(), &[mode, "--", path]).await.map(|_| {})
+    }
+
+    async fn chown(&self, path: &str, owner: Option<&str>, group: Option<&str>) -> Resul<()> {
+        if let Some(name) = owner.into_iter().chain(group).find(|name| !Self::valid_owner(name)) {
+            return Err(Erro::FileOwnerInvalid(name.into()));
+        }
+
+        let owner_group = match (owner, group) {
+            (Some(owner), Some(group)) => format!("{}:{}", owner, group),
+            (Some(owner), None) => owner.to_string(),
+            (None, Some(group)) => format!(":{}", group),
+            (None, None) => return Err(Erro::FileAttributesMissing),
+        };
+
+        tracing::debug!("[CHOWN] {} {}", owner_group, path);
+        self.run_args(Self::chown(), &[owner_group.as_str(), "--", path]).await.map(|_| {})
+    }
+
+    async fn copy(&self, source: &str, target: &str) -> Resul<()> {
+        tracing::debug!("[COPY] {} to {}", source, target);
+        self.run_args(Self::cp(), &["-r", "--", source, target]).await.map(|_| {})
+    }
+
+    async fn hash(&self, path: &str) -> Resul<String> {
+        self.checksum(path, HashAlgorithm::Sha256).await
+    }
+
+    async fn checksum(&self, path: &str, algorithm: HashAlgorithm) -> Resul<String> {
+        let (program, options): (&str, &[&str]) = match (algorithm, self.os.as_ref()) {
+            (HashAlgorithm::Sha256, Some(Os::Macos)) => (Self::shasum(), &["-a", "256"]),
+            (HashAlgorithm::Sha256, Some(Os::FreeBsd)) => (Self::sha256(), &["-q"]),
+            (HashAlgorithm::Md5, Some(Os::Macos | Os::FreeBsd)) => (Self::md5(), &["-q"]),
+            (HashAlgorithm::Sha256, _) => (Self::sha256sum(), &[]),
+            (HashAlgorithm::Md5, _) => (Self::md5sum(), &[]),
+        };
+        let mut arguments = options.to_vec();
+        arguments.extend(["--", path]);
+        let output = String::from_utf8(self.run_args(program, &arguments).await?)?;
+
+        // file names with special characters are escaped and the line starts with a backslash
+        output.trim_start_matches('\\')
+            .split_whitespace()
+            .next()
+            .map(ToString::to_string)
+            .ok_or(Erro::HashInvalid(output.clone()))
+    }
+
+    async fn rename(&self, source: &str, target: &str) -> Resul<()> {
+        tracing::debug!("[RENAME] {} to {}", source, target);
+        self.run_args(Self::mv(), &["--", source, target]).await.map(|_| {})
+    }
+
+    async fn create_dir(&self, path: &str) -> Resul<()> {
+        tracing::debug!("[CREATE DIR] creating {}", path);
+        self.run_args(Self::mkdir(), &["--", path]).await.map(|_| {})
+    }
+
+    async fn create_dir_all(&self, path: &str) -> Resul<()> {
+        tracing::debug!("[CREATE DIR] creating {} with parents", path);
+        self.run_args(Self::mkdir(), &["-p", "--", path]).await.map(|_| {})
+    }
+
+    async fn remove_dir(&self, path: &str) -> Resul<()> {
+        tracing::debug!("[REMOVE DIR] removing {}", path);
+        self.run_args(Self::rmdir(), &["--", path]).await.map(|_| {})
+    }
+
+    async fn remove_dir_all(&self, path: &str) -> Resul<()> {
+        tracing::debug!("[REMOVE DIR ALL] removing {} recursively", path);
+        self.run_args(Self::rm(), &["-r", "--", path]).await.map(|_| {})
+    }
+
+    fn set_workspace(&mut self, dir: Option<String>) -> Resul<()> {
+        self.workspace = dir;
+        Ok(())
+    }
+
+    /// sticky like `/tmp`, users only remove their own entries
+    async fn create_workspace(&self, dir: &str) -> Resul<()> {
+        self.run_args(Self::mkdir(), &["-p", "-m", "1777", "--", dir]).await.map(|_| ())
+    }
+
+    async fn create_temp(&self, dir: &str, directory: bool) -> Resul<String> {
+        self.create_workspace(dir).await?;
+
+        let template = format!("{}/{}.XXXXXXXX", dir.trim_end_matches('/'), if directory { "dir" } else { "file" });
+        let mut args = vec![];
+        if directory {
+            args.push("-d");
+        }
+        args.push(template.as_str());
+
+        let path = String::from_utf8(self.run_args(Self::mktemp(), &args).await?)?;
+        Ok(path.trim_end_matches('\n').to_string())
+    }
+
+    /// `dd` without input truncates or extends the file to the offset, the chunk is uploaded next to it and appended
+    async fn write_chunk(&self, path: &str, offset: usize, content: &[u8]) -> Resul<()> {
+        let of = format!("of={}", path);
+        let seek = format!("seek={}", offset);
+        self.run_args(Self::dd(), &["if=/dev/null", of.as_str(), "bs=1", seek.as_str()]).await?;
+
+        let chunk = format!("{}.chunk-{:x}", path, rand::random::<u64>());
+        self.write(&chunk, content).await?;
+
+        let result = self.run_args(Self::sh(), &["-c", "cat -- \"$1\" >> \"$2\"", "sh", chunk.as_str(), path]).await;
+        self.run_args(Self::unlink(), &[chunk.as_str()]).await?;
+        result.map(|_| ())
+    }
+
+    async fn replace(&self, source: &str, target: &str) -> Resul<()> {
+        let target = self.write_target(target).await?;
+        let target_temp = Self::write_temp_path(&target);
+
+        tracing::debug!("[REPLACE] copy from {:?} to {:?}", source, target_temp);
+        let result: Resul<bool> = async {
+            self.write_step(&target, "copy", Self::cp(), &["--", source, target_temp.as_str()]).await?;
+            self.write_replace(&target_temp, &target).await
+        }.await;
+
+        match self.write_discard(&target_temp, result).await {
+            Ok(true) => Ok(()),
+            Ok(false) | Err(Erro::WriteAtomic(_, "copy", _)) => self.write_in_place(source, &target).await,
+            Err(e) => Err(e),
+        }
+    }
+
+    /// `find -mmin` counts whole minutes, items of other users are skipped as the sticky directory protects them anyway
+    async fn remove_stale(&self, dir: &str, max_age: Duration) -> Resul<()> {
+        let minutes = format!("+{}", max_age.as_secs() / 60);
+        tracing::debug!("[REMOVE STALE] removing entries of {} older than {} minutes", dir, minutes);
+
+        self.run_args(Self::find(), &[
+            dir, "-mindepth", "1", "-maxdepth", "1",
+            "-user", self.credential().username(),
+            "-mmin", minutes.as_str(),
+            "-exec", Self::rm(), "-rf", "--", "{}", "+",
+        ]).await.map(|_| {})
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::system::posix::Posix;
+
+    #[test]
+    fn test_write_temp_path() {
+        let temp = Posix::write_temp_path("/etc/passwd");
+        assert!(temp.starts_with("/etc/.passwd.boofi-"));
+        assert_ne!(temp, Posix::write_temp_path("/etc/passwd"));
+        assert!(Posix::write_temp_path("passwd").starts_with("./.passwd.boofi-"));
+    }
+
+    #[test]
+    fn test_valid_owner() {
+        assert!(Posix::valid_owner("root"));
+        assert!(Posix::valid_owner("_apt"));
+        assert!(Posix::valid_owner("systemd-network"));
+        assert!(Posix::valid_owner("1000"));
+        assert!(!Posix::valid_owner(""));
+        assert!(!Posix::valid_owner("-R"));
+        assert!(!Posix::valid_owner("root:root"));
+        assert!(!Posix::valid_owner("1000a"));
+        assert!(!Posix::valid_owner("Admin"));
+    }
+
+    #[test]
+    fn test_decode_base64() {
+        assert_eq!(Posix::decode_base64(b"AAEK/4AiDUF/\n".to_vec()).unwrap(), vec![0, 1, 10, 255, 128, 34, 13, 65, 127]);
+        assert_eq!(Posix::decode_base64(b"AAEK\n/4Ai\n".to_vec()).unwrap(), vec![0, 1, 10, 255, 128, 34]);
+        assert!(Posix::decode_base64(b"AA*K\n".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_private_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("upload");
+        let create = || std::process::Command::new("/bin/sh").args(["-c", Posix::PRIVATE_FILE, "sh", path.to_str().unwrap()]).output().unwrap().status;
+
+        assert!(create().success());
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(!create().success());
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(Posix::quote("/etc/hosts"), "/etc/hosts");
+        assert_eq!(Posix::quote(""), "''");
+        assert_eq!(Posix::quote("a b"), "'a b'");
+        assert_eq!(Posix::quote("it's"), r"'it'\''s'");
+
+        // the shell passes hostile arguments unchanged instead of expanding or executing them
+        let hostile = ["$(touch /tmp/boofi-injected)", "`id`", "\"; id; \"", "it's", "a\nb", "$HOME", "*", "\\", "-n", ""];
+        let command = format!("printf '%s\\0' {}", hostile.iter().map(|a| Posix::quote(a)).collect::<Vec<String>>().join(" "));
+        let output = std::process::Command::new("/bin/sh").args(["-c", &command]).output().unwrap();
+
+        let arguments = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(arguments.split_terminator('\0').collect::<Vec<&str>>(), hostile);
+        assert!(!std::path::Path::new("/tmp/boofi-injected").exists());
+    }
+}
@@ -0,0 +1,438 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+use serde_json::{to_value, Value};
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+use crate::apps::{AppBuilders, Progress, ProgressSender};
+use crate::apps::prelude::Deserialize;
+use crate::error::{Erro, Resul};
+use crate::system::System;
+use crate::webhook::Webhook;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Created,
+    Running,
+    Finished,
+    Failed,
+}
+
+/// Represents a task with id, in/output, app name and status
+#[derive(Serialize, Deserialize)]
+pub struct Task {
+    id: usize,
+    app_name: String,
+    status: TaskStatus,
+    app_input: Value,
+    #[serde(skip)]
+    app: Option<AppBuilders>,
+    app_output: Option<Value>,
+    app_error: Option<String>,
+    progress: Option<Progress>,
+    #[serde(skip)]
+    finished_at: Option<SystemTime>,
+}
+
+impl Task {
+    pub fn id(&self) -> usize { self.id }
+
+    /// Finished or failed tasks can be pruned
+    fn done(&self) -> bool {
+        matches!(self.status, TaskStatus::Finished | TaskStatus::Failed)
+    }
+}
+
+/// Limits how many finished tasks are kept in memory
+/// max_count:  maximum number of tasks, oldest finished tasks are removed first
+/// max_age:    seconds a finished task is kept
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TaskRetention {
+    pub max_count: Option<usize>,
+    pub max_age: Option<u64>,
+}
+
+/// Filter and pagination of the task list
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskFilter {
+    status: Option<TaskStatus>,
+    app_name: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+impl TaskFilter {
+    fn r#match(&self, task: &Task) -> bool {
+        self.status.as_ref().map(|status| status == &task.status).unwrap_or(true) &&
+            self.app_name.as_ref().map(|name| name == &task.app_name).unwrap_or(true)
+    }
+}
+
+/// Manages all tasks
+/// All tasks (apps) running asynchronous
+pub struct TaskController {
+    tasks: Arc::<Mutex::<Vec<Task>>>,
+    last_id: usize,
+    retention: TaskRetention,
+    webhook: Option<Webhook>,
+}
+
+impl Default for TaskController {
+    fn default() -> Self {
+        Self::new(TaskRetention::default(), None)
+    }
+}
+
+impl TaskController {
+    pub fn new(retention: TaskRetention, webhook: Option<Webhook>) -> Self {
+        Self {
+            tasks: Arc::new(Mutex::new(vec![])),
+            last_id: 0,
+            retention,
+            webhook,
+        }
+    }
+
+    pub fn set_retention(&mut self, retention: TaskRetention) {
+        self.retention = retention;
+    }
+
+    pub fn set_webhook(&mut self, webhook: Option<Webhook>) {
+        self.webhook = webhook;
+    }
+
+    /// Removes finished tasks exceeding the retention
+    /// Created or running tasks are never removed
+    pub async fn prune(&self) {
+        let mut tasks = self.tasks.lock().await;
+        let len = tasks.len();
+
+        if let Some(max_age) = self.retention.max_age.map(Duration::from_secs) {
+            let now = SystemTime::now();
+            tasks.retain(|task| {
+                !task.done() || task.finished_at
+                    .and_then(|finished| now.duration_since(finished).ok())
+                    .map(|age| age <= max_age)
+                    .unwrap_or(true)
+            });
+        }
+
+        if let Some(max_count) = self.retention.max_count {
+            let mut exceeding = tasks.len().saturating_sub(max_count);
+            tasks.retain(|task| {
+                if exceeding > 0 && task.done() {
+                    exceeding -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+
+        if len != tasks.len() {
+            tracing::debug!("[TASK] {} tasks pruned", len - tasks.len());
+        }
+    }
+
+    /// Generate a new task and starts the app asynchronously
+    /// In and output is stored in json format
+    /// The finished or failed task is posted to the callback url or the configured webhook
+    pub async fn new_task(&mut self, mut app: AppBuilders, value: Value, system: System, callback_url: Option<String>) -> Resul<Value> {
+        let name = app.name().to_string();
+
+        self.spawn(&name, value.clone(), callback_url, move |sender| async move {
+            let result = app.run_with_progress(value, &system, &sender).await
+                .and_then(|output| to_value(output).map_err(Into::into));
+            (result, Some(app))
+        }).await
+    }
+
+    /// Generate a new task of work which isn't an app, e.g. a directory listing, its output is the result
+    pub async fn new_task_future<F>(&mut self, name: &str, input: Value, future: F, callback_url: Option<String>) -> Resul<Value>
+        where F: Future<Output = Resul<Value>> + Send + 'static
+    {
+        self.spawn(name, input, callback_url, move |_| async move { (future.await, None) }).await
+    }
+
+    async fn spawn<R, F>(&mut self, name: &str, input: Value, callback_url: Option<String>, run: R) -> Resul<Value>
+        where R: FnOnce(ProgressSender) -> F + Send + 'static,
+              F: Future<Output = (Resul<Value>, Option<AppBuilders>)> + Send
+    {
+        tracing::trace!("[TASK] creating new task with app {}", name);
+
+        self.prune().await;
+        let mut tasks = self.tasks.lock().await;
+        let id = self.last_id + 1;
+
+        let task = Task {
+            id,
+            app_name: name.into(),
+            app_input: input,
+            app: None,
+            app_output: None,
+            status: TaskStatus::Created,
+            app_error: None,
+            progress: None,
+            finished_at: None,
+        };
+
+        let task_value = to_value(&task)?;
+        tasks.push(task);
+
+        tracing::debug!("[TASK] new task {} created", id);
+
+        self.last_id = id;
+
+        let tasks = self.tasks.clone();
+        let webhook = match callback_url {
+            Some(url) => Some(Webhook::new(url, self.webhook.as_ref().and_then(|w| w.retries))),
+            None => self.webhook.clone(),
+        };
+
+        let j: JoinHandle<Resul<()>> = tokio::spawn(async move {
+            tracing::trace!("[TASK] task {} spawned", id);
+
+            tasks.lock().await.iter_mut().find(|t| t.id == id).ok_or(Erro::TaskInvalidIndex)?.status = TaskStatus::Running;
+            tracing::debug!("[TASK] task {} running", id);
+
+            let (sender, mut receiver) = watch::channel(Progress::default());
+            let progress_tasks = tasks.clone();
+            let progress: JoinHandle<()> = tokio::spawn(async move {
+                while receiver.changed().await.is_ok() {
+                    let latest = receiver.borrow_and_update().clone();
+                    tracing::trace!("[TASK] task {} progress {:?}", id, latest);
+
+                    if let Some(task) = progress_tasks.lock().await.iter_mut().find(|t| t.id == id) {
+                        task.progress = Some(latest);
+                    }
+                }
+            }.in_current_span());
+
+            // the last progress is stored before the result, the sender is dropped with the run
+            let (result, app) = run(sender).await;
+            tracing::debug!("[TASK] task {} run done", id);
+            progress.await?;
+
+            let mut tasks_unlocked = tasks.lock().await;
+            let mut task = tasks_unlocked.iter_mut().find(|t| t.id == id).ok_or(Erro::TaskInvalidIndex)?;
+
+            match result {
+                Ok(result) => {
+                    tracing::info!("[TASK] task {} run successfully", id);
+                    task.app_output = Some(result);
+                    task.status = TaskStatus::Finished;
+                    task.progress.get_or_insert_with(Progress::default).percent = Some(100);
+                }
+                Err(error) => {
+                    tracing::error!("[TASK] task {} failed", id);
+                    task.app_error = Some(format!("{:?}", error));
+                    task.status = TaskStatus::Failed;
+                }
+            };
+
+            task.app = app;
+            task.finished_at = Some(SystemTime::now());
+
+            if let Some(webhook) = webhook {
+                let task_value = to_value(&*task)?;
+                drop(tasks_unlocked);
+                webhook.notify(&task_value).await;
+            }
+
+            Ok(())
+        }.instrument(tracing::info_span!("task", id)));
+
+        drop(j);
+
+        Ok(task_value)
+    }
+
+    /// Returns all tasks in a mutex context
+    pub fn tasks(&self) -> Arc<Mutex<Vec<Task>>> {
+        self.tasks.clone()
+    }
+
+    /// Filtered and paginated tasks in json format
+    pub async fn list(&self, filter: &TaskFilter) -> Resul<Vec<Value>> {
+        self.prune().await;
+
+        self.tasks.lock().await.iter()
+            .filter(|task| filter.r#match(task))
+            .skip(filter.offset.unwrap_or(0))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .map(|task| to_value(task).map_err(Into::into))
+            .collect()
+    }
+
+    /// Filtered and paginated tasks like `list`, only their ids are collected and each task is serialized when polled.
+    /// Tasks pruned in the meantime are left out.
+    pub async fn stream(&self, filter: &TaskFilter) -> impl Stream<Item = Resul<Value>> {
+        self.prune().await;
+
+        let ids: Vec<usize> = self.tasks.lock().await.iter()
+            .filter(|task| filter.r#match(task))
+            .skip(filter.offset.unwrap_or(0))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .map(|task| task.id)
+            .collect();
+
+        let tasks = self.tasks.clone();
+        futures_util::stream::iter(ids).filter_map(move |id| {
+            let tasks = tasks.clone();
+            async move {
+                let tasks = tasks.lock().await;
+                // ids are increasing in the order of the tasks
+                let i = tasks.binary_search_by_key(&id, |task| task.id).ok()?;
+                Some(to_value(&tasks[i]).map_err(Into::into))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+    use futures_util::StreamExt;
+    use serde_json::{from_value, json, Value};
+    use crate::apps::ls::LsBuilder;
+    use crate::error::Erro;
+    use crate::apps::AppBuilders;
+    use crate::task::{Task, TaskController, TaskFilter, TaskRetention, TaskStatus};
+    use crate::utils::test::system_user;
+
+    async fn ids(tk: &TaskController) -> Vec<usize> {
+        tk.tasks().lock().await.iter().map(Task::id).collect()
+    }
+
+    #[tokio::test]
+    async fn prune() {
+        let tk = TaskController::new(TaskRetention {
+            max_count: Some(2),
+            max_age: Some(60),
+        }, None);
+
+        let now = SystemTime::now();
+        tk.tasks().lock().await.extend([
+            Task { id: 1, app_name: "ls".into(), status: TaskStatus::Finished, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: Some(now - Duration::from_secs(120)) },
+            Task { id: 2, app_name: "ls".into(), status: TaskStatus::Running, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: None },
+            Task { id: 3, app_name: "sh".into(), status: TaskStatus::Failed, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: Some(now - Duration::from_secs(10)) },
+            Task { id: 4, app_name: "sh".into(), status: TaskStatus::Finished, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: Some(now - Duration::from_secs(5)) },
+        ]);
+
+        tk.prune().await;
+
+        // 1 is too old, 3 is the oldest finished one exceeding the count and 2 is still running
+        assert_eq!(ids(&tk).await, vec![2, 4]);
+    }
+
+    #[tokio::test]
+    async fn list() {
+        let tk = TaskController::default();
+
+        let now = SystemTime::now();
+        tk.tasks().lock().await.extend([
+            Task { id: 1, app_name: "ls".into(), status: TaskStatus::Finished, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: Some(now - Duration::from_secs(1)) },
+            Task { id: 2, app_name: "sh".into(), status: TaskStatus::Running, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: None },
+            Task { id: 3, app_name: "ls".into(), status: TaskStatus::Running, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: None },
+            Task { id: 4, app_name: "ls".into(), status: TaskStatus::Running, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: None },
+        ]);
+
+        let filter: TaskFilter = from_value(json!({"status": "running", "app_name": "ls"})).unwrap();
+        let tasks: Vec<Task> = from_value(tk.list(&filter).await.unwrap().into()).unwrap();
+        assert_eq!(tasks.iter().map(Task::id).collect::<Vec<usize>>(), vec![3, 4]);
+
+        let filter: TaskFilter = from_value(json!({"limit": 2, "offset": 1})).unwrap();
+        let tasks: Vec<Task> = from_value(tk.list(&filter).await.unwrap().into()).unwrap();
+        assert_eq!(tasks.iter().map(Task::id).collect::<Vec<usize>>(), vec![2, 3]);
+
+        // no retention configured
+        assert_eq!(ids(&tk).await, vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn stream() {
+        let tk = TaskController::default();
+
+        let now = SystemTime::now();
+        tk.tasks().lock().await.extend([
+            Task { id: 1, app_name: "ls".into(), status: TaskStatus::Finished, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: Some(now - Duration::from_secs(1)) },
+            Task { id: 2, app_name: "sh".into(), status: TaskStatus::Running, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: None },
+            Task { id: 3, app_name: "ls".into(), status: TaskStatus::Running, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: None },
+            Task { id: 4, app_name: "ls".into(), status: TaskStatus::Running, app_input: json!({}), app: None, app_output: None, app_error: None, progress: None, finished_at: None },
+        ]);
+
+        let filter: TaskFilter = from_value(json!({"app_name": "ls", "limit": 2})).unwrap();
+        let stream = tk.stream(&filter).await;
+        // removed after the stream was created
+        tk.tasks().lock().await.retain(|task| task.id != 3);
+
+        let tasks: Vec<Value> = stream.map(Result::unwrap).collect().await;
+        assert_eq!(tasks.iter().map(|task| task["id"].clone()).collect::<Vec<Value>>(), vec![json!(1)]);
+    }
+
+    #[tokio::test]
+    async fn new_task() {
+        let mut tk = TaskController::default();
+
+        let app_builder = AppBuilders::LsBuilder(LsBuilder::default());
+        let app = app_builder;
+        let input = json!({"path": "/"});
+        let result = tk.new_task(app.clone(), input.clone(), system_user().await, None).await.unwrap();
+
+        let t1: Task = from_value(result).unwrap();
+
+        assert_eq!(t1.status, TaskStatus::Created);
+        assert_eq!(t1.id, 1);
+        assert_eq!(t1.app_input, input);
+        assert_eq!(t1.app_name, app.name().to_string());
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+
+        let t = tk.tasks();
+        let tasks = t.lock().await;
+        assert_eq!(tasks[0].status, TaskStatus::Finished);
+        assert!(tasks[0].app_output.as_ref().unwrap().is_array());
+        assert_eq!(tasks[0].progress.as_ref().unwrap().percent, Some(100));
+    }
+
+    #[tokio::test]
+    async fn new_task_failed() {
+        let mut tk = TaskController::default();
+
+        let app_builder = AppBuilders::LsBuilder(LsBuilder::default());
+        let app = app_builder;
+        let input = json!({"invalid": "/"});
+        tk.new_task(app, input.clone(), system_user().await, None).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+
+        let t = tk.tasks();
+        let tasks = t.lock().await;
+
+        assert_eq!(tasks[0].status, TaskStatus::Failed);
+        dbg!(&tasks[0].app_error);
+        assert!(tasks[0].app_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn new_task_future() {
+        let mut tk = TaskController::default();
+
+        let result = tk.new_task_future("dir_list", json!({"path": "/tmp"}), async { Ok(json!([])) }, None).await.unwrap();
+        let t1: Task = from_value(result).unwrap();
+        assert_eq!(t1.app_name, "dir_list");
+        assert_eq!(t1.status, TaskStatus::Created);
+
+        tk.new_task_future("dir_list", json!({"path": "/none"}), async { Err(Erro::PathNotFound("/none".into())) }, None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let t = tk.tasks();
+        let tasks = t.lock().await;
+        assert_eq!(tasks[0].status, TaskStatus::Finished);
+        assert_eq!(tasks[0].app_output, Some(json!([])));
+        assert_eq!(tasks[1].status, TaskStatus::Failed);
+    }
+}
@@ -0,0 +1,86 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+use thiserror::Error;
+use crate::error::Resul;
+
+lazy_static! {
+    static ref VARIABLE: Regex = Regex::new(r"\{\{\s*([A-Za-z0-9_]+)\s*\}\}").unwrap();
+}
+
+/// Variables of a request replacing `{{name}}` in app inputs
+pub type Variables = Map<String, Value>;
+
+/// Replaces `{{name}}` in all strings of the input, object keys are kept.
+/// A string which is only a variable becomes its value with type, e.g. a number or list, otherwise the value is inserted as text.
+pub fn render(input: Value, variables: &Variables) -> Resul<Value> {
+    Ok(match input {
+        Value::String(s) => render_string(s, variables)?,
+        Value::Array(values) => Value::Array(values.into_iter()
+            .map(|v| render(v, variables))
+            .collect::<Resul<Vec<Value>>>()?),
+        Value::Object(map) => Value::Object(map.into_iter()
+            .map(|(k, v)| render(v, variables).map(|v| (k, v)))
+            .collect::<Resul<Map<String, Value>>>()?),
+        value => value,
+    })
+}
+
+fn variable<'a>(name: &str, variables: &'a Variables) -> Result<&'a Value, TemplateError> {
+    variables.get(name).ok_or_else(|| TemplateError::VariableUnknown(name.into()))
+}
+
+fn render_string(s: String, variables: &Variables) -> Resul<Value> {
+    if let Some(captures) = VARIABLE.captures(&s).filter(|c| c[0].len() == s.len()) {
+        return Ok(variable(&captures[1], variables)?.clone());
+    }
+
+    let mut unknown = None;
+    let rendered = VARIABLE.replace_all(&s, |c: &Captures| match variable(&c[1], variables) {
+        Ok(Value::String(value)) => value.clone(),
+        Ok(value) => value.to_string(),
+        Err(e) => {
+            unknown.get_or_insert(e);
+            String::new()
+        }
+    });
+
+    match unknown {
+        Some(e) => Err(e.into()),
+        None => Ok(Value::String(rendered.into_owned())),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("variable {0} is not defined in vars")]
+    VariableUnknown(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::template::{render, Variables};
+
+    #[test]
+    fn render_variables() {
+        let variables: Variables = serde_json::from_value(json!({"version": "1.2.3", "port": 8080, "hosts": ["a", "b"]})).unwrap();
+
+        assert_eq!(render(json!({
+            "url": "https://example.com/app-{{version}}.tar.gz",
+            "port": "{{ port }}",
+            "args": ["{{hosts}}", "--port={{port}}", "{{hosts}}-x"],
+            "{{version}}": true,
+            "count": 1
+        }), &variables).unwrap(), json!({
+            "url": "https://example.com/app-1.2.3.tar.gz",
+            "port": 8080,
+            "args": [["a", "b"], "--port=8080", "[\"a\",\"b\"]-x"],
+            "{{version}}": true,
+            "count": 1
+        }));
+
+        assert!(render(json!({"path": "/opt/{{missing}}"}), &variables).is_err());
+        assert!(render(json!("{{missing}}"), &variables).is_err());
+    }
+}
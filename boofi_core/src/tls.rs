@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use rustls::RootCertStore;
+use serde::{Deserialize, Serialize};
+use tokio::fs::{read, try_exists};
+use yasna::models::ObjectIdentifier;
+use yasna::Tag;
+use yasna::tags::TAG_UTCTIME;
+use crate::secret::{Secret, SecretKey};
+use crate::error::{Erro, Resul};
+
+/// Locations of the system certificate authorities used to trust remote servers
+const ROOT_BUNDLES: [&str; 3] = [
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/cert.pem",
+];
+
+/// Certificate authorities of the first system bundle found
+pub async fn root_certificates() -> Resul<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+
+    for bundle in ROOT_BUNDLES {
+        if try_exists(bundle).await? {
+            let certificates = rustls_pemfile::certs(&mut read(bundle).await?.as_slice())?;
+            roots.add_parsable_certificates(&certificates);
+            tracing::debug!("[TLS] certificate authorities loaded from {}", bundle);
+            break;
+        }
+    }
+
+    if roots.is_empty() {
+        return Err(Erro::RootCertificatesMissing);
+    }
+
+    Ok(roots)
+}
+
+/// Certificate authorities of a pem bundle, at least one must be valid
+pub async fn certificate_authorities(path: &str) -> Resul<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    let (valid, _) = roots.add_parsable_certificates(&rustls_pemfile::certs(&mut read(path).await?.as_slice())?);
+
+    if valid == 0 {
+        return Err(Erro::ClientCaInvalid(path.into()));
+    }
+
+    tracing::debug!("[TLS] {} certificate authorities loaded from {}", valid, path);
+    Ok(roots)
+}
+
+/// Client certificate authentication, only used with ssl
+/// ca_path:    pem bundle of trusted certificate authorities
+/// required:   reject connections without a valid client certificate
+/// users:      common name or dns alternative name of a certificate with the credential used instead of basic auth
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientAuth {
+    pub ca_path: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    users: HashMap<String, ClientUser>,
+}
+
+impl ClientAuth {
+    /// Resolves the passwords of the users
+    pub async fn resolve(&mut self, key: Option<&SecretKey>) -> Resul<()> {
+        for user in self.users.values_mut() {
+            user.password.resolve(key).await?;
+        }
+        Ok(())
+    }
+
+    /// Credential of the first certificate name with a user mapping
+    pub fn user(&self, certificate: &[u8]) -> Resul<Option<&ClientUser>> {
+        Ok(certificate_names(certificate)?
+            .iter()
+            .find_map(|name| self.users.get(name)))
+    }
+}
+
+/// password:   plain text or a tagged secret, e.g. `!file /etc/boofi/backup.password`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientUser {
+    pub username: String,
+    pub password: Secret,
+}
+
+/// Elements of the tbs certificate of a DER encoded certificate with their tag, without the optional version.
+/// serial, signature, issuer, validity, subject, public key and optional ids and extensions
+pub(crate) fn certificate_elements(certificate: &[u8]) -> Resul<Vec<(Tag, Vec<u8>)>> {
+    let mut elements = yasna::parse_der(certificate, |r| r.read_sequence(|r| {
+        let elements = r.next().read_sequence(|r| {
+            let mut elements = vec![];
+            while let Some(e) = r.read_optional(|r| Ok((r.lookahead_tag()?, r.read_der()?)))? {
+                elements.push(e);
+            }
+            Ok(elements)
+        })?;
+        r.next().read_der()?; // signature algorithm
+        r.next().read_der()?; // signature
+        Ok(elements)
+    }))?;
+
+    if elements.first().map(|(tag, _)| *tag == Tag::context(0)).unwrap_or(false) {
+        elements.remove(0);
+    }
+
+    Ok(elements)
+}
+
+/// Start and end of the validity of a DER encoded certificate
+pub fn certificate_validity(certificate: &[u8]) -> Resul<(SystemTime, SystemTime)> {
+    let elements = certificate_elements(certificate)?;
+    let (_, validity) = elements.get(3).ok_or(Erro::CertificateInvalid)?;
+
+    fn time(r: yasna::BERReader) -> yasna::ASN1Result<i64> {
+        if r.lookahead_tag()? == TAG_UTCTIME {
+            r.read_utctime().map(|t| t.datetime().unix_timestamp())
+        } else {
+            r.read_generalized_time().map(|t| t.datetime().unix_timestamp())
+        }
+    }
+
+    let (not_before, not_after) = yasna::parse_der(validity, |r| r.read_sequence(|r| Ok((time(r.next())?, time(r.next())?))))?;
+
+    let system_time = |timestamp: i64| UNIX_EPOCH + Duration::from_secs(u64::try_from(timestamp).unwrap_or_default());
+    Ok((system_time(not_before), system_time(not_after)))
+}
+
+/// End of the validity of a DER encoded certificate
+pub fn certificate_expiration(certificate: &[u8]) -> Resul<SystemTime> {
+    certificate_validity(certificate).map(|(_, not_after)| not_after)
+}
+
+/// Subject common names followed by dns alternative names of a DER encoded certificate
+pub fn certificate_names(certificate: &[u8]) -> Resul<Vec<String>> {
+    const COMMON_NAME: [u64; 4] = [2, 5, 4, 3];
+    const ALTERNATIVE_NAME: [u64; 4] = [2, 5, 29, 17];
+
+    let elements = certificate_elements(certificate)?;
+    let mut names = vec![];
+
+    if let Some((_, subject)) = elements.get(4) {
+        let attributes = yasna::parse_der(subject, |r| r.collect_sequence_of(|r| r.collect_set_of(|r| r.read_sequence(|r| {
+            let oid = r.next().read_oid()?;
+            let value = r.next().read_tagged_der()?;
+            Ok((oid, value))
+        }))))?;
+
+        names.extend(attributes.into_iter()
+            .flatten()
+            .filter(|(oid, _)| oid == &ObjectIdentifier::from_slice(&COMMON_NAME))
+            .filter_map(|(_, value)| value.as_str().map(ToString::to_string)));
+    }
+
+    if let Some((_, extensions)) = elements.iter().find(|(tag, _)| *tag == Tag::context(3)) {
+        let extensions = yasna::parse_der(extensions, |r| r.read_tagged(Tag::context(3), |r| r.collect_sequence_of(|r| r.read_sequence(|r| {
+            let oid = r.next().read_oid()?;
+            r.read_optional(|r| r.read_bool())?; // critical
+            let value = r.next().read_bytes()?;
+            Ok((oid, value))
+        }))))?;
+
+        for (_, value) in extensions.iter().filter(|(oid, _)| oid == &ObjectIdentifier::from_slice(&ALTERNATIVE_NAME)) {
+            let alternative_names = yasna::parse_der(value, |r| r.collect_sequence_of(|r| r.read_tagged_der()))?;
+
+            // dns names are implicitly tagged ia5 strings
+            names.extend(alternative_names.into_iter()
+                .filter(|name| name.tag() == Tag::context(2))
+                .filter_map(|name| String::from_utf8(name.value().to_vec()).ok()));
+        }
+    }
+
+    Ok(names)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::time::{Duration, UNIX_EPOCH};
+    use rcgen::{date_time_ymd, Certificate, CertificateParams, DistinguishedName, DnType};
+    use crate::tls::{certificate_expiration, certificate_names, ClientAuth, ClientUser};
+
+    fn certificate() -> Vec<u8> {
+        let mut params = CertificateParams::new(vec!["client.example.com".into(), "backup.example.com".into()]);
+        params.distinguished_name = DistinguishedName::new();
+        params.distinguished_name.push(DnType::CommonName, "client");
+        params.not_after = date_time_ymd(2030, 1, 1);
+        Certificate::from_params(params).unwrap().serialize_der().unwrap()
+    }
+
+    #[test]
+    fn test_certificate_expiration() {
+        assert_eq!(certificate_expiration(&certificate()).unwrap(), UNIX_EPOCH + Duration::from_secs(1893456000));
+    }
+
+    #[test]
+    fn test_certificate_names() {
+        assert_eq!(certificate_names(&certificate()).unwrap(), vec!["client", "client.example.com", "backup.example.com"]);
+        assert!(certificate_names(b"invalid").is_err());
+    }
+
+    #[test]
+    fn test_user() {
+        let client_auth = ClientAuth {
+            ca_path: "/etc/boofi/ca.pem".into(),
+            required: false,
+            users: HashMap::from([("backup.example.com".into(), ClientUser {
+                username: "backup".into(),
+                password: "secret".into(),
+            })]),
+        };
+
+        assert_eq!(client_auth.user(&certificate()).unwrap().unwrap().username, "backup");
+    }
+}
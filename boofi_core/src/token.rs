@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::controller::Auth;
+use crate::error::Resul;
+use crate::secret::{write_private, SecretKey};
+
+/// Keeps the issued tokens of a service, every change of the tokens is saved.
+/// Implement it to keep tokens somewhere else, e.g. in a database.
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+    /// Tokens saved before, expired ones are left to the auth controller
+    fn load(&self) -> Resul<Vec<Auth>>;
+
+    /// Replaces all saved tokens, called in the background and never while the controller is locked
+    async fn save(&self, auths: &[Auth]) -> Resul<()>;
+}
+
+/// Tokens are only kept in memory and lost on restart
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryTokenStore;
+
+#[async_trait]
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> Resul<Vec<Auth>> {
+        Ok(vec![])
+    }
+
+    async fn save(&self, _auths: &[Auth]) -> Resul<()> {
+        Ok(())
+    }
+}
+
+/// Tokens of all services are kept in encrypted files and survive restarts
+/// dir:        directory of the token files, `<service>.tokens` for each service with other characters than `[A-Za-z0-9_-]` percent encoded
+/// key_file:   base64 encoded 256 bit key, a random key is generated if the file is missing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenStoreConfig {
+    pub dir: String,
+    pub key_file: String,
+}
+
+impl TokenStoreConfig {
+    /// Store of one service, the directory and key file are created if missing
+    pub async fn store(&self, service: &str) -> Resul<FileTokenStore> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        Ok(FileTokenStore {
+            path: Path::new(&self.dir).join(format!("{}.tokens", Self::file_name(service))),
+            key: SecretKey::load_or_generate(&self.key_file).await?,
+        })
+    }
+
+    /// Service name with every byte except `[A-Za-z0-9_-]` percent encoded, distinct services never share a file
+    fn file_name(service: &str) -> String {
+        service.bytes().map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        }).collect()
+    }
+}
+
+/// Tokens of one service encrypted with AES-256-GCM, the file holds the nonce followed by the encrypted json
+#[derive(Debug)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    key: SecretKey,
+}
+
+#[async_trait]
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Resul<Vec<Auth>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+
+        let json = self.key.open(&std::fs::read(&self.path)?)
+            .map_err(|_| TokenStoreError::Decrypt(self.path.display().to_string()))?;
+
+        tracing::debug!("[TOKENS] loading tokens from {}", self.path.display());
+        serde_json::from_slice(&json).map_err(Into::into)
+    }
+
+    async fn save(&self, auths: &[Auth]) -> Resul<()> {
+        let sealed = self.key.seal(&serde_json::to_vec(auths)?)?;
+
+        tracing::debug!("[TOKENS] saving {} tokens to {}", auths.len(), self.path.display());
+        let partial = self.path.with_extension("partial");
+        write_private(&partial, &sealed).await?;
+        tokio::fs::rename(&partial, &self.path).await.map_err(Into::into)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TokenStoreError {
+    #[error("tokens in {0} can't be decrypted, the key changed or the file is damaged")]
+    Decrypt(String),
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::tempdir;
+    use crate::controller::AuthController;
+    use crate::token::{MemoryTokenStore, TokenStore, TokenStoreConfig};
+
+    #[tokio::test]
+    async fn test_file_store() {
+        let dir = tempdir().unwrap();
+        let config = TokenStoreConfig {
+            dir: dir.path().join("tokens").to_string_lossy().to_string(),
+            key_file: dir.path().join("token.key").to_string_lossy().to_string(),
+        };
+
+        let store = config.store("web/1").await.unwrap();
+        assert!(store.load().unwrap().is_empty());
+
+        let mut auth = AuthController::new(std::time::Duration::from_secs(60), Box::new(store));
+        let token = auth.insert_or_replace("user".into(), "secret".into(), Some("laptop".into())).token().to_string();
+
+        // tokens are saved in the background
+        let path = dir.path().join("tokens/web%2F1.tokens");
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while !path.exists() {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        }).await.unwrap();
+
+        let content = std::fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&content).contains("secret"));
+
+        // same key after a restart
+        let auths = config.store("web/1").await.unwrap().load().unwrap();
+        assert_eq!(auths.len(), 1);
+        assert_eq!(auths[0].token(), token);
+        assert_eq!(auths[0].password(), "secret");
+        assert_eq!(auths[0].client(), Some("laptop"));
+
+        // names which only differ in replaced characters don't share a file
+        assert!(config.store("web_1").await.unwrap().load().unwrap().is_empty());
+        assert!(!dir.path().join("tokens/web_1.tokens").exists());
+
+        // another key can't read them
+        let other = TokenStoreConfig {
+            key_file: dir.path().join("other.key").to_string_lossy().to_string(),
+            ..config.clone()
+        };
+        assert!(other.store("web/1").await.unwrap().load().is_err());
+
+        std::fs::write(&config.key_file, "c2hvcnQ=").unwrap();
+        assert!(config.store("web/1").await.is_err());
+
+        assert!(MemoryTokenStore.load().unwrap().is_empty());
+    }
+}
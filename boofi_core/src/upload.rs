@@ -0,0 +1,160 @@
+use std::str::FromStr;
+use serde::Serialize;
+use crate::error::{Erro, Resul};
+use crate::patch;
+use crate::system::{HashAlgorithm, System};
+use crate::workspace::Workspace;
+
+/// Byte range of a chunk in `Content-Range: bytes <start>-<end>/<total or *>`, both ends are inclusive
+#[derive(Debug, PartialEq)]
+pub struct ContentRange {
+    start: usize,
+    end: usize,
+    total: Option<usize>,
+}
+
+impl ContentRange {
+    pub fn length(&self) -> usize {
+        self.end - self.start + 1
+    }
+}
+
+impl FromStr for ContentRange {
+    type Err = Erro;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Erro::ContentRangeInvalid(s.into());
+
+        let (range, total) = s.trim().strip_prefix("bytes ").and_then(|r| r.split_once('/')).ok_or_else(invalid)?;
+        let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+        let range = Self {
+            start: start.trim().parse().map_err(|_| invalid())?,
+            end: end.trim().parse().map_err(|_| invalid())?,
+            total: match total.trim() {
+                "*" => None,
+                total => Some(total.parse().map_err(|_| invalid())?),
+            },
+        };
+
+        if range.end < range.start || range.total.map(|t| range.end >= t).unwrap_or(false) {
+            return Err(invalid());
+        }
+
+        Ok(range)
+    }
+}
+
+/// Received bytes of a chunked upload, a resumed upload continues at `size`
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ChunkedUploadStatus {
+    path: String,
+    size: u64,
+}
+
+/// Chunks of a file are collected in the workspace and replace the target once the checksum matches.
+/// The partial file is named after user and target, so an interrupted upload is resumed by the same user only.
+pub struct ChunkedUpload {
+    target: String,
+    partial: String,
+    workspace: Workspace,
+}
+
+impl ChunkedUpload {
+    pub fn new(target: &str, username: &str, workspace: &Workspace) -> Self {
+        let id = patch::sha256(format!("{}:{}", username, target).as_bytes());
+
+        Self {
+            target: target.into(),
+            partial: format!("{}/.partial-{}", workspace.dir(), &id[..16]),
+            workspace: workspace.clone(),
+        }
+    }
+
+    pub async fn status(&self, system: &System) -> Resul<ChunkedUploadStatus> {
+        let size = if system.path_exist(&self.partial).await? {
+            system.metadata(&self.partial).await?.size()
+        } else {
+            0
+        };
+
+        Ok(ChunkedUploadStatus {
+            path: self.target.clone(),
+            size,
+        })
+    }
+
+    /// Appends a chunk or overwrites everything from its start, a chunk behind the received bytes is rejected
+    pub async fn write(&self, range: &ContentRange, content: &[u8], system: &System) -> Resul<ChunkedUploadStatus> {
+        if range.length() != content.len() {
+            return Err(Erro::ContentRangeLength(range.length(), content.len()));
+        }
+
+        let status = self.status(system).await?;
+        if range.start as u64 > status.size {
+            return Err(Erro::ContentRangeUnsatisfiable(status.size));
+        }
+
+        if range.start == 0 {
+            self.workspace.prepare(system).await?;
+        }
+
+        tracing::debug!("[CHUNKED UPLOAD] bytes {}-{} of {} to {}", range.start, range.end, self.target, self.partial);
+        system.write_chunk(&self.partial, range.start, content).await?;
+
+        Ok(ChunkedUploadStatus {
+            path: self.target.clone(),
+            size: (range.end + 1) as u64,
+        })
+    }
+
+    /// Replaces the target with the received bytes if their checksum is the expected one
+    pub async fn finalize(&self, algorithm: HashAlgorithm, digest: &str, system: &System) -> Resul<()> {
+        if !system.path_exist(&self.partial).await? {
+            return Err(Erro::PathNotFound(self.partial.clone()));
+        }
+
+        let checksum = system.checksum(&self.partial, algorithm).await?;
+        if !checksum.eq_ignore_ascii_case(digest.trim()) {
+            return Err(Erro::ChecksumMismatch(digest.into(), checksum));
+        }
+
+        tracing::debug!("[CHUNKED UPLOAD] replacing {} with {}", self.target, self.partial);
+        system.replace(&self.partial, &self.target).await?;
+        system.delete(&self.partial).await
+    }
+
+    /// Drops the received bytes
+    pub async fn cancel(&self, system: &System) -> Resul<()> {
+        if system.path_exist(&self.partial).await? {
+            system.delete(&self.partial).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::upload::{ChunkedUpload, ContentRange};
+    use crate::workspace::Workspace;
+
+    #[test]
+    fn test_content_range() {
+        assert_eq!("bytes 0-1023/4096".parse::<ContentRange>().unwrap(), ContentRange { start: 0, end: 1023, total: Some(4096) });
+        assert_eq!("bytes 1024-1024/*".parse::<ContentRange>().unwrap().length(), 1);
+
+        for invalid in ["bytes 10-5/*", "bytes 0-4096/4096", "bytes */4096", "0-10/20", "bytes a-b/*", "bytes 0-10"] {
+            assert!(invalid.parse::<ContentRange>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_partial() {
+        let workspace = Workspace::default();
+        let upload = ChunkedUpload::new("/opt/app.tar", "dev", &workspace);
+
+        assert!(upload.partial.starts_with("/tmp/boofi-default/.partial-"));
+        assert_eq!(upload.partial, ChunkedUpload::new("/opt/app.tar", "dev", &workspace).partial);
+        assert_ne!(upload.partial, ChunkedUpload::new("/opt/app.tar", "ops", &workspace).partial);
+    }
+}
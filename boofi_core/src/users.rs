@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::num::NonZeroU32;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use ring::pbkdf2::{self, PBKDF2_HMAC_SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::error::{Erro, Resul};
+
+/// Scheme of the password hashes
+const SCHEME: &str = "pbkdf2-sha256";
+
+/// Iterations of new password hashes
+const ITERATIONS: u32 = 210_000;
+
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+
+/// Api users verified by boofi, independent of the accounts on the endpoint.
+/// Usernames with their password hash `pbkdf2-sha256$<iterations>$<salt>$<hash>`, see `hash_password`.
+#[derive(Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ApiUsers(BTreeMap<String, String>);
+
+impl Debug for ApiUsers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.0.keys()).finish()
+    }
+}
+
+impl ApiUsers {
+    /// Checks the password against the hash, unknown users take as long as a wrong password.
+    /// Hashing takes tens of milliseconds and runs on the blocking pool instead of an async worker.
+    pub async fn verify(&self, username: &str, password: &str) -> Resul<()> {
+        let hash = self.0.get(username).cloned();
+        let password = password.to_string();
+
+        let result = tokio::task::spawn_blocking(move || match hash {
+            Some(hash) => verify_hash(&hash, &password).map(|_| true),
+            None => hash_password_with(&password, ITERATIONS).map(|_| false),
+        }).await?;
+
+        match result {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                tracing::debug!("[USERS] unknown user {}", username);
+                Err(Erro::AuthUserInvalid)
+            }
+            Err(e) => {
+                tracing::debug!("[USERS] password of {} rejected", username);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Hash of a password to configure an api user
+pub fn hash_password(password: &str) -> Resul<String> {
+    hash_password_with(password, ITERATIONS)
+}
+
+fn hash_password_with(password: &str, iterations: u32) -> Resul<String> {
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new().fill(&mut salt).map_err(|_| UsersError::Random)?;
+
+    let mut hash = [0u8; HASH_LEN];
+    let count = NonZeroU32::new(iterations).ok_or(UsersError::HashInvalid)?;
+    pbkdf2::derive(PBKDF2_HMAC_SHA256, count, &salt, password.as_bytes(), &mut hash);
+
+    Ok(format!("{}${}${}${}", SCHEME, iterations, STANDARD_NO_PAD.encode(salt), STANDARD_NO_PAD.encode(hash)))
+}
+
+fn verify_hash(hash: &str, password: &str) -> Resul<()> {
+    let parts: Vec<&str> = hash.split('$').collect();
+    let [SCHEME, iterations, salt, hash] = parts.as_slice() else {
+        return Err(UsersError::HashInvalid.into());
+    };
+
+    let iterations = iterations.parse::<u32>().ok().and_then(NonZeroU32::new).ok_or(UsersError::HashInvalid)?;
+    let salt = STANDARD_NO_PAD.decode(salt).map_err(|_| UsersError::HashInvalid)?;
+    let hash = STANDARD_NO_PAD.decode(hash).map_err(|_| UsersError::HashInvalid)?;
+
+    pbkdf2::verify(PBKDF2_HMAC_SHA256, iterations, &salt, password.as_bytes(), &hash).map_err(|_| Erro::AuthUserInvalid)
+}
+
+#[derive(Debug, Error)]
+pub enum UsersError {
+    #[error("password hash is not pbkdf2-sha256$<iterations>$<salt>$<hash>")]
+    HashInvalid,
+    #[error("random salt failed")]
+    Random,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use crate::error::Erro;
+    use crate::users::{hash_password, hash_password_with, ApiUsers};
+
+    #[tokio::test]
+    async fn verify_users() {
+        let users = ApiUsers(BTreeMap::from([
+            ("alice".to_string(), hash_password_with("s3cret", 1000).unwrap()),
+            ("bob".to_string(), "plain".to_string()),
+        ]));
+
+        users.verify("alice", "s3cret").await.unwrap();
+        assert!(matches!(users.verify("alice", "wrong").await, Err(Erro::AuthUserInvalid)));
+        assert!(matches!(users.verify("carol", "s3cret").await, Err(Erro::AuthUserInvalid)));
+        assert!(matches!(users.verify("bob", "plain").await, Err(Erro::Users(_))));
+
+        let hash = hash_password("s3cret").unwrap();
+        assert!(hash.starts_with("pbkdf2-sha256$210000$"));
+        assert_ne!(hash, hash_password("s3cret").unwrap());
+    }
+}
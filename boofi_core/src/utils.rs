@@ -1,23 +1,29 @@
+use std::net::IpAddr;
+use std::path::PathBuf;
+use regex::Regex;
+use serde_json::Value;
+use crate::error::Resul;
+
 #[cfg(test)]
-pub(crate) mod test {
+pub mod test {
     use std::fs::read_to_string;
     use crate::system::os::Os;
     use crate::system::{Credential, Platform, System, PlatformActions};
     use crate::system::posix::Posix;
 
-    pub(crate) const RESOURCES: &str = "/resources/test/";
-    pub(crate) const SSH_ENDPOINT: &str = "127.0.0.1:22";
-    pub(crate) const USERNAME: &str = "dev";
-    pub(crate) const PASSWORD: &str = "admin12345";
+    pub const RESOURCES: &str = "/resources/test/";
+    pub const SSH_ENDPOINT: &str = "127.0.0.1:22";
+    pub const USERNAME: &str = "dev";
+    pub const PASSWORD: &str = "admin12345";
 
-    pub(crate) fn test_resources(name: &str) -> String {
+    pub fn test_resources(name: &str) -> String {
         let mut base = env!("CARGO_MANIFEST_DIR").to_string();
         base.push_str(RESOURCES);
         base.push_str(name);
         base
     }
 
-    pub(crate) fn read_test_resources(name: &str) -> String {
+    pub fn read_test_resources(name: &str) -> String {
         read_to_string(test_resources(name)).unwrap()
     }
 
@@ -29,13 +35,13 @@ pub(crate) mod test {
         Credential::new(USERNAME, PASSWORD)
     }
 
-    pub(crate) async fn os() -> Os {
+    pub async fn os() -> Os {
         Posix::new(credential(),
                    endpoint_some(),
         ).detect_os().await.unwrap()
     }
 
-    pub(crate) async fn system_ssh() -> System {
+    pub async fn system_ssh() -> System {
         System::new(Platform::Posix(
             Posix::new(credential(),
                        endpoint_some(),
@@ -43,7 +49,7 @@ pub(crate) mod test {
         ), Some(os().await))
     }
 
-    pub(crate) async fn system_user() -> System {
+    pub async fn system_user() -> System {
         System::new(Platform::Posix(
             Posix::new(credential(), None)
         ), Some(os().await))
@@ -135,3 +141,37 @@ macro_rules! app_metadata {
 pub(crate) use app_metadata;
 pub(crate) use file_metadata;
 pub(crate) use count;
+
+/// Address and prefix length of a CIDR notation like `192.168.1.10/24`
+pub fn parse_cidr(value: &str) -> Option<(IpAddr, u8)> {
+    let (address, prefix) = value.split_once('/')?;
+    let address: IpAddr = address.parse().ok()?;
+    let prefix: u8 = prefix.parse().ok()?;
+
+    (prefix <= if address.is_ipv4() { 32 } else { 128 }).then_some((address, prefix))
+}
+
+/// Paths of all `.yaml`, `.yml` and `.json` manifests of a plugin directory
+pub async fn manifest_paths(dir: &str) -> Resul<Vec<PathBuf>> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    let mut paths = vec![];
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml" | "json")) {
+            paths.push(path);
+        }
+    }
+
+    Ok(paths)
+}
+
+/// One object per matching line with the named groups of the pattern, groups which did not participate are null
+pub fn regex_captures(pattern: &Regex, text: &str) -> Vec<Value> {
+    text.lines().filter_map(|line| {
+        let captures = pattern.captures(line)?;
+        Some(Value::Object(pattern.capture_names().flatten().map(|name| {
+            (name.to_string(), captures.name(name).map(|m| Value::String(m.as_str().into())).unwrap_or_default())
+        }).collect()))
+    }).collect()
+}
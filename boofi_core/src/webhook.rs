@@ -0,0 +1,101 @@
+use std::time::Duration;
+use axum::http::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use crate::error::Resul;
+use crate::http;
+
+/// Delay before the first retry, doubled for each further one
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Finished or failed tasks are posted to this url, disabled by default.
+/// A `callback_url` of the request takes precedence.
+/// url:        http or https url receiving the task as json
+/// retries:    further attempts if the post fails, 3 by default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    pub retries: Option<usize>,
+}
+
+impl Webhook {
+    const RETRIES: usize = 3;
+
+    pub fn new(url: String, retries: Option<usize>) -> Self {
+        Self { url, retries }
+    }
+
+    /// Posts the value and retries with exponential backoff, errors are only logged
+    pub async fn notify(&self, value: &Value) {
+        let body = value.to_string();
+        let mut delay = RETRY_DELAY;
+
+        for attempt in 0..=self.retries.unwrap_or(Self::RETRIES) {
+            if attempt > 0 {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+
+            match Self::post(&self.url, body.clone()).await {
+                Ok(()) => {
+                    tracing::debug!("[WEBHOOK] posted to {}", self.url);
+                    return;
+                }
+                Err(error) => tracing::warn!("[WEBHOOK] post to {} failed in attempt {}: {:?}", self.url, attempt + 1, error),
+            }
+        }
+
+        tracing::error!("[WEBHOOK] giving up posting to {}", self.url);
+    }
+
+    async fn post(url: &str, body: String) -> Resul<()> {
+        let status = http::send(url, Method::POST, &[], Some(body), None).await?.status;
+
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(WebhookError::Status(status.as_u16()).into())
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("webhook responded with status {0}")]
+    Status(u16),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use axum::Router;
+    use axum::routing::post;
+    use serde_json::{json, Value};
+    use tokio::sync::Mutex;
+    use crate::webhook::Webhook;
+
+    #[tokio::test]
+    async fn notify() {
+        let received = Arc::new(Mutex::new(vec![]));
+        let router = Router::new()
+            .route("/hook", post(|State(received): State<Arc<Mutex<Vec<Value>>>>, body: String| async move {
+                received.lock().await.push(serde_json::from_str::<Value>(&body).unwrap());
+                StatusCode::OK
+            }))
+            .with_state(received.clone());
+
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(router.into_make_service());
+        let address = server.local_addr();
+        tokio::spawn(server);
+
+        let value = json!({"id": 1, "status": "finished"});
+        Webhook::new(format!("http://{}/hook", address), Some(0)).notify(&value).await;
+
+        assert_eq!(*received.lock().await, vec![value]);
+        assert!(Webhook::post("ftp://localhost/hook", "{}".into()).await.is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::error::{Erro, Resul};
+use crate::system::{FileType, Metadata, System, SystemManager};
+
+/// Stale items of the detected systems are removed in this interval
+pub const CLEANUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Scratch files and directories on the targets, stale items are removed periodically and before new ones are created
+/// max_age:    seconds since the last modification after which an item is stale, one hour by default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceConfig {
+    #[serde(default = "WorkspaceConfig::default_max_age")]
+    max_age: u64,
+}
+
+impl WorkspaceConfig {
+    fn default_max_age() -> u64 {
+        60 * 60
+    }
+}
+
+impl Default for WorkspaceConfig {
+    fn default() -> Self {
+        Self {
+            max_age: Self::default_max_age(),
+        }
+    }
+}
+
+/// Kind of a new scratch item
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceItemType {
+    #[default]
+    File,
+    Directory,
+}
+
+/// A scratch file or directory with its absolute path
+#[derive(Debug, PartialEq, Serialize)]
+pub struct WorkspaceItem {
+    name: String,
+    path: String,
+    r#type: FileType,
+    metadata: Metadata,
+}
+
+/// Workspace directory `/tmp/boofi-<service>` of one service, shared by its users.
+/// The directory is sticky like `/tmp`, users only remove their own items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    dir: String,
+    max_age: Duration,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new("default", &WorkspaceConfig::default())
+    }
+}
+
+impl Workspace {
+    pub fn new(service: &str, config: &WorkspaceConfig) -> Self {
+        let service: String = service.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+
+        Self {
+            dir: format!("/tmp/boofi-{}", service),
+            max_age: Duration::from_secs(config.max_age),
+        }
+    }
+
+    pub fn dir(&self) -> &str {
+        &self.dir
+    }
+
+    /// Path of an item, names must not leave the workspace
+    fn path(&self, name: &str) -> Resul<String> {
+        if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+            return Err(Erro::WorkspaceItemInvalid(name.into()));
+        }
+
+        Ok(format!("{}/{}", self.dir, name))
+    }
+
+    async fn item(&self, path: String, system: &System) -> Resul<WorkspaceItem> {
+        let name = path.rsplit('/').next().unwrap_or_default().to_string();
+
+        Ok(WorkspaceItem {
+            name,
+            r#type: system.file_type(&path).await?,
+            metadata: system.metadata(&path).await?,
+            path,
+        })
+    }
+
+    /// Creates the directory if it is missing
+    pub async fn prepare(&self, system: &System) -> Resul<()> {
+        system.create_workspace(&self.dir).await
+    }
+
+    /// Creates an empty file or directory with a random name, stale items are removed before
+    pub async fn create(&self, r#type: WorkspaceItemType, system: &System) -> Resul<WorkspaceItem> {
+        if let Err(e) = self.cleanup(system).await {
+            tracing::warn!("[WORKSPACE] stale items of {} not removed: {}", self.dir, e);
+        }
+
+        let path = system.create_temp(&self.dir, r#type == WorkspaceItemType::Directory).await?;
+        tracing::debug!("[WORKSPACE] {} created", path);
+        self.item(path, system).await
+    }
+
+    pub async fn items(&self, system: &System) -> Resul<Vec<WorkspaceItem>> {
+        if !system.path_exist(&self.dir).await? {
+            return Ok(vec![]);
+        }
+
+        Ok(system.dir_list(&self.dir).await?.into_iter()
+            .map(|entry| WorkspaceItem {
+                path: format!("{}/{}", self.dir, entry.name),
+                name: entry.name,
+                r#type: entry.file_type,
+                metadata: entry.metadata,
+            })
+            .collect())
+    }
+
+    /// Removes a file or a directory with all its content
+    pub async fn remove(&self, name: &str, system: &System) -> Resul<()> {
+        let path = self.path(name)?;
+
+        if !system.path_exist(&path).await? {
+            return Err(Erro::PathNotFound(path));
+        }
+
+        tracing::debug!("[WORKSPACE] removing {}", path);
+        system.remove_dir_all(&path).await
+    }
+
+    /// Removes the stale items of the user of the system
+    pub async fn cleanup(&self, system: &System) -> Resul<()> {
+        if system.path_exist(&self.dir).await? {
+            system.remove_stale(&self.dir, self.max_age).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the stale items of every user with a detected system, others are cleaned up with their next item
+    pub async fn cleanup_all(&self, system_manager: &SystemManager) {
+        for system in system_manager.systems() {
+            if let Err(e) = self.cleanup(&system).await {
+                tracing::warn!("[WORKSPACE] stale items of {} in {} not removed: {}", system.credential().username(), self.dir, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::workspace::{Workspace, WorkspaceConfig};
+
+    #[test]
+    fn test_path() {
+        let workspace = Workspace::new("web/1 prod", &WorkspaceConfig::default());
+        assert_eq!(workspace.dir(), "/tmp/boofi-web_1_prod");
+        assert_eq!(workspace.path("file.a1B2c3D4").unwrap(), "/tmp/boofi-web_1_prod/file.a1B2c3D4");
+
+        for name in ["", ".", "..", "../etc", "a/b"] {
+            assert!(workspace.path(name).is_err(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use std::time::Duration;
+use boofi_core::controller::{Controller, ControllerSettings};
+
+#[tokio::test]
+async fn embedded_controller() {
+    let controller = Controller::new(ControllerSettings {
+        max_token_expiration: Duration::from_secs(60),
+        ..Default::default()
+    }, None).await.unwrap();
+
+    assert!(controller.app("ls").is_some());
+    assert!(controller.file_registry().by_name("hosts").is_ok());
+}
@@ -1,6 +1,6 @@
 use proc_macro::TokenStream;
 use syn::{Attribute, parse_macro_input, DeriveInput, Data, Fields, Expr, Type, Token, ExprAssign,
-          Field, Lit, GenericParam, parse_quote, PathArguments};
+          Field, Lit, GenericParam, parse_quote, PathArguments, Meta, Variant};
 use syn::__private::quote::quote;
 use syn::__private::ToTokens;
 use syn::punctuated::Punctuated;
@@ -21,13 +21,104 @@ fn const_fix(typ: &mut Type, cnst: impl ToTokens) -> impl ToTokens {
     }
 }
 
+/// `Option<T>` fields may be left out
+fn optional(typ: &Type) -> bool {
+    match typ {
+        Type::Path(p) => p.qself.is_none() && p.path.segments.last().map(|s| s.ident == "Option").unwrap_or(false),
+        _ => false
+    }
+}
+
+/// Serde attributes which change the serialized shape
+#[derive(Debug, Default)]
+struct SerdeAttributes {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    default: bool,
+    skip: bool,
+    flatten: bool,
+}
+
+impl SerdeAttributes {
+    fn parse(attrs: &[Attribute]) -> Self {
+        let mut serde = Self::default();
+
+        for attr in attrs {
+            if attr.meta.path().segments[0].ident == "serde" {
+                for meta in attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated).unwrap() {
+                    let value = match &meta {
+                        Meta::NameValue(nv) => match &nv.value {
+                            Expr::Lit(l) => match &l.lit {
+                                Lit::Str(s) => Some(s.value()),
+                                _ => None
+                            }
+                            _ => None
+                        }
+                        _ => None
+                    };
+
+                    match meta.path().segments[0].ident.to_string().as_str() {
+                        "rename" => serde.rename = value,
+                        "rename_all" => serde.rename_all = value,
+                        "default" => serde.default = true,
+                        "flatten" => serde.flatten = true,
+                        "skip" | "skip_serializing" | "skip_deserializing" => serde.skip = true,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        serde
+    }
+}
+
+/// Applies a serde `rename_all` rule to a snake_case field or PascalCase variant name
+fn rename(name: &str, rule: &str) -> String {
+    let mut words = vec![];
+    for part in name.split('_').filter(|p| !p.is_empty()) {
+        let mut word = String::new();
+        for c in part.chars() {
+            if c.is_uppercase() && !word.is_empty() {
+                words.push(word);
+                word = String::new();
+            }
+            word.push(c.to_ascii_lowercase());
+        }
+        words.push(word);
+    }
+
+    let capitalize = |w: &String| {
+        let mut c = w.chars();
+        c.next().map(|f| f.to_ascii_uppercase().to_string() + c.as_str()).unwrap_or_default()
+    };
+
+    match rule {
+        "lowercase" => words.concat(),
+        "UPPERCASE" => words.concat().to_ascii_uppercase(),
+        "PascalCase" => words.iter().map(capitalize).collect(),
+        "camelCase" => words.iter().enumerate()
+            .map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) })
+            .collect(),
+        "snake_case" => words.join("_"),
+        "SCREAMING_SNAKE_CASE" => words.join("_").to_ascii_uppercase(),
+        "kebab-case" => words.join("-"),
+        "SCREAMING-KEBAB-CASE" => words.join("-").to_ascii_uppercase(),
+        _ => panic!("unsupported rename rule {}", rule)
+    }
+}
+
 /// Represents a field with their information
 #[derive(Debug)]
 struct FieldAttributes {
     name: Option<String>,
     kind: Option<String>,
     description: Option<String>,
+    default: Option<String>,
     typ: Type,
+    required: bool,
+    skip: bool,
+    flatten: bool,
 }
 
 impl FieldAttributes {
@@ -62,6 +153,14 @@ impl FieldAttributes {
         }
     }
 
+    // default value from attribute, json or plain text
+    fn default(&self) -> impl ToTokens {
+        match &self.default {
+            Some(d) => quote!(Some(#d)),
+            None => quote!(None),
+        }
+    }
+
     /// parse attribute key=value (separated by ,) and store it
     fn add_key_value(&mut self, kv: &Expr) {
         #[allow(unused_assignments)]
@@ -98,10 +197,11 @@ impl FieldAttributes {
         }
 
         // assign known attribute
-        match key.expect("identifier missing (name/kind/description)").as_str() {
+        match key.expect("identifier missing (name/kind/description/default)").as_str() {
             "name" => self.name = value,
             "kind" => self.kind = value,
             "description" => self.description = value,
+            "default" => self.default = value,
             _=> {}
         }
     }
@@ -117,24 +217,110 @@ fn parse_attributes(attrs: &[Attribute], field_attributes: &mut FieldAttributes)
     }
 }
 
-fn parse_field_attributes(field: &Field)  -> FieldAttributes {
+fn parse_field_attributes(field: &Field, rename_all: Option<&str>)  -> FieldAttributes {
+    let serde = SerdeAttributes::parse(field.attrs.as_slice());
     let mut desc = FieldAttributes {
         name: None,
         kind: None,
         description: None,
+        default: None,
         typ: field.ty.clone(),
+        required: !serde.default && !serde.flatten && !optional(&field.ty),
+        skip: serde.skip,
+        flatten: serde.flatten,
     };
 
-    // field name - eventually overridden by attribute
-    desc.name = field.ident.as_ref().map(|i|i.to_string());
+    // field name like serialized by serde - eventually overridden by attribute
+    desc.name = field.ident.as_ref().map(|i| {
+        let name = i.to_string().trim_start_matches("r#").to_string();
+        serde.rename.unwrap_or_else(|| rename_all.map(|r| rename(&name, r)).unwrap_or(name))
+    });
 
     // attributes
     parse_attributes(field.attrs.as_slice(), &mut desc);
     desc
 }
 
+/// Generates the description of each (not skipped) field
+fn field_impls(fields: &mut [FieldAttributes]) -> Vec<impl ToTokens> {
+    let mut field_impls = vec![];
+
+    for f in fields.iter_mut().filter(|f| !f.skip) {
+        let kind = f.kind();
+        let name = f.name();
+        let description = f.description();
+        let required = f.required;
+        let default = f.default();
+        let flatten = f.flatten;
+
+        let typ = &mut f.typ;
+        let fields = const_fix(typ, quote!(FIELDS)).to_token_stream();
+        let value_type = const_fix(typ, quote!(TYPE)).to_token_stream();
+
+        field_impls.push(quote!{
+            crate::description::DescriptionField {
+                kind: #kind,
+                name: #name,
+                description: #description,
+                r#type: #value_type,
+                required: #required,
+                default: #default,
+                flatten: #flatten,
+                fields: #fields
+            }
+        });
+    }
+
+    field_impls
+}
+
+/// Generates the description of an enum variant
+/// Unit variants are `Null`, tuple variants `Tuple` and struct variants `Object`
+fn variant_impl(variant: &Variant, rename_all: Option<&str>) -> impl ToTokens {
+    let serde = SerdeAttributes::parse(variant.attrs.as_slice());
+    let ident = variant.ident.to_string();
+    let name = serde.rename.unwrap_or_else(|| rename_all.map(|r| rename(&ident, r)).unwrap_or(ident));
+
+    let mut desc = FieldAttributes {
+        name: Some(name),
+        kind: Some("variant".into()),
+        description: Some("".into()),
+        default: None,
+        typ: parse_quote!(()),
+        required: true,
+        skip: serde.skip,
+        flatten: false,
+    };
+    parse_attributes(variant.attrs.as_slice(), &mut desc);
+
+    let (value_type, mut fields): (_, Vec<FieldAttributes>) = match &variant.fields {
+        Fields::Named(n) => (quote!(Object), n.named.iter().map(|f| parse_field_attributes(f, None)).collect()),
+        Fields::Unnamed(u) => (quote!(Tuple), u.unnamed.iter().map(|f| parse_field_attributes(f, None)).collect()),
+        Fields::Unit => (quote!(Null), vec![]),
+    };
+    let fields = field_impls(&mut fields);
+    let kind = desc.kind.unwrap();
+    let name = desc.name.unwrap();
+    let description = desc.description.unwrap();
+
+    quote!{
+        crate::description::DescriptionField {
+            kind: #kind,
+            name: #name,
+            description: #description,
+            r#type: crate::description::DescriptionType::#value_type,
+            required: true,
+            default: None,
+            flatten: false,
+            fields: &[#(#fields),*]
+        }
+    }
+}
+
 /// Generates Description implementation for the provided object.
-/// Name, kind and description can be override by attribute `desc`
+/// Name, kind, description and default value can be override by attribute `desc`
+/// Enum variants are described as fields. Serde `rename`, `rename_all`, `default` and `skip` are respected.
+/// Fields of type `Option<T>` or with serde `default` are not required, serde `flatten` fields are marked.
 ///
 /// Description is used to generate serializable documentation.
 ///
@@ -148,49 +334,37 @@ pub fn desc(item: TokenStream) -> TokenStream {
         name: None,
         kind: None,
         description: None,
+        default: None,
         typ: parse_quote!(#i),
+        required: true,
+        skip: false,
+        flatten: false,
     };
     parse_attributes(ast.attrs.as_slice(), &mut desc);
+    let serde = SerdeAttributes::parse(ast.attrs.as_slice());
+    let rename_all = serde.rename_all.as_deref();
 
-    // parse struct fields
-    let mut fields = vec![];
-    match ast.data {
+    // parse struct fields or enum variants
+    let (value_type, field_impls) = match &ast.data {
         Data::Struct(s) => {
-            match s.fields {
-                Fields::Named(n) => {
-                    for name in n.named {
-                        fields.push(parse_field_attributes(&name));
-                    }
-                }
-                Fields::Unnamed(_) => {}
-                Fields::Unit => {}
-            }
+            let mut fields: Vec<FieldAttributes> = match &s.fields {
+                Fields::Named(n) => n.named.iter().map(|f| parse_field_attributes(f, rename_all)).collect(),
+                Fields::Unnamed(_) => vec![],
+                Fields::Unit => vec![],
+            };
+            (quote!(Object), field_impls(&mut fields).into_iter().map(|f| f.to_token_stream()).collect())
         }
-        Data::Enum(_) => {}
-        Data::Union(_) => {}
-    }
+        Data::Enum(e) => {
+            (quote!(Enum), e.variants.iter()
+                .filter(|v| !SerdeAttributes::parse(v.attrs.as_slice()).skip)
+                .map(|v| variant_impl(v, rename_all).to_token_stream())
+                .collect::<Vec<_>>())
+        }
+        Data::Union(_) => (quote!(Unknown), vec![]),
+    };
 
     let ident = ast.ident.to_token_stream();
     let generics = &mut ast.generics;
-    let mut field_impls = vec![];
-
-    for f in fields.iter_mut(){
-        let kind = f.kind();
-        let name = f.name();
-        let description = f.description();
-
-        let typ = &mut f.typ;
-        let fields = const_fix(typ, quote!(FIELDS)).to_token_stream();
-
-        field_impls.push(quote!{
-            crate::description::DescriptionField {
-                kind: #kind,
-                name: #name,
-                description: #description,
-                fields: #fields
-            }
-        });
-    }
 
     // add description bound to generic
     let mut generics_with_bounds = generics.clone();
@@ -223,6 +397,7 @@ pub fn desc(item: TokenStream) -> TokenStream {
     let q = quote! {
         impl #generics_with_bounds crate::description::Description for #ident #generics {
             const KIND: &'static str =  #kind;
+            const TYPE: crate::description::DescriptionType = crate::description::DescriptionType::#value_type;
             #name
             #description
 